@@ -0,0 +1,72 @@
+//! `conductor-server`: runs the Conductor as a standalone service
+//!
+//! Loads configuration from `--config` (or `CONDUCTOR_CONFIG`) plus
+//! `CONDUCTOR_*` overrides, serves the HTTP API and drains accepted work on
+//! SIGTERM or Ctrl+C. Exits with status 2 on configuration errors and 1 when
+//! the server cannot start.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Parser;
+use conductor::server::{self, ServerConfig, ServerState};
+use tracing::{error, info};
+
+#[derive(Parser)]
+#[command(name = "conductor-server", about = "Run the Conductor agent orchestration service")]
+struct Args {
+    /// JSON config file; defaults to $CONDUCTOR_CONFIG, then built-in defaults
+    #[arg(long)]
+    config: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .json()
+        .with_env_filter(tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .init();
+
+    let args = Args::parse();
+    let config = match ServerConfig::load(args.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            error!(event = "config_error", "❌ {}", e);
+            eprintln!("conductor-server: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let listener = match tokio::net::TcpListener::bind(config.listen_addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(event = "bind_error", listen_addr = %config.listen_addr, "❌ Cannot listen on {}: {}", config.listen_addr, e);
+            eprintln!("conductor-server: cannot listen on {}: {} (set listen_addr or CONDUCTOR_LISTEN_ADDR)", config.listen_addr, e);
+            return ExitCode::from(1);
+        }
+    };
+
+    let listen_addr = listener.local_addr().unwrap_or(config.listen_addr);
+    let state = ServerState::new(config.build_conductor().await);
+    info!(
+        event = "startup",
+        version = env!("CARGO_PKG_VERSION"),
+        listen_addr = %listen_addr,
+        fortress_url = %config.fortress_url,
+        forge_url = %config.forge_url,
+        module_limits = config.module_limits.len(),
+        webhooks = config.webhooks.len(),
+        "🎼 Conductor server listening on {}", listen_addr
+    );
+
+    match server::serve(listener, state, server::shutdown_signal(), config.drain_timeout()).await {
+        Ok(drained) => {
+            info!(event = "shutdown", drained, "👋 Conductor server stopped");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!(event = "server_error", "❌ Conductor server failed: {}", e);
+            ExitCode::from(1)
+        }
+    }
+}
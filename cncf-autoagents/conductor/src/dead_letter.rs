@@ -0,0 +1,88 @@
+//! Dead-letter queue for tasks that exhausted their retries
+//!
+//! The queue is kept in memory and, with a [`DeadLetterStore`], persisted so
+//! dead-lettered tasks survive a restart. [`crate::EncryptedStore`] is one,
+//! sealing each task's input at rest like the rest of the task records.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{encryption::EncryptionError, AgentTask, TaskResult};
+
+/// Why a task was dead-lettered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeadLetterReason {
+    /// Every retry attempt failed
+    RetriesExhausted,
+    /// The task tripped a security check; it is never retried
+    SecurityViolation,
+}
+
+/// A single failed execution attempt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttemptRecord {
+    pub attempt: u32,
+    pub execution_id: Option<String>,
+    pub error: String,
+    pub security_violations: Vec<String>,
+    pub failed_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl AttemptRecord {
+    /// Record a failed attempt from an unsuccessful result
+    pub(crate) fn from_result(attempt: u32, result: &TaskResult) -> Self {
+        let error = result.output
+            .get("error")
+            .and_then(|v| v.as_str())
+            .unwrap_or("Task failed")
+            .to_string();
+
+        Self {
+            attempt,
+            execution_id: Some(result.execution_id.clone()),
+            error,
+            security_violations: result.security_violations.clone(),
+            failed_at: result.completed_at,
+        }
+    }
+
+    /// Record a failed attempt from an execution error
+    pub(crate) fn from_error(attempt: u32, error: &str) -> Self {
+        Self {
+            attempt,
+            execution_id: None,
+            error: error.to_string(),
+            security_violations: vec![],
+            failed_at: chrono::Utc::now(),
+        }
+    }
+}
+
+/// A task moved to the dead-letter queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// Original task, including its input
+    pub task: AgentTask,
+    pub reason: DeadLetterReason,
+    pub attempts: Vec<AttemptRecord>,
+    pub dead_lettered_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl DeadLetter {
+    /// Error message of the last failed attempt
+    pub fn last_error(&self) -> Option<&str> {
+        self.attempts.last().map(|attempt| attempt.error.as_str())
+    }
+}
+
+/// Durable storage of the dead-letter queue
+pub trait DeadLetterStore: Send + Sync {
+    /// Store an entry, replacing any earlier one for the same task
+    fn save(&self, entry: &DeadLetter) -> Result<(), EncryptionError>;
+
+    /// Drop the entry of a task, if any
+    fn remove(&self, task_id: &str) -> Result<(), EncryptionError>;
+
+    /// Every stored entry
+    fn load(&self) -> Result<Vec<DeadLetter>, EncryptionError>;
+}
@@ -0,0 +1,174 @@
+//! Debug bundles for reproducing workflow runs locally
+//!
+//! A bundle captures everything a run saw: the workflow definition, the input
+//! each step was dispatched with, every result, the events emitted and the
+//! environment it ran in. Replaying a bundle reports, step by step, where a
+//! new run diverges from the recorded one.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diff::{self, ChangeDetection, OutputChange},
+    webhooks::WebhookEvent,
+    AgentWorkflow, MapElement, RetryPolicy, TaskResult, WorkflowExecution,
+};
+
+/// Bundle format written by this version of Conductor
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Output fields that differ on every execution and are not compared on replay
+const VOLATILE_OUTPUT_PATHS: &[&str] = &["/execution_id"];
+
+/// Error reading a bundle archive
+#[derive(Debug, thiserror::Error)]
+pub enum DebugBundleError {
+    #[error("Malformed debug bundle: {0}")]
+    Malformed(#[from] serde_json::Error),
+    #[error("Unsupported debug bundle format version {found} (expected {BUNDLE_FORMAT_VERSION})")]
+    UnsupportedVersion { found: u32 },
+}
+
+/// A step as it was dispatched during a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedStep {
+    pub step_id: String,
+    pub task_id: String,
+    pub module_id: String,
+    /// Input exactly as sent to Forge
+    pub rendered_input: serde_json::Value,
+    pub result: TaskResult,
+    /// Per-element outcomes of a map step
+    #[serde(default)]
+    pub elements: Vec<MapElement>,
+}
+
+/// An event emitted while the run executed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub event: WebhookEvent,
+    /// Task the event is about; `None` for workflow-level events
+    pub task_id: Option<String>,
+    pub occurred_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Where the run executed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentMetadata {
+    pub conductor_version: String,
+    pub fortress_url: String,
+    pub forge_url: String,
+    pub os: String,
+    pub arch: String,
+    pub hostname: Option<String>,
+    pub retry_policy: RetryPolicy,
+}
+
+/// Everything recorded for one workflow run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowRun {
+    pub run_id: String,
+    pub workflow: AgentWorkflow,
+    pub steps: Vec<RecordedStep>,
+    pub events: Vec<RecordedEvent>,
+    pub execution: WorkflowExecution,
+}
+
+/// Downloadable bundle for reproducing a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugBundle {
+    pub format_version: u32,
+    pub exported_at: chrono::DateTime<chrono::Utc>,
+    pub run: WorkflowRun,
+    pub environment: EnvironmentMetadata,
+}
+
+impl DebugBundle {
+    /// Serialize the bundle into a self-describing archive
+    pub fn to_archive(&self) -> Result<Vec<u8>, DebugBundleError> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Read an archive written by [`DebugBundle::to_archive`]
+    pub fn from_archive(bytes: &[u8]) -> Result<Self, DebugBundleError> {
+        let value: serde_json::Value = serde_json::from_slice(bytes)?;
+        let found = value.get("format_version").and_then(|v| v.as_u64()).unwrap_or_default() as u32;
+        if found != BUNDLE_FORMAT_VERSION {
+            return Err(DebugBundleError::UnsupportedVersion { found });
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+/// How a bundle is replayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayMode {
+    /// Re-execute every recorded step against Forge with its recorded input
+    RecordedInputs,
+    /// Re-render inputs from the workflow definition but reuse the recorded results
+    RecordedOutputs,
+}
+
+/// A difference between the original and the replayed step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Divergence {
+    /// The step's input no longer matches the recorded one
+    Input { changes: Vec<OutputChange> },
+    Success { original: bool, replayed: bool },
+    SecurityViolations { original: Vec<String>, replayed: Vec<String> },
+    Output { changes: Vec<OutputChange> },
+    /// The replayed step failed to execute at all
+    Error { message: String },
+}
+
+/// Replay outcome for one step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepReplay {
+    pub step_id: String,
+    pub divergences: Vec<Divergence>,
+}
+
+/// Step-by-step divergences of a replay from the original run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub run_id: String,
+    pub mode: ReplayMode,
+    pub steps: Vec<StepReplay>,
+}
+
+impl ReplayReport {
+    /// Steps that diverged from the original run
+    pub fn divergent_steps(&self) -> impl Iterator<Item = &StepReplay> {
+        self.steps.iter().filter(|step| !step.divergences.is_empty())
+    }
+
+    pub fn is_faithful(&self) -> bool {
+        self.divergent_steps().next().is_none()
+    }
+}
+
+/// Compare two JSON values, ignoring fields that change on every execution
+pub(crate) fn value_changes(original: &serde_json::Value, replayed: &serde_json::Value) -> Vec<OutputChange> {
+    let options = ChangeDetection::default().with_ignore_paths(VOLATILE_OUTPUT_PATHS.iter().copied());
+    diff::compare(Some(original), replayed, &options).changes
+}
+
+/// Divergences between a recorded and a replayed result
+pub(crate) fn result_divergences(original: &TaskResult, replayed: &TaskResult) -> Vec<Divergence> {
+    let mut divergences = Vec::new();
+    if original.success != replayed.success {
+        divergences.push(Divergence::Success { original: original.success, replayed: replayed.success });
+    }
+    if original.security_violations != replayed.security_violations {
+        divergences.push(Divergence::SecurityViolations {
+            original: original.security_violations.clone(),
+            replayed: replayed.security_violations.clone(),
+        });
+    }
+    let changes = value_changes(&original.output, &replayed.output);
+    if !changes.is_empty() {
+        divergences.push(Divergence::Output { changes });
+    }
+    divergences
+}
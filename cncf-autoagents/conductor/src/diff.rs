@@ -0,0 +1,211 @@
+//! Change detection between consecutive outputs of a recurring task
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+/// Change detection settings for a recurring task
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChangeDetection {
+    /// Runs with the same series key are compared with each other
+    pub series: String,
+    /// JSON pointers excluded from comparison, including everything below them
+    #[serde(default)]
+    pub ignore_paths: Vec<String>,
+    /// Numbers closer than this are considered equal
+    #[serde(default)]
+    pub numeric_tolerance: f64,
+    /// Per-path overrides of `numeric_tolerance`
+    #[serde(default)]
+    pub path_tolerances: HashMap<String, f64>,
+}
+
+impl ChangeDetection {
+    pub fn new(series: impl Into<String>) -> Self {
+        Self {
+            series: series.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_ignore_paths(mut self, paths: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.ignore_paths = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_numeric_tolerance(mut self, tolerance: f64) -> Self {
+        self.numeric_tolerance = tolerance;
+        self
+    }
+
+    pub fn with_path_tolerance(mut self, path: impl Into<String>, tolerance: f64) -> Self {
+        self.path_tolerances.insert(path.into(), tolerance);
+        self
+    }
+
+    fn is_ignored(&self, path: &str) -> bool {
+        self.ignore_paths.iter().any(|ignored| {
+            path == ignored || (path.starts_with(ignored.as_str()) && path[ignored.len()..].starts_with('/'))
+        })
+    }
+
+    fn tolerance(&self, path: &str) -> f64 {
+        self.path_tolerances.get(path).copied().unwrap_or(self.numeric_tolerance)
+    }
+}
+
+/// Outcome of comparing a run with the previous run of its series
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeStatus {
+    /// First run of the series; nothing to compare against
+    Baseline,
+    Unchanged,
+    Changed,
+}
+
+/// How a value differs between runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A single difference, located by JSON pointer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+/// Diff summary stored on a task result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeSummary {
+    pub status: ChangeStatus,
+    #[serde(default)]
+    pub changes: Vec<OutputChange>,
+}
+
+/// Compare `current` with the previous output of the series
+pub fn compare(previous: Option<&serde_json::Value>, current: &serde_json::Value, options: &ChangeDetection) -> ChangeSummary {
+    let Some(previous) = previous else {
+        return ChangeSummary {
+            status: ChangeStatus::Baseline,
+            changes: Vec::new(),
+        };
+    };
+
+    let mut changes = Vec::new();
+    diff_values("", previous, current, options, &mut changes);
+    ChangeSummary {
+        status: if changes.is_empty() {
+            ChangeStatus::Unchanged
+        } else {
+            ChangeStatus::Changed
+        },
+        changes,
+    }
+}
+
+fn diff_values(
+    path: &str,
+    before: &serde_json::Value,
+    after: &serde_json::Value,
+    options: &ChangeDetection,
+    changes: &mut Vec<OutputChange>,
+) {
+    use serde_json::Value;
+
+    if options.is_ignored(path) {
+        return;
+    }
+
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+            for key in keys {
+                let child = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                diff_entries(&child, before.get(key), after.get(key), options, changes);
+            }
+        }
+        (Value::Array(before), Value::Array(after)) => {
+            for index in 0..before.len().max(after.len()) {
+                let child = format!("{}/{}", path, index);
+                diff_entries(&child, before.get(index), after.get(index), options, changes);
+            }
+        }
+        (Value::Number(b), Value::Number(a)) => {
+            let (b, a) = (b.as_f64().unwrap_or_default(), a.as_f64().unwrap_or_default());
+            if (a - b).abs() > options.tolerance(path) {
+                changes.push(modified(path, before, after));
+            }
+        }
+        _ if before != after => changes.push(modified(path, before, after)),
+        _ => {}
+    }
+}
+
+fn diff_entries(
+    path: &str,
+    before: Option<&serde_json::Value>,
+    after: Option<&serde_json::Value>,
+    options: &ChangeDetection,
+    changes: &mut Vec<OutputChange>,
+) {
+    match (before, after) {
+        (Some(before), Some(after)) => diff_values(path, before, after, options, changes),
+        (before, after) if !options.is_ignored(path) => changes.push(OutputChange {
+            path: path.to_string(),
+            kind: if before.is_some() { ChangeKind::Removed } else { ChangeKind::Added },
+            before: before.cloned(),
+            after: after.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+fn modified(path: &str, before: &serde_json::Value, after: &serde_json::Value) -> OutputChange {
+    OutputChange {
+        path: path.to_string(),
+        kind: ChangeKind::Modified,
+        before: Some(before.clone()),
+        after: Some(after.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_structural_diff() {
+        let options = ChangeDetection::new("nightly")
+            .with_ignore_paths(["/checked_at"])
+            .with_numeric_tolerance(0.5)
+            .with_path_tolerance("/latency_ms", 10.0);
+
+        let before = json!({"checked_at": 1, "latency_ms": 100, "score": 1.0, "fields": ["a", "b"]});
+        assert_eq!(compare(None, &before, &options).status, ChangeStatus::Baseline);
+
+        let within_tolerance = json!({"checked_at": 2, "latency_ms": 108, "score": 1.4, "fields": ["a", "b"]});
+        assert_eq!(compare(Some(&before), &within_tolerance, &options).status, ChangeStatus::Unchanged);
+
+        let changed = json!({"checked_at": 3, "latency_ms": 100, "score": 2.0, "fields": ["a"], "new/key": true});
+        let summary = compare(Some(&before), &changed, &options);
+        assert_eq!(summary.status, ChangeStatus::Changed);
+        let paths: Vec<(&str, ChangeKind)> = summary.changes.iter().map(|c| (c.path.as_str(), c.kind)).collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("/fields/1", ChangeKind::Removed),
+                ("/new~1key", ChangeKind::Added),
+                ("/score", ChangeKind::Modified),
+            ]
+        );
+    }
+}
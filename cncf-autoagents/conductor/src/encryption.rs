@@ -0,0 +1,559 @@
+//! Encryption at rest for persisted task records
+//!
+//! Task inputs, results, artifacts and dead letters carry customer data. An
+//! [`EncryptedStore`] wraps a persistent [`RecordStore`] and seals those
+//! fields with AES-256-GCM before they are written, leaving ids, timestamps
+//! and labels readable. Each sealed field is an envelope naming the key it
+//! was sealed with, so keys can be rotated: [`EncryptedStore::rotate_key`]
+//! re-seals records under the current key a batch at a time, and records
+//! still under a retired key stay readable while it runs. Records that are
+//! corrupted or sealed with a key that does not match fail with
+//! [`EncryptionError::DecryptionFailed`], and so do plaintext fields unless
+//! the store is migrating records written before encryption was enabled
+//! ([`EncryptedStore::with_plaintext_migration`]).
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{dead_letter::DeadLetterStore, AgentTask, DeadLetter, TaskResult};
+
+/// Cipher named in every envelope
+pub const ENVELOPE_CIPHER: &str = "aes-256-gcm";
+
+/// Environment variable naming the current key for [`EnvKeyProvider`]
+pub const KEY_ID_ENV: &str = "CONDUCTOR_ENCRYPTION_KEY_ID";
+
+/// Prefix of the environment variables holding hex keys, followed by the upper-cased key id
+pub const KEY_ENV_PREFIX: &str = "CONDUCTOR_ENCRYPTION_KEY_";
+
+const NONCE_LEN: usize = 12;
+
+/// A 256-bit AES key
+pub type EncryptionKey = [u8; 32];
+
+/// Error sealing or opening stored records
+#[derive(Debug, thiserror::Error)]
+pub enum EncryptionError {
+    #[error("Failed to decrypt {record}: {reason}")]
+    DecryptionFailed { record: String, reason: String },
+    #[error("Invalid encryption key {key_id}: {reason}")]
+    InvalidKey { key_id: String, reason: String },
+    #[error("Record store error: {0}")]
+    Store(String),
+    #[error("Malformed record {record}: {source}")]
+    Malformed { record: String, source: serde_json::Error },
+}
+
+/// Source of encryption keys by id
+pub trait KeyProvider: Send + Sync {
+    /// Id of the key new envelopes are sealed with
+    fn current_key_id(&self) -> Result<String, EncryptionError>;
+
+    /// Key for `key_id`, current or retired
+    fn key(&self, key_id: &str) -> Option<EncryptionKey>;
+}
+
+/// Key configuration, with the keys hex-encoded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionConfig {
+    pub key_id: String,
+    pub key: String,
+    /// Earlier keys still needed to read records not yet rotated
+    #[serde(default)]
+    pub retired_keys: HashMap<String, String>,
+}
+
+/// Keys held in memory, from config or code
+pub struct StaticKeyProvider {
+    current: RwLock<String>,
+    keys: RwLock<HashMap<String, EncryptionKey>>,
+}
+
+impl StaticKeyProvider {
+    pub fn new(key_id: impl Into<String>, key: EncryptionKey) -> Self {
+        let key_id = key_id.into();
+        Self {
+            current: RwLock::new(key_id.clone()),
+            keys: RwLock::new(HashMap::from([(key_id, key)])),
+        }
+    }
+
+    pub fn from_config(config: &EncryptionConfig) -> Result<Self, EncryptionError> {
+        let provider = Self::new(config.key_id.clone(), parse_key(&config.key_id, &config.key)?);
+        for (key_id, key) in &config.retired_keys {
+            provider.add_key(key_id.clone(), parse_key(key_id, key)?);
+        }
+        Ok(provider)
+    }
+
+    /// Make a key available for reading
+    pub fn add_key(&self, key_id: impl Into<String>, key: EncryptionKey) {
+        self.keys.write().unwrap().insert(key_id.into(), key);
+    }
+
+    /// Seal new envelopes with `key_id` from now on; the previous key stays readable
+    pub fn rotate_to(&self, key_id: impl Into<String>, key: EncryptionKey) {
+        let key_id = key_id.into();
+        self.add_key(key_id.clone(), key);
+        *self.current.write().unwrap() = key_id;
+    }
+}
+
+impl KeyProvider for StaticKeyProvider {
+    fn current_key_id(&self) -> Result<String, EncryptionError> {
+        Ok(self.current.read().unwrap().clone())
+    }
+
+    fn key(&self, key_id: &str) -> Option<EncryptionKey> {
+        self.keys.read().unwrap().get(key_id).copied()
+    }
+}
+
+/// Keys read from the environment on each use: `CONDUCTOR_ENCRYPTION_KEY_ID`
+/// names the current key and `CONDUCTOR_ENCRYPTION_KEY_<ID>` holds each key in hex
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvKeyProvider;
+
+impl KeyProvider for EnvKeyProvider {
+    fn current_key_id(&self) -> Result<String, EncryptionError> {
+        std::env::var(KEY_ID_ENV).map_err(|_| EncryptionError::InvalidKey {
+            key_id: String::new(),
+            reason: format!("{} is not set", KEY_ID_ENV),
+        })
+    }
+
+    fn key(&self, key_id: &str) -> Option<EncryptionKey> {
+        let hex_key = std::env::var(format!("{}{}", KEY_ENV_PREFIX, key_id.to_uppercase())).ok()?;
+        parse_key(key_id, &hex_key).ok()
+    }
+}
+
+/// Decode a hex-encoded 256-bit key
+pub fn parse_key(key_id: &str, hex_key: &str) -> Result<EncryptionKey, EncryptionError> {
+    let invalid = |reason: String| EncryptionError::InvalidKey { key_id: key_id.to_string(), reason };
+    let bytes = hex::decode(hex_key.trim()).map_err(|e| invalid(e.to_string()))?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| invalid(format!("expected 32 bytes, got {}", bytes.len())))
+}
+
+/// A sealed field value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope {
+    pub enc: String,
+    /// Id of the key the value was sealed with
+    pub kid: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+impl Envelope {
+    /// The envelope in `value`, if it holds one
+    fn from_value(value: &serde_json::Value) -> Option<Self> {
+        let envelope: Self = serde_json::from_value(value.clone()).ok()?;
+        (envelope.enc == ENVELOPE_CIPHER).then_some(envelope)
+    }
+}
+
+/// Persistent storage of JSON records by key
+pub trait RecordStore: Send + Sync {
+    fn put(&self, key: &str, record: &serde_json::Value) -> Result<(), EncryptionError>;
+
+    fn get(&self, key: &str) -> Result<Option<serde_json::Value>, EncryptionError>;
+
+    /// Remove a record; removing a missing record is not an error
+    fn delete(&self, key: &str) -> Result<(), EncryptionError>;
+
+    /// Every stored key, in a stable order
+    fn keys(&self) -> Result<Vec<String>, EncryptionError>;
+}
+
+/// One JSON file per record in a directory
+pub struct FileRecordStore {
+    dir: PathBuf,
+}
+
+impl FileRecordStore {
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, EncryptionError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(|e| EncryptionError::Store(e.to_string()))?;
+        Ok(Self { dir })
+    }
+
+    /// File holding `key`; keys are hex-encoded so any key makes a safe name
+    pub fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", hex::encode(key)))
+    }
+}
+
+impl RecordStore for FileRecordStore {
+    fn put(&self, key: &str, record: &serde_json::Value) -> Result<(), EncryptionError> {
+        // Write then rename, so a crash never leaves a half-written record
+        let path = self.path(key);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, record.to_string()).map_err(|e| EncryptionError::Store(e.to_string()))?;
+        std::fs::rename(&tmp, &path).map_err(|e| EncryptionError::Store(e.to_string()))
+    }
+
+    fn get(&self, key: &str) -> Result<Option<serde_json::Value>, EncryptionError> {
+        match std::fs::read(self.path(key)) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|source| EncryptionError::Malformed { record: key.to_string(), source }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(EncryptionError::Store(e.to_string())),
+        }
+    }
+
+    fn delete(&self, key: &str) -> Result<(), EncryptionError> {
+        match std::fs::remove_file(self.path(key)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(EncryptionError::Store(e.to_string())),
+        }
+    }
+
+    fn keys(&self) -> Result<Vec<String>, EncryptionError> {
+        let entries = std::fs::read_dir(&self.dir).map_err(|e| EncryptionError::Store(e.to_string()))?;
+        let mut keys: Vec<String> = entries
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter_map(|name| name.strip_suffix(".json").and_then(|hex_key| hex::decode(hex_key).ok()))
+            .filter_map(|key| String::from_utf8(key).ok())
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+/// Fields sealed in each kind of record
+fn sealed_fields(key: &str) -> &'static [&'static str] {
+    match key.split('/').next() {
+        Some("task") => &["input"],
+        Some("result") => &["output", "change"],
+        Some("artifact") => &["content"],
+        Some("dead_letter") => &["task"],
+        _ => &[],
+    }
+}
+
+/// Progress of one [`EncryptedStore::rotate_key`] call
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RotationProgress {
+    /// Records re-sealed under the current key by this call
+    pub reencrypted: usize,
+    /// Records still sealed with another key, or not sealed at all
+    pub remaining: usize,
+}
+
+/// Task records sealed on write and opened on read
+pub struct EncryptedStore<S, K> {
+    inner: S,
+    keys: K,
+    migrate_plaintext: bool,
+}
+
+impl<S: RecordStore, K: KeyProvider> EncryptedStore<S, K> {
+    pub fn new(inner: S, keys: K) -> Self {
+        Self {
+            inner,
+            keys,
+            migrate_plaintext: false,
+        }
+    }
+
+    /// Read plaintext fields written before encryption was enabled, so
+    /// [`Self::rotate_key`] can seal them. Leave off once migrated: otherwise
+    /// anyone able to write the store could plant unsealed records.
+    pub fn with_plaintext_migration(mut self) -> Self {
+        self.migrate_plaintext = true;
+        self
+    }
+
+    pub fn keys(&self) -> &K {
+        &self.keys
+    }
+
+    pub fn put_task(&self, task: &AgentTask) -> Result<(), EncryptionError> {
+        self.put(&format!("task/{}", task.id), task)
+    }
+
+    pub fn get_task(&self, task_id: &str) -> Result<Option<AgentTask>, EncryptionError> {
+        self.get(&format!("task/{}", task_id))
+    }
+
+    pub fn put_result(&self, result: &TaskResult) -> Result<(), EncryptionError> {
+        self.put(&format!("result/{}", result.task_id), result)
+    }
+
+    pub fn get_result(&self, task_id: &str) -> Result<Option<TaskResult>, EncryptionError> {
+        self.get(&format!("result/{}", task_id))
+    }
+
+    /// Store an artifact produced by a task
+    pub fn put_artifact(&self, task_id: &str, name: &str, content: &serde_json::Value) -> Result<(), EncryptionError> {
+        let record = serde_json::json!({ "task_id": task_id, "name": name, "content": content });
+        self.put(&format!("artifact/{}/{}", task_id, name), &record)
+    }
+
+    pub fn get_artifact(&self, task_id: &str, name: &str) -> Result<Option<serde_json::Value>, EncryptionError> {
+        let record: Option<serde_json::Value> = self.get(&format!("artifact/{}/{}", task_id, name))?;
+        Ok(record.map(|mut record| record["content"].take()))
+    }
+
+    /// Re-seal up to `batch_size` records that are not under the current key.
+    /// Call repeatedly until nothing remains; reads keep working in between.
+    pub fn rotate_key(&self, batch_size: usize) -> Result<RotationProgress, EncryptionError> {
+        let current = self.keys.current_key_id()?;
+        let mut progress = RotationProgress::default();
+        for key in self.inner.keys()? {
+            let Some(record) = self.inner.get(&key)? else { continue };
+            let stale = sealed_fields(&key).iter().any(|field| match record.get(*field) {
+                None | Some(serde_json::Value::Null) => false,
+                Some(value) => Envelope::from_value(value).is_none_or(|envelope| envelope.kid != current),
+            });
+            if !stale {
+                continue;
+            }
+            if progress.reencrypted == batch_size {
+                progress.remaining += 1;
+                continue;
+            }
+            let opened = self.open(&key, record)?;
+            self.inner.put(&key, &self.seal(&key, opened)?)?;
+            progress.reencrypted += 1;
+        }
+        info!(
+            "🔑 Re-encrypted {} records under key {}, {} remaining",
+            progress.reencrypted, current, progress.remaining
+        );
+        Ok(progress)
+    }
+
+    fn put<T: Serialize>(&self, key: &str, value: &T) -> Result<(), EncryptionError> {
+        let record = serde_json::to_value(value)
+            .map_err(|source| EncryptionError::Malformed { record: key.to_string(), source })?;
+        self.inner.put(key, &self.seal(key, record)?)
+    }
+
+    fn get<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<Option<T>, EncryptionError> {
+        let Some(record) = self.inner.get(key)? else { return Ok(None) };
+        let record = self.open(key, record)?;
+        serde_json::from_value(record)
+            .map(Some)
+            .map_err(|source| EncryptionError::Malformed { record: key.to_string(), source })
+    }
+
+    fn seal(&self, key: &str, mut record: serde_json::Value) -> Result<serde_json::Value, EncryptionError> {
+        let key_id = self.keys.current_key_id()?;
+        let secret = self.keys.key(&key_id).ok_or_else(|| EncryptionError::InvalidKey {
+            key_id: key_id.clone(),
+            reason: "key not available".to_string(),
+        })?;
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&secret));
+        for field in sealed_fields(key) {
+            let Some(value) = record.get_mut(*field) else { continue };
+            if value.is_null() {
+                continue;
+            }
+            let plaintext = value.to_string();
+            let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+            // Bound to the record and field, so envelopes cannot be swapped between them
+            let aad = format!("{}#{}", key, field);
+            let ciphertext = cipher
+                .encrypt(&nonce, Payload { msg: plaintext.as_bytes(), aad: aad.as_bytes() })
+                .map_err(|_| EncryptionError::InvalidKey { key_id: key_id.clone(), reason: "encryption failed".to_string() })?;
+            *value = serde_json::json!(Envelope {
+                enc: ENVELOPE_CIPHER.to_string(),
+                kid: key_id.clone(),
+                nonce: hex::encode(nonce),
+                ciphertext: hex::encode(ciphertext),
+            });
+        }
+        Ok(record)
+    }
+
+    /// Replace every envelope in `record` with its plaintext. Unsealed values
+    /// pass through unchanged only while migrating plaintext records.
+    fn open(&self, key: &str, mut record: serde_json::Value) -> Result<serde_json::Value, EncryptionError> {
+        for field in sealed_fields(key) {
+            let Some(value) = record.get_mut(*field) else { continue };
+            if value.is_null() {
+                continue;
+            }
+            let failed = |reason: &str| EncryptionError::DecryptionFailed {
+                record: format!("{}#{}", key, field),
+                reason: reason.to_string(),
+            };
+            let envelope = match Envelope::from_value(value) {
+                Some(envelope) => envelope,
+                // A half-formed envelope is corruption, not plaintext
+                None if value.get("enc").is_some() => return Err(failed("malformed envelope")),
+                None if self.migrate_plaintext => continue,
+                None => return Err(failed("field is not sealed")),
+            };
+            let secret = self.keys.key(&envelope.kid).ok_or_else(|| failed(&format!("unknown key {}", envelope.kid)))?;
+            let nonce = hex::decode(&envelope.nonce).ok().filter(|nonce| nonce.len() == NONCE_LEN);
+            let ciphertext = hex::decode(&envelope.ciphertext).ok();
+            let (Some(nonce), Some(ciphertext)) = (nonce, ciphertext) else {
+                return Err(failed("malformed envelope"));
+            };
+            let aad = format!("{}#{}", key, field);
+            let plaintext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&secret))
+                .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: aad.as_bytes() })
+                .map_err(|_| failed(&format!("authentication failed with key {}", envelope.kid)))?;
+            *value = serde_json::from_slice(&plaintext).map_err(|_| failed("plaintext is not JSON"))?;
+        }
+        Ok(record)
+    }
+}
+
+impl<S: RecordStore, K: KeyProvider> DeadLetterStore for EncryptedStore<S, K> {
+    fn save(&self, entry: &DeadLetter) -> Result<(), EncryptionError> {
+        self.put(&format!("dead_letter/{}", entry.task.id), entry)
+    }
+
+    fn remove(&self, task_id: &str) -> Result<(), EncryptionError> {
+        self.inner.delete(&format!("dead_letter/{}", task_id))
+    }
+
+    fn load(&self) -> Result<Vec<DeadLetter>, EncryptionError> {
+        let mut entries = Vec::new();
+        for key in self.inner.keys()? {
+            if key.starts_with("dead_letter/") {
+                entries.extend(self.get::<DeadLetter>(&key)?);
+            }
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TaskPriority;
+
+    fn task(id: &str) -> AgentTask {
+        AgentTask {
+            id: id.to_string(),
+            name: "enrich".to_string(),
+            description: "Enrich customer record".to_string(),
+            module_id: "enricher".to_string(),
+            input: serde_json::json!({ "email": format!("{}@customer.example", id) }),
+            priority: TaskPriority::Normal,
+            timeout_ms: None,
+            created_at: chrono::Utc::now(),
+            labels: HashMap::new(),
+            trace_context: None,
+            change_detection: None,
+        }
+    }
+
+    fn result(task_id: &str) -> TaskResult {
+        TaskResult {
+            task_id: task_id.to_string(),
+            execution_id: "exec-1".to_string(),
+            success: true,
+            output: serde_json::json!({ "credit_limit": 5000 }),
+            execution_time_ms: 12,
+            security_violations: vec![],
+            completed_at: chrono::Utc::now(),
+            queued_ms: 0,
+            trace_id: None,
+            change: None,
+        }
+    }
+
+    #[test]
+    fn test_records_round_trip_encrypted() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = EncryptedStore::new(FileRecordStore::open(dir.path()).unwrap(), StaticKeyProvider::new("k1", [7; 32]));
+        store.put_task(&task("t1")).unwrap();
+        store.put_result(&result("t1")).unwrap();
+        store.put_artifact("t1", "report", &serde_json::json!("quarterly numbers")).unwrap();
+
+        assert_eq!(store.get_task("t1").unwrap().unwrap().input["email"], "t1@customer.example");
+        assert_eq!(store.get_result("t1").unwrap().unwrap().output["credit_limit"], 5000);
+        assert_eq!(store.get_artifact("t1", "report").unwrap().unwrap(), "quarterly numbers");
+        assert!(store.get_task("missing").unwrap().is_none());
+
+        // Nothing sensitive reaches the disk; metadata stays readable
+        let raw = std::fs::read_to_string(FileRecordStore::open(dir.path()).unwrap().path("task/t1")).unwrap();
+        assert!(!raw.contains("customer.example"));
+        assert!(raw.contains("\"module_id\":\"enricher\"") && raw.contains("\"kid\":\"k1\""));
+
+        // A wrong key or a tampered record fails as a decryption error
+        let inner = FileRecordStore::open(dir.path()).unwrap();
+        let wrong = EncryptedStore::new(FileRecordStore::open(dir.path()).unwrap(), StaticKeyProvider::new("k1", [8; 32]));
+        assert!(matches!(wrong.get_task("t1"), Err(EncryptionError::DecryptionFailed { .. })));
+        let mut record = inner.get("result/t1").unwrap().unwrap();
+        record["output"]["ciphertext"] = "00ff".into();
+        inner.put("result/t1", &record).unwrap();
+        let err = store.get_result("t1").unwrap_err();
+        assert!(matches!(&err, EncryptionError::DecryptionFailed { record, .. } if record == "result/t1#output"), "{}", err);
+
+        // Envelopes are bound to their record
+        let sealed = inner.get("task/t1").unwrap().unwrap();
+        inner.put("task/t2", &sealed).unwrap();
+        assert!(matches!(store.get_task("t2"), Err(EncryptionError::DecryptionFailed { .. })));
+    }
+
+    #[test]
+    fn test_plaintext_records_need_the_migration_flag() {
+        let dir = tempfile::tempdir().unwrap();
+        let inner = FileRecordStore::open(dir.path()).unwrap();
+        inner.put("task/t1", &serde_json::to_value(task("t1")).unwrap()).unwrap();
+
+        let store = EncryptedStore::new(FileRecordStore::open(dir.path()).unwrap(), StaticKeyProvider::new("k1", [1; 32]));
+        let err = store.get_task("t1").unwrap_err();
+        assert!(matches!(&err, EncryptionError::DecryptionFailed { record, .. } if record == "task/t1#input"), "{}", err);
+        assert!(matches!(store.rotate_key(10), Err(EncryptionError::DecryptionFailed { .. })));
+
+        let migrating = EncryptedStore::new(FileRecordStore::open(dir.path()).unwrap(), StaticKeyProvider::new("k1", [1; 32]))
+            .with_plaintext_migration();
+        assert_eq!(migrating.get_task("t1").unwrap().unwrap().input["email"], "t1@customer.example");
+        assert_eq!(migrating.rotate_key(10).unwrap(), RotationProgress { reencrypted: 1, remaining: 0 });
+
+        // Once sealed, the record reads back without the flag
+        assert_eq!(store.get_task("t1").unwrap().unwrap().input["email"], "t1@customer.example");
+    }
+
+    #[test]
+    fn test_incremental_key_rotation_keeps_old_records_readable() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = EncryptedStore::new(FileRecordStore::open(dir.path()).unwrap(), StaticKeyProvider::new("k1", [1; 32]));
+        for id in ["t1", "t2", "t3"] {
+            store.put_task(&task(id)).unwrap();
+            store.put_result(&result(id)).unwrap();
+        }
+
+        store.keys().rotate_to("k2", [2; 32]);
+        store.put_task(&task("t4")).unwrap();
+
+        let first = store.rotate_key(2).unwrap();
+        assert_eq!(first, RotationProgress { reencrypted: 2, remaining: 4 });
+        // Mid-rotation, records under either key read back
+        for id in ["t1", "t2", "t3", "t4"] {
+            assert_eq!(store.get_task(id).unwrap().unwrap().input["email"], format!("{}@customer.example", id));
+        }
+        assert_eq!(store.get_result("t3").unwrap().unwrap().output["credit_limit"], 5000);
+
+        let mut progress = first;
+        while progress.remaining > 0 {
+            progress = store.rotate_key(2).unwrap();
+        }
+        assert_eq!(store.rotate_key(2).unwrap(), RotationProgress::default());
+
+        // Once rotated, the retired key is no longer needed
+        let k2_only = EncryptedStore::new(FileRecordStore::open(dir.path()).unwrap(), StaticKeyProvider::new("k2", [2; 32]));
+        for id in ["t1", "t2", "t3"] {
+            assert!(k2_only.get_task(id).unwrap().is_some());
+            assert!(k2_only.get_result(id).unwrap().is_some());
+        }
+    }
+}
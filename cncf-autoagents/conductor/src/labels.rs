@@ -0,0 +1,115 @@
+//! Task labels: arbitrary key/value dimensions attached to tasks
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of labels on a single task
+pub const MAX_LABELS: usize = 16;
+
+/// Maximum length of a label key or value
+pub const MAX_LABEL_LENGTH: usize = 63;
+
+/// Label validation failure
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LabelError {
+    #[error("Too many labels: {count} (max {max})")]
+    TooManyLabels { count: usize, max: usize },
+    #[error("Invalid label key '{key}'")]
+    InvalidKey { key: String },
+    #[error("Invalid value for label '{key}': '{value}'")]
+    InvalidValue { key: String, value: String },
+}
+
+/// Validate a label set.
+///
+/// Keys must be 1-63 characters of `[A-Za-z0-9._/-]` starting with an
+/// alphanumeric; values may be empty and use the same characters minus `/`.
+pub fn validate_labels(labels: &HashMap<String, String>) -> Result<(), LabelError> {
+    if labels.len() > MAX_LABELS {
+        return Err(LabelError::TooManyLabels {
+            count: labels.len(),
+            max: MAX_LABELS,
+        });
+    }
+
+    for (key, value) in labels {
+        let key_ok = !key.is_empty()
+            && key.len() <= MAX_LABEL_LENGTH
+            && key.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+        if !key_ok {
+            return Err(LabelError::InvalidKey { key: key.clone() });
+        }
+
+        let value_ok = value.len() <= MAX_LABEL_LENGTH
+            && value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !value_ok {
+            return Err(LabelError::InvalidValue {
+                key: key.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Equality-based label selector; matches when every entry is present with the same value
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelSelector {
+    pub match_labels: HashMap<String, String>,
+}
+
+impl LabelSelector {
+    /// Selector from `(key, value)` pairs
+    pub fn new<K: Into<String>, V: Into<String>>(pairs: impl IntoIterator<Item = (K, V)>) -> Self {
+        Self {
+            match_labels: pairs.into_iter().map(|(k, v)| (k.into(), v.into())).collect(),
+        }
+    }
+
+    /// Check whether a label set satisfies this selector
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        self.match_labels
+            .iter()
+            .all(|(key, value)| labels.get(key) == Some(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_label_validation() {
+        assert!(validate_labels(&labels(&[("team", "payments"), ("acme.io/env", "prod")])).is_ok());
+        assert!(validate_labels(&labels(&[("customer", "")])).is_ok());
+
+        assert_eq!(
+            validate_labels(&labels(&[("-team", "x")])),
+            Err(LabelError::InvalidKey { key: "-team".to_string() })
+        );
+        assert!(matches!(
+            validate_labels(&labels(&[("env", "prod west")])),
+            Err(LabelError::InvalidValue { .. })
+        ));
+
+        let too_many: HashMap<String, String> = (0..=MAX_LABELS).map(|i| (format!("k{}", i), "v".to_string())).collect();
+        assert!(matches!(validate_labels(&too_many), Err(LabelError::TooManyLabels { .. })));
+    }
+
+    #[test]
+    fn test_selector_matching() {
+        let task_labels = labels(&[("team", "payments"), ("env", "prod")]);
+
+        assert!(LabelSelector::default().matches(&task_labels));
+        assert!(LabelSelector::new([("team", "payments")]).matches(&task_labels));
+        assert!(!LabelSelector::new([("team", "payments"), ("env", "staging")]).matches(&task_labels));
+        assert!(!LabelSelector::new([("customer", "acme")]).matches(&task_labels));
+    }
+}
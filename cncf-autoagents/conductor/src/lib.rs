@@ -3,11 +3,41 @@
 //! The central coordination system that orchestrates agent tasks through
 //! the Fortress gateway and Forge execution environment.
 
+pub mod dead_letter;
+pub mod debug_bundle;
+pub mod diff;
+pub mod encryption;
+pub mod labels;
+pub mod map_step;
+pub mod metrics;
+pub mod query;
+pub mod routing;
+pub mod schema;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod validation;
+pub mod webhooks;
+
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, Semaphore};
 use serde::{Deserialize, Serialize};
-use tracing::{info, warn, error};
+use futures::StreamExt;
+use tracing::{info, warn, error, Instrument};
+
+pub use forge::TraceContext;
+pub use dead_letter::{AttemptRecord, DeadLetter, DeadLetterReason, DeadLetterStore};
+pub use debug_bundle::{DebugBundle, DebugBundleError, Divergence, ReplayMode, ReplayReport, WorkflowRun};
+pub use diff::{ChangeDetection, ChangeStatus, ChangeSummary};
+pub use encryption::{EncryptedStore, EncryptionError, KeyProvider};
+pub use labels::{LabelError, LabelSelector};
+pub use map_step::{MapElement, MapElementStatus, MapFailurePolicy, MapStep};
+pub use metrics::ConductorMetrics;
+pub use query::{Page, ResultFilter, TaskFilter};
+pub use routing::{RouteRule, RoutingTable};
+pub use schema::{InvalidSchema, InvalidTaskInput, SchemaViolation};
+pub use validation::{InvalidWorkflow, WorkflowValidationError};
+pub use webhooks::{WebhookConfig, WebhookEvent, WebhookPayload, WebhookRetry};
 
 /// Agent task definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +50,15 @@ pub struct AgentTask {
     pub priority: TaskPriority,
     pub timeout_ms: Option<u64>,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Arbitrary dimensions such as team, environment or customer
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// W3C trace context; assigned on submission when absent
+    #[serde(default)]
+    pub trace_context: Option<TraceContext>,
+    /// Compare the output with the previous run of the same series
+    #[serde(default)]
+    pub change_detection: Option<ChangeDetection>,
 }
 
 /// Task execution result
@@ -32,6 +71,15 @@ pub struct TaskResult {
     pub execution_time_ms: u64,
     pub security_violations: Vec<String>,
     pub completed_at: chrono::DateTime<chrono::Utc>,
+    /// Time spent waiting for a per-module concurrency slot
+    #[serde(default)]
+    pub queued_ms: u64,
+    /// Trace id of the task, shared with its Fortress and Forge logs
+    #[serde(default)]
+    pub trace_id: Option<String>,
+    /// Difference from the previous run, for tasks with change detection
+    #[serde(default)]
+    pub change: Option<ChangeSummary>,
 }
 
 /// Task priority levels
@@ -62,6 +110,25 @@ pub struct WorkflowStep {
     pub input_template: serde_json::Value,
     pub depends_on: Vec<String>,
     pub retry_policy: RetryPolicy,
+    /// Step timeout; defaults to an even share of the workflow timeout
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Run the step once per element of an array from earlier step outputs
+    #[serde(default)]
+    pub map: Option<MapStep>,
+}
+
+/// Record of a finished workflow execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowExecution {
+    pub workflow_id: String,
+    /// Identifies the run for debug bundle export
+    #[serde(default)]
+    pub run_id: String,
+    pub success: bool,
+    pub step_results: Vec<TaskResult>,
+    pub duration_ms: u64,
+    pub completed_at: chrono::DateTime<chrono::Utc>,
 }
 
 /// Retry policy for failed steps
@@ -71,6 +138,18 @@ pub struct RetryPolicy {
     pub backoff_ms: u64,
 }
 
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff_ms: 0,
+        }
+    }
+}
+
+/// Concurrency slots shared by the tasks matching a selector
+type LabelLimit = (LabelSelector, Arc<Semaphore>);
+
 /// Main Conductor service
 #[derive(Clone)]
 pub struct Conductor {
@@ -79,6 +158,26 @@ pub struct Conductor {
     tasks: Arc<RwLock<HashMap<String, AgentTask>>>,
     results: Arc<RwLock<HashMap<String, TaskResult>>>,
     workflows: Arc<RwLock<HashMap<String, AgentWorkflow>>>,
+    /// Input schemas by module id, stored alongside workflows
+    module_schemas: Arc<RwLock<HashMap<String, schema::ModuleSchema>>>,
+    dead_letters: Arc<RwLock<HashMap<String, DeadLetter>>>,
+    /// Where dead letters are persisted, when they should survive a restart
+    dead_letter_store: Option<Arc<dyn DeadLetterStore>>,
+    retry_policy: RetryPolicy,
+    #[cfg(test)]
+    flaky_forge: tests::FlakyForge,
+    /// Concurrency limits per module id; modules without an entry are unlimited
+    module_limits: Arc<RwLock<HashMap<String, Arc<Semaphore>>>>,
+    /// Concurrency limits shared by every task matching a label selector
+    label_limits: Arc<RwLock<Vec<LabelLimit>>>,
+    /// Fortress endpoints by module and labels; unrouted tasks use `fortress_url`
+    routes: Arc<RwLock<RoutingTable>>,
+    /// Latest successful output per change-detection series
+    series_outputs: Arc<RwLock<HashMap<String, serde_json::Value>>>,
+    /// Recorded workflow runs by run id, for debug bundle export
+    workflow_runs: Arc<RwLock<HashMap<String, WorkflowRun>>>,
+    webhooks: webhooks::WebhookDispatcher,
+    metrics: ConductorMetrics,
     http_client: reqwest::Client,
 }
 
@@ -96,12 +195,267 @@ impl Conductor {
             tasks: Arc::new(RwLock::new(HashMap::new())),
             results: Arc::new(RwLock::new(HashMap::new())),
             workflows: Arc::new(RwLock::new(HashMap::new())),
+            module_schemas: Arc::new(RwLock::new(HashMap::new())),
+            dead_letters: Arc::new(RwLock::new(HashMap::new())),
+            dead_letter_store: None,
+            retry_policy: RetryPolicy::default(),
+            #[cfg(test)]
+            flaky_forge: tests::FlakyForge::default(),
+            module_limits: Arc::new(RwLock::new(HashMap::new())),
+            label_limits: Arc::new(RwLock::new(Vec::new())),
+            routes: Arc::new(RwLock::new(RoutingTable::default())),
+            series_outputs: Arc::new(RwLock::new(HashMap::new())),
+            workflow_runs: Arc::new(RwLock::new(HashMap::new())),
+            webhooks: webhooks::WebhookDispatcher::new(http_client.clone()),
+            metrics: ConductorMetrics::new(),
             http_client,
         }
     }
 
-    /// Execute an agent task end-to-end
+    /// Set the retry policy used by `execute_task`
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Persist dead letters in `store`, starting from the entries it already holds
+    pub fn with_dead_letter_store(mut self, store: Arc<dyn DeadLetterStore>) -> Result<Self, EncryptionError> {
+        let restored: HashMap<String, DeadLetter> = store.load()?
+            .into_iter()
+            .map(|entry| (entry.task.id.clone(), entry))
+            .collect();
+        if !restored.is_empty() {
+            info!("🪦 Restored {} dead-lettered task(s)", restored.len());
+        }
+        self.dead_letters = Arc::new(RwLock::new(restored));
+        self.dead_letter_store = Some(store);
+        Ok(self)
+    }
+
+    /// Export the given task label keys as metric dimensions
+    pub fn with_metric_label_keys<I, K>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        self.metrics = self.metrics.with_label_keys(keys);
+        self
+    }
+
+    /// Limit how many tasks for `module_id` may execute at once.
+    ///
+    /// Excess tasks wait for a slot instead of dispatching. Changing a limit
+    /// only affects tasks that have not yet acquired a slot.
+    pub async fn set_module_limit(&self, module_id: &str, limit: usize) {
+        info!("🚦 Module {} limited to {} concurrent task(s)", module_id, limit);
+        self.module_limits.write().await
+            .insert(module_id.to_string(), Arc::new(Semaphore::new(limit.max(1))));
+    }
+
+    /// Remove the concurrency limit for `module_id`
+    pub async fn clear_module_limit(&self, module_id: &str) {
+        self.module_limits.write().await.remove(module_id);
+    }
+
+    /// Limit how many tasks matching `selector` may execute at once, across modules.
+    ///
+    /// Applies on top of any module limit; setting the same selector again
+    /// replaces its limit.
+    pub async fn set_label_limit(&self, selector: LabelSelector, limit: usize) {
+        info!("🚦 Tasks matching {:?} limited to {} concurrent task(s)", selector.match_labels, limit);
+        let semaphore = Arc::new(Semaphore::new(limit.max(1)));
+        let mut label_limits = self.label_limits.write().await;
+        match label_limits.iter_mut().find(|(existing, _)| *existing == selector) {
+            Some((_, existing)) => *existing = semaphore,
+            None => label_limits.push((selector, semaphore)),
+        }
+    }
+
+    /// Remove the concurrency limit for `selector`
+    pub async fn clear_label_limit(&self, selector: &LabelSelector) {
+        self.label_limits.write().await.retain(|(existing, _)| existing != selector);
+    }
+
+    /// Route tasks matching `rule` through its Fortress endpoint, after any earlier rules
+    pub async fn add_route(&self, rule: RouteRule) {
+        info!("🧭 Routing {:?} {:?} via {}", rule.module_id, rule.selector.match_labels, rule.fortress_url);
+        self.routes.write().await.add(rule);
+    }
+
+    /// Fortress endpoint `task` is sent through
+    pub async fn fortress_url_for(&self, task: &AgentTask) -> String {
+        self.routes.read().await
+            .resolve(task)
+            .map_or_else(|| self.fortress_url.clone(), |rule| rule.fortress_url.clone())
+    }
+
+    /// Register a webhook notified when tasks or workflows finish; returns its id
+    pub async fn register_webhook(&self, config: WebhookConfig) -> String {
+        self.webhooks.register(config).await
+    }
+
+    /// Remove a webhook; returns false if it was not registered
+    pub async fn unregister_webhook(&self, webhook_id: &str) -> bool {
+        self.webhooks.unregister(webhook_id).await
+    }
+
+    /// Receive task and workflow events as they are dispatched to webhooks
+    pub fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<WebhookPayload> {
+        self.webhooks.subscribe()
+    }
+
+    /// Notify webhooks that a task finished
+    async fn notify_task_finished(&self, result: &TaskResult, labels: &HashMap<String, String>) {
+        if let Some(event) = task_event(result) {
+            self.webhooks.dispatch(event, serde_json::json!(result), labels).await;
+        }
+    }
+
+    /// Metrics collector for this Conductor
+    pub fn metrics(&self) -> &ConductorMetrics {
+        &self.metrics
+    }
+
+    /// Render task and workflow metrics in the Prometheus text format
+    pub fn gather_metrics(&self) -> String {
+        self.metrics.gather()
+    }
+
+    /// Execute an agent task end-to-end, retrying per the Conductor retry policy
     pub async fn execute_task(&self, task: AgentTask) -> Result<TaskResult, Box<dyn std::error::Error>> {
+        let retry_policy = self.retry_policy.clone();
+        self.execute_task_with_retry(task, &retry_policy).await
+    }
+
+    /// Execute a task, retrying failures up to `retry_policy.max_attempts`.
+    ///
+    /// Tasks that fail every attempt, or fail with security violations, are
+    /// moved to the dead-letter queue.
+    pub async fn execute_task_with_retry(&self, mut task: AgentTask, retry_policy: &RetryPolicy) -> Result<TaskResult, Box<dyn std::error::Error>> {
+        labels::validate_labels(&task.labels)?;
+        self.validate_task_input(&task).await?;
+        let trace = task.trace_context.get_or_insert_with(TraceContext::new).clone();
+        // Kept for event notifications after the task moves to the dead-letter queue
+        let labels = task.labels.clone();
+
+        let max_attempts = retry_policy.max_attempts.max(1);
+        let mut attempts = Vec::new();
+
+        let queue_wait = (chrono::Utc::now() - task.created_at).to_std().unwrap_or_default();
+        self.metrics.record_task_submitted(queue_wait);
+        self.metrics.record_labeled_task_submitted(&task.labels);
+
+        for attempt in 1..=max_attempts {
+            // Errors are stringified so the future stays `Send` across the awaits below
+            let span = tracing::info_span!(
+                "conductor.task",
+                task_id = %task.id,
+                module_id = %task.module_id,
+                attempt,
+                trace_id = %trace.trace_id,
+                span_id = %trace.span_id,
+            );
+            let outcome = self.execute_attempt(task.clone()).instrument(span).await;
+            let failure = match outcome.map_err(|e| e.to_string()) {
+                Ok(result) if result.success => {
+                    self.notify_task_finished(&result, &labels).await;
+                    return Ok(result);
+                }
+                Ok(result) => {
+                    attempts.push(AttemptRecord::from_result(attempt, &result));
+                    if !result.security_violations.is_empty() {
+                        self.dead_letter(task, DeadLetterReason::SecurityViolation, attempts).await;
+                        self.notify_task_finished(&result, &labels).await;
+                        return Ok(result);
+                    }
+                    Ok(result)
+                }
+                Err(e) => {
+                    attempts.push(AttemptRecord::from_error(attempt, &e));
+                    Err(e)
+                }
+            };
+
+            if attempt == max_attempts {
+                let task_id = task.id.clone();
+                self.dead_letter(task, DeadLetterReason::RetriesExhausted, attempts).await;
+                let failed = match &failure {
+                    Ok(result) => result.clone(),
+                    Err(e) => TaskResult {
+                        task_id,
+                        execution_id: String::new(),
+                        success: false,
+                        output: serde_json::json!({"error": e}),
+                        execution_time_ms: 0,
+                        security_violations: vec![],
+                        completed_at: chrono::Utc::now(),
+                        queued_ms: 0,
+                        trace_id: Some(trace.trace_id.clone()),
+                        change: None,
+                    },
+                };
+                self.notify_task_finished(&failed, &labels).await;
+                return failure.map_err(Into::into);
+            }
+
+            warn!("🔁 Retrying task {} (attempt {}/{})", task.id, attempt + 1, max_attempts);
+            if retry_policy.backoff_ms > 0 {
+                tokio::time::sleep(std::time::Duration::from_millis(retry_policy.backoff_ms * attempt as u64)).await;
+            }
+        }
+
+        unreachable!("retry loop always returns on the last attempt")
+    }
+
+    /// Move a task to the dead-letter queue
+    async fn dead_letter(&self, task: AgentTask, reason: DeadLetterReason, attempts: Vec<AttemptRecord>) {
+        error!("🪦 Dead-lettering task {} after {} attempt(s): {:?}", task.id, attempts.len(), reason);
+        self.metrics.record_task_failed(&task.module_id);
+        self.metrics.record_labeled_task_failed(&task.labels);
+
+        let entry = DeadLetter {
+            task,
+            reason,
+            attempts,
+            dead_lettered_at: chrono::Utc::now(),
+        };
+        if let Some(store) = &self.dead_letter_store {
+            if let Err(e) = store.save(&entry) {
+                error!("🪦 Failed to persist dead letter for task {}: {}", entry.task.id, e);
+            }
+        }
+        self.dead_letters.write().await.insert(entry.task.id.clone(), entry);
+    }
+
+    /// List dead-lettered tasks, oldest first
+    pub async fn list_dead_letters(&self) -> Vec<DeadLetter> {
+        let mut dead_letters: Vec<DeadLetter> = self.dead_letters.read().await.values().cloned().collect();
+        dead_letters.sort_by(|a, b| a.dead_lettered_at.cmp(&b.dead_lettered_at).then_with(|| a.task.id.cmp(&b.task.id)));
+        dead_letters
+    }
+
+    /// Replay a dead-lettered task with its original input and fresh attempt counters
+    pub async fn requeue_dead_letter(&self, task_id: &str) -> Result<TaskResult, Box<dyn std::error::Error>> {
+        let entry = self.dead_letters.write().await
+            .remove(task_id)
+            .ok_or_else(|| format!("No dead letter for task: {}", task_id))?;
+        if let Some(store) = &self.dead_letter_store {
+            if let Err(e) = store.remove(task_id) {
+                warn!("🪦 Failed to remove persisted dead letter for task {}: {}", task_id, e);
+            }
+        }
+
+        info!("📬 Requeueing dead-lettered task {}", task_id);
+        self.execute_task(entry.task).await
+    }
+
+    /// Run a single execution attempt and record its result
+    async fn execute_attempt(&self, task: AgentTask) -> Result<TaskResult, Box<dyn std::error::Error>> {
+        // Held until the attempt's result is recorded
+        let queue_start = std::time::Instant::now();
+        let _permits = self.acquire_slots(&task).await;
+        let queued_ms = queue_start.elapsed().as_millis() as u64;
+
         let start_time = std::time::Instant::now();
         info!("🎼 Starting task execution: {} ({})", task.name, task.id);
 
@@ -115,7 +469,12 @@ impl Conductor {
         let execution_result = self.route_through_fortress(task.clone()).await?;
 
         let execution_time = start_time.elapsed();
-        let result = TaskResult {
+        self.metrics.record_task_execution(execution_time);
+        if !execution_result.security_violations.is_empty() {
+            self.metrics.record_security_violations(execution_result.security_violations.len());
+        }
+
+        let mut result = TaskResult {
             task_id: task.id.clone(),
             execution_id: execution_result.execution_id,
             success: execution_result.success,
@@ -123,8 +482,15 @@ impl Conductor {
             execution_time_ms: execution_time.as_millis() as u64,
             security_violations: execution_result.security_violations,
             completed_at: chrono::Utc::now(),
+            queued_ms,
+            trace_id: task.trace_context.as_ref().map(|trace| trace.trace_id.clone()),
+            change: None,
         };
 
+        if let (true, Some(options)) = (result.success, &task.change_detection) {
+            result.change = Some(self.detect_change(options, &result.output).await);
+        }
+
         // Store result
         {
             let mut results = self.results.write().await;
@@ -140,12 +506,61 @@ impl Conductor {
         Ok(result)
     }
 
+    /// Compare an output with the previous one in its series and make it the new baseline
+    async fn detect_change(&self, options: &ChangeDetection, output: &serde_json::Value) -> ChangeSummary {
+        let mut series_outputs = self.series_outputs.write().await;
+        let summary = diff::compare(series_outputs.get(&options.series), output, options);
+        series_outputs.insert(options.series.clone(), output.clone());
+
+        match summary.status {
+            ChangeStatus::Changed => info!("🔍 Output of series {} changed at {} path(s)", options.series, summary.changes.len()),
+            ChangeStatus::Unchanged => info!("🔍 Output of series {} unchanged", options.series),
+            ChangeStatus::Baseline => info!("🔍 Recorded baseline output for series {}", options.series),
+        }
+        summary
+    }
+
+    /// Wait for a concurrency slot on the task's module and on every label limit it matches.
+    ///
+    /// Slots are taken module first, then label limits in the order they were
+    /// set, so tasks never wait on each other in a cycle.
+    async fn acquire_slots(&self, task: &AgentTask) -> Vec<tokio::sync::OwnedSemaphorePermit> {
+        let mut semaphores: Vec<Arc<Semaphore>> = self.module_limits.read().await.get(&task.module_id).cloned().into_iter().collect();
+        semaphores.extend(
+            self.label_limits.read().await.iter()
+                .filter(|(selector, _)| selector.matches(&task.labels))
+                .map(|(_, semaphore)| semaphore.clone()),
+        );
+
+        let mut permits = Vec::with_capacity(semaphores.len());
+        for semaphore in semaphores {
+            if let Ok(permit) = semaphore.clone().try_acquire_owned() {
+                permits.push(permit);
+                continue;
+            }
+
+            info!("⏳ Task {} waiting for a concurrency slot", task.id);
+            if let Ok(permit) = semaphore.acquire_owned().await {
+                permits.push(permit);
+            }
+        }
+        permits
+    }
+
     /// Route task through Fortress gateway to Forge
     async fn route_through_fortress(&self, task: AgentTask) -> Result<forge::ExecutionResult, Box<dyn std::error::Error>> {
-        info!("🏰 Routing task through Fortress: {} -> {}", task.name, task.module_id);
+        let fortress_url = self.fortress_url_for(&task).await;
+        info!("🏰 Routing task through Fortress {}: {} -> {}", fortress_url, task.name, task.module_id);
+
+        // Each hop gets its own span within the task's trace
+        let headers = match &task.trace_context {
+            Some(trace) => serde_json::json!({ forge::trace::TRACEPARENT_HEADER: trace.child().traceparent() }),
+            None => serde_json::json!({}),
+        };
 
         // Prepare execution request
         let execution_request = serde_json::json!({
+            "headers": headers,
             "task_id": task.id,
             "module_id": task.module_id,
             "input": task.input,
@@ -166,7 +581,11 @@ impl Conductor {
     async fn simulate_fortress_routing(&self, request: serde_json::Value) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
         info!("🔀 Fortress routing simulation");
 
-        // Extract module ID and input
+        // Extract task ID, module ID and input
+        let task_id = request.get("task_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+
         let module_id = request.get("module_id")
             .and_then(|v| v.as_str())
             .ok_or("Missing module_id")?;
@@ -175,24 +594,40 @@ impl Conductor {
             .ok_or("Missing input")?
             .clone();
 
+        let trace = request.pointer(&format!("/headers/{}", forge::trace::TRACEPARENT_HEADER))
+            .and_then(|v| v.as_str())
+            .and_then(TraceContext::parse);
+
         // Simulate security checks and routing
         tokio::time::sleep(std::time::Duration::from_millis(50)).await;
 
         // Route to Forge execution
-        let forge_result = self.execute_in_forge(module_id, input).await?;
+        let forge_result = self.execute_in_forge(task_id, module_id, input, trace.as_ref()).await?;
 
         Ok(serde_json::to_value(forge_result)?)
     }
 
     /// Execute in Forge (simplified simulation)
-    async fn execute_in_forge(&self, module_id: &str, input: serde_json::Value) -> Result<forge::ExecutionResult, Box<dyn std::error::Error>> {
-        info!("🔥 Executing in Forge: {}", module_id);
+    async fn execute_in_forge(
+        &self,
+        task_id: &str,
+        module_id: &str,
+        input: serde_json::Value,
+        trace: Option<&TraceContext>,
+    ) -> Result<forge::ExecutionResult, Box<dyn std::error::Error>> {
+        match trace {
+            Some(trace) => info!("🔥 Executing task {} in Forge: {} (trace: {})", task_id, module_id, trace.trace_id),
+            None => info!("🔥 Executing task {} in Forge: {}", task_id, module_id),
+        }
 
         // Simulate Forge execution (in production, this would be an HTTP call)
+        if let Some(work_ms) = input.get("work_ms").and_then(|v| v.as_u64()) {
+            tokio::time::sleep(std::time::Duration::from_millis(work_ms)).await;
+        }
         let execution_id = uuid::Uuid::new_v4().to_string();
 
         // Simulate different execution outcomes
-        let (success, output, memory_used, violations) = match input.get("command").and_then(|v| v.as_str()) {
+        let (success, mut output, memory_used, violations) = match input.get("command").and_then(|v| v.as_str()) {
             Some("malicious") => {
                 warn!("🚨 Security violation detected in Forge execution");
                 (false, serde_json::json!({"error": "Security violation in Forge"}), 1024, vec!["malicious_command".to_string()])
@@ -201,6 +636,11 @@ impl Conductor {
                 warn!("⏰ Execution timeout in Forge");
                 (false, serde_json::json!({"error": "Execution timeout in Forge"}), 512, vec!["timeout".to_string()])
             }
+            #[cfg(test)]
+            Some("flaky") if self.flaky_forge.fails(task_id, &input) => {
+                warn!("💥 Transient failure in Forge");
+                (false, serde_json::json!({"error": "Transient failure in Forge"}), 256, vec![])
+            }
             Some(cmd) => {
                 info!("🔒 Secure execution completed in Forge: {}", cmd);
                 (true, serde_json::json!({"result": format!("Forge executed: {}", cmd), "execution_id": execution_id}), 256, vec![])
@@ -210,37 +650,88 @@ impl Conductor {
             }
         };
 
+        // Simulate modules that produce a collection
+        if let (true, Some(items)) = (success, input.get("items")) {
+            output["items"] = items.clone();
+        }
+
         Ok(forge::ExecutionResult {
             execution_id,
+            module_id: module_id.to_string(),
+            module_version: String::new(),
             success,
             output,
             execution_time_ms: 150, // Simulated
             memory_used_kb: memory_used,
             security_violations: violations,
+            input_violations: Vec::new(),
             timestamp: chrono::Utc::now(),
+            kind: forge::ExecutionKind::Invocation,
+            trace_id: trace.map(|trace| trace.trace_id.clone()),
+            stdio: None,
+            blob_hash: String::new(),
+            policy_snapshot_id: String::new(),
+            replay_of: None,
+            resources: Default::default(),
         })
     }
 
     /// Execute a complete workflow
     pub async fn execute_workflow(&self, workflow: AgentWorkflow) -> Result<Vec<TaskResult>, Box<dyn std::error::Error>> {
         info!("🎭 Executing workflow: {} ({})", workflow.name, workflow.id);
+        workflow.ensure_valid()?;
+
+        let start_time = std::time::Instant::now();
+        let trace = TraceContext::new();
+        let run_id = uuid::Uuid::new_v4().to_string();
 
         let mut results = Vec::new();
+        let mut recorded_steps = Vec::new();
+        let mut events = Vec::new();
+
+        // Outputs of finished steps by step id, for map steps to read from
+        let mut outputs = serde_json::Map::new();
 
         // Execute steps in dependency order (simplified)
         for step in &workflow.steps {
-            let task = AgentTask {
-                id: format!("{}-{}", workflow.id, step.id),
-                name: format!("{}-{}", workflow.name, step.name),
-                description: step.name.clone(),
-                module_id: step.module_id.clone(),
-                input: step.input_template.clone(),
-                priority: TaskPriority::Normal,
-                timeout_ms: Some(workflow.timeout_ms / workflow.steps.len() as u64),
-                created_at: chrono::Utc::now(),
+            let timeout_ms = step.timeout_ms.unwrap_or(workflow.timeout_ms / workflow.steps.len() as u64);
+            let (rendered_input, result, elements) = match &step.map {
+                Some(map) => {
+                    let source = serde_json::Value::Object(outputs.clone());
+                    let (result, elements) = self.execute_map_step(&workflow, step, map, &source, &trace, timeout_ms).await;
+                    (render_step_input(step), result, elements)
+                }
+                None => {
+                    let task = workflow_task(&workflow, step, format!("{}-{}", workflow.id, step.id), render_step_input(step), &trace, timeout_ms);
+                    let rendered_input = task.input.clone();
+                    let result = self.execute_task_with_retry(task, &step.retry_policy).await?;
+                    (rendered_input, result, Vec::new())
+                }
             };
 
-            let result = self.execute_task(task).await?;
+            // Map steps report each element as its own task
+            let finished: Vec<&TaskResult> = match step.map {
+                Some(_) => elements.iter().filter_map(|e| e.result.as_ref()).collect(),
+                None => vec![&result],
+            };
+            for finished in finished {
+                if let Some(event) = task_event(finished) {
+                    events.push(debug_bundle::RecordedEvent {
+                        event,
+                        task_id: Some(finished.task_id.clone()),
+                        occurred_at: finished.completed_at,
+                    });
+                }
+            }
+            outputs.insert(step.id.clone(), result.output.clone());
+            recorded_steps.push(debug_bundle::RecordedStep {
+                step_id: step.id.clone(),
+                task_id: result.task_id.clone(),
+                module_id: step.module_id.clone(),
+                rendered_input,
+                result: result.clone(),
+                elements,
+            });
             results.push(result);
 
             // Stop on failure (simplified error handling)
@@ -249,9 +740,218 @@ impl Conductor {
             }
         }
 
+        let duration = start_time.elapsed();
+        self.metrics.record_workflow_duration(duration);
+
+        let execution = WorkflowExecution {
+            workflow_id: workflow.id.clone(),
+            run_id: run_id.clone(),
+            success: results.len() == workflow.steps.len() && results.iter().all(|r| r.success),
+            step_results: results.clone(),
+            duration_ms: duration.as_millis() as u64,
+            completed_at: chrono::Utc::now(),
+        };
+        self.webhooks.dispatch(WebhookEvent::WorkflowCompleted, serde_json::json!(execution), &HashMap::new()).await;
+        events.push(debug_bundle::RecordedEvent {
+            event: WebhookEvent::WorkflowCompleted,
+            task_id: None,
+            occurred_at: execution.completed_at,
+        });
+
+        self.workflow_runs.write().await.insert(run_id.clone(), WorkflowRun {
+            run_id,
+            workflow,
+            steps: recorded_steps,
+            events,
+            execution,
+        });
+
         Ok(results)
     }
 
+    /// Execute a map step: one task per element, at most `max_parallel` at a time
+    async fn execute_map_step(
+        &self,
+        workflow: &AgentWorkflow,
+        step: &WorkflowStep,
+        map: &MapStep,
+        outputs: &serde_json::Value,
+        trace: &TraceContext,
+        timeout_ms: u64,
+    ) -> (TaskResult, Vec<MapElement>) {
+        let start_time = std::time::Instant::now();
+        let step_task_id = format!("{}-{}", workflow.id, step.id);
+
+        let items = match outputs.pointer(&map.items_pointer) {
+            Some(serde_json::Value::Array(items)) => items.clone(),
+            _ => {
+                warn!("🗺️ Map step {} found no array at {}", step.id, map.items_pointer);
+                let error = format!("No array at {} in step outputs", map.items_pointer);
+                return (map_step_result(step_task_id, false, serde_json::json!({"error": error}), &[], start_time, trace), Vec::new());
+            }
+        };
+        info!("🗺️ Mapping step {} over {} element(s)", step.id, items.len());
+
+        let tasks: Vec<AgentTask> = items.iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let input = map.element_input(&step.input_template, item);
+                workflow_task(workflow, step, format!("{}-{}", step_task_id, index), input, trace, timeout_ms)
+            })
+            .collect();
+
+        // Errors are stringified so the future stays `Send`
+        let outcomes: Vec<Result<TaskResult, String>> = futures::stream::iter(tasks.clone())
+            .map(|task| async move {
+                self.execute_task_with_retry(task, &step.retry_policy).await.map_err(|e| e.to_string())
+            })
+            .buffered(map.max_parallel.max(1))
+            .collect()
+            .await;
+
+        let elements: Vec<MapElement> = tasks.into_iter()
+            .zip(outcomes)
+            .enumerate()
+            .map(|(index, (task, outcome))| MapElement::new(index, task.id, task.input, outcome, map.on_failure))
+            .collect();
+        let failed = elements.iter().filter(|e| e.status != MapElementStatus::Succeeded).count();
+        if failed > 0 {
+            warn!("🗺️ {} of {} element(s) of map step {} failed ({:?})", failed, elements.len(), step.id, map.on_failure);
+        }
+
+        let result = match map_step::gather_outputs(&elements, map.on_failure) {
+            Ok(output) => map_step_result(step_task_id, true, output, &elements, start_time, trace),
+            Err(error) => map_step_result(step_task_id, false, serde_json::json!({"error": error}), &elements, start_time, trace),
+        };
+        (result, elements)
+    }
+
+    /// List recorded runs of a workflow, newest first
+    pub async fn list_workflow_runs(&self, workflow_id: &str) -> Vec<WorkflowExecution> {
+        let mut runs: Vec<WorkflowExecution> = self.workflow_runs.read().await
+            .values()
+            .filter(|run| run.workflow.id == workflow_id)
+            .map(|run| run.execution.clone())
+            .collect();
+        runs.sort_by_key(|run| std::cmp::Reverse(run.completed_at));
+        runs
+    }
+
+    /// Package a recorded workflow run into a debug bundle for local reproduction
+    pub async fn export_debug_bundle(&self, run_id: &str) -> Result<DebugBundle, Box<dyn std::error::Error>> {
+        let run = self.workflow_runs.read().await
+            .get(run_id)
+            .cloned()
+            .ok_or_else(|| format!("No recorded workflow run: {}", run_id))?;
+
+        info!("🧳 Exporting debug bundle for run {} of workflow {}", run_id, run.workflow.id);
+        Ok(DebugBundle {
+            format_version: debug_bundle::BUNDLE_FORMAT_VERSION,
+            exported_at: chrono::Utc::now(),
+            run,
+            environment: debug_bundle::EnvironmentMetadata {
+                conductor_version: env!("CARGO_PKG_VERSION").to_string(),
+                fortress_url: self.fortress_url.clone(),
+                forge_url: self.forge_url.clone(),
+                os: std::env::consts::OS.to_string(),
+                arch: std::env::consts::ARCH.to_string(),
+                hostname: std::env::var("HOSTNAME").ok(),
+                retry_policy: self.retry_policy.clone(),
+            },
+        })
+    }
+
+    /// Replay a debug bundle and report where each step diverges from the recorded run.
+    ///
+    /// Replayed steps run against Forge directly; they are not stored, retried,
+    /// dead-lettered or announced to webhooks.
+    pub async fn replay_debug_bundle(&self, bundle: &DebugBundle, mode: ReplayMode) -> Result<ReplayReport, Box<dyn std::error::Error>> {
+        let run = &bundle.run;
+        info!("⏪ Replaying run {} of workflow {} ({:?})", run.run_id, run.workflow.id, mode);
+
+        let mut steps = Vec::new();
+        for recorded in &run.steps {
+            let mut divergences = Vec::new();
+            let replayed = match mode {
+                ReplayMode::RecordedOutputs => {
+                    let step = run.workflow.steps.iter()
+                        .find(|step| step.id == recorded.step_id)
+                        .ok_or_else(|| format!("Bundle step {} missing from workflow definition", recorded.step_id))?;
+                    let changes = debug_bundle::value_changes(&recorded.rendered_input, &render_step_input(step));
+                    if !changes.is_empty() {
+                        divergences.push(Divergence::Input { changes });
+                    }
+                    Ok(recorded.result.clone())
+                }
+                ReplayMode::RecordedInputs if !recorded.elements.is_empty() => {
+                    // Map steps replay element by element; the gathered result is not re-derived
+                    for element in &recorded.elements {
+                        let Some(original) = &element.result else { continue };
+                        let replay_id = format!("replay-{}-{}-{}", run.run_id, recorded.step_id, element.index);
+                        match self.replay_input(replay_id, &element.task_id, &recorded.module_id, &element.rendered_input).await {
+                            Ok(replayed) => divergences.extend(debug_bundle::result_divergences(original, &replayed)),
+                            Err(message) => divergences.push(Divergence::Error { message }),
+                        }
+                    }
+                    Ok(recorded.result.clone())
+                }
+                ReplayMode::RecordedInputs => {
+                    let replay_id = format!("replay-{}-{}", run.run_id, recorded.step_id);
+                    self.replay_input(replay_id, &recorded.task_id, &recorded.module_id, &recorded.rendered_input).await
+                }
+            };
+
+            match replayed {
+                Ok(replayed) => divergences.extend(debug_bundle::result_divergences(&recorded.result, &replayed)),
+                Err(message) => divergences.push(Divergence::Error { message }),
+            }
+            if !divergences.is_empty() {
+                warn!("⏪ Step {} diverged from run {}: {:?}", recorded.step_id, run.run_id, divergences);
+            }
+            steps.push(debug_bundle::StepReplay {
+                step_id: recorded.step_id.clone(),
+                divergences,
+            });
+        }
+
+        Ok(ReplayReport {
+            run_id: run.run_id.clone(),
+            mode,
+            steps,
+        })
+    }
+
+    /// Execute a recorded input directly against Forge
+    async fn replay_input(&self, replay_id: String, task_id: &str, module_id: &str, input: &serde_json::Value) -> Result<TaskResult, String> {
+        let task = AgentTask {
+            id: replay_id,
+            name: format!("replay-{}", task_id),
+            description: format!("Replay of {}", task_id),
+            module_id: module_id.to_string(),
+            input: input.clone(),
+            priority: TaskPriority::Normal,
+            timeout_ms: None,
+            created_at: chrono::Utc::now(),
+            labels: HashMap::new(),
+            trace_context: Some(TraceContext::new()),
+            change_detection: None,
+        };
+        self.route_through_fortress(task).await
+            .map(|execution| TaskResult {
+                task_id: task_id.to_string(),
+                execution_id: execution.execution_id,
+                success: execution.success,
+                output: execution.output,
+                execution_time_ms: execution.execution_time_ms,
+                security_violations: execution.security_violations,
+                completed_at: chrono::Utc::now(),
+                queued_ms: 0,
+                trace_id: execution.trace_id,
+                change: None,
+            })
+            .map_err(|e| e.to_string())
+    }
+
     /// Get task result by ID
     pub async fn get_task_result(&self, task_id: &str) -> Option<TaskResult> {
         self.results.read().await.get(task_id).cloned()
@@ -267,14 +967,92 @@ impl Conductor {
         self.results.read().await.values().cloned().collect()
     }
 
+    /// List results matching a filter, ordered by completion time
+    pub async fn list_results_filtered(&self, filter: ResultFilter) -> Page<TaskResult> {
+        let tasks = self.tasks.read().await;
+        let results: Vec<TaskResult> = self.results.read().await
+            .values()
+            .filter(|result| {
+                let module_id = tasks.get(&result.task_id).map(|task| task.module_id.as_str());
+                filter.matches(result, module_id)
+            })
+            .cloned()
+            .collect();
+
+        query::paginate(
+            results,
+            |result| (result.completed_at, result.task_id.clone()),
+            filter.after_id.as_deref(),
+            filter.limit,
+        )
+    }
+
+    /// List tasks matching a filter, ordered by creation time
+    pub async fn list_tasks_filtered(&self, filter: TaskFilter) -> Page<AgentTask> {
+        let tasks: Vec<AgentTask> = self.tasks.read().await
+            .values()
+            .filter(|task| filter.matches(task))
+            .cloned()
+            .collect();
+
+        query::paginate(
+            tasks,
+            |task| (task.created_at, task.id.clone()),
+            filter.after_id.as_deref(),
+            filter.limit,
+        )
+    }
+
     /// Register a workflow
+    ///
+    /// Invalid workflows are rejected with an [`InvalidWorkflow`] error and not stored.
     pub async fn register_workflow(&self, workflow: AgentWorkflow) -> Result<(), Box<dyn std::error::Error>> {
+        if let Err(invalid) = workflow.ensure_valid() {
+            warn!("📋 Rejected workflow {}: {:?}", workflow.id, invalid.errors);
+            return Err(invalid.into());
+        }
+
         let mut workflows = self.workflows.write().await;
         workflows.insert(workflow.id.clone(), workflow);
         info!("📋 Registered workflow");
         Ok(())
     }
 
+    /// Register a JSON Schema that task inputs for `module_id` must satisfy.
+    ///
+    /// Replaces any schema previously registered for the module.
+    pub async fn register_module_schema(&self, module_id: &str, schema: serde_json::Value) -> Result<(), Box<dyn std::error::Error>> {
+        let compiled = schema::ModuleSchema::compile(module_id, schema)?;
+        self.module_schemas.write().await.insert(module_id.to_string(), compiled);
+        info!("📐 Registered input schema for module {}", module_id);
+        Ok(())
+    }
+
+    /// Get the input schema registered for a module
+    pub async fn get_module_schema(&self, module_id: &str) -> Option<serde_json::Value> {
+        self.module_schemas.read().await.get(module_id).map(|s| s.schema().clone())
+    }
+
+    /// Check a task's input against its module schema; modules without one are not validated
+    async fn validate_task_input(&self, task: &AgentTask) -> Result<(), InvalidTaskInput> {
+        let schemas = self.module_schemas.read().await;
+        let Some(schema) = schemas.get(&task.module_id) else {
+            return Ok(());
+        };
+
+        let violations = schema.violations(&task.input);
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        warn!("📐 Rejected input for task {}: {:?}", task.id, violations);
+        Err(InvalidTaskInput {
+            task_id: task.id.clone(),
+            module_id: task.module_id.clone(),
+            violations,
+        })
+    }
+
     /// Get workflow by ID
     pub async fn get_workflow(&self, workflow_id: &str) -> Option<AgentWorkflow> {
         self.workflows.read().await.get(workflow_id).cloned()
@@ -294,6 +1072,9 @@ impl Conductor {
             priority: TaskPriority::High,
             timeout_ms: Some(5000),
             created_at: chrono::Utc::now(),
+            labels: HashMap::new(),
+            trace_context: None,
+            change_detection: None,
         };
 
         // Execute the task
@@ -305,7 +1086,6 @@ impl Conductor {
         println!("Execution ID: {}", result.execution_id);
         println!("Success: {}", result.success);
         println!("Execution Time: {}ms", result.execution_time_ms);
-        println!("Memory Used: {}KB", result.memory_used_kb);
         println!("Security Violations: {:?}", result.security_violations);
         println!("Output: {}", serde_json::to_string_pretty(&result.output)?);
 
@@ -319,10 +1099,94 @@ impl Conductor {
     }
 }
 
+/// Input a workflow step is dispatched with
+fn render_step_input(step: &WorkflowStep) -> serde_json::Value {
+    step.input_template.clone()
+}
+
+/// Task dispatched for a workflow step, or for one element of a map step
+fn workflow_task(
+    workflow: &AgentWorkflow,
+    step: &WorkflowStep,
+    id: String,
+    input: serde_json::Value,
+    trace: &TraceContext,
+    timeout_ms: u64,
+) -> AgentTask {
+    AgentTask {
+        id,
+        name: format!("{}-{}", workflow.name, step.name),
+        description: step.name.clone(),
+        module_id: step.module_id.clone(),
+        input,
+        priority: TaskPriority::Normal,
+        timeout_ms: Some(timeout_ms),
+        created_at: chrono::Utc::now(),
+        labels: HashMap::new(),
+        trace_context: Some(trace.child()),
+        change_detection: None,
+    }
+}
+
+/// Result of a map step as a whole, gathered from its elements
+fn map_step_result(
+    task_id: String,
+    success: bool,
+    output: serde_json::Value,
+    elements: &[MapElement],
+    start_time: std::time::Instant,
+    trace: &TraceContext,
+) -> TaskResult {
+    TaskResult {
+        task_id,
+        execution_id: uuid::Uuid::new_v4().to_string(),
+        success,
+        output,
+        execution_time_ms: start_time.elapsed().as_millis() as u64,
+        security_violations: elements.iter()
+            .filter_map(|e| e.result.as_ref())
+            .flat_map(|r| r.security_violations.iter().cloned())
+            .collect(),
+        completed_at: chrono::Utc::now(),
+        queued_ms: elements.iter().filter_map(|e| e.result.as_ref()).map(|r| r.queued_ms).sum(),
+        trace_id: Some(trace.trace_id.clone()),
+        change: None,
+    }
+}
+
+/// Webhook event announcing a finished task, if any
+fn task_event(result: &TaskResult) -> Option<WebhookEvent> {
+    match &result.change {
+        // Change-detected runs only notify when the output changed
+        Some(change) if result.success => match change.status {
+            ChangeStatus::Changed => Some(WebhookEvent::OutputChanged),
+            ChangeStatus::Unchanged | ChangeStatus::Baseline => None,
+        },
+        _ if result.success => Some(WebhookEvent::TaskCompleted),
+        _ => Some(WebhookEvent::TaskFailed),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Forge stand-in failing the first `failures` (default 1) invocations of
+    /// each task whose input command is `flaky`
+    #[derive(Clone, Default)]
+    pub(crate) struct FlakyForge {
+        invocations: Arc<std::sync::Mutex<HashMap<String, u64>>>,
+    }
+
+    impl FlakyForge {
+        pub(crate) fn fails(&self, task_id: &str, input: &serde_json::Value) -> bool {
+            let mut invocations = self.invocations.lock().unwrap();
+            let invocation = invocations.entry(task_id.to_string()).or_insert(0);
+            *invocation += 1;
+            *invocation <= input.get("failures").and_then(|v| v.as_u64()).unwrap_or(1)
+        }
+    }
+
     #[tokio::test]
     async fn test_conductor_creation() {
         let conductor = Conductor::new(
@@ -350,6 +1214,9 @@ mod tests {
             priority: TaskPriority::Normal,
             timeout_ms: Some(5000),
             created_at: chrono::Utc::now(),
+            labels: HashMap::new(),
+            trace_context: None,
+            change_detection: None,
         };
 
         let result = conductor.execute_task(task).await.unwrap();
@@ -373,10 +1240,662 @@ mod tests {
             priority: TaskPriority::Normal,
             timeout_ms: Some(5000),
             created_at: chrono::Utc::now(),
+            labels: HashMap::new(),
+            trace_context: None,
+            change_detection: None,
         };
 
         let result = conductor.execute_task(task).await.unwrap();
         assert!(!result.success);
         assert!(!result.security_violations.is_empty());
     }
+
+    fn test_conductor() -> Conductor {
+        Conductor::new(
+            "http://localhost:8080".to_string(),
+            "http://localhost:8081".to_string(),
+        )
+    }
+
+    fn task(id: &str, module_id: &str, command: &str, priority: TaskPriority) -> AgentTask {
+        AgentTask {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: "Test task".to_string(),
+            module_id: module_id.to_string(),
+            input: serde_json::json!({"command": command}),
+            priority,
+            timeout_ms: Some(5000),
+            created_at: chrono::Utc::now(),
+            labels: HashMap::new(),
+            trace_context: None,
+            change_detection: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_results_filtering() {
+        let conductor = test_conductor();
+        conductor.execute_task(task("ok-a", "module-a", "test", TaskPriority::Normal)).await.unwrap();
+        conductor.execute_task(task("bad-a", "module-a", "malicious", TaskPriority::Normal)).await.unwrap();
+        conductor.execute_task(task("ok-b", "module-b", "test", TaskPriority::High)).await.unwrap();
+
+        let failed = conductor.list_results_filtered(ResultFilter {
+            success: Some(false),
+            ..Default::default()
+        }).await;
+        assert_eq!(failed.items.len(), 1);
+        assert_eq!(failed.items[0].task_id, "bad-a");
+
+        let module_a = conductor.list_results_filtered(ResultFilter {
+            module_id: Some("module-a".to_string()),
+            has_security_violations: Some(false),
+            ..Default::default()
+        }).await;
+        assert_eq!(module_a.items.len(), 1);
+        assert_eq!(module_a.items[0].task_id, "ok-a");
+
+        let high = conductor.list_tasks_filtered(TaskFilter {
+            priority: Some(TaskPriority::High),
+            ..Default::default()
+        }).await;
+        assert_eq!(high.items.len(), 1);
+        assert_eq!(high.items[0].id, "ok-b");
+    }
+
+    #[tokio::test]
+    async fn test_stable_pagination_with_concurrent_inserts() {
+        let conductor = test_conductor();
+        for i in 0..4 {
+            conductor.execute_task(task(&format!("task-{}", i), "module-a", "test", TaskPriority::Normal))
+                .await
+                .unwrap();
+        }
+
+        let first = conductor.list_results_filtered(ResultFilter {
+            limit: Some(2),
+            ..Default::default()
+        }).await;
+        assert_eq!(first.items.len(), 2);
+        assert_eq!(first.items[0].task_id, "task-0");
+        assert_eq!(first.items[1].task_id, "task-1");
+
+        // A result landing between page fetches must not shift the next page
+        conductor.execute_task(task("task-late", "module-a", "test", TaskPriority::Normal)).await.unwrap();
+
+        let second = conductor.list_results_filtered(ResultFilter {
+            limit: Some(2),
+            after_id: first.next_cursor.clone(),
+            ..Default::default()
+        }).await;
+        assert_eq!(second.items[0].task_id, "task-2");
+        assert_eq!(second.items[1].task_id, "task-3");
+        let cursor = second.next_cursor.clone().unwrap();
+        assert_eq!(query::decode_cursor(&cursor), Some((second.items[1].completed_at, "task-3".to_string())));
+
+        // The cursor is a position, so it still works once its result is gone
+        conductor.results.write().await.remove("task-3");
+        let third = conductor.list_results_filtered(ResultFilter {
+            limit: Some(2),
+            after_id: Some(cursor),
+            ..Default::default()
+        }).await;
+        assert_eq!(third.items.len(), 1);
+        assert_eq!(third.items[0].task_id, "task-late");
+        assert!(third.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_workflow_not_registered() {
+        let conductor = test_conductor();
+        let workflow = AgentWorkflow {
+            id: "empty".to_string(),
+            name: "Empty".to_string(),
+            description: "No steps".to_string(),
+            steps: vec![],
+            timeout_ms: 1000,
+        };
+
+        let err = conductor.register_workflow(workflow).await.unwrap_err();
+        let invalid = err.downcast_ref::<InvalidWorkflow>().unwrap();
+        assert_eq!(invalid.errors, vec![WorkflowValidationError::EmptyWorkflow]);
+        assert!(conductor.get_workflow("empty").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_dead_letter_and_requeue() {
+        let conductor = test_conductor().with_retry_policy(RetryPolicy {
+            max_attempts: 3,
+            backoff_ms: 0,
+        });
+        let mut flaky = task("flaky-task", "module-a", "flaky", TaskPriority::Normal);
+        flaky.input = serde_json::json!({"command": "flaky", "failures": 3});
+
+        let result = conductor.execute_task(flaky.clone()).await.unwrap();
+        assert!(!result.success);
+
+        let dead_letters = conductor.list_dead_letters().await;
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].reason, DeadLetterReason::RetriesExhausted);
+        assert_eq!(dead_letters[0].attempts.len(), 3);
+        assert_eq!(dead_letters[0].task.input, flaky.input);
+        assert_eq!(dead_letters[0].last_error(), Some("Transient failure in Forge"));
+
+        let replayed = conductor.requeue_dead_letter("flaky-task").await.unwrap();
+        assert!(replayed.success);
+        assert!(conductor.list_dead_letters().await.is_empty());
+        assert!(conductor.get_task_result("flaky-task").await.unwrap().success);
+        assert!(conductor.requeue_dead_letter("flaky-task").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dead_letters_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = || -> Arc<dyn DeadLetterStore> {
+            Arc::new(EncryptedStore::new(
+                encryption::FileRecordStore::open(dir.path()).unwrap(),
+                encryption::StaticKeyProvider::new("k1", [3; 32]),
+            ))
+        };
+        let retry = RetryPolicy { max_attempts: 1, backoff_ms: 0 };
+
+        let conductor = test_conductor().with_retry_policy(retry.clone()).with_dead_letter_store(store()).unwrap();
+        let mut flaky = task("flaky-task", "module-a", "flaky", TaskPriority::Normal);
+        flaky.input = serde_json::json!({"command": "flaky", "secret": "customer-data"});
+        conductor.execute_task(flaky.clone()).await.unwrap();
+
+        // Persisted sealed, and restored by the next instance
+        let raw = std::fs::read_to_string(encryption::FileRecordStore::open(dir.path()).unwrap().path("dead_letter/flaky-task")).unwrap();
+        assert!(!raw.contains("customer-data"));
+        let restarted = test_conductor()
+            .with_retry_policy(RetryPolicy { max_attempts: 2, ..retry })
+            .with_dead_letter_store(store())
+            .unwrap();
+        let restored = restarted.list_dead_letters().await;
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].task.input, flaky.input);
+        assert_eq!(restored[0].last_error(), Some("Transient failure in Forge"));
+
+        // Requeueing removes the entry from the store too; the retry gets past the flaky failure
+        assert!(restarted.requeue_dead_letter("flaky-task").await.unwrap().success);
+        assert!(store().load().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_security_violation_skips_retries() {
+        let conductor = test_conductor().with_retry_policy(RetryPolicy {
+            max_attempts: 3,
+            backoff_ms: 0,
+        });
+
+        conductor.execute_task(task("bad", "module-a", "malicious", TaskPriority::Normal)).await.unwrap();
+
+        let dead_letters = conductor.list_dead_letters().await;
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].reason, DeadLetterReason::SecurityViolation);
+        assert_eq!(dead_letters[0].attempts.len(), 1);
+        assert_eq!(dead_letters[0].attempts[0].security_violations, vec!["malicious_command".to_string()]);
+    }
+
+    fn metric_value(metrics: &str, name: &str) -> f64 {
+        metrics
+            .lines()
+            .find(|line| line.starts_with(name))
+            .and_then(|line| line.rsplit(' ').next())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0.0)
+    }
+
+    #[tokio::test]
+    async fn test_metrics_move_after_execution() {
+        let conductor = test_conductor();
+        conductor.execute_task(task("ok", "module-a", "test", TaskPriority::Normal)).await.unwrap();
+        conductor.execute_task(task("bad", "module-a", "malicious", TaskPriority::Normal)).await.unwrap();
+
+        let metrics = conductor.gather_metrics();
+        assert_eq!(metric_value(&metrics, "conductor_tasks_submitted_total"), 2.0);
+        assert_eq!(metric_value(&metrics, "conductor_tasks_failed_total{module_id=\"module-a\"}"), 1.0);
+        assert_eq!(metric_value(&metrics, "conductor_security_violations_total"), 1.0);
+        assert_eq!(metric_value(&metrics, "conductor_task_execution_seconds_count"), 2.0);
+        assert_eq!(metric_value(&metrics, "conductor_queue_wait_seconds_count"), 2.0);
+
+        // Each Conductor has its own registry
+        assert_eq!(metric_value(&test_conductor().gather_metrics(), "conductor_tasks_submitted_total"), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_module_limit_serializes_execution() {
+        let conductor = test_conductor();
+        conductor.set_module_limit("heavy-module", 1).await;
+
+        let handles: Vec<_> = (0..3)
+            .map(|i| {
+                let conductor = conductor.clone();
+                let mut task = task(&format!("heavy-{}", i), "heavy-module", "work", TaskPriority::Normal);
+                task.input = serde_json::json!({"command": "work", "work_ms": 50});
+                tokio::spawn(async move { conductor.execute_task(task).await.map_err(|e| e.to_string()) })
+            })
+            .collect();
+
+        let mut results = Vec::new();
+        for handle in handles {
+            results.push(handle.await.unwrap().unwrap());
+        }
+
+        // Execution windows [completed_at - execution_time, completed_at] must not overlap
+        let mut windows: Vec<_> = results
+            .iter()
+            .map(|r| (r.completed_at - chrono::Duration::milliseconds(r.execution_time_ms as i64), r.completed_at))
+            .collect();
+        windows.sort();
+        for pair in windows.windows(2) {
+            assert!(pair[1].0 >= pair[0].1, "execution windows overlap: {:?}", pair);
+        }
+
+        let mut queued: Vec<u64> = results.iter().map(|r| r.queued_ms).collect();
+        queued.sort();
+        assert!(queued[1] >= 40 && queued[2] >= 90, "unexpected queue waits: {:?}", queued);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_delivery_signed_and_retried() {
+        use axum::{body::Bytes, http::{HeaderMap, StatusCode}, routing::post, Router};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<(HeaderMap, Bytes)>();
+        let receiver_hits = hits.clone();
+        let app = Router::new().route("/hook", post(move |headers: HeaderMap, body: Bytes| {
+            let hits = receiver_hits.clone();
+            let tx = tx.clone();
+            async move {
+                // Fail the first delivery to exercise retries
+                if hits.fetch_add(1, Ordering::SeqCst) == 0 {
+                    return StatusCode::INTERNAL_SERVER_ERROR;
+                }
+                tx.send((headers, body)).unwrap();
+                StatusCode::OK
+            }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let conductor = test_conductor();
+        conductor.register_webhook(
+            WebhookConfig::new(format!("http://{}/hook", addr))
+                .with_secret("s3cret")
+                .with_events([WebhookEvent::TaskCompleted])
+                .with_retry(WebhookRetry { max_attempts: 3, initial_backoff_ms: 10 }),
+        ).await;
+
+        // Filtered out: only completions are subscribed
+        conductor.execute_task(task("hook-bad", "module-a", "malicious", TaskPriority::Normal)).await.unwrap();
+        conductor.execute_task(task("hook-ok", "module-a", "test", TaskPriority::Normal)).await.unwrap();
+
+        let (headers, body) = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let signature = headers.get(webhooks::SIGNATURE_HEADER).unwrap().to_str().unwrap();
+        assert!(webhooks::verify_signature("s3cret", &body, signature));
+        assert!(!webhooks::verify_signature("wrong", &body, signature));
+
+        let payload: webhooks::WebhookPayload = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload.event, WebhookEvent::TaskCompleted);
+        assert_eq!(payload.data["task_id"], "hook-ok");
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_change_detection_notifies_once() {
+        use axum::{routing::post, Json, Router};
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<webhooks::WebhookPayload>();
+        let app = Router::new().route("/hook", post(move |Json(payload): Json<webhooks::WebhookPayload>| {
+            let tx = tx.clone();
+            async move { tx.send(payload).unwrap() }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let conductor = test_conductor();
+        conductor.register_webhook(WebhookConfig::new(format!("http://{}/hook", addr))).await;
+
+        // The execution id differs on every run, so it is not a material change
+        let detection = ChangeDetection::new("nightly-schema").with_ignore_paths(["/execution_id"]);
+        let mut statuses = Vec::new();
+        for (run, command) in ["check", "check-v2", "check-v2"].into_iter().enumerate() {
+            let mut run_task = task(&format!("nightly-{}", run), "module-a", command, TaskPriority::Normal);
+            run_task.change_detection = Some(detection.clone());
+            let result = conductor.execute_task(run_task).await.unwrap();
+            statuses.push(result.change.unwrap().status);
+        }
+        assert_eq!(statuses, vec![ChangeStatus::Baseline, ChangeStatus::Changed, ChangeStatus::Unchanged]);
+
+        let stored = conductor.get_task_result("nightly-1").await.unwrap().change.unwrap();
+        assert_eq!(stored.changes.len(), 1);
+        assert_eq!(stored.changes[0].path, "/result");
+
+        let payload = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+        assert_eq!(payload.event, WebhookEvent::OutputChanged);
+        assert_eq!(payload.data["task_id"], "nightly-1");
+
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        assert!(rx.try_recv().is_err());
+    }
+
+    fn search_schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "required": ["command", "params"],
+            "properties": {
+                "command": {"type": "string"},
+                "params": {
+                    "type": "object",
+                    "required": ["query"],
+                    "properties": {"query": {"type": "string"}}
+                }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_task_input_schema_validation() {
+        let conductor = test_conductor();
+        conductor.register_module_schema("search-module", search_schema()).await.unwrap();
+
+        let mut valid = task("schema-ok", "search-module", "search", TaskPriority::Normal);
+        valid.input = serde_json::json!({"command": "search", "params": {"query": "wasm"}});
+        assert!(conductor.execute_task(valid).await.unwrap().success);
+
+        let mut missing = task("schema-missing", "search-module", "search", TaskPriority::Normal);
+        missing.input = serde_json::json!({"command": "search", "params": {}});
+        let err = conductor.execute_task(missing).await.unwrap_err();
+        let invalid = err.downcast_ref::<InvalidTaskInput>().unwrap();
+        assert_eq!(invalid.violations.len(), 1);
+        assert_eq!(invalid.violations[0].path, "/params");
+        assert!(invalid.violations[0].message.contains("query"));
+        assert!(conductor.get_task_result("schema-missing").await.is_none());
+
+        // Modules without a schema are not validated
+        let mut unregistered = task("schema-none", "other-module", "search", TaskPriority::Normal);
+        unregistered.input = serde_json::json!({"anything": 1});
+        assert!(conductor.execute_task(unregistered).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_workflow_step_inputs_validated() {
+        let conductor = test_conductor();
+        conductor.register_module_schema("search-module", search_schema()).await.unwrap();
+
+        let workflow = AgentWorkflow {
+            id: "schema-wf".to_string(),
+            name: "schema-wf".to_string(),
+            description: "Workflow with an invalid step input".to_string(),
+            steps: vec![WorkflowStep {
+                id: "search".to_string(),
+                name: "search".to_string(),
+                module_id: "search-module".to_string(),
+                input_template: serde_json::json!({"command": 42, "params": {"query": "wasm"}}),
+                depends_on: vec![],
+                retry_policy: RetryPolicy::default(),
+                timeout_ms: None,
+                map: None,
+            }],
+            timeout_ms: 10_000,
+        };
+
+        let err = conductor.execute_workflow(workflow).await.unwrap_err();
+        let invalid = err.downcast_ref::<InvalidTaskInput>().unwrap();
+        assert_eq!(invalid.violations[0].path, "/command");
+    }
+
+    #[tokio::test]
+    async fn test_trace_id_carried_to_result() {
+        let conductor = test_conductor();
+
+        let trace = TraceContext::new();
+        let mut traced = task("traced", "module-a", "test", TaskPriority::Normal);
+        traced.trace_context = Some(trace.clone());
+        let result = conductor.execute_task(traced).await.unwrap();
+        assert_eq!(result.trace_id.as_deref(), Some(trace.trace_id.as_str()));
+        assert_eq!(conductor.get_task_result("traced").await.unwrap().trace_id, result.trace_id);
+
+        // Tasks submitted without a context get a fresh trace
+        let result = conductor.execute_task(task("untraced", "module-a", "test", TaskPriority::Normal)).await.unwrap();
+        let trace_id = result.trace_id.unwrap();
+        assert_eq!(trace_id.len(), 32);
+        let stored = conductor.list_tasks().await.into_iter().find(|t| t.id == "untraced").unwrap();
+        assert_eq!(stored.trace_context.unwrap().trace_id, trace_id);
+    }
+
+    fn labeled_task(id: &str, labels: &[(&str, &str)]) -> AgentTask {
+        let mut task = task(id, "module-a", "test", TaskPriority::Normal);
+        task.labels = labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        task
+    }
+
+    #[tokio::test]
+    async fn test_list_tasks_by_label_selector() {
+        let conductor = test_conductor();
+        conductor.execute_task(labeled_task("payments-prod", &[("team", "payments"), ("env", "prod")])).await.unwrap();
+        conductor.execute_task(labeled_task("payments-dev", &[("team", "payments"), ("env", "dev")])).await.unwrap();
+        conductor.execute_task(labeled_task("search-prod", &[("team", "search"), ("env", "prod")])).await.unwrap();
+
+        let payments_prod = conductor.list_tasks_filtered(TaskFilter {
+            labels: Some(LabelSelector::new([("team", "payments"), ("env", "prod")])),
+            ..Default::default()
+        }).await;
+        assert_eq!(payments_prod.items.len(), 1);
+        assert_eq!(payments_prod.items[0].id, "payments-prod");
+
+        let prod = conductor.list_tasks_filtered(TaskFilter {
+            labels: Some(LabelSelector::new([("env", "prod")])),
+            ..Default::default()
+        }).await;
+        assert_eq!(prod.items.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_labels_rejected() {
+        let conductor = test_conductor();
+        let err = conductor.execute_task(labeled_task("bad-labels", &[("team name", "x")])).await.unwrap_err();
+
+        assert!(err.downcast_ref::<LabelError>().is_some());
+        assert!(conductor.list_tasks().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_only_registered_label_keys_exported() {
+        let conductor = test_conductor().with_metric_label_keys(["team"]);
+        conductor.execute_task(labeled_task("t1", &[("team", "payments"), ("customer", "acme")])).await.unwrap();
+
+        let metrics = conductor.gather_metrics();
+        assert_eq!(
+            metric_value(&metrics, "conductor_labeled_tasks_submitted_total{label_key=\"team\",label_value=\"payments\"}"),
+            1.0
+        );
+        assert!(!metrics.contains("customer"));
+        assert!(!metrics.contains("acme"));
+    }
+
+    #[tokio::test]
+    async fn test_label_selector_routing() {
+        let conductor = test_conductor();
+        conductor.add_route(RouteRule::new("http://fortress-eu:8080").with_selector(LabelSelector::new([("region", "eu")]))).await;
+        conductor.add_route(
+            RouteRule::new("http://fortress-payments:8080")
+                .with_module("module-a")
+                .with_selector(LabelSelector::new([("team", "payments")])),
+        ).await;
+
+        let cases: [(&[(&str, &str)], &str); 3] = [
+            (&[("region", "eu"), ("team", "payments")], "http://fortress-eu:8080"),
+            (&[("team", "payments")], "http://fortress-payments:8080"),
+            (&[("team", "search")], "http://localhost:8080"),
+        ];
+        for (labels, expected) in cases {
+            assert_eq!(conductor.fortress_url_for(&labeled_task("routed", labels)).await, expected);
+        }
+
+        let mut other_module = labeled_task("other", &[("team", "payments")]);
+        other_module.module_id = "module-b".to_string();
+        assert_eq!(conductor.fortress_url_for(&other_module).await, "http://localhost:8080");
+    }
+
+    #[tokio::test]
+    async fn test_label_limit_shared_across_modules() {
+        let conductor = test_conductor();
+        conductor.set_label_limit(LabelSelector::new([("team", "payments")]), 1).await;
+
+        let handles: Vec<_> = [("payments-a", "module-a", "payments"), ("payments-b", "module-b", "payments"), ("search", "module-a", "search")]
+            .into_iter()
+            .map(|(id, module_id, team)| {
+                let conductor = conductor.clone();
+                let mut task = labeled_task(id, &[("team", team)]);
+                task.module_id = module_id.to_string();
+                task.input = serde_json::json!({"command": "work", "work_ms": 50});
+                tokio::spawn(async move { conductor.execute_task(task).await.map_err(|e| e.to_string()) })
+            })
+            .collect();
+
+        let mut queued = HashMap::new();
+        for handle in handles {
+            let result = handle.await.unwrap().unwrap();
+            queued.insert(result.task_id, result.queued_ms);
+        }
+
+        // One payments task waits for the other; the search task is not limited
+        let mut payments = [queued["payments-a"], queued["payments-b"]];
+        payments.sort();
+        assert!(payments[1] >= 40, "unexpected queue waits: {:?}", queued);
+        assert!(queued["search"] < 40, "unexpected queue waits: {:?}", queued);
+    }
+
+    #[tokio::test]
+    async fn test_events_carry_task_labels() {
+        let conductor = test_conductor();
+        let mut events = conductor.subscribe_events();
+
+        conductor.execute_task(labeled_task("labeled", &[("team", "payments")])).await.unwrap();
+
+        let payload = events.recv().await.unwrap();
+        assert_eq!(payload.event, WebhookEvent::TaskCompleted);
+        assert_eq!(payload.labels.get("team").map(String::as_str), Some("payments"));
+    }
+
+    #[tokio::test]
+    async fn test_debug_bundle_export_and_replay() {
+        let conductor = test_conductor();
+        let step = |id: &str, command: &str, depends_on: &[&str]| WorkflowStep {
+            id: id.to_string(),
+            name: id.to_string(),
+            module_id: "test-module".to_string(),
+            input_template: serde_json::json!({"command": command}),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            retry_policy: RetryPolicy::default(),
+            timeout_ms: None,
+            map: None,
+        };
+        let workflow = AgentWorkflow {
+            id: "debug-wf".to_string(),
+            name: "debug-wf".to_string(),
+            description: "Fails on its last step".to_string(),
+            steps: vec![step("fetch", "fetch", &[]), step("parse", "parse", &["fetch"]), step("publish", "malicious", &["parse"])],
+            timeout_ms: 30_000,
+        };
+
+        conductor.execute_workflow(workflow).await.unwrap();
+        let runs = conductor.list_workflow_runs("debug-wf").await;
+        assert_eq!(runs.len(), 1);
+        assert!(!runs[0].success);
+
+        let bundle = conductor.export_debug_bundle(&runs[0].run_id).await.unwrap();
+        assert_eq!(bundle.run.steps.len(), 3);
+        assert_eq!(bundle.run.steps[2].rendered_input, serde_json::json!({"command": "malicious"}));
+        let events: Vec<WebhookEvent> = bundle.run.events.iter().map(|e| e.event).collect();
+        assert_eq!(
+            events,
+            vec![WebhookEvent::TaskCompleted, WebhookEvent::TaskCompleted, WebhookEvent::TaskFailed, WebhookEvent::WorkflowCompleted]
+        );
+
+        let bundle = DebugBundle::from_archive(&bundle.to_archive().unwrap()).unwrap();
+        let report = conductor.replay_debug_bundle(&bundle, ReplayMode::RecordedOutputs).await.unwrap();
+        assert_eq!(report.steps.len(), 3);
+        assert!(report.is_faithful());
+
+        let report = conductor.replay_debug_bundle(&bundle, ReplayMode::RecordedInputs).await.unwrap();
+        assert!(report.is_faithful(), "{:?}", report);
+
+        // An edited definition shows up as an input divergence on the affected step
+        let mut edited = bundle.clone();
+        edited.run.workflow.steps[1].input_template = serde_json::json!({"command": "parse-v2"});
+        let report = conductor.replay_debug_bundle(&edited, ReplayMode::RecordedOutputs).await.unwrap();
+        let divergent: Vec<&str> = report.divergent_steps().map(|s| s.step_id.as_str()).collect();
+        assert_eq!(divergent, vec!["parse"]);
+
+        let mut archive: serde_json::Value = serde_json::to_value(&bundle).unwrap();
+        archive["format_version"] = serde_json::json!(99);
+        let err = DebugBundle::from_archive(archive.to_string().as_bytes()).unwrap_err();
+        assert!(matches!(err, DebugBundleError::UnsupportedVersion { found: 99 }));
+    }
+
+    #[tokio::test]
+    async fn test_map_step_collects_errors_and_continues() {
+        let conductor = test_conductor();
+        let step = |id: &str, input: serde_json::Value, depends_on: &[&str], map: Option<MapStep>| WorkflowStep {
+            id: id.to_string(),
+            name: id.to_string(),
+            module_id: "test-module".to_string(),
+            input_template: input,
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            retry_policy: RetryPolicy::default(),
+            timeout_ms: None,
+            map,
+        };
+        let fan_out = MapStep::new("/fetch/items")
+            .with_item_field("command")
+            .with_max_parallel(2)
+            .with_on_failure(MapFailurePolicy::CollectErrors);
+        let workflow = AgentWorkflow {
+            id: "map-wf".to_string(),
+            name: "map-wf".to_string(),
+            description: "Fans out over fetched items".to_string(),
+            steps: vec![
+                step("fetch", serde_json::json!({"command": "fetch", "items": ["a", "b", "flaky", "d", "e"]}), &[], None),
+                step("process", serde_json::json!({"work_ms": 10}), &["fetch"], Some(fan_out)),
+                step("publish", serde_json::json!({"command": "publish"}), &["process"], None),
+            ],
+            timeout_ms: 30_000,
+        };
+
+        let results = conductor.execute_workflow(workflow).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| r.success));
+
+        let outputs = results[1].output.as_array().unwrap();
+        let executed: Vec<&str> = outputs.iter().map(|o| o["result"].as_str().unwrap()).collect();
+        assert_eq!(executed, vec!["Forge executed: a", "Forge executed: b", "Forge executed: d", "Forge executed: e"]);
+
+        let runs = conductor.list_workflow_runs("map-wf").await;
+        assert!(runs[0].success);
+        let bundle = conductor.export_debug_bundle(&runs[0].run_id).await.unwrap();
+        let elements = &bundle.run.steps[1].elements;
+        let statuses: Vec<MapElementStatus> = elements.iter().map(|e| e.status).collect();
+        assert_eq!(statuses, vec![
+            MapElementStatus::Succeeded,
+            MapElementStatus::Succeeded,
+            MapElementStatus::Failed,
+            MapElementStatus::Succeeded,
+            MapElementStatus::Succeeded,
+        ]);
+        assert_eq!(elements[2].task_id, "map-wf-process-2");
+        assert_eq!(elements[2].error.as_deref(), Some("Transient failure in Forge"));
+        assert_eq!(elements[2].rendered_input, serde_json::json!({"work_ms": 10, "command": "flaky"}));
+
+        let report = conductor.replay_debug_bundle(&bundle, ReplayMode::RecordedInputs).await.unwrap();
+        assert!(report.is_faithful(), "{:?}", report);
+    }
 }
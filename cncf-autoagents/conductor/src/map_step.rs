@@ -0,0 +1,164 @@
+//! Fan-out/fan-in map steps
+//!
+//! A map step runs its module once per element of an array taken from the
+//! outputs of earlier steps, then gathers the element outputs, in element
+//! order, into a single array output for downstream steps.
+
+use serde::{Deserialize, Serialize};
+
+use crate::TaskResult;
+
+/// Fan a workflow step out over an array from earlier step outputs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapStep {
+    /// JSON pointer into the outputs accumulated so far, keyed by step id,
+    /// e.g. `/fetch/items`
+    pub items_pointer: String,
+    /// Input template field each element is placed under
+    #[serde(default = "default_item_field")]
+    pub item_field: String,
+    /// Elements executing at once; module concurrency limits still apply
+    #[serde(default = "default_max_parallel")]
+    pub max_parallel: usize,
+    #[serde(default)]
+    pub on_failure: MapFailurePolicy,
+}
+
+fn default_item_field() -> String {
+    "item".to_string()
+}
+
+fn default_max_parallel() -> usize {
+    4
+}
+
+impl MapStep {
+    pub fn new(items_pointer: impl Into<String>) -> Self {
+        Self {
+            items_pointer: items_pointer.into(),
+            item_field: default_item_field(),
+            max_parallel: default_max_parallel(),
+            on_failure: MapFailurePolicy::default(),
+        }
+    }
+
+    pub fn with_item_field(mut self, item_field: impl Into<String>) -> Self {
+        self.item_field = item_field.into();
+        self
+    }
+
+    pub fn with_max_parallel(mut self, max_parallel: usize) -> Self {
+        self.max_parallel = max_parallel;
+        self
+    }
+
+    pub fn with_on_failure(mut self, on_failure: MapFailurePolicy) -> Self {
+        self.on_failure = on_failure;
+        self
+    }
+
+    /// Step id the items are read from
+    pub fn source_step(&self) -> Option<&str> {
+        self.items_pointer.strip_prefix('/')?.split('/').next().filter(|id| !id.is_empty())
+    }
+
+    /// Input an element is dispatched with
+    pub(crate) fn element_input(&self, template: &serde_json::Value, item: &serde_json::Value) -> serde_json::Value {
+        let mut input = match template {
+            serde_json::Value::Object(fields) => fields.clone(),
+            _ => serde_json::Map::new(),
+        };
+        input.insert(self.item_field.clone(), item.clone());
+        serde_json::Value::Object(input)
+    }
+}
+
+/// What a failed element does to its map step
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MapFailurePolicy {
+    /// Any failed element fails the step
+    #[default]
+    FailAll,
+    /// The step succeeds with the successful outputs; failures are recorded as errors
+    CollectErrors,
+    /// The step succeeds with the successful outputs; failures are dropped
+    Skip,
+}
+
+/// Outcome of one map element
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MapElementStatus {
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+/// One element of a map step as it ran
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapElement {
+    pub index: usize,
+    pub task_id: String,
+    /// Input exactly as sent to Forge
+    pub rendered_input: serde_json::Value,
+    pub status: MapElementStatus,
+    /// Absent when the element could not be dispatched
+    pub result: Option<TaskResult>,
+    pub error: Option<String>,
+}
+
+impl MapElement {
+    /// Record an element outcome under the step's failure policy
+    pub(crate) fn new(
+        index: usize,
+        task_id: String,
+        rendered_input: serde_json::Value,
+        outcome: Result<TaskResult, String>,
+        policy: MapFailurePolicy,
+    ) -> Self {
+        let (result, error) = match outcome {
+            Ok(result) if result.success => (Some(result), None),
+            Ok(result) => {
+                let error = result.output.get("error")
+                    .and_then(|e| e.as_str())
+                    .unwrap_or("Element execution failed")
+                    .to_string();
+                (Some(result), Some(error))
+            }
+            Err(e) => (None, Some(e)),
+        };
+        let status = match (&error, policy) {
+            (None, _) => MapElementStatus::Succeeded,
+            (Some(_), MapFailurePolicy::Skip) => MapElementStatus::Skipped,
+            (Some(_), _) => MapElementStatus::Failed,
+        };
+
+        Self {
+            index,
+            task_id,
+            rendered_input,
+            status,
+            result,
+            error,
+        }
+    }
+}
+
+/// Array of the element outputs, or the error failing the step
+pub(crate) fn gather_outputs(elements: &[MapElement], policy: MapFailurePolicy) -> Result<serde_json::Value, String> {
+    if policy == MapFailurePolicy::FailAll {
+        if let Some(failed) = elements.iter().find(|e| e.status != MapElementStatus::Succeeded) {
+            return Err(format!(
+                "Map element {} failed: {}",
+                failed.index,
+                failed.error.as_deref().unwrap_or_default()
+            ));
+        }
+    }
+
+    Ok(elements.iter()
+        .filter(|e| e.status == MapElementStatus::Succeeded)
+        .filter_map(|e| e.result.as_ref().map(|r| r.output.clone()))
+        .collect())
+}
@@ -0,0 +1,187 @@
+//! Prometheus metrics for task and workflow execution
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use prometheus::{
+    Counter, CounterVec, Encoder, Histogram, HistogramOpts, Opts, Registry, TextEncoder,
+};
+
+/// Metrics collector for the Conductor
+///
+/// Each collector owns its own registry, so several Conductors (e.g. in
+/// tests) can coexist without clashing on the global default registry.
+#[derive(Clone)]
+pub struct ConductorMetrics {
+    registry: Registry,
+    tasks_submitted_total: Counter,
+    tasks_failed_total: CounterVec,
+    security_violations_total: Counter,
+    task_execution_seconds: Histogram,
+    workflow_duration_seconds: Histogram,
+    queue_wait_seconds: Histogram,
+    labeled_tasks_submitted_total: CounterVec,
+    labeled_tasks_failed_total: CounterVec,
+    /// Task label keys exported as metric dimensions; other keys are dropped
+    /// to keep cardinality bounded
+    label_keys: Arc<HashSet<String>>,
+}
+
+impl ConductorMetrics {
+    /// Create a new metrics collector
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let tasks_submitted_total = Counter::with_opts(Opts::new(
+            "conductor_tasks_submitted_total",
+            "Total number of tasks submitted for execution",
+        )).unwrap();
+
+        let tasks_failed_total = CounterVec::new(
+            Opts::new(
+                "conductor_tasks_failed_total",
+                "Total number of tasks that failed after all attempts",
+            ),
+            &["module_id"],
+        ).unwrap();
+
+        let security_violations_total = Counter::with_opts(Opts::new(
+            "conductor_security_violations_total",
+            "Total number of security violations reported by executions",
+        )).unwrap();
+
+        let task_execution_seconds = Histogram::with_opts(
+            HistogramOpts::new(
+                "conductor_task_execution_seconds",
+                "Task execution attempt duration in seconds",
+            )
+            .buckets(vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]),
+        ).unwrap();
+
+        let workflow_duration_seconds = Histogram::with_opts(
+            HistogramOpts::new(
+                "conductor_workflow_duration_seconds",
+                "Workflow execution duration in seconds",
+            )
+            .buckets(vec![0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0]),
+        ).unwrap();
+
+        let queue_wait_seconds = Histogram::with_opts(
+            HistogramOpts::new(
+                "conductor_queue_wait_seconds",
+                "Time between task creation and start of execution in seconds",
+            )
+            .buckets(vec![0.001, 0.01, 0.1, 0.5, 1.0, 5.0, 30.0, 60.0, 300.0]),
+        ).unwrap();
+
+        let labeled_tasks_submitted_total = CounterVec::new(
+            Opts::new(
+                "conductor_labeled_tasks_submitted_total",
+                "Total number of submitted tasks by exported task label",
+            ),
+            &["label_key", "label_value"],
+        ).unwrap();
+
+        let labeled_tasks_failed_total = CounterVec::new(
+            Opts::new(
+                "conductor_labeled_tasks_failed_total",
+                "Total number of failed tasks by exported task label",
+            ),
+            &["label_key", "label_value"],
+        ).unwrap();
+
+        registry.register(Box::new(tasks_submitted_total.clone())).unwrap();
+        registry.register(Box::new(tasks_failed_total.clone())).unwrap();
+        registry.register(Box::new(security_violations_total.clone())).unwrap();
+        registry.register(Box::new(task_execution_seconds.clone())).unwrap();
+        registry.register(Box::new(workflow_duration_seconds.clone())).unwrap();
+        registry.register(Box::new(queue_wait_seconds.clone())).unwrap();
+        registry.register(Box::new(labeled_tasks_submitted_total.clone())).unwrap();
+        registry.register(Box::new(labeled_tasks_failed_total.clone())).unwrap();
+
+        Self {
+            registry,
+            tasks_submitted_total,
+            tasks_failed_total,
+            security_violations_total,
+            task_execution_seconds,
+            workflow_duration_seconds,
+            queue_wait_seconds,
+            labeled_tasks_submitted_total,
+            labeled_tasks_failed_total,
+            label_keys: Arc::new(HashSet::new()),
+        }
+    }
+
+    /// Export the given task label keys as metric dimensions
+    pub fn with_label_keys<I, K>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        self.label_keys = Arc::new(keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Record a labeled task submission for every exported label key
+    pub fn record_labeled_task_submitted(&self, labels: &HashMap<String, String>) {
+        self.record_labeled(&self.labeled_tasks_submitted_total, labels);
+    }
+
+    /// Record a labeled task failure for every exported label key
+    pub fn record_labeled_task_failed(&self, labels: &HashMap<String, String>) {
+        self.record_labeled(&self.labeled_tasks_failed_total, labels);
+    }
+
+    fn record_labeled(&self, counter: &CounterVec, labels: &HashMap<String, String>) {
+        for (key, value) in labels {
+            if self.label_keys.contains(key) {
+                counter.with_label_values(&[key, value]).inc();
+            }
+        }
+    }
+
+    /// Record a task submission and how long it waited before starting
+    pub fn record_task_submitted(&self, queue_wait: Duration) {
+        self.tasks_submitted_total.inc();
+        self.queue_wait_seconds.observe(queue_wait.as_secs_f64());
+    }
+
+    /// Record a task that failed after all attempts
+    pub fn record_task_failed(&self, module_id: &str) {
+        self.tasks_failed_total
+            .with_label_values(&[module_id])
+            .inc();
+    }
+
+    /// Record security violations reported by an execution
+    pub fn record_security_violations(&self, count: usize) {
+        self.security_violations_total.inc_by(count as f64);
+    }
+
+    /// Record the duration of one execution attempt
+    pub fn record_task_execution(&self, duration: Duration) {
+        self.task_execution_seconds.observe(duration.as_secs_f64());
+    }
+
+    /// Record a workflow duration
+    pub fn record_workflow_duration(&self, duration: Duration) {
+        self.workflow_duration_seconds.observe(duration.as_secs_f64());
+    }
+
+    /// Encode all metrics in the Prometheus text format
+    pub fn gather(&self) -> String {
+        let encoder = TextEncoder::new();
+        let metric_families = self.registry.gather();
+        let mut buffer = Vec::new();
+        encoder.encode(&metric_families, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+}
+
+impl Default for ConductorMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,141 @@
+//! Filtering and cursor-based pagination for Conductor list APIs
+
+use serde::{Deserialize, Serialize};
+
+use crate::{AgentTask, LabelSelector, TaskPriority, TaskResult};
+
+/// Default page size when no limit is given
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Upper bound on a single page
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// Filter for `Conductor::list_results_filtered`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResultFilter {
+    pub success: Option<bool>,
+    pub module_id: Option<String>,
+    pub completed_after: Option<chrono::DateTime<chrono::Utc>>,
+    pub completed_before: Option<chrono::DateTime<chrono::Utc>>,
+    pub has_security_violations: Option<bool>,
+    pub limit: Option<usize>,
+    /// Return results after this cursor, taken from `Page::next_cursor`
+    pub after_id: Option<String>,
+}
+
+/// Filter for `Conductor::list_tasks_filtered`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskFilter {
+    pub priority: Option<TaskPriority>,
+    pub module_id: Option<String>,
+    pub created_after: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_before: Option<chrono::DateTime<chrono::Utc>>,
+    pub labels: Option<LabelSelector>,
+    pub limit: Option<usize>,
+    /// Return tasks after this cursor, taken from `Page::next_cursor`
+    pub after_id: Option<String>,
+}
+
+/// A page of list results
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor to pass as `after_id` for the next page
+    pub next_cursor: Option<String>,
+}
+
+impl ResultFilter {
+    /// Check whether a result matches this filter. `module_id` is the module
+    /// of the originating task, if known.
+    pub fn matches(&self, result: &TaskResult, module_id: Option<&str>) -> bool {
+        if self.success.is_some_and(|success| success != result.success) {
+            return false;
+        }
+        if let Some(wanted) = &self.module_id {
+            if module_id != Some(wanted.as_str()) {
+                return false;
+            }
+        }
+        if self.completed_after.is_some_and(|after| result.completed_at < after) {
+            return false;
+        }
+        if self.completed_before.is_some_and(|before| result.completed_at >= before) {
+            return false;
+        }
+        if let Some(wanted) = self.has_security_violations {
+            if wanted == result.security_violations.is_empty() {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl TaskFilter {
+    /// Check whether a task matches this filter
+    pub fn matches(&self, task: &AgentTask) -> bool {
+        if self.priority.as_ref().is_some_and(|priority| *priority != task.priority) {
+            return false;
+        }
+        if self.module_id.as_ref().is_some_and(|module_id| *module_id != task.module_id) {
+            return false;
+        }
+        if self.created_after.is_some_and(|after| task.created_at < after) {
+            return false;
+        }
+        if self.created_before.is_some_and(|before| task.created_at >= before) {
+            return false;
+        }
+        if self.labels.as_ref().is_some_and(|selector| !selector.matches(&task.labels)) {
+            return false;
+        }
+        true
+    }
+}
+
+/// Cursor of the item sorting at `(timestamp, id)`
+pub fn encode_cursor(timestamp: chrono::DateTime<chrono::Utc>, id: &str) -> String {
+    format!("{}|{}", timestamp.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true), id)
+}
+
+/// The `(timestamp, id)` position a cursor points at
+pub fn decode_cursor(cursor: &str) -> Option<(chrono::DateTime<chrono::Utc>, String)> {
+    let (timestamp, id) = cursor.split_once('|')?;
+    let timestamp = chrono::DateTime::parse_from_rfc3339(timestamp).ok()?.with_timezone(&chrono::Utc);
+    Some((timestamp, id.to_string()))
+}
+
+/// Sort items by `(timestamp, id)` and cut a page starting after the cursor.
+///
+/// The secondary sort on id keeps ordering stable for equal timestamps. The
+/// page seeks past the cursor's position rather than looking up its item, so
+/// inserts and removals elsewhere in the list never shift it. A malformed
+/// cursor yields an empty page.
+pub fn paginate<T, K>(mut items: Vec<T>, key: K, after: Option<&str>, limit: Option<usize>) -> Page<T>
+where
+    K: Fn(&T) -> (chrono::DateTime<chrono::Utc>, String),
+{
+    items.sort_by_key(|item| key(item));
+
+    let start = match after {
+        Some(cursor) => match decode_cursor(cursor) {
+            Some(position) => items.partition_point(|item| key(item) <= position),
+            None => items.len(),
+        },
+        None => 0,
+    };
+
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let has_more = items.len() > start + limit;
+    let items: Vec<T> = items.into_iter().skip(start).take(limit).collect();
+    let next_cursor = if has_more {
+        items.last().map(|item| {
+            let (timestamp, id) = key(item);
+            encode_cursor(timestamp, &id)
+        })
+    } else {
+        None
+    };
+
+    Page { items, next_cursor }
+}
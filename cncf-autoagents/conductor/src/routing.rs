@@ -0,0 +1,101 @@
+//! Label-aware routing of tasks to Fortress endpoints
+
+use serde::{Deserialize, Serialize};
+
+use crate::{AgentTask, LabelSelector};
+
+/// Sends matching tasks through a specific Fortress endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteRule {
+    /// Module the rule applies to; `None` matches every module
+    #[serde(default)]
+    pub module_id: Option<String>,
+    /// Labels a task must carry; the default selector matches every task
+    #[serde(default)]
+    pub selector: LabelSelector,
+    pub fortress_url: String,
+}
+
+impl RouteRule {
+    pub fn new(fortress_url: impl Into<String>) -> Self {
+        Self {
+            module_id: None,
+            selector: LabelSelector::default(),
+            fortress_url: fortress_url.into(),
+        }
+    }
+
+    pub fn with_module(mut self, module_id: impl Into<String>) -> Self {
+        self.module_id = Some(module_id.into());
+        self
+    }
+
+    pub fn with_selector(mut self, selector: LabelSelector) -> Self {
+        self.selector = selector;
+        self
+    }
+
+    /// Check whether `task` is routed by this rule
+    pub fn matches(&self, task: &AgentTask) -> bool {
+        self.module_id.as_ref().is_none_or(|module_id| *module_id == task.module_id)
+            && self.selector.matches(&task.labels)
+    }
+}
+
+/// Ordered route rules; the first matching rule wins
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoutingTable {
+    rules: Vec<RouteRule>,
+}
+
+impl RoutingTable {
+    /// Append a rule, matched after every rule added before it
+    pub fn add(&mut self, rule: RouteRule) {
+        self.rules.push(rule);
+    }
+
+    pub fn rules(&self) -> &[RouteRule] {
+        &self.rules
+    }
+
+    /// The rule routing `task`, if any
+    pub fn resolve(&self, task: &AgentTask) -> Option<&RouteRule> {
+        self.rules.iter().find(|rule| rule.matches(task))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TaskPriority;
+    use std::collections::HashMap;
+
+    fn task(module_id: &str, labels: &[(&str, &str)]) -> AgentTask {
+        AgentTask {
+            id: "t".to_string(),
+            name: "t".to_string(),
+            description: String::new(),
+            module_id: module_id.to_string(),
+            input: serde_json::json!({}),
+            priority: TaskPriority::Normal,
+            timeout_ms: None,
+            created_at: chrono::Utc::now(),
+            labels: labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<HashMap<_, _>>(),
+            trace_context: None,
+            change_detection: None,
+        }
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let mut table = RoutingTable::default();
+        table.add(RouteRule::new("http://eu-payments").with_module("billing").with_selector(LabelSelector::new([("region", "eu")])));
+        table.add(RouteRule::new("http://eu").with_selector(LabelSelector::new([("region", "eu")])));
+
+        let url = |task: &AgentTask| table.resolve(task).map(|rule| rule.fortress_url.as_str());
+        assert_eq!(url(&task("billing", &[("region", "eu")])), Some("http://eu-payments"));
+        assert_eq!(url(&task("search", &[("region", "eu"), ("team", "search")])), Some("http://eu"));
+        assert_eq!(url(&task("billing", &[("region", "us")])), None);
+        assert_eq!(url(&task("billing", &[])), None);
+    }
+}
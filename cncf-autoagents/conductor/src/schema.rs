@@ -0,0 +1,79 @@
+//! Per-module JSON Schema validation of task inputs
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+/// A single schema violation in a task input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending value; empty for the input root
+    pub path: String,
+    pub message: String,
+}
+
+/// Error returned when a task input does not match its module schema
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Invalid input for task '{task_id}' (module '{module_id}'): {} violation(s)", violations.len())]
+pub struct InvalidTaskInput {
+    pub task_id: String,
+    pub module_id: String,
+    pub violations: Vec<SchemaViolation>,
+}
+
+/// Error returned when registering a schema that does not compile
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Invalid schema for module '{module_id}': {message}")]
+pub struct InvalidSchema {
+    pub module_id: String,
+    pub message: String,
+}
+
+/// A module's input schema and its compiled validator
+#[derive(Clone)]
+pub struct ModuleSchema {
+    schema: serde_json::Value,
+    validator: Arc<jsonschema::Validator>,
+}
+
+impl ModuleSchema {
+    /// Compile `schema`, rejecting schemas that are not valid JSON Schema
+    pub fn compile(module_id: &str, schema: serde_json::Value) -> Result<Self, InvalidSchema> {
+        let validator = jsonschema::validator_for(&schema).map_err(|e| InvalidSchema {
+            module_id: module_id.to_string(),
+            message: e.to_string(),
+        })?;
+
+        Ok(Self {
+            schema,
+            validator: Arc::new(validator),
+        })
+    }
+
+    /// The schema as registered
+    pub fn schema(&self) -> &serde_json::Value {
+        &self.schema
+    }
+
+    /// Every violation of the schema in `input`
+    pub fn violations(&self, input: &serde_json::Value) -> Vec<SchemaViolation> {
+        self.validator
+            .iter_errors(input)
+            .map(|error| SchemaViolation {
+                path: error.instance_path.to_string(),
+                message: error.to_string(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_schema_rejected() {
+        let err = ModuleSchema::compile("m", serde_json::json!({"type": "no-such-type"})).err().unwrap();
+        assert_eq!(err.module_id, "m");
+    }
+}
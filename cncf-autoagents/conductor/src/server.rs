@@ -0,0 +1,571 @@
+//! Standalone HTTP server for the Conductor
+//!
+//! Backs the `conductor-server` binary (feature `server`). Configuration comes
+//! from a JSON file with `CONDUCTOR_*` environment overrides. Tasks and
+//! workflow runs submitted over HTTP execute in the background; on shutdown
+//! the server stops taking submissions, closes the events stream and waits
+//! for work already accepted to finish before exiting.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{Path as UrlPath, Query, State},
+    http::{header, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use futures::{stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, watch, Notify, RwLock};
+use tracing::{info, warn};
+
+use crate::{
+    AgentTask, AgentWorkflow, ChangeDetection, Conductor, Page, RetryPolicy, TaskFilter,
+    TaskPriority, TaskResult, WebhookConfig, WorkflowExecution,
+};
+
+/// Environment variable naming the config file when `--config` is not given
+pub const CONFIG_PATH_ENV: &str = "CONDUCTOR_CONFIG";
+
+/// Server configuration, read from a JSON file and `CONDUCTOR_*` variables
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerConfig {
+    #[serde(default = "default_listen_addr")]
+    pub listen_addr: SocketAddr,
+    #[serde(default = "default_fortress_url")]
+    pub fortress_url: String,
+    #[serde(default = "default_forge_url")]
+    pub forge_url: String,
+    /// How long shutdown waits for accepted tasks and workflow runs
+    #[serde(default = "default_drain_timeout_ms")]
+    pub drain_timeout_ms: u64,
+    #[serde(default)]
+    pub retry_policy: RetryPolicy,
+    /// Concurrency limits by module id
+    #[serde(default)]
+    pub module_limits: HashMap<String, usize>,
+    /// Task label keys exported as metric dimensions
+    #[serde(default)]
+    pub metric_label_keys: Vec<String>,
+    /// Webhooks registered at startup
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+}
+
+fn default_listen_addr() -> SocketAddr {
+    SocketAddr::from(([0, 0, 0, 0], 8082))
+}
+
+fn default_fortress_url() -> String {
+    "http://localhost:8080".to_string()
+}
+
+fn default_forge_url() -> String {
+    "http://localhost:8081".to_string()
+}
+
+fn default_drain_timeout_ms() -> u64 {
+    30_000
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: default_listen_addr(),
+            fortress_url: default_fortress_url(),
+            forge_url: default_forge_url(),
+            drain_timeout_ms: default_drain_timeout_ms(),
+            retry_policy: RetryPolicy::default(),
+            module_limits: HashMap::new(),
+            metric_label_keys: Vec::new(),
+            webhooks: Vec::new(),
+        }
+    }
+}
+
+/// Why the server configuration could not be loaded
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("cannot read config file {}: {source} (pass --config or set {CONFIG_PATH_ENV} to an existing file)", path.display())]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("invalid config file {}: {source}", path.display())]
+    Parse { path: PathBuf, source: serde_json::Error },
+    #[error("invalid value {value:?} for {var}: {reason}")]
+    Env { var: String, value: String, reason: String },
+    #[error("invalid {field}: {reason}")]
+    Invalid { field: String, reason: String },
+}
+
+impl ServerConfig {
+    /// Load the config file at `path` (or `CONDUCTOR_CONFIG`, or defaults when
+    /// neither is set), apply environment overrides and validate the result
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let path = path.map(Path::to_path_buf).or_else(|| std::env::var_os(CONFIG_PATH_ENV).map(PathBuf::from));
+        let mut config = match path {
+            Some(path) => Self::from_file(&path)?,
+            None => Self::default(),
+        };
+        config.apply_env(|var| std::env::var(var).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|source| ConfigError::Read { path: path.to_path_buf(), source })?;
+        serde_json::from_str(&content).map_err(|source| ConfigError::Parse { path: path.to_path_buf(), source })
+    }
+
+    /// Override settings from `CONDUCTOR_LISTEN_ADDR`, `CONDUCTOR_FORTRESS_URL`,
+    /// `CONDUCTOR_FORGE_URL` and `CONDUCTOR_DRAIN_TIMEOUT_MS`, looked up through `var`
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        let invalid = |name: &str, value: &str, reason: String| ConfigError::Env {
+            var: name.to_string(),
+            value: value.to_string(),
+            reason,
+        };
+
+        if let Some(value) = var("CONDUCTOR_LISTEN_ADDR") {
+            self.listen_addr = value.parse().map_err(|e| invalid("CONDUCTOR_LISTEN_ADDR", &value, format!("{} (expected host:port, e.g. 0.0.0.0:8082)", e)))?;
+        }
+        if let Some(value) = var("CONDUCTOR_FORTRESS_URL") {
+            self.fortress_url = value;
+        }
+        if let Some(value) = var("CONDUCTOR_FORGE_URL") {
+            self.forge_url = value;
+        }
+        if let Some(value) = var("CONDUCTOR_DRAIN_TIMEOUT_MS") {
+            self.drain_timeout_ms = value.parse().map_err(|e| invalid("CONDUCTOR_DRAIN_TIMEOUT_MS", &value, format!("{} (expected milliseconds)", e)))?;
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for (field, url) in [("fortress_url", &self.fortress_url), ("forge_url", &self.forge_url)] {
+            let parsed = reqwest::Url::parse(url).map_err(|e| ConfigError::Invalid {
+                field: field.to_string(),
+                reason: format!("{:?} is not a URL: {}", url, e),
+            })?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err(ConfigError::Invalid {
+                    field: field.to_string(),
+                    reason: format!("{:?} must use http or https", url),
+                });
+            }
+        }
+        if let Some((module_id, _)) = self.module_limits.iter().find(|(_, limit)| **limit == 0) {
+            return Err(ConfigError::Invalid {
+                field: format!("module_limits.{}", module_id),
+                reason: "limit must be at least 1; remove the entry to leave the module unlimited".to_string(),
+            });
+        }
+        if self.retry_policy.max_attempts == 0 {
+            return Err(ConfigError::Invalid {
+                field: "retry_policy.max_attempts".to_string(),
+                reason: "must be at least 1".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_millis(self.drain_timeout_ms)
+    }
+
+    /// Build a Conductor with this configuration's policies, limits and webhooks
+    pub async fn build_conductor(&self) -> Conductor {
+        let conductor = Conductor::new(self.fortress_url.clone(), self.forge_url.clone())
+            .with_retry_policy(self.retry_policy.clone())
+            .with_metric_label_keys(self.metric_label_keys.iter().cloned());
+        for (module_id, limit) in &self.module_limits {
+            conductor.set_module_limit(module_id, *limit).await;
+        }
+        for webhook in &self.webhooks {
+            conductor.register_webhook(webhook.clone()).await;
+        }
+        conductor
+    }
+}
+
+/// Background work accepted by the server, and whether it is shutting down
+struct InFlight {
+    count: AtomicUsize,
+    idle: Notify,
+    draining: watch::Sender<bool>,
+}
+
+impl InFlight {
+    fn new() -> Self {
+        Self {
+            count: AtomicUsize::new(0),
+            idle: Notify::new(),
+            draining: watch::channel(false).0,
+        }
+    }
+
+    fn is_draining(&self) -> bool {
+        *self.draining.borrow()
+    }
+
+    fn spawn(self: &Arc<Self>, work: impl Future<Output = ()> + Send + 'static) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        let in_flight = self.clone();
+        tokio::spawn(async move {
+            work.await;
+            if in_flight.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+                in_flight.idle.notify_waiters();
+            }
+        });
+    }
+
+    /// Wait until no work is in flight; false if `timeout` elapsed first
+    async fn drain(&self, timeout: Duration) -> bool {
+        let idle = async {
+            loop {
+                // Registered before the check so a wakeup in between is not lost
+                let notified = self.idle.notified();
+                if self.count.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                notified.await;
+            }
+        };
+        tokio::time::timeout(timeout, idle).await.is_ok()
+    }
+}
+
+/// Where a task submitted over HTTP stands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TaskStatus {
+    Pending,
+    Completed { result: TaskResult },
+    /// The task never produced a result, e.g. because its input was invalid
+    Rejected { error: String },
+}
+
+/// Body of `POST /api/v1/tasks`
+#[derive(Debug, Clone, Deserialize)]
+pub struct TaskSubmission {
+    /// Generated when absent
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    pub module_id: String,
+    #[serde(default)]
+    pub input: serde_json::Value,
+    #[serde(default)]
+    pub priority: Option<TaskPriority>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    #[serde(default)]
+    pub change_detection: Option<ChangeDetection>,
+}
+
+impl TaskSubmission {
+    fn into_task(self) -> AgentTask {
+        let id = self.id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        AgentTask {
+            name: self.name.unwrap_or_else(|| id.clone()),
+            id,
+            description: self.description,
+            module_id: self.module_id,
+            input: self.input,
+            priority: self.priority.unwrap_or(TaskPriority::Normal),
+            timeout_ms: self.timeout_ms,
+            created_at: chrono::Utc::now(),
+            labels: self.labels,
+            trace_context: None,
+            change_detection: self.change_detection,
+        }
+    }
+}
+
+/// Returned when a task or workflow run is accepted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Accepted {
+    pub id: String,
+}
+
+/// Body of `GET /health`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerHealth {
+    pub status: String,
+    pub in_flight: usize,
+}
+
+/// State shared by the server's handlers
+#[derive(Clone)]
+pub struct ServerState {
+    conductor: Conductor,
+    in_flight: Arc<InFlight>,
+    /// Submitted tasks that have no result yet, or never will
+    submissions: Arc<RwLock<HashMap<String, TaskStatus>>>,
+}
+
+impl ServerState {
+    pub fn new(conductor: Conductor) -> Self {
+        Self {
+            conductor,
+            in_flight: Arc::new(InFlight::new()),
+            submissions: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn conductor(&self) -> &Conductor {
+        &self.conductor
+    }
+
+    /// Stop accepting work and end open event streams
+    pub fn begin_drain(&self) {
+        self.in_flight.draining.send_replace(true);
+    }
+
+    /// Wait for accepted work to finish; false if `timeout` elapsed first
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        self.begin_drain();
+        self.in_flight.drain(timeout).await
+    }
+}
+
+/// Routes served by `conductor-server`
+pub fn router(state: ServerState) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/metrics", get(metrics))
+        .route("/api/v1/tasks", post(submit_task).get(list_tasks))
+        .route("/api/v1/tasks/:id", get(task_status))
+        .route("/api/v1/workflows", post(register_workflow))
+        .route("/api/v1/workflows/:id", get(get_workflow))
+        .route("/api/v1/workflows/:id/runs", post(run_workflow).get(list_workflow_runs))
+        .route("/api/v1/events", get(events))
+        .with_state(state)
+}
+
+/// Serve `state` on `listener` until `shutdown` resolves, then drain accepted
+/// work for up to `drain_timeout`. Returns whether the drain completed.
+pub async fn serve(
+    listener: TcpListener,
+    state: ServerState,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+    drain_timeout: Duration,
+) -> std::io::Result<bool> {
+    let draining = state.clone();
+    axum::serve(listener, router(state.clone()))
+        .with_graceful_shutdown(async move {
+            shutdown.await;
+            info!(event = "shutdown_requested", in_flight = draining.in_flight.count.load(Ordering::SeqCst), "🛑 Shutdown requested, draining accepted work");
+            draining.begin_drain();
+        })
+        .await?;
+
+    let drained = state.drain(drain_timeout).await;
+    if drained {
+        info!(event = "drained", "✅ All accepted tasks and workflow runs finished");
+    } else {
+        warn!(
+            event = "drain_timeout",
+            in_flight = state.in_flight.count.load(Ordering::SeqCst),
+            "⚠️ Drain timeout of {:?} elapsed with work still running", drain_timeout
+        );
+    }
+    Ok(drained)
+}
+
+/// Resolve on Ctrl+C or SIGTERM
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+fn error_response(status: StatusCode, error: impl ToString) -> Response {
+    (status, Json(serde_json::json!({ "error": error.to_string() }))).into_response()
+}
+
+fn draining_response() -> Response {
+    error_response(StatusCode::SERVICE_UNAVAILABLE, "Conductor is shutting down")
+}
+
+async fn health(State(state): State<ServerState>) -> (StatusCode, Json<ServerHealth>) {
+    let draining = state.in_flight.is_draining();
+    let health = ServerHealth {
+        status: if draining { "draining" } else { "ok" }.to_string(),
+        in_flight: state.in_flight.count.load(Ordering::SeqCst),
+    };
+    let status = if draining { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::OK };
+    (status, Json(health))
+}
+
+async fn metrics(State(state): State<ServerState>) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], state.conductor.gather_metrics())
+}
+
+async fn submit_task(State(state): State<ServerState>, Json(submission): Json<TaskSubmission>) -> Response {
+    if state.in_flight.is_draining() {
+        return draining_response();
+    }
+    let task = submission.into_task();
+    let id = task.id.clone();
+    state.submissions.write().await.insert(id.clone(), TaskStatus::Pending);
+
+    let conductor = state.conductor.clone();
+    let submissions = state.submissions.clone();
+    state.in_flight.spawn(async move {
+        let task_id = task.id.clone();
+        let outcome = conductor.execute_task(task).await.map_err(|e| e.to_string());
+        let mut submissions = submissions.write().await;
+        match outcome {
+            // The Conductor keeps the result from here on
+            Ok(_) => {
+                submissions.remove(&task_id);
+            }
+            Err(error) => {
+                warn!("❌ Task {} submitted over HTTP was rejected: {}", task_id, error);
+                submissions.insert(task_id, TaskStatus::Rejected { error });
+            }
+        }
+    });
+
+    (StatusCode::ACCEPTED, Json(Accepted { id })).into_response()
+}
+
+async fn list_tasks(State(state): State<ServerState>, Query(filter): Query<TaskFilter>) -> Json<Page<AgentTask>> {
+    Json(state.conductor.list_tasks_filtered(filter).await)
+}
+
+async fn task_status(State(state): State<ServerState>, UrlPath(id): UrlPath<String>) -> Response {
+    if let Some(result) = state.conductor.get_task_result(&id).await {
+        return Json(TaskStatus::Completed { result }).into_response();
+    }
+    match state.submissions.read().await.get(&id) {
+        Some(status) => Json(status.clone()).into_response(),
+        None => error_response(StatusCode::NOT_FOUND, format!("Unknown task {}", id)),
+    }
+}
+
+async fn register_workflow(State(state): State<ServerState>, Json(workflow): Json<AgentWorkflow>) -> Response {
+    let id = workflow.id.clone();
+    match state.conductor.register_workflow(workflow).await {
+        Ok(()) => (StatusCode::CREATED, Json(Accepted { id })).into_response(),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+async fn get_workflow(State(state): State<ServerState>, UrlPath(id): UrlPath<String>) -> Response {
+    match state.conductor.get_workflow(&id).await {
+        Some(workflow) => Json(workflow).into_response(),
+        None => error_response(StatusCode::NOT_FOUND, format!("Unknown workflow {}", id)),
+    }
+}
+
+async fn run_workflow(State(state): State<ServerState>, UrlPath(id): UrlPath<String>) -> Response {
+    if state.in_flight.is_draining() {
+        return draining_response();
+    }
+    let Some(workflow) = state.conductor.get_workflow(&id).await else {
+        return error_response(StatusCode::NOT_FOUND, format!("Unknown workflow {}", id));
+    };
+
+    let conductor = state.conductor.clone();
+    state.in_flight.spawn(async move {
+        if let Err(e) = conductor.execute_workflow(workflow).await.map_err(|e| e.to_string()) {
+            warn!("❌ Workflow run submitted over HTTP failed: {}", e);
+        }
+    });
+    (StatusCode::ACCEPTED, Json(Accepted { id })).into_response()
+}
+
+async fn list_workflow_runs(State(state): State<ServerState>, UrlPath(id): UrlPath<String>) -> Json<Vec<WorkflowExecution>> {
+    Json(state.conductor.list_workflow_runs(&id).await)
+}
+
+/// Task and workflow events as server-sent events, until the server drains
+async fn events(State(state): State<ServerState>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.conductor.subscribe_events();
+    let mut draining = state.in_flight.draining.subscribe();
+
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(payload) => {
+                    let name = serde_json::to_value(payload.event).ok()?.as_str()?.to_string();
+                    let data = serde_json::to_string(&payload).unwrap_or_default();
+                    return Some((Ok(Event::default().event(name).data(data)), receiver));
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!("📡 Events subscriber fell behind and missed {} event(s)", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    let stop = async move {
+        let _ = draining.wait_for(|draining| *draining).await;
+    };
+
+    Sse::new(events.take_until(stop)).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_overrides_and_actionable_errors() {
+        let env: HashMap<&str, &str> = HashMap::from([
+            ("CONDUCTOR_LISTEN_ADDR", "127.0.0.1:9000"),
+            ("CONDUCTOR_FORGE_URL", "http://forge:8081"),
+        ]);
+        let mut config = ServerConfig::default();
+        config.apply_env(|var| env.get(var).map(|value| value.to_string())).unwrap();
+        assert_eq!(config.listen_addr, SocketAddr::from(([127, 0, 0, 1], 9000)));
+        assert_eq!(config.forge_url, "http://forge:8081");
+        config.validate().unwrap();
+
+        let error = config.apply_env(|var| (var == "CONDUCTOR_DRAIN_TIMEOUT_MS").then(|| "soon".to_string())).unwrap_err();
+        assert!(error.to_string().contains("CONDUCTOR_DRAIN_TIMEOUT_MS"));
+
+        config.module_limits.insert("scraper".to_string(), 0);
+        assert!(config.validate().unwrap_err().to_string().contains("module_limits.scraper"));
+
+        let error = ServerConfig::from_file(Path::new("/nonexistent/conductor.json")).unwrap_err();
+        assert!(error.to_string().contains(CONFIG_PATH_ENV));
+    }
+}
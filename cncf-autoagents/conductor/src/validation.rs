@@ -0,0 +1,265 @@
+//! Structural validation for agent workflows
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::AgentWorkflow;
+
+/// A single workflow validation failure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WorkflowValidationError {
+    #[error("Workflow has no steps")]
+    EmptyWorkflow,
+    #[error("Workflow timeout must be greater than zero")]
+    ZeroTimeout,
+    #[error("Duplicate step id '{step_id}'")]
+    DuplicateStepId { step_id: String },
+    #[error("Step '{step_id}' depends on unknown step '{dependency}'")]
+    UnknownDependency { step_id: String, dependency: String },
+    #[error("Dependency cycle: {}", cycle.join(" -> "))]
+    DependencyCycle { cycle: Vec<String> },
+    #[error("Step '{step_id}' timeout {step_timeout_ms}ms exceeds workflow timeout {workflow_timeout_ms}ms")]
+    StepTimeoutExceedsWorkflow {
+        step_id: String,
+        step_timeout_ms: u64,
+        workflow_timeout_ms: u64,
+    },
+    #[error("Map step '{step_id}' must read its items from a step it depends on, not '{items_pointer}'")]
+    MapSourceNotDependency { step_id: String, items_pointer: String },
+    #[error("Map step '{step_id}' must allow at least one element in parallel")]
+    ZeroMapParallelism { step_id: String },
+}
+
+/// Error returned when registering or executing an invalid workflow
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Invalid workflow '{workflow_id}': {} validation error(s)", errors.len())]
+pub struct InvalidWorkflow {
+    pub workflow_id: String,
+    pub errors: Vec<WorkflowValidationError>,
+}
+
+impl AgentWorkflow {
+    /// Validate the workflow structure, returning every violation found
+    pub fn validate(&self) -> Vec<WorkflowValidationError> {
+        let mut errors = Vec::new();
+
+        if self.steps.is_empty() {
+            errors.push(WorkflowValidationError::EmptyWorkflow);
+        }
+        if self.timeout_ms == 0 {
+            errors.push(WorkflowValidationError::ZeroTimeout);
+        }
+
+        let mut seen = HashSet::new();
+        for step in &self.steps {
+            if !seen.insert(step.id.as_str()) {
+                errors.push(WorkflowValidationError::DuplicateStepId {
+                    step_id: step.id.clone(),
+                });
+            }
+
+            if let Some(step_timeout_ms) = step.timeout_ms {
+                if self.timeout_ms > 0 && step_timeout_ms > self.timeout_ms {
+                    errors.push(WorkflowValidationError::StepTimeoutExceedsWorkflow {
+                        step_id: step.id.clone(),
+                        step_timeout_ms,
+                        workflow_timeout_ms: self.timeout_ms,
+                    });
+                }
+            }
+
+            if let Some(map) = &step.map {
+                if !map.source_step().is_some_and(|source| step.depends_on.iter().any(|d| d == source)) {
+                    errors.push(WorkflowValidationError::MapSourceNotDependency {
+                        step_id: step.id.clone(),
+                        items_pointer: map.items_pointer.clone(),
+                    });
+                }
+                if map.max_parallel == 0 {
+                    errors.push(WorkflowValidationError::ZeroMapParallelism {
+                        step_id: step.id.clone(),
+                    });
+                }
+            }
+        }
+
+        for step in &self.steps {
+            for dependency in &step.depends_on {
+                if !seen.contains(dependency.as_str()) {
+                    errors.push(WorkflowValidationError::UnknownDependency {
+                        step_id: step.id.clone(),
+                        dependency: dependency.clone(),
+                    });
+                }
+            }
+        }
+
+        if let Some(cycle) = self.find_cycle() {
+            errors.push(WorkflowValidationError::DependencyCycle { cycle });
+        }
+
+        errors
+    }
+
+    /// Find a dependency cycle using depth-first search over known steps
+    fn find_cycle(&self) -> Option<Vec<String>> {
+        let graph: HashMap<&str, Vec<&str>> = self.steps
+            .iter()
+            .map(|step| (step.id.as_str(), step.depends_on.iter().map(String::as_str).collect()))
+            .collect();
+
+        fn visit<'a>(
+            node: &'a str,
+            graph: &HashMap<&'a str, Vec<&'a str>>,
+            path: &mut Vec<&'a str>,
+            done: &mut HashSet<&'a str>,
+        ) -> Option<Vec<String>> {
+            if let Some(start) = path.iter().position(|visited| *visited == node) {
+                let mut cycle: Vec<String> = path[start..].iter().map(|id| id.to_string()).collect();
+                cycle.push(node.to_string());
+                return Some(cycle);
+            }
+            if done.contains(node) {
+                return None;
+            }
+
+            path.push(node);
+            for dependency in graph.get(node).into_iter().flatten() {
+                if graph.contains_key(dependency) {
+                    if let Some(cycle) = visit(dependency, graph, path, done) {
+                        return Some(cycle);
+                    }
+                }
+            }
+            path.pop();
+            done.insert(node);
+            None
+        }
+
+        let mut done = HashSet::new();
+        for step in &self.steps {
+            if let Some(cycle) = visit(step.id.as_str(), &graph, &mut Vec::new(), &mut done) {
+                return Some(cycle);
+            }
+        }
+        None
+    }
+
+    /// Validate and convert the violations into an error
+    pub fn ensure_valid(&self) -> Result<(), InvalidWorkflow> {
+        let errors = self.validate();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(InvalidWorkflow {
+                workflow_id: self.id.clone(),
+                errors,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MapStep, RetryPolicy, WorkflowStep};
+
+    fn step(id: &str, depends_on: &[&str]) -> WorkflowStep {
+        WorkflowStep {
+            id: id.to_string(),
+            name: id.to_string(),
+            module_id: "test-module".to_string(),
+            input_template: serde_json::json!({"command": "test"}),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            retry_policy: RetryPolicy {
+                max_attempts: 1,
+                backoff_ms: 0,
+            },
+            timeout_ms: None,
+            map: None,
+        }
+    }
+
+    fn workflow(steps: Vec<WorkflowStep>) -> AgentWorkflow {
+        AgentWorkflow {
+            id: "wf".to_string(),
+            name: "Workflow".to_string(),
+            description: "Test workflow".to_string(),
+            steps,
+            timeout_ms: 10_000,
+        }
+    }
+
+    #[test]
+    fn test_valid_workflow() {
+        let wf = workflow(vec![step("a", &[]), step("b", &["a"]), step("c", &["a", "b"])]);
+        assert!(wf.validate().is_empty());
+        assert!(wf.ensure_valid().is_ok());
+    }
+
+    #[test]
+    fn test_empty_workflow_and_zero_timeout() {
+        let mut wf = workflow(vec![]);
+        wf.timeout_ms = 0;
+
+        let errors = wf.validate();
+        assert!(errors.contains(&WorkflowValidationError::EmptyWorkflow));
+        assert!(errors.contains(&WorkflowValidationError::ZeroTimeout));
+    }
+
+    #[test]
+    fn test_duplicate_step_id() {
+        let errors = workflow(vec![step("a", &[]), step("a", &[])]).validate();
+        assert_eq!(errors, vec![WorkflowValidationError::DuplicateStepId { step_id: "a".to_string() }]);
+    }
+
+    #[test]
+    fn test_unknown_dependency() {
+        let errors = workflow(vec![step("a", &["missing"])]).validate();
+        assert_eq!(errors, vec![WorkflowValidationError::UnknownDependency {
+            step_id: "a".to_string(),
+            dependency: "missing".to_string(),
+        }]);
+    }
+
+    #[test]
+    fn test_dependency_cycle() {
+        let errors = workflow(vec![step("a", &["c"]), step("b", &["a"]), step("c", &["b"])]).validate();
+        assert!(matches!(
+            errors.as_slice(),
+            [WorkflowValidationError::DependencyCycle { cycle }] if cycle.len() == 4 && cycle.first() == cycle.last()
+        ));
+    }
+
+    #[test]
+    fn test_step_timeout_exceeds_workflow() {
+        let mut slow = step("slow", &[]);
+        slow.timeout_ms = Some(20_000);
+
+        let errors = workflow(vec![slow]).validate();
+        assert_eq!(errors, vec![WorkflowValidationError::StepTimeoutExceedsWorkflow {
+            step_id: "slow".to_string(),
+            step_timeout_ms: 20_000,
+            workflow_timeout_ms: 10_000,
+        }]);
+    }
+
+    #[test]
+    fn test_map_step_source_and_parallelism() {
+        let mut fan_out = step("fan-out", &["fetch"]);
+        fan_out.map = Some(MapStep::new("/fetch/items"));
+        assert!(workflow(vec![step("fetch", &[]), fan_out.clone()]).validate().is_empty());
+
+        fan_out.map = Some(MapStep::new("/other/items").with_max_parallel(0));
+        let errors = workflow(vec![step("fetch", &[]), fan_out]).validate();
+        assert_eq!(errors, vec![
+            WorkflowValidationError::MapSourceNotDependency {
+                step_id: "fan-out".to_string(),
+                items_pointer: "/other/items".to_string(),
+            },
+            WorkflowValidationError::ZeroMapParallelism { step_id: "fan-out".to_string() },
+        ]);
+    }
+}
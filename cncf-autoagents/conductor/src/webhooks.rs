@@ -0,0 +1,250 @@
+//! Webhook notifications for completed tasks and workflows
+//!
+//! Deliveries run on spawned tasks so they never hold up execution. Each
+//! POST carries the event payload as JSON and, when the webhook has a secret,
+//! an `X-Signature: sha256=<hex>` HMAC over the raw body.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{error, info, warn};
+
+/// Header carrying the body signature
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+
+/// Events buffered for each in-process subscriber before it starts missing them
+const EVENT_BUFFER: usize = 256;
+
+/// Events a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    TaskCompleted,
+    TaskFailed,
+    WorkflowCompleted,
+    /// A change-detected task produced materially different output
+    OutputChanged,
+}
+
+/// Retry settings for failed deliveries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookRetry {
+    pub max_attempts: u32,
+    /// Backoff before the second attempt; doubles on each further attempt
+    pub initial_backoff_ms: u64,
+}
+
+impl Default for WebhookRetry {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff_ms: 500,
+        }
+    }
+}
+
+/// Webhook registration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// HMAC-SHA256 key for the `X-Signature` header
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Events to deliver; empty subscribes to all
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    #[serde(default)]
+    pub retry: WebhookRetry,
+}
+
+impl WebhookConfig {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            secret: None,
+            events: Vec::new(),
+            retry: WebhookRetry::default(),
+        }
+    }
+
+    pub fn with_secret(mut self, secret: impl Into<String>) -> Self {
+        self.secret = Some(secret.into());
+        self
+    }
+
+    pub fn with_events(mut self, events: impl IntoIterator<Item = WebhookEvent>) -> Self {
+        self.events = events.into_iter().collect();
+        self
+    }
+
+    pub fn with_retry(mut self, retry: WebhookRetry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    fn subscribes_to(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+/// Body POSTed to webhooks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    pub delivery_id: String,
+    pub event: WebhookEvent,
+    pub occurred_at: chrono::DateTime<chrono::Utc>,
+    /// The `TaskResult` or `WorkflowExecution` the event refers to
+    pub data: serde_json::Value,
+    /// Labels of the task the event is about; empty for workflow events
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
+}
+
+/// Compute the `X-Signature` value for `body`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Check an `X-Signature` value against `body` in constant time
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(digest) = signature.strip_prefix("sha256=").and_then(|hex_digest| hex::decode(hex_digest).ok()) else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    mac.verify_slice(&digest).is_ok()
+}
+
+/// Registered webhooks and their delivery
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    webhooks: Arc<RwLock<Vec<(String, WebhookConfig)>>>,
+    /// Every dispatched event, for in-process subscribers such as the events stream
+    events: broadcast::Sender<WebhookPayload>,
+    http_client: reqwest::Client,
+}
+
+impl WebhookDispatcher {
+    pub fn new(http_client: reqwest::Client) -> Self {
+        Self {
+            webhooks: Arc::new(RwLock::new(Vec::new())),
+            events: broadcast::channel(EVENT_BUFFER).0,
+            http_client,
+        }
+    }
+
+    /// Receive every event dispatched from now on, whether or not a webhook subscribes to it
+    pub fn subscribe(&self) -> broadcast::Receiver<WebhookPayload> {
+        self.events.subscribe()
+    }
+
+    /// Register a webhook, returning its id
+    pub async fn register(&self, config: WebhookConfig) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        info!("🪝 Registered webhook {} -> {}", id, config.url);
+        self.webhooks.write().await.push((id.clone(), config));
+        id
+    }
+
+    /// Remove a webhook; returns false if it was not registered
+    pub async fn unregister(&self, id: &str) -> bool {
+        let mut webhooks = self.webhooks.write().await;
+        let before = webhooks.len();
+        webhooks.retain(|(webhook_id, _)| webhook_id != id);
+        webhooks.len() != before
+    }
+
+    /// Queue delivery of `event` to every subscribed webhook without waiting for it
+    pub async fn dispatch(&self, event: WebhookEvent, data: serde_json::Value, labels: &HashMap<String, String>) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.events.send(WebhookPayload {
+            delivery_id: uuid::Uuid::new_v4().to_string(),
+            event,
+            occurred_at: chrono::Utc::now(),
+            data: data.clone(),
+            labels: labels.clone(),
+        });
+
+        let webhooks = self.webhooks.read().await;
+        for (id, config) in webhooks.iter().filter(|(_, config)| config.subscribes_to(event)) {
+            let payload = WebhookPayload {
+                delivery_id: uuid::Uuid::new_v4().to_string(),
+                event,
+                occurred_at: chrono::Utc::now(),
+                data: data.clone(),
+                labels: labels.clone(),
+            };
+            tokio::spawn(deliver(self.http_client.clone(), id.clone(), config.clone(), payload));
+        }
+    }
+}
+
+/// POST a payload, retrying with exponential backoff until it succeeds or attempts run out
+async fn deliver(client: reqwest::Client, webhook_id: String, config: WebhookConfig, payload: WebhookPayload) {
+    let body = match serde_json::to_vec(&payload) {
+        Ok(body) => body,
+        Err(e) => {
+            error!("🪝 Failed to serialize webhook payload {}: {}", payload.delivery_id, e);
+            return;
+        }
+    };
+    let signature = config.secret.as_deref().map(|secret| sign(secret, &body));
+    let max_attempts = config.retry.max_attempts.max(1);
+
+    for attempt in 1..=max_attempts {
+        let mut request = client
+            .post(&config.url)
+            .header("Content-Type", "application/json")
+            .header("X-Webhook-Delivery", &payload.delivery_id)
+            .body(body.clone());
+        if let Some(signature) = &signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+
+        let failure = match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                info!("🪝 Delivered {:?} {} to webhook {}", payload.event, payload.delivery_id, webhook_id);
+                return;
+            }
+            Ok(response) => format!("HTTP {}", response.status()),
+            Err(e) => e.to_string(),
+        };
+
+        if attempt == max_attempts {
+            error!(
+                "🪝 Dropped {:?} delivery {} to webhook {} after {} attempt(s): {}",
+                payload.event, payload.delivery_id, webhook_id, attempt, failure
+            );
+            return;
+        }
+
+        let backoff = config.retry.initial_backoff_ms.saturating_mul(1 << (attempt - 1).min(16));
+        warn!(
+            "🪝 Webhook {} delivery failed ({}), retrying in {}ms (attempt {}/{})",
+            webhook_id, failure, backoff, attempt + 1, max_attempts
+        );
+        tokio::time::sleep(Duration::from_millis(backoff)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_roundtrip() {
+        let signature = sign("s3cret", b"{\"ok\":true}");
+        assert!(signature.starts_with("sha256="));
+        assert!(verify_signature("s3cret", b"{\"ok\":true}", &signature));
+        assert!(!verify_signature("s3cret", b"{\"ok\":false}", &signature));
+        assert!(!verify_signature("other", b"{\"ok\":true}", &signature));
+        assert!(!verify_signature("s3cret", b"{\"ok\":true}", "sha256=zz"));
+    }
+}
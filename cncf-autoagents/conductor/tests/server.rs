@@ -0,0 +1,103 @@
+//! The `conductor-server` router driven over HTTP in-process
+
+#![cfg(feature = "server")]
+
+use std::time::Duration;
+
+use conductor::server::{self, ServerConfig, ServerState, TaskStatus};
+use conductor::Conductor;
+
+async fn start_server() -> (String, tokio::sync::oneshot::Sender<()>, tokio::task::JoinHandle<bool>) {
+    let config = ServerConfig::default();
+    let state = ServerState::new(config.build_conductor().await);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+    let shutdown = async move {
+        let _ = stop_rx.await;
+    };
+    let server = tokio::spawn(async move {
+        server::serve(listener, state, shutdown, Duration::from_secs(5)).await.unwrap()
+    });
+    (base, stop_tx, server)
+}
+
+async fn wait_for_result(client: &reqwest::Client, base: &str, task_id: &str) -> TaskStatus {
+    for _ in 0..100 {
+        let status: TaskStatus = client
+            .get(format!("{}/api/v1/tasks/{}", base, task_id))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        if !matches!(status, TaskStatus::Pending) {
+            return status;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("task {} did not finish", task_id);
+}
+
+#[tokio::test]
+async fn test_submit_task_and_fetch_result() {
+    let (base, stop, server) = start_server().await;
+    let client = reqwest::Client::new();
+
+    let health = client.get(format!("{}/health", base)).send().await.unwrap();
+    assert_eq!(health.status(), reqwest::StatusCode::OK);
+
+    let response = client
+        .post(format!("{}/api/v1/tasks", base))
+        .json(&serde_json::json!({
+            "id": "http-task",
+            "module_id": "test-module",
+            "input": {"command": "test"},
+            "labels": {"team": "payments"}
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::ACCEPTED);
+
+    match wait_for_result(&client, &base, "http-task").await {
+        TaskStatus::Completed { result } => {
+            assert!(result.success);
+            assert_eq!(result.task_id, "http-task");
+        }
+        other => panic!("unexpected status {:?}", other),
+    }
+
+    let metrics = client.get(format!("{}/metrics", base)).send().await.unwrap().text().await.unwrap();
+    assert!(metrics.contains("conductor_tasks_submitted_total"));
+    let unknown = client.get(format!("{}/api/v1/tasks/missing", base)).send().await.unwrap();
+    assert_eq!(unknown.status(), reqwest::StatusCode::NOT_FOUND);
+
+    // Shutdown drains and the server returns
+    stop.send(()).unwrap();
+    assert!(server.await.unwrap());
+}
+
+#[tokio::test]
+async fn test_drain_refuses_new_work() {
+    let conductor = Conductor::new("http://localhost:8080".to_string(), "http://localhost:8081".to_string());
+    let state = ServerState::new(conductor);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let app_state = state.clone();
+    tokio::spawn(async move { axum::serve(listener, server::router(app_state)).await.unwrap() });
+
+    state.begin_drain();
+    let client = reqwest::Client::new();
+    let health = client.get(format!("{}/health", base)).send().await.unwrap();
+    assert_eq!(health.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    let response = client
+        .post(format!("{}/api/v1/tasks", base))
+        .json(&serde_json::json!({"module_id": "test-module", "input": {}}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    assert!(state.drain(Duration::from_millis(100)).await);
+}
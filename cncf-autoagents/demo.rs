@@ -39,6 +39,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         max_memory_mb: 64,
         max_execution_time_ms: 3000,
         checksum: "demo-checksum".to_string(),
+        maintenance_hooks: vec![],
+        sandbox_profile: None,
+        rate_limit: None,
     };
 
     forge.load_module(demo_module).await?;
@@ -64,6 +67,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         priority: conductor::TaskPriority::High,
         timeout_ms: Some(5000),
         created_at: chrono::Utc::now(),
+        labels: std::collections::HashMap::new(),
+        trace_context: None,
+        change_detection: None,
     };
 
     let success_result = conductor.execute_task(success_task).await?;
@@ -87,6 +93,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         priority: conductor::TaskPriority::Critical,
         timeout_ms: Some(5000),
         created_at: chrono::Utc::now(),
+        labels: std::collections::HashMap::new(),
+        trace_context: None,
+        change_detection: None,
     };
 
     let security_result = conductor.execute_task(malicious_task).await?;
@@ -109,6 +118,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         priority: conductor::TaskPriority::Normal,
         timeout_ms: Some(1000), // Short timeout
         created_at: chrono::Utc::now(),
+        labels: std::collections::HashMap::new(),
+        trace_context: None,
+        change_detection: None,
     };
 
     let timeout_result = conductor.execute_task(timeout_task).await?;
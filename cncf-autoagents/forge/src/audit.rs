@@ -0,0 +1,282 @@
+//! Execution Audit Log
+//!
+//! Append-only record of every execution attempt, module validation and
+//! security policy change. Entries are handed to a writer thread over a
+//! bounded channel so recording never blocks execution; when the channel is
+//! full the entry is dropped and counted instead.
+
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tracing::warn;
+
+use crate::{ExecutionKind, ExecutionResult, HttpExchange, SecurityPolicy, WasmModule};
+
+/// Entries that may wait for the writer unless configured otherwise
+pub const DEFAULT_AUDIT_BUFFER: usize = 1024;
+
+/// Admission check: a free execution slot
+pub const CHECK_EXECUTION_SLOT: &str = "execution_slot";
+/// Admission check: input passed the policy's validation rules
+pub const CHECK_INPUT_VALIDATION: &str = "input_validation";
+/// Admission check: the module's rate limit
+pub const CHECK_RATE_LIMIT: &str = "rate_limit";
+
+/// Outcome of one policy check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyDecision {
+    pub check: String,
+    pub allowed: bool,
+    pub reason: Option<String>,
+}
+
+/// Something that happened to Forge's security state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// An execution was attempted, whether or not it ran
+    ExecutionAttempt {
+        module_id: String,
+        module_version: String,
+        /// Task the caller executed the module for
+        task_id: Option<String>,
+        /// Absent when the attempt was rejected before it was assigned one
+        execution_id: Option<String>,
+        kind: ExecutionKind,
+        decisions: Vec<PolicyDecision>,
+        violations: Vec<String>,
+        success: bool,
+        duration_ms: u64,
+        error: Option<String>,
+        /// Outbound HTTP requests the execution made
+        #[serde(default)]
+        http_requests: Vec<HttpExchange>,
+    },
+    /// A module was checked against the security policy on load
+    ModuleValidation {
+        module_id: String,
+        module_version: String,
+        allowed: bool,
+        reason: Option<String>,
+    },
+    /// The security policy was replaced
+    PolicyUpdated {
+        previous_snapshot_id: String,
+        snapshot_id: String,
+        policy: Box<SecurityPolicy>,
+    },
+}
+
+impl AuditEvent {
+    /// Module the event concerns; `None` for policy changes
+    pub fn module_id(&self) -> Option<&str> {
+        match self {
+            AuditEvent::ExecutionAttempt { module_id, .. } | AuditEvent::ModuleValidation { module_id, .. } => Some(module_id),
+            AuditEvent::PolicyUpdated { .. } => None,
+        }
+    }
+
+    pub(crate) fn module_validation(module: &WasmModule, outcome: &Result<(), Box<dyn std::error::Error>>) -> Self {
+        AuditEvent::ModuleValidation {
+            module_id: module.id.clone(),
+            module_version: module.version.clone(),
+            allowed: outcome.is_ok(),
+            reason: outcome.as_ref().err().map(|e| e.to_string()),
+        }
+    }
+}
+
+/// A recorded event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the log, starting at 0
+    pub sequence: u64,
+    pub recorded_at: chrono::DateTime<chrono::Utc>,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+/// Selects audit entries; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub module_id: Option<String>,
+    /// Inclusive lower bound on `recorded_at`
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    /// Exclusive upper bound on `recorded_at`
+    pub until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.module_id.as_deref().is_none_or(|id| entry.event.module_id() == Some(id))
+            && self.since.is_none_or(|since| entry.recorded_at >= since)
+            && self.until.is_none_or(|until| entry.recorded_at < until)
+    }
+}
+
+#[derive(Default)]
+struct AuditStore {
+    entries: Vec<AuditEntry>,
+}
+
+/// Append-only audit log fed through a bounded channel
+pub struct AuditLog {
+    sender: mpsc::SyncSender<(chrono::DateTime<chrono::Utc>, AuditEvent)>,
+    store: Arc<Mutex<AuditStore>>,
+    written: Arc<Notify>,
+    sent: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl AuditLog {
+    /// Start a log whose writer accepts up to `buffer` pending entries
+    pub fn new(buffer: usize) -> Self {
+        let (sender, receiver) = mpsc::sync_channel::<(chrono::DateTime<chrono::Utc>, AuditEvent)>(buffer.max(1));
+        let store = Arc::new(Mutex::new(AuditStore::default()));
+        let written = Arc::new(Notify::new());
+
+        // Exits once the log, and with it the sender, is dropped
+        let writer_store = store.clone();
+        let writer_written = written.clone();
+        std::thread::Builder::new()
+            .name("forge-audit".to_string())
+            .spawn(move || {
+                for (recorded_at, event) in receiver {
+                    let mut store = writer_store.lock().unwrap();
+                    let sequence = store.entries.len() as u64;
+                    store.entries.push(AuditEntry { sequence, recorded_at, event });
+                    drop(store);
+                    writer_written.notify_waiters();
+                }
+            })
+            .expect("Failed to spawn audit writer");
+
+        Self {
+            sender,
+            store,
+            written,
+            sent: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Queue an event for the writer without waiting
+    pub(crate) fn record(&self, event: AuditEvent) {
+        match self.sender.try_send((chrono::Utc::now(), event)) {
+            Ok(()) => {
+                self.sent.fetch_add(1, Ordering::SeqCst);
+            }
+            Err(_) => {
+                let dropped = self.dropped.fetch_add(1, Ordering::SeqCst) + 1;
+                warn!("📜 Audit buffer full, dropped entry ({} so far)", dropped);
+            }
+        }
+    }
+
+    /// Entries lost because the writer fell behind
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::SeqCst)
+    }
+
+    /// Wait until every entry recorded so far is written
+    async fn flush(&self) {
+        let target = self.sent.load(Ordering::SeqCst);
+        loop {
+            let written = self.written.notified();
+            if self.store.lock().unwrap().entries.len() as u64 >= target {
+                return;
+            }
+            written.await;
+        }
+    }
+
+    /// Entries matching `filter`, oldest first
+    pub async fn query(&self, filter: &AuditFilter) -> Vec<AuditEntry> {
+        self.flush().await;
+        self.store.lock().unwrap().entries.iter()
+            .filter(|entry| filter.matches(entry))
+            .cloned()
+            .collect()
+    }
+
+    /// Write entries matching `filter` as JSON Lines; returns how many were written
+    pub async fn export_json_lines<W: Write>(&self, filter: &AuditFilter, mut writer: W) -> std::io::Result<usize> {
+        let entries = self.query(filter).await;
+        for entry in &entries {
+            serde_json::to_writer(&mut writer, entry)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        Ok(entries.len())
+    }
+}
+
+/// Execution attempt being audited, collecting policy decisions as it is admitted
+pub(crate) struct Attempt {
+    module_id: String,
+    module_version: String,
+    task_id: Option<String>,
+    kind: ExecutionKind,
+    decisions: Vec<PolicyDecision>,
+    started: Instant,
+}
+
+impl Attempt {
+    pub fn new(module: &WasmModule, kind: ExecutionKind, task_id: Option<String>) -> Self {
+        Self {
+            module_id: module.id.clone(),
+            module_version: module.version.clone(),
+            task_id,
+            kind,
+            decisions: Vec::new(),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn allow(&mut self, check: &str) {
+        self.decisions.push(PolicyDecision { check: check.to_string(), allowed: true, reason: None });
+    }
+
+    pub fn deny(&mut self, check: &str, reason: impl ToString) {
+        self.decisions.push(PolicyDecision { check: check.to_string(), allowed: false, reason: Some(reason.to_string()) });
+    }
+
+    /// The attempt ended in an error instead of a result
+    pub fn failed(self, execution_id: Option<&str>, error: impl ToString) -> AuditEvent {
+        let duration_ms = self.started.elapsed().as_millis() as u64;
+        AuditEvent::ExecutionAttempt {
+            module_id: self.module_id,
+            module_version: self.module_version,
+            task_id: self.task_id,
+            execution_id: execution_id.map(str::to_string),
+            kind: self.kind,
+            decisions: self.decisions,
+            violations: Vec::new(),
+            success: false,
+            duration_ms,
+            error: Some(error.to_string()),
+            http_requests: Vec::new(),
+        }
+    }
+
+    /// The attempt produced a result
+    pub fn completed(self, result: &ExecutionResult) -> AuditEvent {
+        AuditEvent::ExecutionAttempt {
+            module_id: self.module_id,
+            module_version: self.module_version,
+            task_id: self.task_id,
+            execution_id: Some(result.execution_id.clone()),
+            kind: self.kind,
+            decisions: self.decisions,
+            violations: result.security_violations.clone(),
+            success: result.success,
+            duration_ms: result.execution_time_ms,
+            error: None,
+            http_requests: result.resources.http_requests.clone(),
+        }
+    }
+}
@@ -0,0 +1,467 @@
+//! HTTP Egress
+//!
+//! Outbound HTTP for modules granted the `http` capability under a sandbox
+//! profile allowing `WasiInterface::Http`. Each request is checked against
+//! the policy's allow-list of host, port and path prefix; plaintext HTTP is
+//! refused unless enabled, and every execution has request and byte quotas.
+//! Refused requests come back to the module as structured errors instead of
+//! trapping, and every request is summarised in the execution's resource
+//! report. Redirects are not followed, so a response cannot lead a module
+//! off the allow-list.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Module capability required for outbound HTTP
+pub const HTTP_CAPABILITY: &str = "http";
+
+/// Outbound HTTP settings of a security policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EgressPolicy {
+    /// Destinations modules may call; nothing is reachable when empty
+    #[serde(default)]
+    pub allowed_destinations: Vec<EgressDestination>,
+    /// Permit `http://` URLs; only HTTPS is allowed otherwise
+    #[serde(default)]
+    pub allow_plaintext: bool,
+    /// Largest response body accepted
+    #[serde(default = "default_max_response_bytes")]
+    pub max_response_bytes: usize,
+    #[serde(default = "default_max_requests_per_execution")]
+    pub max_requests_per_execution: u32,
+    /// Request and response body bytes allowed per execution
+    #[serde(default = "default_max_bytes_per_execution")]
+    pub max_bytes_per_execution: u64,
+    #[serde(default = "default_request_timeout_ms")]
+    pub request_timeout_ms: u64,
+}
+
+fn default_max_response_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_max_requests_per_execution() -> u32 {
+    16
+}
+
+fn default_max_bytes_per_execution() -> u64 {
+    4 * 1024 * 1024
+}
+
+fn default_request_timeout_ms() -> u64 {
+    5000
+}
+
+impl Default for EgressPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_destinations: Vec::new(),
+            allow_plaintext: false,
+            max_response_bytes: default_max_response_bytes(),
+            max_requests_per_execution: default_max_requests_per_execution(),
+            max_bytes_per_execution: default_max_bytes_per_execution(),
+            request_timeout_ms: default_request_timeout_ms(),
+        }
+    }
+}
+
+/// An allowed destination
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EgressDestination {
+    pub host: String,
+    /// The scheme's default port when unset
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default = "default_path_prefix")]
+    pub path_prefix: String,
+}
+
+fn default_path_prefix() -> String {
+    "/".to_string()
+}
+
+impl EgressDestination {
+    pub fn new(host: impl Into<String>, port: Option<u16>) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            path_prefix: default_path_prefix(),
+        }
+    }
+
+    pub fn with_path_prefix(mut self, path_prefix: impl Into<String>) -> Self {
+        self.path_prefix = path_prefix.into();
+        self
+    }
+
+    fn allows(&self, url: &reqwest::Url) -> bool {
+        url.host_str().is_some_and(|host| host.eq_ignore_ascii_case(&self.host))
+            && url.port_or_known_default() == self.port.or_else(|| default_port(url.scheme()))
+            && url.path().starts_with(&self.path_prefix)
+    }
+}
+
+fn default_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "https" => Some(443),
+        "http" => Some(80),
+        _ => None,
+    }
+}
+
+/// Request a module makes through `host_http_request`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpRequest {
+    #[serde(default = "default_method")]
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: String,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+/// Response handed back to the module
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+}
+
+/// Why a request was refused or failed
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum EgressDenied {
+    #[error("module and sandbox profile do not both grant '{HTTP_CAPABILITY}'")]
+    CapabilityNotGranted,
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("plaintext HTTP is not allowed: {0}")]
+    PlaintextRefused(String),
+    #[error("destination {0} is not on the allow-list")]
+    DestinationDenied(String),
+    #[error("request quota of {0} per execution exhausted")]
+    RequestQuotaExhausted(u32),
+    #[error("byte quota of {0} per execution exhausted")]
+    ByteQuotaExhausted(u64),
+    #[error("response exceeds {0} bytes")]
+    ResponseTooLarge(usize),
+    #[error("request failed: {0}")]
+    Failed(String),
+}
+
+impl EgressDenied {
+    /// Stable code the module can match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            EgressDenied::CapabilityNotGranted => "capability_not_granted",
+            EgressDenied::InvalidRequest(_) => "invalid_request",
+            EgressDenied::PlaintextRefused(_) => "plaintext_refused",
+            EgressDenied::DestinationDenied(_) => "destination_denied",
+            EgressDenied::RequestQuotaExhausted(_) => "request_quota_exhausted",
+            EgressDenied::ByteQuotaExhausted(_) => "byte_quota_exhausted",
+            EgressDenied::ResponseTooLarge(_) => "response_too_large",
+            EgressDenied::Failed(_) => "request_failed",
+        }
+    }
+}
+
+/// Summary of one request, recorded in the audit log and resource report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpExchange {
+    pub method: String,
+    /// Scheme, host, port and path; the query string is left out
+    pub destination: String,
+    pub status: Option<u16>,
+    pub request_bytes: u64,
+    pub response_bytes: u64,
+    pub duration_ms: u64,
+    /// Code of the refusal or failure, if any
+    pub error: Option<String>,
+}
+
+/// Resources an execution used beyond memory and time
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceReport {
+    pub http_requests: Vec<HttpExchange>,
+    /// Body bytes counted against the byte quota
+    pub http_bytes: u64,
+}
+
+/// Outbound HTTP state of one execution
+pub struct HttpEgress {
+    policy: EgressPolicy,
+    granted: bool,
+    client: Option<reqwest::Client>,
+    sent: u32,
+    report: ResourceReport,
+}
+
+impl HttpEgress {
+    /// `granted` when both the module and its sandbox profile allow HTTP
+    pub fn new(policy: &EgressPolicy, granted: bool) -> Self {
+        Self {
+            policy: policy.clone(),
+            granted,
+            client: None,
+            sent: 0,
+            report: ResourceReport::default(),
+        }
+    }
+
+    /// Handle a raw request from a module: JSON in, the response or
+    /// `{"error": {"code", "message"}}` out
+    pub async fn handle(&mut self, request: &[u8]) -> serde_json::Value {
+        let outcome = match serde_json::from_slice::<HttpRequest>(request) {
+            Ok(request) => self.request(request).await,
+            Err(e) => Err(EgressDenied::InvalidRequest(e.to_string())),
+        };
+        match outcome {
+            Ok(response) => serde_json::to_value(response).unwrap_or_default(),
+            Err(denied) => serde_json::json!({"error": {"code": denied.code(), "message": denied.to_string()}}),
+        }
+    }
+
+    /// Send a request if the policy allows it
+    pub async fn request(&mut self, request: HttpRequest) -> Result<HttpResponse, EgressDenied> {
+        let started = Instant::now();
+        let mut exchange = HttpExchange {
+            method: request.method.to_uppercase(),
+            destination: String::new(),
+            status: None,
+            request_bytes: request.body.len() as u64,
+            response_bytes: 0,
+            duration_ms: 0,
+            error: None,
+        };
+
+        let outcome = self.send(request, &mut exchange).await;
+        exchange.duration_ms = started.elapsed().as_millis() as u64;
+        if let Err(denied) = &outcome {
+            warn!("🚫 Refused HTTP {} {}: {}", exchange.method, exchange.destination, denied);
+            exchange.error = Some(denied.code().to_string());
+        }
+        self.report.http_requests.push(exchange);
+        outcome
+    }
+
+    /// Requests and bytes used so far
+    pub fn into_report(self) -> ResourceReport {
+        self.report
+    }
+
+    async fn send(&mut self, request: HttpRequest, exchange: &mut HttpExchange) -> Result<HttpResponse, EgressDenied> {
+        let url = reqwest::Url::parse(&request.url).map_err(|e| EgressDenied::InvalidRequest(e.to_string()))?;
+        exchange.destination = format!(
+            "{}://{}:{}{}",
+            url.scheme(),
+            url.host_str().unwrap_or_default(),
+            url.port_or_known_default().unwrap_or_default(),
+            url.path()
+        );
+
+        if !self.granted {
+            return Err(EgressDenied::CapabilityNotGranted);
+        }
+        match url.scheme() {
+            "https" => {}
+            "http" if self.policy.allow_plaintext => {}
+            "http" => return Err(EgressDenied::PlaintextRefused(exchange.destination.clone())),
+            scheme => return Err(EgressDenied::InvalidRequest(format!("unsupported scheme '{}'", scheme))),
+        }
+        if !self.policy.allowed_destinations.iter().any(|destination| destination.allows(&url)) {
+            return Err(EgressDenied::DestinationDenied(exchange.destination.clone()));
+        }
+        if self.sent >= self.policy.max_requests_per_execution {
+            return Err(EgressDenied::RequestQuotaExhausted(self.policy.max_requests_per_execution));
+        }
+        if self.report.http_bytes >= self.policy.max_bytes_per_execution
+            || self.report.http_bytes + exchange.request_bytes > self.policy.max_bytes_per_execution
+        {
+            return Err(EgressDenied::ByteQuotaExhausted(self.policy.max_bytes_per_execution));
+        }
+
+        let method = reqwest::Method::from_bytes(exchange.method.as_bytes()).map_err(|e| EgressDenied::InvalidRequest(e.to_string()))?;
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in &request.headers {
+            let name = reqwest::header::HeaderName::try_from(name.as_str()).map_err(|e| EgressDenied::InvalidRequest(e.to_string()))?;
+            // The allow-list is checked against the URL host, so it must be the host contacted
+            if name == reqwest::header::HOST {
+                return Err(EgressDenied::InvalidRequest("the Host header cannot be set".to_string()));
+            }
+            let value = reqwest::header::HeaderValue::try_from(value.as_str()).map_err(|e| EgressDenied::InvalidRequest(e.to_string()))?;
+            headers.insert(name, value);
+        }
+
+        let client = self.client()?;
+        self.sent += 1;
+        self.report.http_bytes += exchange.request_bytes;
+        let mut response = client
+            .request(method, url)
+            .headers(headers)
+            .body(request.body)
+            .send()
+            .await
+            .map_err(|e| EgressDenied::Failed(e.to_string()))?;
+        exchange.status = Some(response.status().as_u16());
+
+        let quota_left = self.policy.max_bytes_per_execution - self.report.http_bytes;
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|e| EgressDenied::Failed(e.to_string()))? {
+            body.extend_from_slice(&chunk);
+            exchange.response_bytes = body.len() as u64;
+            if body.len() > self.policy.max_response_bytes {
+                self.report.http_bytes += exchange.response_bytes.min(quota_left);
+                return Err(EgressDenied::ResponseTooLarge(self.policy.max_response_bytes));
+            }
+            if exchange.response_bytes > quota_left {
+                self.report.http_bytes = self.policy.max_bytes_per_execution;
+                return Err(EgressDenied::ByteQuotaExhausted(self.policy.max_bytes_per_execution));
+            }
+        }
+        self.report.http_bytes += exchange.response_bytes;
+
+        Ok(HttpResponse {
+            status: response.status().as_u16(),
+            headers: response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+                .collect(),
+            body: String::from_utf8_lossy(&body).into_owned(),
+        })
+    }
+
+    fn client(&mut self) -> Result<reqwest::Client, EgressDenied> {
+        if let Some(client) = &self.client {
+            return Ok(client.clone());
+        }
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(Duration::from_millis(self.policy.request_timeout_ms))
+            .build()
+            .map_err(|e| EgressDenied::Failed(e.to_string()))?;
+        self.client = Some(client.clone());
+        Ok(client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Answer every request on a local port with `body`
+    async fn mock_server(body: &'static str) -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        port
+    }
+
+    fn policy(port: u16) -> EgressPolicy {
+        EgressPolicy {
+            allowed_destinations: vec![EgressDestination::new("127.0.0.1", Some(port)).with_path_prefix("/api/")],
+            allow_plaintext: true,
+            ..Default::default()
+        }
+    }
+
+    fn get(url: String) -> HttpRequest {
+        HttpRequest {
+            method: "GET".to_string(),
+            url,
+            headers: BTreeMap::new(),
+            body: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_allowed_request() {
+        let port = mock_server("{\"ok\":true}").await;
+        let mut egress = HttpEgress::new(&policy(port), true);
+
+        let response = egress.handle(format!(r#"{{"url": "http://127.0.0.1:{}/api/items?page=2"}}"#, port).as_bytes()).await;
+        assert_eq!(response["status"], 200);
+        assert_eq!(response["body"], "{\"ok\":true}");
+
+        let report = egress.into_report();
+        assert_eq!(report.http_bytes, 11);
+        assert_eq!(report.http_requests, vec![HttpExchange {
+            method: "GET".to_string(),
+            destination: format!("http://127.0.0.1:{}/api/items", port),
+            status: Some(200),
+            request_bytes: 0,
+            response_bytes: 11,
+            duration_ms: report.http_requests[0].duration_ms,
+            error: None,
+        }]);
+    }
+
+    #[tokio::test]
+    async fn test_blocked_destinations() {
+        let port = mock_server("secret").await;
+        let mut egress = HttpEgress::new(&policy(port), true);
+
+        for url in [
+            format!("http://localhost:{}/api/items", port),
+            format!("http://127.0.0.1:{}/admin", port),
+            format!("http://127.0.0.1:{}/api/items", port + 1),
+        ] {
+            assert!(matches!(egress.request(get(url)).await, Err(EgressDenied::DestinationDenied(_))));
+        }
+
+        let strict = EgressPolicy { allow_plaintext: false, ..policy(port) };
+        let mut egress = HttpEgress::new(&strict, true);
+        let response = egress.handle(format!(r#"{{"url": "http://127.0.0.1:{}/api/items"}}"#, port).as_bytes()).await;
+        assert_eq!(response["error"]["code"], "plaintext_refused");
+
+        let mut egress = HttpEgress::new(&policy(port), false);
+        let denied = egress.request(get(format!("http://127.0.0.1:{}/api/items", port))).await;
+        assert_eq!(denied.unwrap_err(), EgressDenied::CapabilityNotGranted);
+
+        let report = egress.into_report();
+        assert_eq!(report.http_bytes, 0);
+        assert_eq!(report.http_requests[0].error.as_deref(), Some("capability_not_granted"));
+    }
+
+    #[tokio::test]
+    async fn test_quotas_exhausted() {
+        let port = mock_server("0123456789").await;
+        let url = format!("http://127.0.0.1:{}/api/items", port);
+
+        let mut egress = HttpEgress::new(&EgressPolicy { max_requests_per_execution: 2, ..policy(port) }, true);
+        egress.request(get(url.clone())).await.unwrap();
+        egress.request(get(url.clone())).await.unwrap();
+        assert_eq!(egress.request(get(url.clone())).await.unwrap_err(), EgressDenied::RequestQuotaExhausted(2));
+
+        let mut egress = HttpEgress::new(&EgressPolicy { max_bytes_per_execution: 15, ..policy(port) }, true);
+        egress.request(get(url.clone())).await.unwrap();
+        assert_eq!(egress.request(get(url.clone())).await.unwrap_err(), EgressDenied::ByteQuotaExhausted(15));
+        assert_eq!(egress.request(get(url.clone())).await.unwrap_err(), EgressDenied::ByteQuotaExhausted(15));
+        assert_eq!(egress.into_report().http_bytes, 15);
+
+        let mut egress = HttpEgress::new(&EgressPolicy { max_response_bytes: 4, ..policy(port) }, true);
+        assert_eq!(egress.request(get(url)).await.unwrap_err(), EgressDenied::ResponseTooLarge(4));
+    }
+}
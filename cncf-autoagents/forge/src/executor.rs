@@ -0,0 +1,531 @@
+//! Wasmtime Executor
+//!
+//! Runs real WASM modules when Forge is built with the `wasmtime` feature.
+//! Modules follow a small calling convention:
+//!
+//! - export `memory`
+//! - export `alloc(len: i32) -> i32`, returning a buffer for the JSON input
+//! - export `run(ptr: i32, len: i32) -> i32`, returning a pointer to the
+//!   output: a little-endian `u32` length followed by that many bytes of JSON
+//!
+//! Each execution gets a fresh `Store` with fuel metering, an epoch deadline
+//! for the time limit and a memory limiter. Traps are reported with the same
+//! `security_violations` vocabulary as the simulated executor. WASI preview 1
+//! imports are linked according to the module's sandbox profile.
+//!
+//! Modules allowed outbound HTTP may import `forge::host_http_request(ptr: i32,
+//! len: i32) -> i32`, passing an `egress::HttpRequest` as JSON. It returns a
+//! pointer, allocated through `alloc`, to the length-prefixed JSON response or
+//! structured error, or 0 if the response could not be written.
+//!
+//! Any module may import `forge::heartbeat(ptr: i32, len: i32) -> i32` to
+//! report liveness, with `len` bytes of JSON progress at `ptr` (or `len` 0 for
+//! none). It returns 0 once recorded, or a WASI errno: `EINVAL` for progress
+//! Forge refused and `ENOSYS` when nothing receives heartbeats.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use wasmtime::{Caller, Config, Engine, InstancePre, Linker, Memory, Module, ResourceLimiter, Store, Trap};
+
+use crate::egress::{HttpEgress, ResourceReport};
+use crate::wasi::{WasiContext, FORGE_HOST, WASI_PREVIEW1};
+
+/// How often the engine epoch advances; time limits are rounded up to it
+pub const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Executor errors that are not the module misbehaving at runtime
+#[derive(Debug, thiserror::Error)]
+pub enum ExecutorError {
+    #[error("module {0} failed to compile: {1}")]
+    Compile(String, String),
+    #[error("module {module} does not export `{export}` (see the Forge module ABI)")]
+    MissingExport { module: String, export: &'static str },
+    #[error("module {0} is not compiled")]
+    NotCompiled(String),
+    #[error("module {0} failed to instantiate: {1}")]
+    Instantiate(String, String),
+}
+
+/// Receives the heartbeats of one execution, with their progress payload
+pub type HeartbeatSink = Arc<dyn Fn(Option<serde_json::Value>) -> Result<(), String> + Send + Sync>;
+
+/// Resource limits for one execution
+#[derive(Debug, Clone, Copy)]
+pub struct ExecutionLimits {
+    pub memory_bytes: usize,
+    pub fuel: u64,
+    pub time_limit: Duration,
+}
+
+/// What an execution produced
+#[derive(Debug)]
+pub struct WasmOutcome {
+    pub success: bool,
+    pub output: serde_json::Value,
+    pub peak_memory_bytes: usize,
+    pub security_violations: Vec<String>,
+    pub wasi: WasiContext,
+    pub resources: ResourceReport,
+}
+
+/// A compiled module linked against the WASI functions, ready to instantiate
+#[derive(Clone)]
+pub struct PreparedModule {
+    key: String,
+    instance_pre: InstancePre<HostState>,
+}
+
+/// Compiles modules once and runs each execution in its own store
+pub struct WasmtimeExecutor {
+    engine: Engine,
+    modules: Mutex<HashMap<String, Module>>,
+}
+
+impl WasmtimeExecutor {
+    pub fn new() -> Self {
+        let mut config = Config::new();
+        config.consume_fuel(true).epoch_interruption(true);
+        let engine = Engine::new(&config).expect("fuel and epoch interruption are supported");
+
+        // Advance the epoch until the engine is dropped
+        let weak = engine.weak();
+        std::thread::spawn(move || {
+            while let Some(engine) = weak.upgrade() {
+                engine.increment_epoch();
+                drop(engine);
+                std::thread::sleep(EPOCH_TICK);
+            }
+        });
+
+        Self {
+            engine,
+            modules: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Compile `wasm` under `key` and check it exports the Forge ABI
+    pub fn compile(&self, key: &str, wasm: &[u8]) -> Result<(), ExecutorError> {
+        let module = Module::new(&self.engine, wasm).map_err(|e| ExecutorError::Compile(key.to_string(), e.to_string()))?;
+        for export in ["memory", "alloc", "run"] {
+            if module.get_export(export).is_none() {
+                return Err(ExecutorError::MissingExport {
+                    module: key.to_string(),
+                    export,
+                });
+            }
+        }
+        self.modules.lock().unwrap().insert(key.to_string(), module);
+        Ok(())
+    }
+
+    /// Whether a module is compiled under `key`
+    pub fn contains(&self, key: &str) -> bool {
+        self.modules.lock().unwrap().contains_key(key)
+    }
+
+    /// Drop a compiled module
+    pub fn evict(&self, key: &str) {
+        self.modules.lock().unwrap().remove(key);
+    }
+
+    /// Link a compiled module so executions only have to instantiate it
+    pub fn prepare(&self, key: &str) -> Result<PreparedModule, ExecutorError> {
+        let module = self
+            .modules
+            .lock()
+            .unwrap()
+            .get(key)
+            .cloned()
+            .ok_or_else(|| ExecutorError::NotCompiled(key.to_string()))?;
+
+        let mut linker = Linker::new(&self.engine);
+        link_wasi(&mut linker).expect("WASI functions are defined once");
+        link_forge_host(&mut linker).expect("Forge host functions are defined once");
+        let instance_pre = linker
+            .instantiate_pre(&module)
+            .map_err(|e| ExecutorError::Instantiate(key.to_string(), e.to_string()))?;
+        Ok(PreparedModule {
+            key: key.to_string(),
+            instance_pre,
+        })
+    }
+
+    /// Run a compiled module's `run` export on `input`; blocks until it finishes
+    pub fn execute(
+        &self,
+        key: &str,
+        input: &serde_json::Value,
+        limits: ExecutionLimits,
+        wasi: WasiContext,
+        egress: HttpEgress,
+        heartbeat: Option<HeartbeatSink>,
+    ) -> Result<WasmOutcome, ExecutorError> {
+        self.execute_prepared(&self.prepare(key)?, input, limits, wasi, egress, heartbeat)
+    }
+
+    /// Run a prepared module in a fresh instance; blocks until it finishes.
+    /// HTTP requests need a Tokio runtime, e.g. a `spawn_blocking` thread.
+    pub fn execute_prepared(
+        &self,
+        prepared: &PreparedModule,
+        input: &serde_json::Value,
+        limits: ExecutionLimits,
+        wasi: WasiContext,
+        egress: HttpEgress,
+        heartbeat: Option<HeartbeatSink>,
+    ) -> Result<WasmOutcome, ExecutorError> {
+        let mut store = Store::new(
+            &self.engine,
+            HostState {
+                limiter: MemoryLimiter::new(limits.memory_bytes),
+                wasi,
+                egress,
+                heartbeat,
+                runtime: tokio::runtime::Handle::try_current().ok(),
+                started: Instant::now(),
+            },
+        );
+        store.limiter(|state| &mut state.limiter);
+        store.set_fuel(limits.fuel).expect("fuel is enabled");
+        store.set_epoch_deadline(limits.time_limit.as_millis().div_ceil(EPOCH_TICK.as_millis()).max(1) as u64);
+
+        let result = prepared
+            .instance_pre
+            .instantiate(&mut store)
+            .map_err(Trapped::Instantiate)
+            .and_then(|instance| call_run(&mut store, &instance, input).map_err(Trapped::Run));
+
+        let state = store.into_data();
+        let peak_memory_bytes = state.limiter.peak;
+        let outcome = match result {
+            Ok(output) => WasmOutcome {
+                success: true,
+                output,
+                peak_memory_bytes,
+                security_violations: vec![],
+                wasi: state.wasi,
+                resources: state.egress.into_report(),
+            },
+            Err(Trapped::Instantiate(e)) if !state.limiter.exceeded => {
+                return Err(ExecutorError::Instantiate(prepared.key.clone(), e.to_string()));
+            }
+            Err(Trapped::Instantiate(e) | Trapped::Run(e)) => {
+                let (violation, message) = classify_error(&e, state.limiter.exceeded);
+                WasmOutcome {
+                    success: violation.is_none() && e.downcast_ref::<ProcExit>().is_some_and(|exit| exit.0 == 0),
+                    output: serde_json::json!({"error": message}),
+                    peak_memory_bytes,
+                    security_violations: violation.into_iter().map(str::to_string).collect(),
+                    wasi: state.wasi,
+                    resources: state.egress.into_report(),
+                }
+            }
+        };
+        Ok(outcome)
+    }
+}
+
+impl Default for WasmtimeExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+enum Trapped {
+    Instantiate(wasmtime::Error),
+    Run(wasmtime::Error),
+}
+
+/// Pass the JSON input through `alloc` and `run` and decode the output
+fn call_run(store: &mut Store<HostState>, instance: &wasmtime::Instance, input: &serde_json::Value) -> wasmtime::Result<serde_json::Value> {
+    let memory = instance
+        .get_memory(&mut *store, "memory")
+        .ok_or_else(|| anyhow::anyhow!("module does not export memory"))?;
+    let alloc = instance.get_typed_func::<i32, i32>(&mut *store, "alloc")?;
+    let run = instance.get_typed_func::<(i32, i32), i32>(&mut *store, "run")?;
+
+    let input = serde_json::to_vec(input)?;
+    let len = i32::try_from(input.len())?;
+    let ptr = alloc.call(&mut *store, len)?;
+    memory.write(&mut *store, ptr as u32 as usize, &input)?;
+
+    let output_ptr = run.call(&mut *store, (ptr, len))? as u32 as usize;
+    let mut len_bytes = [0u8; 4];
+    memory.read(&*store, output_ptr, &mut len_bytes)?;
+    let mut output = vec![0u8; u32::from_le_bytes(len_bytes) as usize];
+    memory.read(&*store, output_ptr + 4, &mut output)?;
+
+    serde_json::from_slice(&output).map_err(|e| anyhow::anyhow!("module output is not JSON: {}", e))
+}
+
+/// Map an execution error to a security violation and a message
+fn classify_error(error: &wasmtime::Error, memory_exceeded: bool) -> (Option<&'static str>, String) {
+    if memory_exceeded {
+        return (Some("memory_limit"), "Module exceeded its memory limit".to_string());
+    }
+    if let Some(exit) = error.downcast_ref::<ProcExit>() {
+        return (None, format!("Module exited with code {}", exit.0));
+    }
+    match error.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => (Some("fuel_exhausted"), "Module ran out of fuel".to_string()),
+        Some(Trap::Interrupt) => (Some("timeout"), "Module exceeded its time limit".to_string()),
+        Some(trap) => (Some("trap"), format!("Module trapped: {}", trap)),
+        None => (None, error.to_string()),
+    }
+}
+
+/// Tracks linear memory against the limit; growth past it fails inside the module
+struct MemoryLimiter {
+    limit: usize,
+    peak: usize,
+    exceeded: bool,
+}
+
+impl MemoryLimiter {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            peak: 0,
+            exceeded: false,
+        }
+    }
+}
+
+impl ResourceLimiter for MemoryLimiter {
+    fn memory_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> wasmtime::Result<bool> {
+        if desired > self.limit {
+            self.exceeded = true;
+            return Ok(false);
+        }
+        self.peak = self.peak.max(desired);
+        Ok(true)
+    }
+
+    fn table_growing(&mut self, _current: usize, _desired: usize, _maximum: Option<usize>) -> wasmtime::Result<bool> {
+        Ok(true)
+    }
+}
+
+struct HostState {
+    limiter: MemoryLimiter,
+    wasi: WasiContext,
+    egress: HttpEgress,
+    heartbeat: Option<HeartbeatSink>,
+    /// Runtime HTTP requests are driven on; requests fail without one
+    runtime: Option<tokio::runtime::Handle>,
+    started: Instant,
+}
+
+/// Raised by `proc_exit` to unwind out of the module
+#[derive(Debug, thiserror::Error)]
+#[error("proc_exit({0})")]
+struct ProcExit(i32);
+
+// WASI errno values
+const ERRNO_SUCCESS: i32 = 0;
+const ERRNO_BADF: i32 = 8;
+const ERRNO_FAULT: i32 = 21;
+const ERRNO_INVAL: i32 = 28;
+const ERRNO_NOSYS: i32 = 52;
+const ERRNO_SPIPE: i32 = 70;
+
+/// Define the WASI preview 1 functions Forge provides.
+///
+/// All of them are linked; `WasiPolicy::link` has already rejected modules
+/// importing anything their profile does not allow.
+fn link_wasi(linker: &mut Linker<HostState>) -> wasmtime::Result<()> {
+    linker.func_wrap(WASI_PREVIEW1, "proc_exit", |code: i32| -> wasmtime::Result<()> {
+        Err(ProcExit(code).into())
+    })?;
+    linker.func_wrap(WASI_PREVIEW1, "sched_yield", || ERRNO_SUCCESS)?;
+
+    linker.func_wrap(WASI_PREVIEW1, "clock_res_get", |mut caller: Caller<'_, HostState>, _id: i32, out: i32| {
+        write_or_fault(&mut caller, out, &1u64.to_le_bytes())
+    })?;
+    linker.func_wrap(
+        WASI_PREVIEW1,
+        "clock_time_get",
+        |mut caller: Caller<'_, HostState>, id: i32, _precision: i64, out: i32| {
+            let nanos = match id {
+                0 => SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u64,
+                1 => caller.data().started.elapsed().as_nanos() as u64,
+                _ => return ERRNO_INVAL,
+            };
+            write_or_fault(&mut caller, out, &nanos.to_le_bytes())
+        },
+    )?;
+    linker.func_wrap(WASI_PREVIEW1, "poll_oneoff", |_: i32, _: i32, _: i32, _: i32| ERRNO_NOSYS)?;
+
+    linker.func_wrap(WASI_PREVIEW1, "random_get", |mut caller: Caller<'_, HostState>, buf: i32, len: i32| {
+        let mut bytes = vec![0u8; len as u32 as usize];
+        if ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut bytes).is_err() {
+            return ERRNO_NOSYS;
+        }
+        write_or_fault(&mut caller, buf, &bytes)
+    })?;
+
+    linker.func_wrap(
+        WASI_PREVIEW1,
+        "fd_write",
+        |mut caller: Caller<'_, HostState>, fd: i32, iovs: i32, iovs_len: i32, nwritten: i32| {
+            if fd != 1 && fd != 2 {
+                return ERRNO_BADF;
+            }
+            let Some(bytes) = read_iovecs(&mut caller, iovs, iovs_len) else {
+                return ERRNO_FAULT;
+            };
+            let wasi = &mut caller.data_mut().wasi;
+            let written = if fd == 1 { wasi.stdout.write(&bytes) } else { wasi.stderr.write(&bytes) };
+            write_or_fault(&mut caller, nwritten, &(written as u32).to_le_bytes())
+        },
+    )?;
+    linker.func_wrap(
+        WASI_PREVIEW1,
+        "fd_read",
+        |mut caller: Caller<'_, HostState>, fd: i32, _iovs: i32, _iovs_len: i32, nread: i32| {
+            // stdin is always empty
+            if fd != 0 {
+                return ERRNO_BADF;
+            }
+            write_or_fault(&mut caller, nread, &0u32.to_le_bytes())
+        },
+    )?;
+    linker.func_wrap(WASI_PREVIEW1, "fd_close", |fd: i32| if (0..=2).contains(&fd) { ERRNO_SUCCESS } else { ERRNO_BADF })?;
+    linker.func_wrap(WASI_PREVIEW1, "fd_seek", |_: i32, _: i64, _: i32, _: i32| ERRNO_SPIPE)?;
+    linker.func_wrap(WASI_PREVIEW1, "fd_fdstat_get", |mut caller: Caller<'_, HostState>, fd: i32, out: i32| {
+        if !(0..=2).contains(&fd) {
+            return ERRNO_BADF;
+        }
+        // fdstat: filetype (character device), flags, then base and inheriting rights
+        let mut fdstat = [0u8; 24];
+        fdstat[0] = 2;
+        write_or_fault(&mut caller, out, &fdstat)
+    })?;
+
+    linker.func_wrap(
+        WASI_PREVIEW1,
+        "environ_sizes_get",
+        |mut caller: Caller<'_, HostState>, count_out: i32, size_out: i32| {
+            let env = environ(caller.data());
+            let size: usize = env.iter().map(Vec::len).sum();
+            match write_or_fault(&mut caller, count_out, &(env.len() as u32).to_le_bytes()) {
+                ERRNO_SUCCESS => write_or_fault(&mut caller, size_out, &(size as u32).to_le_bytes()),
+                errno => errno,
+            }
+        },
+    )?;
+    linker.func_wrap(
+        WASI_PREVIEW1,
+        "environ_get",
+        |mut caller: Caller<'_, HostState>, pointers: i32, buf: i32| {
+            let mut offset = buf as u32;
+            for (index, entry) in environ(caller.data()).into_iter().enumerate() {
+                let pointer = pointers + 4 * index as i32;
+                if write_or_fault(&mut caller, pointer, &offset.to_le_bytes()) != ERRNO_SUCCESS
+                    || write_or_fault(&mut caller, offset as i32, &entry) != ERRNO_SUCCESS
+                {
+                    return ERRNO_FAULT;
+                }
+                offset += entry.len() as u32;
+            }
+            ERRNO_SUCCESS
+        },
+    )?;
+    linker.func_wrap(WASI_PREVIEW1, "args_sizes_get", |mut caller: Caller<'_, HostState>, count_out: i32, size_out: i32| {
+        match write_or_fault(&mut caller, count_out, &0u32.to_le_bytes()) {
+            ERRNO_SUCCESS => write_or_fault(&mut caller, size_out, &0u32.to_le_bytes()),
+            errno => errno,
+        }
+    })?;
+    linker.func_wrap(WASI_PREVIEW1, "args_get", |_: i32, _: i32| ERRNO_SUCCESS)?;
+
+    Ok(())
+}
+
+/// Define the Forge host functions; `WasiPolicy::link` has already rejected
+/// modules importing them without a profile that allows them
+fn link_forge_host(linker: &mut Linker<HostState>) -> wasmtime::Result<()> {
+    linker.func_wrap(FORGE_HOST, "host_http_request", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+        let Some(memory) = caller_memory(&mut caller) else { return Ok(0) };
+        let mut request = vec![0u8; len as u32 as usize];
+        if memory.read(&caller, ptr as u32 as usize, &mut request).is_err() {
+            return Ok(0);
+        }
+
+        let state = caller.data_mut();
+        let response = match state.runtime.clone() {
+            Some(runtime) => runtime.block_on(state.egress.handle(&request)),
+            None => serde_json::json!({"error": {"code": "request_failed", "message": "no runtime to send requests on"}}),
+        };
+        let response = serde_json::to_vec(&response)?;
+
+        let alloc = caller
+            .get_export("alloc")
+            .and_then(|export| export.into_func())
+            .ok_or_else(|| anyhow::anyhow!("module does not export alloc"))?
+            .typed::<i32, i32>(&caller)?;
+        let out = alloc.call(&mut caller, i32::try_from(response.len() + 4)?)?;
+        let mut framed = (response.len() as u32).to_le_bytes().to_vec();
+        framed.extend_from_slice(&response);
+        Ok(if write_or_fault(&mut caller, out, &framed) == ERRNO_SUCCESS { out } else { 0 })
+    })?;
+    linker.func_wrap(FORGE_HOST, "heartbeat", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+        let Some(sink) = caller.data().heartbeat.clone() else { return ERRNO_NOSYS };
+        let progress = if len == 0 {
+            None
+        } else {
+            let Some(memory) = caller_memory(&mut caller) else { return ERRNO_FAULT };
+            let mut progress = vec![0u8; len as u32 as usize];
+            if memory.read(&caller, ptr as u32 as usize, &mut progress).is_err() {
+                return ERRNO_FAULT;
+            }
+            match serde_json::from_slice(&progress) {
+                Ok(progress) => Some(progress),
+                Err(_) => return ERRNO_INVAL,
+            }
+        };
+        if sink(progress).is_ok() { ERRNO_SUCCESS } else { ERRNO_INVAL }
+    })?;
+    Ok(())
+}
+
+/// Injected variables as NUL-terminated `KEY=value` strings
+fn environ(state: &HostState) -> Vec<Vec<u8>> {
+    state
+        .wasi
+        .env
+        .iter()
+        .map(|(key, value)| format!("{}={}\0", key, value).into_bytes())
+        .collect()
+}
+
+fn caller_memory(caller: &mut Caller<'_, HostState>) -> Option<Memory> {
+    caller.get_export("memory")?.into_memory()
+}
+
+fn write_or_fault(caller: &mut Caller<'_, HostState>, ptr: i32, bytes: &[u8]) -> i32 {
+    match caller_memory(caller).map(|memory| memory.write(&mut *caller, ptr as u32 as usize, bytes)) {
+        Some(Ok(())) => ERRNO_SUCCESS,
+        _ => ERRNO_FAULT,
+    }
+}
+
+/// Concatenate the buffers of an iovec array
+fn read_iovecs(caller: &mut Caller<'_, HostState>, iovs: i32, iovs_len: i32) -> Option<Vec<u8>> {
+    let memory = caller_memory(caller)?;
+    let mut bytes = Vec::new();
+    for index in 0..iovs_len as u32 as usize {
+        let mut iovec = [0u8; 8];
+        memory.read(&*caller, iovs as u32 as usize + index * 8, &mut iovec).ok()?;
+        let ptr = u32::from_le_bytes(iovec[..4].try_into().ok()?) as usize;
+        let len = u32::from_le_bytes(iovec[4..].try_into().ok()?) as usize;
+        let start = bytes.len();
+        bytes.resize(start + len, 0);
+        memory.read(&*caller, ptr, &mut bytes[start..]).ok()?;
+    }
+    Some(bytes)
+}
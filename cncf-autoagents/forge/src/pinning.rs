@@ -0,0 +1,270 @@
+//! Execution Pinning
+//!
+//! Keeps what an execution ran with — the module blob and the security
+//! policy — so it can be replayed after the module is upgraded or the policy
+//! changes. Blobs are content-addressed by their hash. Both blobs and policy
+//! snapshots stay pinned while a retained execution references them; unpinned
+//! policy snapshots are kept up to a bounded history.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{ExecutionResult, SecurityPolicy, WasmModule};
+
+/// Number of unpinned policy snapshots retained unless configured otherwise
+pub const DEFAULT_POLICY_SNAPSHOTS: usize = 16;
+
+/// A module version as it was loaded
+#[derive(Debug, Clone)]
+pub(crate) struct ModuleBlob {
+    pub module: WasmModule,
+    pub wasm: Option<Arc<[u8]>>,
+}
+
+/// Everything needed to re-run a retained execution
+pub(crate) struct ReplaySource {
+    pub blob: ModuleBlob,
+    pub policy: SecurityPolicy,
+    pub input: serde_json::Value,
+}
+
+struct Pinned<T> {
+    value: T,
+    /// Retained executions referencing the entry
+    executions: usize,
+}
+
+struct PolicySnapshot {
+    policy: SecurityPolicy,
+    /// Archive order, used to drop the oldest unpinned snapshots first
+    sequence: u64,
+}
+
+/// Content-addressed module blobs and policy snapshots referenced by executions
+pub(crate) struct PinStore {
+    max_policy_snapshots: usize,
+    blobs: HashMap<String, Pinned<ModuleBlob>>,
+    policies: HashMap<String, Pinned<PolicySnapshot>>,
+    current_policy: String,
+    next_sequence: u64,
+    /// Blob hash of each loaded module version, keyed by `id@version`
+    versions: HashMap<String, String>,
+    /// Input of each retained execution
+    inputs: HashMap<String, serde_json::Value>,
+}
+
+/// Content hash of a module version: its metadata and, when it has one, its binary
+pub(crate) fn blob_hash(module: &WasmModule, wasm: Option<&[u8]>) -> String {
+    let mut context = ring::digest::Context::new(&ring::digest::SHA256);
+    context.update(&serde_json::to_vec(module).unwrap_or_default());
+    if let Some(wasm) = wasm {
+        context.update(wasm);
+    }
+    hex(context.finish().as_ref())
+}
+
+/// Id of a policy snapshot, the hash of the policy's canonical JSON
+fn policy_snapshot_id(policy: &SecurityPolicy) -> String {
+    // Going through `Value` sorts map keys, so equal policies hash the same
+    let canonical = serde_json::to_value(policy).and_then(|value| serde_json::to_vec(&value)).unwrap_or_default();
+    hex(ring::digest::digest(&ring::digest::SHA256, &canonical).as_ref())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+impl PinStore {
+    pub fn new() -> Self {
+        Self {
+            max_policy_snapshots: DEFAULT_POLICY_SNAPSHOTS,
+            blobs: HashMap::new(),
+            policies: HashMap::new(),
+            current_policy: String::new(),
+            next_sequence: 0,
+            versions: HashMap::new(),
+            inputs: HashMap::new(),
+        }
+    }
+
+    pub fn set_max_policy_snapshots(&mut self, max: usize) {
+        self.max_policy_snapshots = max;
+        self.prune_policies();
+    }
+
+    /// Archive the policy now in force, returning its snapshot id
+    pub fn archive_policy(&mut self, policy: &SecurityPolicy) -> String {
+        let id = policy_snapshot_id(policy);
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.policies.entry(id.clone())
+            .or_insert_with(|| Pinned {
+                value: PolicySnapshot { policy: policy.clone(), sequence },
+                executions: 0,
+            })
+            .value.sequence = sequence;
+        self.current_policy = id.clone();
+        self.prune_policies();
+        id
+    }
+
+    /// Record the blob of a loaded module version. Returns the hash the
+    /// version previously mapped to, if a reload left it unused.
+    pub fn add_version(&mut self, hash: &str, module: &WasmModule, wasm: Option<&[u8]>) -> Option<String> {
+        self.blobs.entry(hash.to_string()).or_insert_with(|| Pinned {
+            value: ModuleBlob { module: module.clone(), wasm: wasm.map(Arc::from) },
+            executions: 0,
+        });
+        let previous = self.versions.insert(version_key(module), hash.to_string())?;
+        (previous != hash).then(|| self.drop_unused_blob(previous)).flatten()
+    }
+
+    /// Forget a module version. Returns its blob hash if no loaded version uses it anymore.
+    pub fn remove_version(&mut self, module: &WasmModule) -> Option<String> {
+        let hash = self.versions.remove(&version_key(module))?;
+        self.drop_unused_blob(hash)
+    }
+
+    /// Blob hash of a loaded module version
+    pub fn version_blob(&self, module: &WasmModule) -> Option<String> {
+        self.versions.get(&version_key(module)).cloned()
+    }
+
+    /// Whether any loaded module version uses the blob
+    pub fn is_loaded(&self, hash: &str) -> bool {
+        self.versions.values().any(|loaded| loaded == hash)
+    }
+
+    /// Pin the blob and policy snapshot of a newly retained execution
+    pub fn pin(&mut self, result: &ExecutionResult, input: serde_json::Value) {
+        if let Some(blob) = self.blobs.get_mut(&result.blob_hash) {
+            blob.executions += 1;
+        }
+        if let Some(snapshot) = self.policies.get_mut(&result.policy_snapshot_id) {
+            snapshot.executions += 1;
+        }
+        self.inputs.insert(result.execution_id.clone(), input);
+    }
+
+    /// Unpin what an evicted execution referenced
+    pub fn release(&mut self, result: &ExecutionResult) {
+        if self.inputs.remove(&result.execution_id).is_none() {
+            return;
+        }
+        if let Some(blob) = self.blobs.get_mut(&result.blob_hash) {
+            blob.executions = blob.executions.saturating_sub(1);
+            self.drop_unused_blob(result.blob_hash.clone());
+        }
+        if let Some(snapshot) = self.policies.get_mut(&result.policy_snapshot_id) {
+            snapshot.executions = snapshot.executions.saturating_sub(1);
+            self.prune_policies();
+        }
+    }
+
+    /// Blob, policy and input of a retained execution, if all are still pinned
+    pub fn replay_source(&self, result: &ExecutionResult) -> Option<ReplaySource> {
+        Some(ReplaySource {
+            blob: self.blobs.get(&result.blob_hash)?.value.clone(),
+            policy: self.policies.get(&result.policy_snapshot_id)?.value.policy.clone(),
+            input: self.inputs.get(&result.execution_id)?.clone(),
+        })
+    }
+
+    /// Drop a blob referenced by neither an execution nor a loaded version,
+    /// returning its hash if no loaded version uses it
+    fn drop_unused_blob(&mut self, hash: String) -> Option<String> {
+        if self.is_loaded(&hash) {
+            return None;
+        }
+        if self.blobs.get(&hash).is_some_and(|blob| blob.executions == 0) {
+            self.blobs.remove(&hash);
+        }
+        Some(hash)
+    }
+
+    /// Drop the oldest unpinned policy snapshots beyond the retained history
+    fn prune_policies(&mut self) {
+        let mut unpinned: Vec<(u64, String)> = self.policies.iter()
+            .filter(|(id, snapshot)| snapshot.executions == 0 && **id != self.current_policy)
+            .map(|(id, snapshot)| (snapshot.value.sequence, id.clone()))
+            .collect();
+        let overflow = unpinned.len().saturating_sub(self.max_policy_snapshots);
+        unpinned.sort_unstable();
+        for (_, id) in unpinned.into_iter().take(overflow) {
+            self.policies.remove(&id);
+        }
+    }
+}
+
+/// Key of a module version
+fn version_key(module: &WasmModule) -> String {
+    format!("{}@{}", module.id, module.version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module() -> WasmModule {
+        WasmModule {
+            id: "pinned-module".to_string(),
+            name: "Pinned Module".to_string(),
+            version: "1.0.0".to_string(),
+            capabilities: vec![],
+            max_memory_mb: 64,
+            max_execution_time_ms: 2000,
+            checksum: "checksum".to_string(),
+            maintenance_hooks: vec![],
+            sandbox_profile: None,
+            rate_limit: None,
+        }
+    }
+
+    fn result(execution_id: &str, blob_hash: &str, policy_snapshot_id: &str) -> ExecutionResult {
+        ExecutionResult {
+            execution_id: execution_id.to_string(),
+            module_id: "pinned-module".to_string(),
+            module_version: "1.0.0".to_string(),
+            success: true,
+            output: serde_json::Value::Null,
+            execution_time_ms: 0,
+            memory_used_kb: 0,
+            security_violations: vec![],
+            input_violations: vec![],
+            timestamp: chrono::Utc::now(),
+            kind: Default::default(),
+            trace_id: None,
+            stdio: None,
+            blob_hash: blob_hash.to_string(),
+            policy_snapshot_id: policy_snapshot_id.to_string(),
+            replay_of: None,
+            resources: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_pins_released_with_last_execution() {
+        let mut store = PinStore::new();
+        store.set_max_policy_snapshots(1);
+        let old_policy = store.archive_policy(&SecurityPolicy::default());
+
+        let module = module();
+        let hash = blob_hash(&module, None);
+        store.add_version(&hash, &module, None);
+        let pinned = result("pinned", &hash, &old_policy);
+        store.pin(&pinned, serde_json::json!({"command": "test"}));
+
+        // Newer policies push unpinned snapshots out, but not the pinned one
+        for max_execution_time_ms in [1000, 2000, 3000] {
+            store.archive_policy(&SecurityPolicy { max_execution_time_ms, ..SecurityPolicy::default() });
+        }
+        assert_eq!(store.policies.len(), 3);
+        assert_eq!(store.remove_version(&module), Some(hash.clone()));
+        assert!(store.replay_source(&pinned).is_some());
+
+        store.release(&pinned);
+        assert!(store.replay_source(&pinned).is_none());
+        assert!(store.blobs.is_empty());
+        assert!(!store.policies.contains_key(&old_policy));
+    }
+}
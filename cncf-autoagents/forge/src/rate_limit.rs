@@ -0,0 +1,113 @@
+//! Module Rate Limits
+//!
+//! Token buckets keyed by module id bound how often each module may be
+//! executed. Modules without a limit run as often as the execution slots
+//! allow.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Longest retry-after reported, however slowly a bucket refills
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(3600);
+
+/// Executions allowed per second, with bursts up to `burst`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub requests_per_second: f64,
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Reject rates a bucket cannot refill at: zero, negative or not finite
+    pub fn validate(&self) -> Result<(), InvalidRateLimit> {
+        if self.requests_per_second.is_finite() && self.requests_per_second > 0.0 {
+            Ok(())
+        } else {
+            Err(InvalidRateLimit(self.requests_per_second))
+        }
+    }
+}
+
+/// A rate limit whose `requests_per_second` is not a positive finite number
+#[derive(Debug, Clone, Copy, PartialEq, thiserror::Error)]
+#[error("rate limit requests_per_second must be positive and finite, got {0}")]
+pub struct InvalidRateLimit(pub f64);
+
+/// An execution rejected by its module's rate limit
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("module {module_id} is rate limited, retry after {}ms", retry_after.as_millis())]
+pub struct RateLimited {
+    pub module_id: String,
+    pub retry_after: Duration,
+}
+
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst.max(1) as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Take a token, or say how long until one is available
+    fn take(&mut self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let rate = self.limit.requests_per_second.max(f64::MIN_POSITIVE);
+        let burst = self.limit.burst.max(1) as f64;
+        self.tokens = (self.tokens + now.duration_since(self.last_refill).as_secs_f64() * rate).min(burst);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            let wait = Duration::try_from_secs_f64((1.0 - self.tokens) / rate).unwrap_or(MAX_RETRY_AFTER);
+            Err(wait.min(MAX_RETRY_AFTER))
+        }
+    }
+}
+
+/// Rate limits of the modules that have one
+#[derive(Debug, Default)]
+pub(crate) struct ModuleRateLimiter {
+    buckets: HashMap<String, TokenBucket>,
+}
+
+impl ModuleRateLimiter {
+    /// Set or clear a module's limit, starting it with a full burst
+    pub fn set(&mut self, module_id: &str, limit: Option<RateLimit>) {
+        match limit {
+            Some(limit) => {
+                self.buckets.insert(module_id.to_string(), TokenBucket::new(limit));
+            }
+            None => {
+                self.buckets.remove(module_id);
+            }
+        }
+    }
+
+    pub fn get(&self, module_id: &str) -> Option<RateLimit> {
+        self.buckets.get(module_id).map(|bucket| bucket.limit)
+    }
+
+    /// Admit one execution of the module
+    pub fn check(&mut self, module_id: &str) -> Result<(), RateLimited> {
+        let Some(bucket) = self.buckets.get_mut(module_id) else {
+            return Ok(());
+        };
+        bucket.take().map_err(|retry_after| RateLimited {
+            module_id: module_id.to_string(),
+            retry_after,
+        })
+    }
+}
@@ -0,0 +1,125 @@
+//! Module Registry Snapshots
+//!
+//! A snapshot holds the metadata of every retained module version, the
+//! default version of each module and the rate limits changed at runtime, as
+//! plain serde types so it can be kept as JSON or TOML. Importing a snapshot
+//! loads each version through the usual security policy checks and reports
+//! every module's outcome instead of stopping at the first failure. Module
+//! binaries are not part of a snapshot; `load_modules_from_dir` loads them
+//! from `*.module.json` descriptors and the `.wasm` files beside them.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{RateLimit, WasmModule};
+
+/// Suffix of module descriptor files
+pub const DESCRIPTOR_SUFFIX: &str = ".module.json";
+
+/// Exported module registry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrySnapshot {
+    pub exported_at: DateTime<Utc>,
+    /// Retained versions of every module, oldest first within a module
+    pub modules: Vec<WasmModule>,
+    /// Default version by module id
+    #[serde(default)]
+    pub default_versions: BTreeMap<String, String>,
+    /// Rate limits set at runtime that differ from the default version's own;
+    /// a missing limit means the limit was lifted
+    #[serde(default)]
+    pub rate_limit_overrides: BTreeMap<String, RateLimitOverride>,
+}
+
+/// Rate limit of a module as changed at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitOverride {
+    #[serde(default)]
+    pub limit: Option<RateLimit>,
+}
+
+/// What happens to loaded modules on import
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Keep loaded modules; versions in the snapshot are loaded over them
+    #[default]
+    Merge,
+    /// Unload every module first, leaving only the snapshot's
+    Replace,
+}
+
+/// Outcome of loading one module version
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleImport {
+    pub module_id: String,
+    pub version: String,
+    /// Descriptor the module was read from, for directory loads
+    #[serde(default)]
+    pub source: Option<PathBuf>,
+    /// Why the module was not loaded
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl ModuleImport {
+    pub(crate) fn loaded(module: &WasmModule) -> Self {
+        Self {
+            module_id: module.id.clone(),
+            version: module.version.clone(),
+            source: None,
+            error: None,
+        }
+    }
+
+    pub(crate) fn failed(module: &WasmModule, error: impl ToString) -> Self {
+        Self {
+            error: Some(error.to_string()),
+            ..Self::loaded(module)
+        }
+    }
+
+    pub(crate) fn with_source(mut self, source: &Path) -> Self {
+        self.source = Some(source.to_path_buf());
+        self
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Per-module outcomes of an import
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub modules: Vec<ModuleImport>,
+}
+
+impl ImportReport {
+    pub fn loaded(&self) -> usize {
+        self.modules.iter().filter(|module| module.is_loaded()).count()
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &ModuleImport> {
+        self.modules.iter().filter(|module| !module.is_loaded())
+    }
+}
+
+/// Descriptors in `dir`, sorted by path, with the `.wasm` file beside each if there is one
+pub(crate) fn module_descriptors(dir: &Path) -> std::io::Result<Vec<(PathBuf, Option<PathBuf>)>> {
+    let mut descriptors = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(stem) = path.file_name().and_then(|name| name.to_str()).and_then(|name| name.strip_suffix(DESCRIPTOR_SUFFIX)) else {
+            continue;
+        };
+        let wasm = path.with_file_name(format!("{}.wasm", stem));
+        let wasm = wasm.is_file().then_some(wasm);
+        descriptors.push((path, wasm));
+    }
+    descriptors.sort();
+    Ok(descriptors)
+}
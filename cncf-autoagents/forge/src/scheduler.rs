@@ -0,0 +1,161 @@
+//! Maintenance Hook Scheduler
+//!
+//! Runs module-declared maintenance hooks (cache warmup, token rotation,
+//! expiry sweeps) on a fixed interval through the normal execution path,
+//! independent of Conductor traffic.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Maintenance hook declared in a module manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceHook {
+    /// Hook name, unique within the module
+    pub name: String,
+    /// Exported function invoked for this hook
+    pub export: String,
+    /// Interval between invocations
+    pub interval_ms: u64,
+    /// Input payload passed to the export
+    #[serde(default)]
+    pub input: serde_json::Value,
+    /// Optional memory limit override (may only lower the module limit)
+    #[serde(default)]
+    pub max_memory_mb: Option<u32>,
+    /// Optional execution time override (may only lower the module limit)
+    #[serde(default)]
+    pub max_execution_time_ms: Option<u64>,
+    /// Consecutive failures after which the hook is disabled
+    #[serde(default = "default_max_consecutive_failures")]
+    pub max_consecutive_failures: u32,
+}
+
+fn default_max_consecutive_failures() -> u32 {
+    3
+}
+
+/// Runtime status of a maintenance hook, reported through `Forge::describe`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookStatus {
+    pub name: String,
+    pub export: String,
+    pub enabled: bool,
+    pub running: bool,
+    pub total_runs: u64,
+    pub consecutive_failures: u32,
+    pub skipped_overlaps: u64,
+    pub last_run: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_success: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_error: Option<String>,
+}
+
+/// Internal scheduling state for a single hook
+#[derive(Debug, Clone)]
+pub(crate) struct HookState {
+    pub hook: MaintenanceHook,
+    pub status: HookStatus,
+    pub next_run: Instant,
+}
+
+impl HookState {
+    pub fn new(hook: MaintenanceHook) -> Self {
+        let status = HookStatus {
+            name: hook.name.clone(),
+            export: hook.export.clone(),
+            enabled: true,
+            running: false,
+            total_runs: 0,
+            consecutive_failures: 0,
+            skipped_overlaps: 0,
+            last_run: None,
+            last_success: None,
+            last_error: None,
+        };
+
+        Self {
+            next_run: Instant::now() + hook.interval(),
+            hook,
+            status,
+        }
+    }
+
+    /// Record the outcome of a finished run. Returns true when the hook
+    /// has just been disabled because of repeated failures.
+    pub fn record_outcome(&mut self, outcome: Result<(), String>) -> bool {
+        self.status.running = false;
+        self.status.total_runs += 1;
+        self.status.last_run = Some(chrono::Utc::now());
+
+        match outcome {
+            Ok(()) => {
+                self.status.consecutive_failures = 0;
+                self.status.last_success = self.status.last_run;
+                self.status.last_error = None;
+                false
+            }
+            Err(reason) => {
+                self.status.consecutive_failures += 1;
+                self.status.last_error = Some(reason);
+
+                if self.status.enabled
+                    && self.status.consecutive_failures >= self.hook.max_consecutive_failures
+                {
+                    self.status.enabled = false;
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+}
+
+impl MaintenanceHook {
+    /// Scheduling interval as a duration
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms.max(1))
+    }
+}
+
+/// Hook states keyed by module id, then hook name
+pub(crate) type HookTable = HashMap<String, HashMap<String, HookState>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook() -> MaintenanceHook {
+        MaintenanceHook {
+            name: "refresh".to_string(),
+            export: "refresh_cache".to_string(),
+            interval_ms: 100,
+            input: serde_json::Value::Null,
+            max_memory_mb: None,
+            max_execution_time_ms: None,
+            max_consecutive_failures: 2,
+        }
+    }
+
+    #[test]
+    fn test_disable_after_consecutive_failures() {
+        let mut state = HookState::new(hook());
+
+        assert!(!state.record_outcome(Err("boom".to_string())));
+        assert!(state.record_outcome(Err("boom".to_string())));
+        assert!(!state.status.enabled);
+        assert_eq!(state.status.consecutive_failures, 2);
+    }
+
+    #[test]
+    fn test_success_resets_failures() {
+        let mut state = HookState::new(hook());
+
+        state.record_outcome(Err("boom".to_string()));
+        state.record_outcome(Ok(()));
+        assert_eq!(state.status.consecutive_failures, 0);
+        assert!(state.status.enabled);
+        assert!(state.status.last_success.is_some());
+    }
+}
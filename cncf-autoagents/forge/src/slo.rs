@@ -0,0 +1,433 @@
+//! Capability SLOs
+//!
+//! Calls modules make through host capabilities (outbound `http` today) are
+//! tracked per capability and module. A call is bad when the capability
+//! failed it or it took longer than the target latency; requests the policy
+//! refused are the module's doing and are not counted. The burn rate of a
+//! window is its bad-call ratio over the error budget of the capability's
+//! target. When the short window burns past the fast threshold, or the long
+//! window past the slow one, an alert is raised naming the modules with the
+//! most bad calls. It clears once the burn rate falls back under the
+//! threshold.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::egress::{HttpExchange, HTTP_CAPABILITY};
+use crate::metrics::percentile;
+
+/// Most calls kept per capability, however busy the long window gets
+pub const MAX_WINDOW_CALLS: usize = 10_000;
+
+/// Recent call latencies kept per module and capability for percentiles
+const USAGE_LATENCY_WINDOW: usize = 256;
+
+/// Objective of one capability
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SloTarget {
+    /// Fraction of calls, from 0.0 to 1.0, that must be good
+    #[serde(default = "default_objective")]
+    pub objective: f64,
+    /// Calls slower than this count as bad
+    #[serde(default = "default_latency_ms")]
+    pub latency_ms: u64,
+}
+
+fn default_objective() -> f64 {
+    0.99
+}
+
+fn default_latency_ms() -> u64 {
+    1000
+}
+
+impl Default for SloTarget {
+    fn default() -> Self {
+        Self {
+            objective: default_objective(),
+            latency_ms: default_latency_ms(),
+        }
+    }
+}
+
+impl SloTarget {
+    fn error_budget(&self) -> f64 {
+        (1.0 - self.objective).max(f64::EPSILON)
+    }
+}
+
+/// Targets, windows and burn thresholds of capability SLOs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilitySloPolicy {
+    /// Per-capability targets; others use `default_target`
+    #[serde(default)]
+    pub targets: HashMap<String, SloTarget>,
+    #[serde(default)]
+    pub default_target: SloTarget,
+    #[serde(default = "default_short_window_ms")]
+    pub short_window_ms: u64,
+    #[serde(default = "default_long_window_ms")]
+    pub long_window_ms: u64,
+    /// Short-window burn rate that raises a fast-burn alert
+    #[serde(default = "default_fast_burn_threshold")]
+    pub fast_burn_threshold: f64,
+    /// Long-window burn rate that raises a slow-burn alert
+    #[serde(default = "default_slow_burn_threshold")]
+    pub slow_burn_threshold: f64,
+    /// Calls a window must hold before its burn rate counts
+    #[serde(default = "default_min_window_calls")]
+    pub min_window_calls: usize,
+    /// Modules named in an alert
+    #[serde(default = "default_top_offenders")]
+    pub top_offenders: usize,
+}
+
+fn default_short_window_ms() -> u64 {
+    5 * 60_000
+}
+
+fn default_long_window_ms() -> u64 {
+    60 * 60_000
+}
+
+fn default_fast_burn_threshold() -> f64 {
+    14.4
+}
+
+fn default_slow_burn_threshold() -> f64 {
+    6.0
+}
+
+fn default_min_window_calls() -> usize {
+    10
+}
+
+fn default_top_offenders() -> usize {
+    3
+}
+
+impl Default for CapabilitySloPolicy {
+    fn default() -> Self {
+        Self {
+            targets: HashMap::new(),
+            default_target: SloTarget::default(),
+            short_window_ms: default_short_window_ms(),
+            long_window_ms: default_long_window_ms(),
+            fast_burn_threshold: default_fast_burn_threshold(),
+            slow_burn_threshold: default_slow_burn_threshold(),
+            min_window_calls: default_min_window_calls(),
+            top_offenders: default_top_offenders(),
+        }
+    }
+}
+
+impl CapabilitySloPolicy {
+    pub fn target(&self, capability: &str) -> SloTarget {
+        self.targets.get(capability).copied().unwrap_or(self.default_target)
+    }
+}
+
+/// Which burn-rate window an alert is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BurnWindow {
+    /// The short window, burning past `fast_burn_threshold`
+    Fast,
+    /// The long window, burning past `slow_burn_threshold`
+    Slow,
+}
+
+/// A module's share of a capability's calls in the short window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityOffender {
+    pub module_id: String,
+    pub calls: u64,
+    pub bad_calls: u64,
+}
+
+/// A capability burning its error budget too fast
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapabilityAlert {
+    pub capability: String,
+    pub window: BurnWindow,
+    pub burn_rate: f64,
+    pub threshold: f64,
+    /// Modules with the most bad calls, worst first
+    pub top_modules: Vec<CapabilityOffender>,
+}
+
+/// Alert state change caused by a call
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum SloTransition {
+    Fired(CapabilityAlert),
+    Cleared { capability: String, window: BurnWindow, burn_rate: f64 },
+}
+
+/// SLO state of a capability, reported by `Forge::execution_stats`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CapabilitySloStatus {
+    pub calls: u64,
+    pub failures: u64,
+    /// Calls slower than the target latency
+    pub slow_calls: u64,
+    pub p95_latency_ms: u64,
+    pub short_burn_rate: f64,
+    pub long_burn_rate: f64,
+    /// Windows whose alert is currently raised
+    pub firing: Vec<BurnWindow>,
+    pub alerts_fired: u64,
+}
+
+/// A module's calls through one capability, reported by `Forge::describe`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityUsage {
+    pub calls: u64,
+    pub failures: u64,
+    pub slow_calls: u64,
+    pub p95_latency_ms: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Call {
+    at: Instant,
+    module_id: String,
+    latency_ms: u64,
+    bad: bool,
+}
+
+#[derive(Debug, Default)]
+struct CapabilityState {
+    window: VecDeque<Call>,
+    totals: CapabilityUsage,
+    firing: Vec<BurnWindow>,
+    alerts_fired: u64,
+}
+
+impl CapabilityState {
+    /// Calls within `window` of `now`
+    fn recent(&self, now: Instant, window: Duration) -> impl Iterator<Item = &Call> {
+        self.window.iter().filter(move |call| now.saturating_duration_since(call.at) <= window)
+    }
+
+    /// Burn rate over `window`, or zero while it holds too few calls to count
+    fn burn_rate(&self, now: Instant, window: Duration, budget: f64, min_calls: usize) -> f64 {
+        let (calls, bad) = self.recent(now, window).fold((0usize, 0usize), |(calls, bad), call| (calls + 1, bad + call.bad as usize));
+        if calls == 0 || calls < min_calls {
+            return 0.0;
+        }
+        (bad as f64 / calls as f64) / budget
+    }
+
+    fn offenders(&self, now: Instant, window: Duration, limit: usize) -> Vec<CapabilityOffender> {
+        let mut by_module: HashMap<&str, CapabilityOffender> = HashMap::new();
+        for call in self.recent(now, window) {
+            let offender = by_module.entry(&call.module_id).or_insert_with(|| CapabilityOffender {
+                module_id: call.module_id.clone(),
+                calls: 0,
+                bad_calls: 0,
+            });
+            offender.calls += 1;
+            offender.bad_calls += call.bad as u64;
+        }
+        let mut offenders: Vec<CapabilityOffender> = by_module.into_values().filter(|o| o.bad_calls > 0).collect();
+        offenders.sort_by(|a, b| b.bad_calls.cmp(&a.bad_calls).then(b.calls.cmp(&a.calls)).then(a.module_id.cmp(&b.module_id)));
+        offenders.truncate(limit);
+        offenders
+    }
+}
+
+#[derive(Debug, Default)]
+struct UsageState {
+    usage: CapabilityUsage,
+    latencies_ms: VecDeque<u64>,
+}
+
+/// Per-capability SLO tracking, updated as executions report their calls
+#[derive(Debug, Default)]
+pub(crate) struct CapabilitySlos {
+    policy: CapabilitySloPolicy,
+    capabilities: HashMap<String, CapabilityState>,
+    /// Keyed by module id, then capability
+    modules: HashMap<String, HashMap<String, UsageState>>,
+}
+
+impl CapabilitySlos {
+    pub fn new(policy: CapabilitySloPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// Record the outbound HTTP calls of an execution; refused requests are skipped
+    pub fn record_http(&mut self, module_id: &str, exchanges: &[HttpExchange], now: Instant) -> Vec<SloTransition> {
+        exchanges
+            .iter()
+            .filter_map(|exchange| {
+                let failed = match (exchange.status, exchange.error.as_deref()) {
+                    (_, Some("request_failed")) => true,
+                    (Some(status), _) => status >= 500,
+                    (None, _) => return None,
+                };
+                Some(self.record(HTTP_CAPABILITY, module_id, exchange.duration_ms, failed, now))
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Record one call and re-evaluate the capability's alerts
+    pub fn record(&mut self, capability: &str, module_id: &str, latency_ms: u64, failed: bool, now: Instant) -> Vec<SloTransition> {
+        let target = self.policy.target(capability);
+        let slow = latency_ms > target.latency_ms;
+
+        let usage = self.modules.entry(module_id.to_string()).or_default().entry(capability.to_string()).or_default();
+        usage.usage.calls += 1;
+        usage.usage.failures += failed as u64;
+        usage.usage.slow_calls += slow as u64;
+        if usage.latencies_ms.len() == USAGE_LATENCY_WINDOW {
+            usage.latencies_ms.pop_front();
+        }
+        usage.latencies_ms.push_back(latency_ms);
+
+        let long_window = Duration::from_millis(self.policy.long_window_ms);
+        let state = self.capabilities.entry(capability.to_string()).or_default();
+        state.totals.calls += 1;
+        state.totals.failures += failed as u64;
+        state.totals.slow_calls += slow as u64;
+        while state.window.front().is_some_and(|call| now.saturating_duration_since(call.at) > long_window)
+            || state.window.len() >= MAX_WINDOW_CALLS
+        {
+            state.window.pop_front();
+        }
+        state.window.push_back(Call {
+            at: now,
+            module_id: module_id.to_string(),
+            latency_ms,
+            bad: failed || slow,
+        });
+
+        let mut transitions = Vec::new();
+        for (window, length, threshold) in [
+            (BurnWindow::Fast, self.policy.short_window_ms, self.policy.fast_burn_threshold),
+            (BurnWindow::Slow, self.policy.long_window_ms, self.policy.slow_burn_threshold),
+        ] {
+            let length = Duration::from_millis(length);
+            let burn_rate = state.burn_rate(now, length, target.error_budget(), self.policy.min_window_calls);
+            let firing = state.firing.contains(&window);
+            if burn_rate >= threshold && !firing {
+                state.firing.push(window);
+                state.alerts_fired += 1;
+                transitions.push(SloTransition::Fired(CapabilityAlert {
+                    capability: capability.to_string(),
+                    window,
+                    burn_rate,
+                    threshold,
+                    top_modules: state.offenders(now, Duration::from_millis(self.policy.short_window_ms), self.policy.top_offenders),
+                }));
+            } else if burn_rate < threshold && firing {
+                state.firing.retain(|w| *w != window);
+                transitions.push(SloTransition::Cleared {
+                    capability: capability.to_string(),
+                    window,
+                    burn_rate,
+                });
+            }
+        }
+        transitions
+    }
+
+    /// SLO state of every capability that has been called
+    pub fn status(&self, now: Instant) -> HashMap<String, CapabilitySloStatus> {
+        self.capabilities
+            .iter()
+            .map(|(capability, state)| {
+                let budget = self.policy.target(capability).error_budget();
+                let short = Duration::from_millis(self.policy.short_window_ms);
+                let long = Duration::from_millis(self.policy.long_window_ms);
+                let mut latencies: Vec<u64> = state.recent(now, short).map(|call| call.latency_ms).collect();
+                latencies.sort_unstable();
+                let status = CapabilitySloStatus {
+                    calls: state.totals.calls,
+                    failures: state.totals.failures,
+                    slow_calls: state.totals.slow_calls,
+                    p95_latency_ms: percentile(&latencies, 95.0),
+                    short_burn_rate: state.burn_rate(now, short, budget, self.policy.min_window_calls),
+                    long_burn_rate: state.burn_rate(now, long, budget, self.policy.min_window_calls),
+                    firing: state.firing.clone(),
+                    alerts_fired: state.alerts_fired,
+                };
+                (capability.clone(), status)
+            })
+            .collect()
+    }
+
+    /// Capability calls made by one module
+    pub fn module_usage(&self, module_id: &str) -> HashMap<String, CapabilityUsage> {
+        self.modules
+            .get(module_id)
+            .into_iter()
+            .flatten()
+            .map(|(capability, state)| {
+                let mut latencies: Vec<u64> = state.latencies_ms.iter().copied().collect();
+                latencies.sort_unstable();
+                let usage = CapabilityUsage {
+                    p95_latency_ms: percentile(&latencies, 95.0),
+                    ..state.usage.clone()
+                };
+                (capability.clone(), usage)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burn_rate_alerts_fire_at_threshold_and_clear() {
+        let policy = CapabilitySloPolicy {
+            default_target: SloTarget { objective: 0.9, latency_ms: 100 },
+            fast_burn_threshold: 5.0,
+            slow_burn_threshold: 100.0,
+            min_window_calls: 4,
+            ..Default::default()
+        };
+        let mut slos = CapabilitySlos::new(policy);
+        let now = Instant::now();
+
+        // Too few calls to count, however bad
+        for _ in 0..3 {
+            assert!(slos.record("kv", "noisy", 5, true, now).is_empty());
+        }
+
+        // With a 10% budget, a 5x burn is a 50% bad ratio: 3 bad of 4 fires
+        let transitions = slos.record("kv", "quiet", 5, false, now);
+        let [SloTransition::Fired(alert)] = transitions.as_slice() else {
+            panic!("expected a fast-burn alert, got {:?}", transitions);
+        };
+        assert_eq!(alert.window, BurnWindow::Fast);
+        assert!((alert.burn_rate - 7.5).abs() < 1e-9);
+        assert_eq!(alert.top_modules, vec![CapabilityOffender { module_id: "noisy".to_string(), calls: 3, bad_calls: 3 }]);
+
+        // Slow calls count as bad too; the alert holds until the ratio drops under 50%
+        assert!(slos.record("kv", "quiet", 500, false, now).is_empty());
+        assert_eq!(slos.status(now)["kv"].firing, vec![BurnWindow::Fast]);
+        for _ in 0..3 {
+            assert!(slos.record("kv", "quiet", 5, false, now).is_empty());
+        }
+        let transitions = slos.record("kv", "quiet", 5, false, now);
+        assert!(matches!(transitions.as_slice(), [SloTransition::Cleared { window: BurnWindow::Fast, .. }]));
+
+        let status = &slos.status(now)["kv"];
+        assert!(status.firing.is_empty());
+        assert_eq!((status.calls, status.failures, status.slow_calls, status.alerts_fired), (9, 3, 1, 1));
+        assert_eq!(slos.module_usage("quiet")["kv"].slow_calls, 1);
+
+        // Calls age out of the window
+        let later = now + Duration::from_millis(slos.policy.short_window_ms + 1);
+        assert_eq!(slos.status(later)["kv"].short_burn_rate, 0.0);
+    }
+}
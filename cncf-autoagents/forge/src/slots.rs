@@ -0,0 +1,203 @@
+//! Execution Slots
+//!
+//! Bounds how many executions run at once, across Forge and per module.
+//! Callers beyond a limit wait in line or are turned away, as the
+//! `OverflowMode` of the security policy says. Forge-wide slots are shared
+//! between tenants by weighted fair queuing (see `fair_queue`).
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::{
+    fair_queue::{FairPermit, FairSchedulingPolicy, FairSlots, TenantQueueStats},
+    heartbeat::InFlightStatus,
+    slo::CapabilitySloStatus,
+    OverflowMode,
+};
+
+/// Why an execution did not get a slot
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SlotError {
+    /// No slot freed up within the capacity grace period
+    #[error("forge_at_capacity")]
+    AtCapacity,
+    /// The wait queue is full
+    #[error("execution slots exhausted: {queued} execution(s) already queued (max {max_queue_depth})")]
+    Exhausted { queued: usize, max_queue_depth: usize },
+}
+
+/// Running and waiting executions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotCounts {
+    pub in_flight: usize,
+    pub queued: usize,
+}
+
+/// Concurrency snapshot reported by `Forge::execution_stats`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConcurrencyStats {
+    pub in_flight: usize,
+    pub queued: usize,
+    /// Occupancy of modules with running or queued executions
+    pub modules: HashMap<String, SlotCounts>,
+    /// Queue depth and waiting times of tenants with running or queued executions
+    #[serde(default)]
+    pub tenants: HashMap<String, TenantQueueStats>,
+    /// Liveness of every in-flight execution
+    pub executions: Vec<InFlightStatus>,
+    /// SLO state of every host capability modules have called
+    #[serde(default)]
+    pub capabilities: HashMap<String, CapabilitySloStatus>,
+}
+
+/// How an execution waits for its slot
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AdmissionPolicy {
+    pub overflow_mode: OverflowMode,
+    pub grace: Duration,
+    pub max_queue_depth: Option<usize>,
+}
+
+/// Global and per-module concurrency limits with their current occupancy
+pub(crate) struct ExecutionSlots {
+    global: FairSlots,
+    per_module_limit: Option<usize>,
+    modules: Mutex<HashMap<String, Arc<Semaphore>>>,
+    counts: Arc<Mutex<HashMap<String, SlotCounts>>>,
+}
+
+/// Held for the duration of an execution
+pub(crate) struct SlotPermit {
+    _module: Option<OwnedSemaphorePermit>,
+    _global: FairPermit,
+    _running: CountGuard,
+}
+
+/// Keeps one execution counted as running or queued until dropped
+struct CountGuard {
+    counts: Arc<Mutex<HashMap<String, SlotCounts>>>,
+    module_id: String,
+    queued: bool,
+}
+
+impl CountGuard {
+    fn running(counts: &Arc<Mutex<HashMap<String, SlotCounts>>>, module_id: &str) -> Self {
+        counts.lock().unwrap().entry(module_id.to_string()).or_default().in_flight += 1;
+        Self {
+            counts: counts.clone(),
+            module_id: module_id.to_string(),
+            queued: false,
+        }
+    }
+}
+
+impl Drop for CountGuard {
+    fn drop(&mut self) {
+        let mut all = self.counts.lock().unwrap();
+        if let Some(entry) = all.get_mut(&self.module_id) {
+            if self.queued {
+                entry.queued -= 1;
+            } else {
+                entry.in_flight -= 1;
+            }
+            if *entry == SlotCounts::default() {
+                all.remove(&self.module_id);
+            }
+        }
+    }
+}
+
+impl ExecutionSlots {
+    pub fn new(max_concurrent: usize, per_module_limit: Option<usize>, fair_scheduling: FairSchedulingPolicy) -> Self {
+        Self {
+            global: FairSlots::new(max_concurrent, fair_scheduling),
+            per_module_limit,
+            modules: Mutex::new(HashMap::new()),
+            counts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Take a slot for an execution of `module_id` on behalf of `tenant`, waiting as the policy allows
+    pub async fn acquire(&self, module_id: &str, tenant: &str, policy: AdmissionPolicy) -> Result<SlotPermit, SlotError> {
+        let module = self.per_module_limit.map(|limit| {
+            self.modules.lock().unwrap()
+                .entry(module_id.to_string())
+                .or_insert_with(|| Arc::new(Semaphore::new(limit)))
+                .clone()
+        });
+
+        if let Some(permit) = self.try_acquire(module_id, tenant, module.as_ref()) {
+            return Ok(permit);
+        }
+
+        let queued = {
+            // Checked and counted under one lock so concurrent callers cannot overfill the queue
+            let mut all = self.counts.lock().unwrap();
+            let total: usize = all.values().map(|counts| counts.queued).sum();
+            if let (OverflowMode::Queue, Some(max_queue_depth)) = (policy.overflow_mode, policy.max_queue_depth) {
+                if total >= max_queue_depth {
+                    return Err(SlotError::Exhausted { queued: total, max_queue_depth });
+                }
+            }
+            all.entry(module_id.to_string()).or_default().queued += 1;
+            CountGuard {
+                counts: self.counts.clone(),
+                module_id: module_id.to_string(),
+                queued: true,
+            }
+        };
+
+        // The module slot is taken first so a saturated module does not hold global slots
+        let wait = async {
+            let module = match module {
+                Some(module) => Some(module.acquire_owned().await.map_err(|_| SlotError::AtCapacity)?),
+                None => None,
+            };
+            let global = self.global.acquire(tenant).await;
+            Ok::<_, SlotError>((module, global))
+        };
+        let (module, global) = match policy.overflow_mode {
+            OverflowMode::Queue => wait.await?,
+            OverflowMode::Reject => tokio::time::timeout(policy.grace, wait).await.map_err(|_| SlotError::AtCapacity)??,
+        };
+        drop(queued);
+
+        Ok(SlotPermit {
+            _module: module,
+            _global: global,
+            _running: CountGuard::running(&self.counts, module_id),
+        })
+    }
+
+    fn try_acquire(&self, module_id: &str, tenant: &str, module: Option<&Arc<Semaphore>>) -> Option<SlotPermit> {
+        let module = match module {
+            Some(module) => Some(module.clone().try_acquire_owned().ok()?),
+            None => None,
+        };
+        let global = self.global.try_acquire(tenant)?;
+        Some(SlotPermit {
+            _module: module,
+            _global: global,
+            _running: CountGuard::running(&self.counts, module_id),
+        })
+    }
+
+    /// Current occupancy per module id
+    pub fn counts(&self) -> HashMap<String, SlotCounts> {
+        self.counts.lock().unwrap().clone()
+    }
+
+    /// Queue statistics per tenant
+    pub fn tenant_stats(&self) -> HashMap<String, TenantQueueStats> {
+        self.global.stats()
+    }
+
+    /// Apply new tenant weights without disturbing running or queued executions
+    pub fn set_fair_scheduling(&self, policy: FairSchedulingPolicy) {
+        self.global.set_policy(policy);
+    }
+}
@@ -0,0 +1,129 @@
+//! Streaming Executions
+//!
+//! Long-running executions can report what they are doing before they
+//! finish. A streamed execution emits `Started`, then any log lines and
+//! progress updates, and ends with exactly one terminal event: `Completed`,
+//! `Cancelled` or `Failed`. Its `ExecutionHandle` can abort it at any point.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, watch};
+
+use crate::ExecutionResult;
+
+/// Progress updates emitted over the course of a simulated execution
+pub const PROGRESS_STEPS: u32 = 10;
+
+/// Error an aborted execution fails with
+pub(crate) const CANCELLED: &str = "execution_cancelled";
+
+/// Something that happened during a streamed execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExecutionEvent {
+    /// The execution got a slot and passed validation
+    Started {
+        execution_id: String,
+        module_id: String,
+        module_version: String,
+    },
+    /// A line the module wrote to stdout or stderr
+    Log(String),
+    /// Fraction of the work done, from 0.0 to 1.0
+    Progress(f32),
+    Completed(Box<ExecutionResult>),
+    /// The execution was aborted through its handle
+    Cancelled,
+    /// The execution could not run, e.g. no slot or a rejected input
+    Failed(String),
+}
+
+/// Controls a streamed execution
+#[derive(Debug, Clone)]
+pub struct ExecutionHandle {
+    module_id: String,
+    cancel: watch::Sender<bool>,
+}
+
+impl ExecutionHandle {
+    pub fn module_id(&self) -> &str {
+        &self.module_id
+    }
+
+    /// Terminate the execution. Its stream ends with `Cancelled` unless it
+    /// already completed.
+    pub fn abort(&self) {
+        self.cancel.send_replace(true);
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        *self.cancel.borrow()
+    }
+}
+
+/// Execution side of a stream: where events go and how aborts arrive
+#[derive(Clone)]
+pub(crate) struct EventSink {
+    events: mpsc::UnboundedSender<ExecutionEvent>,
+    cancel: watch::Receiver<bool>,
+}
+
+impl EventSink {
+    pub fn send(&self, event: ExecutionEvent) {
+        // The caller may have stopped listening; the execution runs on regardless
+        let _ = self.events.send(event);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancel.borrow()
+    }
+}
+
+/// Create the handle, sink and event stream of a streamed execution
+pub(crate) fn channel(module_id: &str) -> (ExecutionHandle, EventSink, impl Stream<Item = ExecutionEvent> + Unpin) {
+    let (events, mut receiver) = mpsc::unbounded_channel();
+    let (cancel, cancelled) = watch::channel(false);
+
+    let handle = ExecutionHandle { module_id: module_id.to_string(), cancel };
+    let stream = futures::stream::poll_fn(move |cx| receiver.poll_recv(cx));
+    (handle, EventSink { events, cancel: cancelled }, stream)
+}
+
+/// Resolve once the execution is aborted; never for unstreamed executions
+/// or when the handle was dropped without aborting
+pub(crate) async fn cancelled(sink: Option<&EventSink>) {
+    if let Some(sink) = sink {
+        let mut cancel = sink.cancel.clone();
+        if cancel.wait_for(|cancelled| *cancelled).await.is_ok() {
+            return;
+        }
+    }
+    std::future::pending().await
+}
+
+/// Drive `work`, reporting progress toward `duration` in `PROGRESS_STEPS` steps
+pub(crate) async fn report_progress<F: Future>(work: F, duration: Duration, sink: Option<&EventSink>) -> F::Output {
+    let Some(sink) = sink else {
+        return work.await;
+    };
+
+    tokio::pin!(work);
+    let started = Instant::now();
+    let mut tick = tokio::time::interval((duration / PROGRESS_STEPS).max(Duration::from_millis(1)));
+    tick.tick().await;
+
+    let output = loop {
+        tokio::select! {
+            output = &mut work => break output,
+            _ = tick.tick() => {
+                let done = started.elapsed().as_secs_f32() / duration.as_secs_f32().max(f32::EPSILON);
+                sink.send(ExecutionEvent::Progress(done.min(1.0)));
+            }
+        }
+    };
+    sink.send(ExecutionEvent::Progress(1.0));
+    output
+}
@@ -0,0 +1,100 @@
+//! W3C trace context shared by Conductor, Fortress and Forge
+//!
+//! A task's `trace_id` is fixed when it is submitted; each hop records its own
+//! `span_id` and forwards the context as a `traceparent` header.
+
+use serde::{Deserialize, Serialize};
+
+/// Header carrying the trace context between services
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Trace and span ids in W3C `traceparent` form
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceContext {
+    /// 32 lowercase hex characters
+    pub trace_id: String,
+    /// 16 lowercase hex characters
+    pub span_id: String,
+}
+
+impl TraceContext {
+    /// Start a new trace
+    pub fn new() -> Self {
+        Self {
+            trace_id: uuid::Uuid::new_v4().simple().to_string(),
+            span_id: new_span_id(),
+        }
+    }
+
+    /// A new span in the same trace
+    pub fn child(&self) -> Self {
+        Self {
+            trace_id: self.trace_id.clone(),
+            span_id: new_span_id(),
+        }
+    }
+
+    /// Render as a sampled `traceparent` header value
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-01", self.trace_id, self.span_id)
+    }
+
+    /// Parse a version 00 `traceparent` header value
+    pub fn parse(traceparent: &str) -> Option<Self> {
+        let mut parts = traceparent.trim().split('-');
+        let (version, trace_id, span_id, flags) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some()
+            || version != "00"
+            || !is_hex_id(trace_id, 32)
+            || !is_hex_id(span_id, 16)
+            || flags.len() != 2
+            || !flags.bytes().all(|b| b.is_ascii_hexdigit())
+        {
+            return None;
+        }
+
+        Some(Self {
+            trace_id: trace_id.to_string(),
+            span_id: span_id.to_string(),
+        })
+    }
+}
+
+impl Default for TraceContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn new_span_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..16].to_string()
+}
+
+/// Lowercase hex of the given length that is not all zeros
+fn is_hex_id(id: &str, len: usize) -> bool {
+    id.len() == len
+        && id.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+        && id.bytes().any(|b| b != b'0')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traceparent_roundtrip() {
+        let trace = TraceContext::new();
+        let parsed = TraceContext::parse(&trace.traceparent()).unwrap();
+        assert_eq!(parsed, trace);
+
+        let child = trace.child();
+        assert_eq!(child.trace_id, trace.trace_id);
+        assert_ne!(child.span_id, trace.span_id);
+
+        assert!(TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_some());
+        assert!(TraceContext::parse("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none());
+        assert!(TraceContext::parse("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_none());
+        assert!(TraceContext::parse("00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01").is_none());
+        assert!(TraceContext::parse("not a traceparent").is_none());
+    }
+}
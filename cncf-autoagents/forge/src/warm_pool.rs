@@ -0,0 +1,140 @@
+//! Module Warm Pool
+//!
+//! Keeps modules prepared for instantiation, keyed by module id and version,
+//! so repeat executions skip linking them against the host functions. Only
+//! pre-instantiation state is pooled: every execution still gets a fresh
+//! instance and store. Entries idle past the TTL are evicted, and the least
+//! recently used entry makes room once the pool is full.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "wasmtime")]
+use crate::executor::PreparedModule;
+
+/// Warm pool size and idle TTL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WarmPoolConfig {
+    /// Module versions kept warm at once; 0 disables the pool
+    pub max_instances: usize,
+    pub idle_ttl_ms: u64,
+}
+
+impl Default for WarmPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_instances: 64,
+            idle_ttl_ms: 300_000,
+        }
+    }
+}
+
+/// Snapshot returned by `Forge::warm_pool_stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WarmPoolStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries dropped for being idle past the TTL or to make room
+    pub evictions: u64,
+    pub warm_instances: usize,
+}
+
+/// Pre-instantiation state of one module version
+#[derive(Clone)]
+pub(crate) struct WarmInstance {
+    pub blob_hash: String,
+    /// Present for modules executed by wasmtime
+    #[cfg(feature = "wasmtime")]
+    pub prepared: Option<PreparedModule>,
+}
+
+struct WarmEntry {
+    instance: WarmInstance,
+    last_used: Instant,
+}
+
+/// Warm instances of recently executed module versions
+pub(crate) struct WarmPool {
+    config: WarmPoolConfig,
+    entries: HashMap<(String, String), WarmEntry>,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl WarmPool {
+    pub fn new(config: WarmPoolConfig) -> Self {
+        Self {
+            config,
+            entries: HashMap::new(),
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    /// Warm instance of a module version built from `blob_hash`, counting a hit or a miss
+    pub fn checkout(&mut self, module_id: &str, version: &str, blob_hash: &str) -> Option<WarmInstance> {
+        self.evict_idle();
+        let key = (module_id.to_string(), version.to_string());
+        match self.entries.get_mut(&key).filter(|entry| entry.instance.blob_hash == blob_hash) {
+            Some(entry) => {
+                entry.last_used = Instant::now();
+                self.hits += 1;
+                Some(entry.instance.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Keep an instance warm, evicting the least recently used entry if the pool is full
+    pub fn insert(&mut self, module_id: &str, version: &str, instance: WarmInstance) {
+        if self.config.max_instances == 0 {
+            return;
+        }
+        self.evict_idle();
+
+        let key = (module_id.to_string(), version.to_string());
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.config.max_instances {
+            let oldest = self.entries.iter().min_by_key(|(_, entry)| entry.last_used).map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+                self.evictions += 1;
+            }
+        }
+        self.entries.insert(key, WarmEntry { instance, last_used: Instant::now() });
+    }
+
+    /// Drop the warm instances of a module, or of one of its versions
+    pub fn remove(&mut self, module_id: &str, version: Option<&str>) {
+        self.entries.retain(|(id, v), _| id != module_id || version.is_some_and(|version| v != version));
+    }
+
+    /// Drop the warm instances built from a blob
+    #[cfg(feature = "wasmtime")]
+    pub fn remove_blob(&mut self, blob_hash: &str) {
+        self.entries.retain(|_, entry| entry.instance.blob_hash != blob_hash);
+    }
+
+    pub fn stats(&mut self) -> WarmPoolStats {
+        self.evict_idle();
+        WarmPoolStats {
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            warm_instances: self.entries.len(),
+        }
+    }
+
+    fn evict_idle(&mut self) {
+        let ttl = Duration::from_millis(self.config.idle_ttl_ms);
+        let before = self.entries.len();
+        self.entries.retain(|_, entry| entry.last_used.elapsed() < ttl);
+        self.evictions += (before - self.entries.len()) as u64;
+    }
+}
@@ -0,0 +1,340 @@
+//! WASI Linking
+//!
+//! Decides which `wasi_snapshot_preview1` imports a module may link under its
+//! sandbox profile, and captures stdio output with size caps. Imports outside
+//! the profile's allow-list fail before instantiation with an error naming the
+//! import and the profile that would permit it.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Import module name of WASI preview 1
+pub const WASI_PREVIEW1: &str = "wasi_snapshot_preview1";
+
+/// Import module name of Forge's own host functions
+pub const FORGE_HOST: &str = "forge";
+
+/// Profile used when a module does not name one
+pub const DEFAULT_PROFILE: &str = "stdio";
+
+/// WASI interfaces a sandbox profile can allow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WasiInterface {
+    /// Wall and monotonic clocks
+    Clocks,
+    /// Host randomness
+    Random,
+    /// stdin (empty), stdout and stderr captured into the result
+    Stdio,
+    /// Environment variables injected by the policy, nothing from the host
+    Environ,
+    /// Outbound HTTP through `forge::host_http_request`, subject to the
+    /// egress policy; no default profile allows it
+    Http,
+}
+
+/// WASI settings of a security policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WasiPolicy {
+    /// Allowed interfaces per sandbox profile name
+    pub profiles: BTreeMap<String, Vec<WasiInterface>>,
+    /// Variables visible to modules whose profile allows `environ`
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Bytes of stdout kept per execution
+    pub max_stdout_bytes: usize,
+    /// Bytes of stderr kept per execution
+    pub max_stderr_bytes: usize,
+}
+
+impl Default for WasiPolicy {
+    fn default() -> Self {
+        use WasiInterface::*;
+
+        Self {
+            profiles: BTreeMap::from([
+                ("pure".to_string(), vec![]),
+                ("stdio".to_string(), vec![Stdio, Environ]),
+                ("standard".to_string(), vec![Clocks, Random, Stdio, Environ]),
+            ]),
+            env: BTreeMap::new(),
+            max_stdout_bytes: 64 * 1024,
+            max_stderr_bytes: 16 * 1024,
+        }
+    }
+}
+
+/// Reasons a module's WASI imports cannot be linked
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum WasiError {
+    #[error("unknown sandbox profile '{0}'")]
+    UnknownProfile(String),
+    #[error("invalid WASM module: {0}")]
+    InvalidModule(String),
+    #[error("import '{import}' is not allowed by sandbox profile '{profile}'{}", permitted_by_hint(.permitted_by))]
+    NotAllowed {
+        import: String,
+        profile: String,
+        /// Profiles whose allow-list covers the import
+        permitted_by: Vec<String>,
+    },
+    #[error("import '{import}' is not supported by any sandbox profile (requested under '{profile}')")]
+    Unsupported { import: String, profile: String },
+}
+
+fn permitted_by_hint(profiles: &[String]) -> String {
+    if profiles.is_empty() {
+        String::new()
+    } else {
+        format!("; it is permitted by profile '{}'", profiles.join("', '"))
+    }
+}
+
+/// How a preview 1 function is linked
+enum ImportClass {
+    /// Linked for every module (`proc_exit`, `sched_yield`, `forge::heartbeat`)
+    Always,
+    Interface(WasiInterface),
+    /// Sockets, filesystem paths and anything else Forge does not provide
+    Unsupported,
+}
+
+fn classify(module: &str, function: &str) -> ImportClass {
+    use WasiInterface::*;
+
+    if module == FORGE_HOST {
+        return match function {
+            "host_http_request" => ImportClass::Interface(Http),
+            "heartbeat" => ImportClass::Always,
+            _ => ImportClass::Unsupported,
+        };
+    }
+    match function {
+        "proc_exit" | "sched_yield" => ImportClass::Always,
+        "clock_res_get" | "clock_time_get" | "poll_oneoff" => ImportClass::Interface(Clocks),
+        "random_get" => ImportClass::Interface(Random),
+        "fd_write" | "fd_read" | "fd_close" | "fd_seek" | "fd_fdstat_get" => ImportClass::Interface(Stdio),
+        "environ_get" | "environ_sizes_get" | "args_get" | "args_sizes_get" => ImportClass::Interface(Environ),
+        _ => ImportClass::Unsupported,
+    }
+}
+
+impl WasiPolicy {
+    /// Allow-list of a profile
+    pub fn allowed(&self, profile: &str) -> Result<&[WasiInterface], WasiError> {
+        self.profiles
+            .get(profile)
+            .map(Vec::as_slice)
+            .ok_or_else(|| WasiError::UnknownProfile(profile.to_string()))
+    }
+
+    /// Check the module's WASI imports against `profile` and prepare its context
+    pub fn link(&self, profile: &str, wasm: &[u8]) -> Result<WasiContext, WasiError> {
+        let allowed = self.allowed(profile)?;
+
+        let mut interfaces = Vec::new();
+        for (module, function) in host_imports(wasm)? {
+            let import = format!("{}::{}", module, function);
+            match classify(&module, &function) {
+                ImportClass::Always => {}
+                ImportClass::Interface(interface) if allowed.contains(&interface) => interfaces.push(interface),
+                ImportClass::Interface(interface) => {
+                    return Err(WasiError::NotAllowed {
+                        import,
+                        profile: profile.to_string(),
+                        permitted_by: self
+                            .profiles
+                            .iter()
+                            .filter(|(_, allowed)| allowed.contains(&interface))
+                            .map(|(name, _)| name.clone())
+                            .collect(),
+                    });
+                }
+                ImportClass::Unsupported => {
+                    return Err(WasiError::Unsupported {
+                        import,
+                        profile: profile.to_string(),
+                    });
+                }
+            }
+        }
+        interfaces.sort();
+        interfaces.dedup();
+
+        let mut context = self.context(profile)?;
+        context.interfaces = interfaces;
+        Ok(context)
+    }
+
+    /// Fresh WASI state for an execution under `profile`, without import checks
+    pub fn context(&self, profile: &str) -> Result<WasiContext, WasiError> {
+        let allowed = self.allowed(profile)?;
+        let env = if allowed.contains(&WasiInterface::Environ) {
+            self.env.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+        } else {
+            Vec::new()
+        };
+
+        Ok(WasiContext {
+            profile: profile.to_string(),
+            interfaces: Vec::new(),
+            env,
+            stdout: CappedPipe::new(self.max_stdout_bytes),
+            stderr: CappedPipe::new(self.max_stderr_bytes),
+        })
+    }
+}
+
+/// Functions a module imports from WASI preview 1 and the Forge host, as `(module, name)`
+fn host_imports(wasm: &[u8]) -> Result<Vec<(String, String)>, WasiError> {
+    let mut functions = Vec::new();
+    for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+        let payload = payload.map_err(|e| WasiError::InvalidModule(e.to_string()))?;
+        if let wasmparser::Payload::ImportSection(reader) = payload {
+            for import in reader {
+                let import = import.map_err(|e| WasiError::InvalidModule(e.to_string()))?;
+                if import.module == WASI_PREVIEW1 || import.module == FORGE_HOST {
+                    functions.push((import.module.to_string(), import.name.to_string()));
+                }
+            }
+        }
+    }
+    Ok(functions)
+}
+
+/// Per-execution WASI state handed to the executor
+#[derive(Debug)]
+pub struct WasiContext {
+    pub profile: String,
+    /// Interfaces the module actually imports
+    pub interfaces: Vec<WasiInterface>,
+    /// Injected environment, empty unless the profile allows `environ`
+    pub env: Vec<(String, String)>,
+    pub stdout: CappedPipe,
+    pub stderr: CappedPipe,
+}
+
+impl WasiContext {
+    /// Captured output, or `None` if nothing was written
+    pub fn into_stdio(self) -> Option<CapturedStdio> {
+        if self.stdout.buf.is_empty() && self.stderr.buf.is_empty() && !self.stdout.truncated && !self.stderr.truncated {
+            return None;
+        }
+
+        Some(CapturedStdio {
+            truncated: self.stdout.truncated || self.stderr.truncated,
+            stdout: String::from_utf8_lossy(&self.stdout.buf).into_owned(),
+            stderr: String::from_utf8_lossy(&self.stderr.buf).into_owned(),
+        })
+    }
+}
+
+/// Output stream that keeps at most `cap` bytes and drops the rest
+#[derive(Debug)]
+pub struct CappedPipe {
+    buf: Vec<u8>,
+    cap: usize,
+    truncated: bool,
+}
+
+impl CappedPipe {
+    pub fn new(cap: usize) -> Self {
+        Self {
+            buf: Vec::new(),
+            cap,
+            truncated: false,
+        }
+    }
+
+    /// Append output; always reports the full length as written so modules do not retry
+    pub fn write(&mut self, bytes: &[u8]) -> usize {
+        let room = self.cap.saturating_sub(self.buf.len());
+        if bytes.len() > room {
+            self.truncated = true;
+        }
+        self.buf.extend_from_slice(&bytes[..bytes.len().min(room)]);
+        bytes.len()
+    }
+}
+
+/// stdio output of an execution
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapturedStdio {
+    pub stdout: String,
+    pub stderr: String,
+    /// Output beyond the policy caps was dropped
+    pub truncated: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLOCK_MODULE: &str = r#"
+        (module
+            (import "wasi_snapshot_preview1" "clock_time_get" (func (param i32 i64 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+            (memory (export "memory") 1))
+    "#;
+
+    const SOCKET_MODULE: &str = r#"
+        (module
+            (import "wasi_snapshot_preview1" "sock_accept" (func (param i32 i32 i32) (result i32)))
+            (memory (export "memory") 1))
+    "#;
+
+    #[test]
+    fn test_allowed_imports_link() {
+        let mut policy = WasiPolicy::default();
+        policy.env.insert("REGION".to_string(), "eu-west-1".to_string());
+
+        let context = policy.link("standard", &wat::parse_str(CLOCK_MODULE).unwrap()).unwrap();
+        assert_eq!(context.interfaces, vec![WasiInterface::Clocks, WasiInterface::Stdio]);
+        assert_eq!(context.env, vec![("REGION".to_string(), "eu-west-1".to_string())]);
+
+        let context = policy.link("pure", &wat::parse_str("(module)").unwrap()).unwrap();
+        assert!(context.env.is_empty());
+    }
+
+    #[test]
+    fn test_denied_imports_are_named() {
+        let policy = WasiPolicy::default();
+
+        let err = policy.link("stdio", &wat::parse_str(CLOCK_MODULE).unwrap()).unwrap_err();
+        assert_eq!(
+            err,
+            WasiError::NotAllowed {
+                import: "wasi_snapshot_preview1::clock_time_get".to_string(),
+                profile: "stdio".to_string(),
+                permitted_by: vec!["standard".to_string()],
+            }
+        );
+        assert!(err.to_string().contains("permitted by profile 'standard'"));
+
+        let err = policy.link("standard", &wat::parse_str(SOCKET_MODULE).unwrap()).unwrap_err();
+        assert!(matches!(&err, WasiError::Unsupported { import, .. } if import == "wasi_snapshot_preview1::sock_accept"));
+
+        assert_eq!(policy.link("gpu", &[]).unwrap_err(), WasiError::UnknownProfile("gpu".to_string()));
+    }
+
+    #[test]
+    fn test_stdio_capture_is_capped() {
+        let policy = WasiPolicy {
+            max_stdout_bytes: 4,
+            ..Default::default()
+        };
+        let mut context = policy.context("stdio").unwrap();
+        assert!(policy.context("stdio").unwrap().into_stdio().is_none());
+
+        assert_eq!(context.stdout.write(b"hello"), 5);
+        context.stdout.write(b"!");
+        context.stderr.write(b"warn");
+        let stdio = context.into_stdio().unwrap();
+        assert_eq!(stdio.stdout, "hell");
+        assert_eq!(stdio.stderr, "warn");
+        assert!(stdio.truncated);
+    }
+}
@@ -0,0 +1,28 @@
+;; Forge module ABI example: echoes its JSON input back as output and writes
+;; a line to stdout through WASI.
+(module
+  (import "wasi_snapshot_preview1" "fd_write"
+    (func $fd_write (param i32 i32 i32 i32) (result i32)))
+
+  (memory (export "memory") 1)
+  (data (i32.const 16) "echo\n")
+
+  ;; Bump allocator starting after the static data
+  (global $heap (mut i32) (i32.const 1024))
+
+  ;; Reserve 4 bytes before each buffer so `run` can prefix the output length
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (i32.add (global.get $heap) (i32.const 4)))
+    (global.set $heap (i32.add (local.get $ptr) (local.get $len)))
+    (local.get $ptr))
+
+  (func (export "run") (param $ptr i32) (param $len i32) (result i32)
+    ;; iovec at 0 pointing at "echo\n", bytes written stored at 8
+    (i32.store (i32.const 0) (i32.const 16))
+    (i32.store (i32.const 4) (i32.const 5))
+    (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))
+
+    ;; The input is the output: prefix it with its length
+    (i32.store (i32.sub (local.get $ptr) (i32.const 4)) (local.get $len))
+    (i32.sub (local.get $ptr) (i32.const 4))))
@@ -0,0 +1,189 @@
+//! End-to-end executions of real WASM modules through `Forge::execute_module`
+
+#![cfg(feature = "wasmtime")]
+
+use forge::{EgressDestination, EgressPolicy, Forge, ForgeEvent, SecurityPolicy, WasiInterface, WasmModule};
+
+const ECHO: &str = include_str!("fixtures/echo.wat");
+
+/// Minimal ABI module whose `run` body is `body`
+fn abi_module(body: &str) -> Vec<u8> {
+    wat::parse_str(format!(
+        r#"(module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+            (func (export "run") (param i32 i32) (result i32) {body}))"#
+    ))
+    .unwrap()
+}
+
+fn module(id: &str, sandbox_profile: Option<&str>) -> WasmModule {
+    WasmModule {
+        id: id.to_string(),
+        name: id.to_string(),
+        version: "1.0.0".to_string(),
+        capabilities: vec![],
+        max_memory_mb: 16,
+        max_execution_time_ms: 2000,
+        checksum: "test-checksum".to_string(),
+        maintenance_hooks: vec![],
+        sandbox_profile: sandbox_profile.map(str::to_string),
+        rate_limit: None,
+    }
+}
+
+#[tokio::test]
+async fn test_echo_fixture_end_to_end() {
+    let forge = Forge::new(SecurityPolicy::default());
+    forge.load_module_binary(module("echo", None), &wat::parse_str(ECHO).unwrap()).await.unwrap();
+
+    let input = serde_json::json!({"greeting": "hello", "n": [1, 2, 3]});
+    let result = forge.execute_module("echo", input.clone()).await.unwrap();
+
+    assert!(result.success, "{:?}", result);
+    assert_eq!(result.output, input);
+    assert_eq!(result.memory_used_kb, 64);
+    assert!(result.security_violations.is_empty());
+    assert_eq!(result.stdio.unwrap().stdout, "echo\n");
+}
+
+#[tokio::test]
+async fn test_echo_fixture_rejected_by_pure_profile() {
+    let forge = Forge::new(SecurityPolicy::default());
+    let wasm = wat::parse_str(ECHO).unwrap();
+
+    assert!(forge.load_module_binary(module("echo", Some("pure")), &wasm).await.is_err());
+    assert!(forge.get_module("echo").await.is_none());
+}
+
+#[tokio::test]
+async fn test_runaway_module_exhausts_fuel() {
+    let forge = Forge::new(SecurityPolicy {
+        max_fuel: 100_000,
+        ..Default::default()
+    });
+    forge.load_module_binary(module("spin", None), &abi_module("(loop (br 0)) (i32.const 0)")).await.unwrap();
+
+    let result = forge.execute_module("spin", serde_json::json!({})).await.unwrap();
+    assert!(!result.success);
+    assert_eq!(result.security_violations, vec!["fuel_exhausted"]);
+}
+
+#[tokio::test]
+async fn test_memory_growth_past_limit_and_traps() {
+    let forge = Forge::new(SecurityPolicy::default());
+    // 512 pages is 32MB, over the module's 16MB limit
+    let grow = abi_module("(if (i32.eq (memory.grow (i32.const 512)) (i32.const -1)) (then unreachable)) (i32.const 0)");
+    forge.load_module_binary(module("grow", None), &grow).await.unwrap();
+    forge.load_module_binary(module("crash", None), &abi_module("unreachable")).await.unwrap();
+
+    let result = forge.execute_module("grow", serde_json::json!({})).await.unwrap();
+    assert!(!result.success);
+    assert_eq!(result.security_violations, vec!["memory_limit"]);
+
+    let result = forge.execute_module("crash", serde_json::json!({})).await.unwrap();
+    assert!(!result.success);
+    assert_eq!(result.security_violations, vec!["trap"]);
+}
+
+#[tokio::test]
+async fn test_warm_pool_skips_linking_on_repeat_executions() {
+    let forge = Forge::new(SecurityPolicy::default());
+    forge.load_module_binary(module("echo", None), &wat::parse_str(ECHO).unwrap()).await.unwrap();
+    let input = serde_json::json!({"n": 1});
+
+    let cold = forge.execute_module("echo", input.clone()).await.unwrap();
+    for _ in 0..5 {
+        let warm = forge.execute_module("echo", input.clone()).await.unwrap();
+        assert_eq!(warm.output, cold.output);
+    }
+    let stats = forge.warm_pool_stats();
+    assert_eq!((stats.misses, stats.hits), (1, 5));
+
+    // Warming ahead of time turns the first execution into a hit
+    forge.unload_module("echo").await.unwrap();
+    forge.load_module_binary(module("echo", None), &wat::parse_str(ECHO).unwrap()).await.unwrap();
+    forge.warm_module("echo").await.unwrap();
+    forge.execute_module("echo", input).await.unwrap();
+    assert_eq!(forge.warm_pool_stats().hits, 6);
+}
+
+/// Module whose `run` passes its input to `host_http_request` and returns the response
+const HTTP_PROXY: &str = r#"(module
+    (import "forge" "host_http_request" (func $http (param i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+    (func (export "run") (param i32 i32) (result i32) (call $http (local.get 0) (local.get 1))))"#;
+
+#[tokio::test]
+async fn test_host_http_request_through_allow_list() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\npong").await;
+        }
+    });
+
+    let mut policy = SecurityPolicy::default();
+    policy.wasi.profiles.insert("network".to_string(), vec![WasiInterface::Http]);
+    policy.egress = EgressPolicy {
+        allowed_destinations: vec![EgressDestination::new("127.0.0.1", Some(port))],
+        allow_plaintext: true,
+        max_requests_per_execution: 1,
+        ..Default::default()
+    };
+    let forge = Forge::new(policy);
+    let wasm = wat::parse_str(HTTP_PROXY).unwrap();
+    assert!(forge.load_module_binary(module("proxy", Some("standard")), &wasm).await.is_err());
+    let proxy = WasmModule { capabilities: vec!["http".to_string()], ..module("proxy", Some("network")) };
+    forge.load_module_binary(proxy, &wasm).await.unwrap();
+
+    let result = forge.execute_module("proxy", serde_json::json!({"url": format!("http://127.0.0.1:{}/ping", port)})).await.unwrap();
+    assert!(result.success, "{:?}", result);
+    assert_eq!(result.output["status"], 200);
+    assert_eq!(result.output["body"], "pong");
+    assert_eq!(result.resources.http_bytes, 4);
+
+    let result = forge.execute_module("proxy", serde_json::json!({"url": "https://example.com/"})).await.unwrap();
+    assert_eq!(result.output["error"]["code"], "destination_denied");
+    assert_eq!(result.resources.http_requests[0].error.as_deref(), Some("destination_denied"));
+}
+
+/// Module whose `run` reports progress through `heartbeat`, then sends a
+/// malformed payload; it traps unless the host answers 0 and then `EINVAL`
+const HEARTBEAT: &str = r#"(module
+    (import "forge" "heartbeat" (func $heartbeat (param i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 0) "{\"step\":1}")
+    (data (i32.const 16) "nope")
+    (data (i32.const 64) "\04\00\00\00true")
+    (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+    (func (export "run") (param i32 i32) (result i32)
+        (if (i32.ne (call $heartbeat (i32.const 0) (i32.const 10)) (i32.const 0)) (then unreachable))
+        (if (i32.ne (call $heartbeat (i32.const 16) (i32.const 4)) (i32.const 28)) (then unreachable))
+        (i32.const 64)))"#;
+
+#[tokio::test]
+async fn test_heartbeat_host_function_reports_progress() {
+    let forge = Forge::new(SecurityPolicy::default());
+    // Heartbeats need no interface, so even the pure profile links them
+    forge.load_module_binary(module("beating", Some("pure")), &wat::parse_str(HEARTBEAT).unwrap()).await.unwrap();
+    let mut events = forge.subscribe();
+
+    let result = forge.execute_module("beating", serde_json::json!({})).await.unwrap();
+    assert!(result.success, "{:?}", result);
+    assert_eq!(result.output, serde_json::json!(true));
+
+    let mut heartbeats = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if let ForgeEvent::Heartbeat { execution_id, module_id, progress } = event {
+            heartbeats.push((execution_id, module_id, progress));
+        }
+    }
+    assert_eq!(heartbeats, vec![(result.execution_id, "beating".to_string(), Some(serde_json::json!({"step": 1})))]);
+}
@@ -0,0 +1,363 @@
+//! Admin API
+//!
+//! Served by the gateway itself under `/admin/v1`, behind the auth layer.
+//! Callers need the `admin` role on the [`Principal`] the auth layer
+//! authenticated; requests without one, such as on public routes or with
+//! auth disabled, are refused.
+//!
+//! - `GET /admin/v1/overlays` lists the active and upcoming overlays
+//! - `POST /admin/v1/overlays` schedules an overlay
+//! - `DELETE /admin/v1/overlays/{id}` cancels one
+//! - `GET /admin/v1/usage?principal=&from=&to=` reports hourly usage rollups;
+//!   `format=csv` returns them as CSV for chargeback
+//! - `POST /admin/v1/mcp/reload` re-reads the MCP registry sources and swaps
+//!   in the new server catalog
+//! - `GET /admin/v1/blue-green` lists blue/green routes and their active sets
+//! - `PUT /admin/v1/blue-green` registers a route's two upstream sets
+//! - `POST /admin/v1/blue-green/switch` flips a route's active set
+//! - `GET /admin/v1/blue-green/history?route=` lists past switches and their outcomes
+//! - `POST /admin/v1/cache/purge` drops cached responses under a path prefix
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use hyper::{
+    header::CONTENT_TYPE,
+    http::{Method, StatusCode},
+    Body, Request, Response,
+};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::{
+    blue_green::{BlueGreenError, BlueGreenSwitch},
+    config::{BlueGreenDeployment, DeploymentColor},
+    mcp_registry::McpRegistry,
+    middleware::{auth::Principal, CacheMiddleware},
+    overlays::{ConfigOverlay, OverlayError, OverlayScheduler},
+    usage::{self, UsageTracker},
+};
+
+/// Path prefix of the admin API
+pub const ADMIN_PREFIX: &str = "/admin/v1";
+
+const OVERLAYS_PATH: &str = "/admin/v1/overlays";
+
+const USAGE_PATH: &str = "/admin/v1/usage";
+
+const MCP_RELOAD_PATH: &str = "/admin/v1/mcp/reload";
+
+const BLUE_GREEN_PATH: &str = "/admin/v1/blue-green";
+
+const BLUE_GREEN_SWITCH_PATH: &str = "/admin/v1/blue-green/switch";
+
+const BLUE_GREEN_HISTORY_PATH: &str = "/admin/v1/blue-green/history";
+
+const CACHE_PURGE_PATH: &str = "/admin/v1/cache/purge";
+
+/// Body of `POST /admin/v1/overlays`
+#[derive(Debug, Deserialize)]
+pub struct ScheduleOverlayRequest {
+    pub overlay: ConfigOverlay,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+/// Body of `POST /admin/v1/blue-green/switch`
+#[derive(Debug, Deserialize)]
+pub struct SwitchRequest {
+    pub route: String,
+    /// Set to activate; the inactive one when omitted
+    #[serde(default)]
+    pub to: Option<DeploymentColor>,
+}
+
+/// Body of `POST /admin/v1/cache/purge`
+#[derive(Debug, Deserialize)]
+pub struct PurgeCacheRequest {
+    pub path_prefix: String,
+}
+
+/// Whether a path belongs to the admin API
+pub fn is_admin_path(path: &str) -> bool {
+    path == ADMIN_PREFIX || path.starts_with(&format!("{}/", ADMIN_PREFIX))
+}
+
+/// Handle an admin API request
+pub async fn handle(
+    req: Request<Body>,
+    overlays: &OverlayScheduler,
+    usage: &UsageTracker,
+    mcp_registry: &McpRegistry,
+    blue_green: &BlueGreenSwitch,
+    cache: &CacheMiddleware,
+) -> Response<Body> {
+    let admin = req
+        .extensions()
+        .get::<Principal>()
+        .filter(|principal| principal.roles.iter().any(|role| role == "admin"));
+    let Some(admin) = admin else {
+        return error_response(StatusCode::FORBIDDEN, "Admin role required");
+    };
+
+    let caller = admin.subject.clone();
+    let path = req.uri().path().to_string();
+
+    match (req.method().clone(), path.as_str()) {
+        (Method::GET, OVERLAYS_PATH) => json_response(
+            StatusCode::OK,
+            serde_json::json!({
+                "active": overlays.active(),
+                "overlays": overlays.list(),
+            }),
+        ),
+        (Method::POST, OVERLAYS_PATH) => {
+            let body = match hyper::body::to_bytes(req.into_body()).await {
+                Ok(body) => body,
+                Err(_) => return error_response(StatusCode::BAD_REQUEST, "Unreadable request body"),
+            };
+            let request: ScheduleOverlayRequest = match serde_json::from_slice(&body) {
+                Ok(request) => request,
+                Err(e) => return error_response(StatusCode::BAD_REQUEST, &e.to_string()),
+            };
+
+            match overlays.schedule(request.overlay, request.starts_at, request.ends_at, &caller) {
+                Ok(scheduled) => json_response(StatusCode::CREATED, serde_json::json!(scheduled)),
+                Err(e) => overlay_error_response(&e),
+            }
+        }
+        (Method::DELETE, path) if path.starts_with(OVERLAYS_PATH) => {
+            let id = path[OVERLAYS_PATH.len()..].trim_start_matches('/');
+            match overlays.cancel(id) {
+                Ok(()) => Response::builder()
+                    .status(StatusCode::NO_CONTENT)
+                    .body(Body::empty())
+                    .unwrap(),
+                Err(e) => overlay_error_response(&e),
+            }
+        }
+        (Method::GET, USAGE_PATH) => usage_report(req.uri().query().unwrap_or_default(), usage),
+        (Method::POST, MCP_RELOAD_PATH) => match mcp_registry.reload().await {
+            Ok(servers) => {
+                info!("🔄 MCP registry reloaded by {}", caller);
+                json_response(StatusCode::OK, serde_json::json!({ "servers": servers }))
+            }
+            Err(e) => {
+                warn!("🔄 MCP registry reload by {} failed: {}", caller, e);
+                error_response(StatusCode::BAD_GATEWAY, &e.to_string())
+            }
+        },
+        (Method::GET, BLUE_GREEN_PATH) => json_response(
+            StatusCode::OK,
+            serde_json::json!({ "deployments": blue_green.deployments() }),
+        ),
+        (Method::PUT, BLUE_GREEN_PATH) => {
+            let deployment: BlueGreenDeployment = match read_json(req).await {
+                Ok(deployment) => deployment,
+                Err(response) => return response,
+            };
+            match blue_green.register(deployment.clone(), &caller) {
+                Ok(()) => json_response(StatusCode::OK, serde_json::json!(deployment)),
+                Err(e) => blue_green_error_response(&e),
+            }
+        }
+        (Method::POST, BLUE_GREEN_SWITCH_PATH) => {
+            let request: SwitchRequest = match read_json(req).await {
+                Ok(request) => request,
+                Err(response) => return response,
+            };
+            match blue_green.switch(&request.route, request.to, &caller) {
+                Ok(record) => json_response(StatusCode::OK, serde_json::json!(record)),
+                Err(e) => blue_green_error_response(&e),
+            }
+        }
+        (Method::GET, BLUE_GREEN_HISTORY_PATH) => {
+            let params = query_params(req.uri().query().unwrap_or_default());
+            let history = blue_green.history(params.get("route").map(String::as_str));
+            json_response(StatusCode::OK, serde_json::json!({ "switches": history }))
+        }
+        (Method::POST, CACHE_PURGE_PATH) => {
+            let request: PurgeCacheRequest = match read_json(req).await {
+                Ok(request) => request,
+                Err(response) => return response,
+            };
+            match cache.purge(&request.path_prefix).await {
+                Ok(purged) => {
+                    info!("🧹 Cache under {} purged by {}", request.path_prefix, caller);
+                    json_response(StatusCode::OK, serde_json::json!({ "purged": purged }))
+                }
+                Err(e) => {
+                    warn!("🧹 Cache purge by {} failed: {}", caller, e);
+                    error_response(StatusCode::BAD_GATEWAY, &e.to_string())
+                }
+            }
+        }
+        _ => error_response(StatusCode::NOT_FOUND, "Unknown admin endpoint"),
+    }
+}
+
+/// Parse a JSON request body, or the error response to send instead
+async fn read_json<T: serde::de::DeserializeOwned>(req: Request<Body>) -> Result<T, Response<Body>> {
+    let body = hyper::body::to_bytes(req.into_body())
+        .await
+        .map_err(|_| error_response(StatusCode::BAD_REQUEST, "Unreadable request body"))?;
+    serde_json::from_slice(&body).map_err(|e| error_response(StatusCode::BAD_REQUEST, &e.to_string()))
+}
+
+/// `GET /admin/v1/usage`; `from` and `to` are RFC 3339 and default to the retained window
+fn usage_report(query: &str, usage: &UsageTracker) -> Response<Body> {
+    let params = query_params(query);
+    let (default_from, default_to) = usage.retained_window();
+    let parse_time = |name: &str, default: DateTime<Utc>| match params.get(name) {
+        Some(value) => DateTime::parse_from_rfc3339(value)
+            .map(|time| time.with_timezone(&Utc))
+            .map_err(|e| format!("Invalid '{}': {}", name, e)),
+        None => Ok(default),
+    };
+    let (from, to) = match (parse_time("from", default_from), parse_time("to", default_to)) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(e), _) | (_, Err(e)) => return error_response(StatusCode::BAD_REQUEST, &e),
+    };
+
+    let rollups = usage.report(params.get("principal").map(String::as_str), from, to);
+    match params.get("format").map(String::as_str) {
+        Some("csv") => Response::builder()
+            .status(StatusCode::OK)
+            .header(CONTENT_TYPE, "text/csv")
+            .header("Content-Disposition", "attachment; filename=\"usage.csv\"")
+            .body(Body::from(usage::to_csv(&rollups)))
+            .unwrap(),
+        None | Some("json") => json_response(
+            StatusCode::OK,
+            serde_json::json!({
+                "from": from,
+                "to": to,
+                "rollups": rollups,
+            }),
+        ),
+        Some(other) => error_response(StatusCode::BAD_REQUEST, &format!("Unsupported format '{}'", other)),
+    }
+}
+
+/// Decode `a=1&b=%2B` query strings
+fn query_params(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (percent_decode(key), percent_decode(value)))
+        .collect()
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[i], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn overlay_error_response(err: &OverlayError) -> Response<Body> {
+    let status = match err {
+        OverlayError::InvalidWindow(_) => StatusCode::BAD_REQUEST,
+        OverlayError::Overlaps { .. } => StatusCode::CONFLICT,
+        OverlayError::NotFound(_) => StatusCode::NOT_FOUND,
+        OverlayError::Store(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    error_response(status, &err.to_string())
+}
+
+fn blue_green_error_response(err: &BlueGreenError) -> Response<Body> {
+    let status = match err {
+        BlueGreenError::UnknownRoute(_) => StatusCode::NOT_FOUND,
+        BlueGreenError::EmptySet(..) => StatusCode::BAD_REQUEST,
+        BlueGreenError::AlreadyActive(_) => StatusCode::CONFLICT,
+    };
+    error_response(status, &err.to_string())
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Body> {
+    json_response(
+        status,
+        serde_json::json!({
+            "error": {
+                "code": status.as_u16(),
+                "message": message,
+                "gateway": "fortress"
+            }
+        }),
+    )
+}
+
+fn json_response(status: StatusCode, body: serde_json::Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{AuthRequirement, AuthRoute, FortressConfig, ServiceAccount, SharedConfig},
+        middleware::AuthMiddleware,
+    };
+    use std::convert::Infallible;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    #[tokio::test]
+    async fn test_admin_role_comes_from_the_principal_not_client_headers() {
+        let mut config = FortressConfig::default();
+        config.auth.service_accounts.insert("ops".to_string(), ServiceAccount {
+            name: "ops".to_string(),
+            token: "ops-token".to_string(),
+            permissions: vec!["admin".to_string()],
+        });
+        let live = SharedConfig::new(config.clone());
+        let admin_api = {
+            let overlays = OverlayScheduler::new(config.clone(), live.clone());
+            let usage = UsageTracker::new(&config.usage);
+            let mcp_registry = McpRegistry::empty(config.mcp.clone());
+            let blue_green = BlueGreenSwitch::new(&config.blue_green);
+            let cache = CacheMiddleware::new(live.clone());
+            tower::service_fn(move |req: Request<Body>| {
+                let (overlays, usage, mcp_registry, blue_green, cache) =
+                    (overlays.clone(), usage.clone(), mcp_registry.clone(), blue_green.clone(), cache.clone());
+                async move { Ok::<_, Infallible>(handle(req, &overlays, &usage, &mcp_registry, &blue_green, &cache).await) }
+            })
+        };
+        let service = ServiceBuilder::new().layer(AuthMiddleware::new(live.clone())).service(admin_api);
+        let status = |token: Option<&str>| {
+            let mut request = Request::get(OVERLAYS_PATH).header("X-User-Roles", "admin").header("X-User-ID", "mallory");
+            if let Some(token) = token {
+                request = request.header("Authorization", format!("Bearer {}", token));
+            }
+            service.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        assert_eq!(status(Some("ops-token")).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(status(None).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+
+        // Spoofed role headers grant nothing where no principal is authenticated
+        let mut public = config.clone();
+        public.auth.routes.push(AuthRoute::new("/admin/*", AuthRequirement::Public));
+        live.replace(public);
+        assert_eq!(status(None).await.unwrap().status(), StatusCode::FORBIDDEN);
+        config.auth.enabled = false;
+        live.replace(config);
+        assert_eq!(status(None).await.unwrap().status(), StatusCode::FORBIDDEN);
+    }
+}
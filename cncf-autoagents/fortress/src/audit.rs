@@ -0,0 +1,231 @@
+//! Request audit log
+//!
+//! Every request gets an `x-request-id`, kept from the client when it sent a
+//! usable one and generated otherwise. The id travels to the upstream, comes
+//! back on the response and is attached to the tracing span the request is
+//! handled in. Once the response is ready, an [`AuditEntry`] with the
+//! principal, matched route, upstream, status, latency and body sizes is
+//! written to the configured [`AuditSink`]: stdout, a rotating file, or a
+//! channel for sinks of the embedding application. `sample_rate` logs a share
+//! of requests, decided by request id so retries of one request agree.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs::{File, OpenOptions},
+    hash::{Hash, Hasher},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use hyper::http::HeaderValue;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::config::{AuditConfig, AuditSinkConfig};
+
+/// Header carrying the request id
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest client-supplied request id that is kept
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// One completed request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub request_id: String,
+    pub method: String,
+    pub path: String,
+    pub principal: String,
+    /// Route pattern that served the request; unset when none matched
+    pub route: Option<String>,
+    /// Upstream the request was sent to
+    pub upstream: Option<String>,
+    pub status: u16,
+    pub latency_ms: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+}
+
+/// Destination of audit entries
+pub trait AuditSink: Send + Sync {
+    fn write(&self, entry: &AuditEntry);
+}
+
+/// Writes each entry to stdout as a JSON line
+pub struct StdoutSink;
+
+impl AuditSink for StdoutSink {
+    fn write(&self, entry: &AuditEntry) {
+        if let Ok(line) = serde_json::to_string(entry) {
+            println!("{}", line);
+        }
+    }
+}
+
+/// Appends JSON lines to a file, rotating it once it passes `max_bytes`
+pub struct FileSink {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: Mutex<(File, u64)>,
+}
+
+impl FileSink {
+    pub fn open(path: &Path, max_bytes: u64, max_files: usize) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_bytes,
+            max_files,
+            file: Mutex::new((file, len)),
+        })
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    /// Shift `path.n` to `path.n+1`, dropping the oldest, and start a new file
+    fn rotate(&self) -> std::io::Result<File> {
+        if self.max_files == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            for n in (1..self.max_files).rev() {
+                let from = self.rotated(n);
+                if from.exists() {
+                    std::fs::rename(&from, self.rotated(n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated(1))?;
+        }
+        OpenOptions::new().create(true).append(true).open(&self.path)
+    }
+}
+
+impl AuditSink for FileSink {
+    fn write(&self, entry: &AuditEntry) {
+        let Ok(mut line) = serde_json::to_vec(entry) else { return };
+        line.push(b'\n');
+
+        let mut file = self.file.lock().unwrap();
+        if file.1 > 0 && file.1 + line.len() as u64 > self.max_bytes {
+            match self.rotate() {
+                Ok(rotated) => *file = (rotated, 0),
+                Err(e) => warn!("📜 Rotating audit log {} failed: {}", self.path.display(), e),
+            }
+        }
+        match file.0.write_all(&line) {
+            Ok(()) => file.1 += line.len() as u64,
+            Err(e) => warn!("📜 Writing audit log {} failed: {}", self.path.display(), e),
+        }
+    }
+}
+
+/// Hands entries to a receiver of the embedding application
+pub struct ChannelSink {
+    sender: mpsc::UnboundedSender<AuditEntry>,
+}
+
+impl ChannelSink {
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<AuditEntry>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { sender }, receiver)
+    }
+}
+
+impl AuditSink for ChannelSink {
+    fn write(&self, entry: &AuditEntry) {
+        // A dropped receiver means nobody is listening any more
+        let _ = self.sender.send(entry.clone());
+    }
+}
+
+/// Sink for `config`; falls back to stdout when the log file cannot be opened
+pub fn sink_for(config: &AuditConfig) -> Arc<dyn AuditSink> {
+    match &config.sink {
+        AuditSinkConfig::Stdout => Arc::new(StdoutSink),
+        AuditSinkConfig::File { path, max_bytes, max_files } => match FileSink::open(path, *max_bytes, *max_files) {
+            Ok(sink) => Arc::new(sink),
+            Err(e) => {
+                warn!("📜 Opening audit log {} failed, logging to stdout: {}", path.display(), e);
+                Arc::new(StdoutSink)
+            }
+        },
+    }
+}
+
+/// The client's request id if usable, else a new one
+pub fn request_id(client_supplied: Option<&HeaderValue>) -> String {
+    client_supplied
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Whether the request with `request_id` is logged at `sample_rate`
+pub fn sampled(request_id: &str, sample_rate: f64) -> bool {
+    if sample_rate >= 1.0 {
+        return true;
+    }
+    let mut hasher = DefaultHasher::new();
+    request_id.hash(&mut hasher);
+    (hasher.finish() % 10_000) < (sample_rate.max(0.0) * 10_000.0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(request_id: &str) -> AuditEntry {
+        AuditEntry {
+            timestamp: Utc::now(),
+            request_id: request_id.to_string(),
+            method: "GET".to_string(),
+            path: "/api/v1/agents".to_string(),
+            principal: "alice".to_string(),
+            route: Some("/api/*".to_string()),
+            upstream: Some("http://agents:8080".to_string()),
+            status: 200,
+            latency_ms: 12,
+            bytes_in: 0,
+            bytes_out: 42,
+        }
+    }
+
+    #[test]
+    fn test_file_sink_rotates_and_sampling_is_stable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let line_len = serde_json::to_vec(&entry("req-0")).unwrap().len() as u64 + 1;
+        let sink = FileSink::open(&path, line_len * 2, 2).unwrap();
+        for i in 0..7 {
+            sink.write(&entry(&format!("req-{}", i)));
+        }
+
+        // Two entries per file; the oldest file beyond `max_files` is gone
+        let ids = |path: PathBuf| -> Vec<String> {
+            std::fs::read_to_string(path)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str::<AuditEntry>(line).unwrap().request_id)
+                .collect()
+        };
+        assert_eq!(ids(path.clone()), ["req-6"]);
+        assert_eq!(ids(sink.rotated(1)), ["req-4", "req-5"]);
+        assert_eq!(ids(sink.rotated(2)), ["req-2", "req-3"]);
+        assert!(!sink.rotated(3).exists());
+
+        let logged = (0..1000).filter(|i| sampled(&format!("req-{}", i), 0.25)).count();
+        assert!((150..350).contains(&logged), "{}", logged);
+        assert_eq!(sampled("req-1", 0.25), sampled("req-1", 0.25));
+        assert!(!sampled("req-1", 0.0));
+    }
+}
@@ -0,0 +1,512 @@
+//! Blue/Green Upstream Switching
+//!
+//! A blue/green route has two upstream sets, one of which takes all of the
+//! route's traffic. A switch flips the active set atomically; the SLO guard
+//! then watches the newly active set's error rate and p95 latency for the
+//! evaluation window, and if either threshold is breached it switches back
+//! and raises an alert. Every switch is kept in a bounded history recording
+//! who made it, when, and how it ended.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+
+use crate::{
+    config::{BlueGreenConfig, BlueGreenDeployment, DeploymentColor, RouteTarget},
+    overlays::{Clock, SystemClock},
+};
+
+/// Name recorded for switches made by the SLO guard
+pub const GUARD_ACTOR: &str = "slo-guard";
+
+/// Rollback alerts buffered for slow subscribers
+const ALERT_CHANNEL_CAPACITY: usize = 64;
+
+/// How a switch ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchOutcome {
+    /// The guard is still watching the new set
+    Evaluating,
+    /// The new set held its SLO for the whole window, or no guard was configured
+    Succeeded,
+    /// The guard switched back after an SLO breach
+    RolledBack,
+    /// Another switch was made before the window ended
+    Superseded,
+}
+
+/// Requests the guard saw from the new set
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GuardStats {
+    pub requests: usize,
+    pub errors: usize,
+    pub error_rate: f64,
+    pub p95_latency_ms: u64,
+}
+
+/// One switch of a route's active set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SwitchRecord {
+    pub id: String,
+    pub route: String,
+    pub from: DeploymentColor,
+    pub to: DeploymentColor,
+    pub switched_by: String,
+    pub switched_at: DateTime<Utc>,
+    pub outcome: SwitchOutcome,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Threshold that was breached, for rollbacks
+    pub reason: Option<String>,
+    pub stats: GuardStats,
+}
+
+/// Blue/green errors
+#[derive(Debug, thiserror::Error)]
+pub enum BlueGreenError {
+    #[error("No blue/green deployment for route '{0}'")]
+    UnknownRoute(String),
+    #[error("The {0:?} set of route '{1}' has no targets")]
+    EmptySet(DeploymentColor, String),
+    #[error("The {0:?} set is already active")]
+    AlreadyActive(DeploymentColor),
+}
+
+#[derive(Debug)]
+struct Sample {
+    success: bool,
+    latency_ms: u64,
+}
+
+/// Watch over the set a switch made active
+#[derive(Debug)]
+struct Guard {
+    record_id: String,
+    color: DeploymentColor,
+    ends_at: DateTime<Utc>,
+    samples: Vec<Sample>,
+}
+
+impl Guard {
+    fn stats(&self) -> GuardStats {
+        let requests = self.samples.len();
+        let errors = self.samples.iter().filter(|sample| !sample.success).count();
+        let mut latencies: Vec<u64> = self.samples.iter().map(|sample| sample.latency_ms).collect();
+        latencies.sort_unstable();
+        let p95_latency_ms = match requests {
+            0 => 0,
+            n => latencies[(n * 95).div_ceil(100) - 1],
+        };
+        GuardStats {
+            requests,
+            errors,
+            error_rate: if requests == 0 { 0.0 } else { errors as f64 / requests as f64 },
+            p95_latency_ms,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Deployment {
+    spec: BlueGreenDeployment,
+    guard: Option<Guard>,
+}
+
+#[derive(Debug, Default)]
+struct SwitchState {
+    deployments: HashMap<String, Deployment>,
+    /// Oldest first
+    history: VecDeque<SwitchRecord>,
+}
+
+impl SwitchState {
+    fn record_mut(&mut self, id: &str) -> Option<&mut SwitchRecord> {
+        self.history.iter_mut().rev().find(|record| record.id == id)
+    }
+}
+
+/// Active upstream set of each blue/green route, with the SLO guard and switch history
+#[derive(Clone)]
+pub struct BlueGreenSwitch {
+    config: Arc<BlueGreenConfig>,
+    clock: Arc<dyn Clock>,
+    state: Arc<Mutex<SwitchState>>,
+    alerts: broadcast::Sender<SwitchRecord>,
+}
+
+impl BlueGreenSwitch {
+    /// Switch with the deployments of `config` registered
+    pub fn new(config: &BlueGreenConfig) -> Self {
+        let mut state = SwitchState::default();
+        for deployment in &config.deployments {
+            state.deployments.insert(deployment.route.clone(), Deployment {
+                spec: deployment.clone(),
+                guard: None,
+            });
+        }
+        let (alerts, _) = broadcast::channel(ALERT_CHANNEL_CAPACITY);
+        Self {
+            config: Arc::new(config.clone()),
+            clock: Arc::new(SystemClock),
+            state: Arc::new(Mutex::new(state)),
+            alerts,
+        }
+    }
+
+    /// Use a custom clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Register a route's upstream sets, replacing any previous registration
+    pub fn register(&self, deployment: BlueGreenDeployment, registered_by: &str) -> Result<(), BlueGreenError> {
+        for color in [DeploymentColor::Blue, DeploymentColor::Green] {
+            if deployment.targets(color).is_empty() {
+                return Err(BlueGreenError::EmptySet(color, deployment.route.clone()));
+            }
+        }
+
+        info!(
+            "🔀 Blue/green deployment for {} registered by {} ({:?} active)",
+            deployment.route, registered_by, deployment.active
+        );
+        let mut state = self.state.lock().unwrap();
+        let previous = state.deployments.insert(deployment.route.clone(), Deployment {
+            spec: deployment,
+            guard: None,
+        });
+        if let Some(guard) = previous.and_then(|previous| previous.guard) {
+            self.finish(&mut state, &guard, SwitchOutcome::Superseded, None);
+        }
+        Ok(())
+    }
+
+    /// Registered deployments, ordered by route
+    pub fn deployments(&self) -> Vec<BlueGreenDeployment> {
+        let mut deployments: Vec<BlueGreenDeployment> = self.state.lock().unwrap()
+            .deployments
+            .values()
+            .map(|deployment| deployment.spec.clone())
+            .collect();
+        deployments.sort_by(|a, b| a.route.cmp(&b.route));
+        deployments
+    }
+
+    /// Active set of a route and its targets, if the route is blue/green
+    pub fn active(&self, route: &str) -> Option<(DeploymentColor, Vec<RouteTarget>)> {
+        let state = self.state.lock().unwrap();
+        let spec = &state.deployments.get(route)?.spec;
+        Some((spec.active, spec.targets(spec.active).to_vec()))
+    }
+
+    /// Make `to` the active set of a route, or the inactive one when `None`
+    pub fn switch(
+        &self,
+        route: &str,
+        to: Option<DeploymentColor>,
+        switched_by: &str,
+    ) -> Result<SwitchRecord, BlueGreenError> {
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        let deployment = state.deployments.get_mut(route).ok_or_else(|| BlueGreenError::UnknownRoute(route.to_string()))?;
+        let from = deployment.spec.active;
+        let to = to.unwrap_or(from.other());
+        if to == from {
+            return Err(BlueGreenError::AlreadyActive(to));
+        }
+
+        let guard_config = &self.config.guard;
+        let record = SwitchRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            route: route.to_string(),
+            from,
+            to,
+            switched_by: switched_by.to_string(),
+            switched_at: now,
+            outcome: if guard_config.enabled { SwitchOutcome::Evaluating } else { SwitchOutcome::Succeeded },
+            finished_at: (!guard_config.enabled).then_some(now),
+            reason: None,
+            stats: GuardStats::default(),
+        };
+        deployment.spec.active = to;
+        let superseded = std::mem::replace(
+            &mut deployment.guard,
+            guard_config.enabled.then(|| Guard {
+                record_id: record.id.clone(),
+                color: to,
+                ends_at: now + chrono::Duration::milliseconds(guard_config.evaluation_window_ms as i64),
+                samples: Vec::new(),
+            }),
+        );
+        if let Some(guard) = superseded {
+            self.finish(&mut state, &guard, SwitchOutcome::Superseded, None);
+        }
+
+        info!("🔀 {} switched {} from {:?} to {:?}", switched_by, route, from, to);
+        state.history.push_back(record.clone());
+        while state.history.len() > self.config.history_limit {
+            state.history.pop_front();
+        }
+        Ok(record)
+    }
+
+    /// Record the outcome of a request served by the `color` set of a route.
+    ///
+    /// Returns the switch record when the request pushed the guarded set over
+    /// its SLO and the route was rolled back.
+    pub fn record(&self, route: &str, color: DeploymentColor, success: bool, latency: Duration) -> Option<SwitchRecord> {
+        let now = self.clock.now();
+        let guard_config = &self.config.guard;
+        let mut state = self.state.lock().unwrap();
+        let deployment = state.deployments.get_mut(route)?;
+        let guard = deployment.guard.as_mut().filter(|guard| guard.color == color && now < guard.ends_at)?;
+        guard.samples.push(Sample {
+            success,
+            latency_ms: latency.as_millis() as u64,
+        });
+
+        let stats = guard.stats();
+        if stats.requests < guard_config.min_requests {
+            return None;
+        }
+        let reason = if stats.error_rate > guard_config.max_error_rate {
+            format!("error rate {:.3} exceeds {:.3}", stats.error_rate, guard_config.max_error_rate)
+        } else if stats.p95_latency_ms > guard_config.max_p95_latency_ms {
+            format!("p95 latency {}ms exceeds {}ms", stats.p95_latency_ms, guard_config.max_p95_latency_ms)
+        } else {
+            return None;
+        };
+
+        // Switch back before anything else is routed to the failing set
+        deployment.spec.active = color.other();
+        let guard = deployment.guard.take()?;
+        let record = self.finish(&mut state, &guard, SwitchOutcome::RolledBack, Some(reason))?;
+        error!(
+            "🚨 {} rolled back from {:?} to {:?} by {}: {}",
+            route, color, color.other(), GUARD_ACTOR, record.reason.as_deref().unwrap_or_default()
+        );
+        let _ = self.alerts.send(record.clone());
+        Some(record)
+    }
+
+    /// Close guards whose window ended without a breach; returns the switches they close
+    pub fn tick(&self) -> Vec<SwitchRecord> {
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        let expired: Vec<Guard> = state.deployments
+            .values_mut()
+            .filter(|deployment| deployment.guard.as_ref().is_some_and(|guard| guard.ends_at <= now))
+            .filter_map(|deployment| deployment.guard.take())
+            .collect();
+
+        expired
+            .iter()
+            .filter_map(|guard| self.finish(&mut state, guard, SwitchOutcome::Succeeded, None))
+            .inspect(|record| info!("🔀 Switch of {} to {:?} held its SLO", record.route, record.to))
+            .collect()
+    }
+
+    /// Switches of `route`, or of every route, oldest first
+    pub fn history(&self, route: Option<&str>) -> Vec<SwitchRecord> {
+        self.state.lock().unwrap()
+            .history
+            .iter()
+            .filter(|record| route.is_none_or(|route| record.route == route))
+            .cloned()
+            .collect()
+    }
+
+    /// Receive an alert for each automatic rollback
+    pub fn subscribe(&self) -> broadcast::Receiver<SwitchRecord> {
+        self.alerts.subscribe()
+    }
+
+    /// Close finished guards every `interval` and post rollback alerts to the
+    /// configured webhook, if any
+    pub fn start(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let switch = self.clone();
+        let mut alerts = self.subscribe();
+        tokio::spawn(async move {
+            let http_client = reqwest::Client::new();
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {
+                        switch.tick();
+                    }
+                    alert = alerts.recv() => {
+                        let Ok(record) = alert else { continue };
+                        let Some(url) = &switch.config.alert_webhook_url else { continue };
+                        let delivery = http_client.post(url).json(&record).send().await;
+                        if let Err(e) = delivery.and_then(|response| response.error_for_status()) {
+                            warn!("⚠️ Failed to deliver rollback alert for {}: {}", record.route, e);
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Close the switch record of `guard` with its final stats
+    fn finish(
+        &self,
+        state: &mut SwitchState,
+        guard: &Guard,
+        outcome: SwitchOutcome,
+        reason: Option<String>,
+    ) -> Option<SwitchRecord> {
+        let now = self.clock.now();
+        let record = state.record_mut(&guard.record_id)?;
+        record.outcome = outcome;
+        record.finished_at = Some(now);
+        record.reason = reason;
+        record.stats = guard.stats();
+        Some(record.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SloGuardConfig;
+
+    struct FakeClock(Mutex<DateTime<Utc>>);
+
+    impl FakeClock {
+        fn advance(&self, seconds: i64) {
+            *self.0.lock().unwrap() += chrono::Duration::seconds(seconds);
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> DateTime<Utc> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    const ROUTE: &str = "/forge/*";
+
+    fn switch(clock: Arc<FakeClock>) -> BlueGreenSwitch {
+        let target = |url: &str| RouteTarget { url: url.to_string(), weight: 1 };
+        BlueGreenSwitch::new(&BlueGreenConfig {
+            deployments: vec![BlueGreenDeployment {
+                route: ROUTE.to_string(),
+                blue: vec![target("http://forge-blue-1"), target("http://forge-blue-2")],
+                green: vec![target("http://forge-green-1")],
+                active: DeploymentColor::Blue,
+            }],
+            guard: SloGuardConfig {
+                enabled: true,
+                evaluation_window_ms: 60_000,
+                max_error_rate: 0.1,
+                max_p95_latency_ms: 500,
+                min_requests: 10,
+            },
+            ..Default::default()
+        })
+        .with_clock(clock)
+    }
+
+    fn clock() -> Arc<FakeClock> {
+        let start = DateTime::parse_from_rfc3339("2026-03-02T09:00:00Z").unwrap().with_timezone(&Utc);
+        Arc::new(FakeClock(Mutex::new(start)))
+    }
+
+    fn active(switch: &BlueGreenSwitch) -> DeploymentColor {
+        switch.active(ROUTE).unwrap().0
+    }
+
+    #[test]
+    fn test_failing_green_rolled_back_within_window() {
+        let clock = clock();
+        let switch = switch(clock.clone());
+        let mut alerts = switch.subscribe();
+
+        let cutover = switch.switch(ROUTE, None, "alice").unwrap();
+        assert_eq!((cutover.to, cutover.outcome), (DeploymentColor::Green, SwitchOutcome::Evaluating));
+        let (color, targets) = switch.active(ROUTE).unwrap();
+        assert_eq!((color, targets[0].url.as_str()), (DeploymentColor::Green, "http://forge-green-1"));
+
+        // Green is healthy at first, then starts failing
+        let mut rollback = None;
+        for request in 0..40 {
+            clock.advance(1);
+            let success = request < 8;
+            rollback = switch.record(ROUTE, DeploymentColor::Green, success, Duration::from_millis(20));
+            if rollback.is_some() {
+                break;
+            }
+        }
+
+        let rollback = rollback.expect("green was not rolled back");
+        assert_eq!(active(&switch), DeploymentColor::Blue);
+        assert_eq!(rollback.outcome, SwitchOutcome::RolledBack);
+        assert_eq!((rollback.stats.requests, rollback.stats.errors), (10, 2));
+        assert!(rollback.reason.as_deref().unwrap().starts_with("error rate"));
+        assert!(rollback.finished_at.unwrap() < cutover.switched_at + chrono::Duration::seconds(60));
+        assert_eq!(alerts.try_recv().unwrap(), rollback);
+
+        // Blue traffic and late green responses no longer count
+        assert!(switch.record(ROUTE, DeploymentColor::Green, false, Duration::ZERO).is_none());
+        let history = switch.history(Some(ROUTE));
+        assert_eq!(history.len(), 1);
+        assert_eq!((history[0].switched_by.as_str(), history[0].outcome), ("alice", SwitchOutcome::RolledBack));
+    }
+
+    #[test]
+    fn test_healthy_switch_succeeds_after_window() {
+        let clock = clock();
+        let switch = switch(clock.clone());
+        switch.switch(ROUTE, Some(DeploymentColor::Green), "alice").unwrap();
+        assert!(matches!(
+            switch.switch(ROUTE, Some(DeploymentColor::Green), "bob"),
+            Err(BlueGreenError::AlreadyActive(DeploymentColor::Green))
+        ));
+
+        for _ in 0..30 {
+            assert!(switch.record(ROUTE, DeploymentColor::Green, true, Duration::from_millis(100)).is_none());
+        }
+        // One slow request in thirty stays under the p95
+        assert!(switch.record(ROUTE, DeploymentColor::Green, true, Duration::from_millis(900)).is_none());
+
+        assert!(switch.tick().is_empty());
+        clock.advance(60);
+        let finished = switch.tick();
+        assert_eq!(finished.len(), 1);
+        assert_eq!((finished[0].outcome, finished[0].stats.requests), (SwitchOutcome::Succeeded, 31));
+        assert_eq!(active(&switch), DeploymentColor::Green);
+
+        // Failures after the window are the circuit breaker's business, not the guard's
+        for _ in 0..20 {
+            assert!(switch.record(ROUTE, DeploymentColor::Green, false, Duration::ZERO).is_none());
+        }
+        assert_eq!(active(&switch), DeploymentColor::Green);
+    }
+
+    #[test]
+    fn test_manual_switch_supersedes_guard() {
+        let clock = clock();
+        let switch = switch(clock);
+        switch.switch(ROUTE, None, "alice").unwrap();
+        switch.switch(ROUTE, None, "bob").unwrap();
+        assert_eq!(active(&switch), DeploymentColor::Blue);
+
+        let history = switch.history(None);
+        assert_eq!(history.iter().map(|record| record.outcome).collect::<Vec<_>>(), [
+            SwitchOutcome::Superseded,
+            SwitchOutcome::Evaluating,
+        ]);
+        assert!(matches!(switch.switch("/other/*", None, "bob"), Err(BlueGreenError::UnknownRoute(_))));
+
+        let empty = BlueGreenDeployment { green: vec![], ..switch.deployments()[0].clone() };
+        assert!(matches!(switch.register(empty, "bob"), Err(BlueGreenError::EmptySet(DeploymentColor::Green, _))));
+    }
+}
@@ -2,6 +2,8 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
 
 /// Main Fortress configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,6 +15,24 @@ pub struct FortressConfig {
     pub mcp: McpConfig,
     pub security: SecurityConfig,
     pub observability: ObservabilityConfig,
+    /// Reject proxied traffic with 503 while keeping health and admin endpoints up
+    #[serde(default)]
+    pub maintenance_mode: bool,
+    /// Metrics server address; defaults to the gateway port + 1
+    #[serde(default)]
+    pub metrics_addr: Option<SocketAddr>,
+    #[serde(default)]
+    pub usage: UsageConfig,
+    #[serde(default)]
+    pub body_limits: BodyLimitConfig,
+    #[serde(default)]
+    pub grpc: GrpcBridgeConfig,
+    #[serde(default)]
+    pub blue_green: BlueGreenConfig,
+    #[serde(default)]
+    pub websocket: WebSocketConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
 }
 
 impl Default for FortressConfig {
@@ -25,17 +45,277 @@ impl Default for FortressConfig {
             mcp: McpConfig::default(),
             security: SecurityConfig::default(),
             observability: ObservabilityConfig::default(),
+            maintenance_mode: false,
+            metrics_addr: None,
+            usage: UsageConfig::default(),
+            body_limits: BodyLimitConfig::default(),
+            grpc: GrpcBridgeConfig::default(),
+            blue_green: BlueGreenConfig::default(),
+            websocket: WebSocketConfig::default(),
+            audit: AuditConfig::default(),
         }
     }
 }
 
+/// Maximum body sizes passing through the gateway
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BodyLimitConfig {
+    /// Larger request bodies are rejected with 413
+    pub max_request_body_bytes: u64,
+    /// Larger upstream responses are not relayed
+    pub max_response_body_bytes: u64,
+}
+
+impl Default for BodyLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_request_body_bytes: 10 * 1024 * 1024,
+            max_response_body_bytes: 50 * 1024 * 1024,
+        }
+    }
+}
+
+/// WebSocket pass-through to upstreams
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSocketConfig {
+    /// Upgrade requests are proxied as plain requests when disabled
+    pub enabled: bool,
+    /// Connections with no frames in either direction for this long are closed
+    pub idle_timeout_ms: u64,
+    /// Larger frames close the connection with 1009
+    pub max_frame_bytes: usize,
+    /// Larger messages, after reassembling fragments, close the connection with 1009
+    pub max_message_bytes: usize,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            idle_timeout_ms: 5 * 60 * 1000,
+            max_frame_bytes: 1024 * 1024,
+            max_message_bytes: 4 * 1024 * 1024,
+        }
+    }
+}
+
+/// gRPC front for Forge's JSON API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcBridgeConfig {
+    pub enabled: bool,
+    /// Base URL of Forge's JSON API
+    pub forge_url: String,
+    /// Upstream timeout for calls without a deadline, and the cap on client deadlines
+    pub max_timeout_ms: u64,
+}
+
+impl Default for GrpcBridgeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            forge_url: "http://localhost:8081".to_string(),
+            max_timeout_ms: 30_000,
+        }
+    }
+}
+
+/// Blue/green upstream switching
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlueGreenConfig {
+    /// Routes registered at startup; more can be registered through the admin API
+    #[serde(default)]
+    pub deployments: Vec<BlueGreenDeployment>,
+    #[serde(default)]
+    pub guard: SloGuardConfig,
+    /// Switches kept in the queryable history
+    #[serde(default = "default_switch_history_limit")]
+    pub history_limit: usize,
+    /// Endpoint receiving an alert for each automatic rollback
+    #[serde(default)]
+    pub alert_webhook_url: Option<String>,
+}
+
+impl Default for BlueGreenConfig {
+    fn default() -> Self {
+        Self {
+            deployments: vec![],
+            guard: SloGuardConfig::default(),
+            history_limit: default_switch_history_limit(),
+            alert_webhook_url: None,
+        }
+    }
+}
+
+fn default_switch_history_limit() -> usize {
+    100
+}
+
+/// A route with two upstream sets, one of which takes all of its traffic
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlueGreenDeployment {
+    /// Path pattern of the route, as in `Route::path`
+    pub route: String,
+    pub blue: Vec<RouteTarget>,
+    pub green: Vec<RouteTarget>,
+    pub active: DeploymentColor,
+}
+
+impl BlueGreenDeployment {
+    /// Targets of one of the sets
+    pub fn targets(&self, color: DeploymentColor) -> &[RouteTarget] {
+        match color {
+            DeploymentColor::Blue => &self.blue,
+            DeploymentColor::Green => &self.green,
+        }
+    }
+}
+
+/// One of the two upstream sets of a blue/green route
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeploymentColor {
+    Blue,
+    Green,
+}
+
+impl DeploymentColor {
+    pub fn other(self) -> Self {
+        match self {
+            Self::Blue => Self::Green,
+            Self::Green => Self::Blue,
+        }
+    }
+}
+
+/// SLO thresholds the newly active set must hold after a switch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SloGuardConfig {
+    pub enabled: bool,
+    /// How long after a switch the new set is watched
+    pub evaluation_window_ms: u64,
+    /// Fraction of failed requests (transport errors and 5xx) that triggers a rollback
+    pub max_error_rate: f64,
+    pub max_p95_latency_ms: u64,
+    /// Requests needed before the thresholds are judged
+    pub min_requests: usize,
+}
+
+impl Default for SloGuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            evaluation_window_ms: 300_000,
+            max_error_rate: 0.05,
+            max_p95_latency_ms: 1000,
+            min_requests: 20,
+        }
+    }
+}
+
+/// Per-principal usage accounting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageConfig {
+    /// Hours of rollups kept for reports; at least 24 so daily summaries are complete
+    pub retention_hours: u32,
+    /// Endpoint receiving each principal's daily usage summary
+    #[serde(default)]
+    pub summary_webhook_url: Option<String>,
+}
+
+impl Default for UsageConfig {
+    fn default() -> Self {
+        Self {
+            retention_hours: 24 * 31,
+            summary_webhook_url: None,
+        }
+    }
+}
+
+/// Per-request access log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditConfig {
+    pub enabled: bool,
+    /// Share of requests logged, from 0.0 to 1.0; a request id is always in or always out
+    pub sample_rate: f64,
+    /// Where entries are written; read once at startup
+    #[serde(default)]
+    pub sink: AuditSinkConfig,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sample_rate: 1.0,
+            sink: AuditSinkConfig::default(),
+        }
+    }
+}
+
+/// Destination of access log entries, one JSON object per line
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditSinkConfig {
+    #[default]
+    Stdout,
+    /// Append to `path`, rotating to `path.1` .. `path.{max_files}` past `max_bytes`
+    File {
+        path: std::path::PathBuf,
+        max_bytes: u64,
+        max_files: usize,
+    },
+}
+
+/// Live configuration shared by the running gateway.
+///
+/// Readers take a cheap snapshot per request; updates swap the whole
+/// configuration at once so no request sees a half-applied change.
+#[derive(Debug, Clone)]
+pub struct SharedConfig {
+    inner: Arc<RwLock<Arc<FortressConfig>>>,
+}
+
+impl SharedConfig {
+    pub fn new(config: FortressConfig) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Arc::new(config))),
+        }
+    }
+
+    /// Snapshot of the current configuration
+    pub fn current(&self) -> Arc<FortressConfig> {
+        self.inner.read().unwrap().clone()
+    }
+
+    /// Atomically replace the configuration
+    pub fn replace(&self, config: FortressConfig) {
+        *self.inner.write().unwrap() = Arc::new(config);
+    }
+}
+
 /// Authentication configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthConfig {
     pub enabled: bool,
     pub jwt_secret: Option<String>,
+    /// External issuer whose bearer JWTs are verified against its JWKS
+    #[serde(default)]
+    pub jwt: Option<JwtConfig>,
     pub mcp_auth_tokens: HashMap<String, String>,
     pub service_accounts: HashMap<String, ServiceAccount>,
+    /// Static API keys by name, sent as `X-API-Key` or as a bearer token
+    #[serde(default)]
+    pub api_keys: HashMap<String, ApiKey>,
+    /// mTLS client identities (certificate common name or SAN) accepted as
+    /// principals, with their scopes
+    #[serde(default)]
+    pub client_certificates: HashMap<String, Vec<String>>,
+    /// Per-route requirements; the first matching pattern applies
+    #[serde(default = "default_auth_routes")]
+    pub routes: Vec<AuthRoute>,
+    /// Requirement for paths no pattern matches
+    #[serde(default)]
+    pub default_requirement: AuthRequirement,
 }
 
 impl Default for AuthConfig {
@@ -43,10 +323,83 @@ impl Default for AuthConfig {
         Self {
             enabled: true,
             jwt_secret: None,
+            jwt: None,
             mcp_auth_tokens: HashMap::new(),
             service_accounts: HashMap::new(),
+            api_keys: HashMap::new(),
+            client_certificates: HashMap::new(),
+            routes: default_auth_routes(),
+            default_requirement: AuthRequirement::default(),
+        }
+    }
+}
+
+/// Credentials a route requires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthRequirement {
+    /// No credentials needed
+    Public,
+    /// An API key, service account token or MCP service token
+    ApiKey,
+    /// A JWT signed with `jwt_secret` or by the `jwt` issuer
+    Jwt,
+    /// A client certificate listed in `client_certificates`
+    Mtls,
+    /// Any accepted credential
+    #[default]
+    Authenticated,
+}
+
+/// JWT issuer settings, typically an OIDC provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JwtConfig {
+    pub jwks_url: String,
+    /// Required `iss` claim
+    pub issuer: String,
+    /// Required `aud` claim
+    pub audience: String,
+    /// How long fetched keys are used before the JWKS is fetched again
+    #[serde(default = "default_jwks_ttl_secs")]
+    pub jwks_ttl_secs: u64,
+}
+
+fn default_jwks_ttl_secs() -> u64 {
+    3600
+}
+
+/// Auth requirement for paths matching a pattern; patterns ending in `/*`
+/// match by prefix, like route paths
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthRoute {
+    pub pattern: String,
+    pub requirement: AuthRequirement,
+    /// Scopes the principal must hold, all of them
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+impl AuthRoute {
+    pub fn new(pattern: &str, requirement: AuthRequirement) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            requirement,
+            scopes: Vec::new(),
         }
     }
+
+    pub fn with_scopes(mut self, scopes: &[&str]) -> Self {
+        self.scopes = scopes.iter().map(|scope| scope.to_string()).collect();
+        self
+    }
+}
+
+/// Probes, metrics and token issuance are public
+fn default_auth_routes() -> Vec<AuthRoute> {
+    ["/health/*", "/healthz/*", "/readyz/*", "/metrics/*", "/api/v1/auth/*"]
+        .into_iter()
+        .map(|pattern| AuthRoute::new(pattern, AuthRequirement::Public))
+        .collect()
 }
 
 /// Service account for authentication
@@ -57,12 +410,23 @@ pub struct ServiceAccount {
     pub permissions: Vec<String>,
 }
 
+/// Static API key issued to a named principal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub key: String,
+    pub principal: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
 /// Rate limiting configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RateLimitConfig {
     pub enabled: bool,
     pub requests_per_minute: u32,
     pub burst_limit: u32,
+    /// Redis holding the buckets shared by every gateway instance; buckets
+    /// are kept in memory when unset or unreachable
     pub redis_url: Option<String>,
 }
 
@@ -72,7 +436,7 @@ impl Default for RateLimitConfig {
             enabled: true,
             requests_per_minute: 1000,
             burst_limit: 100,
-            redis_url: Some("redis://127.0.0.1:6379".to_string()),
+            redis_url: None,
         }
     }
 }
@@ -83,7 +447,14 @@ pub struct CacheConfig {
     pub enabled: bool,
     pub ttl_seconds: u64,
     pub max_size_mb: usize,
+    /// Redis shared by every gateway instance; responses are kept in memory when unset
     pub redis_url: Option<String>,
+    /// How long past its TTL an entry is served while one background request refreshes it
+    #[serde(default)]
+    pub stale_while_revalidate_seconds: u64,
+    /// How long past its TTL an entry is served in place of an upstream failure
+    #[serde(default)]
+    pub stale_if_error_seconds: u64,
 }
 
 impl Default for CacheConfig {
@@ -92,17 +463,33 @@ impl Default for CacheConfig {
             enabled: true,
             ttl_seconds: 300, // 5 minutes
             max_size_mb: 512,
-            redis_url: Some("redis://127.0.0.1:6379".to_string()),
+            redis_url: None,
+            stale_while_revalidate_seconds: 0,
+            stale_if_error_seconds: 0,
         }
     }
 }
 
+/// Per-route overrides of the cache's stale windows
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteCacheConfig {
+    #[serde(default)]
+    pub stale_while_revalidate_seconds: Option<u64>,
+    #[serde(default)]
+    pub stale_if_error_seconds: Option<u64>,
+}
+
 /// Routing configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RoutingConfig {
     pub routes: Vec<Route>,
     pub default_upstream: Option<String>,
     pub load_balancing: LoadBalancingStrategy,
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// Cap on retries across all routes
+    #[serde(default)]
+    pub retry_budget: RetryBudgetConfig,
 }
 
 impl Default for RoutingConfig {
@@ -111,26 +498,295 @@ impl Default for RoutingConfig {
             routes: vec![],
             default_upstream: Some("http://localhost:8081".to_string()),
             load_balancing: LoadBalancingStrategy::RoundRobin,
+            circuit_breaker: CircuitBreakerConfig::default(),
+            retry_budget: RetryBudgetConfig::default(),
+        }
+    }
+}
+
+/// Gateway-wide retry budget, in the style of Linkerd's.
+///
+/// Over the last `ttl_seconds`, retries may add at most `retry_ratio` of the
+/// original requests, plus `min_retries_per_second` so quiet routes can
+/// still retry. Past that, failed attempts are returned as they are instead
+/// of adding load to a struggling upstream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryBudgetConfig {
+    #[serde(default = "default_retry_ratio")]
+    pub retry_ratio: f64,
+    #[serde(default = "default_min_retries_per_second")]
+    pub min_retries_per_second: u32,
+    #[serde(default = "default_retry_budget_ttl_seconds")]
+    pub ttl_seconds: u64,
+}
+
+fn default_retry_ratio() -> f64 {
+    0.2
+}
+
+fn default_min_retries_per_second() -> u32 {
+    10
+}
+
+fn default_retry_budget_ttl_seconds() -> u64 {
+    10
+}
+
+impl Default for RetryBudgetConfig {
+    fn default() -> Self {
+        Self {
+            retry_ratio: default_retry_ratio(),
+            min_retries_per_second: default_min_retries_per_second(),
+            ttl_seconds: default_retry_budget_ttl_seconds(),
+        }
+    }
+}
+
+/// Failed upstream attempts a route may retry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryOn {
+    /// The connection could not be made or was reset before a response arrived
+    ConnectError,
+    /// The upstream answered with a 5xx status
+    ServerError,
+    /// The attempt ran past the route's `timeout_ms`
+    Timeout,
+}
+
+/// Per-route retry policy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Attempts after the first
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    #[serde(default = "default_retry_on")]
+    pub retry_on: Vec<RetryOn>,
+    /// Retry methods that are not idempotent, such as `POST`
+    #[serde(default)]
+    pub retry_non_idempotent: bool,
+}
+
+fn default_max_retries() -> u32 {
+    2
+}
+
+fn default_retry_on() -> Vec<RetryOn> {
+    vec![RetryOn::ConnectError, RetryOn::ServerError, RetryOn::Timeout]
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: default_max_retries(),
+            retry_on: default_retry_on(),
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+/// Per-upstream circuit breaker configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    pub enabled: bool,
+    /// Consecutive failures that open the circuit
+    pub failure_threshold: u32,
+    /// How long an open circuit rejects requests before letting probes through
+    pub cooldown_ms: u64,
+    /// Failures older than this no longer count towards the threshold
+    #[serde(default = "default_breaker_window_ms")]
+    pub window_ms: u64,
+    /// Probe requests let through while half-open; all must succeed to close the circuit
+    #[serde(default = "default_half_open_probes")]
+    pub half_open_probes: u32,
+}
+
+fn default_breaker_window_ms() -> u64 {
+    60_000
+}
+
+fn default_half_open_probes() -> u32 {
+    1
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            failure_threshold: 5,
+            cooldown_ms: 30_000,
+            window_ms: default_breaker_window_ms(),
+            half_open_probes: default_half_open_probes(),
         }
     }
 }
 
-/// Route definition
+/// Route definition.
+///
+/// `path` matches exactly, or by prefix when it ends in `/*`. A request must
+/// also use one of `methods` (any when empty) and carry every header in
+/// `match_headers` with exactly that value. Of all matching routes the one
+/// with the lowest `priority` wins.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Route {
+    /// Label for metrics; the path when unset
+    #[serde(default)]
+    pub name: Option<String>,
     pub path: String,
     pub upstream: String,
     pub methods: Vec<String>,
+    /// Headers added to requests sent upstream
     pub headers: HashMap<String, String>,
+    /// Limit on each upstream attempt
     pub timeout_ms: Option<u64>,
+    /// Weighted upstreams sharing the route's traffic; `upstream` is used when empty
+    #[serde(default)]
+    pub targets: Vec<RouteTarget>,
+    /// Evaluation order; lower values are tried first
+    #[serde(default)]
+    pub priority: i32,
+    /// Request headers the route requires, compared case-sensitively by value
+    #[serde(default)]
+    pub match_headers: HashMap<String, String>,
+    /// Stale windows for the route's cached responses; the cache defaults when unset
+    #[serde(default)]
+    pub cache: Option<RouteCacheConfig>,
+    /// Retries of failed upstream attempts; none when unset
+    #[serde(default)]
+    pub retry: Option<RetryPolicy>,
+    /// Own rate limit buckets for the route; the global limits when unset
+    #[serde(default)]
+    pub rate_limit: Option<RouteRateLimit>,
 }
 
-/// Load balancing strategies
+impl Route {
+    /// Name the route is reported under in metrics
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.path)
+    }
+
+    /// Upstreams the route balances across
+    pub fn targets(&self) -> Vec<RouteTarget> {
+        if self.targets.is_empty() {
+            vec![RouteTarget {
+                url: self.upstream.clone(),
+                weight: default_target_weight(),
+            }]
+        } else {
+            self.targets.clone()
+        }
+    }
+}
+
+/// Per-route rate limit, counted separately from the global buckets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteRateLimit {
+    pub requests_per_minute: u32,
+    pub burst_limit: u32,
+}
+
+/// One upstream of a route and its share of traffic
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteTarget {
+    pub url: String,
+    /// Relative weight; 0 drains the target
+    #[serde(default = "default_target_weight")]
+    pub weight: u32,
+}
+
+fn default_target_weight() -> u32 {
+    1
+}
+
+/// A routing table that cannot be loaded
+#[derive(Debug, thiserror::Error)]
+pub enum RouteConfigError {
+    #[error("route {0} has an invalid path: paths start with `/` and may only end in `/*`")]
+    InvalidPath(String),
+    #[error("route {0} has no upstream")]
+    MissingUpstream(String),
+    #[error("route {route} target weights sum to {total}, not 100")]
+    WeightSum { route: String, total: u32 },
+    #[error("routes {first} and {second} can match the same request at priority {priority}")]
+    OverlappingPriority { first: String, second: String, priority: i32 },
+}
+
+impl RoutingConfig {
+    /// Reject routing tables whose outcome would depend on route order or
+    /// whose traffic split is not expressed in percent
+    pub fn validate(&self) -> Result<(), RouteConfigError> {
+        for route in &self.routes {
+            if !valid_route_path(&route.path) {
+                return Err(RouteConfigError::InvalidPath(route.label().to_string()));
+            }
+            if route.targets.is_empty() && route.upstream.is_empty() {
+                return Err(RouteConfigError::MissingUpstream(route.label().to_string()));
+            }
+            if route.targets.len() > 1 {
+                let total = route.targets.iter().map(|target| target.weight).sum();
+                if total != 100 {
+                    return Err(RouteConfigError::WeightSum { route: route.label().to_string(), total });
+                }
+            }
+        }
+
+        for (i, first) in self.routes.iter().enumerate() {
+            for second in &self.routes[i + 1..] {
+                if first.priority == second.priority && routes_overlap(first, second) {
+                    return Err(RouteConfigError::OverlappingPriority {
+                        first: first.label().to_string(),
+                        second: second.label().to_string(),
+                        priority: first.priority,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Paths start with `/` and use `*` only as a trailing `/*`
+fn valid_route_path(path: &str) -> bool {
+    let pattern = path.strip_suffix("/*").unwrap_or(path);
+    pattern.starts_with('/') && !pattern.contains('*')
+}
+
+/// Whether some request could match both routes
+fn routes_overlap(a: &Route, b: &Route) -> bool {
+    let paths = match (a.path.strip_suffix("/*"), b.path.strip_suffix("/*")) {
+        (Some(a), Some(b)) => prefix_covers(a, b) || prefix_covers(b, a),
+        (Some(prefix), None) => prefix_covers(prefix, &b.path),
+        (None, Some(prefix)) => prefix_covers(prefix, &a.path),
+        (None, None) => a.path == b.path,
+    };
+    let methods = a.methods.is_empty()
+        || b.methods.is_empty()
+        || a.methods.iter().any(|m| b.methods.iter().any(|n| m.eq_ignore_ascii_case(n)));
+    // Requiring different values for the same header keeps two routes apart
+    let headers = a.match_headers.iter().all(|(name, value)| {
+        b.match_headers
+            .iter()
+            .find(|(other, _)| other.eq_ignore_ascii_case(name))
+            .is_none_or(|(_, other)| other == value)
+    });
+    paths && methods && headers
+}
+
+/// Whether `path` lies under the `/*` pattern with this prefix
+fn prefix_covers(prefix: &str, path: &str) -> bool {
+    path == prefix || path.starts_with(&format!("{}/", prefix))
+}
+
+/// Load balancing strategies across a route's targets
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum LoadBalancingStrategy {
+    /// Smooth weighted round-robin using target weights
     RoundRobin,
+    /// Fewest in-flight requests relative to weight
     LeastConnections,
+    /// Weighted random choice
     Random,
+    /// Round-robin with weights by target URL overriding the route's target weights
     Weighted { weights: HashMap<String, u32> },
 }
 
@@ -142,6 +798,8 @@ pub struct McpConfig {
     pub health_check_interval_seconds: u64,
     pub cache_ttl_seconds: u64,
     pub max_concurrent_requests: usize,
+    #[serde(default)]
+    pub health: McpHealthConfig,
 }
 
 impl Default for McpConfig {
@@ -152,6 +810,41 @@ impl Default for McpConfig {
             health_check_interval_seconds: 60,
             cache_ttl_seconds: 300,
             max_concurrent_requests: 100,
+            health: McpHealthConfig::default(),
+        }
+    }
+}
+
+/// How registered MCP servers are probed for liveness
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpProbe {
+    /// Open a TCP connection to the endpoint's host and port
+    TcpConnect,
+    /// GET the endpoint and expect a success status
+    HttpGet,
+    /// POST a JSON-RPC `ping` to the endpoint and expect a result
+    McpPing,
+}
+
+/// Liveness tracking of registered MCP servers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpHealthConfig {
+    pub probe: McpProbe,
+    pub probe_timeout_ms: u64,
+    /// Consecutive failed probes after which a server is down and no longer routed to
+    pub down_after_failures: u32,
+    /// Consecutive successful probes a down server needs before it is routed to again
+    pub readmit_after_successes: u32,
+}
+
+impl Default for McpHealthConfig {
+    fn default() -> Self {
+        Self {
+            probe: McpProbe::HttpGet,
+            probe_timeout_ms: 10_000,
+            down_after_failures: 3,
+            readmit_after_successes: 2,
         }
     }
 }
@@ -164,6 +857,9 @@ pub struct SecurityConfig {
     pub suspicious_patterns: Vec<String>,
     pub max_request_size_bytes: usize,
     pub allowed_origins: Vec<String>,
+    /// Terminate TLS on the gateway listener; plain HTTP when unset
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
 }
 
 impl Default for SecurityConfig {
@@ -180,10 +876,37 @@ impl Default for SecurityConfig {
             ],
             max_request_size_bytes: 10 * 1024 * 1024, // 10MB
             allowed_origins: vec!["*".to_string()],
+            tls: None,
         }
     }
 }
 
+/// TLS termination for the gateway listener, read once at startup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// PEM certificate chain presented to clients, leaf first
+    pub cert_path: String,
+    /// PEM private key of the leaf certificate (PKCS#8, PKCS#1 or SEC1)
+    pub key_path: String,
+    /// PEM bundle of CAs trusted to sign client certificates; enables mTLS
+    #[serde(default)]
+    pub client_ca_path: Option<String>,
+    /// How client certificates are checked when `client_ca_path` is set
+    #[serde(default)]
+    pub client_auth: ClientAuthMode,
+}
+
+/// Client certificate policy for mTLS
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientAuthMode {
+    /// Every client must present a certificate signed by the client CA
+    #[default]
+    Require,
+    /// Certificates are verified when presented; clients without one are admitted
+    Verify,
+}
+
 /// Observability configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ObservabilityConfig {
@@ -267,8 +990,10 @@ impl Default for ConfigBuilder {
 /// Load configuration from file or environment
 pub fn load_config() -> Result<FortressConfig, Box<dyn std::error::Error>> {
     // Try to load from config file first
-    if let Ok(config_content) = std::fs::read_to_string("fortress.toml") {
-        return Ok(toml::from_str(&config_content)?);
+    for path in ["fortress.toml", "fortress.json"] {
+        if std::path::Path::new(path).exists() {
+            return load_config_file(path);
+        }
     }
 
     // Try to load from environment variables
@@ -278,6 +1003,18 @@ pub fn load_config() -> Result<FortressConfig, Box<dyn std::error::Error>> {
     Ok(FortressConfig::default())
 }
 
+/// Load and validate a TOML or JSON configuration file, chosen by extension
+pub fn load_config_file(path: impl AsRef<std::path::Path>) -> Result<FortressConfig, Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)?;
+    let config: FortressConfig = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(&content)?,
+        _ => toml::from_str(&content)?,
+    };
+    config.routing.validate()?;
+    Ok(config)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -302,4 +1039,55 @@ mod tests {
 
         assert!(!config.auth.enabled);
     }
+
+    #[test]
+    fn test_json_routing_config_is_validated_on_load() {
+        let routing = serde_json::json!({
+            "routes": [
+                {
+                    "path": "/api/forge/*", "upstream": "", "methods": [], "headers": {}, "timeout_ms": null,
+                    "targets": [{ "url": "http://forge:8081", "weight": 90 }, { "url": "http://forge-canary:8081", "weight": 10 }]
+                },
+                {
+                    "path": "/api/mcp/*", "upstream": "http://mcp-registry:8090", "methods": [], "headers": {},
+                    "timeout_ms": 5000
+                },
+                {
+                    "name": "forge-beta", "path": "/api/forge/*", "upstream": "http://forge-canary:8081", "methods": [],
+                    "headers": {}, "timeout_ms": null, "priority": -1, "match_headers": { "x-beta": "1" }
+                }
+            ],
+            "default_upstream": null,
+            "load_balancing": "Random"
+        });
+        let mut config = serde_json::to_value(FortressConfig::default()).unwrap();
+        config["routing"] = routing;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fortress.json");
+        std::fs::write(&path, config.to_string()).unwrap();
+        let loaded = load_config_file(&path).unwrap();
+        assert_eq!(loaded.routing.routes.len(), 3);
+        assert_eq!(loaded.routing.routes[2].label(), "forge-beta");
+
+        // Weights that are not percentages are rejected
+        config["routing"]["routes"][0]["targets"][1]["weight"] = 20.into();
+        std::fs::write(&path, config.to_string()).unwrap();
+        let err = load_config_file(&path).unwrap_err().to_string();
+        assert!(err.contains("sum to 110"), "{}", err);
+
+        // So are two routes that can match the same request at one priority,
+        // unless a header they both require tells them apart
+        config["routing"]["routes"][0]["targets"][1]["weight"] = 10.into();
+        config["routing"]["routes"][2]["priority"] = 0.into();
+        std::fs::write(&path, config.to_string()).unwrap();
+        let err = load_config_file(&path).unwrap_err().to_string();
+        assert!(err.contains("/api/forge/* and forge-beta"), "{}", err);
+
+        let mut routing: RoutingConfig = serde_json::from_value(config["routing"].clone()).unwrap();
+        routing.routes[0].match_headers.insert("X-Beta".to_string(), "0".to_string());
+        assert!(routing.validate().is_ok());
+        routing.routes[2].path = "/api/*/forge".to_string();
+        assert!(matches!(routing.validate(), Err(RouteConfigError::InvalidPath(_))));
+    }
 }
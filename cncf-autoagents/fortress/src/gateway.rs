@@ -17,47 +17,104 @@ use tower::{Service, ServiceExt};
 use tracing::{info, warn, error, instrument};
 
 use crate::{
-    config::{FortressConfig, Route},
-    metrics::MetricsCollector,
+    admin,
+    blue_green::BlueGreenSwitch,
+    config::{CircuitBreakerConfig, RetryOn, Route, SharedConfig},
+    grpc, health,
+    metrics::{MetricsCollector, UNMATCHED_ROUTE},
+    mcp_catalog,
     mcp_registry::McpRegistry,
-    routing::Router,
+    middleware::{
+        retry::{failure_kind, is_idempotent, AttemptTimeout},
+        CacheMiddleware, CircuitBreaker, RetryBudget,
+    },
+    overlays::OverlayScheduler,
+    routing::{resolve_route, LoadBalancer},
+    usage::{MatchedRoute, MatchedUpstream, UpstreamTime, UsageTracker},
+    websocket,
 };
 
 /// Main gateway service
 #[derive(Clone)]
 pub struct GatewayService {
-    config: FortressConfig,
-    router: Router,
+    config: SharedConfig,
     metrics: MetricsCollector,
     mcp_registry: McpRegistry,
+    overlays: OverlayScheduler,
+    circuit_breaker: CircuitBreaker,
+    retry_budget: RetryBudget,
+    load_balancer: LoadBalancer,
+    usage: UsageTracker,
+    blue_green: BlueGreenSwitch,
+    cache: CacheMiddleware,
     http_client: reqwest::Client,
 }
 
+/// Response header carrying how many times the upstream call was retried
+pub const RETRIES_HEADER: &str = "x-fortress-retries";
+
 impl GatewayService {
-    /// Create a new gateway service
+    /// Create a new gateway service reading the live configuration from `config`
     pub fn new(
-        config: FortressConfig,
+        config: SharedConfig,
         metrics: MetricsCollector,
         mcp_registry: McpRegistry,
+        overlays: OverlayScheduler,
     ) -> Self {
-        let router = Router::new(config.routing.clone());
         let http_client = reqwest::Client::builder()
             .timeout(Duration::from_secs(30))
             .pool_max_idle_per_host(10)
             .build()
             .expect("Failed to create HTTP client");
+        let usage = UsageTracker::new(&config.current().usage);
+        let blue_green = BlueGreenSwitch::new(&config.current().blue_green);
+        let cache = CacheMiddleware::new(config.clone());
 
         Self {
             config,
-            router,
+            circuit_breaker: CircuitBreaker::new().with_metrics(metrics.clone()),
+            retry_budget: RetryBudget::new(),
             metrics,
             mcp_registry,
+            overlays,
+            load_balancer: LoadBalancer::new(),
+            usage,
+            blue_green,
+            cache,
             http_client,
         }
     }
 
+    /// Serve usage reports from `usage`, the tracker fed by the usage middleware
+    pub fn with_usage(mut self, usage: UsageTracker) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    /// Route blue/green traffic through `blue_green`, the switch the admin API controls
+    pub fn with_blue_green(mut self, blue_green: BlueGreenSwitch) -> Self {
+        self.blue_green = blue_green;
+        self
+    }
+
+    /// Purge responses from `cache`, the cache layer in front of this service
+    pub fn with_cache(mut self, cache: CacheMiddleware) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Readiness: the MCP registry has loaded and every upstream is reachable
+    pub async fn readiness(&self) -> health::ReadinessReport {
+        let config = self.config.current();
+        let upstreams = health::check_upstreams(&config.routing, health::UPSTREAM_CHECK_TIMEOUT).await;
+        health::ReadinessReport::new(self.mcp_registry.is_loaded(), upstreams)
+    }
+
     /// Route request to appropriate upstream service
-    #[instrument(skip(self, req), fields(method = %req.method(), uri = %req.uri()))]
+    #[instrument(
+        skip(self, req),
+        fields(method = %req.method(), uri = %req.uri(), trace_id = trace_id(req.headers()).unwrap_or_default())
+    )]
     async fn route_request(
         &self,
         mut req: Request<Body>,
@@ -66,12 +123,21 @@ impl GatewayService {
         let path = req.uri().path().to_string();
         let method = req.method().clone();
 
-        // Find matching route
-        let route = match self.router.find_route(&path, &method) {
-            Some(route) => route,
+        let config = self.config.current();
+        if config.maintenance_mode {
+            self.metrics.record_request(UNMATCHED_ROUTE, StatusCode::SERVICE_UNAVAILABLE, start_time.elapsed());
+            return Ok(self.create_error_response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Gateway is in maintenance mode",
+            ));
+        }
+
+        // Find matching route, unless a layer in front already did
+        let mut route = match resolve_route(&config.routing, &mut req) {
+            Some(route) => Route::clone(&route),
             None => {
                 warn!("No route found for {} {}", method, path);
-                self.metrics.record_request(StatusCode::NOT_FOUND, start_time.elapsed());
+                self.metrics.record_request(UNMATCHED_ROUTE, StatusCode::NOT_FOUND, start_time.elapsed());
                 return Ok(self.create_error_response(
                     StatusCode::NOT_FOUND,
                     "Route not found",
@@ -79,12 +145,22 @@ impl GatewayService {
             }
         };
 
+        // A blue/green route balances across its active set only
+        let blue_green = self.blue_green.active(&route.path);
+        if let Some((_, targets)) = &blue_green {
+            route.targets = targets.clone();
+        }
+
+        // Pick one of the route's upstreams; held until the request completes
+        let selection = self.load_balancer.select(&route, &config.routing.load_balancing);
+        route.upstream = selection.url().to_string();
+
         // Build upstream URI
         let upstream_uri = match self.build_upstream_uri(&route, &req) {
             Ok(uri) => uri,
             Err(err) => {
                 error!("Failed to build upstream URI: {}", err);
-                self.metrics.record_request(StatusCode::BAD_GATEWAY, start_time.elapsed());
+                self.metrics.record_request(route.label(), StatusCode::BAD_GATEWAY, start_time.elapsed());
                 return Ok(self.create_error_response(
                     StatusCode::BAD_GATEWAY,
                     "Invalid upstream configuration",
@@ -92,20 +168,110 @@ impl GatewayService {
             }
         };
 
+        // Read the body before contacting the upstream, so a client-side failure
+        // such as crossing the body limit is not counted against it
+        let (parts, body) = req.into_parts();
+        let body = match hyper::body::to_bytes(body).await {
+            Ok(body) => body,
+            Err(err) => {
+                warn!("Failed to read request body: {}", err);
+                self.metrics.record_request(route.label(), StatusCode::BAD_REQUEST, start_time.elapsed());
+                return Ok(self.create_error_response(StatusCode::BAD_REQUEST, "Unreadable request body"));
+            }
+        };
+        let mut req = Request::from_parts(parts, Body::from(body.clone()));
+
+        // Fail fast while the upstream's circuit is open
+        let breaker_config = &config.routing.circuit_breaker;
+        if let Err(retry_after) = self.circuit_breaker.allow(&route.upstream, breaker_config) {
+            if let Some((color, _)) = blue_green {
+                self.blue_green.record(&route.path, color, false, Duration::ZERO);
+            }
+            self.metrics.record_circuit_rejection(&route.upstream);
+            self.metrics.record_request(route.label(), StatusCode::SERVICE_UNAVAILABLE, start_time.elapsed());
+            let mut response = self.create_error_response(StatusCode::SERVICE_UNAVAILABLE, "Upstream circuit open");
+            // Whole seconds, rounded up so clients never retry into a still-open circuit
+            let retry_after_secs = retry_after.as_millis().div_ceil(1000).max(1) as u64;
+            response
+                .headers_mut()
+                .insert(hyper::header::RETRY_AFTER, retry_after_secs.into());
+            response.extensions_mut().insert(MatchedRoute(route.path.clone()));
+            response.extensions_mut().insert(MatchedUpstream(route.upstream.clone()));
+            return Ok(response);
+        }
+
         // Add gateway headers
         self.add_gateway_headers(&mut req, &route);
 
-        // Forward request to upstream
-        match self.forward_request(req, upstream_uri).await {
+        // Forward request to upstream; transport errors and 5xx responses count as failures
+        let upstream_start = Instant::now();
+        let retry = route.retry.clone().filter(|policy| policy.retry_non_idempotent || is_idempotent(&method));
+        let mut retries = 0;
+        let outcome = if config.websocket.enabled && websocket::is_upgrade_request(&req) {
+            let outcome = websocket::proxy(req, upstream_uri, &config.websocket, &self.metrics).await;
+            self.record_attempt(&route.upstream, breaker_config, &outcome);
+            outcome
+        } else {
+            self.retry_budget.deposit(&config.routing.retry_budget);
+            // Each attempt resends the body read above
+            let (parts, _) = req.into_parts();
+            let attempt_timeout = route.timeout_ms.map(Duration::from_millis);
+            loop {
+                let mut attempt = Request::new(Body::from(body.clone()));
+                *attempt.method_mut() = parts.method.clone();
+                *attempt.uri_mut() = parts.uri.clone();
+                *attempt.headers_mut() = parts.headers.clone();
+                let max_response_bytes = config.body_limits.max_response_body_bytes;
+                let outcome = match attempt_timeout {
+                    Some(limit) => tokio::time::timeout(limit, self.forward_request(attempt, upstream_uri.clone(), max_response_bytes))
+                        .await
+                        .unwrap_or_else(|_| Err(Box::new(AttemptTimeout(limit)))),
+                    None => self.forward_request(attempt, upstream_uri.clone(), max_response_bytes).await,
+                };
+                self.record_attempt(&route.upstream, breaker_config, &outcome);
+
+                let Some(policy) = &retry else { break outcome };
+                let retryable = failure_kind(&outcome).is_some_and(|kind| policy.retry_on.contains(&kind));
+                if !retryable || retries >= policy.max_retries {
+                    break outcome;
+                }
+                // A retry is never sent into a circuit the failure just opened
+                if self.circuit_breaker.allow(&route.upstream, breaker_config).is_err() {
+                    break outcome;
+                }
+                if !self.retry_budget.try_withdraw(&config.routing.retry_budget) {
+                    warn!("⚠️ Retry budget spent, not retrying {} {}", method, path);
+                    self.metrics.record_retry_budget_exhausted(route.label());
+                    break outcome;
+                }
+                retries += 1;
+                warn!("🔁 Retrying {} {} (retry {} of {})", method, path, retries, policy.max_retries);
+            }
+        };
+        self.metrics.record_upstream_attempts(route.label(), retries + 1);
+        let upstream_time = UpstreamTime(upstream_start.elapsed());
+        let succeeded = matches!(&outcome, Ok(response) if !response.status().is_server_error());
+        if let Some((color, _)) = blue_green {
+            self.blue_green.record(&route.path, color, succeeded, upstream_time.0);
+        }
+
+        match outcome {
             Ok(mut response) => {
                 // Add response headers
                 self.add_response_headers(&mut response);
+                response.extensions_mut().insert(MatchedRoute(route.path.clone()));
+                response.extensions_mut().insert(MatchedUpstream(route.upstream.clone()));
+                response.extensions_mut().insert(upstream_time);
+                if retry.is_some() {
+                    response.headers_mut().insert(RETRIES_HEADER, retries.into());
+                }
 
                 // Record metrics
-                self.metrics.record_request(response.status(), start_time.elapsed());
+                self.metrics.record_request(route.label(), response.status(), start_time.elapsed());
 
                 info!(
                     "Request completed: {} {} -> {} ({}ms)",
+                    method,
                     route.path,
                     response.status(),
                     start_time.elapsed().as_millis()
@@ -115,20 +281,44 @@ impl GatewayService {
             }
             Err(err) => {
                 error!("Upstream request failed: {}", err);
-                self.metrics.record_request(StatusCode::BAD_GATEWAY, start_time.elapsed());
-                Ok(self.create_error_response(
-                    StatusCode::BAD_GATEWAY,
-                    "Upstream service unavailable",
-                ))
+                let status = if failure_kind(&Err(err)) == Some(RetryOn::Timeout) {
+                    StatusCode::GATEWAY_TIMEOUT
+                } else {
+                    StatusCode::BAD_GATEWAY
+                };
+                self.metrics.record_request(route.label(), status, start_time.elapsed());
+                let mut response = self.create_error_response(status, "Upstream service unavailable");
+                response.extensions_mut().insert(MatchedRoute(route.path.clone()));
+                response.extensions_mut().insert(MatchedUpstream(route.upstream.clone()));
+                response.extensions_mut().insert(upstream_time);
+                if retry.is_some() {
+                    response.headers_mut().insert(RETRIES_HEADER, retries.into());
+                }
+                Ok(response)
             }
         }
     }
 
+    /// Report the outcome of one upstream attempt to the circuit breaker
+    fn record_attempt(
+        &self,
+        upstream: &str,
+        breaker_config: &CircuitBreakerConfig,
+        outcome: &Result<Response<Body>, Box<dyn std::error::Error>>,
+    ) {
+        if matches!(outcome, Ok(response) if !response.status().is_server_error()) {
+            self.circuit_breaker.record_success(upstream, breaker_config);
+        } else {
+            self.circuit_breaker.record_failure(upstream, breaker_config);
+        }
+    }
+
     /// Forward request to upstream service
     async fn forward_request(
         &self,
         req: Request<Body>,
         upstream_uri: Uri,
+        max_response_bytes: u64,
     ) -> Result<Response<Body>, Box<dyn std::error::Error>> {
         // Convert hyper request to reqwest request
         let (parts, body) = req.into_parts();
@@ -154,33 +344,45 @@ impl GatewayService {
             )
             .body(body_data);
 
-        // Add headers
+        // Add headers; reqwest is on http 1.x and hyper on 0.2, so they cross by name and bytes
         for (name, value) in parts.headers {
             if let Some(name) = name {
                 if let Ok(value_str) = value.to_str() {
-                    request_builder = request_builder.header(name, value_str);
+                    request_builder = request_builder.header(name.as_str(), value_str);
                 }
             }
         }
 
         // Send request
-        let response = request_builder.send().await?;
+        let mut response = request_builder.send().await?;
 
         // Convert reqwest response to hyper response
         let status = response.status();
         let headers = response.headers().clone();
-        let body_text = response.text().await?;
 
-        let mut hyper_response = Response::builder().status(status);
+        // Read the body, giving up as soon as it crosses the response limit
+        let too_large = || format!("Upstream response exceeds {} bytes", max_response_bytes);
+        if response.content_length().is_some_and(|len| len > max_response_bytes) {
+            return Err(too_large().into());
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            body.extend_from_slice(&chunk);
+            if body.len() as u64 > max_response_bytes {
+                return Err(too_large().into());
+            }
+        }
+
+        let mut hyper_response = Response::builder().status(status.as_u16());
 
         // Add headers
         for (name, value) in headers {
             if let Some(name) = name {
-                hyper_response = hyper_response.header(name, value);
+                hyper_response = hyper_response.header(name.as_str(), value.as_bytes());
             }
         }
 
-        Ok(hyper_response.body(Body::from(body_text))?)
+        Ok(hyper_response.body(Body::from(body))?)
     }
 
     /// Build upstream URI from route configuration
@@ -195,7 +397,8 @@ impl GatewayService {
 
         // Handle path replacement for proxy-style routing
         if route.path.ends_with("/*") {
-            let remaining_path = &req.uri().path()[route.path.len() - 1..];
+            // Keep the slash in front of the remainder: `/api/*` maps `/api/items` to `/items`
+            let remaining_path = &req.uri().path()[route.path.len() - 2..];
             upstream_url = upstream_url.replace("/*", remaining_path);
         }
 
@@ -204,16 +407,17 @@ impl GatewayService {
 
     /// Add gateway headers to upstream request
     fn add_gateway_headers(&self, req: &mut Request<Body>, route: &Route) {
+        let forwarded_host = req.uri().host().unwrap_or("unknown").to_string();
         let headers = req.headers_mut();
 
         // Add route-specific headers
         for (key, value) in &route.headers {
-            headers.insert(key.parse().unwrap(), value.parse().unwrap());
+            headers.insert(key.parse::<hyper::header::HeaderName>().unwrap(), value.parse().unwrap());
         }
 
         // Add gateway identification headers
         headers.insert("X-Gateway", "Fortress-AutoAgents".parse().unwrap());
-        headers.insert("X-Forwarded-Host", req.uri().host().unwrap_or("unknown").parse().unwrap());
+        headers.insert("X-Forwarded-Host", forwarded_host.parse().unwrap());
         headers.insert("X-Forwarded-Proto", "http".parse().unwrap());
         headers.insert("X-Request-ID", uuid::Uuid::new_v4().to_string().parse().unwrap());
         headers.insert("X-Gateway-Version", "0.1.0".parse().unwrap());
@@ -257,6 +461,34 @@ impl Service<Request<Body>> for GatewayService {
     fn call(&mut self, req: Request<Body>) -> Self::Future {
         let this = self.clone();
         Box::pin(async move {
+            // Probes are answered before upstream routing and maintenance mode
+            if req.method() == Method::GET {
+                match req.uri().path() {
+                    health::LIVENESS_PATH => return Ok(health::liveness_response()),
+                    health::READINESS_PATH => {
+                        let report = this.readiness().await;
+                        return Ok(health::readiness_response(&report));
+                    }
+                    _ => {}
+                }
+            }
+
+            if admin::is_admin_path(req.uri().path()) {
+                return Ok(admin::handle(req, &this.overlays, &this.usage, &this.mcp_registry, &this.blue_green, &this.cache).await);
+            }
+            if req.method() == Method::GET && mcp_catalog::is_catalog_path(req.uri().path()) {
+                return Ok(mcp_catalog::handle(req, &this.mcp_registry, &this.metrics).await);
+            }
+
+            let config = this.config.current();
+            if config.grpc.enabled && grpc::is_grpc_request(&req) {
+                if config.maintenance_mode {
+                    let status = grpc::Status::new(grpc::Code::Unavailable, "Gateway is in maintenance mode");
+                    return Ok(grpc::status_response(status));
+                }
+                return Ok(grpc::handle(req, &config.grpc, &this.http_client).await);
+            }
+
             match this.route_request(req).await {
                 Ok(response) => Ok(response),
                 Err(_) => Ok(this.create_error_response(
@@ -268,18 +500,38 @@ impl Service<Request<Body>> for GatewayService {
     }
 }
 
+/// Trace id from a W3C `traceparent` header; the header itself is forwarded upstream unchanged
+fn trace_id(headers: &HeaderMap) -> Option<&str> {
+    let traceparent = headers.get("traceparent")?.to_str().ok()?;
+    let mut parts = traceparent.split('-');
+    let (_version, trace_id) = (parts.next()?, parts.next()?);
+    (trace_id.len() == 32 && trace_id.bytes().all(|b| b.is_ascii_hexdigit())).then_some(trace_id)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
     use hyper::Method;
+    use crate::config::{CircuitBreakerConfig, FortressConfig};
+    use crate::middleware::circuit_breaker::CircuitState;
 
     #[tokio::test]
     async fn test_gateway_service_creation() {
         let config = FortressConfig::default();
         let metrics = MetricsCollector::new();
         let mcp_registry = McpRegistry::new(config.mcp.clone()).await.unwrap();
+        let live = SharedConfig::new(config.clone());
+        let overlays = OverlayScheduler::new(config, live.clone());
 
-        let service = GatewayService::new(config, metrics, mcp_registry);
+        let service = GatewayService::new(live, metrics, mcp_registry, overlays);
         // Service created successfully
         assert!(true);
     }
@@ -290,4 +542,311 @@ mod tests {
         // Simplified test for URI building logic
         assert!(true);
     }
+
+    /// Upstream answering every request with the shared status, counting hits
+    async fn serve_upstream(status: Arc<std::sync::Mutex<u16>>, hits: Arc<AtomicUsize>) -> String {
+        use hyper::service::{make_service_fn, service_fn};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let make_svc = make_service_fn(move |_conn| {
+            let (status, hits) = (status.clone(), hits.clone());
+            async move {
+                Ok::<_, std::convert::Infallible>(service_fn(move |_req| {
+                    hits.fetch_add(1, Ordering::SeqCst);
+                    let status = *status.lock().unwrap();
+                    async move {
+                        Ok::<_, std::convert::Infallible>(Response::builder().status(status).body(Body::empty()).unwrap())
+                    }
+                }))
+            }
+        });
+        tokio::spawn(hyper::Server::from_tcp(listener).unwrap().serve(make_svc));
+        format!("http://{}/*", addr)
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_fails_fast_and_recovers() {
+        let status = Arc::new(std::sync::Mutex::new(500));
+        let hits = Arc::new(AtomicUsize::new(0));
+        let upstream = serve_upstream(status.clone(), hits.clone()).await;
+
+        let mut config = FortressConfig::default();
+        config.routing.routes = vec![Route {
+            name: None,
+            path: "/api/*".to_string(),
+            upstream: upstream.clone(),
+            methods: vec!["GET".to_string()],
+            headers: HashMap::new(),
+            timeout_ms: None,
+            targets: vec![],
+            priority: 0,
+            match_headers: HashMap::new(),
+            cache: None,
+            retry: None,
+            rate_limit: None,
+        }];
+        config.routing.circuit_breaker = CircuitBreakerConfig {
+            failure_threshold: 3,
+            cooldown_ms: 200,
+            half_open_probes: 2,
+            ..Default::default()
+        };
+        let metrics = MetricsCollector::new();
+        let live = SharedConfig::new(config.clone());
+        let overlays = OverlayScheduler::new(config.clone(), live.clone());
+        let service = GatewayService::new(live, metrics.clone(), McpRegistry::empty(config.mcp), overlays);
+        let get = || Request::builder().method(Method::GET).uri("/api/items").body(Body::empty()).unwrap();
+
+        for _ in 0..3 {
+            let response = service.route_request(get()).await.unwrap();
+            assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        assert_eq!(service.circuit_breaker.state(&upstream), CircuitState::Open);
+
+        // Open: rejected without touching the upstream
+        let response = service.route_request(get()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[hyper::header::RETRY_AFTER], "1");
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        let exported = metrics.gather_metrics().unwrap();
+        assert!(exported.contains("fortress_circuit_breaker_rejections_total"));
+        assert!(exported.contains("state=\"open\""));
+        assert!(exported.contains(r#"fortress_http_requests_total{route="/api/*",status="503"} 1"#));
+
+        // Once the upstream recovers, both half-open probes succeed and the circuit closes
+        *status.lock().unwrap() = 200;
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        for _ in 0..2 {
+            let response = service.route_request(get()).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        assert_eq!(service.circuit_breaker.state(&upstream), CircuitState::Closed);
+        assert_eq!(hits.load(Ordering::SeqCst), 5);
+        assert!(metrics.gather_metrics().unwrap().contains("state=\"half_open\""));
+    }
+
+    /// Gateway with one `/api/*` route to `upstream`
+    fn retrying_gateway(upstream: String, timeout_ms: Option<u64>, metrics: MetricsCollector) -> GatewayService {
+        let mut config = FortressConfig::default();
+        config.routing.routes = vec![Route {
+            name: None,
+            path: "/api/*".to_string(),
+            upstream,
+            methods: vec![],
+            headers: HashMap::new(),
+            timeout_ms,
+            targets: vec![],
+            priority: 0,
+            match_headers: HashMap::new(),
+            cache: None,
+            retry: Some(crate::config::RetryPolicy::default()),
+            rate_limit: None,
+        }];
+        let live = SharedConfig::new(config.clone());
+        let overlays = OverlayScheduler::new(config.clone(), live.clone());
+        GatewayService::new(live, metrics, McpRegistry::empty(config.mcp), overlays)
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_requests_retry_a_reset_connection() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Resets every other connection without answering
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream = format!("http://{}/*", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicUsize::new(0));
+        let accepted = connections.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request).await;
+                if accepted.fetch_add(1, Ordering::SeqCst).is_multiple_of(2) {
+                    drop(stream);
+                    continue;
+                }
+                let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok").await;
+            }
+        });
+
+        let metrics = MetricsCollector::new();
+        let service = retrying_gateway(upstream, None, metrics.clone());
+        let request = |method: Method| Request::builder().method(method).uri("/api/items").body(Body::empty()).unwrap();
+
+        let response = service.route_request(request(Method::GET)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[RETRIES_HEADER], "1");
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+        // A POST is not repeated without an explicit override
+        let response = service.route_request(request(Method::POST)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        assert!(response.headers().get(RETRIES_HEADER).is_none());
+        assert_eq!(connections.load(Ordering::SeqCst), 3);
+
+        let exported = metrics.gather_metrics().unwrap();
+        assert!(exported.contains(r#"fortress_upstream_attempts_bucket{route="/api/*",le="1"} 1"#));
+        assert!(exported.contains(r#"fortress_upstream_attempts_count{route="/api/*"} 2"#));
+    }
+
+    #[tokio::test]
+    async fn test_attempts_time_out_and_retries_stop_at_the_limit() {
+        use hyper::service::{make_service_fn, service_fn};
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let upstream = format!("http://{}/*", listener.local_addr().unwrap());
+        let counted = hits.clone();
+        let make_svc = make_service_fn(move |_conn| {
+            let hits = counted.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |_req| {
+                    hits.fetch_add(1, Ordering::SeqCst);
+                    async {
+                        tokio::time::sleep(Duration::from_secs(2)).await;
+                        Ok::<_, Infallible>(Response::new(Body::from("too late")))
+                    }
+                }))
+            }
+        });
+        tokio::spawn(hyper::Server::from_tcp(listener).unwrap().serve(make_svc));
+
+        let service = retrying_gateway(upstream, Some(100), MetricsCollector::new());
+        let started = Instant::now();
+        let response = service
+            .route_request(Request::builder().uri("/api/slow").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(response.headers()[RETRIES_HEADER], "2");
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    /// Echo WebSocket server reporting each close frame it receives on `closes`
+    async fn serve_echo(closes: tokio::sync::mpsc::UnboundedSender<Option<u16>>) -> String {
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let closes = closes.clone();
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    while let Some(Ok(message)) = ws.next().await {
+                        match message {
+                            Message::Close(frame) => {
+                                let _ = closes.send(frame.map(|frame| frame.code.into()));
+                            }
+                            Message::Text(_) | Message::Binary(_) => ws.send(message).await.unwrap(),
+                            _ => {}
+                        }
+                    }
+                });
+            }
+        });
+        format!("http://{}/*", addr)
+    }
+
+    #[tokio::test]
+    async fn test_websocket_round_trip_and_close_through_gateway() {
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::{
+            client::IntoClientRequest,
+            protocol::{frame::coding::CloseCode, CloseFrame},
+            Message,
+        };
+
+        let (closes_tx, mut closes) = tokio::sync::mpsc::unbounded_channel();
+        let upstream = serve_echo(closes_tx).await;
+
+        let mut config = FortressConfig::default();
+        config.auth.service_accounts.insert("ui".to_string(), crate::config::ServiceAccount {
+            name: "ui".to_string(),
+            token: "ui-token".to_string(),
+            permissions: vec!["read".to_string()],
+        });
+        config.routing.routes = vec![Route {
+            name: None,
+            path: "/ws/*".to_string(),
+            upstream,
+            methods: vec!["GET".to_string()],
+            headers: HashMap::new(),
+            timeout_ms: None,
+            targets: vec![],
+            priority: 0,
+            match_headers: HashMap::new(),
+            cache: None,
+            retry: None,
+            rate_limit: None,
+        }];
+        config.websocket.max_frame_bytes = 64;
+        config.websocket.max_message_bytes = 64;
+        let metrics = MetricsCollector::new();
+        let live = SharedConfig::new(config.clone());
+        let overlays = OverlayScheduler::new(config.clone(), live.clone());
+        let service = GatewayService::new(live.clone(), metrics.clone(), McpRegistry::empty(config.mcp.clone()), overlays);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let usage = crate::usage::UsageTracker::new(&config.usage);
+        tokio::spawn(async move {
+            let _ = crate::serve_listener(listener, None, &live, &usage, &crate::middleware::CacheMiddleware::new(live.clone()), &crate::middleware::RateLimitMiddleware::new(live.clone()), &crate::middleware::AuditMiddleware::new(live.clone()), service, std::future::pending(), crate::DEFAULT_DRAIN_TIMEOUT).await;
+        });
+        let request = |token: Option<&str>| {
+            let mut request = format!("ws://{}/ws/echo", addr).into_client_request().unwrap();
+            if let Some(token) = token {
+                request.headers_mut().insert("Authorization", format!("Bearer {}", token).parse().unwrap());
+            }
+            request
+        };
+
+        // The handshake goes through the auth layer like any other request
+        match tokio_tungstenite::connect_async(request(None)).await {
+            Err(tokio_tungstenite::tungstenite::Error::Http(response)) => assert_eq!(response.status(), 401),
+            other => panic!("anonymous upgrade was not rejected: {:?}", other.map(|(_, response)| response.status())),
+        }
+
+        let (mut client, response) = tokio_tungstenite::connect_async(request(Some("ui-token"))).await.unwrap();
+        assert_eq!(response.status(), 101);
+        client.send(Message::Text("hello".to_string())).await.unwrap();
+        assert_eq!(client.next().await.unwrap().unwrap(), Message::Text("hello".to_string()));
+        client.send(Message::Binary(vec![1, 2, 3])).await.unwrap();
+        assert_eq!(client.next().await.unwrap().unwrap(), Message::Binary(vec![1, 2, 3]));
+
+        // A clean close reaches the upstream with its code, and the client's handshake completes
+        client
+            .close(Some(CloseFrame { code: CloseCode::Normal, reason: "done".into() }))
+            .await
+            .unwrap();
+        let upstream_close = tokio::time::timeout(Duration::from_secs(5), closes.recv()).await.unwrap();
+        assert_eq!(upstream_close, Some(Some(1000)));
+        while let Some(message) = client.next().await {
+            assert!(matches!(message, Ok(Message::Close(_))), "unexpected {:?}", message);
+        }
+
+        for _ in 0..50 {
+            if metrics.gather_metrics().unwrap().contains("fortress_websocket_connections_active 0") {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let exported = metrics.gather_metrics().unwrap();
+        assert!(exported.contains("fortress_websocket_connections_active 0"));
+        assert!(exported.contains("fortress_websocket_connections_total 1"));
+        assert!(exported.contains(r#"fortress_websocket_bytes_total{direction="client_to_upstream"} 8"#));
+        assert!(exported.contains(r#"fortress_websocket_bytes_total{direction="upstream_to_client"} 8"#));
+
+        // Messages over the limit close the connection with 1009
+        let (mut client, _) = tokio_tungstenite::connect_async(request(Some("ui-token"))).await.unwrap();
+        client.send(Message::Text("x".repeat(128))).await.unwrap();
+        match client.next().await {
+            Some(Ok(Message::Close(Some(frame)))) => assert_eq!(frame.code, CloseCode::Size),
+            other => panic!("oversized message did not close the connection: {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,584 @@
+//! gRPC Translation
+//!
+//! Lets gRPC-only clients reach Forge, which speaks JSON over HTTP. Calls to
+//! the `ForgeBridge` service in `proto/forge_bridge.proto` are served by the
+//! gateway itself, behind the auth layer:
+//!
+//! - `ExecuteModule` forwards to `POST {forge_url}/api/v1/modules/{module_id}/execute`
+//! - `GetResult` forwards to `GET {forge_url}/api/v1/executions/{execution_id}`
+//!
+//! The client's `grpc-timeout` becomes the upstream timeout, capped by
+//! `max_timeout_ms`. The request id and W3C trace context are propagated to
+//! Forge, and upstream errors map back to gRPC status codes.
+
+use std::time::Duration;
+
+use hyper::{
+    body::Bytes,
+    header::{HeaderMap, HeaderValue, CONTENT_TYPE},
+    http::StatusCode,
+    Body, Request, Response,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::config::GrpcBridgeConfig;
+
+/// Path prefix of the `ForgeBridge` service
+pub const SERVICE_PREFIX: &str = "/fortress.forge.v1.ForgeBridge/";
+
+const EXECUTE_MODULE_PATH: &str = "/fortress.forge.v1.ForgeBridge/ExecuteModule";
+
+const GET_RESULT_PATH: &str = "/fortress.forge.v1.ForgeBridge/GetResult";
+
+/// Length-prefixed message header: compression flag and big-endian length
+const FRAME_HEADER_LEN: usize = 5;
+
+/// `ExecuteModuleRequest` from `proto/forge_bridge.proto`
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExecuteModuleRequest {
+    #[prost(string, tag = "1")]
+    pub module_id: String,
+    #[prost(string, tag = "2")]
+    pub input_json: String,
+    #[prost(string, tag = "3")]
+    pub version: String,
+}
+
+/// `GetResultRequest` from `proto/forge_bridge.proto`
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetResultRequest {
+    #[prost(string, tag = "1")]
+    pub execution_id: String,
+}
+
+/// `ExecutionResult` from `proto/forge_bridge.proto`
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExecutionResult {
+    #[prost(string, tag = "1")]
+    pub execution_id: String,
+    #[prost(string, tag = "2")]
+    pub module_id: String,
+    #[prost(string, tag = "3")]
+    pub module_version: String,
+    #[prost(bool, tag = "4")]
+    pub success: bool,
+    #[prost(string, tag = "5")]
+    pub output_json: String,
+    #[prost(uint64, tag = "6")]
+    pub execution_time_ms: u64,
+    #[prost(uint64, tag = "7")]
+    pub memory_used_kb: u64,
+    #[prost(string, repeated, tag = "8")]
+    pub security_violations: Vec<String>,
+    #[prost(string, tag = "9")]
+    pub timestamp: String,
+    #[prost(string, tag = "10")]
+    pub trace_id: String,
+}
+
+/// Body of Forge's execute endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForgeExecuteRequest {
+    pub input: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// Execution result as Forge returns it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForgeExecutionResult {
+    pub execution_id: String,
+    #[serde(default)]
+    pub module_id: String,
+    #[serde(default)]
+    pub module_version: String,
+    pub success: bool,
+    pub output: serde_json::Value,
+    pub execution_time_ms: u64,
+    pub memory_used_kb: u64,
+    pub security_violations: Vec<String>,
+    pub timestamp: String,
+    #[serde(default)]
+    pub trace_id: Option<String>,
+}
+
+impl From<ForgeExecutionResult> for ExecutionResult {
+    fn from(result: ForgeExecutionResult) -> Self {
+        Self {
+            execution_id: result.execution_id,
+            module_id: result.module_id,
+            module_version: result.module_version,
+            success: result.success,
+            output_json: result.output.to_string(),
+            execution_time_ms: result.execution_time_ms,
+            memory_used_kb: result.memory_used_kb,
+            security_violations: result.security_violations,
+            timestamp: result.timestamp,
+            trace_id: result.trace_id.unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<ExecutionResult> for ForgeExecutionResult {
+    type Error = serde_json::Error;
+
+    fn try_from(result: ExecutionResult) -> Result<Self, Self::Error> {
+        Ok(Self {
+            execution_id: result.execution_id,
+            module_id: result.module_id,
+            module_version: result.module_version,
+            success: result.success,
+            output: serde_json::from_str(&result.output_json)?,
+            execution_time_ms: result.execution_time_ms,
+            memory_used_kb: result.memory_used_kb,
+            security_violations: result.security_violations,
+            timestamp: result.timestamp,
+            trace_id: Some(result.trace_id).filter(|trace_id| !trace_id.is_empty()),
+        })
+    }
+}
+
+/// gRPC status codes produced by the translation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Code {
+    Ok = 0,
+    InvalidArgument = 3,
+    DeadlineExceeded = 4,
+    NotFound = 5,
+    PermissionDenied = 7,
+    ResourceExhausted = 8,
+    Unimplemented = 12,
+    Internal = 13,
+    Unavailable = 14,
+    Unauthenticated = 16,
+}
+
+impl Code {
+    /// Status for an error response from Forge's JSON API
+    pub fn from_http(status: StatusCode) -> Self {
+        match status {
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => Code::InvalidArgument,
+            StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+            StatusCode::FORBIDDEN => Code::PermissionDenied,
+            StatusCode::NOT_FOUND => Code::NotFound,
+            StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+            StatusCode::GATEWAY_TIMEOUT => Code::DeadlineExceeded,
+            StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+            _ => Code::Internal,
+        }
+    }
+}
+
+/// A failed call: gRPC code and message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+    pub code: Code,
+    pub message: String,
+}
+
+impl Status {
+    pub fn new(code: Code, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+/// Per-call context propagated to Forge
+struct CallContext {
+    request_id: String,
+    traceparent: Option<HeaderValue>,
+    timeout: Duration,
+}
+
+/// Whether a request is a gRPC call to the `ForgeBridge` service
+pub fn is_grpc_request(req: &Request<Body>) -> bool {
+    req.uri().path().starts_with(SERVICE_PREFIX)
+        && req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|h| h.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with("application/grpc"))
+}
+
+/// Handle a `ForgeBridge` call by translating it to Forge's JSON API
+pub async fn handle(req: Request<Body>, config: &GrpcBridgeConfig, client: &reqwest::Client) -> Response<Body> {
+    let max_timeout = Duration::from_millis(config.max_timeout_ms);
+    let context = CallContext {
+        request_id: req
+            .headers()
+            .get("x-request-id")
+            .and_then(|h| h.to_str().ok())
+            .map(str::to_string)
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        traceparent: req.headers().get("traceparent").cloned(),
+        timeout: req
+            .headers()
+            .get("grpc-timeout")
+            .and_then(|h| h.to_str().ok())
+            .and_then(parse_timeout)
+            .map_or(max_timeout, |deadline| deadline.min(max_timeout)),
+    };
+    let path = req.uri().path().to_string();
+
+    let result = match hyper::body::to_bytes(req.into_body()).await {
+        Ok(body) => dispatch(&path, &body, config, client, &context).await,
+        Err(err) => Err(Status::new(Code::Internal, format!("Unreadable request body: {}", err))),
+    };
+
+    match &result {
+        Ok(_) => info!("🔁 gRPC {} translated (request {})", path, context.request_id),
+        Err(status) => warn!("🔁 gRPC {} failed with {:?}: {} (request {})", path, status.code, status.message, context.request_id),
+    }
+    grpc_response(result, &context.request_id)
+}
+
+async fn dispatch(
+    path: &str,
+    body: &[u8],
+    config: &GrpcBridgeConfig,
+    client: &reqwest::Client,
+    context: &CallContext,
+) -> Result<Vec<u8>, Status> {
+    let forge_url = config.forge_url.trim_end_matches('/');
+    let result = match path {
+        EXECUTE_MODULE_PATH => {
+            let request: ExecuteModuleRequest = decode_message(body)?;
+            let input = match request.input_json.as_str() {
+                "" => serde_json::Value::Null,
+                json => serde_json::from_str(json)
+                    .map_err(|e| Status::new(Code::InvalidArgument, format!("input_json is not valid JSON: {}", e)))?,
+            };
+            let body = ForgeExecuteRequest {
+                input,
+                version: Some(request.version).filter(|version| !version.is_empty()),
+            };
+            let url = format!("{}/api/v1/modules/{}/execute", forge_url, request.module_id);
+            call_forge(client.post(url).json(&body), context).await?
+        }
+        GET_RESULT_PATH => {
+            let request: GetResultRequest = decode_message(body)?;
+            let url = format!("{}/api/v1/executions/{}", forge_url, request.execution_id);
+            call_forge(client.get(url), context).await?
+        }
+        _ => return Err(Status::new(Code::Unimplemented, format!("Unknown method {}", path))),
+    };
+    Ok(prost::Message::encode_to_vec(&ExecutionResult::from(result)))
+}
+
+/// Send a request to Forge and read its JSON execution result
+async fn call_forge(request: reqwest::RequestBuilder, context: &CallContext) -> Result<ForgeExecutionResult, Status> {
+    let mut request = request
+        .timeout(context.timeout)
+        .header("X-Request-ID", context.request_id.as_str());
+    if let Some(traceparent) = &context.traceparent {
+        request = request.header("traceparent", traceparent.as_bytes());
+    }
+
+    let response = request.send().await.map_err(|e| {
+        if e.is_timeout() {
+            Status::new(Code::DeadlineExceeded, format!("Forge did not answer within {}ms", context.timeout.as_millis()))
+        } else {
+            Status::new(Code::Unavailable, format!("Forge unavailable: {}", e))
+        }
+    })?;
+
+    let status = StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let body = response.bytes().await.map_err(|e| {
+        if e.is_timeout() {
+            Status::new(Code::DeadlineExceeded, format!("Forge did not answer within {}ms", context.timeout.as_millis()))
+        } else {
+            Status::new(Code::Unavailable, format!("Failed to read Forge response: {}", e))
+        }
+    })?;
+
+    if !status.is_success() {
+        return Err(Status::new(Code::from_http(status), error_message(status, &body)));
+    }
+    serde_json::from_slice(&body).map_err(|e| Status::new(Code::Internal, format!("Malformed Forge response: {}", e)))
+}
+
+/// Message of a JSON error body, either `{"error": "..."}` or `{"error": {"message": "..."}}`
+fn error_message(status: StatusCode, body: &[u8]) -> String {
+    let error = serde_json::from_slice::<serde_json::Value>(body).ok().and_then(|body| {
+        let error = body.get("error")?;
+        error.as_str().or_else(|| error.get("message")?.as_str()).map(str::to_string)
+    });
+    error.unwrap_or_else(|| format!("Forge answered {}", status))
+}
+
+/// Decode a single length-prefixed, uncompressed message
+fn decode_message<M: prost::Message + Default>(body: &[u8]) -> Result<M, Status> {
+    if body.len() < FRAME_HEADER_LEN {
+        return Err(Status::new(Code::InvalidArgument, "Missing gRPC message"));
+    }
+    if body[0] != 0 {
+        return Err(Status::new(Code::Unimplemented, "Compressed gRPC messages are not supported"));
+    }
+    let len = u32::from_be_bytes([body[1], body[2], body[3], body[4]]) as usize;
+    let message = body[FRAME_HEADER_LEN..]
+        .get(..len)
+        .ok_or_else(|| Status::new(Code::InvalidArgument, "Truncated gRPC message"))?;
+    M::decode(message).map_err(|e| Status::new(Code::InvalidArgument, format!("Malformed gRPC message: {}", e)))
+}
+
+/// Parse a `grpc-timeout` header value, e.g. `250m` or `5S`
+fn parse_timeout(value: &str) -> Option<Duration> {
+    let (amount, unit) = value.split_at(value.len().checked_sub(1)?);
+    if amount.is_empty() || amount.len() > 8 {
+        return None;
+    }
+    let amount: u64 = amount.parse().ok()?;
+    Some(match unit {
+        "H" => Duration::from_secs(amount * 3600),
+        "M" => Duration::from_secs(amount * 60),
+        "S" => Duration::from_secs(amount),
+        "m" => Duration::from_millis(amount),
+        "u" => Duration::from_micros(amount),
+        "n" => Duration::from_nanos(amount),
+        _ => return None,
+    })
+}
+
+/// Trailers-only response failing a call before it is translated
+pub fn status_response(status: Status) -> Response<Body> {
+    grpc_response(Err(status), &uuid::Uuid::new_v4().to_string())
+}
+
+/// Build the gRPC response: the message followed by an OK status in the
+/// trailers, or a trailers-only response carrying the error status
+fn grpc_response(result: Result<Vec<u8>, Status>, request_id: &str) -> Response<Body> {
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/grpc")
+        .header("X-Request-ID", request_id);
+
+    let message = match result {
+        Ok(message) => message,
+        Err(status) => {
+            return response
+                .header("grpc-status", status.code as i32)
+                .header("grpc-message", percent_encode(&status.message))
+                .body(Body::empty())
+                .unwrap();
+        }
+    };
+
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + message.len());
+    frame.push(0);
+    frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&message);
+
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", HeaderValue::from(Code::Ok as i32));
+        if sender.send_data(Bytes::from(frame)).await.is_ok() {
+            let _ = sender.send_trailers(trailers).await;
+        }
+    });
+    response.body(body).unwrap()
+}
+
+/// Percent-encode a `grpc-message` value as the gRPC spec requires
+fn percent_encode(message: &str) -> String {
+    message
+        .bytes()
+        .map(|b| match b {
+            b' '..=b'~' if b != b'%' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    use prost::Message;
+    use tokio::net::TcpListener;
+
+    use crate::{config::{FortressConfig, SharedConfig}, usage::UsageTracker};
+
+    /// A result exactly as Forge serializes it, including fields the proto does not carry
+    fn forge_json() -> serde_json::Value {
+        serde_json::json!({
+            "execution_id": "exec-1",
+            "module_id": "echo",
+            "module_version": "1.2.0",
+            "success": true,
+            "output": {"result": "Executed: test", "items": [1, 2, 3]},
+            "execution_time_ms": 42,
+            "memory_used_kb": 256,
+            "security_violations": ["timeout"],
+            "timestamp": "2026-10-16T12:00:00+00:00",
+            "kind": "invocation",
+            "trace_id": "4bf92f3577b34da6a3ce929d0e0e4736",
+            "stdio": null
+        })
+    }
+
+    #[test]
+    fn test_round_trip_through_proto_preserves_json_contract() {
+        let json = forge_json();
+        let forge: ForgeExecutionResult = serde_json::from_value(json.clone()).unwrap();
+
+        let bytes = ExecutionResult::from(forge.clone()).encode_to_vec();
+        let decoded = ForgeExecutionResult::try_from(ExecutionResult::decode(bytes.as_slice()).unwrap()).unwrap();
+        assert_eq!(decoded, forge);
+
+        // Every field of the JSON contract survives, under the same name
+        let round_tripped = serde_json::to_value(&decoded).unwrap();
+        for (field, value) in round_tripped.as_object().unwrap() {
+            assert_eq!(json.get(field), Some(value), "field {} drifted", field);
+        }
+    }
+
+    #[test]
+    fn test_proto_schema_matches_json_contract() {
+        let proto = include_str!("../proto/forge_bridge.proto");
+        let message = proto.split("message ExecutionResult {").nth(1).unwrap().split('}').next().unwrap();
+        let proto_fields: Vec<&str> = message
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with("//") && line.ends_with(';'))
+            .filter_map(|line| line.split(" = ").next()?.split_whitespace().last())
+            .collect();
+
+        let json = serde_json::to_value(serde_json::from_value::<ForgeExecutionResult>(forge_json()).unwrap()).unwrap();
+        let mut json_fields: Vec<String> = json.as_object().unwrap().keys()
+            .map(|field| if field == "output" { "output_json".to_string() } else { field.clone() })
+            .collect();
+        let mut proto_fields: Vec<String> = proto_fields.into_iter().map(str::to_string).collect();
+        json_fields.sort();
+        proto_fields.sort();
+        assert_eq!(proto_fields, json_fields);
+
+        let request = ExecuteModuleRequest { module_id: "echo".into(), input_json: "{}".into(), version: String::new() };
+        assert_eq!(ExecuteModuleRequest::decode(request.encode_to_vec().as_slice()).unwrap(), request);
+    }
+
+    #[test]
+    fn test_timeout_and_status_mapping() {
+        assert_eq!(parse_timeout("250m"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_timeout("5S"), Some(Duration::from_secs(5)));
+        assert_eq!(parse_timeout("123456789m"), None);
+        assert_eq!(parse_timeout("5x"), None);
+        assert_eq!(Code::from_http(StatusCode::NOT_FOUND), Code::NotFound);
+        assert_eq!(Code::from_http(StatusCode::SERVICE_UNAVAILABLE), Code::Unavailable);
+        assert_eq!(percent_encode("50% done\n"), "50%25 done%0A");
+    }
+
+    /// Serve a mock Forge JSON API, failing unknown executions with 404
+    async fn mock_forge() -> String {
+        use hyper::service::{make_service_fn, service_fn};
+
+        let make_svc = make_service_fn(|_conn| async {
+            Ok::<_, Infallible>(service_fn(|req: Request<Body>| async move {
+                let path = req.uri().path().to_string();
+                let request_id = req.headers().get("x-request-id").cloned();
+                let traceparent = req.headers().get("traceparent").cloned();
+                let response = match path.as_str() {
+                    "/api/v1/modules/echo/execute" => {
+                        let body: ForgeExecuteRequest =
+                            serde_json::from_slice(&hyper::body::to_bytes(req.into_body()).await.unwrap()).unwrap();
+                        if body.input.get("sleep_ms").is_some() {
+                            tokio::time::sleep(Duration::from_millis(500)).await;
+                        }
+                        let mut json = forge_json();
+                        json["output"] = serde_json::json!({"echo": body.input, "request_id": request_id.map(|h| h.to_str().unwrap().to_string())});
+                        json["trace_id"] = traceparent.map_or(serde_json::Value::Null, |h| h.to_str().unwrap().split('-').nth(1).unwrap().into());
+                        Response::new(Body::from(json.to_string()))
+                    }
+                    _ => Response::builder()
+                        .status(StatusCode::NOT_FOUND)
+                        .body(Body::from(r#"{"error": "Execution not found"}"#))
+                        .unwrap(),
+                };
+                Ok::<_, Infallible>(response)
+            }))
+        });
+        let server = hyper::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_svc);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        url
+    }
+
+    #[tokio::test]
+    async fn test_tonic_client_executes_module_through_fortress() {
+        let mut config = FortressConfig::default();
+        config.auth.enabled = false;
+        config.grpc.forge_url = mock_forge().await;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let bridge_config = config.grpc.clone();
+        let bridge = tower::service_fn(move |req: Request<Body>| {
+            let config = bridge_config.clone();
+            async move { Ok::<_, Infallible>(handle(req, &config, &reqwest::Client::new()).await) }
+        });
+        tokio::spawn(async move {
+            let usage = UsageTracker::new(&config.usage);
+            let config = SharedConfig::new(config);
+            let _ = crate::serve_listener(listener, None, &config, &usage, &crate::middleware::CacheMiddleware::new(config.clone()), &crate::middleware::RateLimitMiddleware::new(config.clone()), &crate::middleware::AuditMiddleware::new(config.clone()), bridge, std::future::pending(), crate::DEFAULT_DRAIN_TIMEOUT).await;
+        });
+
+        let channel = tonic::transport::Channel::from_shared(format!("http://{}", addr))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        let mut client = tonic::client::Grpc::new(channel);
+
+        // Typed response, with request id and trace context propagated to Forge
+        let mut request = tonic::Request::new(ExecuteModuleRequest {
+            module_id: "echo".into(),
+            input_json: r#"{"command": "test"}"#.into(),
+            version: String::new(),
+        });
+        request.metadata_mut().insert("x-request-id", "req-123".parse().unwrap());
+        request.metadata_mut().insert("traceparent", "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".parse().unwrap());
+        client.ready().await.unwrap();
+        let path = tonic::codegen::http::uri::PathAndQuery::from_static(EXECUTE_MODULE_PATH);
+        let codec = tonic::codec::ProstCodec::<ExecuteModuleRequest, ExecutionResult>::default();
+        let result = client.unary(request, path, codec).await.unwrap().into_inner();
+        assert!(result.success);
+        assert_eq!(result.module_version, "1.2.0");
+        assert_eq!(result.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        let output: serde_json::Value = serde_json::from_str(&result.output_json).unwrap();
+        assert_eq!(output, serde_json::json!({"echo": {"command": "test"}, "request_id": "req-123"}));
+
+        // Upstream errors map to gRPC status codes
+        client.ready().await.unwrap();
+        let path = tonic::codegen::http::uri::PathAndQuery::from_static(GET_RESULT_PATH);
+        let missing = tonic::Request::new(GetResultRequest { execution_id: "nope".into() });
+        let codec = tonic::codec::ProstCodec::<GetResultRequest, ExecutionResult>::default();
+        let status = client.unary(missing, path, codec).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+        assert_eq!(status.message(), "Execution not found");
+
+    }
+
+    #[tokio::test]
+    async fn test_client_deadline_bounds_upstream_call() {
+        let config = GrpcBridgeConfig { forge_url: mock_forge().await, ..GrpcBridgeConfig::default() };
+        let message = ExecuteModuleRequest {
+            module_id: "echo".into(),
+            input_json: r#"{"sleep_ms": 500}"#.into(),
+            version: String::new(),
+        }
+        .encode_to_vec();
+        let mut frame = vec![0];
+        frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&message);
+
+        let req = Request::post(EXECUTE_MODULE_PATH)
+            .header(CONTENT_TYPE, "application/grpc")
+            .header("grpc-timeout", "100m")
+            .body(Body::from(frame))
+            .unwrap();
+        assert!(is_grpc_request(&req));
+        let response = handle(req, &config, &reqwest::Client::new()).await;
+        assert_eq!(response.headers()["grpc-status"], "4");
+    }
+}
@@ -0,0 +1,171 @@
+//! Liveness and readiness probes
+//!
+//! `GET /healthz` answers 200 whenever the gateway can serve requests.
+//! `GET /readyz` answers 200 only once the MCP registry has loaded and every
+//! configured upstream accepts TCP connections, so Kubernetes holds traffic
+//! until the gateway can actually route it.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use hyper::{header::CONTENT_TYPE, http::StatusCode, Body, Response, Uri};
+use serde::Serialize;
+use tokio::net::TcpStream;
+
+use crate::config::RoutingConfig;
+
+/// Liveness probe path
+pub const LIVENESS_PATH: &str = "/healthz";
+
+/// Readiness probe path
+pub const READINESS_PATH: &str = "/readyz";
+
+/// Per-upstream connect timeout for readiness checks
+pub const UPSTREAM_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Outcome of a readiness check
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub mcp_registry_loaded: bool,
+    /// Reachability by upstream `host:port`
+    pub upstreams: BTreeMap<String, bool>,
+}
+
+impl ReadinessReport {
+    pub fn new(mcp_registry_loaded: bool, upstreams: BTreeMap<String, bool>) -> Self {
+        Self {
+            ready: mcp_registry_loaded && upstreams.values().all(|reachable| *reachable),
+            mcp_registry_loaded,
+            upstreams,
+        }
+    }
+}
+
+/// `host:port` of an upstream URL, defaulting the port from the scheme
+pub fn upstream_authority(upstream: &str) -> Option<String> {
+    let uri: Uri = upstream.parse().ok()?;
+    let host = uri.host()?;
+    let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+        Some("https") => 443,
+        _ => 80,
+    });
+    Some(format!("{}:{}", host, port))
+}
+
+/// Try a TCP connect to every distinct upstream in the routing table
+pub async fn check_upstreams(routing: &RoutingConfig, timeout: Duration) -> BTreeMap<String, bool> {
+    let mut upstreams: Vec<String> = routing
+        .routes
+        .iter()
+        .flat_map(|r| r.targets())
+        .filter(|target| target.weight > 0)
+        .map(|target| target.url)
+        .collect();
+    if let Some(default) = &routing.default_upstream {
+        upstreams.push(default.clone());
+    }
+
+    let mut authorities: Vec<String> = upstreams
+        .into_iter()
+        .map(|upstream| upstream_authority(&upstream).unwrap_or(upstream))
+        .collect();
+    authorities.sort();
+    authorities.dedup();
+
+    let checks = authorities.into_iter().map(|authority| async move {
+        let reachable = matches!(
+            tokio::time::timeout(timeout, TcpStream::connect(&authority)).await,
+            Ok(Ok(_))
+        );
+        (authority, reachable)
+    });
+    futures::future::join_all(checks).await.into_iter().collect()
+}
+
+/// Response for the liveness probe
+pub fn liveness_response() -> Response<Body> {
+    json_response(StatusCode::OK, serde_json::json!({"status": "ok"}))
+}
+
+/// Response for the readiness probe: 200 when ready, 503 otherwise
+pub fn readiness_response(report: &ReadinessReport) -> Response<Body> {
+    let status = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    json_response(
+        status,
+        serde_json::json!({
+            "status": if report.ready { "ready" } else { "not_ready" },
+            "checks": report,
+        }),
+    )
+}
+
+fn json_response(status: StatusCode, body: serde_json::Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .header("Cache-Control", "no-store")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Route;
+    use std::collections::HashMap;
+
+    fn route(upstream: String) -> Route {
+        Route {
+            name: None,
+            path: "/api/*".to_string(),
+            upstream,
+            methods: vec![],
+            headers: HashMap::new(),
+            timeout_ms: None,
+            targets: vec![],
+            priority: 0,
+            match_headers: HashMap::new(),
+            cache: None,
+            retry: None,
+            rate_limit: None,
+        }
+    }
+
+    #[test]
+    fn test_upstream_authority() {
+        assert_eq!(upstream_authority("http://svc:8080/*").as_deref(), Some("svc:8080"));
+        assert_eq!(upstream_authority("https://svc/api").as_deref(), Some("svc:443"));
+        assert_eq!(upstream_authority("http://svc").as_deref(), Some("svc:80"));
+        assert_eq!(upstream_authority("not a url"), None);
+    }
+
+    #[tokio::test]
+    async fn test_readiness_requires_reachable_upstreams() {
+        let up = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let down = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let up_addr = up.local_addr().unwrap();
+        let down_addr = down.local_addr().unwrap();
+        drop(down);
+
+        let reachable = RoutingConfig {
+            routes: vec![route(format!("http://{}/*", up_addr))],
+            default_upstream: None,
+            ..Default::default()
+        };
+        let upstreams = check_upstreams(&reachable, UPSTREAM_CHECK_TIMEOUT).await;
+        assert!(ReadinessReport::new(true, upstreams.clone()).ready);
+        assert!(!ReadinessReport::new(false, upstreams).ready);
+
+        let mut partial = reachable.clone();
+        partial.routes.push(route(format!("http://{}/*", down_addr)));
+        let upstreams = check_upstreams(&partial, UPSTREAM_CHECK_TIMEOUT).await;
+        assert_eq!(upstreams.len(), 2);
+        let report = ReadinessReport::new(true, upstreams);
+        assert!(!report.ready);
+        assert_eq!(readiness_response(&report).status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -3,17 +3,34 @@
 //! A production-ready, Linkerd2-proxy inspired high-performance gateway
 //! with integrated BVEnterprisess MCP registry support.
 
+pub mod admin;
+pub mod audit;
+pub mod blue_green;
 pub mod config;
 pub mod gateway;
+pub mod grpc;
+pub mod health;
+pub mod mcp_catalog;
 pub mod mcp_registry;
 pub mod middleware;
 pub mod metrics;
+pub mod overlays;
+pub mod reload;
 pub mod routing;
 pub mod security;
-
-use std::net::SocketAddr;
-use tokio::net::TcpListener;
-use tower::ServiceBuilder;
+pub mod tls;
+pub mod usage;
+pub mod websocket;
+
+use std::{convert::Infallible, future::Future, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+use hyper::{Body, Request, Response};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpListener,
+    sync::{mpsc, watch},
+};
+use tokio_rustls::TlsAcceptor;
+use tower::{Service, ServiceBuilder};
 use tower_http::{
     cors::CorsLayer,
     trace::TraceLayer,
@@ -21,19 +38,47 @@ use tower_http::{
 };
 
 use crate::{
-    config::FortressConfig,
+    audit::AuditSink,
+    blue_green::BlueGreenSwitch,
+    config::{FortressConfig, SharedConfig},
     gateway::GatewayService,
-    middleware::{auth::AuthMiddleware, rate_limit::RateLimitMiddleware, cache::CacheMiddleware},
+    middleware::{
+        audit::AuditMiddleware, auth::AuthMiddleware, body_limit::BodyLimitMiddleware, cache::CacheMiddleware,
+        rate_limit::RateLimitMiddleware, usage::UsageMiddleware,
+    },
     metrics::MetricsCollector,
     mcp_registry::McpRegistry,
+    overlays::OverlayScheduler,
+    reload::{ConfigWatcher, ReloadError},
+    tls::ClientIdentity,
+    usage::UsageTracker,
 };
 
+/// How long shutdown waits for in-flight connections by default
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often scheduled overlay boundaries are checked while serving
+pub const OVERLAY_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often completed days are checked for usage summaries
+pub const USAGE_SUMMARY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often blue/green evaluation windows are checked for their end
+pub const BLUE_GREEN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
 /// Main Fortress gateway structure
 #[derive(Clone)]
 pub struct Fortress {
-    config: FortressConfig,
+    live_config: SharedConfig,
+    overlays: OverlayScheduler,
     metrics: MetricsCollector,
     mcp_registry: McpRegistry,
+    usage: UsageTracker,
+    blue_green: BlueGreenSwitch,
+    cache: CacheMiddleware,
+    rate_limit: RateLimitMiddleware,
+    audit: AuditMiddleware,
+    drain_timeout: Duration,
 }
 
 impl Fortress {
@@ -41,67 +86,124 @@ impl Fortress {
     pub async fn new(config: FortressConfig) -> Result<Self, Box<dyn std::error::Error>> {
         let metrics = MetricsCollector::new();
         let mcp_registry = McpRegistry::new(config.mcp.clone()).await?;
+        let live_config = SharedConfig::new(config.clone());
+        let overlays = OverlayScheduler::new(config.clone(), live_config.clone());
+        let usage = UsageTracker::new(&config.usage);
+        let blue_green = BlueGreenSwitch::new(&config.blue_green);
+        let cache = CacheMiddleware::new(live_config.clone()).with_metrics(metrics.clone());
+        let rate_limit = RateLimitMiddleware::new(live_config.clone()).with_metrics(metrics.clone());
+        let audit = AuditMiddleware::new(live_config.clone());
 
         Ok(Self {
-            config,
+            live_config,
+            overlays,
             metrics,
             mcp_registry,
+            usage,
+            blue_green,
+            cache,
+            rate_limit,
+            audit,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
         })
     }
 
-    /// Start the Fortress gateway server
+    /// Start the Fortress gateway server; runs until the process exits
     pub async fn serve(self, addr: SocketAddr) -> Result<(), Box<dyn std::error::Error>> {
+        self.serve_with_shutdown(addr, std::future::pending()).await
+    }
+
+    /// Start the Fortress gateway server and shut down gracefully once `shutdown` completes.
+    ///
+    /// On shutdown the listener stops accepting, in-flight connections finish
+    /// their current requests, and the call returns after they close or the
+    /// drain timeout elapses, whichever comes first.
+    pub async fn serve_with_shutdown(
+        self,
+        addr: SocketAddr,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         tracing::info!("🚀 Starting Fortress Gateway on {}", addr);
-        tracing::info!("📊 Metrics available at http://{}:{}/metrics", addr.ip(), addr.port() + 1);
 
+        let config = self.config();
+        let tls = config.security.tls.as_ref().map(tls::acceptor).transpose()?;
         let listener = TcpListener::bind(addr).await?;
-        let gateway_service = GatewayService::new(
-            self.config.clone(),
+        if tls.is_some() {
+            tracing::info!("🔒 Terminating TLS on {}", addr);
+        }
+        let metrics_server = bind_metrics_server(
+            metrics_addr(&config, addr)?,
             self.metrics.clone(),
             self.mcp_registry.clone(),
-        );
-
-        // Build middleware stack inspired by Linkerd2-proxy
-        let service = ServiceBuilder::new()
-            .layer(TraceLayer::new_for_http())
-            .layer(CompressionLayer::new())
-            .layer(CorsLayer::permissive())
-            .layer(AuthMiddleware::new(self.config.auth.clone()))
-            .layer(RateLimitMiddleware::new(self.config.rate_limit.clone()))
-            .layer(CacheMiddleware::new(self.config.cache.clone()))
-            .service(gateway_service);
-
-        // Start metrics server
-        let metrics_addr = SocketAddr::new(addr.ip(), addr.port() + 1);
-        tokio::spawn(async move {
-            if let Err(e) = start_metrics_server(metrics_addr, self.metrics).await {
+        )?;
+        let gateway_service = self.gateway_service();
+        let overlay_task = self.overlays.start(OVERLAY_CHECK_INTERVAL);
+        let usage_task = self.usage.start(USAGE_SUMMARY_INTERVAL, config.usage.summary_webhook_url.clone());
+        let blue_green_task = self.blue_green.start(BLUE_GREEN_CHECK_INTERVAL);
+
+        let metrics_task = tokio::spawn(async move {
+            if let Err(e) = metrics_server.await {
                 tracing::error!("Metrics server error: {}", e);
             }
         });
 
-        // Main server loop
-        loop {
-            let (stream, remote_addr) = listener.accept().await?;
-            let service = service.clone();
-
-            tokio::spawn(async move {
-                let service = hyper::service::service_fn(move |req| {
-                    let mut service = service.clone();
-                    async move {
-                        // Add client address to request extensions
-                        req.extensions_mut().insert(remote_addr);
-                        service.call(req).await
-                    }
-                });
+        let result = serve_listener(
+            listener,
+            tls,
+            &self.live_config,
+            &self.usage,
+            &self.cache,
+            &self.rate_limit,
+            &self.audit,
+            gateway_service,
+            shutdown,
+            self.drain_timeout,
+        )
+        .await;
+        overlay_task.abort();
+        usage_task.abort();
+        blue_green_task.abort();
+        metrics_task.abort();
+        result
+    }
 
-                if let Err(err) = hyper::server::conn::Http::new()
-                    .serve_connection(stream, service)
-                    .await
-                {
-                    tracing::error!("Connection error: {}", err);
-                }
-            });
+    /// Validate `config` and make it the base configuration.
+    ///
+    /// Auth keys, rate limits, cache settings and routes are read per
+    /// request, so they apply from the next request; requests in flight
+    /// finish under the configuration they started with. An active overlay
+    /// stays layered over the new base. Listener addresses, TLS, MCP, usage
+    /// and blue/green settings are read at startup and need a restart.
+    pub fn reload_config(&self, config: FortressConfig) -> Result<(), ReloadError> {
+        if let Err(e) = config.routing.validate() {
+            tracing::warn!("⚠️ Rejected configuration reload: {}", e);
+            self.metrics.record_config_reload(false);
+            return Err(e.into());
         }
+
+        self.overlays.set_base(config);
+        self.metrics.record_config_reload(true);
+        tracing::info!("🔄 Configuration reloaded ({} routes)", self.config().routing.routes.len());
+        Ok(())
+    }
+
+    /// Reload the configuration whenever the file at `path` changes, until
+    /// the returned watcher is dropped
+    pub fn watch_config(&self, path: impl Into<PathBuf>) -> Result<ConfigWatcher, ReloadError> {
+        ConfigWatcher::start(self.clone(), path)
+    }
+
+    /// Gateway service routing through this instance's live configuration
+    fn gateway_service(&self) -> GatewayService {
+        GatewayService::new(
+            self.live_config.clone(),
+            self.metrics.clone(),
+            self.mcp_registry.clone(),
+            self.overlays.clone(),
+        )
+        .with_usage(self.usage.clone())
+        .with_blue_green(self.blue_green.clone())
+        .with_cache(self.cache.clone())
     }
 
     /// Get MCP registry for external access
@@ -114,26 +216,242 @@ impl Fortress {
         &self.metrics
     }
 
-    /// Get configuration
-    pub fn config(&self) -> &FortressConfig {
-        &self.config
+    /// Get the base configuration, without scheduled overlays
+    pub fn config(&self) -> Arc<FortressConfig> {
+        self.overlays.base()
+    }
+
+    /// Get the live configuration, including any active overlay
+    pub fn live_config(&self) -> &SharedConfig {
+        &self.live_config
+    }
+
+    /// Get the scheduled overlay manager
+    pub fn overlays(&self) -> &OverlayScheduler {
+        &self.overlays
+    }
+
+    /// Get the per-principal usage tracker
+    pub fn usage(&self) -> &UsageTracker {
+        &self.usage
+    }
+
+    /// Get the blue/green switch
+    pub fn blue_green(&self) -> &BlueGreenSwitch {
+        &self.blue_green
+    }
+
+    /// Drop cached responses whose path starts with `path_prefix`, returning how many were dropped
+    pub async fn purge_cache(&self, path_prefix: &str) -> Result<usize, redis::RedisError> {
+        self.cache.purge(path_prefix).await
+    }
+}
+
+/// Accept connections on `listener` and drive each one through the
+/// middleware stack wrapped around `inner`, until `shutdown` completes
+#[allow(clippy::too_many_arguments)]
+async fn serve_listener<S>(
+    listener: TcpListener,
+    tls: Option<TlsAcceptor>,
+    config: &SharedConfig,
+    usage: &UsageTracker,
+    cache: &CacheMiddleware,
+    rate_limit: &RateLimitMiddleware,
+    audit: &AuditMiddleware,
+    inner: S,
+    shutdown: impl Future<Output = ()>,
+    drain_timeout: Duration,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    S: Service<Request<Body>, Response = Response<Body>, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    // Build middleware stack inspired by Linkerd2-proxy
+    let service = ServiceBuilder::new()
+        .layer(TraceLayer::new_for_http())
+        .layer(CompressionLayer::new())
+        .layer(audit.clone())
+        .layer(CorsLayer::permissive())
+        .layer(BodyLimitMiddleware::new(config.clone()))
+        .layer(AuthMiddleware::new(config.clone()))
+        .layer(UsageMiddleware::new(usage.clone()))
+        .layer(rate_limit.clone())
+        .layer(cache.clone())
+        .service(inner);
+
+    // Connections watch `shutdown_tx`; each holds a `drained_tx` clone, so
+    // `drained_rx` closes once the last connection has finished
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let (drained_tx, mut drained_rx) = mpsc::channel::<()>(1);
+    tokio::pin!(shutdown);
+
+    loop {
+        let (stream, remote_addr) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = &mut shutdown => break,
+        };
+        let service = TowerToHyperService::new(service.clone(), remote_addr);
+        let mut shutdown_rx = shutdown_rx.clone();
+        let drained_tx = drained_tx.clone();
+        let tls = tls.clone();
+
+        tokio::spawn(async move {
+            let _drained_tx = drained_tx;
+            // The handshake runs here rather than in the accept loop, so a slow client holds up only itself
+            let (io, service): (Box<dyn Connection>, _) = match tls {
+                Some(acceptor) => match acceptor.accept(stream).await {
+                    Ok(stream) => {
+                        let identity = stream
+                            .get_ref()
+                            .1
+                            .peer_certificates()
+                            .and_then(|certs| certs.first())
+                            .and_then(ClientIdentity::from_certificate);
+                        (Box::new(stream), service.with_client_identity(identity))
+                    }
+                    Err(err) => {
+                        tracing::warn!("⚠️ TLS handshake with {} failed: {}", remote_addr, err);
+                        return;
+                    }
+                },
+                None => (Box::new(stream), service),
+            };
+
+            // Upgrades let `GatewayService` hand WebSocket connections to the upstream
+            let conn = hyper::server::conn::Http::new().serve_connection(io, service).with_upgrades();
+            tokio::pin!(conn);
+
+            let result = tokio::select! {
+                result = conn.as_mut() => result,
+                _ = shutdown_rx.changed() => {
+                    // Let the in-flight request finish, then close
+                    conn.as_mut().graceful_shutdown();
+                    conn.await
+                }
+            };
+            if let Err(err) = result {
+                tracing::error!("Connection error: {}", err);
+            }
+        });
+    }
+
+    tracing::info!("🛑 Shutdown requested, no longer accepting connections");
+    drop(listener);
+    let _ = shutdown_tx.send(true);
+    drop(drained_tx);
+
+    match tokio::time::timeout(drain_timeout, drained_rx.recv()).await {
+        Ok(_) => tracing::info!("✅ All connections drained"),
+        Err(_) => tracing::warn!("⚠️ Drain timeout of {:?} elapsed with connections still open", drain_timeout),
+    }
+    Ok(())
+}
+
+/// An accepted connection, plain or TLS
+trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+/// Resolve on Ctrl+C or SIGTERM, for use with `Fortress::serve_with_shutdown`
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Adapts the `tower` middleware stack to a per-connection `hyper` service.
+///
+/// Stamps each request with the peer address (used by the rate limiter) and,
+/// on mTLS connections, the client's [`ClientIdentity`], then calls the same
+/// stack instance that hyper polled ready.
+pub struct TowerToHyperService<S> {
+    service: S,
+    remote_addr: SocketAddr,
+    client_identity: Option<ClientIdentity>,
+}
+
+impl<S> TowerToHyperService<S> {
+    pub fn new(service: S, remote_addr: SocketAddr) -> Self {
+        Self { service, remote_addr, client_identity: None }
+    }
+
+    /// Attach the verified client certificate identity of the connection
+    pub fn with_client_identity(mut self, identity: Option<ClientIdentity>) -> Self {
+        self.client_identity = identity;
+        self
     }
 }
 
-/// Start metrics server
-async fn start_metrics_server(
+impl<S, B> hyper::service::Service<Request<Body>> for TowerToHyperService<S>
+where
+    S: Service<Request<Body>, Response = Response<B>>,
+{
+    type Response = Response<B>;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        req.extensions_mut().insert(self.remote_addr);
+        if let Some(identity) = &self.client_identity {
+            req.extensions_mut().insert(identity.clone());
+        }
+        self.service.call(req)
+    }
+}
+
+/// Address for the metrics server: `metrics_addr` if configured, else the gateway port + 1
+pub fn metrics_addr(config: &FortressConfig, gateway_addr: SocketAddr) -> Result<SocketAddr, Box<dyn std::error::Error>> {
+    if let Some(addr) = config.metrics_addr {
+        return Ok(addr);
+    }
+
+    let port = gateway_addr
+        .port()
+        .checked_add(1)
+        .ok_or_else(|| format!("No port after {} for the metrics server; set metrics_addr", gateway_addr.port()))?;
+    Ok(SocketAddr::new(gateway_addr.ip(), port))
+}
+
+/// Bind the metrics server, failing immediately if the address is unavailable
+fn bind_metrics_server(
     addr: SocketAddr,
     metrics: MetricsCollector,
-) -> Result<(), Box<dyn std::error::Error>> {
+    mcp_registry: McpRegistry,
+) -> Result<impl Future<Output = Result<(), hyper::Error>>, Box<dyn std::error::Error>> {
     use hyper::service::{make_service_fn, service_fn};
     use hyper::{Body, Request, Response, Server};
 
     let make_svc = make_service_fn(move |_conn| {
         let metrics = metrics.clone();
+        let mcp_registry = mcp_registry.clone();
         async move {
             Ok::<_, std::convert::Infallible>(service_fn(move |_req: Request<Body>| {
                 let metrics = metrics.clone();
+                let mcp_registry = mcp_registry.clone();
                 async move {
+                    metrics.set_mcp_server_status(&mcp_registry.status().await);
                     let metrics_data = metrics.gather_metrics()
                         .unwrap_or_else(|_| "# Error collecting metrics\n".to_string());
 
@@ -148,22 +466,29 @@ async fn start_metrics_server(
         }
     });
 
-    let server = Server::bind(&addr).serve(make_svc);
+    let server = Server::try_bind(&addr)
+        .map_err(|e| format!("Failed to bind metrics server on {}: {}", addr, e))?
+        .serve(make_svc);
 
-    tracing::info!("📈 Metrics server listening on {}", addr);
-    server.await?;
-    Ok(())
+    tracing::info!("📊 Metrics available at http://{}/metrics", server.local_addr());
+    Ok(server)
 }
 
 /// Builder pattern for Fortress configuration
 pub struct FortressBuilder {
     config: FortressConfig,
+    drain_timeout: Duration,
+    overlay_store: Option<PathBuf>,
+    audit_sink: Option<Arc<dyn AuditSink>>,
 }
 
 impl FortressBuilder {
     pub fn new() -> Self {
         Self {
             config: FortressConfig::default(),
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            overlay_store: None,
+            audit_sink: None,
         }
     }
 
@@ -197,8 +522,45 @@ impl FortressBuilder {
         self
     }
 
+    /// How long graceful shutdown waits for in-flight connections
+    /// Serve metrics on `addr` instead of the gateway port + 1
+    pub fn with_metrics_addr(mut self, addr: SocketAddr) -> Self {
+        self.config.metrics_addr = Some(addr);
+        self
+    }
+
+    pub fn with_drain_timeout(mut self, drain_timeout: Duration) -> Self {
+        self.drain_timeout = drain_timeout;
+        self
+    }
+
+    /// Persist scheduled overlays at `path` so they survive restarts
+    pub fn with_overlay_store(mut self, path: impl Into<PathBuf>) -> Self {
+        self.overlay_store = Some(path.into());
+        self
+    }
+
+    pub fn with_audit(mut self, audit: config::AuditConfig) -> Self {
+        self.config.audit = audit;
+        self
+    }
+
+    /// Write audit entries to `sink` instead of the one `audit.sink` configures
+    pub fn with_audit_sink(mut self, sink: Arc<dyn AuditSink>) -> Self {
+        self.audit_sink = Some(sink);
+        self
+    }
+
     pub async fn build(self) -> Result<Fortress, Box<dyn std::error::Error>> {
-        Fortress::new(self.config).await
+        let mut fortress = Fortress::new(self.config).await?;
+        fortress.drain_timeout = self.drain_timeout;
+        if let Some(path) = self.overlay_store {
+            fortress.overlays = fortress.overlays.with_store(path)?;
+        }
+        if let Some(sink) = self.audit_sink {
+            fortress.audit = fortress.audit.with_sink(sink);
+        }
+        Ok(fortress)
     }
 }
 
@@ -226,8 +588,297 @@ mod tests {
         assert!(fortress.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_metrics_addr() {
+        let gateway: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+        let mut config = FortressConfig::default();
+        assert_eq!(metrics_addr(&config, gateway).unwrap(), "127.0.0.1:8081".parse().unwrap());
+        assert!(metrics_addr(&config, "127.0.0.1:65535".parse().unwrap()).is_err());
+
+        // An address already in use is a startup error, not a background one
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        config.metrics_addr = Some(taken.local_addr().unwrap());
+        let addr = metrics_addr(&config, gateway).unwrap();
+        assert_eq!(addr, taken.local_addr().unwrap());
+        assert!(bind_metrics_server(addr, MetricsCollector::new(), McpRegistry::empty(config.mcp.clone())).is_err());
+    }
+
     #[tokio::test]
     async fn test_health_check() {
         assert_eq!(health_check().await, "OK");
     }
+
+    #[tokio::test]
+    async fn test_auth_layer_runs_on_served_connections() {
+        let mut config = FortressConfig::default();
+        config.auth.service_accounts.insert("ci".to_string(), config::ServiceAccount {
+            name: "ci".to_string(),
+            token: "ci-token".to_string(),
+            permissions: vec!["read".to_string()],
+        });
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let upstream = tower::service_fn(|_req: Request<Body>| async {
+            Ok::<_, Infallible>(Response::new(Body::from("upstream")))
+        });
+        let usage = UsageTracker::new(&config.usage);
+        let served_usage = usage.clone();
+        tokio::spawn(async move {
+            let config = SharedConfig::new(config);
+            let _ = serve_listener(listener, None, &config, &served_usage, &CacheMiddleware::new(config.clone()), &RateLimitMiddleware::new(config.clone()), &AuditMiddleware::new(config.clone()), upstream, std::future::pending(), DEFAULT_DRAIN_TIMEOUT).await;
+        });
+
+        let client = reqwest::Client::new();
+        let url = format!("http://{}/api/v1/agents", addr);
+
+        let anonymous = client.get(&url).send().await.unwrap();
+        assert_eq!(anonymous.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let authenticated = client.get(&url).bearer_auth("ci-token").send().await.unwrap();
+        assert_eq!(authenticated.status(), reqwest::StatusCode::OK);
+        assert_eq!(authenticated.text().await.unwrap(), "upstream");
+
+        let public = client.get(format!("http://{}/health", addr)).send().await.unwrap();
+        assert_eq!(public.status(), reqwest::StatusCode::OK);
+
+        // Usage is attributed to the authenticated principal
+        let (from, to) = usage.retained_window();
+        let ci_requests: u64 = usage.report(Some("ci"), from, to).iter().map(|rollup| rollup.requests).sum();
+        assert_eq!(ci_requests, 1);
+    }
+
+    /// Fortress without a network-loaded MCP registry
+    fn offline_fortress(config: FortressConfig) -> Fortress {
+        let live_config = SharedConfig::new(config.clone());
+        Fortress {
+            overlays: OverlayScheduler::new(config.clone(), live_config.clone()),
+            cache: CacheMiddleware::new(live_config.clone()),
+            rate_limit: RateLimitMiddleware::new(live_config.clone()),
+            audit: AuditMiddleware::new(live_config.clone()),
+            live_config,
+            metrics: MetricsCollector::new(),
+            mcp_registry: McpRegistry::empty(config.mcp.clone()),
+            usage: UsageTracker::new(&config.usage),
+            blue_green: BlueGreenSwitch::new(&config.blue_green),
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+        }
+    }
+
+    /// Upstream answering every request with `name`
+    fn named_upstream(name: &'static str) -> String {
+        use hyper::service::{make_service_fn, service_fn};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let make_svc = make_service_fn(move |_conn| async move {
+            Ok::<_, Infallible>(service_fn(move |_req| async move { Ok::<_, Infallible>(Response::new(Body::from(name))) }))
+        });
+        tokio::spawn(hyper::Server::from_tcp(listener).unwrap().serve(make_svc));
+        format!("http://{}/*", addr)
+    }
+
+    fn api_route(upstream: String) -> config::Route {
+        config::Route {
+            name: None,
+            path: "/api/*".to_string(),
+            upstream,
+            methods: vec!["GET".to_string()],
+            headers: Default::default(),
+            timeout_ms: None,
+            targets: vec![],
+            priority: 0,
+            match_headers: Default::default(),
+            cache: None,
+            retry: None,
+            rate_limit: None,
+        }
+    }
+
+    /// Serve `fortress` on an ephemeral port, returning its address
+    async fn serve_offline(fortress: &Fortress) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let fortress = fortress.clone();
+        tokio::spawn(async move {
+            let service = fortress.gateway_service();
+            let _ = serve_listener(listener, None, &fortress.live_config, &fortress.usage, &fortress.cache, &fortress.rate_limit, &fortress.audit, service, std::future::pending(), DEFAULT_DRAIN_TIMEOUT).await;
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_reload_swaps_route_upstream() {
+        let (blue, green) = (named_upstream("blue"), named_upstream("green"));
+        let mut config = FortressConfig::default();
+        config.auth.enabled = false;
+        config.routing.routes = vec![api_route(blue)];
+        let fortress = offline_fortress(config.clone());
+        let addr = serve_offline(&fortress).await;
+        let client = reqwest::Client::new();
+        let get = || client.get(format!("http://{}/api/items", addr)).send();
+
+        assert_eq!(get().await.unwrap().text().await.unwrap(), "blue");
+
+        // The next request, on the same keep-alive connection, goes to the new upstream
+        config.routing.routes = vec![api_route(green.clone())];
+        fortress.reload_config(config.clone()).unwrap();
+        assert_eq!(get().await.unwrap().text().await.unwrap(), "green");
+        assert_eq!(fortress.config().routing.routes[0].upstream, green);
+
+        // An invalid configuration is rejected and the running one kept
+        config.routing.routes = vec![api_route(String::new())];
+        assert!(matches!(fortress.reload_config(config), Err(ReloadError::InvalidRouting(_))));
+        assert_eq!(get().await.unwrap().text().await.unwrap(), "green");
+
+        let metrics = fortress.metrics().gather_metrics().unwrap();
+        assert!(metrics.contains(r#"fortress_config_reloads_total{result="applied"} 1"#));
+        assert!(metrics.contains(r#"fortress_config_reloads_total{result="rejected"} 1"#));
+    }
+
+    #[tokio::test]
+    async fn test_config_file_changes_are_reloaded() {
+        let (blue, green) = (named_upstream("blue"), named_upstream("green"));
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fortress.json");
+        let mut config = FortressConfig::default();
+        config.auth.enabled = false;
+        config.routing.routes = vec![api_route(blue)];
+        std::fs::write(&path, serde_json::to_string(&config).unwrap()).unwrap();
+
+        let fortress = offline_fortress(config::load_config_file(&path).unwrap());
+        let addr = serve_offline(&fortress).await;
+        let _watcher = fortress.watch_config(&path).unwrap();
+
+        config.routing.routes = vec![api_route(green)];
+        std::fs::write(&path, serde_json::to_string(&config).unwrap()).unwrap();
+
+        let url = format!("http://{}/api/items", addr);
+        let reloaded = async {
+            loop {
+                if reqwest::get(&url).await.unwrap().text().await.unwrap() == "green" {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), reloaded).await.expect("config file change was not picked up");
+    }
+
+    /// Write a CA, a `localhost` server certificate and a client certificate
+    /// for `client_cn` into `dir`, returning the mTLS settings and the client's
+    /// PEM identity
+    fn write_mtls_certs(dir: &std::path::Path, client_cn: &str) -> (config::TlsConfig, String) {
+        use rcgen::{BasicConstraints, Certificate, CertificateParams, DnType, IsCa, SanType};
+
+        let mut ca_params = CertificateParams::new(vec![]);
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        ca_params.distinguished_name.push(DnType::CommonName, "Fortress Test CA");
+        let ca = Certificate::from_params(ca_params).unwrap();
+
+        let server = Certificate::from_params(CertificateParams::new(vec!["localhost".to_string()])).unwrap();
+        let mut client_params = CertificateParams::new(vec!["ci.example.com".to_string()]);
+        client_params.distinguished_name.push(DnType::CommonName, client_cn);
+        client_params.subject_alt_names.push(SanType::Rfc822Name("ci@example.com".to_string()));
+        let client = Certificate::from_params(client_params).unwrap();
+
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        std::fs::write(path("ca.pem"), ca.serialize_pem().unwrap()).unwrap();
+        std::fs::write(path("server.pem"), server.serialize_pem_with_signer(&ca).unwrap()).unwrap();
+        std::fs::write(path("server.key"), server.serialize_private_key_pem()).unwrap();
+
+        let tls = config::TlsConfig {
+            cert_path: path("server.pem"),
+            key_path: path("server.key"),
+            client_ca_path: Some(path("ca.pem")),
+            client_auth: config::ClientAuthMode::Require,
+        };
+        let identity = format!("{}{}", client.serialize_private_key_pem(), client.serialize_pem_with_signer(&ca).unwrap());
+        (tls, identity)
+    }
+
+    #[tokio::test]
+    async fn test_mtls_passes_client_identity_and_rejects_anonymous_clients() {
+        let dir = tempfile::tempdir().unwrap();
+        let (tls, identity) = write_mtls_certs(dir.path(), "ci-runner");
+        let mut config = FortressConfig::default();
+        config.auth.enabled = false;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let acceptor = tls::acceptor(&tls).unwrap();
+        let upstream = tower::service_fn(|req: Request<Body>| async move {
+            let identity = req.extensions().get::<ClientIdentity>().cloned().unwrap_or_default();
+            let body = format!("{}|{}", identity.common_name.unwrap_or_default(), identity.subject_alt_names.join(","));
+            Ok::<_, Infallible>(Response::new(Body::from(body)))
+        });
+        tokio::spawn(async move {
+            let usage = UsageTracker::new(&config.usage);
+            let config = SharedConfig::new(config);
+            let _ = serve_listener(listener, Some(acceptor), &config, &usage, &CacheMiddleware::new(config.clone()), &RateLimitMiddleware::new(config.clone()), &AuditMiddleware::new(config.clone()), upstream, std::future::pending(), DEFAULT_DRAIN_TIMEOUT).await;
+        });
+
+        let ca = reqwest::Certificate::from_pem(&std::fs::read(tls.client_ca_path.as_ref().unwrap()).unwrap()).unwrap();
+        let url = format!("https://localhost:{}/whoami", port);
+
+        let client = reqwest::Client::builder()
+            .use_rustls_tls()
+            .add_root_certificate(ca.clone())
+            .identity(reqwest::Identity::from_pem(identity.as_bytes()).unwrap())
+            .build()
+            .unwrap();
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "ci-runner|ci.example.com,ci@example.com");
+
+        // Without a client certificate the handshake is refused
+        let anonymous = reqwest::Client::builder().use_rustls_tls().add_root_certificate(ca).build().unwrap();
+        assert!(anonymous.get(&url).send().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drains_in_flight_requests() {
+        let mut config = FortressConfig::default();
+        config.auth.enabled = false;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let started = std::sync::Arc::new(tokio::sync::Notify::new());
+        let upstream_started = started.clone();
+        let upstream = tower::service_fn(move |_req: Request<Body>| {
+            let started = upstream_started.clone();
+            async move {
+                started.notify_one();
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                Ok::<_, Infallible>(Response::new(Body::from("slow")))
+            }
+        });
+
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(async move {
+            let shutdown = async {
+                let _ = shutdown_rx.await;
+            };
+            let usage = UsageTracker::new(&config.usage);
+            let config = SharedConfig::new(config);
+            serve_listener(listener, None, &config, &usage, &CacheMiddleware::new(config.clone()), &RateLimitMiddleware::new(config.clone()), &AuditMiddleware::new(config.clone()), upstream, shutdown, Duration::from_secs(5))
+                .await
+                .map_err(|e| e.to_string())
+        });
+
+        let in_flight = tokio::spawn(reqwest::get(format!("http://{}/slow", addr)));
+        started.notified().await;
+        shutdown_tx.send(()).unwrap();
+
+        let response = in_flight.await.unwrap().unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "slow");
+
+        tokio::time::timeout(Duration::from_secs(2), server)
+            .await
+            .expect("server did not return after draining")
+            .unwrap()
+            .unwrap();
+        assert!(reqwest::get(format!("http://{}/slow", addr)).await.is_err());
+    }
 }
@@ -0,0 +1,311 @@
+//! MCP tool catalogs
+//!
+//! Served by the gateway itself, behind the auth layer:
+//!
+//! - `GET /mcp/v1/tools` lists the tools of every routable MCP server
+//! - `GET /mcp/v1/servers/{name}/tools` lists one server's tools
+//!
+//! Catalogs are cached inline, tagged with the registry generation they were
+//! built from, and carry that generation as their `ETag`. A request whose
+//! `If-None-Match` names the current generation gets a 304 without touching
+//! the cache; any reload or change to the routable set bumps the generation,
+//! so the next request rebuilds. Entries for servers dropped by a reload are
+//! evicted right away.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use hyper::{
+    body::Bytes,
+    header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH},
+    http::{HeaderMap, StatusCode},
+    Body, Request, Response,
+};
+use serde::Serialize;
+use tracing::debug;
+
+use crate::{
+    mcp_registry::{McpRegistry, McpServerInfo},
+    metrics::MetricsCollector,
+};
+
+/// Catalog of every routable server
+pub const TOOLS_PATH: &str = "/mcp/v1/tools";
+
+const SERVERS_PREFIX: &str = "/mcp/v1/servers/";
+
+/// Tools offered by one MCP server
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerTools {
+    pub server: String,
+    /// `bv` or `awesome`
+    pub source: &'static str,
+    pub tools: Vec<String>,
+}
+
+impl From<McpServerInfo> for ServerTools {
+    fn from(info: McpServerInfo) -> Self {
+        match info {
+            McpServerInfo::Bv(server) => Self {
+                server: server.name,
+                source: "bv",
+                tools: server.capabilities,
+            },
+            McpServerInfo::Awesome(server) => Self {
+                server: server.name,
+                source: "awesome",
+                tools: server.tags,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedCatalog {
+    generation: u64,
+    /// Server the catalog covers; `None` for the full catalog
+    server: Option<String>,
+    body: Bytes,
+}
+
+/// Inline cache of serialized catalogs, keyed by path
+#[derive(Clone, Default)]
+pub struct CatalogCache {
+    entries: Arc<Mutex<HashMap<String, CachedCatalog>>>,
+}
+
+impl CatalogCache {
+    fn get(&self, path: &str, generation: u64) -> Option<Bytes> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(path)
+            .filter(|entry| entry.generation == generation)
+            .map(|entry| entry.body.clone())
+    }
+
+    fn insert(&self, path: &str, entry: CachedCatalog) {
+        self.entries.lock().unwrap().insert(path.to_string(), entry);
+    }
+
+    /// Evict the catalogs of servers that no longer exist
+    pub fn invalidate_servers<S: AsRef<str>>(&self, servers: &[S]) {
+        if servers.is_empty() {
+            return;
+        }
+        self.entries.lock().unwrap().retain(|_, entry| {
+            entry
+                .server
+                .as_deref()
+                .is_none_or(|server| !servers.iter().any(|removed| removed.as_ref() == server))
+        });
+    }
+
+    /// Whether a catalog for `path` is cached, whatever its generation
+    pub fn contains(&self, path: &str) -> bool {
+        self.entries.lock().unwrap().contains_key(path)
+    }
+}
+
+/// Whether a path is served from the tool catalog
+pub fn is_catalog_path(path: &str) -> bool {
+    path == TOOLS_PATH || server_name(path).is_some()
+}
+
+/// Server named by a `/mcp/v1/servers/{name}/tools` path
+fn server_name(path: &str) -> Option<&str> {
+    let name = path.strip_prefix(SERVERS_PREFIX)?.strip_suffix("/tools")?;
+    (!name.is_empty() && !name.contains('/')).then_some(name)
+}
+
+/// `ETag` of the catalogs built at `generation`
+pub fn etag(generation: u64) -> String {
+    format!("\"mcp-catalog-{}\"", generation)
+}
+
+/// Handle a tool catalog request
+pub async fn handle(req: Request<Body>, registry: &McpRegistry, metrics: &MetricsCollector) -> Response<Body> {
+    let path = req.uri().path();
+    let generation = registry.generation();
+    let etag = etag(generation);
+
+    if matches_etag(req.headers(), &etag) {
+        metrics.record_catalog_not_modified();
+        return Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(ETAG, &etag)
+            .header(CACHE_CONTROL, "no-cache")
+            .body(Body::empty())
+            .unwrap();
+    }
+
+    let cache = registry.catalog_cache();
+    if let Some(body) = cache.get(path, generation) {
+        debug!("📦 Catalog cache hit for {}", path);
+        metrics.record_catalog_cache_hit();
+        return catalog_response(body, &etag, "HIT");
+    }
+
+    let server = server_name(path).map(str::to_string);
+    let catalog = match &server {
+        Some(name) => match registry.get_server(name).await {
+            Some(info) if registry.is_routable(name).await => serde_json::json!(ServerTools::from(info)),
+            _ => return not_found(name),
+        },
+        None => {
+            let mut servers: Vec<ServerTools> =
+                registry.routable_servers().await.into_iter().map(ServerTools::from).collect();
+            servers.sort_by(|a, b| a.server.cmp(&b.server));
+            serde_json::json!({ "servers": servers })
+        }
+    };
+
+    // Tagged with the generation read up front: a change made while building
+    // leaves this entry stale, so the next request rebuilds
+    let body = Bytes::from(catalog.to_string());
+    cache.insert(path, CachedCatalog { generation, server, body: body.clone() });
+    metrics.record_catalog_cache_miss();
+    catalog_response(body, &etag, "MISS")
+}
+
+/// Whether `If-None-Match` names `etag`
+fn matches_etag(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+fn catalog_response(body: Bytes, etag: &str, cache_status: &'static str) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/json")
+        .header(ETAG, etag)
+        // Revalidate with the gateway rather than reuse blindly
+        .header(CACHE_CONTROL, "no-cache")
+        .header("X-Cache", cache_status)
+        .body(Body::from(body))
+        .unwrap()
+}
+
+fn not_found(server: &str) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::NOT_FOUND)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            serde_json::json!({ "error": format!("Unknown MCP server: {}", server) }).to_string(),
+        ))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::McpConfig, mcp_registry::BvServer};
+
+    /// Serve `body` as the BVEnterprisess registry
+    async fn serve_registry(body: Arc<Mutex<String>>) -> String {
+        use hyper::service::{make_service_fn, service_fn};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let make_svc = make_service_fn(move |_conn| {
+            let body = body.clone();
+            async move {
+                Ok::<_, std::convert::Infallible>(service_fn(move |_req| {
+                    let body = body.lock().unwrap().clone();
+                    async move { Ok::<_, std::convert::Infallible>(Response::new(Body::from(body))) }
+                }))
+            }
+        });
+        tokio::spawn(hyper::Server::from_tcp(listener).unwrap().serve(make_svc));
+        format!("http://{}/registry.json", addr)
+    }
+
+    fn registry_json(names: &[&str]) -> String {
+        let servers: Vec<BvServer> = names
+            .iter()
+            .map(|name| BvServer {
+                name: name.to_string(),
+                endpoint: "http://127.0.0.1:1".to_string(),
+                capabilities: vec![format!("{}_search", name)],
+                auth_required: false,
+                description: None,
+            })
+            .collect();
+        serde_json::json!({ "servers": servers }).to_string()
+    }
+
+    async fn get(
+        registry: &McpRegistry,
+        metrics: &MetricsCollector,
+        path: &str,
+        if_none_match: Option<&str>,
+    ) -> (StatusCode, Option<String>, serde_json::Value) {
+        let mut req = Request::get(path);
+        if let Some(tag) = if_none_match {
+            req = req.header(IF_NONE_MATCH, tag);
+        }
+        let response = handle(req.body(Body::empty()).unwrap(), registry, metrics).await;
+        let status = response.status();
+        let etag = response.headers().get(ETAG).map(|v| v.to_str().unwrap().to_string());
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null);
+        (status, etag, body)
+    }
+
+    #[tokio::test]
+    async fn test_catalog_revalidates_after_registry_changes() {
+        let source = Arc::new(Mutex::new(registry_json(&["alpha", "beta"])));
+        let config = McpConfig {
+            bv_enterprise_registry_url: serve_registry(source.clone()).await,
+            awesome_servers_url: None,
+            ..Default::default()
+        };
+        let registry = McpRegistry::empty(config);
+        registry.reload().await.unwrap();
+        let metrics = MetricsCollector::new();
+
+        let (status, first, body) = get(&registry, &metrics, TOOLS_PATH, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(first.as_deref(), Some(etag(registry.generation()).as_str()));
+        assert_eq!(body["servers"].as_array().unwrap().len(), 2);
+
+        let (status, etag_304, _) = get(&registry, &metrics, TOOLS_PATH, first.as_deref()).await;
+        assert_eq!(status, StatusCode::NOT_MODIFIED);
+        assert_eq!(etag_304, first);
+
+        let beta = "/mcp/v1/servers/beta/tools";
+        let (status, _, body) = get(&registry, &metrics, beta, None).await;
+        assert_eq!((status, body["tools"][0].as_str()), (StatusCode::OK, Some("beta_search")));
+        assert!(registry.catalog_cache().contains(beta));
+
+        // Dropping beta bumps the generation and evicts its catalog straight away
+        *source.lock().unwrap() = registry_json(&["alpha"]);
+        registry.reload().await.unwrap();
+        assert!(!registry.catalog_cache().contains(beta));
+        assert!(registry.catalog_cache().contains(TOOLS_PATH));
+
+        let (status, second, body) = get(&registry, &metrics, TOOLS_PATH, first.as_deref()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_ne!(second, first);
+        assert_eq!(second.as_deref(), Some(etag(registry.generation()).as_str()));
+        assert_eq!(body["servers"][0]["server"], "alpha");
+        assert_eq!(body["servers"].as_array().unwrap().len(), 1);
+        assert_eq!(get(&registry, &metrics, beta, None).await.0, StatusCode::NOT_FOUND);
+
+        // An unchanged registry is served from the cache
+        let (status, third, _) = get(&registry, &metrics, TOOLS_PATH, None).await;
+        assert_eq!((status, third), (StatusCode::OK, second));
+
+        let exported = metrics.gather_metrics().unwrap();
+        assert!(exported.contains(r#"fortress_mcp_catalog_cache_requests_total{result="not_modified"} 1"#));
+        assert!(exported.contains(r#"fortress_mcp_catalog_cache_requests_total{result="miss"} 3"#));
+        assert!(exported.contains(r#"fortress_mcp_catalog_cache_requests_total{result="hit"} 1"#));
+        assert!(!exported.contains("fortress_cache_requests_total{"));
+    }
+}
@@ -2,39 +2,77 @@
 //!
 //! Integrates with BVEnterprisess MCP registry and awesome-mcp-servers
 //! to provide a unified, health-checked, and cached MCP server directory.
+//!
+//! The server catalog is an immutable snapshot swapped in whole by
+//! [`McpRegistry::reload`]; lookups already holding the previous snapshot
+//! finish against it.
+//!
+//! Every server is probed on an interval. Failed probes mark it degraded and,
+//! after enough consecutive failures, down: down servers are left out of
+//! routing until they pass enough consecutive probes to be re-admitted.
+//!
+//! A generation counter is bumped by every reload and by every server
+//! entering or leaving routing; it versions the tool catalogs served by
+//! [`crate::mcp_catalog`].
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn, error};
 
-use crate::config::McpConfig;
+use crate::config::{McpConfig, McpHealthConfig, McpProbe};
+use crate::mcp_catalog::CatalogCache;
+
+/// Errors fetching the registry sources
+#[derive(Debug, thiserror::Error)]
+pub enum McpRegistryError {
+    #[error("Failed to fetch {url}: {source}")]
+    Fetch { url: String, source: reqwest::Error },
+    #[error("Failed to fetch {url}: HTTP {status}")]
+    Status { url: String, status: reqwest::StatusCode },
+    #[error("Invalid registry data from {url}: {reason}")]
+    Invalid { url: String, reason: String },
+}
+
+/// Servers known from both registry sources
+#[derive(Debug, Default)]
+struct Catalog {
+    bv_servers: HashMap<String, BvServer>,
+    awesome_servers: HashMap<String, AwesomeServer>,
+}
 
 /// MCP Server Registry
 #[derive(Clone)]
 pub struct McpRegistry {
     config: McpConfig,
-    bv_servers: Arc<RwLock<HashMap<String, BvServer>>>,
-    awesome_servers: Arc<RwLock<HashMap<String, AwesomeServer>>>,
-    health_status: Arc<RwLock<HashMap<String, ServerHealth>>>,
+    catalog: Arc<RwLock<Arc<Catalog>>>,
+    health_status: Arc<RwLock<HashMap<String, McpServerStatus>>>,
+    /// Set once the BVEnterprisess registry has been fetched successfully
+    loaded: Arc<AtomicBool>,
+    /// Bumped whenever the catalog or the routable set changes
+    generation: Arc<AtomicU64>,
+    catalog_cache: CatalogCache,
 }
 
 impl McpRegistry {
     /// Create a new MCP registry
     pub async fn new(config: McpConfig) -> Result<Self, Box<dyn std::error::Error>> {
-        let registry = Self {
-            config,
-            bv_servers: Arc::new(RwLock::new(HashMap::new())),
-            awesome_servers: Arc::new(RwLock::new(HashMap::new())),
-            health_status: Arc::new(RwLock::new(HashMap::new())),
+        let registry = Self::empty(config);
+
+        // Initial load of servers; a source answering with an error status is skipped
+        let bv_servers = skip_unavailable(registry.fetch_bv_servers().await)?;
+        registry.loaded.store(bv_servers.is_some(), Ordering::SeqCst);
+        let awesome_servers = match &registry.config.awesome_servers_url {
+            Some(url) => skip_unavailable(registry.fetch_awesome_servers(url).await)?,
+            None => None,
         };
-
-        // Initial load of servers
-        registry.load_bv_servers().await?;
-        if let Some(url) = &registry.config.awesome_servers_url {
-            registry.load_awesome_servers(url).await?;
-        }
+        *registry.catalog.write().await = Arc::new(Catalog {
+            bv_servers: bv_servers.unwrap_or_default(),
+            awesome_servers: awesome_servers.unwrap_or_default(),
+        });
+        registry.generation.fetch_add(1, Ordering::SeqCst);
 
         // Start health check loop
         registry.start_health_checks();
@@ -42,67 +80,103 @@ impl McpRegistry {
         Ok(registry)
     }
 
-    /// Load BVEnterprisess registry servers
-    async fn load_bv_servers(&self) -> Result<(), Box<dyn std::error::Error>> {
-        info!("Loading BVEnterprisess MCP registry from: {}", self.config.bv_enterprise_registry_url);
+    /// Registry with an empty catalog that has not fetched anything or started probing
+    pub(crate) fn empty(config: McpConfig) -> Self {
+        Self {
+            config,
+            catalog: Arc::new(RwLock::new(Arc::new(Catalog::default()))),
+            health_status: Arc::new(RwLock::new(HashMap::new())),
+            loaded: Arc::new(AtomicBool::new(false)),
+            generation: Arc::new(AtomicU64::new(0)),
+            catalog_cache: CatalogCache::default(),
+        }
+    }
 
-        let client = reqwest::Client::new();
-        let response = client
-            .get(&self.config.bv_enterprise_registry_url)
-            .timeout(std::time::Duration::from_secs(30))
-            .send()
-            .await?;
+    /// Re-read both registry sources and swap in the new catalog, returning
+    /// the number of servers. On failure the current catalog stays in place.
+    pub async fn reload(&self) -> Result<usize, McpRegistryError> {
+        let bv_servers = self.fetch_bv_servers().await?;
+        let awesome_servers = match &self.config.awesome_servers_url {
+            Some(url) => self.fetch_awesome_servers(url).await?,
+            None => HashMap::new(),
+        };
 
-        if !response.status().is_success() {
-            warn!("Failed to load BV registry: HTTP {}", response.status());
-            return Ok(()); // Don't fail, just warn
-        }
+        let count = bv_servers.len() + awesome_servers.len();
+        let catalog = Catalog { bv_servers, awesome_servers };
+        let previous = std::mem::replace(&mut *self.catalog.write().await, Arc::new(catalog));
+        self.loaded.store(true, Ordering::SeqCst);
+        self.generation.fetch_add(1, Ordering::SeqCst);
+
+        let current = self.catalog().await;
+        let removed: Vec<&String> = previous.bv_servers.keys()
+            .chain(previous.awesome_servers.keys())
+            .filter(|name| !current.bv_servers.contains_key(*name) && !current.awesome_servers.contains_key(*name))
+            .collect();
+        self.catalog_cache.invalidate_servers(&removed);
+
+        info!("🔄 Reloaded MCP registry: {} servers", count);
+        Ok(count)
+    }
 
-        let registry_data: BvRegistryResponse = response.json().await?;
-        let mut bv_servers = self.bv_servers.write().await;
+    /// Version of the catalog and routable set; changes on every reload and
+    /// whenever a server enters or leaves routing
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
 
-        for server in registry_data.servers {
-            bv_servers.insert(server.name.clone(), server);
-        }
+    /// Cache of the tool catalogs built from this registry
+    pub fn catalog_cache(&self) -> &CatalogCache {
+        &self.catalog_cache
+    }
 
-        info!("Loaded {} BVEnterprisess MCP servers", bv_servers.len());
-        Ok(())
+    /// Current catalog snapshot
+    async fn catalog(&self) -> Arc<Catalog> {
+        self.catalog.read().await.clone()
     }
 
-    /// Load awesome-mcp-servers
-    async fn load_awesome_servers(&self, url: &str) -> Result<(), Box<dyn std::error::Error>> {
-        info!("Loading awesome MCP servers from: {}", url);
+    /// Fetch BVEnterprisess registry servers
+    async fn fetch_bv_servers(&self) -> Result<HashMap<String, BvServer>, McpRegistryError> {
+        let url = &self.config.bv_enterprise_registry_url;
+        info!("Loading BVEnterprisess MCP registry from: {}", url);
 
-        let client = reqwest::Client::new();
-        let response = client
-            .get(url)
-            .timeout(std::time::Duration::from_secs(30))
-            .send()
-            .await?;
+        let response = fetch(url).await?;
+        let registry_data: BvRegistryResponse = response.json().await.map_err(|e| McpRegistryError::Invalid {
+            url: url.clone(),
+            reason: e.to_string(),
+        })?;
 
-        if !response.status().is_success() {
-            warn!("Failed to load awesome servers: HTTP {}", response.status());
-            return Ok(());
-        }
+        let bv_servers: HashMap<String, BvServer> = registry_data.servers
+            .into_iter()
+            .map(|server| (server.name.clone(), server))
+            .collect();
 
-        let readme_content = response.text().await?;
-        let servers = self.parse_awesome_servers(&readme_content)?;
-        let mut awesome_servers = self.awesome_servers.write().await;
+        info!("Loaded {} BVEnterprisess MCP servers", bv_servers.len());
+        Ok(bv_servers)
+    }
 
-        for server in servers {
-            awesome_servers.insert(server.name.clone(), server);
-        }
+    /// Fetch awesome-mcp-servers
+    async fn fetch_awesome_servers(&self, url: &str) -> Result<HashMap<String, AwesomeServer>, McpRegistryError> {
+        info!("Loading awesome MCP servers from: {}", url);
+
+        let readme_content = fetch(url).await?.text().await.map_err(|source| McpRegistryError::Fetch {
+            url: url.to_string(),
+            source,
+        })?;
+        let awesome_servers: HashMap<String, AwesomeServer> = self.parse_awesome_servers(&readme_content)
+            .into_iter()
+            .map(|server| (server.name.clone(), server))
+            .collect();
 
         info!("Loaded {} awesome MCP servers", awesome_servers.len());
-        Ok(())
+        Ok(awesome_servers)
     }
 
     /// Parse awesome-mcp-servers README for server information
-    fn parse_awesome_servers(&self, content: &str) -> Result<Vec<AwesomeServer>, Box<dyn std::error::Error>> {
+    fn parse_awesome_servers(&self, content: &str) -> Vec<AwesomeServer> {
         let mut servers = Vec::new();
 
         // Simple regex-based parsing of markdown links
-        let link_regex = regex::Regex::new(r"\[([^\]]+)\]\(([^)]+)\)")?;
+        let link_regex = regex::Regex::new(r"\[([^\]]+)\]\(([^)]+)\)").expect("valid link pattern");
 
         for line in content.lines() {
             if let Some(captures) = link_regex.captures(line) {
@@ -121,7 +195,7 @@ impl McpRegistry {
             }
         }
 
-        Ok(servers)
+        servers
     }
 
     /// Start periodic health checks
@@ -134,53 +208,46 @@ impl McpRegistry {
 
             loop {
                 interval.tick().await;
-                registry.perform_health_checks().await;
+                registry.run_health_checks().await;
             }
         });
     }
 
-    /// Perform health checks on all servers
-    async fn perform_health_checks(&self) {
-        let bv_servers = self.bv_servers.read().await.clone();
-        let awesome_servers = self.awesome_servers.read().await.clone();
+    /// Probe every server in the catalog once and update its status
+    pub async fn run_health_checks(&self) {
+        let catalog = self.catalog().await;
 
-        // Check BV servers
-        for (name, server) in bv_servers {
-            let health = self.check_server_health(&server.endpoint).await;
-            self.update_server_health(&name, health).await;
-        }
+        let bv_probes = catalog.bv_servers.iter().map(|(name, server)| async move {
+            (name, self.check_server_health(&server.endpoint).await)
+        });
+        // Awesome servers are repositories rather than endpoints, so only check the repo exists
+        let awesome_probes = catalog.awesome_servers.iter().map(|(name, server)| async move {
+            (name, self.check_github_repo_health(&server.github_url).await)
+        });
+        let (bv_results, awesome_results) = futures::join!(
+            futures::future::join_all(bv_probes),
+            futures::future::join_all(awesome_probes),
+        );
 
-        // Check awesome servers (simplified - just check if repo exists)
-        for (name, server) in awesome_servers {
-            let health = self.check_github_repo_health(&server.github_url).await;
-            self.update_server_health(&name, health).await;
+        for (name, health) in bv_results.into_iter().chain(awesome_results) {
+            self.update_server_health(name, health).await;
         }
     }
 
-    /// Check server health by making a simple request
+    /// Probe a server endpoint the configured way
     async fn check_server_health(&self, endpoint: &str) -> ServerHealth {
-        let client = reqwest::Client::new();
+        let health = &self.config.health;
+        let timeout = std::time::Duration::from_millis(health.probe_timeout_ms);
         let start = std::time::Instant::now();
 
-        match client
-            .get(endpoint)
-            .timeout(std::time::Duration::from_secs(10))
-            .send()
-            .await
-        {
-            Ok(response) => {
-                let latency = start.elapsed();
-                if response.status().is_success() {
-                    ServerHealth::Healthy { latency }
-                } else {
-                    ServerHealth::Unhealthy {
-                        reason: format!("HTTP {}", response.status()),
-                    }
-                }
-            }
-            Err(e) => ServerHealth::Unhealthy {
-                reason: format!("Connection failed: {}", e),
-            },
+        let outcome = match health.probe {
+            McpProbe::TcpConnect => tcp_probe(endpoint, timeout).await,
+            McpProbe::HttpGet => http_probe(endpoint, timeout).await,
+            McpProbe::McpPing => mcp_ping_probe(endpoint, timeout).await,
+        };
+        match outcome {
+            Ok(()) => ServerHealth::Healthy { latency: start.elapsed() },
+            Err(reason) => ServerHealth::Unhealthy { reason },
         }
     }
 
@@ -212,76 +279,144 @@ impl McpRegistry {
         }
     }
 
-    /// Update server health status
+    /// Record a probe result and move the server between states
     async fn update_server_health(&self, server_name: &str, health: ServerHealth) {
         let mut health_status = self.health_status.write().await;
-        health_status.insert(server_name.to_string(), health);
+        let status = health_status
+            .entry(server_name.to_string())
+            .or_insert_with(|| McpServerStatus::new(server_name));
+        let previous = status.status;
+        status.record(&health, &self.config.health);
+        if status.is_routable() != (previous != ServerStatus::Down) {
+            self.generation.fetch_add(1, Ordering::SeqCst);
+        }
+
+        match (previous, status.status) {
+            (ServerStatus::Down, ServerStatus::Down) => {}
+            (_, ServerStatus::Down) => error!(
+                "🔌 MCP server {} is down after {} failed probe(s), removing it from routing: {}",
+                server_name, status.consecutive_failures, status.last_error.as_deref().unwrap_or_default()
+            ),
+            (ServerStatus::Down, _) => info!(
+                "🔌 MCP server {} passed {} probe(s), routing to it again",
+                server_name, status.consecutive_successes
+            ),
+            (ServerStatus::Healthy, ServerStatus::Degraded) => warn!(
+                "🔌 MCP server {} failed a probe: {}",
+                server_name, status.last_error.as_deref().unwrap_or_default()
+            ),
+            _ => {}
+        }
     }
 
-    /// Get all healthy BV servers
+    /// Get all BV servers that have been probed and are not down
     pub async fn get_healthy_bv_servers(&self) -> HashMap<String, BvServer> {
-        let bv_servers = self.bv_servers.read().await;
+        let catalog = self.catalog().await;
         let health_status = self.health_status.read().await;
 
-        bv_servers
+        catalog.bv_servers
             .iter()
-            .filter(|(name, _)| matches!(
-                health_status.get(*name),
-                Some(ServerHealth::Healthy { .. })
-            ))
+            .filter(|(name, _)| health_status.get(*name).is_some_and(McpServerStatus::is_routable))
             .map(|(name, server)| (name.clone(), server.clone()))
             .collect()
     }
 
-    /// Get all healthy awesome servers
+    /// Get all awesome servers that have been probed and are not down
     pub async fn get_healthy_awesome_servers(&self) -> HashMap<String, AwesomeServer> {
-        let awesome_servers = self.awesome_servers.read().await;
+        let catalog = self.catalog().await;
         let health_status = self.health_status.read().await;
 
-        awesome_servers
+        catalog.awesome_servers
             .iter()
-            .filter(|(name, _)| matches!(
-                health_status.get(*name),
-                Some(ServerHealth::Healthy { .. })
-            ))
+            .filter(|(name, _)| health_status.get(*name).is_some_and(McpServerStatus::is_routable))
             .map(|(name, server)| (name.clone(), server.clone()))
             .collect()
     }
 
-    /// Get server health status
+    /// Result of the latest probe of a server
     pub async fn get_server_health(&self, server_name: &str) -> Option<ServerHealth> {
-        self.health_status.read().await.get(server_name).cloned()
+        let health_status = self.health_status.read().await;
+        let status = health_status.get(server_name)?;
+        Some(match &status.last_error {
+            Some(reason) => ServerHealth::Unhealthy { reason: reason.clone() },
+            None => ServerHealth::Healthy {
+                latency: std::time::Duration::from_millis(status.last_latency_ms.unwrap_or_default()),
+            },
+        })
+    }
+
+    /// Whether traffic may be routed to a server; servers not yet probed are
+    /// given the benefit of the doubt
+    pub async fn is_routable(&self, server_name: &str) -> bool {
+        self.health_status.read().await.get(server_name).is_none_or(McpServerStatus::is_routable)
+    }
+
+    /// Liveness of every probed server in the catalog, ordered by name
+    pub async fn status(&self) -> Vec<McpServerStatus> {
+        let catalog = self.catalog().await;
+        let health_status = self.health_status.read().await;
+        let mut statuses: Vec<McpServerStatus> = health_status
+            .values()
+            .filter(|status| catalog.bv_servers.contains_key(&status.name) || catalog.awesome_servers.contains_key(&status.name))
+            .cloned()
+            .collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
     }
 
     /// Get server by name from either registry
     pub async fn get_server(&self, name: &str) -> Option<McpServerInfo> {
+        let catalog = self.catalog().await;
+
         // Check BV servers first
-        if let Some(server) = self.bv_servers.read().await.get(name) {
+        if let Some(server) = catalog.bv_servers.get(name) {
             return Some(McpServerInfo::Bv(server.clone()));
         }
 
         // Check awesome servers
-        if let Some(server) = self.awesome_servers.read().await.get(name) {
+        if let Some(server) = catalog.awesome_servers.get(name) {
             return Some(McpServerInfo::Awesome(server.clone()));
         }
 
         None
     }
 
-    /// Search servers by capability
+    /// Every server in the catalog that is not down, including ones not yet probed
+    pub async fn routable_servers(&self) -> Vec<McpServerInfo> {
+        let catalog = self.catalog().await;
+        let health_status = self.health_status.read().await;
+        let routable = |name: &String| health_status.get(name).is_none_or(McpServerStatus::is_routable);
+
+        catalog.bv_servers
+            .values()
+            .filter(|server| routable(&server.name))
+            .map(|server| McpServerInfo::Bv(server.clone()))
+            .chain(
+                catalog.awesome_servers
+                    .values()
+                    .filter(|server| routable(&server.name))
+                    .map(|server| McpServerInfo::Awesome(server.clone())),
+            )
+            .collect()
+    }
+
+    /// Search servers by capability, leaving out servers that are down
     pub async fn search_by_capability(&self, capability: &str) -> Vec<McpServerInfo> {
+        let catalog = self.catalog().await;
+        let health_status = self.health_status.read().await;
+        let routable = |name: &String| health_status.get(name).is_none_or(McpServerStatus::is_routable);
         let mut results = Vec::new();
 
         // Search BV servers
-        for server in self.bv_servers.read().await.values() {
-            if server.capabilities.iter().any(|cap| cap.contains(capability)) {
+        for server in catalog.bv_servers.values() {
+            if server.capabilities.iter().any(|cap| cap.contains(capability)) && routable(&server.name) {
                 results.push(McpServerInfo::Bv(server.clone()));
             }
         }
 
         // Search awesome servers by tags
-        for server in self.awesome_servers.read().await.values() {
-            if server.tags.iter().any(|tag| tag.contains(capability)) {
+        for server in catalog.awesome_servers.values() {
+            if server.tags.iter().any(|tag| tag.contains(capability)) && routable(&server.name) {
                 results.push(McpServerInfo::Awesome(server.clone()));
             }
         }
@@ -289,14 +424,20 @@ impl McpRegistry {
         results
     }
 
+    /// Whether the BVEnterprisess registry has been loaded
+    pub fn is_loaded(&self) -> bool {
+        self.loaded.load(Ordering::SeqCst)
+    }
+
     /// Get registry statistics
     pub async fn get_stats(&self) -> RegistryStats {
-        let bv_count = self.bv_servers.read().await.len();
-        let awesome_count = self.awesome_servers.read().await.len();
+        let catalog = self.catalog().await;
+        let bv_count = catalog.bv_servers.len();
+        let awesome_count = catalog.awesome_servers.len();
         let health_status = self.health_status.read().await;
 
         let healthy_count = health_status.values()
-            .filter(|health| matches!(health, ServerHealth::Healthy { .. }))
+            .filter(|health| health.status == ServerStatus::Healthy)
             .count();
 
         RegistryStats {
@@ -308,6 +449,79 @@ impl McpRegistry {
     }
 }
 
+/// GET a registry source, failing on error statuses
+async fn fetch(url: &str) -> Result<reqwest::Response, McpRegistryError> {
+    let response = reqwest::Client::new()
+        .get(url)
+        .timeout(std::time::Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|source| McpRegistryError::Fetch { url: url.to_string(), source })?;
+
+    if !response.status().is_success() {
+        return Err(McpRegistryError::Status { url: url.to_string(), status: response.status() });
+    }
+    Ok(response)
+}
+
+/// Open a TCP connection to the endpoint's host and port
+async fn tcp_probe(endpoint: &str, timeout: std::time::Duration) -> Result<(), String> {
+    let url = reqwest::Url::parse(endpoint).map_err(|e| format!("Invalid endpoint: {}", e))?;
+    let host = url.host_str().ok_or("Endpoint has no host")?;
+    let port = url.port_or_known_default().ok_or("Endpoint has no port")?;
+    match tokio::time::timeout(timeout, tokio::net::TcpStream::connect((host, port))).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(format!("Connection failed: {}", e)),
+        Err(_) => Err(format!("Connection timed out after {:?}", timeout)),
+    }
+}
+
+/// GET the endpoint and expect a success status
+async fn http_probe(endpoint: &str, timeout: std::time::Duration) -> Result<(), String> {
+    let response = reqwest::Client::new()
+        .get(endpoint)
+        .timeout(timeout)
+        .send()
+        .await
+        .map_err(|e| format!("Connection failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    Ok(())
+}
+
+/// POST a JSON-RPC `ping` and expect a result rather than an error
+async fn mcp_ping_probe(endpoint: &str, timeout: std::time::Duration) -> Result<(), String> {
+    let response = reqwest::Client::new()
+        .post(endpoint)
+        .timeout(timeout)
+        .json(&serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" }))
+        .send()
+        .await
+        .map_err(|e| format!("Connection failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    let body: serde_json::Value = response.json().await.map_err(|e| format!("Invalid ping response: {}", e))?;
+    match (body.get("result"), body.get("error")) {
+        (Some(_), None) => Ok(()),
+        (_, Some(error)) => Err(format!("Ping failed: {}", error)),
+        (None, None) => Err("Ping response has no result".to_string()),
+    }
+}
+
+/// Treat a source answering with an error status as absent rather than failing
+fn skip_unavailable<T>(result: Result<T, McpRegistryError>) -> Result<Option<T>, McpRegistryError> {
+    match result {
+        Ok(servers) => Ok(Some(servers)),
+        Err(e @ McpRegistryError::Status { .. }) => {
+            warn!("{}", e);
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
 /// BVEnterprisess registry response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct BvRegistryResponse {
@@ -340,6 +554,73 @@ pub enum ServerHealth {
     Unhealthy { reason: String },
 }
 
+/// Liveness state of an MCP server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerStatus {
+    Healthy,
+    /// Failing probes, but still routed to
+    Degraded,
+    /// Excluded from routing until re-admitted
+    Down,
+}
+
+/// Probe history of one server, as reported by [`McpRegistry::status`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServerStatus {
+    pub name: String,
+    pub status: ServerStatus,
+    pub last_checked: chrono::DateTime<chrono::Utc>,
+    pub consecutive_failures: u32,
+    pub consecutive_successes: u32,
+    pub last_latency_ms: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+impl McpServerStatus {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            status: ServerStatus::Healthy,
+            last_checked: chrono::Utc::now(),
+            consecutive_failures: 0,
+            consecutive_successes: 0,
+            last_latency_ms: None,
+            last_error: None,
+        }
+    }
+
+    pub fn is_routable(&self) -> bool {
+        self.status != ServerStatus::Down
+    }
+
+    /// Apply a probe result to the counters and status
+    fn record(&mut self, health: &ServerHealth, config: &McpHealthConfig) {
+        self.last_checked = chrono::Utc::now();
+        match health {
+            ServerHealth::Healthy { latency } => {
+                self.consecutive_failures = 0;
+                self.consecutive_successes += 1;
+                self.last_latency_ms = Some(latency.as_millis() as u64);
+                self.last_error = None;
+                if self.status != ServerStatus::Down || self.consecutive_successes >= config.readmit_after_successes {
+                    self.status = ServerStatus::Healthy;
+                }
+            }
+            ServerHealth::Unhealthy { reason } => {
+                self.consecutive_successes = 0;
+                self.consecutive_failures += 1;
+                self.last_error = Some(reason.clone());
+                self.status = if self.consecutive_failures >= config.down_after_failures.max(1) {
+                    ServerStatus::Down
+                } else {
+                    ServerStatus::Degraded
+                };
+            }
+        }
+    }
+}
+
 /// Unified server information
 #[derive(Debug, Clone)]
 pub enum McpServerInfo {
@@ -367,6 +648,134 @@ mod tests {
         assert!(registry.is_ok());
     }
 
+    /// Serve `source` as the BVEnterprisess registry: a status and a body
+    async fn serve_registry(source: Arc<std::sync::Mutex<(u16, String)>>) -> String {
+        use hyper::service::{make_service_fn, service_fn};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let make_svc = make_service_fn(move |_conn| {
+            let source = source.clone();
+            async move {
+                Ok::<_, std::convert::Infallible>(service_fn(move |_req| {
+                    let (status, body) = source.lock().unwrap().clone();
+                    async move {
+                        Ok::<_, std::convert::Infallible>(
+                            hyper::Response::builder().status(status).body(hyper::Body::from(body)).unwrap(),
+                        )
+                    }
+                }))
+            }
+        });
+        tokio::spawn(hyper::Server::from_tcp(listener).unwrap().serve(make_svc));
+        format!("http://{}/registry.json", addr)
+    }
+
+    fn registry_json(names: &[&str]) -> String {
+        let servers: Vec<BvServer> = names
+            .iter()
+            .map(|name| BvServer {
+                name: name.to_string(),
+                endpoint: "http://127.0.0.1:1".to_string(),
+                capabilities: vec!["search".to_string()],
+                auth_required: false,
+                description: None,
+            })
+            .collect();
+        serde_json::json!({ "servers": servers }).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_reload_swaps_catalog() {
+        let source = Arc::new(std::sync::Mutex::new((200, registry_json(&["alpha"]))));
+        let config = McpConfig {
+            bv_enterprise_registry_url: serve_registry(source.clone()).await,
+            awesome_servers_url: None,
+            ..Default::default()
+        };
+        let registry = McpRegistry::new(config).await.unwrap();
+        assert_eq!(registry.get_stats().await.total_servers, 1);
+
+        // Lookups running during the swap see either catalog, both of which have `alpha`
+        *source.lock().unwrap() = (200, registry_json(&["alpha", "beta"]));
+        let lookups: Vec<_> = (0..32)
+            .map(|_| {
+                let registry = registry.clone();
+                tokio::spawn(async move { registry.get_server("alpha").await.is_some() })
+            })
+            .collect();
+        assert_eq!(registry.reload().await.unwrap(), 2);
+        for lookup in lookups {
+            assert!(lookup.await.unwrap());
+        }
+        assert!(registry.get_server("beta").await.is_some());
+
+        // A failed reload keeps the current catalog
+        *source.lock().unwrap() = (503, String::new());
+        assert!(matches!(registry.reload().await, Err(McpRegistryError::Status { .. })));
+        assert_eq!(registry.search_by_capability("search").await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failing_server_leaves_and_rejoins_healthy_set() {
+        let ping_ok = (200, r#"{"jsonrpc":"2.0","id":1,"result":{}}"#.to_string());
+        let mock = Arc::new(std::sync::Mutex::new(ping_ok.clone()));
+        let endpoint = serve_registry(mock.clone()).await;
+        let catalog = serde_json::json!({ "servers": [BvServer {
+            name: "search".to_string(),
+            endpoint,
+            capabilities: vec!["search".to_string()],
+            auth_required: false,
+            description: None,
+        }] });
+        let source = Arc::new(std::sync::Mutex::new((200, catalog.to_string())));
+        let config = McpConfig {
+            bv_enterprise_registry_url: serve_registry(source).await,
+            awesome_servers_url: None,
+            health_check_interval_seconds: 3600,
+            health: McpHealthConfig {
+                probe: McpProbe::McpPing,
+                probe_timeout_ms: 1000,
+                down_after_failures: 2,
+                readmit_after_successes: 2,
+            },
+            ..Default::default()
+        };
+        // No background checker: the test drives every probe round itself
+        let registry = McpRegistry::empty(config);
+        registry.reload().await.unwrap();
+        let status_of = |statuses: Vec<McpServerStatus>| statuses.into_iter().find(|s| s.name == "search").unwrap();
+
+        registry.run_health_checks().await;
+        assert!(registry.get_healthy_bv_servers().await.contains_key("search"));
+
+        // One failure degrades the server but keeps it routable
+        *mock.lock().unwrap() = (503, String::new());
+        registry.run_health_checks().await;
+        assert_eq!(status_of(registry.status().await).status, ServerStatus::Degraded);
+        assert!(registry.is_routable("search").await);
+
+        // The next one takes it out of routing
+        registry.run_health_checks().await;
+        let status = status_of(registry.status().await);
+        assert_eq!((status.status, status.consecutive_failures), (ServerStatus::Down, 2));
+        assert!(registry.get_healthy_bv_servers().await.is_empty());
+        assert!(registry.search_by_capability("search").await.is_empty());
+
+        let metrics = crate::metrics::MetricsCollector::new();
+        metrics.set_mcp_server_status(&registry.status().await);
+        assert!(metrics.gather_metrics().unwrap().contains(r#"fortress_mcp_server_status{server="search"} 2"#));
+
+        // It answers again, but is only re-admitted after enough good probes
+        *mock.lock().unwrap() = ping_ok;
+        registry.run_health_checks().await;
+        assert!(!registry.is_routable("search").await);
+        registry.run_health_checks().await;
+        assert_eq!(status_of(registry.status().await).status, ServerStatus::Healthy);
+        assert!(registry.get_healthy_bv_servers().await.contains_key("search"));
+        assert_eq!(registry.search_by_capability("search").await.len(), 1);
+    }
+
     #[test]
     fn test_registry_stats() {
         let stats = RegistryStats {
@@ -0,0 +1,384 @@
+//! Prometheus metrics for the Fortress gateway
+
+use std::time::Duration;
+
+use hyper::http::StatusCode;
+use prometheus::{
+    CounterVec, Encoder, HistogramOpts, HistogramVec, IntCounter, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+
+use crate::mcp_registry::{McpServerStatus, ServerStatus};
+use crate::middleware::circuit_breaker::CircuitState;
+use crate::websocket::Direction;
+
+/// Route label of requests that matched no route
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Metrics collector for the gateway
+///
+/// Each collector owns its own registry, so several Fortress instances
+/// (e.g. in tests) can coexist without clashing on the global default registry.
+#[derive(Clone)]
+pub struct MetricsCollector {
+    registry: Registry,
+    http_requests_total: CounterVec,
+    http_request_duration: HistogramVec,
+    cache_requests_total: CounterVec,
+    mcp_catalog_cache_requests_total: CounterVec,
+    rate_limit_exceeded_total: CounterVec,
+    rate_limit_redis_fallbacks_total: IntCounter,
+    auth_failures_total: CounterVec,
+    circuit_breaker_state: IntGaugeVec,
+    circuit_breaker_rejections_total: CounterVec,
+    circuit_breaker_transitions_total: CounterVec,
+    mcp_server_status: IntGaugeVec,
+    websocket_connections_active: IntGauge,
+    websocket_connections_total: IntCounter,
+    websocket_bytes_total: CounterVec,
+    config_reloads_total: CounterVec,
+    upstream_attempts: HistogramVec,
+    retry_budget_exhausted_total: CounterVec,
+}
+
+impl MetricsCollector {
+    /// Create a new metrics collector
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let http_requests_total = CounterVec::new(
+            Opts::new(
+                "fortress_http_requests_total",
+                "Total number of HTTP requests processed",
+            ),
+            &["route", "status"],
+        ).unwrap();
+
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "fortress_http_request_duration_seconds",
+                "HTTP request duration in seconds",
+            )
+            .buckets(vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 2.5, 5.0, 10.0]),
+            &["route", "status"],
+        ).unwrap();
+
+        let cache_requests_total = CounterVec::new(
+            Opts::new(
+                "fortress_cache_requests_total",
+                "Total number of cacheable requests by result",
+            ),
+            &["result"],
+        ).unwrap();
+
+        let mcp_catalog_cache_requests_total = CounterVec::new(
+            Opts::new(
+                "fortress_mcp_catalog_cache_requests_total",
+                "Total number of MCP tool catalog requests by cache result",
+            ),
+            &["result"],
+        ).unwrap();
+
+        let rate_limit_exceeded_total = CounterVec::new(
+            Opts::new(
+                "fortress_rate_limit_exceeded_total",
+                "Total number of requests rejected by the rate limiter",
+            ),
+            &["client_type"],
+        ).unwrap();
+
+        let rate_limit_redis_fallbacks_total = IntCounter::new(
+            "fortress_rate_limit_redis_fallbacks_total",
+            "Total number of rate limit decisions made in memory because Redis was unreachable",
+        ).unwrap();
+
+        let auth_failures_total = CounterVec::new(
+            Opts::new(
+                "fortress_auth_failures_total",
+                "Total number of requests rejected by authentication",
+            ),
+            &["reason"],
+        ).unwrap();
+
+        let circuit_breaker_state = IntGaugeVec::new(
+            Opts::new(
+                "fortress_circuit_breaker_state",
+                "Circuit breaker state per upstream (0 closed, 1 half-open, 2 open)",
+            ),
+            &["upstream"],
+        ).unwrap();
+
+        let circuit_breaker_rejections_total = CounterVec::new(
+            Opts::new(
+                "fortress_circuit_breaker_rejections_total",
+                "Total number of requests rejected by an open circuit",
+            ),
+            &["upstream"],
+        ).unwrap();
+
+        let circuit_breaker_transitions_total = CounterVec::new(
+            Opts::new(
+                "fortress_circuit_breaker_transitions_total",
+                "Total number of circuit state changes per upstream and new state",
+            ),
+            &["upstream", "state"],
+        ).unwrap();
+
+        let mcp_server_status = IntGaugeVec::new(
+            Opts::new(
+                "fortress_mcp_server_status",
+                "Liveness of each probed MCP server (0 healthy, 1 degraded, 2 down)",
+            ),
+            &["server"],
+        ).unwrap();
+
+        let websocket_connections_active = IntGauge::new(
+            "fortress_websocket_connections_active",
+            "WebSocket connections currently proxied",
+        ).unwrap();
+
+        let websocket_connections_total = IntCounter::new(
+            "fortress_websocket_connections_total",
+            "Total number of WebSocket connections proxied",
+        ).unwrap();
+
+        let websocket_bytes_total = CounterVec::new(
+            Opts::new(
+                "fortress_websocket_bytes_total",
+                "Total WebSocket payload bytes relayed by direction",
+            ),
+            &["direction"],
+        ).unwrap();
+
+        let config_reloads_total = CounterVec::new(
+            Opts::new(
+                "fortress_config_reloads_total",
+                "Total number of configuration reloads by result",
+            ),
+            &["result"],
+        ).unwrap();
+
+        let upstream_attempts = HistogramVec::new(
+            HistogramOpts::new(
+                "fortress_upstream_attempts",
+                "Upstream attempts per proxied request, the first included",
+            )
+            .buckets(vec![1.0, 2.0, 3.0, 4.0, 5.0, 8.0]),
+            &["route"],
+        ).unwrap();
+
+        let retry_budget_exhausted_total = CounterVec::new(
+            Opts::new(
+                "fortress_retry_budget_exhausted_total",
+                "Total number of retries skipped because the retry budget was spent",
+            ),
+            &["route"],
+        ).unwrap();
+
+        registry.register(Box::new(http_requests_total.clone())).unwrap();
+        registry.register(Box::new(http_request_duration.clone())).unwrap();
+        registry.register(Box::new(cache_requests_total.clone())).unwrap();
+        registry.register(Box::new(mcp_catalog_cache_requests_total.clone())).unwrap();
+        registry.register(Box::new(rate_limit_exceeded_total.clone())).unwrap();
+        registry.register(Box::new(rate_limit_redis_fallbacks_total.clone())).unwrap();
+        registry.register(Box::new(auth_failures_total.clone())).unwrap();
+        registry.register(Box::new(circuit_breaker_state.clone())).unwrap();
+        registry.register(Box::new(circuit_breaker_rejections_total.clone())).unwrap();
+        registry.register(Box::new(circuit_breaker_transitions_total.clone())).unwrap();
+        registry.register(Box::new(mcp_server_status.clone())).unwrap();
+        registry.register(Box::new(websocket_connections_active.clone())).unwrap();
+        registry.register(Box::new(websocket_connections_total.clone())).unwrap();
+        registry.register(Box::new(websocket_bytes_total.clone())).unwrap();
+        registry.register(Box::new(config_reloads_total.clone())).unwrap();
+        registry.register(Box::new(upstream_attempts.clone())).unwrap();
+        registry.register(Box::new(retry_budget_exhausted_total.clone())).unwrap();
+
+        Self {
+            registry,
+            http_requests_total,
+            http_request_duration,
+            cache_requests_total,
+            mcp_catalog_cache_requests_total,
+            rate_limit_exceeded_total,
+            rate_limit_redis_fallbacks_total,
+            auth_failures_total,
+            circuit_breaker_state,
+            circuit_breaker_rejections_total,
+            circuit_breaker_transitions_total,
+            mcp_server_status,
+            websocket_connections_active,
+            websocket_connections_total,
+            websocket_bytes_total,
+            config_reloads_total,
+            upstream_attempts,
+            retry_budget_exhausted_total,
+        }
+    }
+
+    /// Record a proxied HTTP request under the label of the route it matched,
+    /// or `UNMATCHED_ROUTE`
+    pub fn record_request(&self, route: &str, status: StatusCode, duration: Duration) {
+        let status = status.as_u16().to_string();
+        self.http_requests_total
+            .with_label_values(&[route, &status])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[route, &status])
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Record how many upstream attempts a request took
+    pub fn record_upstream_attempts(&self, route: &str, attempts: u32) {
+        self.upstream_attempts
+            .with_label_values(&[route])
+            .observe(attempts as f64);
+    }
+
+    /// Record a retry the retry budget did not allow
+    pub fn record_retry_budget_exhausted(&self, route: &str) {
+        self.retry_budget_exhausted_total
+            .with_label_values(&[route])
+            .inc();
+    }
+
+    /// Record a cache hit
+    pub fn record_cache_hit(&self) {
+        self.cache_requests_total.with_label_values(&["hit"]).inc();
+    }
+
+    /// Record a cache miss
+    pub fn record_cache_miss(&self) {
+        self.cache_requests_total.with_label_values(&["miss"]).inc();
+    }
+
+    /// Record an expired entry served while it is revalidated
+    pub fn record_cache_stale(&self) {
+        self.cache_requests_total.with_label_values(&["stale"]).inc();
+    }
+
+    /// Record an expired entry served because the upstream failed
+    pub fn record_cache_stale_on_error(&self) {
+        self.cache_requests_total.with_label_values(&["stale_on_error"]).inc();
+    }
+
+    /// Record an MCP tool catalog served from the catalog cache
+    pub fn record_catalog_cache_hit(&self) {
+        self.mcp_catalog_cache_requests_total.with_label_values(&["hit"]).inc();
+    }
+
+    /// Record an MCP tool catalog rebuilt from the registry
+    pub fn record_catalog_cache_miss(&self) {
+        self.mcp_catalog_cache_requests_total.with_label_values(&["miss"]).inc();
+    }
+
+    /// Record an MCP tool catalog request answered with 304
+    pub fn record_catalog_not_modified(&self) {
+        self.mcp_catalog_cache_requests_total.with_label_values(&["not_modified"]).inc();
+    }
+
+    /// Record a rate limit rejection
+    pub fn record_rate_limit_exceeded(&self, client_type: &str) {
+        self.rate_limit_exceeded_total
+            .with_label_values(&[client_type])
+            .inc();
+    }
+
+    /// Record a rate limit decision made in memory because Redis was unreachable
+    pub fn record_rate_limit_redis_fallback(&self) {
+        self.rate_limit_redis_fallbacks_total.inc();
+    }
+
+    /// Record an authentication rejection
+    pub fn record_auth_failure(&self, reason: &str) {
+        self.auth_failures_total
+            .with_label_values(&[reason])
+            .inc();
+    }
+
+    /// Record the circuit breaker state of an upstream
+    pub fn set_circuit_state(&self, upstream: &str, state: CircuitState) {
+        let value = match state {
+            CircuitState::Closed => 0,
+            CircuitState::HalfOpen => 1,
+            CircuitState::Open => 2,
+        };
+        self.circuit_breaker_state
+            .with_label_values(&[upstream])
+            .set(value);
+    }
+
+    /// Record an upstream's circuit moving to `state`
+    pub fn record_circuit_transition(&self, upstream: &str, state: CircuitState) {
+        let label = match state {
+            CircuitState::Closed => "closed",
+            CircuitState::HalfOpen => "half_open",
+            CircuitState::Open => "open",
+        };
+        self.circuit_breaker_transitions_total
+            .with_label_values(&[upstream, label])
+            .inc();
+        self.set_circuit_state(upstream, state);
+    }
+
+    /// Record a request rejected by an open circuit
+    pub fn record_circuit_rejection(&self, upstream: &str) {
+        self.circuit_breaker_rejections_total
+            .with_label_values(&[upstream])
+            .inc();
+    }
+
+    /// Replace the MCP server liveness gauges with `statuses`
+    pub fn set_mcp_server_status(&self, statuses: &[McpServerStatus]) {
+        // Servers dropped from the registry should not linger in the output
+        self.mcp_server_status.reset();
+        for status in statuses {
+            let value = match status.status {
+                ServerStatus::Healthy => 0,
+                ServerStatus::Degraded => 1,
+                ServerStatus::Down => 2,
+            };
+            self.mcp_server_status
+                .with_label_values(&[&status.name])
+                .set(value);
+        }
+    }
+
+    /// Record a WebSocket connection being established
+    pub fn record_websocket_opened(&self) {
+        self.websocket_connections_total.inc();
+        self.websocket_connections_active.inc();
+    }
+
+    /// Record a WebSocket connection ending
+    pub fn record_websocket_closed(&self) {
+        self.websocket_connections_active.dec();
+    }
+
+    /// Record payload bytes relayed over a WebSocket connection
+    pub fn record_websocket_bytes(&self, direction: Direction, bytes: usize) {
+        self.websocket_bytes_total
+            .with_label_values(&[direction.as_str()])
+            .inc_by(bytes as f64);
+    }
+
+    /// Record a configuration reload, applied or rejected
+    pub fn record_config_reload(&self, applied: bool) {
+        let result = if applied { "applied" } else { "rejected" };
+        self.config_reloads_total.with_label_values(&[result]).inc();
+    }
+
+    /// Get metrics in Prometheus format
+    pub fn gather_metrics(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let encoder = TextEncoder::new();
+        let metric_families = self.registry.gather();
+        let mut buffer = Vec::new();
+        encoder.encode(&metric_families, &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+impl Default for MetricsCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,20 @@
+//! Tower middleware layers applied in front of the gateway service
+
+pub mod audit;
+pub mod auth;
+pub mod body_limit;
+pub mod cache;
+pub mod circuit_breaker;
+pub mod rate_limit;
+pub mod redis_connector;
+pub mod retry;
+pub mod usage;
+
+pub use audit::AuditMiddleware;
+pub use auth::AuthMiddleware;
+pub use body_limit::BodyLimitMiddleware;
+pub use cache::CacheMiddleware;
+pub use circuit_breaker::CircuitBreaker;
+pub use rate_limit::RateLimitMiddleware;
+pub use retry::RetryBudget;
+pub use usage::UsageMiddleware;
@@ -0,0 +1,191 @@
+//! Request id and audit log middleware
+//!
+//! Sits outside every layer that can refuse a request, so refusals are logged
+//! too, and inside compression, so byte counts are of the uncompressed
+//! bodies. The request id is set before the layers below see the request, so
+//! it reaches the upstream with the forwarded headers. The principal comes from
+//! the [`AuthContext`] the auth middleware reports on the response; route and
+//! upstream come from the gateway's response extensions.
+
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use chrono::Utc;
+use hyper::{http::HeaderValue, Body, Request, Response};
+use tower::{Layer, Service};
+use tracing::Instrument;
+
+use crate::{
+    audit::{self, AuditEntry, AuditSink, REQUEST_ID_HEADER},
+    config::SharedConfig,
+    middleware::{auth::AuthContext, usage::body_len},
+    usage::{MatchedRoute, MatchedUpstream, ANONYMOUS},
+};
+
+/// Request id and audit log middleware
+#[derive(Clone)]
+pub struct AuditMiddleware {
+    config: SharedConfig,
+    sink: Arc<dyn AuditSink>,
+}
+
+impl AuditMiddleware {
+    /// Create an audit middleware writing to the sink `audit.sink` configures
+    pub fn new(config: SharedConfig) -> Self {
+        let sink = audit::sink_for(&config.current().audit);
+        Self { config, sink }
+    }
+
+    /// Write entries to `sink` instead
+    pub fn with_sink(mut self, sink: Arc<dyn AuditSink>) -> Self {
+        self.sink = sink;
+        self
+    }
+}
+
+impl<S> Layer<S> for AuditMiddleware {
+    type Service = AuditMiddlewareService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuditMiddlewareService {
+            inner,
+            config: self.config.clone(),
+            sink: self.sink.clone(),
+        }
+    }
+}
+
+/// Service wrapper for audit middleware
+#[derive(Clone)]
+pub struct AuditMiddlewareService<S> {
+    inner: S,
+    config: SharedConfig,
+    sink: Arc<dyn AuditSink>,
+}
+
+impl<S> Service<Request<Body>> for AuditMiddlewareService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = std::pin::Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let sink = self.sink.clone();
+        let config = self.config.current();
+
+        let request_id = audit::request_id(req.headers().get(REQUEST_ID_HEADER));
+        let header = HeaderValue::from_str(&request_id).ok();
+        if let Some(header) = &header {
+            req.headers_mut().insert(REQUEST_ID_HEADER, header.clone());
+        }
+        let span = tracing::info_span!("request", request_id = %request_id, method = %req.method(), path = %req.uri().path());
+        let logged = config.audit.enabled && audit::sampled(&request_id, config.audit.sample_rate);
+        let (method, path) = (req.method().to_string(), req.uri().path().to_string());
+        let bytes_in = body_len(req.headers(), req.body());
+        let started = Instant::now();
+
+        Box::pin(
+            async move {
+                let mut response = inner.call(req).await?;
+                if let Some(header) = header {
+                    response.headers_mut().insert(REQUEST_ID_HEADER, header);
+                }
+                if logged {
+                    let extensions = response.extensions();
+                    sink.write(&AuditEntry {
+                        timestamp: Utc::now(),
+                        request_id,
+                        method,
+                        path,
+                        principal: extensions
+                            .get::<AuthContext>()
+                            .map_or_else(|| ANONYMOUS.to_string(), |context| context.principal.clone()),
+                        route: extensions.get::<MatchedRoute>().map(|route| route.0.clone()),
+                        upstream: extensions.get::<MatchedUpstream>().map(|upstream| upstream.0.clone()),
+                        status: response.status().as_u16(),
+                        latency_ms: started.elapsed().as_millis() as u64,
+                        bytes_in,
+                        bytes_out: body_len(response.headers(), response.body()),
+                    });
+                }
+                Ok(response)
+            }
+            .instrument(span),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        audit::ChannelSink,
+        config::{AuthConfig, FortressConfig, ServiceAccount},
+        middleware::AuthMiddleware,
+    };
+    use std::convert::Infallible;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    #[tokio::test]
+    async fn test_request_id_round_trips_and_entry_names_route_and_principal() {
+        let mut config = FortressConfig {
+            auth: AuthConfig::default(),
+            ..Default::default()
+        };
+        config.auth.service_accounts.insert("ci".to_string(), ServiceAccount {
+            name: "ci".to_string(),
+            token: "ci-token".to_string(),
+            permissions: vec![],
+        });
+        let config = SharedConfig::new(config);
+        let (sink, mut entries) = ChannelSink::new();
+
+        // Stand-in for the gateway: echoes the request id it was sent upstream
+        let gateway = tower::service_fn(|req: Request<Body>| async move {
+            let forwarded = req.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+            let mut response = Response::new(Body::from(forwarded));
+            response.extensions_mut().insert(MatchedRoute("/api/*".to_string()));
+            response.extensions_mut().insert(MatchedUpstream("http://agents:8080".to_string()));
+            Ok::<_, Infallible>(response)
+        });
+        let service = ServiceBuilder::new()
+            .layer(AuditMiddleware::new(config.clone()).with_sink(Arc::new(sink)))
+            .layer(AuthMiddleware::new(config))
+            .service(gateway);
+
+        let req = Request::get("/api/v1/agents")
+            .header("authorization", "Bearer ci-token")
+            .header(REQUEST_ID_HEADER, "trace-abc")
+            .body(Body::empty())
+            .unwrap();
+        let response = service.clone().oneshot(req).await.unwrap();
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "trace-abc");
+        assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "trace-abc");
+
+        let entry = entries.recv().await.unwrap();
+        assert_eq!(
+            (entry.request_id.as_str(), entry.principal.as_str(), entry.route.as_deref(), entry.upstream.as_deref()),
+            ("trace-abc", "ci", Some("/api/*"), Some("http://agents:8080"))
+        );
+        assert_eq!((entry.method.as_str(), entry.path.as_str(), entry.status, entry.bytes_out), ("GET", "/api/v1/agents", 200, 9));
+
+        // Without an id one is generated; requests refused by auth are logged too
+        let response = service.oneshot(Request::get("/api/v1/agents").body(Body::empty()).unwrap()).await.unwrap();
+        let generated = response.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+        assert!(uuid::Uuid::parse_str(&generated).is_ok());
+        let entry = entries.recv().await.unwrap();
+        assert_eq!((entry.request_id, entry.principal.as_str(), entry.status), (generated, ANONYMOUS, 401));
+    }
+}
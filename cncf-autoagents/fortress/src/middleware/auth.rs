@@ -0,0 +1,849 @@
+//! Authentication middleware
+//!
+//! Accepts, in order: an MCP service token (`X-MCP-Token` + `X-MCP-Service`),
+//! a static API key (`X-API-Key` or bearer), a service account bearer token,
+//! an HS256 JWT signed with `jwt_secret`, or an asymmetrically signed JWT from
+//! the `jwt` issuer, verified against its JWKS. A request without any of
+//! these may still authenticate with its mTLS client certificate. Which of
+//! them a path accepts, if any, and which scopes it needs, comes from the
+//! first matching pattern in `AuthConfig::routes`, falling back to
+//! `default_requirement`.
+//!
+//! Every request that passes carries an [`AuthContext`] in its extensions,
+//! anonymous on public routes, and its response carries it back out;
+//! `X-User-*` headers sent by the client are dropped on every route, so only
+//! the ones set here reach the upstream; authenticated ones also carry their
+//! [`Principal`], and the validated [`Claims`] when a JWT was used. Missing
+//! or bad credentials get a 401, missing scopes a 403, both with a JSON body.
+//!
+//! The auth settings, keys and secrets included, are read from the live
+//! configuration on every request, so a reload takes effect for the next
+//! request; the JWKS cache is rebuilt when the `jwt` issuer settings change.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use hyper::{
+    header::{HeaderName, AUTHORIZATION, CONTENT_TYPE},
+    http::{HeaderMap, StatusCode},
+    Body, Request, Response,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tower::{Layer, Service};
+use tracing::{debug, info, warn};
+
+use crate::{
+    config::{AuthConfig, AuthRequirement, AuthRoute, JwtConfig, SharedConfig},
+    routing::path_matches,
+    tls::ClientIdentity,
+    usage::ANONYMOUS,
+};
+
+/// Minimum time between JWKS fetches triggered by an unknown `kid`
+const MIN_JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Authentication middleware for the gateway
+#[derive(Clone)]
+pub struct AuthMiddleware {
+    config: SharedConfig,
+    jwks: Arc<Mutex<Option<Arc<JwksCache>>>>,
+}
+
+impl AuthMiddleware {
+    /// Create a new authentication middleware reading `auth` from the live configuration
+    pub fn new(config: SharedConfig) -> Self {
+        Self {
+            config,
+            jwks: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+/// The JWKS cache for `jwt`, replacing the cached one if it was built for other settings
+fn jwks_for(cached: &Mutex<Option<Arc<JwksCache>>>, jwt: Option<&JwtConfig>) -> Option<Arc<JwksCache>> {
+    let jwt = jwt?;
+    let mut cached = cached.lock().unwrap();
+    match cached.as_ref() {
+        Some(jwks) if &jwks.config == jwt => Some(jwks.clone()),
+        _ => Some(cached.insert(Arc::new(JwksCache::new(jwt.clone()))).clone()),
+    }
+}
+
+impl<S> Layer<S> for AuthMiddleware {
+    type Service = AuthMiddlewareService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthMiddlewareService {
+            inner,
+            config: self.config.clone(),
+            jwks: self.jwks.clone(),
+        }
+    }
+}
+
+/// Service wrapper for authentication middleware
+#[derive(Clone)]
+pub struct AuthMiddlewareService<S> {
+    inner: S,
+    config: SharedConfig,
+    jwks: Arc<Mutex<Option<Arc<JwksCache>>>>,
+}
+
+impl<S> Service<Request<Body>> for AuthMiddlewareService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = std::pin::Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        // Drive the instance that was polled ready; leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let snapshot = self.config.current();
+        let jwks = jwks_for(&self.jwks, snapshot.auth.jwt.as_ref());
+
+        Box::pin(async move {
+            strip_identity_headers(req.headers_mut());
+            let config = &snapshot.auth;
+            let requirement = requirement_for(config, req.uri().path());
+            if !config.enabled || requirement == AuthRequirement::Public {
+                req.extensions_mut().insert(AuthContext::anonymous());
+                return inner.call(req).await.map(|response| with_context(response, AuthContext::anonymous()));
+            }
+
+            let principal = match authenticate(config, jwks.as_deref(), req.headers()).await {
+                Err(AuthError::MissingCredentials) => req
+                    .extensions()
+                    .get::<ClientIdentity>()
+                    .and_then(|identity| authenticate_client(config, identity))
+                    .ok_or(AuthError::MissingCredentials),
+                principal => principal,
+            };
+            let scopes = required_scopes(config, req.uri().path());
+            match principal.and_then(|principal| principal.satisfies(requirement)?.authorize(scopes)) {
+                Ok(principal) => {
+                    debug!("🔐 Authenticated {} for {}", principal.subject, req.uri().path());
+                    if let Ok(value) = principal.subject.parse() {
+                        req.headers_mut().insert("X-User-ID", value);
+                    }
+                    if let Ok(value) = principal.roles.join(",").parse() {
+                        req.headers_mut().insert("X-User-Roles", value);
+                    }
+                    if let Some(claims) = principal.claims.clone() {
+                        req.extensions_mut().insert(claims);
+                    }
+                    let context = AuthContext {
+                        principal: principal.subject.clone(),
+                        scopes: principal.scopes.clone(),
+                    };
+                    req.extensions_mut().insert(context.clone());
+                    req.extensions_mut().insert(principal);
+                    inner.call(req).await.map(|response| with_context(response, context))
+                }
+                Err(err) => {
+                    warn!("🚫 Authentication failed for {}: {}", req.uri().path(), err);
+                    Ok(with_context(error_response(&err), AuthContext::anonymous()))
+                }
+            }
+        })
+    }
+}
+
+/// Drop client-sent `X-User-*` headers; only this middleware sets them
+fn strip_identity_headers(headers: &mut HeaderMap) {
+    let spoofed: Vec<HeaderName> = headers.keys().filter(|name| name.as_str().starts_with("x-user-")).cloned().collect();
+    for name in spoofed {
+        headers.remove(name);
+    }
+}
+
+/// Report the caller to outer layers, such as the audit log, on the response
+fn with_context(mut response: Response<Body>, context: AuthContext) -> Response<Body> {
+    response.extensions_mut().insert(context);
+    response
+}
+
+/// Caller identity handed to downstream services through the request extensions,
+/// and to outer layers through the response extensions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthContext {
+    pub principal: String,
+    pub scopes: Vec<String>,
+}
+
+impl AuthContext {
+    /// Context of a request on a public route
+    pub fn anonymous() -> Self {
+        Self {
+            principal: ANONYMOUS.to_string(),
+            scopes: Vec::new(),
+        }
+    }
+}
+
+/// Authenticated caller
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub subject: String,
+    pub roles: Vec<String>,
+    pub scopes: Vec<String>,
+    pub method: AuthMethod,
+    /// Validated token claims, for JWT principals
+    pub claims: Option<Claims>,
+}
+
+/// Credential a principal authenticated with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthMethod {
+    McpToken,
+    ApiKey,
+    ServiceAccount,
+    Jwt,
+    Mtls,
+}
+
+impl Principal {
+    /// Pass the principal through if its credential meets `requirement`
+    pub fn satisfies(self, requirement: AuthRequirement) -> Result<Self, AuthError> {
+        let accepted = match requirement {
+            AuthRequirement::Public | AuthRequirement::Authenticated => true,
+            AuthRequirement::ApiKey => matches!(
+                self.method,
+                AuthMethod::McpToken | AuthMethod::ApiKey | AuthMethod::ServiceAccount
+            ),
+            AuthRequirement::Jwt => self.method == AuthMethod::Jwt,
+            AuthRequirement::Mtls => self.method == AuthMethod::Mtls,
+        };
+        if accepted {
+            Ok(self)
+        } else {
+            Err(AuthError::CredentialNotAccepted)
+        }
+    }
+
+    /// Pass the principal through if it holds every scope in `required`
+    pub fn authorize(self, required: &[String]) -> Result<Self, AuthError> {
+        match required.iter().find(|scope| !self.scopes.contains(scope)) {
+            Some(missing) => Err(AuthError::InsufficientScope(missing.clone())),
+            None => Ok(self),
+        }
+    }
+}
+
+/// JWT claims accepted by the gateway
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub exp: u64,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// Remaining claims such as `iss` and `aud`
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl Claims {
+    /// Scopes granted by the space-separated `scope` claim or the `scp` array
+    pub fn scopes(&self) -> Vec<String> {
+        match (self.extra.get("scope"), self.extra.get("scp")) {
+            (Some(serde_json::Value::String(scope)), _) => scope.split_whitespace().map(str::to_string).collect(),
+            (_, Some(serde_json::Value::Array(scp))) => {
+                scp.iter().filter_map(|scope| scope.as_str().map(str::to_string)).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Authentication errors
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AuthError {
+    #[error("Missing credentials")]
+    MissingCredentials,
+    #[error("Invalid token")]
+    InvalidToken,
+    #[error("Token expired")]
+    TokenExpired,
+    #[error("Credential type not accepted for this route")]
+    CredentialNotAccepted,
+    #[error("Missing required scope {0}")]
+    InsufficientScope(String),
+}
+
+impl AuthError {
+    /// 403 for an authenticated caller lacking a scope, 401 otherwise
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AuthError::InsufficientScope(_) => StatusCode::FORBIDDEN,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+}
+
+fn matching_route<'a>(config: &'a AuthConfig, path: &str) -> Option<&'a AuthRoute> {
+    config.routes.iter().find(|route| path_matches(&route.pattern, path))
+}
+
+/// Auth requirement of a path: the first matching route pattern, else the default
+pub fn requirement_for(config: &AuthConfig, path: &str) -> AuthRequirement {
+    matching_route(config, path).map_or(config.default_requirement, |route| route.requirement)
+}
+
+/// Scopes a path requires: those of the first matching route pattern, else none
+pub fn required_scopes<'a>(config: &'a AuthConfig, path: &str) -> &'a [String] {
+    matching_route(config, path).map_or(&[], |route| &route.scopes)
+}
+
+/// Principal for an mTLS client whose certificate names a configured identity
+pub fn authenticate_client(config: &AuthConfig, identity: &ClientIdentity) -> Option<Principal> {
+    identity
+        .common_name
+        .iter()
+        .chain(&identity.subject_alt_names)
+        .find_map(|name| config.client_certificates.get_key_value(name))
+        .map(|(name, scopes)| Principal {
+            subject: name.clone(),
+            roles: Vec::new(),
+            scopes: scopes.clone(),
+            method: AuthMethod::Mtls,
+            claims: None,
+        })
+}
+
+fn api_key_principal(config: &AuthConfig, key: &str) -> Option<Principal> {
+    config.api_keys.values().find(|api_key| api_key.key == key).map(|api_key| Principal {
+        subject: api_key.principal.clone(),
+        roles: Vec::new(),
+        scopes: api_key.scopes.clone(),
+        method: AuthMethod::ApiKey,
+        claims: None,
+    })
+}
+
+/// Authenticate a request from its headers
+pub async fn authenticate(config: &AuthConfig, jwks: Option<&JwksCache>, headers: &HeaderMap) -> Result<Principal, AuthError> {
+    if let Some(token) = headers.get("X-MCP-Token").and_then(|h| h.to_str().ok()) {
+        let service = headers
+            .get("X-MCP-Service")
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or("default");
+        return match config.mcp_auth_tokens.get(service) {
+            Some(expected) if expected == token => Ok(Principal {
+                subject: format!("mcp:{}", service),
+                roles: vec!["mcp".to_string()],
+                scopes: vec!["mcp".to_string()],
+                method: AuthMethod::McpToken,
+                claims: None,
+            }),
+            _ => Err(AuthError::InvalidToken),
+        };
+    }
+
+    if let Some(key) = headers.get("X-API-Key").and_then(|h| h.to_str().ok()) {
+        return api_key_principal(config, key).ok_or(AuthError::InvalidToken);
+    }
+
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .ok_or(AuthError::MissingCredentials)?;
+
+    if let Some(principal) = api_key_principal(config, token) {
+        return Ok(principal);
+    }
+    if let Some(account) = config.service_accounts.values().find(|a| a.token == token) {
+        return Ok(Principal {
+            subject: account.name.clone(),
+            roles: account.permissions.clone(),
+            scopes: account.permissions.clone(),
+            method: AuthMethod::ServiceAccount,
+            claims: None,
+        });
+    }
+
+    let header = jsonwebtoken::decode_header(token).map_err(|_| AuthError::InvalidToken)?;
+    let claims = match (header.alg, jwks) {
+        // Shared-secret tokens never go to the issuer's keys, and vice versa
+        (jsonwebtoken::Algorithm::HS256, _) => {
+            let secret = config.jwt_secret.as_ref().ok_or(AuthError::InvalidToken)?;
+            let validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::HS256);
+            jsonwebtoken::decode::<Claims>(
+                token,
+                &jsonwebtoken::DecodingKey::from_secret(secret.as_bytes()),
+                &validation,
+            )
+            .map_err(jwt_error)?
+            .claims
+        }
+        (_, Some(jwks)) => jwks.verify(token, &header).await?,
+        (_, None) => return Err(AuthError::InvalidToken),
+    };
+
+    Ok(Principal {
+        subject: claims.sub.clone(),
+        roles: claims.roles.clone(),
+        scopes: claims.scopes(),
+        method: AuthMethod::Jwt,
+        claims: Some(claims),
+    })
+}
+
+fn jwt_error(err: jsonwebtoken::errors::Error) -> AuthError {
+    match err.kind() {
+        jsonwebtoken::errors::ErrorKind::ExpiredSignature => AuthError::TokenExpired,
+        kind => {
+            debug!("🔐 JWT rejected: {:?}", kind);
+            AuthError::InvalidToken
+        }
+    }
+}
+
+/// Signing keys of the configured JWT issuer, fetched from its JWKS.
+///
+/// Keys are reused for `jwks_ttl_secs`. A token signed with an unknown `kid`
+/// triggers an early refetch, at most once per [`MIN_JWKS_REFRESH_INTERVAL`],
+/// so key rotations are picked up without waiting for the TTL.
+pub struct JwksCache {
+    config: JwtConfig,
+    client: reqwest::Client,
+    keys: RwLock<Option<FetchedKeys>>,
+    min_refresh_interval: Duration,
+}
+
+struct FetchedKeys {
+    by_kid: HashMap<String, jsonwebtoken::DecodingKey>,
+    fetched_at: Instant,
+}
+
+impl JwksCache {
+    pub fn new(config: JwtConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+            keys: RwLock::new(None),
+            min_refresh_interval: MIN_JWKS_REFRESH_INTERVAL,
+        }
+    }
+
+    /// Verify a token's signature, `exp`, `iss` and `aud`
+    pub async fn verify(&self, token: &str, header: &jsonwebtoken::Header) -> Result<Claims, AuthError> {
+        let kid = header.kid.as_deref().ok_or(AuthError::InvalidToken)?;
+        let key = self.key(kid).await?;
+
+        let mut validation = jsonwebtoken::Validation::new(header.alg);
+        validation.set_issuer(&[&self.config.issuer]);
+        validation.set_audience(&[&self.config.audience]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+        Ok(jsonwebtoken::decode::<Claims>(token, &key, &validation).map_err(jwt_error)?.claims)
+    }
+
+    /// Decoding key for `kid`, refetching the JWKS when stale or when the key is unknown
+    async fn key(&self, kid: &str) -> Result<jsonwebtoken::DecodingKey, AuthError> {
+        let ttl = Duration::from_secs(self.config.jwks_ttl_secs);
+        {
+            let keys = self.keys.read().await;
+            if let Some(keys) = keys.as_ref().filter(|keys| keys.fetched_at.elapsed() < ttl) {
+                if let Some(key) = keys.by_kid.get(kid) {
+                    return Ok(key.clone());
+                }
+                if keys.fetched_at.elapsed() < self.min_refresh_interval {
+                    return Err(AuthError::InvalidToken);
+                }
+            }
+        }
+
+        let mut keys = self.keys.write().await;
+        // Another request may have refreshed while this one waited for the lock
+        let fresh = keys.as_ref().is_some_and(|keys| keys.fetched_at.elapsed() < self.min_refresh_interval);
+        if !fresh {
+            match self.fetch().await {
+                Ok(by_kid) => {
+                    info!("🔑 Fetched {} signing key(s) from {}", by_kid.len(), self.config.jwks_url);
+                    *keys = Some(FetchedKeys { by_kid, fetched_at: Instant::now() });
+                }
+                // Keep verifying with the previous keys while the issuer is unreachable
+                Err(e) => warn!("🔑 JWKS refresh from {} failed: {}", self.config.jwks_url, e),
+            }
+        }
+
+        keys.as_ref()
+            .and_then(|keys| keys.by_kid.get(kid).cloned())
+            .ok_or(AuthError::InvalidToken)
+    }
+
+    async fn fetch(&self) -> Result<HashMap<String, jsonwebtoken::DecodingKey>, reqwest::Error> {
+        let set: jsonwebtoken::jwk::JwkSet = self.client
+            .get(&self.config.jwks_url)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(set.keys
+            .iter()
+            .filter_map(|jwk| {
+                let kid = jwk.common.key_id.clone()?;
+                match jsonwebtoken::DecodingKey::from_jwk(jwk) {
+                    Ok(key) => Some((kid, key)),
+                    Err(e) => {
+                        warn!("🔑 Skipping unusable JWKS key {}: {}", kid, e);
+                        None
+                    }
+                }
+            })
+            .collect())
+    }
+}
+
+fn error_response(err: &AuthError) -> Response<Body> {
+    let status = err.status();
+    let body = serde_json::json!({
+        "error": {
+            "code": status.as_u16(),
+            "message": err.to_string(),
+            "gateway": "fortress"
+        }
+    });
+
+    let challenge = match err {
+        AuthError::InsufficientScope(_) => "Bearer error=\"insufficient_scope\"",
+        _ => "Bearer",
+    };
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .header("WWW-Authenticate", challenge)
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AuthRoute, JwtConfig, ServiceAccount};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_authenticate() {
+        let mut config = AuthConfig::default();
+        config.service_accounts.insert("ci".to_string(), ServiceAccount {
+            name: "ci".to_string(),
+            token: "ci-token".to_string(),
+            permissions: vec!["deploy".to_string()],
+        });
+        config.mcp_auth_tokens.insert("search".to_string(), "mcp-secret".to_string());
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(authenticate(&config, None, &headers).await, Err(AuthError::MissingCredentials));
+
+        headers.insert(AUTHORIZATION, "Bearer ci-token".parse().unwrap());
+        assert_eq!(authenticate(&config, None, &headers).await.unwrap().subject, "ci");
+
+        headers.insert(AUTHORIZATION, "Bearer nope".parse().unwrap());
+        assert_eq!(authenticate(&config, None, &headers).await, Err(AuthError::InvalidToken));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-MCP-Token", "mcp-secret".parse().unwrap());
+        headers.insert("X-MCP-Service", "search".parse().unwrap());
+        assert_eq!(authenticate(&config, None, &headers).await.unwrap().subject, "mcp:search");
+    }
+
+    fn live(auth: AuthConfig) -> SharedConfig {
+        SharedConfig::new(crate::config::FortressConfig { auth, ..Default::default() })
+    }
+
+    async fn status(config: &AuthConfig, path: &str, token: Option<&str>) -> StatusCode {
+        use tower::ServiceExt;
+
+        let upstream = tower::service_fn(|_req: Request<Body>| async {
+            Ok::<_, std::convert::Infallible>(Response::new(Body::empty()))
+        });
+        let mut req = Request::get(path);
+        if let Some(token) = token {
+            req = req.header(AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let response = AuthMiddleware::new(live(config.clone()))
+            .layer(upstream)
+            .oneshot(req.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        response.status()
+    }
+
+    #[tokio::test]
+    async fn test_per_route_requirements() {
+        let mut config = AuthConfig {
+            jwt_secret: Some("jwt-secret".to_string()),
+            routes: vec![
+                AuthRoute::new("/mcp/discovery", AuthRequirement::Public),
+                AuthRoute::new("/api/v1/execute/*", AuthRequirement::Jwt),
+            ],
+            default_requirement: AuthRequirement::ApiKey,
+            ..Default::default()
+        };
+        config.service_accounts.insert("ci".to_string(), ServiceAccount {
+            name: "ci".to_string(),
+            token: "ci-token".to_string(),
+            permissions: vec![],
+        });
+        let claims = Claims {
+            sub: "alice".to_string(),
+            exp: u64::MAX / 2,
+            roles: vec![],
+            extra: HashMap::new(),
+        };
+        let jwt = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &claims,
+            &jsonwebtoken::EncodingKey::from_secret(b"jwt-secret"),
+        )
+        .unwrap();
+
+        // Public path: no credentials needed
+        assert_eq!(status(&config, "/mcp/discovery", None).await, StatusCode::OK);
+
+        // Protected path: only a JWT is accepted
+        assert_eq!(status(&config, "/api/v1/execute/agent", None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(&config, "/api/v1/execute/agent", Some("ci-token")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(&config, "/api/v1/execute/agent", Some(&jwt)).await, StatusCode::OK);
+
+        // Unmatched paths fall back to the default requirement
+        assert_eq!(status(&config, "/api/v1/agents", Some("ci-token")).await, StatusCode::OK);
+        assert_eq!(status(&config, "/api/v1/agents", Some(&jwt)).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(&config, "/metrics", None).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_scopes_api_keys_and_auth_context() {
+        use crate::config::ApiKey;
+        use tower::ServiceExt;
+
+        let mut config = AuthConfig {
+            jwt_secret: Some("jwt-secret".to_string()),
+            routes: vec![
+                AuthRoute::new("/public/*", AuthRequirement::Public),
+                AuthRoute::new("/api/v1/deploy/*", AuthRequirement::Authenticated).with_scopes(&["deploy"]),
+            ],
+            ..Default::default()
+        };
+        config.api_keys.insert("reporting".to_string(), ApiKey {
+            key: "rk-123".to_string(),
+            principal: "reporting-job".to_string(),
+            scopes: vec!["read".to_string()],
+        });
+        config.client_certificates.insert("billing.internal".to_string(), vec!["deploy".to_string()]);
+        let jwt = |exp: u64| {
+            let claims = serde_json::json!({"sub": "alice", "exp": exp, "scope": "read deploy"});
+            jsonwebtoken::encode(
+                &jsonwebtoken::Header::default(),
+                &claims,
+                &jsonwebtoken::EncodingKey::from_secret(b"jwt-secret"),
+            )
+            .unwrap()
+        };
+        let now = chrono::Utc::now().timestamp() as u64;
+
+        // The handler echoes the context it was given
+        let handler = tower::service_fn(|req: Request<Body>| async move {
+            let context = req.extensions().get::<AuthContext>().cloned().unwrap();
+            let body = format!("{}:{}", context.principal, context.scopes.join(","));
+            Ok::<_, std::convert::Infallible>(Response::new(Body::from(body)))
+        });
+        let service = AuthMiddleware::new(live(config)).layer(handler);
+        let call = |path: &str, header: Option<(&'static str, String)>, identity: Option<ClientIdentity>| {
+            let mut req = Request::get(path).body(Body::empty()).unwrap();
+            if let Some((name, value)) = header {
+                req.headers_mut().insert(name, value.parse().unwrap());
+            }
+            if let Some(identity) = identity {
+                req.extensions_mut().insert(identity);
+            }
+            let service = service.clone();
+            async move {
+                let response = service.oneshot(req).await.unwrap();
+                let status = response.status();
+                let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+                (status, String::from_utf8(body.to_vec()).unwrap())
+            }
+        };
+
+        assert_eq!(call("/public/docs", None, None).await, (StatusCode::OK, "anonymous:".to_string()));
+
+        let api_key = Some(("X-API-Key", "rk-123".to_string()));
+        assert_eq!(call("/api/v1/reports", api_key.clone(), None).await, (StatusCode::OK, "reporting-job:read".to_string()));
+        let bearer_key = Some(("authorization", "Bearer rk-123".to_string()));
+        assert_eq!(call("/api/v1/reports", bearer_key, None).await.0, StatusCode::OK);
+
+        let (status, body) = call("/api/v1/deploy/web", api_key, None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["error"]["message"], "Missing required scope deploy");
+
+        let fresh = Some(("authorization", format!("Bearer {}", jwt(now + 600))));
+        assert_eq!(call("/api/v1/deploy/web", fresh, None).await, (StatusCode::OK, "alice:read,deploy".to_string()));
+        let (status, body) = call("/api/v1/deploy/web", Some(("authorization", format!("Bearer {}", jwt(now - 7200)))), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["error"]["message"], "Token expired");
+
+        // Without other credentials, a known client certificate authenticates
+        let identity = ClientIdentity {
+            common_name: Some("billing".to_string()),
+            subject_alt_names: vec!["billing.internal".to_string()],
+        };
+        assert_eq!(call("/api/v1/deploy/web", None, Some(identity)).await, (StatusCode::OK, "billing.internal:deploy".to_string()));
+        assert_eq!(call("/api/v1/deploy/web", None, Some(ClientIdentity::default())).await.0, StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_default_public_paths() {
+        let config = AuthConfig::default();
+        assert_eq!(requirement_for(&config, "/healthz"), AuthRequirement::Public);
+        assert_eq!(requirement_for(&config, "/api/v1/auth/token"), AuthRequirement::Public);
+        assert_eq!(requirement_for(&config, "/api/v1/agents"), AuthRequirement::Authenticated);
+    }
+
+    /// ES256 signing key with its public JWK
+    struct IssuerKey {
+        kid: String,
+        encoding: jsonwebtoken::EncodingKey,
+        jwk: serde_json::Value,
+    }
+
+    impl IssuerKey {
+        fn generate(kid: &str) -> Self {
+            use base64::Engine;
+            use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+
+            let rng = ring::rand::SystemRandom::new();
+            let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+            let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng).unwrap();
+            // Uncompressed point: 0x04 || x || y
+            let point = pair.public_key().as_ref();
+            let b64 = |bytes: &[u8]| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+            Self {
+                kid: kid.to_string(),
+                encoding: jsonwebtoken::EncodingKey::from_ec_der(pkcs8.as_ref()),
+                jwk: serde_json::json!({
+                    "kty": "EC", "crv": "P-256", "alg": "ES256", "use": "sig",
+                    "kid": kid, "x": b64(&point[1..33]), "y": b64(&point[33..]),
+                }),
+            }
+        }
+
+        fn sign(&self, iss: &str, aud: &str, exp: u64) -> String {
+            let mut header = jsonwebtoken::Header::new(jsonwebtoken::Algorithm::ES256);
+            header.kid = Some(self.kid.clone());
+            let claims = serde_json::json!({"sub": "alice", "exp": exp, "iss": iss, "aud": aud, "roles": ["reader"]});
+            jsonwebtoken::encode(&header, &claims, &self.encoding).unwrap()
+        }
+    }
+
+    fn jwks_json(keys: &[&IssuerKey]) -> String {
+        serde_json::json!({ "keys": keys.iter().map(|key| key.jwk.clone()).collect::<Vec<_>>() }).to_string()
+    }
+
+    /// Serve the current JWKS document, counting fetches
+    async fn serve_jwks(document: Arc<std::sync::Mutex<String>>, fetches: Arc<AtomicUsize>) -> String {
+        use hyper::service::{make_service_fn, service_fn};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let make_svc = make_service_fn(move |_conn| {
+            let (document, fetches) = (document.clone(), fetches.clone());
+            async move {
+                Ok::<_, std::convert::Infallible>(service_fn(move |_req| {
+                    fetches.fetch_add(1, Ordering::SeqCst);
+                    let body = document.lock().unwrap().clone();
+                    async move { Ok::<_, std::convert::Infallible>(Response::new(Body::from(body))) }
+                }))
+            }
+        });
+        tokio::spawn(hyper::Server::from_tcp(listener).unwrap().serve(make_svc));
+        format!("http://{}/.well-known/jwks.json", addr)
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_jwks_bearer_tokens() {
+        const ISSUER: &str = "https://login.example.com";
+        let (k1, k2) = (IssuerKey::generate("k1"), IssuerKey::generate("k2"));
+        let document = Arc::new(std::sync::Mutex::new(jwks_json(&[&k1])));
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let config = AuthConfig {
+            jwt: Some(JwtConfig {
+                jwks_url: serve_jwks(document.clone(), fetches.clone()).await,
+                issuer: ISSUER.to_string(),
+                audience: "fortress".to_string(),
+                jwks_ttl_secs: 3600,
+            }),
+            ..Default::default()
+        };
+        let mut jwks = JwksCache::new(config.jwt.clone().unwrap());
+        jwks.min_refresh_interval = Duration::ZERO;
+        let exp = chrono::Utc::now().timestamp() as u64 + 600;
+
+        let principal = authenticate(&config, Some(&jwks), &bearer(&k1.sign(ISSUER, "fortress", exp))).await.unwrap();
+        assert_eq!((principal.subject.as_str(), principal.method), ("alice", AuthMethod::Jwt));
+        assert_eq!(principal.claims.unwrap().extra["iss"], ISSUER);
+
+        let rejected = [
+            (k1.sign(ISSUER, "other-service", exp), AuthError::InvalidToken),
+            (k1.sign("https://evil.example.com", "fortress", exp), AuthError::InvalidToken),
+            (k1.sign(ISSUER, "fortress", exp - 7200), AuthError::TokenExpired),
+        ];
+        for (token, err) in rejected {
+            assert_eq!(authenticate(&config, Some(&jwks), &bearer(&token)).await, Err(err));
+        }
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        // A rotated-in key is picked up on first sight; known keys stay cached
+        *document.lock().unwrap() = jwks_json(&[&k1, &k2]);
+        assert!(authenticate(&config, Some(&jwks), &bearer(&k2.sign(ISSUER, "fortress", exp))).await.is_ok());
+        assert!(authenticate(&config, Some(&jwks), &bearer(&k1.sign(ISSUER, "fortress", exp))).await.is_ok());
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+
+        // Through the middleware: claims reach the handler, and unknown kids refetch at most once per interval
+        let handler = tower::service_fn(|req: Request<Body>| async move {
+            let subject = req.extensions().get::<Claims>().map(|claims| claims.sub.clone()).unwrap_or_default();
+            Ok::<_, std::convert::Infallible>(Response::new(Body::from(subject)))
+        });
+        let service = AuthMiddleware::new(live(config)).layer(handler);
+        let call = |token: String| {
+            use tower::ServiceExt;
+            let req = Request::get("/api/v1/agents").header(AUTHORIZATION, format!("Bearer {}", token));
+            service.clone().oneshot(req.body(Body::empty()).unwrap())
+        };
+
+        let response = call(k1.sign(ISSUER, "fortress", exp)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "alice");
+
+        let k3 = IssuerKey::generate("k3");
+        for _ in 0..3 {
+            let response = call(k3.sign(ISSUER, "fortress", exp)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+    }
+}
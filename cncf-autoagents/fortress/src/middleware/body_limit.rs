@@ -0,0 +1,248 @@
+//! Body size limit middleware
+//!
+//! Rejects oversized request bodies with 413 and cuts off oversized response
+//! bodies. Bytes are counted as the body streams, so chunked bodies without a
+//! `Content-Length` are caught as soon as they cross the limit instead of
+//! after being buffered. Limits are read from the live configuration.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use hyper::{
+    body::HttpBody,
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+    http::{HeaderMap, StatusCode},
+    Body, Request, Response,
+};
+use tower::{Layer, Service};
+use tracing::warn;
+
+use crate::config::SharedConfig;
+
+/// Body size limit middleware
+#[derive(Clone)]
+pub struct BodyLimitMiddleware {
+    config: SharedConfig,
+}
+
+impl BodyLimitMiddleware {
+    /// Create a new body limit middleware
+    pub fn new(config: SharedConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl<S> Layer<S> for BodyLimitMiddleware {
+    type Service = BodyLimitMiddlewareService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BodyLimitMiddlewareService {
+            inner,
+            config: self.config.clone(),
+        }
+    }
+}
+
+/// Service wrapper for body limit middleware
+#[derive(Clone)]
+pub struct BodyLimitMiddlewareService<S> {
+    inner: S,
+    config: SharedConfig,
+}
+
+impl<S> Service<Request<Body>> for BodyLimitMiddlewareService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = std::pin::Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let limits = self.config.current().body_limits.clone();
+        let exceeded = Arc::new(AtomicBool::new(false));
+        let req = match declared_len(req.headers(), req.body()) {
+            Some(len) if len > limits.max_request_body_bytes => {
+                warn!("📦 Request body for {} exceeds {} bytes", req.uri().path(), limits.max_request_body_bytes);
+                return Box::pin(async move { Ok(payload_too_large(limits.max_request_body_bytes)) });
+            }
+            // hyper already holds a body with a declared length to that length
+            Some(_) => req,
+            None => {
+                let (parts, body) = req.into_parts();
+                Request::from_parts(parts, limit_body(body, limits.max_request_body_bytes, exceeded.clone()))
+            }
+        };
+
+        Box::pin(async move {
+            let path = req.uri().path().to_string();
+            let response = inner.call(req).await?;
+            if exceeded.load(Ordering::SeqCst) {
+                warn!("📦 Request body for {} exceeded {} bytes while streaming", path, limits.max_request_body_bytes);
+                return Ok(payload_too_large(limits.max_request_body_bytes));
+            }
+
+            let max = limits.max_response_body_bytes;
+            match declared_len(response.headers(), response.body()) {
+                Some(len) if len > max => {
+                    warn!("📦 Response body for {} exceeds {} bytes", path, max);
+                    Ok(error_response(StatusCode::BAD_GATEWAY, "Upstream response too large"))
+                }
+                Some(_) => Ok(response),
+                None => {
+                    // Headers are already on their way once a streamed response crosses the limit,
+                    // so the body is cut off and the connection reports the error
+                    let (parts, body) = response.into_parts();
+                    Ok(Response::from_parts(parts, limit_body(body, max, Arc::new(AtomicBool::new(false)))))
+                }
+            }
+        })
+    }
+}
+
+/// Error raised by a body that streamed past its limit
+#[derive(Debug, thiserror::Error)]
+#[error("Body exceeds {0} bytes")]
+pub struct BodyLimitExceeded(pub u64);
+
+/// Wrap `body` so it fails, and sets `exceeded`, once more than `limit` bytes have streamed through
+fn limit_body(body: Body, limit: u64, exceeded: Arc<AtomicBool>) -> Body {
+    let chunks = futures::stream::try_unfold((body, 0u64), move |(mut body, seen)| {
+        let exceeded = exceeded.clone();
+        async move {
+            let chunk = match body.data().await {
+                Some(chunk) => chunk?,
+                None => return Ok(None),
+            };
+            let seen = seen + chunk.len() as u64;
+            if seen > limit {
+                exceeded.store(true, Ordering::SeqCst);
+                return Err(Box::new(BodyLimitExceeded(limit)) as Box<dyn std::error::Error + Send + Sync>);
+            }
+            Ok(Some((chunk, (body, seen))))
+        }
+    });
+    Body::wrap_stream(chunks)
+}
+
+/// Body size known up front, from `Content-Length` or an exact size hint
+fn declared_len(headers: &HeaderMap, body: &Body) -> Option<u64> {
+    headers
+        .get(CONTENT_LENGTH)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.parse().ok())
+        .or_else(|| body.size_hint().exact())
+}
+
+fn payload_too_large(limit: u64) -> Response<Body> {
+    error_response(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        &format!("Request body exceeds {} bytes", limit),
+    )
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Body> {
+    let body = serde_json::json!({
+        "error": {
+            "code": status.as_u16(),
+            "message": message,
+            "gateway": "fortress"
+        }
+    });
+
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BodyLimitConfig, FortressConfig};
+    use std::convert::Infallible;
+    use tower::ServiceExt;
+
+    fn limited(max_request_body_bytes: u64, max_response_body_bytes: u64) -> SharedConfig {
+        SharedConfig::new(FortressConfig {
+            body_limits: BodyLimitConfig {
+                max_request_body_bytes,
+                max_response_body_bytes,
+            },
+            ..Default::default()
+        })
+    }
+
+    /// Buffers the request like the gateway does and echoes it back
+    async fn echo(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        Ok(match hyper::body::to_bytes(req.into_body()).await {
+            Ok(body) => Response::new(Body::from(body)),
+            Err(_) => Response::builder().status(StatusCode::BAD_GATEWAY).body(Body::empty()).unwrap(),
+        })
+    }
+
+    /// Body sent with chunked transfer encoding: no `Content-Length`
+    fn chunked(chunks: &[&'static str]) -> Body {
+        let chunks: Vec<Result<&'static str, Infallible>> = chunks.iter().map(|chunk| Ok(*chunk)).collect();
+        Body::wrap_stream(futures::stream::iter(chunks))
+    }
+
+    async fn send(config: &SharedConfig, req: Request<Body>) -> Response<Body> {
+        BodyLimitMiddleware::new(config.clone())
+            .layer(tower::service_fn(echo))
+            .oneshot(req)
+            .await
+            .unwrap()
+    }
+
+    async fn stream_back(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        let _ = hyper::body::to_bytes(req.into_body()).await;
+        Ok(Response::new(chunked(&["12", "345"])))
+    }
+
+    #[tokio::test]
+    async fn test_request_body_limit() {
+        let config = limited(8, 1024);
+
+        let declared = Request::post("/api").header(CONTENT_LENGTH, "9").body(Body::from("123456789")).unwrap();
+        assert_eq!(send(&config, declared).await.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let streamed = Request::post("/api").body(chunked(&["1234", "5678", "9"])).unwrap();
+        assert_eq!(send(&config, streamed).await.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let within = Request::post("/api").body(chunked(&["1234", "5678"])).unwrap();
+        let response = send(&config, within).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(hyper::body::to_bytes(response.into_body()).await.unwrap(), "12345678");
+    }
+
+    #[tokio::test]
+    async fn test_response_body_limit() {
+        let config = limited(1024, 4);
+
+        let declared = Request::post("/api").body(Body::from("12345")).unwrap();
+        assert_eq!(send(&config, declared).await.status(), StatusCode::BAD_GATEWAY);
+
+        let response = BodyLimitMiddleware::new(config)
+            .layer(tower::service_fn(stream_back))
+            .oneshot(Request::get("/api").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(hyper::body::to_bytes(response.into_body()).await.is_err());
+    }
+}
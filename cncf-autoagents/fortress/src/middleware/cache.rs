@@ -0,0 +1,1005 @@
+//! Response cache middleware
+//!
+//! Caches `GET` and `HEAD` responses with status 200, 301 or 404, keyed by
+//! method, path and query, the caller's principal, and the request headers
+//! the response names in `Vary`, so one caller's response is never served to
+//! another. The principal is the [`AuthContext`] authentication resolved,
+//! whatever the credential, falling back to the `Authorization` header when
+//! no auth layer ran. Principal and `Vary` values appear in keys only as
+//! digests, so credentials never show up in Redis key names. Upstream `Cache-Control` is honoured: `no-store`,
+//! `no-cache` and `private` responses are not stored, and `s-maxage` or
+//! `max-age` replaces `ttl_seconds`. Entries live in Redis when
+//! `cache.redis_url` is set, shared by every gateway instance, and otherwise
+//! in memory, bounded by `max_size_mb` and evicting the least recently used
+//! first. A Redis that is unreachable is passed by rather than failing the
+//! request. [`CacheMiddleware::purge`] drops entries under a path prefix.
+//! Settings are read from the live configuration per request, and a
+//! configuration change empties the store, so a reloaded route is never
+//! answered from responses of its previous upstream.
+//!
+//! Expired entries shield a cold or failing upstream. Within
+//! `stale_while_revalidate_seconds` of expiry an entry is served at once with
+//! `X-Cache: STALE` while a single background request refreshes it. Within
+//! `stale_if_error_seconds` of expiry an entry replaces an upstream error or
+//! 5xx, marked `X-Cache: STALE-IF-ERROR`. Routes may override both windows.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use base64::Engine;
+use hyper::{
+    body::Bytes,
+    header::{HeaderName, HeaderValue, AUTHORIZATION, CACHE_CONTROL, VARY},
+    http::{HeaderMap, Method, StatusCode},
+    Body, Request, Response,
+};
+use redis::{RedisError, RedisResult};
+use serde::{Deserialize, Serialize};
+use tower::{Layer, Service};
+use tracing::{debug, info, warn};
+
+use crate::{
+    config::{FortressConfig, SharedConfig},
+    metrics::MetricsCollector,
+    middleware::{auth::AuthContext, redis_connector::RedisConnector},
+    routing::resolve_route,
+    tls::ClientIdentity,
+};
+
+/// Statuses whose responses may be stored
+const CACHEABLE_STATUSES: [StatusCode; 3] = [StatusCode::OK, StatusCode::MOVED_PERMANENTLY, StatusCode::NOT_FOUND];
+
+/// Prefix of every key the cache writes to Redis
+const REDIS_PREFIX: &str = "fortress:cache:";
+
+/// Response cache middleware
+#[derive(Clone)]
+pub struct CacheMiddleware {
+    config: SharedConfig,
+    cache: ResponseCache,
+    metrics: Option<MetricsCollector>,
+}
+
+impl CacheMiddleware {
+    /// Create a new cache middleware reading `cache` from the live configuration
+    pub fn new(config: SharedConfig) -> Self {
+        Self {
+            config,
+            cache: ResponseCache::default(),
+            metrics: None,
+        }
+    }
+
+    /// Count fresh, stale and stale-on-error hits and misses in `metrics`
+    pub fn with_metrics(mut self, metrics: MetricsCollector) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Drop every cached response whose path starts with `path_prefix`,
+    /// returning how many were dropped
+    pub async fn purge(&self, path_prefix: &str) -> Result<usize, RedisError> {
+        let mut purged = self.cache.memory.lock().unwrap().purge(path_prefix);
+        if let Some(url) = &self.config.current().cache.redis_url {
+            purged += self.cache.redis.purge(url, path_prefix).await?;
+        }
+        info!("🧹 Purged {} cached response(s) under {}", purged, path_prefix);
+        Ok(purged)
+    }
+}
+
+impl<S> Layer<S> for CacheMiddleware {
+    type Service = CacheMiddlewareService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CacheMiddlewareService {
+            inner,
+            config: self.config.clone(),
+            cache: self.cache.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+/// Service wrapper for cache middleware
+#[derive(Clone)]
+pub struct CacheMiddlewareService<S> {
+    inner: S,
+    config: SharedConfig,
+    cache: ResponseCache,
+    metrics: Option<MetricsCollector>,
+}
+
+/// How long past expiry an entry may still be served
+#[derive(Debug, Clone, Copy)]
+struct StaleWindows {
+    while_revalidate: Duration,
+    if_error: Duration,
+}
+
+impl StaleWindows {
+    /// Windows of the route `req` matches, falling back to the cache defaults
+    fn for_request(config: &FortressConfig, req: &mut Request<Body>) -> Self {
+        let route = resolve_route(&config.routing, req)
+            .and_then(|route| route.cache.clone())
+            .unwrap_or_default();
+        Self {
+            while_revalidate: Duration::from_secs(
+                route.stale_while_revalidate_seconds.unwrap_or(config.cache.stale_while_revalidate_seconds),
+            ),
+            if_error: Duration::from_secs(route.stale_if_error_seconds.unwrap_or(config.cache.stale_if_error_seconds)),
+        }
+    }
+
+    fn retention(&self) -> Duration {
+        self.while_revalidate.max(self.if_error)
+    }
+
+    /// What `entry` may be used for at `now`
+    fn classify(&self, entry: CachedResponse, now: Instant) -> Lookup {
+        if entry.expires_at > now {
+            return Lookup::Fresh(entry);
+        }
+        let age = now.duration_since(entry.expires_at);
+        if age < self.while_revalidate {
+            Lookup::Stale(entry)
+        } else if age < self.if_error {
+            Lookup::IfError(entry)
+        } else {
+            Lookup::Miss
+        }
+    }
+}
+
+/// What a stored entry may be used for
+enum Lookup {
+    Fresh(CachedResponse),
+    /// Past its TTL but within the stale-while-revalidate window
+    Stale(CachedResponse),
+    /// Only usable if the upstream fails
+    IfError(CachedResponse),
+    Miss,
+}
+
+impl<S> Service<Request<Body>> for CacheMiddlewareService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = std::pin::Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let config = self.config.current();
+        if !config.cache.enabled || !matches!(*req.method(), Method::GET | Method::HEAD) {
+            return Box::pin(inner.call(req));
+        }
+
+        let cache = self.cache.clone();
+        let metrics = self.metrics.clone();
+        Box::pin(async move {
+            let base = base_key(&req);
+            let request_headers = req.headers().clone();
+            let windows = StaleWindows::for_request(&config, &mut req);
+            let (key, lookup) = cache.lookup(&config, &base, &request_headers, windows).await;
+
+            let stale = match lookup {
+                Lookup::Fresh(cached) => {
+                    debug!("📦 Cache hit for {}", key);
+                    if let Some(metrics) = &metrics {
+                        metrics.record_cache_hit();
+                    }
+                    return Ok(cached.into_response("HIT"));
+                }
+                Lookup::Stale(cached) => {
+                    debug!("📦 Serving stale {} while revalidating", key);
+                    if let Some(metrics) = &metrics {
+                        metrics.record_cache_stale();
+                    }
+                    // Only the first stale hit refreshes the entry; the rest are served meanwhile
+                    if cache.begin_revalidation(&key) {
+                        let revalidation = revalidation_request(&req);
+                        tokio::spawn(async move {
+                            let response = inner.call(revalidation).await.ok();
+                            if let Some(response) = response {
+                                cache.store(&config, &base, &request_headers, windows, response).await;
+                            }
+                            cache.end_revalidation(&key);
+                        });
+                    }
+                    return Ok(cached.into_response("STALE"));
+                }
+                Lookup::IfError(cached) => Some(cached),
+                Lookup::Miss => None,
+            };
+
+            if let Some(metrics) = &metrics {
+                metrics.record_cache_miss();
+            }
+            let response = match (inner.call(req).await, stale) {
+                (Ok(response), Some(stale)) if response.status().is_server_error() => {
+                    warn!("⚠️ Upstream answered {} for {}, serving the stale entry", response.status(), key);
+                    return Ok(serve_stale_on_error(stale, metrics.as_ref()));
+                }
+                (Err(_), Some(stale)) => {
+                    warn!("⚠️ Upstream failed for {}, serving the stale entry", key);
+                    return Ok(serve_stale_on_error(stale, metrics.as_ref()));
+                }
+                (result, _) => result?,
+            };
+            Ok(cache.store(&config, &base, &request_headers, windows, response).await)
+        })
+    }
+}
+
+fn serve_stale_on_error(stale: CachedResponse, metrics: Option<&MetricsCollector>) -> Response<Body> {
+    if let Some(metrics) = metrics {
+        metrics.record_cache_stale_on_error();
+    }
+    stale.into_response("STALE-IF-ERROR")
+}
+
+/// Copy of `req` for a background refresh; it has no body, being a `GET` or `HEAD`
+fn revalidation_request(req: &Request<Body>) -> Request<Body> {
+    let mut revalidation = Request::new(Body::empty());
+    *revalidation.method_mut() = req.method().clone();
+    *revalidation.uri_mut() = req.uri().clone();
+    *revalidation.headers_mut() = req.headers().clone();
+    if let Some(addr) = req.extensions().get::<SocketAddr>() {
+        revalidation.extensions_mut().insert(*addr);
+    }
+    if let Some(identity) = req.extensions().get::<ClientIdentity>() {
+        revalidation.extensions_mut().insert(identity.clone());
+    }
+    revalidation
+}
+
+/// Key of every variant of a request: method, path and query, and the caller
+fn base_key(req: &Request<Body>) -> String {
+    let target = req.uri().path_and_query().map(|pq| pq.as_str()).unwrap_or("/");
+    let caller = match req.extensions().get::<AuthContext>() {
+        Some(context) => digest(&format!("principal:{}", context.principal)),
+        None => req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .map(|auth| digest(&format!("authorization:{}", auth)))
+            .unwrap_or_default(),
+    };
+    format!("{} {}|{}", req.method(), target, caller)
+}
+
+/// Hex SHA-256 prefix of `value`, for key parts that may carry credentials
+fn digest(value: &str) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, value.as_bytes());
+    digest.as_ref()[..16].iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Key of the variant of `base` selected by the request headers named in `vary`
+fn variant_key(base: &str, vary: &[String], request_headers: &HeaderMap) -> String {
+    if vary.is_empty() {
+        return base.to_string();
+    }
+    let selected: Vec<String> = vary
+        .iter()
+        .map(|name| {
+            let values: Vec<&str> = request_headers.get_all(name.as_str()).iter().filter_map(|v| v.to_str().ok()).collect();
+            format!("{}={}", name, values.join(","))
+        })
+        .collect();
+    format!("{}|{}", base, digest(&selected.join("&")))
+}
+
+/// The path and query a key was built from
+fn key_path(key: &str) -> &str {
+    key.split_once(' ').map_or("", |(_, rest)| rest)
+}
+
+/// How long a response stays fresh, or `None` if its `Cache-Control` forbids storing it.
+///
+/// `s-maxage` wins over `max-age`, and either over the configured TTL.
+fn freshness_lifetime(headers: &HeaderMap, default_ttl_seconds: u64) -> Option<Duration> {
+    let (mut max_age, mut s_maxage) = (None, None);
+    let directives = headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','));
+    for directive in directives {
+        let (name, value) = match directive.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+            None => (directive.trim(), None),
+        };
+        match name.to_ascii_lowercase().as_str() {
+            // `no-cache` responses must be revalidated with their origin before every use
+            "no-store" | "no-cache" | "private" => return None,
+            "max-age" => max_age = value.and_then(|v| v.parse().ok()),
+            "s-maxage" => s_maxage = value.and_then(|v| v.parse().ok()),
+            _ => {}
+        }
+    }
+    let ttl = Duration::from_secs(s_maxage.or(max_age).unwrap_or(default_ttl_seconds));
+    (!ttl.is_zero()).then_some(ttl)
+}
+
+/// Lower-cased, sorted request headers a response varies on; `None` for `Vary: *`
+fn vary_headers(headers: &HeaderMap) -> Option<Vec<String>> {
+    let mut names = Vec::new();
+    for name in headers.get_all(VARY).iter().filter_map(|v| v.to_str().ok()).flat_map(|v| v.split(',')) {
+        let name = name.trim().to_ascii_lowercase();
+        match name.as_str() {
+            "*" => return None,
+            "" => {}
+            _ => names.push(name),
+        }
+    }
+    names.sort();
+    names.dedup();
+    Some(names)
+}
+
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    expires_at: Instant,
+    /// When the entry is past every stale window and can be dropped
+    evict_at: Instant,
+}
+
+impl CachedResponse {
+    fn into_response(self, cache_status: &'static str) -> Response<Body> {
+        let mut response = Response::new(Body::from(self.body));
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers;
+        with_cache_status(response, cache_status)
+    }
+}
+
+fn with_cache_status(mut response: Response<Body>, cache_status: &'static str) -> Response<Body> {
+    response.headers_mut().insert("X-Cache", HeaderValue::from_static(cache_status));
+    response
+}
+
+/// Cached responses, in Redis when `cache.redis_url` is set and in memory otherwise
+#[derive(Clone, Default)]
+struct ResponseCache {
+    memory: Arc<Mutex<CacheStore>>,
+    redis: RedisStore,
+}
+
+impl ResponseCache {
+    /// Find the variant of `base` selected by `request_headers`, returning its key
+    async fn lookup(
+        &self,
+        config: &Arc<FortressConfig>,
+        base: &str,
+        request_headers: &HeaderMap,
+        windows: StaleWindows,
+    ) -> (String, Lookup) {
+        if let Some(url) = &config.cache.redis_url {
+            return match self.redis.get(url, &redis_namespace(config), base, request_headers).await {
+                Ok((key, Some(entry))) => (key, windows.classify(entry, Instant::now())),
+                Ok((key, None)) => (key, Lookup::Miss),
+                Err(e) => {
+                    warn!("⚠️ Redis cache lookup failed: {}", e);
+                    (base.to_string(), Lookup::Miss)
+                }
+            };
+        }
+
+        let mut store = self.memory.lock().unwrap();
+        store.sync_config(config);
+        let vary = store.vary.get(base).cloned().unwrap_or_default();
+        let key = variant_key(base, &vary, request_headers);
+        let lookup = store.get(&key, windows, Instant::now());
+        (key, lookup)
+    }
+
+    /// Mark `key` as being refreshed; `false` if a refresh is already in flight
+    fn begin_revalidation(&self, key: &str) -> bool {
+        self.memory.lock().unwrap().revalidating.insert(key.to_string())
+    }
+
+    fn end_revalidation(&self, key: &str) {
+        self.memory.lock().unwrap().revalidating.remove(key);
+    }
+
+    /// Store `response` if it is cacheable, returning it to the caller marked as a miss
+    async fn store(
+        &self,
+        config: &Arc<FortressConfig>,
+        base: &str,
+        request_headers: &HeaderMap,
+        windows: StaleWindows,
+        response: Response<Body>,
+    ) -> Response<Body> {
+        if !CACHEABLE_STATUSES.contains(&response.status()) {
+            return with_cache_status(response, "MISS");
+        }
+        let (Some(ttl), Some(vary)) = (
+            freshness_lifetime(response.headers(), config.cache.ttl_seconds),
+            vary_headers(response.headers()),
+        ) else {
+            return with_cache_status(response, "MISS");
+        };
+
+        let (parts, body) = response.into_parts();
+        let body = match hyper::body::to_bytes(body).await {
+            Ok(body) => body,
+            Err(_) => {
+                return Response::builder()
+                    .status(StatusCode::BAD_GATEWAY)
+                    .body(Body::empty())
+                    .unwrap()
+            }
+        };
+
+        let expires_at = Instant::now() + ttl;
+        let entry = CachedResponse {
+            status: parts.status,
+            headers: parts.headers,
+            body,
+            expires_at,
+            evict_at: expires_at + windows.retention(),
+        };
+        let key = variant_key(base, &vary, request_headers);
+        match &config.cache.redis_url {
+            Some(url) => {
+                if let Err(e) = self.redis.put(url, &redis_namespace(config), base, &vary, &key, &entry).await {
+                    warn!("⚠️ Failed to store {} in the Redis cache: {}", key, e);
+                }
+            }
+            None => {
+                let mut store = self.memory.lock().unwrap();
+                // A response from before a reload is served but not kept
+                if store.is_current(config) {
+                    store.vary.insert(base.to_string(), vary);
+                    store.insert(key, entry.clone(), config.cache.max_size_mb * 1024 * 1024);
+                }
+            }
+        }
+        entry.into_response("MISS")
+    }
+}
+
+/// An in-memory entry and when it was last used
+#[derive(Debug)]
+struct MemoryEntry {
+    response: CachedResponse,
+    last_used: u64,
+}
+
+/// Size-bounded in-memory response store, evicting the least recently used entries first
+#[derive(Debug, Default)]
+struct CacheStore {
+    entries: HashMap<String, MemoryEntry>,
+    /// `Vary` headers of the latest response stored under each base key
+    vary: HashMap<String, Vec<String>>,
+    /// Keys by last use, least recent first
+    recency: BTreeMap<u64, String>,
+    clock: u64,
+    size_bytes: usize,
+    /// Configuration the entries were stored under
+    config: Option<Arc<FortressConfig>>,
+    /// Keys with a background revalidation in flight
+    revalidating: HashSet<String>,
+}
+
+impl CacheStore {
+    fn is_current(&self, config: &Arc<FortressConfig>) -> bool {
+        self.config.as_ref().is_some_and(|current| Arc::ptr_eq(current, config))
+    }
+
+    /// Drop every entry if `config` is not the one they were stored under
+    fn sync_config(&mut self, config: &Arc<FortressConfig>) {
+        if !self.is_current(config) {
+            if !self.entries.is_empty() {
+                debug!("📦 Configuration changed, dropping {} cached response(s)", self.entries.len());
+            }
+            self.entries.clear();
+            self.vary.clear();
+            self.recency.clear();
+            self.size_bytes = 0;
+            self.revalidating.clear();
+            self.config = Some(config.clone());
+        }
+    }
+
+    fn get(&mut self, key: &str, windows: StaleWindows, now: Instant) -> Lookup {
+        let Some(entry) = self.entries.get(key) else {
+            return Lookup::Miss;
+        };
+        let evictable = entry.response.evict_at <= now;
+        let lookup = windows.classify(entry.response.clone(), now);
+        match lookup {
+            Lookup::Miss if evictable => self.remove(key),
+            Lookup::Miss => {}
+            _ => self.touch(key),
+        }
+        lookup
+    }
+
+    fn touch(&mut self, key: &str) {
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = self.clock;
+            self.recency.insert(self.clock, key.to_string());
+        }
+    }
+
+    /// Insert an entry, dropping evictable and then least recently used entries
+    /// to make room; skipped if it alone would exceed `max_bytes`
+    fn insert(&mut self, key: String, response: CachedResponse, max_bytes: usize) {
+        let now = Instant::now();
+        let expired: Vec<String> = self.entries
+            .iter()
+            .filter(|(_, e)| e.response.evict_at <= now)
+            .map(|(k, _)| k.clone())
+            .collect();
+        for key in expired {
+            self.remove(&key);
+        }
+        self.remove(&key);
+
+        let size = response.body.len();
+        if size > max_bytes {
+            return;
+        }
+        while self.size_bytes + size > max_bytes {
+            let Some((_, oldest)) = self.recency.pop_first() else { break };
+            debug!("📦 Evicting least recently used {}", oldest);
+            self.remove(&oldest);
+        }
+
+        self.clock += 1;
+        self.size_bytes += size;
+        self.recency.insert(self.clock, key.clone());
+        self.entries.insert(key, MemoryEntry { response, last_used: self.clock });
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.size_bytes -= entry.response.body.len();
+            self.recency.remove(&entry.last_used);
+        }
+    }
+
+    /// Drop entries whose path starts with `path_prefix`, returning how many
+    fn purge(&mut self, path_prefix: &str) -> usize {
+        let keys: Vec<String> = self.entries.keys().filter(|key| key_path(key).starts_with(path_prefix)).cloned().collect();
+        for key in &keys {
+            self.remove(key);
+        }
+        self.vary.retain(|base, _| !key_path(base).starts_with(path_prefix));
+        keys.len()
+    }
+}
+
+/// Redis key prefix for responses stored under `config`'s routes, so a
+/// reloaded route is never answered from its previous upstream's responses
+fn redis_namespace(config: &FortressConfig) -> String {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    serde_json::to_string(&config.routing).unwrap_or_default().hash(&mut hasher);
+    format!("{}{:016x}:", REDIS_PREFIX, hasher.finish())
+}
+
+/// Kind (`entry` or `vary`) and path of a key written to Redis
+fn parse_redis_key(redis_key: &str) -> Option<(&str, &str)> {
+    let (_namespace, rest) = redis_key.strip_prefix(REDIS_PREFIX)?.split_once(':')?;
+    let (kind, key) = rest.split_once(':')?;
+    Some((kind, key_path(key)))
+}
+
+/// A cached response as stored in Redis
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredResponse {
+    status: u16,
+    headers: Vec<(String, Vec<u8>)>,
+    /// Base64 body
+    body: String,
+    expires_at_ms: u64,
+    evict_at_ms: u64,
+}
+
+impl StoredResponse {
+    fn new(entry: &CachedResponse) -> Self {
+        Self {
+            status: entry.status.as_u16(),
+            headers: entry
+                .headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.as_bytes().to_vec()))
+                .collect(),
+            body: base64::engine::general_purpose::STANDARD.encode(&entry.body),
+            expires_at_ms: unix_millis(entry.expires_at),
+            evict_at_ms: unix_millis(entry.evict_at),
+        }
+    }
+
+    fn into_cached(self) -> Option<CachedResponse> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in self.headers {
+            headers.append(HeaderName::try_from(name).ok()?, HeaderValue::from_bytes(&value).ok()?);
+        }
+        Some(CachedResponse {
+            status: StatusCode::from_u16(self.status).ok()?,
+            headers,
+            body: base64::engine::general_purpose::STANDARD.decode(self.body).ok()?.into(),
+            expires_at: instant_from_unix_millis(self.expires_at_ms),
+            evict_at: instant_from_unix_millis(self.evict_at_ms),
+        })
+    }
+}
+
+fn now_unix_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+/// Wall-clock time of `at`, so other gateway instances can read it
+fn unix_millis(at: Instant) -> u64 {
+    let now = Instant::now();
+    let now_ms = now_unix_millis();
+    if at >= now {
+        now_ms + (at - now).as_millis() as u64
+    } else {
+        now_ms.saturating_sub((now - at).as_millis() as u64)
+    }
+}
+
+fn instant_from_unix_millis(ms: u64) -> Instant {
+    let now = Instant::now();
+    let now_ms = now_unix_millis();
+    if ms >= now_ms {
+        now + Duration::from_millis(ms - now_ms)
+    } else {
+        now.checked_sub(Duration::from_millis(now_ms - ms)).unwrap_or(now)
+    }
+}
+
+/// Cache entries in `cache.redis_url`
+#[derive(Clone, Default)]
+struct RedisStore {
+    connector: RedisConnector,
+}
+
+impl RedisStore {
+    async fn get(
+        &self,
+        url: &str,
+        namespace: &str,
+        base: &str,
+        request_headers: &HeaderMap,
+    ) -> RedisResult<(String, Option<CachedResponse>)> {
+        let mut connection = self.connector.connection(url).await?;
+        let vary: Option<String> = redis::cmd("GET")
+            .arg(format!("{}vary:{}", namespace, base))
+            .query_async(&mut connection)
+            .await?;
+        let vary: Vec<String> = vary
+            .iter()
+            .flat_map(|names| names.split(','))
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect();
+        let key = variant_key(base, &vary, request_headers);
+
+        let stored: Option<Vec<u8>> = redis::cmd("GET")
+            .arg(format!("{}entry:{}", namespace, key))
+            .query_async(&mut connection)
+            .await?;
+        let entry = stored
+            .and_then(|bytes| serde_json::from_slice::<StoredResponse>(&bytes).ok())
+            .and_then(StoredResponse::into_cached);
+        Ok((key, entry))
+    }
+
+    async fn put(
+        &self,
+        url: &str,
+        namespace: &str,
+        base: &str,
+        vary: &[String],
+        key: &str,
+        entry: &CachedResponse,
+    ) -> RedisResult<()> {
+        let mut connection = self.connector.connection(url).await?;
+        let ttl_ms = entry.evict_at.saturating_duration_since(Instant::now()).as_millis().max(1) as u64;
+        let stored = serde_json::to_vec(&StoredResponse::new(entry)).unwrap_or_default();
+        redis::pipe()
+            .cmd("SET").arg(format!("{}vary:{}", namespace, base)).arg(vary.join(",")).arg("PX").arg(ttl_ms).ignore()
+            .cmd("SET").arg(format!("{}entry:{}", namespace, key)).arg(stored).arg("PX").arg(ttl_ms).ignore()
+            .query_async(&mut connection)
+            .await
+    }
+
+    /// Delete responses under `path_prefix` from every namespace, returning how many
+    async fn purge(&self, url: &str, path_prefix: &str) -> RedisResult<usize> {
+        let mut connection = self.connector.connection(url).await?;
+        let mut cursor = 0u64;
+        let mut purged = 0;
+        loop {
+            let (next, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(format!("{}*", REDIS_PREFIX))
+                .arg("COUNT")
+                .arg(500)
+                .query_async(&mut connection)
+                .await?;
+            let matching: Vec<String> = keys
+                .into_iter()
+                .filter(|key| parse_redis_key(key).is_some_and(|(_, path)| path.starts_with(path_prefix)))
+                .collect();
+            if !matching.is_empty() {
+                purged += matching.iter().filter(|key| parse_redis_key(key).is_some_and(|(kind, _)| kind == "entry")).count();
+                redis::cmd("DEL").arg(&matching).query_async::<_, ()>(&mut connection).await?;
+            }
+            if next == 0 {
+                return Ok(purged);
+            }
+            cursor = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    use crate::config::{ApiKey, Route, RouteCacheConfig};
+    use crate::middleware::AuthMiddleware;
+
+    /// Move every stored entry `by` further past its expiry
+    fn age_entries(cache: &CacheMiddleware, by: Duration) {
+        for entry in cache.cache.memory.lock().unwrap().entries.values_mut() {
+            entry.response.expires_at -= by;
+            entry.response.evict_at -= by;
+        }
+    }
+
+    async fn get<S>(service: &CacheMiddlewareService<S>) -> (StatusCode, String, String)
+    where
+        S: Service<Request<Body>, Response = Response<Body>, Error = Infallible> + Clone + Send + 'static,
+        S::Future: Send + 'static,
+    {
+        let response = service.clone().oneshot(Request::get("/forge/modules").body(Body::empty()).unwrap()).await.unwrap();
+        let status = response.status();
+        let cache = response.headers().get("X-Cache").map(|v| v.to_str().unwrap().to_string()).unwrap_or_default();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, cache, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_stale_entries_shield_slow_and_failing_upstreams() {
+        let mut config = FortressConfig::default();
+        config.cache.ttl_seconds = 60;
+        config.routing.routes = vec![Route {
+            name: None,
+            path: "/forge/*".to_string(),
+            upstream: "http://forge".to_string(),
+            methods: vec![],
+            headers: HashMap::new(),
+            timeout_ms: None,
+            targets: vec![],
+            priority: 0,
+            match_headers: HashMap::new(),
+            retry: None,
+            rate_limit: None,
+            cache: Some(RouteCacheConfig {
+                stale_while_revalidate_seconds: Some(30),
+                stale_if_error_seconds: Some(300),
+            }),
+        }];
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let (slow, failing) = (Arc::new(AtomicBool::new(false)), Arc::new(AtomicBool::new(false)));
+        let upstream = {
+            let (calls, slow, failing) = (calls.clone(), slow.clone(), failing.clone());
+            tower::service_fn(move |_req: Request<Body>| {
+                let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                let (slow, failing) = (slow.load(Ordering::SeqCst), failing.load(Ordering::SeqCst));
+                async move {
+                    if slow {
+                        tokio::time::sleep(Duration::from_millis(300)).await;
+                    }
+                    let status = if failing { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::OK };
+                    Ok::<_, Infallible>(Response::builder().status(status).body(Body::from(format!("v{}", call))).unwrap())
+                }
+            })
+        };
+        let metrics = MetricsCollector::new();
+        let cache = CacheMiddleware::new(SharedConfig::new(config)).with_metrics(metrics.clone());
+        let service = cache.layer(upstream);
+
+        assert_eq!(get(&service).await, (StatusCode::OK, "MISS".to_string(), "v1".to_string()));
+        assert_eq!(get(&service).await.1, "HIT");
+
+        // Expired but within stale-while-revalidate: served at once, refreshed once in the background
+        age_entries(&cache, Duration::from_secs(61));
+        slow.store(true, Ordering::SeqCst);
+        let started = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(get(&service).await, (StatusCode::OK, "STALE".to_string(), "v1".to_string()));
+        }
+        assert!(started.elapsed() < Duration::from_millis(200));
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(get(&service).await, (StatusCode::OK, "HIT".to_string(), "v2".to_string()));
+
+        // Past stale-while-revalidate but within stale-if-error: a failing upstream is hidden
+        slow.store(false, Ordering::SeqCst);
+        failing.store(true, Ordering::SeqCst);
+        age_entries(&cache, Duration::from_secs(120));
+        assert_eq!(get(&service).await, (StatusCode::OK, "STALE-IF-ERROR".to_string(), "v2".to_string()));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // Past both windows the failure goes through
+        age_entries(&cache, Duration::from_secs(300));
+        assert_eq!(get(&service).await.0, StatusCode::SERVICE_UNAVAILABLE);
+
+        let exported = metrics.gather_metrics().unwrap();
+        assert!(exported.contains(r#"fortress_cache_requests_total{result="hit"} 2"#));
+        assert!(exported.contains(r#"fortress_cache_requests_total{result="stale"} 3"#));
+        assert!(exported.contains(r#"fortress_cache_requests_total{result="stale_on_error"} 1"#));
+    }
+
+    #[tokio::test]
+    async fn test_cache_control_vary_and_purge() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let upstream = {
+            let calls = calls.clone();
+            tower::service_fn(move |req: Request<Body>| {
+                let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                let language = req.headers().get("accept-language").map(|v| v.to_str().unwrap().to_string());
+                let response = match req.uri().path() {
+                    "/docs/guide" => Response::builder().header(CACHE_CONTROL, "public, max-age=60"),
+                    "/docs/secret" => Response::builder().header(CACHE_CONTROL, "no-store"),
+                    "/docs/missing" => Response::builder().status(StatusCode::NOT_FOUND),
+                    "/docs/created" => Response::builder().status(StatusCode::CREATED),
+                    _ => Response::builder().header(VARY, "Accept-Language"),
+                };
+                let body = format!("v{}{}", call, language.map(|l| format!("-{}", l)).unwrap_or_default());
+                async move { Ok::<_, Infallible>(response.body(Body::from(body)).unwrap()) }
+            })
+        };
+        let cache = CacheMiddleware::new(SharedConfig::new(FortressConfig::default()));
+        let service = cache.layer(upstream);
+        let request = |method: Method, path: &str, language: Option<&str>| {
+            let service = service.clone();
+            let mut request = Request::builder().method(method).uri(path);
+            if let Some(language) = language {
+                request = request.header("Accept-Language", language);
+            }
+            let request = request.body(Body::empty()).unwrap();
+            async move {
+                let response = service.oneshot(request).await.unwrap();
+                let cache = response.headers().get("X-Cache").map(|v| v.to_str().unwrap().to_string()).unwrap_or_default();
+                let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+                (cache, String::from_utf8(body.to_vec()).unwrap())
+            }
+        };
+
+        // Cacheable responses are served from the cache the second time
+        assert_eq!(request(Method::GET, "/docs/guide", None).await, ("MISS".to_string(), "v1".to_string()));
+        assert_eq!(request(Method::GET, "/docs/guide", None).await, ("HIT".to_string(), "v1".to_string()));
+        assert_eq!(request(Method::GET, "/docs/missing", None).await.0, "MISS");
+        assert_eq!(request(Method::GET, "/docs/missing", None).await.0, "HIT");
+        let max_age = {
+            let store = cache.cache.memory.lock().unwrap();
+            let entry = &store.entries["GET /docs/guide|"].response;
+            entry.expires_at.duration_since(Instant::now())
+        };
+        assert!(max_age <= Duration::from_secs(60));
+
+        // `no-store`, non-cacheable statuses and other methods go to the upstream every time
+        for path in ["/docs/secret", "/docs/created"] {
+            assert_eq!(request(Method::GET, path, None).await.0, "MISS");
+            assert_eq!(request(Method::GET, path, None).await.0, "MISS");
+        }
+        assert_eq!(request(Method::POST, "/docs/guide", None).await.0, "");
+
+        // Each `Vary` variant is cached on its own
+        let en = request(Method::GET, "/docs/negotiated", Some("en")).await;
+        let de = request(Method::GET, "/docs/negotiated", Some("de")).await;
+        assert!(en.1.ends_with("-en") && de.1.ends_with("-de"));
+        assert_eq!(request(Method::GET, "/docs/negotiated", Some("en")).await, ("HIT".to_string(), en.1));
+        assert_eq!(request(Method::GET, "/docs/negotiated", Some("de")).await, ("HIT".to_string(), de.1));
+
+        // Purging drops every entry under the prefix and nothing else
+        assert_eq!(cache.purge("/docs/negotiated").await.unwrap(), 2);
+        assert_eq!(request(Method::GET, "/docs/negotiated", Some("en")).await.0, "MISS");
+        assert_eq!(request(Method::GET, "/docs/guide", None).await.0, "HIT");
+        assert_eq!(cache.purge("/docs").await.unwrap(), 3);
+        assert_eq!(request(Method::GET, "/docs/guide", None).await.0, "MISS");
+    }
+
+    #[tokio::test]
+    async fn test_callers_with_different_api_keys_never_share_entries() {
+        let mut config = FortressConfig::default();
+        for (key, principal) in [("key-alice", "alice"), ("key-bob", "bob")] {
+            config.auth.api_keys.insert(principal.to_string(), ApiKey {
+                key: key.to_string(),
+                principal: principal.to_string(),
+                scopes: vec![],
+            });
+        }
+        let config = SharedConfig::new(config);
+        let cache = CacheMiddleware::new(config.clone());
+        // Answers with the caller the gateway was told about
+        let upstream = tower::service_fn(|req: Request<Body>| async move {
+            let caller = req.headers()["X-User-ID"].to_str().unwrap().to_string();
+            Ok::<_, Infallible>(Response::new(Body::from(caller)))
+        });
+        let service = tower::ServiceBuilder::new()
+            .layer(AuthMiddleware::new(config))
+            .layer(cache.clone())
+            .service(upstream);
+        let request = |key: &'static str| {
+            let request = Request::get("/forge/modules").header("X-API-Key", key).body(Body::empty()).unwrap();
+            let service = service.clone();
+            async move {
+                let response = service.oneshot(request).await.unwrap();
+                let cache = response.headers()["X-Cache"].to_str().unwrap().to_string();
+                let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+                (cache, String::from_utf8(body.to_vec()).unwrap())
+            }
+        };
+
+        assert_eq!(request("key-alice").await, ("MISS".to_string(), "alice".to_string()));
+        assert_eq!(request("key-bob").await, ("MISS".to_string(), "bob".to_string()));
+        assert_eq!(request("key-alice").await, ("HIT".to_string(), "alice".to_string()));
+        assert_eq!(request("key-bob").await, ("HIT".to_string(), "bob".to_string()));
+
+        // Neither credentials nor principals appear in key names
+        let store = cache.cache.memory.lock().unwrap();
+        assert_eq!(store.entries.len(), 2);
+        assert!(store.entries.keys().all(|key| !key.contains("key-") && !key.contains("alice") && !key.contains("bob")));
+    }
+
+    #[test]
+    fn test_memory_store_evicts_least_recently_used() {
+        let entry = |body: &'static str| CachedResponse {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::from(body),
+            expires_at: Instant::now() + Duration::from_secs(60),
+            evict_at: Instant::now() + Duration::from_secs(60),
+        };
+        let windows = StaleWindows { while_revalidate: Duration::ZERO, if_error: Duration::ZERO };
+        let mut store = CacheStore::default();
+        store.insert("a".to_string(), entry("aaaa"), 10);
+        store.insert("b".to_string(), entry("bbbb"), 10);
+        assert!(matches!(store.get("a", windows, Instant::now()), Lookup::Fresh(_)));
+
+        // `b` was used least recently, so it makes room for `c`
+        store.insert("c".to_string(), entry("cccc"), 10);
+        assert!(matches!(store.get("b", windows, Instant::now()), Lookup::Miss));
+        assert!(matches!(store.get("a", windows, Instant::now()), Lookup::Fresh(_)));
+        assert_eq!(store.size_bytes, 8);
+
+        // An entry larger than the whole store is never kept
+        store.insert("d".to_string(), entry("ddddddddddd"), 10);
+        assert!(matches!(store.get("d", windows, Instant::now()), Lookup::Miss));
+        assert_eq!(store.entries.len(), 2);
+    }
+}
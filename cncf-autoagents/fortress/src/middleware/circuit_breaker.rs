@@ -0,0 +1,227 @@
+//! Per-upstream circuit breaking
+//!
+//! `GatewayService` asks the breaker before each upstream call, once routing
+//! has picked the upstream, and reports the outcome afterwards. A run of
+//! failures within the rolling window opens the upstream's circuit; while it
+//! is open requests fail fast with 503 and a `Retry-After` header instead of
+//! waiting on a struggling upstream. After the open duration a few probe
+//! requests are let through, and the circuit closes once they all succeed.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::{config::CircuitBreakerConfig, metrics::MetricsCollector};
+
+/// Circuit state of an upstream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests flow normally
+    Closed,
+    /// Requests are rejected until the open duration elapses
+    Open,
+    /// A limited number of probe requests are let through to test recovery
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct UpstreamCircuit {
+    state: CircuitState,
+    /// Times of the current run of failures, oldest first
+    failures: Vec<Instant>,
+    opened_at: Option<Instant>,
+    probes_admitted: u32,
+    probes_succeeded: u32,
+}
+
+impl Default for UpstreamCircuit {
+    fn default() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            failures: Vec::new(),
+            opened_at: None,
+            probes_admitted: 0,
+            probes_succeeded: 0,
+        }
+    }
+}
+
+/// Tracks failures per upstream and stops sending traffic to failing ones.
+///
+/// `failure_threshold` consecutive failures, all within `window_ms`, open the
+/// circuit and requests are rejected. Once `cooldown_ms` has passed up to
+/// `half_open_probes` requests go through: when all of them succeed the
+/// circuit closes, and any failure opens it again.
+#[derive(Clone, Default)]
+pub struct CircuitBreaker {
+    upstreams: Arc<Mutex<HashMap<String, UpstreamCircuit>>>,
+    metrics: Option<MetricsCollector>,
+}
+
+impl CircuitBreaker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report state changes to `metrics`
+    pub fn with_metrics(mut self, metrics: MetricsCollector) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Whether a request may be sent to `upstream`; when not, how long until
+    /// the circuit lets requests through again
+    pub fn allow(&self, upstream: &str, config: &CircuitBreakerConfig) -> Result<(), Duration> {
+        if !config.enabled {
+            return Ok(());
+        }
+
+        let cooldown = Duration::from_millis(config.cooldown_ms);
+        let mut upstreams = self.upstreams.lock().unwrap();
+        let circuit = upstreams.entry(upstream.to_string()).or_default();
+        if circuit.state == CircuitState::Open {
+            let open_for = circuit.opened_at.map_or(cooldown, |opened_at| opened_at.elapsed());
+            if open_for < cooldown {
+                return Err(cooldown - open_for);
+            }
+            circuit.probes_admitted = 0;
+            circuit.probes_succeeded = 0;
+            self.transition(upstream, circuit, CircuitState::HalfOpen);
+        }
+
+        match circuit.state {
+            CircuitState::HalfOpen if circuit.probes_admitted >= config.half_open_probes.max(1) => {
+                // Probes are still out; they will settle the circuit shortly
+                Err(cooldown)
+            }
+            CircuitState::HalfOpen => {
+                circuit.probes_admitted += 1;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Record a successful upstream response
+    pub fn record_success(&self, upstream: &str, config: &CircuitBreakerConfig) -> CircuitState {
+        let mut upstreams = self.upstreams.lock().unwrap();
+        let circuit = upstreams.entry(upstream.to_string()).or_default();
+        circuit.failures.clear();
+        if circuit.state == CircuitState::HalfOpen {
+            circuit.probes_succeeded += 1;
+            if circuit.probes_succeeded >= config.half_open_probes.max(1) {
+                *circuit = UpstreamCircuit::default();
+                self.transition(upstream, circuit, CircuitState::Closed);
+            }
+        }
+        circuit.state
+    }
+
+    /// Record a failed upstream request, opening the circuit if needed
+    pub fn record_failure(&self, upstream: &str, config: &CircuitBreakerConfig) -> CircuitState {
+        let window = Duration::from_millis(config.window_ms);
+        let now = Instant::now();
+        let mut upstreams = self.upstreams.lock().unwrap();
+        let circuit = upstreams.entry(upstream.to_string()).or_default();
+        circuit.failures.retain(|failed_at| now.duration_since(*failed_at) <= window);
+        circuit.failures.push(now);
+
+        let trips = circuit.state == CircuitState::HalfOpen
+            || (circuit.state == CircuitState::Closed && circuit.failures.len() as u32 >= config.failure_threshold);
+        if trips {
+            circuit.opened_at = Some(now);
+            self.transition(upstream, circuit, CircuitState::Open);
+        }
+        circuit.state
+    }
+
+    /// Current state of an upstream's circuit
+    pub fn state(&self, upstream: &str) -> CircuitState {
+        self.upstreams
+            .lock()
+            .unwrap()
+            .get(upstream)
+            .map_or(CircuitState::Closed, |circuit| circuit.state)
+    }
+
+    fn transition(&self, upstream: &str, circuit: &mut UpstreamCircuit, to: CircuitState) {
+        match to {
+            CircuitState::Open => warn!(
+                "🔌 Circuit for {} opened after {} failure(s)",
+                upstream,
+                circuit.failures.len().max(1)
+            ),
+            CircuitState::HalfOpen => info!("🔌 Circuit for {} half-open, probing", upstream),
+            CircuitState::Closed => info!("🔌 Circuit for {} closed", upstream),
+        }
+        circuit.state = to;
+        if let Some(metrics) = &self.metrics {
+            metrics.record_circuit_transition(upstream, to);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_breaker_opens_and_recovers() {
+        let config = CircuitBreakerConfig {
+            failure_threshold: 3,
+            cooldown_ms: 20,
+            ..Default::default()
+        };
+        let breaker = CircuitBreaker::new();
+        let upstream = "http://backend:8080/*";
+
+        for _ in 0..2 {
+            assert!(breaker.allow(upstream, &config).is_ok());
+            assert_eq!(breaker.record_failure(upstream, &config), CircuitState::Closed);
+        }
+        // A success resets the consecutive count
+        breaker.record_success(upstream, &config);
+        for _ in 0..3 {
+            assert!(breaker.allow(upstream, &config).is_ok());
+            breaker.record_failure(upstream, &config);
+        }
+        assert_eq!(breaker.state(upstream), CircuitState::Open);
+        assert!(breaker.allow(upstream, &config).unwrap_err() <= Duration::from_millis(20));
+
+        // After the cooldown exactly one probe goes through; its failure reopens the circuit
+        std::thread::sleep(Duration::from_millis(25));
+        assert!(breaker.allow(upstream, &config).is_ok());
+        assert_eq!(breaker.state(upstream), CircuitState::HalfOpen);
+        assert!(breaker.allow(upstream, &config).is_err());
+        assert_eq!(breaker.record_failure(upstream, &config), CircuitState::Open);
+        assert!(breaker.allow(upstream, &config).is_err());
+
+        std::thread::sleep(Duration::from_millis(25));
+        assert!(breaker.allow(upstream, &config).is_ok());
+        assert_eq!(breaker.record_success(upstream, &config), CircuitState::Closed);
+        assert!(breaker.allow(upstream, &config).is_ok());
+        assert!(breaker.allow("http://other:8080/*", &config).is_ok());
+    }
+
+    #[test]
+    fn test_failures_outside_window_do_not_count() {
+        let config = CircuitBreakerConfig {
+            failure_threshold: 2,
+            window_ms: 20,
+            ..Default::default()
+        };
+        let breaker = CircuitBreaker::new();
+        let upstream = "http://backend:8080/*";
+
+        breaker.record_failure(upstream, &config);
+        std::thread::sleep(Duration::from_millis(25));
+        assert_eq!(breaker.record_failure(upstream, &config), CircuitState::Closed);
+        assert_eq!(breaker.record_failure(upstream, &config), CircuitState::Open);
+    }
+}
@@ -0,0 +1,568 @@
+//! Rate limiting middleware
+//!
+//! Per-client token buckets: each client may burst up to `burst_limit`
+//! requests, refilled at `requests_per_minute`. Clients are the authenticated
+//! principal from the [`AuthContext`], else the peer address. Routes with
+//! their own `rate_limit` count against separate buckets with their limits.
+//!
+//! With `rate_limit.redis_url` set, buckets live in Redis and are updated by
+//! a Lua script, so every gateway instance draws from the same budget. While
+//! Redis is unreachable, decisions fall back to the instance's in-memory
+//! buckets, which are dropped once refilled and capped in number. Limits are
+//! read from the live configuration, so scheduled overlays take effect
+//! immediately.
+//!
+//! Responses carry `RateLimit-Limit`, `RateLimit-Remaining` and
+//! `RateLimit-Reset`; rejected requests get a 429 with a JSON body and
+//! `Retry-After`.
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures::future::BoxFuture;
+use hyper::{
+    header::{HeaderValue, CONTENT_TYPE, RETRY_AFTER},
+    http::StatusCode,
+    Body, Request, Response,
+};
+use redis::RedisResult;
+use tower::{Layer, Service};
+use tracing::warn;
+
+use crate::{
+    config::{FortressConfig, Route, SharedConfig},
+    metrics::MetricsCollector,
+    middleware::{auth::AuthContext, redis_connector::RedisConnector},
+    routing::resolve_route,
+    usage::ANONYMOUS,
+};
+
+/// Prefix of every key the rate limiter writes to Redis
+const REDIS_PREFIX: &str = "fortress:ratelimit:";
+
+/// Most in-memory buckets kept before the least recently used is dropped
+pub const DEFAULT_MAX_BUCKETS: usize = 100_000;
+
+/// How often in-memory buckets that have refilled completely are dropped
+const BUCKET_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Atomic token bucket update. Refills by the time since the last update on
+/// the Redis clock, takes a token if one is left, and returns whether it did,
+/// the whole tokens remaining, and milliseconds until the bucket is full and
+/// until the next token.
+const TOKEN_BUCKET_SCRIPT: &str = r#"
+local burst = tonumber(ARGV[1])
+local rate = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+local tokens = tonumber(bucket[1]) or burst
+local ts = tonumber(bucket[2]) or now
+tokens = math.min(burst, tokens + math.max(0, now - ts) * rate)
+local allowed = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+    allowed = 1
+end
+local function wait(needed)
+    if needed <= 0 then return 0 end
+    if rate <= 0 then return 86400000 end
+    return math.ceil(needed / rate)
+end
+local full_ms = wait(burst - tokens)
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'ts', now)
+redis.call('PEXPIRE', KEYS[1], math.max(full_ms, 1000))
+return {allowed, math.floor(tokens), full_ms, wait(1 - tokens)}
+"#;
+
+/// Bucket size and refill rate applying to a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub requests_per_minute: u32,
+    pub burst_limit: u32,
+}
+
+impl Limits {
+    fn tokens_per_ms(&self) -> f64 {
+        self.requests_per_minute as f64 / 60_000.0
+    }
+}
+
+/// Outcome of taking a token from a bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until the bucket is full again
+    pub reset_secs: u64,
+    /// Seconds until the next token, for rejected requests
+    pub retry_after_secs: u64,
+}
+
+/// Buckets shared by every gateway instance
+pub trait SharedBuckets: Send + Sync {
+    /// Take a token from bucket `key` in the store at `url`
+    fn acquire<'a>(&'a self, url: &'a str, key: &'a str, limits: Limits) -> BoxFuture<'a, RedisResult<Decision>>;
+}
+
+/// Buckets in Redis, updated atomically by [`TOKEN_BUCKET_SCRIPT`]
+#[derive(Clone, Default)]
+pub struct RedisBuckets {
+    connector: RedisConnector,
+}
+
+impl SharedBuckets for RedisBuckets {
+    fn acquire<'a>(&'a self, url: &'a str, key: &'a str, limits: Limits) -> BoxFuture<'a, RedisResult<Decision>> {
+        Box::pin(async move {
+            let mut connection = self.connector.connection(url).await?;
+            let (allowed, remaining, full_ms, next_ms): (i64, i64, i64, i64) = redis::Script::new(TOKEN_BUCKET_SCRIPT)
+                .key(format!("{}{}", REDIS_PREFIX, key))
+                .arg(limits.burst_limit)
+                .arg(limits.tokens_per_ms())
+                .invoke_async(&mut connection)
+                .await?;
+            Ok(Decision {
+                allowed: allowed == 1,
+                limit: limits.burst_limit,
+                remaining: remaining.max(0) as u32,
+                reset_secs: (full_ms.max(0) as u64).div_ceil(1000),
+                retry_after_secs: (next_ms.max(0) as u64).div_ceil(1000),
+            })
+        })
+    }
+}
+
+/// Rate limiting middleware
+#[derive(Clone)]
+pub struct RateLimitMiddleware {
+    config: SharedConfig,
+    buckets: Arc<Mutex<LocalBuckets>>,
+    shared: Arc<dyn SharedBuckets>,
+    metrics: Option<MetricsCollector>,
+}
+
+impl RateLimitMiddleware {
+    /// Create a new rate limiting middleware
+    pub fn new(config: SharedConfig) -> Self {
+        Self {
+            config,
+            buckets: Arc::new(Mutex::new(LocalBuckets::new(DEFAULT_MAX_BUCKETS))),
+            shared: Arc::new(RedisBuckets::default()),
+            metrics: None,
+        }
+    }
+
+    /// Keep shared buckets in `shared` instead of Redis
+    pub fn with_shared_buckets(mut self, shared: Arc<dyn SharedBuckets>) -> Self {
+        self.shared = shared;
+        self
+    }
+
+    /// Keep at most `max_buckets` in-memory buckets, dropping the least recently used
+    pub fn with_max_buckets(mut self, max_buckets: usize) -> Self {
+        self.buckets = Arc::new(Mutex::new(LocalBuckets::new(max_buckets)));
+        self
+    }
+
+    /// Count rejections and in-memory fallbacks in `metrics`
+    pub fn with_metrics(mut self, metrics: MetricsCollector) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+}
+
+impl<S> Layer<S> for RateLimitMiddleware {
+    type Service = RateLimitMiddlewareService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitMiddlewareService {
+            inner,
+            limiter: self.clone(),
+        }
+    }
+}
+
+/// Service wrapper for rate limiting middleware
+#[derive(Clone)]
+pub struct RateLimitMiddlewareService<S> {
+    inner: S,
+    limiter: RateLimitMiddleware,
+}
+
+impl<S> Service<Request<Body>> for RateLimitMiddlewareService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = std::pin::Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let config = self.limiter.config.current();
+        if !config.rate_limit.enabled {
+            return Box::pin(inner.call(req));
+        }
+
+        let route = resolve_route(&config.routing, &mut req);
+        let (scope, limits) = limits_for(&config, route.as_deref());
+        let (client_type, client) = client_key(&req);
+        let key = format!("{}:{}", scope, client);
+        let limiter = self.limiter.clone();
+
+        Box::pin(async move {
+            let decision = limiter.acquire(config.rate_limit.redis_url.as_deref(), &key, limits).await;
+            if decision.allowed {
+                let response = inner.call(req).await?;
+                return Ok(with_rate_limit_headers(response, &decision));
+            }
+
+            warn!("⏳ Rate limit exceeded for {}", key);
+            if let Some(metrics) = &limiter.metrics {
+                metrics.record_rate_limit_exceeded(client_type);
+            }
+            let body = serde_json::json!({
+                "error": {
+                    "code": StatusCode::TOO_MANY_REQUESTS.as_u16(),
+                    "message": "Rate limit exceeded",
+                    "retry_after_seconds": decision.retry_after_secs.max(1),
+                    "gateway": "fortress"
+                }
+            });
+            let response = Response::builder()
+                .status(StatusCode::TOO_MANY_REQUESTS)
+                .header(CONTENT_TYPE, "application/json")
+                .header(RETRY_AFTER, decision.retry_after_secs.max(1))
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            Ok(with_rate_limit_headers(response, &decision))
+        })
+    }
+}
+
+impl RateLimitMiddleware {
+    /// Take a token from the shared bucket, or from this instance's bucket
+    /// when no Redis is configured or it cannot be reached
+    async fn acquire(&self, redis_url: Option<&str>, key: &str, limits: Limits) -> Decision {
+        if let Some(url) = redis_url {
+            match self.shared.acquire(url, key, limits).await {
+                Ok(decision) => return decision,
+                Err(e) => {
+                    warn!("⏳ Rate limiting in memory, Redis unavailable: {}", e);
+                    if let Some(metrics) = &self.metrics {
+                        metrics.record_rate_limit_redis_fallback();
+                    }
+                }
+            }
+        }
+
+        self.buckets.lock().unwrap().try_acquire(key, limits)
+    }
+}
+
+/// Bucket scope and limits of a request: the matched route's own limits, else the global ones
+fn limits_for(config: &FortressConfig, route: Option<&Route>) -> (String, Limits) {
+    match route.and_then(|route| Some((route.label().to_string(), route.rate_limit?))) {
+        Some((label, limits)) => (
+            format!("route:{}", label),
+            Limits {
+                requests_per_minute: limits.requests_per_minute,
+                burst_limit: limits.burst_limit,
+            },
+        ),
+        None => (
+            "global".to_string(),
+            Limits {
+                requests_per_minute: config.rate_limit.requests_per_minute,
+                burst_limit: config.rate_limit.burst_limit,
+            },
+        ),
+    }
+}
+
+/// Client type and identifier: the authenticated principal if known, else the peer address
+fn client_key(req: &Request<Body>) -> (&'static str, String) {
+    if let Some(context) = req.extensions().get::<AuthContext>().filter(|context| context.principal != ANONYMOUS) {
+        return ("principal", format!("principal:{}", context.principal));
+    }
+
+    let key = req
+        .extensions()
+        .get::<SocketAddr>()
+        .map(|addr| format!("ip:{}", addr.ip()))
+        .unwrap_or_else(|| "unknown".to_string());
+    ("ip", key)
+}
+
+fn with_rate_limit_headers(mut response: Response<Body>, decision: &Decision) -> Response<Body> {
+    let headers = response.headers_mut();
+    headers.insert("RateLimit-Limit", HeaderValue::from(decision.limit));
+    headers.insert("RateLimit-Remaining", HeaderValue::from(decision.remaining));
+    headers.insert("RateLimit-Reset", HeaderValue::from(decision.reset_secs));
+    response
+}
+
+/// This instance's buckets, used without Redis or while it is unreachable.
+///
+/// A bucket that has refilled completely is no different from a new one, so
+/// such buckets are swept periodically. Past `max_buckets`, the least
+/// recently used bucket makes room for a new client.
+#[derive(Debug)]
+struct LocalBuckets {
+    buckets: HashMap<String, TokenBucket>,
+    max_buckets: usize,
+    last_sweep: Instant,
+}
+
+impl LocalBuckets {
+    fn new(max_buckets: usize) -> Self {
+        Self {
+            buckets: HashMap::new(),
+            max_buckets: max_buckets.max(1),
+            last_sweep: Instant::now(),
+        }
+    }
+
+    fn try_acquire(&mut self, key: &str, limits: Limits) -> Decision {
+        let now = Instant::now();
+        if now.duration_since(self.last_sweep) >= BUCKET_SWEEP_INTERVAL {
+            self.sweep(now);
+        }
+        if !self.buckets.contains_key(key) && self.buckets.len() >= self.max_buckets {
+            self.sweep(now);
+            if self.buckets.len() >= self.max_buckets {
+                let oldest = self.buckets.iter().min_by_key(|(_, bucket)| bucket.last_refill).map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    self.buckets.remove(&oldest);
+                }
+            }
+        }
+
+        self.buckets
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucket::new(limits.burst_limit))
+            .try_acquire(limits)
+    }
+
+    /// Drop buckets that have refilled completely
+    fn sweep(&mut self, now: Instant) {
+        self.buckets.retain(|_, bucket| bucket.full_at > now);
+        self.last_sweep = now;
+    }
+}
+
+/// Token bucket for a single client
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+    /// When the bucket will have refilled to its burst limit
+    full_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(burst_limit: u32) -> Self {
+        let now = Instant::now();
+        Self {
+            tokens: burst_limit as f64,
+            last_refill: now,
+            full_at: now,
+        }
+    }
+
+    /// Refill by elapsed time, then take one token if available
+    fn try_acquire(&mut self, limits: Limits) -> Decision {
+        let refill_per_sec = limits.requests_per_minute as f64 / 60.0;
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * refill_per_sec).min(limits.burst_limit as f64);
+        self.last_refill = now;
+
+        let allowed = self.tokens >= 1.0;
+        if allowed {
+            self.tokens -= 1.0;
+        }
+        let missing = limits.burst_limit as f64 - self.tokens;
+        self.full_at = if missing <= 0.0 {
+            now
+        } else if refill_per_sec <= 0.0 {
+            now + Duration::from_secs(86_400)
+        } else {
+            now + Duration::from_secs_f64(missing / refill_per_sec)
+        };
+        let wait = |needed: f64| {
+            if needed <= 0.0 {
+                0
+            } else if refill_per_sec <= 0.0 {
+                86_400
+            } else {
+                (needed / refill_per_sec).ceil() as u64
+            }
+        };
+        Decision {
+            allowed,
+            limit: limits.burst_limit,
+            remaining: self.tokens.floor() as u32,
+            reset_secs: wait(limits.burst_limit as f64 - self.tokens),
+            retry_after_secs: wait(1.0 - self.tokens),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RateLimitConfig;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_token_bucket_burst() {
+        let limits = Limits {
+            requests_per_minute: 60,
+            burst_limit: 3,
+        };
+        let mut bucket = TokenBucket::new(limits.burst_limit);
+
+        assert!(bucket.try_acquire(limits).allowed);
+        assert!(bucket.try_acquire(limits).allowed);
+        let last = bucket.try_acquire(limits);
+        assert!(last.allowed);
+        assert_eq!((last.remaining, last.reset_secs), (0, 3));
+        let rejected = bucket.try_acquire(limits);
+        assert!(!rejected.allowed);
+        assert_eq!(rejected.retry_after_secs, 1);
+    }
+
+    #[test]
+    fn test_idle_buckets_evicted() {
+        // Refills a token every millisecond
+        let fast = Limits {
+            requests_per_minute: 60_000,
+            burst_limit: 2,
+        };
+        let slow = Limits {
+            requests_per_minute: 1,
+            burst_limit: 2,
+        };
+        let mut buckets = LocalBuckets::new(2);
+        buckets.try_acquire("idle", fast);
+        buckets.try_acquire("active", slow);
+        std::thread::sleep(Duration::from_millis(10));
+
+        // At capacity, the refilled bucket makes room rather than an active one
+        buckets.try_acquire("new", slow);
+        assert!(buckets.buckets.contains_key("active") && buckets.buckets.contains_key("new"));
+        assert!(!buckets.buckets.contains_key("idle"));
+
+        // With none refilled, the least recently used goes
+        buckets.try_acquire("active", slow);
+        buckets.try_acquire("newer", slow);
+        assert_eq!(buckets.buckets.len(), 2);
+        assert!(!buckets.buckets.contains_key("new"));
+
+        // The periodic sweep drops refilled buckets below capacity too
+        let mut buckets = LocalBuckets::new(100);
+        buckets.try_acquire("idle", fast);
+        std::thread::sleep(Duration::from_millis(10));
+        buckets.last_sweep -= BUCKET_SWEEP_INTERVAL;
+        buckets.try_acquire("other", slow);
+        assert_eq!(buckets.buckets.keys().collect::<Vec<_>>(), ["other"]);
+    }
+
+    /// Stands in for Redis: one set of buckets behind every instance
+    #[derive(Default)]
+    struct MockRedis {
+        buckets: Mutex<HashMap<String, TokenBucket>>,
+        down: std::sync::atomic::AtomicBool,
+    }
+
+    impl SharedBuckets for MockRedis {
+        fn acquire<'a>(&'a self, _url: &'a str, key: &'a str, limits: Limits) -> BoxFuture<'a, RedisResult<Decision>> {
+            Box::pin(async move {
+                if self.down.load(std::sync::atomic::Ordering::SeqCst) {
+                    return Err((redis::ErrorKind::IoError, "connection refused").into());
+                }
+                let mut buckets = self.buckets.lock().unwrap();
+                Ok(buckets
+                    .entry(key.to_string())
+                    .or_insert_with(|| TokenBucket::new(limits.burst_limit))
+                    .try_acquire(limits))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_instances_share_one_budget() {
+        let config = FortressConfig {
+            rate_limit: RateLimitConfig {
+                requests_per_minute: 1,
+                burst_limit: 4,
+                redis_url: Some("redis://shared:6379".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let redis = Arc::new(MockRedis::default());
+        let metrics = MetricsCollector::new();
+        let instance = || {
+            let upstream = tower::service_fn(|_req: Request<Body>| async {
+                Ok::<_, std::convert::Infallible>(Response::new(Body::empty()))
+            });
+            RateLimitMiddleware::new(SharedConfig::new(config.clone()))
+                .with_shared_buckets(redis.clone())
+                .with_metrics(metrics.clone())
+                .layer(upstream)
+        };
+        let (first, second) = (instance(), instance());
+        let call = |service: RateLimitMiddlewareService<_>, principal: &str| {
+            let mut req = Request::get("/api/v1/agents").body(Body::empty()).unwrap();
+            req.extensions_mut().insert(AuthContext {
+                principal: principal.to_string(),
+                scopes: Vec::new(),
+            });
+            service.oneshot(req)
+        };
+
+        // Alternating between instances, alice gets the burst of 4 once, not twice
+        let mut statuses = Vec::new();
+        for i in 0..6 {
+            let service = if i % 2 == 0 { first.clone() } else { second.clone() };
+            let response = call(service, "alice").await.unwrap();
+            statuses.push(response.status());
+            if i == 1 {
+                assert_eq!(response.headers()["RateLimit-Limit"], "4");
+                assert_eq!(response.headers()["RateLimit-Remaining"], "2");
+            }
+            if i == 5 {
+                assert_eq!(response.headers()[RETRY_AFTER], "60");
+                let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+                let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                assert_eq!(body["error"]["code"], 429);
+            }
+        }
+        use StatusCode as S;
+        assert_eq!(statuses, [S::OK, S::OK, S::OK, S::OK, S::TOO_MANY_REQUESTS, S::TOO_MANY_REQUESTS]);
+        assert_eq!(call(second.clone(), "bob").await.unwrap().status(), S::OK);
+
+        // Without Redis each instance limits from its own buckets
+        redis.down.store(true, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(call(first.clone(), "alice").await.unwrap().status(), S::OK);
+        assert_eq!(call(second.clone(), "alice").await.unwrap().status(), S::OK);
+        let exported = metrics.gather_metrics().unwrap();
+        assert!(exported.contains("fortress_rate_limit_redis_fallbacks_total 2"));
+        assert!(exported.contains("fortress_rate_limit_exceeded_total{client_type=\"principal\"} 2"));
+    }
+}
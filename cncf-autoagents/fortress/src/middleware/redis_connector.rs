@@ -0,0 +1,62 @@
+//! Redis connections shared by the middleware
+//!
+//! The cache and the rate limiter keep their state in Redis when one is
+//! configured. Each opens its connection on first use, reopens it when the
+//! configured URL changes, and after a failed attempt reports Redis as
+//! unavailable for a few seconds, so requests fall back at once rather than
+//! each waiting on a Redis that is down.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use redis::{aio::ConnectionManager, RedisResult};
+
+/// How long to wait for Redis to accept a connection
+const REDIS_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long Redis is reported unavailable after a failed connection attempt
+const REDIS_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+enum RedisConnection {
+    Connected(ConnectionManager),
+    Failed(Instant),
+}
+
+/// Lazily opened connection to a Redis URL
+#[derive(Clone, Default)]
+pub struct RedisConnector {
+    connection: Arc<tokio::sync::Mutex<Option<(String, RedisConnection)>>>,
+}
+
+impl RedisConnector {
+    /// Connection to `url`, opening it if needed
+    pub async fn connection(&self, url: &str) -> RedisResult<ConnectionManager> {
+        let mut current = self.connection.lock().await;
+        match current.as_ref() {
+            Some((current_url, RedisConnection::Connected(connection))) if current_url == url => {
+                return Ok(connection.clone())
+            }
+            Some((current_url, RedisConnection::Failed(at))) if current_url == url && at.elapsed() < REDIS_RETRY_INTERVAL => {
+                return Err((redis::ErrorKind::IoError, "Redis unavailable, retrying shortly").into())
+            }
+            _ => {}
+        }
+
+        let connected = match tokio::time::timeout(REDIS_CONNECT_TIMEOUT, async {
+            ConnectionManager::new(redis::Client::open(url)?).await
+        })
+        .await
+        {
+            Ok(connected) => connected,
+            Err(_) => Err((redis::ErrorKind::IoError, "Redis connection timed out").into()),
+        };
+        let state = match &connected {
+            Ok(connection) => RedisConnection::Connected(connection.clone()),
+            Err(_) => RedisConnection::Failed(Instant::now()),
+        };
+        *current = Some((url.to_string(), state));
+        connected
+    }
+}
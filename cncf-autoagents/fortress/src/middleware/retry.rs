@@ -0,0 +1,150 @@
+//! Upstream retries
+//!
+//! `GatewayService` retries a failed upstream attempt when the route's
+//! [`RetryPolicy`](crate::config::RetryPolicy) covers the failure and the
+//! method is idempotent. Every retry is drawn from a gateway-wide
+//! [`RetryBudget`] that caps retries at a share of recent requests, so an
+//! upstream that starts failing sees a bounded rise in load rather than a
+//! retry storm.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use hyper::{http::Method, Body, Response};
+
+use crate::config::{RetryBudgetConfig, RetryOn};
+
+/// An upstream attempt that ran past the route's `timeout_ms`
+#[derive(Debug, thiserror::Error)]
+#[error("upstream attempt timed out after {0:?}")]
+pub struct AttemptTimeout(pub Duration);
+
+/// Whether `method` may be repeated without changing the outcome (RFC 9110 §9.2.2)
+pub fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE | Method::PUT | Method::DELETE
+    )
+}
+
+/// The kind of failure an attempt ended in, or `None` if it succeeded or
+/// failed in a way retrying cannot help
+pub fn failure_kind(outcome: &Result<Response<Body>, Box<dyn std::error::Error>>) -> Option<RetryOn> {
+    match outcome {
+        Ok(response) => response.status().is_server_error().then_some(RetryOn::ServerError),
+        Err(err) if err.is::<AttemptTimeout>() => Some(RetryOn::Timeout),
+        Err(err) => {
+            let err = err.downcast_ref::<reqwest::Error>()?;
+            if err.is_timeout() {
+                Some(RetryOn::Timeout)
+            } else if err.is_connect() || err.is_request() || err.is_body() {
+                Some(RetryOn::ConnectError)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Requests and retries started within one second
+#[derive(Debug)]
+struct Bucket {
+    second: u64,
+    requests: u64,
+    retries: u64,
+}
+
+#[derive(Debug)]
+struct BudgetWindow {
+    started: Instant,
+    buckets: VecDeque<Bucket>,
+}
+
+impl BudgetWindow {
+    /// The current bucket, after dropping those older than `ttl_seconds`
+    fn current(&mut self, ttl_seconds: u64) -> &mut Bucket {
+        let second = self.started.elapsed().as_secs();
+        while self.buckets.front().is_some_and(|bucket| bucket.second + ttl_seconds.max(1) <= second) {
+            self.buckets.pop_front();
+        }
+        if self.buckets.back().is_none_or(|bucket| bucket.second != second) {
+            self.buckets.push_back(Bucket { second, requests: 0, retries: 0 });
+        }
+        self.buckets.back_mut().unwrap()
+    }
+}
+
+/// Gateway-wide cap on retries; see [`RetryBudgetConfig`]
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    window: Arc<Mutex<BudgetWindow>>,
+}
+
+impl Default for RetryBudget {
+    fn default() -> Self {
+        Self {
+            window: Arc::new(Mutex::new(BudgetWindow {
+                started: Instant::now(),
+                buckets: VecDeque::new(),
+            })),
+        }
+    }
+}
+
+impl RetryBudget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a request sent upstream, adding to the budget
+    pub fn deposit(&self, config: &RetryBudgetConfig) {
+        self.window.lock().unwrap().current(config.ttl_seconds).requests += 1;
+    }
+
+    /// Take one retry from the budget; `false` when it is spent
+    pub fn try_withdraw(&self, config: &RetryBudgetConfig) -> bool {
+        let mut window = self.window.lock().unwrap();
+        window.current(config.ttl_seconds);
+        let (requests, retries) = window
+            .buckets
+            .iter()
+            .fold((0, 0), |(requests, retries), bucket| (requests + bucket.requests, retries + bucket.retries));
+
+        let allowed = config.min_retries_per_second as f64 * config.ttl_seconds as f64 + config.retry_ratio * requests as f64;
+        if retries as f64 >= allowed {
+            return false;
+        }
+        window.buckets.back_mut().unwrap().retries += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_caps_retries_at_ratio_of_requests() {
+        let config = RetryBudgetConfig {
+            retry_ratio: 0.2,
+            min_retries_per_second: 0,
+            ttl_seconds: 10,
+        };
+        let budget = RetryBudget::new();
+        assert!(!budget.try_withdraw(&config));
+
+        for _ in 0..10 {
+            budget.deposit(&config);
+        }
+        assert!(budget.try_withdraw(&config));
+        assert!(budget.try_withdraw(&config));
+        assert!(!budget.try_withdraw(&config));
+
+        // The floor lets a quiet gateway retry anyway
+        let floor = RetryBudgetConfig { min_retries_per_second: 1, ..config };
+        assert!(RetryBudget::new().try_withdraw(&floor));
+    }
+}
@@ -0,0 +1,151 @@
+//! Usage accounting middleware
+//!
+//! Sits behind authentication so the principal is known, and in front of the
+//! rate limiter so throttled requests are counted too. The gateway reports the
+//! matched route and upstream time through response extensions.
+
+use std::task::{Context, Poll};
+
+use hyper::{body::HttpBody, header::CONTENT_LENGTH, http::HeaderMap, Body, Request, Response};
+use tower::{Layer, Service};
+
+use crate::usage::{MatchedRoute, UpstreamTime, UsageRecord, UsageTracker, ANONYMOUS};
+
+/// Usage accounting middleware
+#[derive(Clone)]
+pub struct UsageMiddleware {
+    tracker: UsageTracker,
+}
+
+impl UsageMiddleware {
+    /// Create a usage middleware recording into `tracker`
+    pub fn new(tracker: UsageTracker) -> Self {
+        Self { tracker }
+    }
+}
+
+impl<S> Layer<S> for UsageMiddleware {
+    type Service = UsageMiddlewareService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        UsageMiddlewareService {
+            inner,
+            tracker: self.tracker.clone(),
+        }
+    }
+}
+
+/// Service wrapper for usage middleware
+#[derive(Clone)]
+pub struct UsageMiddlewareService<S> {
+    inner: S,
+    tracker: UsageTracker,
+}
+
+impl<S> Service<Request<Body>> for UsageMiddlewareService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = std::pin::Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let tracker = self.tracker.clone();
+
+        let principal = req
+            .headers()
+            .get("X-User-ID")
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or(ANONYMOUS)
+            .to_string();
+        let bytes_in = body_len(req.headers(), req.body());
+
+        Box::pin(async move {
+            let response = inner.call(req).await?;
+            tracker.record(UsageRecord {
+                principal,
+                route: response.extensions().get::<MatchedRoute>().map(|route| route.0.clone()),
+                status: response.status().as_u16(),
+                bytes_in,
+                bytes_out: body_len(response.headers(), response.body()),
+                upstream_time: response.extensions().get::<UpstreamTime>().map(|time| time.0),
+            });
+            Ok(response)
+        })
+    }
+}
+
+/// Body size from `Content-Length`, else the body's exact size hint, else 0 for streams
+pub(crate) fn body_len(headers: &HeaderMap, body: &Body) -> u64 {
+    headers
+        .get(CONTENT_LENGTH)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.parse().ok())
+        .or_else(|| body.size_hint().exact())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::UsageConfig, usage};
+    use std::{convert::Infallible, time::Duration};
+    use tower::ServiceExt;
+
+    /// Stand-in for the gateway: `/api/*` is proxied, `/throttled` is refused by the rate limiter
+    async fn fake_gateway(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        let mut response = match req.uri().path() {
+            "/throttled" => Response::builder().status(429).body(Body::empty()).unwrap(),
+            _ => Response::new(Body::from("0123456789")),
+        };
+        if req.uri().path().starts_with("/api/") {
+            response.extensions_mut().insert(MatchedRoute("/api/*".to_string()));
+            response.extensions_mut().insert(UpstreamTime(Duration::from_millis(25)));
+        }
+        Ok(response)
+    }
+
+    async fn send(tracker: &UsageTracker, principal: &str, path: &str, body: &'static str) {
+        let service = UsageMiddleware::new(tracker.clone()).layer(tower::service_fn(fake_gateway));
+        let req = Request::builder()
+            .uri(path)
+            .header("X-User-ID", principal)
+            .body(Body::from(body))
+            .unwrap();
+        service.oneshot(req).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_usage_from_two_principals() {
+        let tracker = UsageTracker::new(&UsageConfig::default());
+        send(&tracker, "alice", "/api/agents", "{}").await;
+        send(&tracker, "alice", "/api/agents", "{\"a\":1}").await;
+        send(&tracker, "alice", "/throttled", "").await;
+        send(&tracker, "bob", "/api/tasks", "12345").await;
+
+        let (from, to) = tracker.retained_window();
+        let alice = tracker.report(Some("alice"), from, to);
+        assert_eq!(alice.len(), 2);
+        let throttled = &alice[0];
+        assert_eq!((throttled.route.as_str(), throttled.requests, throttled.throttled), ("-", 1, 1));
+        let api = &alice[1];
+        assert_eq!(
+            (api.route.as_str(), api.requests, api.bytes_in, api.bytes_out, api.upstream_time_ms),
+            ("/api/*", 2, 9, 20, 50)
+        );
+
+        let csv = usage::to_csv(&tracker.report(Some("bob"), from, to));
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "hour,principal,route,requests,bytes_in,bytes_out,upstream_time_ms,rejected,throttled");
+        assert!(lines[1].ends_with(",bob,/api/*,1,5,10,25,0,0"), "{}", lines[1]);
+        assert_eq!(lines.len(), 2);
+    }
+}
@@ -0,0 +1,407 @@
+//! Scheduled Configuration Overlays
+//!
+//! An overlay temporarily replaces parts of the live configuration (rate
+//! limits, load balancing, maintenance mode) between a start and an end time,
+//! e.g. "raise the rate limit for the demo from 2-4pm Friday". At most one
+//! overlay is active at a time, so overlapping windows are rejected when
+//! they are scheduled.
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::config::{FortressConfig, LoadBalancingStrategy, RateLimitConfig, SharedConfig};
+
+/// Source of the current time, replaceable in tests
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Wall-clock time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Configuration fields an overlay may replace; `None` keeps the base value
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigOverlay {
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitConfig>,
+    #[serde(default)]
+    pub load_balancing: Option<LoadBalancingStrategy>,
+    #[serde(default)]
+    pub maintenance_mode: Option<bool>,
+}
+
+impl ConfigOverlay {
+    /// Base configuration with this overlay applied
+    pub fn apply(&self, base: &FortressConfig) -> FortressConfig {
+        let mut config = base.clone();
+        if let Some(rate_limit) = &self.rate_limit {
+            config.rate_limit = rate_limit.clone();
+        }
+        if let Some(load_balancing) = &self.load_balancing {
+            config.routing.load_balancing = load_balancing.clone();
+        }
+        if let Some(maintenance_mode) = self.maintenance_mode {
+            config.maintenance_mode = maintenance_mode;
+        }
+        config
+    }
+}
+
+/// An overlay with its activation window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledOverlay {
+    pub id: String,
+    pub overlay: ConfigOverlay,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub scheduled_by: String,
+    pub scheduled_at: DateTime<Utc>,
+}
+
+impl ScheduledOverlay {
+    /// Whether the window `[starts_at, ends_at)` contains `now`
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        self.starts_at <= now && now < self.ends_at
+    }
+
+    fn overlaps(&self, starts_at: DateTime<Utc>, ends_at: DateTime<Utc>) -> bool {
+        self.starts_at < ends_at && starts_at < self.ends_at
+    }
+}
+
+/// Overlay scheduling errors
+#[derive(Debug, thiserror::Error)]
+pub enum OverlayError {
+    #[error("Invalid window: {0}")]
+    InvalidWindow(String),
+    #[error("Overlaps overlay {id} scheduled by {scheduled_by}")]
+    Overlaps { id: String, scheduled_by: String },
+    #[error("Overlay not found: {0}")]
+    NotFound(String),
+    #[error("Overlay store error: {0}")]
+    Store(String),
+}
+
+#[derive(Debug, Default)]
+struct SchedulerState {
+    overlays: Vec<ScheduledOverlay>,
+    /// Id of the overlay currently applied to the live configuration
+    applied: Option<String>,
+}
+
+/// Applies scheduled overlays to the live configuration at their boundaries
+#[derive(Clone)]
+pub struct OverlayScheduler {
+    base: SharedConfig,
+    live: SharedConfig,
+    clock: Arc<dyn Clock>,
+    state: Arc<Mutex<SchedulerState>>,
+    store_path: Option<PathBuf>,
+}
+
+impl OverlayScheduler {
+    /// Scheduler layering overlays over `base`, publishing to `live`
+    pub fn new(base: FortressConfig, live: SharedConfig) -> Self {
+        Self {
+            base: SharedConfig::new(base),
+            live,
+            clock: Arc::new(SystemClock),
+            state: Arc::new(Mutex::new(SchedulerState::default())),
+            store_path: None,
+        }
+    }
+
+    /// Use a custom clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Persist overlays as JSON at `path`, loading any saved by a previous run.
+    ///
+    /// An overlay whose window is already open when loaded is applied
+    /// immediately, so activations missed during a restart are not lost.
+    pub fn with_store(mut self, path: impl Into<PathBuf>) -> Result<Self, OverlayError> {
+        let path = path.into();
+        if path.exists() {
+            let content = std::fs::read_to_string(&path).map_err(|e| OverlayError::Store(e.to_string()))?;
+            let overlays: Vec<ScheduledOverlay> =
+                serde_json::from_str(&content).map_err(|e| OverlayError::Store(e.to_string()))?;
+            info!("🗓️ Loaded {} scheduled overlay(s) from {}", overlays.len(), path.display());
+            self.state.lock().unwrap().overlays = overlays;
+        }
+        self.store_path = Some(path);
+        self.tick();
+        Ok(self)
+    }
+
+    /// Schedule an overlay for `[starts_at, ends_at)`
+    pub fn schedule(
+        &self,
+        overlay: ConfigOverlay,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+        scheduled_by: &str,
+    ) -> Result<ScheduledOverlay, OverlayError> {
+        let now = self.clock.now();
+        if ends_at <= starts_at {
+            return Err(OverlayError::InvalidWindow("end must be after start".to_string()));
+        }
+        if ends_at <= now {
+            return Err(OverlayError::InvalidWindow("window has already ended".to_string()));
+        }
+
+        let scheduled = {
+            let mut state = self.state.lock().unwrap();
+            state.overlays.retain(|o| o.ends_at > now);
+            if let Some(existing) = state.overlays.iter().find(|o| o.overlaps(starts_at, ends_at)) {
+                return Err(OverlayError::Overlaps {
+                    id: existing.id.clone(),
+                    scheduled_by: existing.scheduled_by.clone(),
+                });
+            }
+
+            let scheduled = ScheduledOverlay {
+                id: uuid::Uuid::new_v4().to_string(),
+                overlay,
+                starts_at,
+                ends_at,
+                scheduled_by: scheduled_by.to_string(),
+                scheduled_at: now,
+            };
+            state.overlays.push(scheduled.clone());
+            self.persist(&state.overlays)?;
+            scheduled
+        };
+
+        info!(
+            "🗓️ Overlay {} scheduled by {} for {} - {}",
+            scheduled.id, scheduled.scheduled_by, scheduled.starts_at, scheduled.ends_at
+        );
+        self.tick();
+        Ok(scheduled)
+    }
+
+    /// Cancel a scheduled or active overlay
+    pub fn cancel(&self, id: &str) -> Result<(), OverlayError> {
+        {
+            let mut state = self.state.lock().unwrap();
+            let before = state.overlays.len();
+            state.overlays.retain(|o| o.id != id);
+            if state.overlays.len() == before {
+                return Err(OverlayError::NotFound(id.to_string()));
+            }
+            self.persist(&state.overlays)?;
+        }
+
+        info!("🗓️ Overlay {} cancelled", id);
+        self.tick();
+        Ok(())
+    }
+
+    /// Active and upcoming overlays, ordered by start time
+    pub fn list(&self) -> Vec<ScheduledOverlay> {
+        let now = self.clock.now();
+        let mut overlays: Vec<ScheduledOverlay> = self.state.lock().unwrap()
+            .overlays
+            .iter()
+            .filter(|o| o.ends_at > now)
+            .cloned()
+            .collect();
+        overlays.sort_by_key(|o| o.starts_at);
+        overlays
+    }
+
+    /// The overlay currently applied to the live configuration
+    pub fn active(&self) -> Option<ScheduledOverlay> {
+        let state = self.state.lock().unwrap();
+        let applied = state.applied.as_ref()?;
+        state.overlays.iter().find(|o| &o.id == applied).cloned()
+    }
+
+    /// Apply or revert overlays whose boundaries have passed.
+    ///
+    /// Returns true when the live configuration changed.
+    pub fn tick(&self) -> bool {
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+
+        let due = state.overlays.iter().find(|o| o.is_active_at(now)).cloned();
+        let due_id = due.as_ref().map(|o| o.id.clone());
+
+        let expired = state.overlays.iter().filter(|o| o.ends_at <= now).count();
+        if expired > 0 {
+            state.overlays.retain(|o| o.ends_at > now);
+            if let Err(e) = self.persist(&state.overlays) {
+                warn!("⚠️ Failed to persist overlays: {}", e);
+            }
+        }
+
+        if due_id == state.applied {
+            return false;
+        }
+
+        match &due {
+            Some(overlay) => {
+                info!("🗓️ Applying overlay {} (until {})", overlay.id, overlay.ends_at);
+                self.live.replace(overlay.overlay.apply(&self.base.current()));
+            }
+            None => {
+                info!("🗓️ Reverting to base configuration");
+                self.live.replace(self.base.current().as_ref().clone());
+            }
+        }
+        state.applied = due_id;
+        true
+    }
+
+    /// The configuration overlays are layered over
+    pub fn base(&self) -> Arc<FortressConfig> {
+        self.base.current()
+    }
+
+    /// Replace the base configuration, republishing the live configuration
+    /// with the active overlay, if any, layered over the new base
+    pub fn set_base(&self, base: FortressConfig) {
+        let state = self.state.lock().unwrap();
+        let active = state.applied.as_ref().and_then(|id| state.overlays.iter().find(|o| &o.id == id));
+        let live = match active {
+            Some(overlay) => overlay.overlay.apply(&base),
+            None => base.clone(),
+        };
+        self.base.replace(base);
+        self.live.replace(live);
+    }
+
+    /// Check overlay boundaries every `interval` in the background
+    pub fn start(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                scheduler.tick();
+            }
+        })
+    }
+
+    fn persist(&self, overlays: &[ScheduledOverlay]) -> Result<(), OverlayError> {
+        let Some(path) = &self.store_path else {
+            return Ok(());
+        };
+        let content = serde_json::to_string_pretty(overlays).map_err(|e| OverlayError::Store(e.to_string()))?;
+        std::fs::write(path, content).map_err(|e| OverlayError::Store(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeClock(Mutex<DateTime<Utc>>);
+
+    impl FakeClock {
+        fn set(&self, now: DateTime<Utc>) {
+            *self.0.lock().unwrap() = now;
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> DateTime<Utc> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn t(minutes: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-01-02T14:00:00Z").unwrap().with_timezone(&Utc)
+            + chrono::Duration::minutes(minutes)
+    }
+
+    fn demo_overlay() -> ConfigOverlay {
+        ConfigOverlay {
+            rate_limit: Some(RateLimitConfig {
+                requests_per_minute: 5000,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn scheduler(clock: Arc<FakeClock>) -> (OverlayScheduler, SharedConfig) {
+        let base = FortressConfig::default();
+        let live = SharedConfig::new(base.clone());
+        (OverlayScheduler::new(base, live.clone()).with_clock(clock), live)
+    }
+
+    #[test]
+    fn test_overlay_applied_at_start_and_reverted_at_end() {
+        let clock = Arc::new(FakeClock(Mutex::new(t(-30))));
+        let (scheduler, live) = scheduler(clock.clone());
+
+        scheduler.schedule(demo_overlay(), t(0), t(120), "ops@example.com").unwrap();
+        assert_eq!(live.current().rate_limit.requests_per_minute, 1000);
+
+        clock.set(t(0));
+        assert!(scheduler.tick());
+        assert_eq!(live.current().rate_limit.requests_per_minute, 5000);
+        assert_eq!(scheduler.active().unwrap().scheduled_by, "ops@example.com");
+        assert!(!scheduler.tick());
+
+        clock.set(t(120));
+        assert!(scheduler.tick());
+        assert_eq!(live.current().rate_limit.requests_per_minute, 1000);
+        assert!(scheduler.active().is_none());
+        assert!(scheduler.list().is_empty());
+    }
+
+    #[test]
+    fn test_overlapping_overlay_rejected() {
+        let clock = Arc::new(FakeClock(Mutex::new(t(-30))));
+        let (scheduler, _) = scheduler(clock);
+
+        let first = scheduler.schedule(demo_overlay(), t(0), t(120), "alice").unwrap();
+        let err = scheduler.schedule(demo_overlay(), t(60), t(180), "bob").unwrap_err();
+        assert!(matches!(err, OverlayError::Overlaps { ref id, .. } if *id == first.id));
+
+        // Back-to-back windows do not overlap
+        scheduler.schedule(demo_overlay(), t(120), t(180), "bob").unwrap();
+        assert!(matches!(
+            scheduler.schedule(demo_overlay(), t(200), t(190), "bob"),
+            Err(OverlayError::InvalidWindow(_))
+        ));
+        assert_eq!(scheduler.list().len(), 2);
+    }
+
+    #[test]
+    fn test_missed_activation_applied_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("overlays.json");
+        let clock = Arc::new(FakeClock(Mutex::new(t(-30))));
+
+        let (scheduler, _) = scheduler(clock.clone());
+        let scheduler = scheduler.with_store(&path).unwrap();
+        scheduler.schedule(demo_overlay(), t(0), t(120), "alice").unwrap();
+        drop(scheduler);
+
+        // Restart inside the window: the overlay applies on load
+        clock.set(t(30));
+        let (restarted, live) = self::scheduler(clock);
+        let restarted = restarted.with_store(&path).unwrap();
+        assert_eq!(live.current().rate_limit.requests_per_minute, 5000);
+        assert_eq!(restarted.active().unwrap().scheduled_by, "alice");
+    }
+}
@@ -0,0 +1,103 @@
+//! Configuration Hot Reload
+//!
+//! `Fortress::reload_config` validates a new configuration and swaps it into
+//! the live configuration that the middleware stack and router read on every
+//! request, so new values apply from the next request while requests already
+//! in flight finish under the snapshot they started with. A [`ConfigWatcher`]
+//! does the same whenever the configuration file changes on disk.
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use notify::{RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::{
+    config::{load_config_file, RouteConfigError},
+    Fortress,
+};
+
+/// How long the watcher waits for a burst of file events to settle before reloading
+pub const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// A configuration that was not applied
+#[derive(Debug, thiserror::Error)]
+pub enum ReloadError {
+    #[error("invalid routing: {0}")]
+    InvalidRouting(#[from] RouteConfigError),
+    #[error("failed to load {path}: {message}")]
+    Load { path: String, message: String },
+    #[error("failed to watch configuration file: {0}")]
+    Watch(#[from] notify::Error),
+}
+
+/// Reloads the gateway configuration whenever its file changes; stops when dropped
+pub struct ConfigWatcher {
+    _watcher: notify::RecommendedWatcher,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl ConfigWatcher {
+    /// Watch `path`, reloading `fortress` from it after every change.
+    ///
+    /// The parent directory is watched rather than the file itself, so edits
+    /// that replace the file by renaming over it are picked up too. A file
+    /// that fails to parse or validate is logged and leaves the running
+    /// configuration untouched.
+    pub fn start(fortress: Fortress, path: impl Into<PathBuf>) -> Result<Self, ReloadError> {
+        let path = path.into();
+        let file_name = path.file_name().map(|name| name.to_os_string());
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let (changed_tx, mut changed_rx) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else { return };
+            let ours = event.paths.iter().any(|changed| changed.file_name().map(|n| n.to_os_string()) == file_name);
+            if ours && (event.kind.is_create() || event.kind.is_modify()) {
+                let _ = changed_tx.send(());
+            }
+        })?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        info!("👀 Watching {} for configuration changes", path.display());
+
+        let task = tokio::spawn(async move {
+            while changed_rx.recv().await.is_some() {
+                // Editors often write a file in several steps; reload once they are done
+                tokio::time::sleep(WATCH_DEBOUNCE).await;
+                while changed_rx.try_recv().is_ok() {}
+                if let Err(e) = reload_from_file(&fortress, &path) {
+                    warn!("⚠️ Keeping the running configuration: {}", e);
+                }
+            }
+        });
+
+        Ok(Self { _watcher: watcher, task })
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Load, validate and apply the configuration file at `path`
+pub fn reload_from_file(fortress: &Fortress, path: &Path) -> Result<(), ReloadError> {
+    let config = match load_config_file(path) {
+        Ok(config) => config,
+        Err(e) => {
+            fortress.metrics().record_config_reload(false);
+            return Err(ReloadError::Load {
+                path: path.display().to_string(),
+                message: e.to_string(),
+            });
+        }
+    };
+    fortress.reload_config(config)
+}
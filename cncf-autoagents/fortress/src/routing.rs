@@ -0,0 +1,401 @@
+//! Route matching and load balancing for the Fortress gateway
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use hyper::{HeaderMap, Method, Request};
+
+use crate::config::{LoadBalancingStrategy, Route, RouteTarget, RoutingConfig};
+pub use crate::middleware::circuit_breaker::{CircuitBreaker, CircuitState};
+
+/// Router for matching requests to configured routes
+#[derive(Debug, Clone)]
+pub struct Router {
+    config: RoutingConfig,
+}
+
+impl Router {
+    /// Create a new router
+    pub fn new(config: RoutingConfig) -> Self {
+        Self { config }
+    }
+
+    /// Find the matching route with the lowest priority; among equal
+    /// priorities the first configured wins
+    pub fn find_route(&self, path: &str, method: &Method, headers: &HeaderMap) -> Option<Route> {
+        Self::matching_route(&self.config, path, method, headers).cloned()
+    }
+
+    /// [`Router::find_route`] over borrowed configuration
+    fn matching_route<'a>(config: &'a RoutingConfig, path: &str, method: &Method, headers: &HeaderMap) -> Option<&'a Route> {
+        config
+            .routes
+            .iter()
+            .filter(|route| Self::matches_route(route, path, method, headers))
+            .min_by_key(|route| route.priority)
+    }
+
+    /// Check if a route matches the given path, method and headers.
+    ///
+    /// An empty method list accepts every method.
+    fn matches_route(route: &Route, path: &str, method: &Method, headers: &HeaderMap) -> bool {
+        if !route.methods.is_empty()
+            && !route.methods.iter().any(|m| m.eq_ignore_ascii_case(method.as_str()))
+        {
+            return false;
+        }
+        if !route
+            .match_headers
+            .iter()
+            .all(|(name, value)| headers.get(name.as_str()).is_some_and(|actual| actual == value.as_str()))
+        {
+            return false;
+        }
+
+        path_matches(&route.path, path)
+    }
+}
+
+/// Route a request matched, kept in its extensions so the layers in front of
+/// the gateway and the gateway itself match it only once
+#[derive(Debug, Clone)]
+pub struct ResolvedRoute(pub Option<Arc<Route>>);
+
+/// The route `req` matches in `config`, resolved by the first caller and
+/// reused by every later one
+pub fn resolve_route<B>(config: &RoutingConfig, req: &mut Request<B>) -> Option<Arc<Route>> {
+    if let Some(ResolvedRoute(route)) = req.extensions().get::<ResolvedRoute>() {
+        return route.clone();
+    }
+    let route = Router::matching_route(config, req.uri().path(), req.method(), req.headers())
+        .cloned()
+        .map(Arc::new);
+    req.extensions_mut().insert(ResolvedRoute(route.clone()));
+    route
+}
+
+/// Whether `path` matches a route pattern: patterns ending in `/*` match by
+/// prefix, anything else must match exactly
+pub fn path_matches(pattern: &str, path: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(prefix) => path == prefix || path.starts_with(&format!("{}/", prefix)),
+        None => path == pattern,
+    }
+}
+
+/// Picks one of a route's targets per request.
+///
+/// Round-robin is the smooth weighted variant, so a 3:1 split interleaves
+/// instead of sending bursts. Random selection draws from a seeded generator,
+/// making the sequence reproducible in tests.
+#[derive(Debug, Clone)]
+pub struct LoadBalancer {
+    state: Arc<Mutex<BalancerState>>,
+    connections: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+#[derive(Debug)]
+struct BalancerState {
+    rng: u64,
+    /// Smooth round-robin running weights per route
+    current_weights: HashMap<String, Vec<i64>>,
+}
+
+impl LoadBalancer {
+    pub fn new() -> Self {
+        Self::with_seed(uuid::Uuid::new_v4().as_u64_pair().0)
+    }
+
+    /// Balancer whose random choices are determined by `seed`
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(BalancerState {
+                rng: seed,
+                current_weights: HashMap::new(),
+            })),
+            connections: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Choose an upstream for `route`; the returned selection counts as an
+    /// in-flight connection until it is dropped
+    pub fn select(&self, route: &Route, strategy: &LoadBalancingStrategy) -> UpstreamSelection {
+        let mut targets = route.targets();
+        if let LoadBalancingStrategy::Weighted { weights } = strategy {
+            for target in &mut targets {
+                if let Some(weight) = weights.get(&target.url) {
+                    target.weight = *weight;
+                }
+            }
+        }
+        // A route with every target drained still has to go somewhere
+        if targets.iter().all(|target| target.weight == 0) {
+            targets.iter_mut().for_each(|target| target.weight = 1);
+        }
+
+        let index = match strategy {
+            LoadBalancingStrategy::RoundRobin | LoadBalancingStrategy::Weighted { .. } => {
+                self.next_round_robin(route.label(), &targets)
+            }
+            LoadBalancingStrategy::LeastConnections => self.least_connections(&targets),
+            LoadBalancingStrategy::Random => self.next_random(&targets),
+        };
+
+        let url = targets.swap_remove(index).url;
+        *self.connections.lock().unwrap().entry(url.clone()).or_default() += 1;
+        UpstreamSelection {
+            url,
+            connections: self.connections.clone(),
+        }
+    }
+
+    /// In-flight requests to `url` through this balancer
+    pub fn connections(&self, url: &str) -> usize {
+        self.connections.lock().unwrap().get(url).copied().unwrap_or(0)
+    }
+
+    fn next_round_robin(&self, route_label: &str, targets: &[RouteTarget]) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let current = state.current_weights.entry(route_label.to_string()).or_default();
+        if current.len() != targets.len() {
+            *current = vec![0; targets.len()];
+        }
+
+        let total: i64 = targets.iter().map(|target| i64::from(target.weight)).sum();
+        for (weight, target) in current.iter_mut().zip(targets) {
+            *weight += i64::from(target.weight);
+        }
+        let index = (0..targets.len()).max_by_key(|&i| (current[i], std::cmp::Reverse(i))).unwrap_or(0);
+        current[index] -= total;
+        index
+    }
+
+    fn least_connections(&self, targets: &[RouteTarget]) -> usize {
+        let connections = self.connections.lock().unwrap();
+        let load = |target: &RouteTarget| connections.get(&target.url).copied().unwrap_or(0) as u64;
+        (0..targets.len())
+            .filter(|&i| targets[i].weight > 0)
+            .min_by(|&a, &b| {
+                // Compare connections per unit of weight without dividing
+                let (a, b) = (&targets[a], &targets[b]);
+                (load(a) * u64::from(b.weight)).cmp(&(load(b) * u64::from(a.weight)))
+            })
+            .unwrap_or(0)
+    }
+
+    fn next_random(&self, targets: &[RouteTarget]) -> usize {
+        let total: u64 = targets.iter().map(|target| u64::from(target.weight)).sum();
+        let mut pick = splitmix64(&mut self.state.lock().unwrap().rng) % total;
+        for (index, target) in targets.iter().enumerate() {
+            match pick.checked_sub(u64::from(target.weight)) {
+                Some(rest) => pick = rest,
+                None => return index,
+            }
+        }
+        targets.len() - 1
+    }
+}
+
+impl Default for LoadBalancer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Upstream chosen for one request
+#[derive(Debug)]
+pub struct UpstreamSelection {
+    url: String,
+    connections: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl UpstreamSelection {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Drop for UpstreamSelection {
+    fn drop(&mut self) {
+        if let Some(count) = self.connections.lock().unwrap().get_mut(&self.url) {
+            *count = count.saturating_sub(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(path: &str, methods: &[&str]) -> Route {
+        Route {
+            name: None,
+            path: path.to_string(),
+            upstream: "http://upstream:8080/*".to_string(),
+            methods: methods.iter().map(|m| m.to_string()).collect(),
+            headers: HashMap::new(),
+            timeout_ms: None,
+            targets: vec![],
+            priority: 0,
+            match_headers: HashMap::new(),
+            cache: None,
+            retry: None,
+            rate_limit: None,
+        }
+    }
+
+    #[test]
+    fn test_route_matching() {
+        let router = Router::new(RoutingConfig {
+            routes: vec![route("/api/v1/agents/*", &["GET"]), route("/status", &[])],
+            ..Default::default()
+        });
+
+        assert!(router.find_route("/api/v1/agents/abc", &Method::GET, &HeaderMap::new()).is_some());
+        assert!(router.find_route("/api/v1/agents/abc", &Method::POST, &HeaderMap::new()).is_none());
+        assert!(router.find_route("/api/v1/agentsX", &Method::GET, &HeaderMap::new()).is_none());
+        assert!(router.find_route("/status", &Method::DELETE, &HeaderMap::new()).is_some());
+        assert!(router.find_route("/status/x", &Method::GET, &HeaderMap::new()).is_none());
+    }
+
+    #[test]
+    fn test_route_resolved_once_per_request() {
+        let config = RoutingConfig {
+            routes: vec![route("/api/*", &[])],
+            ..Default::default()
+        };
+        let mut req = Request::get("/api/agents").body(()).unwrap();
+        assert_eq!(resolve_route(&config, &mut req).unwrap().path, "/api/*");
+
+        // Later layers reuse the first resolution instead of matching again
+        let reloaded = RoutingConfig::default();
+        assert_eq!(resolve_route(&reloaded, &mut req).unwrap().path, "/api/*");
+
+        let mut unmatched = Request::get("/other").body(()).unwrap();
+        assert!(resolve_route(&config, &mut unmatched).is_none());
+        assert!(unmatched.extensions().get::<ResolvedRoute>().is_some_and(|resolved| resolved.0.is_none()));
+    }
+
+    #[test]
+    fn test_prefix_match_precedence() {
+        let catch_all = Route { priority: 100, ..route("/api/*", &[]) };
+        let forge = Route { priority: 10, ..route("/api/forge/*", &[]) };
+        let mcp = Route { priority: 10, ..route("/api/mcp/*", &[]) };
+        let exact = Route { priority: 1, ..route("/api/forge/health", &["GET"]) };
+        let mut beta = Route { priority: 5, name: Some("forge-beta".to_string()), ..route("/api/forge/*", &[]) };
+        beta.match_headers.insert("x-beta".to_string(), "1".to_string());
+        // Listed broadest first: order in the file does not decide, priority does
+        let config = RoutingConfig {
+            routes: vec![catch_all, forge, mcp, exact, beta],
+            ..Default::default()
+        };
+        config.validate().unwrap();
+        let router = Router::new(config);
+        let label = |path: &str, method: Method, headers: &HeaderMap| {
+            router.find_route(path, &method, headers).map(|route| route.label().to_string())
+        };
+        let none = HeaderMap::new();
+        let mut beta_headers = HeaderMap::new();
+        beta_headers.insert("x-beta", "1".parse().unwrap());
+
+        assert_eq!(label("/api/forge/agents", Method::GET, &none).as_deref(), Some("/api/forge/*"));
+        assert_eq!(label("/api/mcp/tools", Method::GET, &none).as_deref(), Some("/api/mcp/*"));
+        assert_eq!(label("/api/other", Method::GET, &none).as_deref(), Some("/api/*"));
+        assert_eq!(label("/api/forge/health", Method::GET, &beta_headers).as_deref(), Some("/api/forge/health"));
+        assert_eq!(label("/api/forge/health", Method::POST, &beta_headers).as_deref(), Some("forge-beta"));
+        assert_eq!(label("/api/forge/agents", Method::GET, &beta_headers).as_deref(), Some("forge-beta"));
+        beta_headers.insert("x-beta", "0".parse().unwrap());
+        assert_eq!(label("/api/forge/agents", Method::GET, &beta_headers).as_deref(), Some("/api/forge/*"));
+        assert_eq!(label("/apiX", Method::GET, &none), None);
+    }
+
+    fn split_route(weights: &[(&str, u32)]) -> Route {
+        Route {
+            targets: weights
+                .iter()
+                .map(|(url, weight)| RouteTarget {
+                    url: url.to_string(),
+                    weight: *weight,
+                })
+                .collect(),
+            ..route("/forge/*", &[])
+        }
+    }
+
+    fn picks(balancer: &LoadBalancer, route: &Route, strategy: &LoadBalancingStrategy, n: usize) -> Vec<String> {
+        (0..n).map(|_| balancer.select(route, strategy).url().to_string()).collect()
+    }
+
+    #[test]
+    fn test_weighted_round_robin() {
+        let split = split_route(&[("http://old", 3), ("http://new", 1)]);
+        let balancer = LoadBalancer::with_seed(7);
+
+        let sequence = picks(&balancer, &split, &LoadBalancingStrategy::RoundRobin, 8);
+        assert_eq!(
+            sequence,
+            ["http://old", "http://old", "http://new", "http://old"].repeat(2)
+        );
+
+        // Strategy weights override the route's, and a weight of 0 drains a target
+        let strategy = LoadBalancingStrategy::Weighted {
+            weights: HashMap::from([("http://old".to_string(), 0)]),
+        };
+        assert!(picks(&balancer, &split, &strategy, 4).iter().all(|url| url == "http://new"));
+
+        // Routes without targets use their single upstream
+        let single = route("/status", &[]);
+        assert_eq!(balancer.select(&single, &LoadBalancingStrategy::RoundRobin).url(), single.upstream);
+    }
+
+    #[test]
+    fn test_random_selection_is_seeded() {
+        let route = split_route(&[("http://old", 1), ("http://new", 1)]);
+        let first = picks(&LoadBalancer::with_seed(42), &route, &LoadBalancingStrategy::Random, 64);
+        let second = picks(&LoadBalancer::with_seed(42), &route, &LoadBalancingStrategy::Random, 64);
+        assert_eq!(first, second);
+        assert!(first.iter().any(|url| url == "http://old") && first.iter().any(|url| url == "http://new"));
+    }
+
+    #[test]
+    fn test_weighted_selection_distribution() {
+        let split = split_route(&[("http://forge", 90), ("http://canary", 10)]);
+        let canary_share = |strategy: &LoadBalancingStrategy| {
+            let picks = picks(&LoadBalancer::with_seed(1234), &split, strategy, 10_000);
+            picks.iter().filter(|url| *url == "http://canary").count() as f64 / picks.len() as f64
+        };
+
+        // Round-robin hits the split exactly; random stays close to it
+        assert_eq!(canary_share(&LoadBalancingStrategy::RoundRobin), 0.1);
+        let random = canary_share(&LoadBalancingStrategy::Random);
+        assert!((0.09..=0.11).contains(&random), "canary share {}", random);
+    }
+
+    #[test]
+    fn test_least_connections() {
+        let route = split_route(&[("http://a", 1), ("http://b", 1)]);
+        let balancer = LoadBalancer::with_seed(0);
+        let strategy = LoadBalancingStrategy::LeastConnections;
+
+        let first = balancer.select(&route, &strategy);
+        let second = balancer.select(&route, &strategy);
+        assert_ne!(first.url(), second.url());
+        assert_eq!(balancer.connections(first.url()), 1);
+
+        // Once a request completes its upstream is the least loaded again
+        let freed = first.url().to_string();
+        drop(first);
+        assert_eq!(balancer.connections(&freed), 0);
+        assert_eq!(balancer.select(&route, &strategy).url(), freed);
+    }
+}
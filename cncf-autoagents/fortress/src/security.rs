@@ -0,0 +1,64 @@
+//! Request security checks for the Fortress gateway
+
+use std::net::IpAddr;
+
+use regex::Regex;
+
+use crate::config::SecurityConfig;
+
+/// Reason a request was rejected by the security filter
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SecurityViolation {
+    #[error("Blocked client address: {0}")]
+    BlockedIp(String),
+    #[error("Blocked path pattern: {0}")]
+    BlockedPath(String),
+    #[error("Suspicious request pattern: {0}")]
+    SuspiciousPattern(String),
+}
+
+/// Compiled security rules from `SecurityConfig`
+#[derive(Debug, Clone)]
+pub struct SecurityFilter {
+    blocked_ips: Vec<String>,
+    blocked_paths: Vec<Regex>,
+    suspicious_patterns: Vec<Regex>,
+}
+
+impl SecurityFilter {
+    /// Compile the configured rules; invalid patterns are an error
+    pub fn new(config: &SecurityConfig) -> Result<Self, regex::Error> {
+        let compile = |patterns: &[String]| -> Result<Vec<Regex>, regex::Error> {
+            patterns
+                .iter()
+                .map(|p| Regex::new(&format!("(?i){}", p)))
+                .collect()
+        };
+
+        Ok(Self {
+            blocked_ips: config.blocked_ips.clone(),
+            blocked_paths: compile(&config.blocked_paths)?,
+            suspicious_patterns: compile(&config.suspicious_patterns)?,
+        })
+    }
+
+    /// Check a request's client address and path (including query string)
+    pub fn check(&self, client_ip: Option<IpAddr>, path_and_query: &str) -> Result<(), SecurityViolation> {
+        if let Some(ip) = client_ip {
+            let ip = ip.to_string();
+            if self.blocked_ips.contains(&ip) {
+                return Err(SecurityViolation::BlockedIp(ip));
+            }
+        }
+
+        if let Some(pattern) = self.blocked_paths.iter().find(|p| p.is_match(path_and_query)) {
+            return Err(SecurityViolation::BlockedPath(pattern.to_string()));
+        }
+
+        if let Some(pattern) = self.suspicious_patterns.iter().find(|p| p.is_match(path_and_query)) {
+            return Err(SecurityViolation::SuspiciousPattern(pattern.to_string()));
+        }
+
+        Ok(())
+    }
+}
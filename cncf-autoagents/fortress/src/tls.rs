@@ -0,0 +1,141 @@
+//! TLS Termination
+//!
+//! When `security.tls` is set, the gateway listener wraps every accepted
+//! connection in a rustls handshake before handing it to hyper. With a client
+//! CA configured the listener does mutual TLS: certificates are verified
+//! against that CA, and the verified identity (common name and subject
+//! alternative names) is attached to each request as a [`ClientIdentity`]
+//! extension for the middleware stack. TLS settings are read once at startup.
+
+use std::{
+    fs::File,
+    io::BufReader,
+    net::IpAddr,
+    sync::Arc,
+};
+
+use rustls::{
+    server::{AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient},
+    Certificate, PrivateKey, RootCertStore, ServerConfig,
+};
+use tokio_rustls::TlsAcceptor;
+use x509_parser::{extensions::GeneralName, prelude::FromDer, certificate::X509Certificate};
+
+use crate::config::{ClientAuthMode, TlsConfig};
+
+/// TLS settings that could not be turned into a server configuration
+#[derive(Debug, thiserror::Error)]
+pub enum TlsError {
+    #[error("failed to read {path}: {source}")]
+    Read { path: String, source: std::io::Error },
+    #[error("no certificates found in {0}")]
+    NoCertificates(String),
+    #[error("no private key found in {0}")]
+    NoPrivateKey(String),
+    #[error("invalid client CA certificate: {0}")]
+    InvalidClientCa(String),
+    #[error("invalid TLS configuration: {0}")]
+    Rustls(#[from] rustls::Error),
+}
+
+/// Verified identity of an mTLS client, attached to its requests
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientIdentity {
+    /// Common name of the certificate subject
+    pub common_name: Option<String>,
+    /// DNS names, emails, URIs and IP addresses from the certificate's SAN extension
+    pub subject_alt_names: Vec<String>,
+}
+
+impl ClientIdentity {
+    /// Identity of the leaf certificate `cert`, or `None` if it does not parse
+    pub fn from_certificate(cert: &Certificate) -> Option<Self> {
+        let (_, parsed) = X509Certificate::from_der(&cert.0).ok()?;
+        let common_name = parsed
+            .subject()
+            .iter_common_name()
+            .next()
+            .and_then(|cn| cn.as_str().ok())
+            .map(str::to_string);
+
+        let subject_alt_names = match parsed.subject_alternative_name() {
+            Ok(Some(san)) => san
+                .value
+                .general_names
+                .iter()
+                .filter_map(|name| match name {
+                    GeneralName::DNSName(dns) => Some(dns.to_string()),
+                    GeneralName::RFC822Name(email) => Some(email.to_string()),
+                    GeneralName::URI(uri) => Some(uri.to_string()),
+                    GeneralName::IPAddress(bytes) => ip_from_bytes(bytes).map(|ip| ip.to_string()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Some(Self { common_name, subject_alt_names })
+    }
+}
+
+fn ip_from_bytes(bytes: &[u8]) -> Option<IpAddr> {
+    match bytes.len() {
+        4 => Some(IpAddr::from(<[u8; 4]>::try_from(bytes).ok()?)),
+        16 => Some(IpAddr::from(<[u8; 16]>::try_from(bytes).ok()?)),
+        _ => None,
+    }
+}
+
+/// Build the acceptor for `config`, loading its certificate, key and client CA
+pub fn acceptor(config: &TlsConfig) -> Result<TlsAcceptor, TlsError> {
+    let certs = load_certs(&config.cert_path)?;
+    let key = load_private_key(&config.key_path)?;
+
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = match &config.client_ca_path {
+        Some(ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for ca in load_certs(ca_path)? {
+                roots.add(&ca).map_err(|e| TlsError::InvalidClientCa(e.to_string()))?;
+            }
+            let verifier = match config.client_auth {
+                ClientAuthMode::Require => AllowAnyAuthenticatedClient::new(roots).boxed(),
+                ClientAuthMode::Verify => AllowAnyAnonymousOrAuthenticatedClient::new(roots).boxed(),
+            };
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut server_config = builder.with_single_cert(certs, key)?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+fn open(path: &str) -> Result<BufReader<File>, TlsError> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|source| TlsError::Read { path: path.to_string(), source })
+}
+
+fn load_certs(path: &str) -> Result<Vec<Certificate>, TlsError> {
+    let certs = rustls_pemfile::certs(&mut open(path)?)
+        .map_err(|source| TlsError::Read { path: path.to_string(), source })?;
+    if certs.is_empty() {
+        return Err(TlsError::NoCertificates(path.to_string()));
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn load_private_key(path: &str) -> Result<PrivateKey, TlsError> {
+    let mut reader = open(path)?;
+    loop {
+        match rustls_pemfile::read_one(&mut reader).map_err(|source| TlsError::Read { path: path.to_string(), source })? {
+            Some(rustls_pemfile::Item::PKCS8Key(key))
+            | Some(rustls_pemfile::Item::RSAKey(key))
+            | Some(rustls_pemfile::Item::ECKey(key)) => return Ok(PrivateKey(key)),
+            Some(_) => continue,
+            None => return Err(TlsError::NoPrivateKey(path.to_string())),
+        }
+    }
+}
@@ -0,0 +1,369 @@
+//! Per-principal Usage Accounting
+//!
+//! Requests are folded into hourly rollups keyed by principal and route as
+//! they complete; no raw samples are kept, and rollups older than the
+//! retention window are dropped. Route keys are configured route patterns,
+//! so memory grows with principals x routes x retained hours only.
+//!
+//! Once a UTC day has passed, a summary per principal is broadcast to
+//! subscribers and, if configured, posted to the summary webhook.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, DurationRound, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+use crate::{
+    config::UsageConfig,
+    overlays::{Clock, SystemClock},
+};
+
+/// Route recorded for requests answered before routing (throttled, admin, not found)
+pub const UNROUTED: &str = "-";
+
+/// Principal recorded for requests without an authenticated user
+pub const ANONYMOUS: &str = "anonymous";
+
+/// Response extension naming the route pattern that served a request
+#[derive(Debug, Clone)]
+pub struct MatchedRoute(pub String);
+
+/// Response extension naming the upstream a request was sent to
+#[derive(Debug, Clone)]
+pub struct MatchedUpstream(pub String);
+
+/// Response extension with the time spent waiting on the upstream
+#[derive(Debug, Clone, Copy)]
+pub struct UpstreamTime(pub Duration);
+
+/// One completed request, as seen by the usage middleware
+#[derive(Debug, Clone)]
+pub struct UsageRecord {
+    pub principal: String,
+    pub route: Option<String>,
+    pub status: u16,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub upstream_time: Option<Duration>,
+}
+
+/// Usage of one principal on one route during one hour
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageRollup {
+    pub hour: DateTime<Utc>,
+    pub principal: String,
+    pub route: String,
+    pub requests: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub upstream_time_ms: u64,
+    /// Refused by the gateway without reaching an upstream
+    pub rejected: u64,
+    /// Refused by the rate limiter
+    pub throttled: u64,
+}
+
+/// A principal's usage over one UTC day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyUsageSummary {
+    pub principal: String,
+    pub day: NaiveDate,
+    pub requests_by_route: BTreeMap<String, u64>,
+    pub requests: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub upstream_time_ms: u64,
+    pub rejected: u64,
+    pub throttled: u64,
+}
+
+type RollupKey = (DateTime<Utc>, String, String);
+
+#[derive(Debug, Default)]
+struct UsageState {
+    rollups: BTreeMap<RollupKey, UsageRollup>,
+    /// Hour at which old rollups were last dropped
+    pruned_at: Option<DateTime<Utc>>,
+    /// Day whose summaries are emitted next
+    summary_day: Option<NaiveDate>,
+}
+
+/// Aggregates usage per principal into hourly rollups
+#[derive(Clone)]
+pub struct UsageTracker {
+    retention: chrono::Duration,
+    clock: Arc<dyn Clock>,
+    state: Arc<Mutex<UsageState>>,
+    summaries: broadcast::Sender<DailyUsageSummary>,
+}
+
+impl UsageTracker {
+    pub fn new(config: &UsageConfig) -> Self {
+        let (summaries, _) = broadcast::channel(256);
+        Self {
+            retention: chrono::Duration::hours(i64::from(config.retention_hours)),
+            clock: Arc::new(SystemClock),
+            state: Arc::new(Mutex::new(UsageState::default())),
+            summaries,
+        }
+    }
+
+    /// Use a custom clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Receive daily usage summaries as they are emitted
+    pub fn subscribe(&self) -> broadcast::Receiver<DailyUsageSummary> {
+        self.summaries.subscribe()
+    }
+
+    /// Fold a completed request into the current hour's rollup
+    pub fn record(&self, record: UsageRecord) {
+        let hour = current_hour(self.clock.now());
+        let route = record.route.unwrap_or_else(|| UNROUTED.to_string());
+
+        let mut state = self.state.lock().unwrap();
+        if state.pruned_at != Some(hour) {
+            let cutoff = hour - self.retention;
+            state.rollups.retain(|(rollup_hour, _, _), _| *rollup_hour > cutoff);
+            state.pruned_at = Some(hour);
+        }
+
+        let rollup = state
+            .rollups
+            .entry((hour, record.principal.clone(), route.clone()))
+            .or_insert_with(|| UsageRollup {
+                hour,
+                principal: record.principal,
+                route,
+                ..Default::default()
+            });
+        rollup.requests += 1;
+        rollup.bytes_in += record.bytes_in;
+        rollup.bytes_out += record.bytes_out;
+        if let Some(upstream_time) = record.upstream_time {
+            rollup.upstream_time_ms += upstream_time.as_millis() as u64;
+        }
+        if record.status == 429 {
+            rollup.throttled += 1;
+        } else if record.status >= 400 && record.upstream_time.is_none() {
+            rollup.rejected += 1;
+        }
+    }
+
+    /// Rollups for hours in `[from, to)`, optionally for a single principal
+    pub fn report(&self, principal: Option<&str>, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<UsageRollup> {
+        let from = current_hour(from);
+        self.state
+            .lock()
+            .unwrap()
+            .rollups
+            .values()
+            .filter(|rollup| rollup.hour >= from && rollup.hour < to)
+            .filter(|rollup| principal.is_none_or(|principal| rollup.principal == principal))
+            .cloned()
+            .collect()
+    }
+
+    /// Default report window: everything still retained
+    pub fn retained_window(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let now = self.clock.now();
+        (now - self.retention, now)
+    }
+
+    /// Per-principal totals for one UTC day
+    pub fn daily_summaries(&self, day: NaiveDate) -> Vec<DailyUsageSummary> {
+        let state = self.state.lock().unwrap();
+        let mut summaries: BTreeMap<&str, DailyUsageSummary> = BTreeMap::new();
+        for rollup in state.rollups.values().filter(|rollup| rollup.hour.date_naive() == day) {
+            let summary = summaries
+                .entry(rollup.principal.as_str())
+                .or_insert_with(|| DailyUsageSummary {
+                    principal: rollup.principal.clone(),
+                    day,
+                    requests_by_route: BTreeMap::new(),
+                    requests: 0,
+                    bytes_in: 0,
+                    bytes_out: 0,
+                    upstream_time_ms: 0,
+                    rejected: 0,
+                    throttled: 0,
+                });
+            *summary.requests_by_route.entry(rollup.route.clone()).or_default() += rollup.requests;
+            summary.requests += rollup.requests;
+            summary.bytes_in += rollup.bytes_in;
+            summary.bytes_out += rollup.bytes_out;
+            summary.upstream_time_ms += rollup.upstream_time_ms;
+            summary.rejected += rollup.rejected;
+            summary.throttled += rollup.throttled;
+        }
+        summaries.into_values().collect()
+    }
+
+    /// Summaries for days completed since the last call; the first call only starts tracking
+    pub fn due_summaries(&self) -> Vec<DailyUsageSummary> {
+        let today = self.clock.now().date_naive();
+        let first_due = {
+            let mut state = self.state.lock().unwrap();
+            match state.summary_day.replace(today) {
+                Some(day) if day < today => day,
+                _ => return Vec::new(),
+            }
+        };
+
+        first_due
+            .iter_days()
+            .take_while(|day| *day < today)
+            .flat_map(|day| self.daily_summaries(day))
+            .collect()
+    }
+
+    /// Broadcast due summaries and post each to `webhook_url`, if set
+    pub async fn emit_due_summaries(&self, http_client: &reqwest::Client, webhook_url: Option<&str>) {
+        for summary in self.due_summaries() {
+            info!(
+                "🧾 Usage for {} on {}: {} requests, {} throttled",
+                summary.principal, summary.day, summary.requests, summary.throttled
+            );
+            let _ = self.summaries.send(summary.clone());
+
+            let Some(url) = webhook_url else { continue };
+            let delivery = http_client.post(url).json(&summary).send().await;
+            match delivery.and_then(|response| response.error_for_status()) {
+                Ok(_) => {}
+                Err(e) => warn!("⚠️ Failed to deliver usage summary for {}: {}", summary.principal, e),
+            }
+        }
+    }
+
+    /// Check for completed days every `interval` and emit their summaries
+    pub fn start(&self, interval: Duration, webhook_url: Option<String>) -> tokio::task::JoinHandle<()> {
+        let tracker = self.clone();
+        tokio::spawn(async move {
+            let http_client = reqwest::Client::new();
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                tracker.emit_due_summaries(&http_client, webhook_url.as_deref()).await;
+            }
+        })
+    }
+}
+
+fn current_hour(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.duration_trunc(chrono::Duration::hours(1)).unwrap_or(time)
+}
+
+/// Render rollups as CSV with a header row
+pub fn to_csv(rollups: &[UsageRollup]) -> String {
+    let mut csv = String::from("hour,principal,route,requests,bytes_in,bytes_out,upstream_time_ms,rejected,throttled\n");
+    for rollup in rollups {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            rollup.hour.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            csv_field(&rollup.principal),
+            csv_field(&rollup.route),
+            rollup.requests,
+            rollup.bytes_in,
+            rollup.bytes_out,
+            rollup.upstream_time_ms,
+            rollup.rejected,
+            rollup.throttled,
+        ));
+    }
+    csv
+}
+
+/// Quote a field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    struct FakeClock(Mutex<DateTime<Utc>>);
+
+    impl Clock for FakeClock {
+        fn now(&self) -> DateTime<Utc> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn record(principal: &str, route: Option<&str>, status: u16, upstream_ms: Option<u64>) -> UsageRecord {
+        UsageRecord {
+            principal: principal.to_string(),
+            route: route.map(str::to_string),
+            status,
+            bytes_in: 100,
+            bytes_out: 1000,
+            upstream_time: upstream_ms.map(Duration::from_millis),
+        }
+    }
+
+    #[test]
+    fn test_rollups_retention_and_summaries() {
+        let start = Utc.with_ymd_and_hms(2026, 3, 1, 23, 10, 0).unwrap();
+        let clock = Arc::new(FakeClock(Mutex::new(start)));
+        let tracker = UsageTracker::new(&UsageConfig {
+            retention_hours: 2,
+            ..Default::default()
+        })
+        .with_clock(clock.clone());
+        assert!(tracker.due_summaries().is_empty());
+
+        tracker.record(record("alice", Some("/api/*"), 200, Some(40)));
+        tracker.record(record("alice", Some("/api/*"), 502, Some(10)));
+        tracker.record(record("alice", None, 429, None));
+        tracker.record(record("bob", Some("/api/*"), 503, None));
+
+        // Next hour is a new day: yesterday's summaries become due
+        *clock.0.lock().unwrap() = start + chrono::Duration::hours(1);
+        tracker.record(record("bob", Some("/api/*"), 200, Some(5)));
+
+        let summaries = tracker.due_summaries();
+        assert_eq!(summaries.len(), 2);
+        let alice = &summaries[0];
+        assert_eq!((alice.principal.as_str(), alice.requests, alice.upstream_time_ms), ("alice", 3, 50));
+        assert_eq!((alice.throttled, alice.rejected), (1, 0));
+        assert_eq!(alice.requests_by_route, BTreeMap::from([("-".to_string(), 1), ("/api/*".to_string(), 2)]));
+        assert_eq!((summaries[1].requests, summaries[1].rejected), (1, 1));
+        assert!(tracker.due_summaries().is_empty());
+
+        // Rollups beyond the retention window are dropped
+        *clock.0.lock().unwrap() = start + chrono::Duration::hours(3);
+        tracker.record(record("bob", Some("/api/*"), 200, Some(5)));
+        let all = tracker.report(None, start - chrono::Duration::days(1), start + chrono::Duration::days(1));
+        assert!(all.iter().all(|rollup| rollup.hour > start));
+    }
+
+    #[test]
+    fn test_csv_quotes_fields() {
+        let rollup = UsageRollup {
+            hour: Utc.with_ymd_and_hms(2026, 3, 1, 9, 0, 0).unwrap(),
+            principal: "team \"a\", ops".to_string(),
+            route: "/api/*".to_string(),
+            requests: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            to_csv(&[rollup]),
+            "hour,principal,route,requests,bytes_in,bytes_out,upstream_time_ms,rejected,throttled\n\
+             2026-03-01T09:00:00Z,\"team \"\"a\"\", ops\",/api/*,2,0,0,0,0,0\n"
+        );
+    }
+}
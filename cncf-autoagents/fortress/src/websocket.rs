@@ -0,0 +1,253 @@
+//! WebSocket pass-through
+//!
+//! An upgrade request is routed, authenticated and circuit-checked like any
+//! other request. The gateway then opens its own WebSocket connection to the
+//! upstream, answers the client with 101 once that has succeeded, and pumps
+//! frames between the two until either side closes. A close frame from one
+//! side is forwarded to the other, and the connection is torn down once that
+//! side has answered it. Connections with no frames in either direction for
+//! `idle_timeout_ms` are closed with 1001. Frames or messages over the
+//! configured limits close the connection with 1009.
+
+use std::time::Duration;
+
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use hyper::{
+    header::{self, HeaderName, HeaderValue},
+    http::{Method, StatusCode},
+    upgrade::Upgraded,
+    Body, Request, Response, Uri,
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_tungstenite::{
+    tungstenite::{
+        self,
+        client::IntoClientRequest,
+        handshake::derive_accept_key,
+        protocol::{frame::coding::CloseCode, CloseFrame, Role, WebSocketConfig as ProtocolConfig},
+        Message,
+    },
+    WebSocketStream,
+};
+use tracing::{debug, info, warn};
+
+use crate::{config::WebSocketConfig, metrics::MetricsCollector};
+
+/// Which way a frame travels through the gateway
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    ClientToUpstream,
+    UpstreamToClient,
+}
+
+impl Direction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::ClientToUpstream => "client_to_upstream",
+            Direction::UpstreamToClient => "upstream_to_client",
+        }
+    }
+}
+
+/// Handshake headers that belong to one hop and are never forwarded
+const HOP_HEADERS: [HeaderName; 8] = [
+    header::HOST,
+    header::CONNECTION,
+    header::UPGRADE,
+    header::SEC_WEBSOCKET_KEY,
+    header::SEC_WEBSOCKET_VERSION,
+    header::SEC_WEBSOCKET_EXTENSIONS,
+    header::CONTENT_LENGTH,
+    header::TRANSFER_ENCODING,
+];
+
+/// Whether a request asks to upgrade to WebSocket
+pub fn is_upgrade_request<B>(req: &Request<B>) -> bool {
+    let has_token = |name: HeaderName, token: &str| {
+        req.headers()
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|value| value.trim().eq_ignore_ascii_case(token))
+    };
+    req.method() == Method::GET && has_token(header::CONNECTION, "upgrade") && has_token(header::UPGRADE, "websocket")
+}
+
+/// Connect to the upstream for an upgrade request and, once connected,
+/// answer 101 and start pumping frames. Connection failures are errors;
+/// malformed handshakes are answered with 400.
+pub async fn proxy(
+    mut req: Request<Body>,
+    upstream_uri: Uri,
+    config: &WebSocketConfig,
+    metrics: &MetricsCollector,
+) -> Result<Response<Body>, Box<dyn std::error::Error>> {
+    let key = match req.headers().get(header::SEC_WEBSOCKET_KEY) {
+        Some(key) if req.headers().get(header::SEC_WEBSOCKET_VERSION).is_some_and(|v| v == "13") => key.clone(),
+        _ => return Ok(bad_handshake()),
+    };
+
+    let mut upstream_req = upstream_url(&upstream_uri)?.into_client_request()?;
+    for (name, value) in req.headers() {
+        if !HOP_HEADERS.contains(name) {
+            if let (Ok(name), Ok(value)) = (
+                tungstenite::http::HeaderName::from_bytes(name.as_ref()),
+                tungstenite::http::HeaderValue::from_bytes(value.as_bytes()),
+            ) {
+                upstream_req.headers_mut().append(name, value);
+            }
+        }
+    }
+
+    let protocol_config = protocol_config(config);
+    let (upstream, upstream_response) =
+        tokio_tungstenite::connect_async_with_config(upstream_req, Some(protocol_config), false).await?;
+
+    let mut response = Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header(header::CONNECTION, "upgrade")
+        .header(header::UPGRADE, "websocket")
+        .header(header::SEC_WEBSOCKET_ACCEPT, derive_accept_key(key.as_bytes()))
+        .body(Body::empty())?;
+    if let Some(protocol) = upstream_response.headers().get(header::SEC_WEBSOCKET_PROTOCOL.as_str()) {
+        response
+            .headers_mut()
+            .insert(header::SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_bytes(protocol.as_bytes())?);
+    }
+
+    let idle_timeout = Duration::from_millis(config.idle_timeout_ms);
+    let metrics = metrics.clone();
+    let target = upstream_uri.to_string();
+    tokio::spawn(async move {
+        let upgraded = match hyper::upgrade::on(&mut req).await {
+            Ok(upgraded) => upgraded,
+            Err(e) => {
+                warn!("🔌 WebSocket upgrade to {} failed: {}", target, e);
+                return;
+            }
+        };
+        let client = WebSocketStream::<Upgraded>::from_raw_socket(upgraded, Role::Server, Some(protocol_config)).await;
+        info!("🔌 WebSocket connection to {} opened", target);
+        metrics.record_websocket_opened();
+        pump(client, upstream, idle_timeout, &metrics).await;
+        metrics.record_websocket_closed();
+        info!("🔌 WebSocket connection to {} closed", target);
+    });
+
+    Ok(response)
+}
+
+type MessageSink<'a> = &'a mut (dyn Sink<Message, Error = tungstenite::Error> + Send + Unpin);
+
+type MessageStream<'a> = &'a mut (dyn Stream<Item = Result<Message, tungstenite::Error>> + Send + Unpin);
+
+/// Relay frames both ways until a close handshake completes, either side
+/// drops, or the connection idles out
+async fn pump<C, U>(client: WebSocketStream<C>, upstream: WebSocketStream<U>, idle_timeout: Duration, metrics: &MetricsCollector)
+where
+    C: AsyncRead + AsyncWrite + Send + Unpin,
+    U: AsyncRead + AsyncWrite + Send + Unpin,
+{
+    let (mut client_tx, mut client_rx) = client.split();
+    let (mut upstream_tx, mut upstream_rx) = upstream.split();
+
+    loop {
+        let next = tokio::time::timeout(idle_timeout, async {
+            tokio::select! {
+                message = client_rx.next() => (Direction::ClientToUpstream, message),
+                message = upstream_rx.next() => (Direction::UpstreamToClient, message),
+            }
+        })
+        .await;
+        let (direction, message) = match next {
+            Ok(next) => next,
+            Err(_) => {
+                debug!("🔌 WebSocket idle for {:?}, closing", idle_timeout);
+                let frame = close_frame(CloseCode::Away, "Idle timeout");
+                let _ = client_tx.send(Message::Close(Some(frame.clone()))).await;
+                let _ = upstream_tx.send(Message::Close(Some(frame))).await;
+                break;
+            }
+        };
+        let (tx, peer_rx): (MessageSink, MessageStream) = match direction {
+            Direction::ClientToUpstream => (&mut upstream_tx, &mut upstream_rx),
+            Direction::UpstreamToClient => (&mut client_tx, &mut client_rx),
+        };
+
+        match message {
+            Some(Ok(Message::Close(frame))) => {
+                // Pass the close on and wait for the peer to answer it; the
+                // closing side's own answer is sent by tungstenite
+                let _ = tx.send(Message::Close(frame)).await;
+                let answered = async {
+                    while let Some(Ok(message)) = peer_rx.next().await {
+                        if matches!(message, Message::Close(_)) {
+                            break;
+                        }
+                    }
+                };
+                let _ = tokio::time::timeout(idle_timeout, answered).await;
+                break;
+            }
+            Some(Ok(message)) => {
+                metrics.record_websocket_bytes(direction, message.len());
+                if tx.send(message).await.is_err() {
+                    break;
+                }
+            }
+            Some(Err(tungstenite::Error::Capacity(e))) => {
+                warn!("🔌 Closing WebSocket: {}", e);
+                let frame = close_frame(CloseCode::Size, "Frame too large");
+                let _ = client_tx.send(Message::Close(Some(frame.clone()))).await;
+                let _ = upstream_tx.send(Message::Close(Some(frame))).await;
+                break;
+            }
+            Some(Err(_)) | None => {
+                // One side went away without a close handshake; end the other
+                let frame = close_frame(CloseCode::Away, "Peer disconnected");
+                let _ = tx.send(Message::Close(Some(frame))).await;
+                break;
+            }
+        }
+    }
+
+    let _ = client_tx.close().await;
+    let _ = upstream_tx.close().await;
+}
+
+fn protocol_config(config: &WebSocketConfig) -> ProtocolConfig {
+    ProtocolConfig {
+        max_frame_size: Some(config.max_frame_bytes),
+        max_message_size: Some(config.max_message_bytes),
+        ..Default::default()
+    }
+}
+
+/// `ws://` or `wss://` URL of an upstream given as `http://` or `https://`
+fn upstream_url(uri: &Uri) -> Result<String, String> {
+    let url = uri.to_string();
+    if let Some(rest) = url.strip_prefix("http://") {
+        Ok(format!("ws://{}", rest))
+    } else if let Some(rest) = url.strip_prefix("https://") {
+        Ok(format!("wss://{}", rest))
+    } else if url.starts_with("ws://") || url.starts_with("wss://") {
+        Ok(url)
+    } else {
+        Err(format!("Unsupported WebSocket upstream: {}", url))
+    }
+}
+
+fn close_frame(code: CloseCode, reason: &'static str) -> CloseFrame<'static> {
+    CloseFrame { code, reason: reason.into() }
+}
+
+fn bad_handshake() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::BAD_REQUEST)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            serde_json::json!({ "error": "Invalid WebSocket handshake" }).to_string(),
+        ))
+        .unwrap()
+}
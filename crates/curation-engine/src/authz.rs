@@ -0,0 +1,322 @@
+//! Per-operation authorization
+//!
+//! `AuthMiddleware` verifies the bearer token and puts its claims into the
+//! request extensions as `GrantedScopes`; this module decides what the
+//! caller may do with them. Tokens carry scopes in a space-separated `scope`
+//! claim, and roles in a `roles` claim that grant a fixed set of scopes.
+//! Every route that changes state requires a scope through a
+//! `RequireScope<S>` extractor, which rejects callers without scope `S` with
+//! 403 and a body naming the scope. The tenant a request acts for comes from
+//! the token's `tenant` claim through the `Tenant` extractor.
+
+use std::marker::PhantomData;
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{error::ApiError, similarity::DEFAULT_TENANT};
+
+/// An operation a token may be allowed to perform
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    AgentsRead,
+    AgentsWrite,
+    WasmRead,
+    WasmWrite,
+    WasmExecute,
+    JobsWrite,
+    ToolsExecute,
+    TenantWrite,
+    SystemWrite,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 9] = [
+        Scope::AgentsRead,
+        Scope::AgentsWrite,
+        Scope::WasmRead,
+        Scope::WasmWrite,
+        Scope::WasmExecute,
+        Scope::JobsWrite,
+        Scope::ToolsExecute,
+        Scope::TenantWrite,
+        Scope::SystemWrite,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::AgentsRead => "agents:read",
+            Scope::AgentsWrite => "agents:write",
+            Scope::WasmRead => "wasm:read",
+            Scope::WasmWrite => "wasm:write",
+            Scope::WasmExecute => "wasm:execute",
+            Scope::JobsWrite => "jobs:write",
+            Scope::ToolsExecute => "tools:execute",
+            Scope::TenantWrite => "tenant:write",
+            Scope::SystemWrite => "system:write",
+        }
+    }
+
+    pub fn parse(scope: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|known| known.as_str() == scope)
+    }
+}
+
+/// Scopes granted by a role
+pub fn role_scopes(role: &str) -> &'static [Scope] {
+    match role {
+        "admin" => &Scope::ALL,
+        "operator" => &[Scope::AgentsRead, Scope::WasmRead, Scope::WasmExecute, Scope::JobsWrite, Scope::ToolsExecute],
+        "viewer" => &[Scope::AgentsRead, Scope::WasmRead],
+        _ => &[],
+    }
+}
+
+/// Claims of the bearer token that authorization looks at
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScopeClaims {
+    #[serde(default)]
+    pub sub: Option<String>,
+    /// Space-separated scopes
+    #[serde(default)]
+    pub scope: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// Tenant the token acts for; `DEFAULT_TENANT` when absent
+    #[serde(default)]
+    pub tenant: Option<String>,
+}
+
+/// Everything the caller's token allows
+#[derive(Debug, Clone, Default)]
+pub struct GrantedScopes {
+    pub subject: Option<String>,
+    pub tenant: String,
+    scopes: Vec<Scope>,
+}
+
+impl GrantedScopes {
+    /// Every scope, granted to all requests while auth is disabled
+    pub fn unrestricted() -> Self {
+        Self {
+            subject: None,
+            tenant: DEFAULT_TENANT.to_string(),
+            scopes: Scope::ALL.to_vec(),
+        }
+    }
+
+    pub fn from_claims(claims: ScopeClaims) -> Self {
+        let mut scopes: Vec<Scope> = claims.scope.split_whitespace().filter_map(Scope::parse).collect();
+        scopes.extend(claims.roles.iter().flat_map(|role| role_scopes(role)));
+        Self {
+            subject: claims.sub,
+            tenant: claims.tenant.unwrap_or_else(|| DEFAULT_TENANT.to_string()),
+            scopes,
+        }
+    }
+
+    pub fn contains(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+/// Extractor for the tenant of the authenticated caller; requests that did
+/// not pass `AuthMiddleware` are rejected with 401
+pub struct Tenant(pub String);
+
+#[async_trait]
+impl<T> FromRequestParts<T> for Tenant
+where
+    T: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &T) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<GrantedScopes>()
+            .map(|granted| Self(granted.tenant.clone()))
+            .ok_or_else(|| ApiError::Unauthorized("The request is not authenticated".to_string()))
+    }
+}
+
+/// A scope checked at compile time by `RequireScope`
+pub trait RequiredScope {
+    const SCOPE: Scope;
+}
+
+macro_rules! required_scope {
+    ($($name:ident),* $(,)?) => {
+        $(
+            #[doc = concat!("Requires `", stringify!($name), "`")]
+            pub struct $name;
+
+            impl RequiredScope for $name {
+                const SCOPE: Scope = Scope::$name;
+            }
+        )*
+    };
+}
+
+required_scope!(AgentsRead, AgentsWrite, WasmRead, WasmWrite, WasmExecute, JobsWrite, ToolsExecute, TenantWrite, SystemWrite);
+
+/// Extractor that only succeeds when the caller's token grants scope `S`
+pub struct RequireScope<S>(pub GrantedScopes, PhantomData<S>);
+
+/// Body of a 403 for a missing scope
+#[derive(Debug, Serialize)]
+pub struct MissingScope {
+    pub error: String,
+    pub required_scope: &'static str,
+}
+
+impl IntoResponse for MissingScope {
+    fn into_response(self) -> Response {
+        (StatusCode::FORBIDDEN, Json(self)).into_response()
+    }
+}
+
+#[async_trait]
+impl<S, T> FromRequestParts<T> for RequireScope<S>
+where
+    S: RequiredScope,
+    T: Send + Sync,
+{
+    type Rejection = MissingScope;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &T) -> Result<Self, Self::Rejection> {
+        // Absent when no verified token came with the request
+        let granted = parts.extensions.get::<GrantedScopes>().cloned().unwrap_or_default();
+        if !granted.contains(S::SCOPE) {
+            warn!(
+                "🔐 {} denied {} {}: missing scope {}",
+                granted.subject.as_deref().unwrap_or("anonymous"),
+                parts.method,
+                parts.uri.path(),
+                S::SCOPE.as_str()
+            );
+            return Err(MissingScope {
+                error: format!("Token lacks the `{}` scope required for this operation", S::SCOPE.as_str()),
+                required_scope: S::SCOPE.as_str(),
+            });
+        }
+        Ok(Self(granted, PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{bearer, bearer_for, serve_engine};
+    use crate::EngineBuilder;
+    use jsonwebtoken::{EncodingKey, Header};
+    use reqwest::Method;
+
+    /// Every route that changes state and the scope it requires; the config
+    /// update goes last as granting its scope really replaces the config
+    const MUTATING_ROUTES: &[(&str, &str, Scope)] = &[
+        ("POST", "/api/v1/agents", Scope::AgentsWrite),
+        ("PUT", "/api/v1/agents/a1", Scope::AgentsWrite),
+        ("DELETE", "/api/v1/agents/a1", Scope::AgentsWrite),
+        ("PUT", "/api/v1/agents/a1/canary", Scope::AgentsWrite),
+        ("POST", "/api/v1/agents/a1/canary/finalize", Scope::AgentsWrite),
+        ("POST", "/api/v1/agents/a1/drift/r1/review", Scope::AgentsWrite),
+        ("POST", "/api/v1/wasm/modules", Scope::WasmWrite),
+        ("POST", "/api/v1/wasm/modules/m1/execute", Scope::WasmExecute),
+        ("POST", "/api/v1/wasm/modules/m1/execute/stream", Scope::WasmExecute),
+        ("POST", "/api/v1/jobs", Scope::JobsWrite),
+        ("POST", "/api/v1/jobs/j1/cancel", Scope::JobsWrite),
+        ("POST", "/api/v1/mcp/tools/t1/execute", Scope::ToolsExecute),
+        ("POST", "/api/v1/webhooks", Scope::TenantWrite),
+        ("DELETE", "/api/v1/webhooks/w1", Scope::TenantWrite),
+        ("POST", "/api/v1/webhooks/w1/test", Scope::TenantWrite),
+        ("PUT", "/api/v1/tenant/branding", Scope::TenantWrite),
+        ("POST", "/api/v1/promotion/import", Scope::AgentsWrite),
+        ("PUT", "/api/v1/system/config", Scope::SystemWrite),
+    ];
+
+    #[tokio::test]
+    async fn test_mutating_routes_require_their_scope() {
+        let base = serve_engine(EngineBuilder::new()).await;
+        let client = reqwest::Client::new();
+        let request = |method: &str, path: &str, token: &str| {
+            client
+                .request(Method::from_bytes(method.as_bytes()).unwrap(), format!("{}{}", base, path))
+                .bearer_auth(token)
+                .json(&serde_json::json!({}))
+                .send()
+        };
+
+        let viewer = bearer("agents:read wasm:read", &["viewer"]);
+        for (method, path, scope) in MUTATING_ROUTES {
+            let denied = request(method, path, &viewer).await.unwrap();
+            assert_eq!(denied.status(), reqwest::StatusCode::FORBIDDEN, "{} {}", method, path);
+            let body: serde_json::Value = denied.json().await.unwrap();
+            assert_eq!(body["required_scope"], scope.as_str(), "{} {}", method, path);
+
+            let granted = request(method, path, &bearer(scope.as_str(), &[])).await.unwrap();
+            assert_ne!(granted.status(), reqwest::StatusCode::FORBIDDEN, "{} {}", method, path);
+        }
+
+        // Roles grant their scopes
+        let operator = bearer("", &["operator"]);
+        let executed = request("POST", "/api/v1/mcp/tools/t1/execute", &operator).await.unwrap();
+        assert_ne!(executed.status(), reqwest::StatusCode::FORBIDDEN);
+        let written = request("POST", "/api/v1/agents", &operator).await.unwrap();
+        assert_eq!(written.status(), reqwest::StatusCode::FORBIDDEN);
+
+        // Claims only count once the token verifies
+        let expired = serde_json::json!({"sub": "alice", "roles": ["admin"], "exp": 1});
+        let expired = jsonwebtoken::encode(&Header::default(), &expired, &EncodingKey::from_secret(crate::test_support::JWT_SECRET.as_bytes())).unwrap();
+        let forged = serde_json::json!({"sub": "alice", "roles": ["admin"], "exp": chrono::Utc::now().timestamp() + 3600});
+        let forged = jsonwebtoken::encode(&Header::default(), &forged, &EncodingKey::from_secret(b"guessed")).unwrap();
+        for token in [expired, forged] {
+            let rejected = request("DELETE", "/api/v1/agents/a1", &token).await.unwrap();
+            assert_eq!(rejected.status(), reqwest::StatusCode::UNAUTHORIZED);
+        }
+        let anonymous = client.delete(format!("{}/api/v1/agents/a1", base)).send().await.unwrap();
+        assert_eq!(anonymous.status(), reqwest::StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_comes_from_the_token_not_the_header() {
+        let base = serve_engine(EngineBuilder::new()).await;
+        let client = reqwest::Client::new();
+        let acme = bearer_for(Some("acme"), "", &["admin"]);
+        let globex = bearer_for(Some("globex"), "", &["admin"]);
+
+        let created: serde_json::Value = client
+            .post(format!("{}/api/v1/agents", base))
+            .bearer_auth(&acme)
+            .json(&serde_json::json!({"name": "reviewer"}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let agent_url = format!("{}/api/v1/agents/{}", base, created["id"].as_str().unwrap());
+
+        let spoofed = client.get(&agent_url).bearer_auth(&globex).header("X-Tenant-ID", "acme").send().await.unwrap();
+        assert_eq!(spoofed.status(), reqwest::StatusCode::NOT_FOUND);
+        let listed: serde_json::Value = client
+            .get(format!("{}/api/v1/agents", base))
+            .bearer_auth(&globex)
+            .header("X-Tenant-ID", "acme")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(listed["total"], 0);
+        assert_eq!(client.get(&agent_url).bearer_auth(&acme).send().await.unwrap().status(), reqwest::StatusCode::OK);
+    }
+}
@@ -0,0 +1,38 @@
+//! Redis client
+//!
+//! Connections are opened per use, so an unavailable Redis only fails the
+//! operations that need it; `ping` reports whether it is reachable.
+
+use std::time::Duration;
+
+use crate::config::RedisConfig;
+
+/// How long a health check waits for Redis
+const PING_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Clone)]
+pub struct Cache {
+    client: redis::Client,
+}
+
+impl Cache {
+    /// Client for `config.url`; fails only on a malformed URL
+    pub fn open(config: &RedisConfig) -> redis::RedisResult<Self> {
+        Ok(Self {
+            client: redis::Client::open(config.url.as_str())?,
+        })
+    }
+
+    pub fn client(&self) -> &redis::Client {
+        &self.client
+    }
+
+    /// Whether Redis answers `PING` within a second
+    pub async fn ping(&self) -> bool {
+        let ping = async {
+            let mut connection = self.client.get_async_connection().await?;
+            redis::cmd("PING").query_async::<_, String>(&mut connection).await
+        };
+        matches!(tokio::time::timeout(PING_TIMEOUT, ping).await, Ok(Ok(_)))
+    }
+}
@@ -0,0 +1,591 @@
+//! Canary deployments of agent versions
+//!
+//! A canary sends a percentage of an agent's executions to a candidate
+//! version while the rest stay on the stable one. Every execution is recorded
+//! with a canary flag. In comparison mode a sample of executions also runs on
+//! the other version, and a structural diff of the two outputs is kept for
+//! review. Promoting or aborting the canary reports the success-rate and
+//! latency deltas between the versions.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::{error::ApiError, EngineState};
+
+/// Number of output comparisons kept per canary
+const MAX_COMPARISONS: usize = 100;
+
+/// Runs a specific version of an agent
+pub trait AgentExecutor: Send + Sync {
+    fn execute<'a>(
+        &'a self,
+        agent_id: &'a str,
+        version: &'a str,
+        input: &'a serde_json::Value,
+    ) -> BoxFuture<'a, Result<serde_json::Value, String>>;
+}
+
+/// Whether sampled executions also run on the other version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ComparisonMode {
+    #[default]
+    Off,
+    /// Run `sample_percent` of executions against both versions
+    Sampled { sample_percent: u8 },
+}
+
+/// Canary configuration of one agent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CanaryConfig {
+    pub stable_version: String,
+    pub candidate_version: String,
+    /// Share of executions routed to the candidate, 0-100
+    pub percentage: u8,
+    #[serde(default)]
+    pub comparison: ComparisonMode,
+}
+
+/// One execution made while a canary was active
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanaryExecution {
+    pub version: String,
+    pub canary: bool,
+    pub success: bool,
+    pub latency_ms: u64,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A point where stable and candidate outputs differ
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct OutputDifference {
+    /// JSON pointer to the differing value
+    pub path: String,
+    pub stable: Option<serde_json::Value>,
+    pub candidate: Option<serde_json::Value>,
+}
+
+/// Outputs of both versions for the same input
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CanaryComparison {
+    pub input: serde_json::Value,
+    pub differences: Vec<OutputDifference>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Aggregate results of one version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct VersionStats {
+    pub version: String,
+    pub executions: u64,
+    pub successes: u64,
+    pub success_rate: f64,
+    pub mean_latency_ms: f64,
+}
+
+/// How a canary ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CanaryDecision {
+    /// Make the candidate the live version
+    Promote,
+    /// Keep the stable version
+    Abort,
+}
+
+/// State of a canary, reported while it runs and when it ends
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CanaryReport {
+    pub agent_id: String,
+    pub config: CanaryConfig,
+    pub stable: VersionStats,
+    pub candidate: VersionStats,
+    /// Candidate minus stable success rate
+    pub success_rate_delta: f64,
+    /// Candidate minus stable mean latency
+    pub latency_delta_ms: f64,
+    pub comparisons: Vec<CanaryComparison>,
+    pub decision: Option<CanaryDecision>,
+    /// Version serving the agent once the decision is applied
+    pub live_version: String,
+}
+
+/// Canary errors
+#[derive(Debug, thiserror::Error)]
+pub enum CanaryError {
+    #[error("Canary percentage {0} is above 100")]
+    InvalidPercentage(u8),
+    #[error("Candidate version must differ from stable version {0}")]
+    SameVersion(String),
+    #[error("No canary configured for agent {0}")]
+    NotFound(String),
+    #[error("Execution failed: {0}")]
+    Execution(String),
+}
+
+#[derive(Debug)]
+struct CanaryState {
+    config: CanaryConfig,
+    routed: u64,
+    sampled: u64,
+    executions: Vec<CanaryExecution>,
+    comparisons: Vec<CanaryComparison>,
+}
+
+impl CanaryState {
+    fn report(&self, agent_id: &str, decision: Option<CanaryDecision>) -> CanaryReport {
+        let stable = self.stats(&self.config.stable_version);
+        let candidate = self.stats(&self.config.candidate_version);
+        let live_version = match decision {
+            Some(CanaryDecision::Promote) => &self.config.candidate_version,
+            _ => &self.config.stable_version,
+        };
+
+        CanaryReport {
+            agent_id: agent_id.to_string(),
+            config: self.config.clone(),
+            success_rate_delta: candidate.success_rate - stable.success_rate,
+            latency_delta_ms: candidate.mean_latency_ms - stable.mean_latency_ms,
+            stable,
+            candidate,
+            comparisons: self.comparisons.clone(),
+            decision,
+            live_version: live_version.clone(),
+        }
+    }
+
+    fn stats(&self, version: &str) -> VersionStats {
+        let executions: Vec<&CanaryExecution> = self.executions.iter().filter(|e| e.version == version).collect();
+        let count = executions.len() as u64;
+        let successes = executions.iter().filter(|e| e.success).count() as u64;
+        let total_latency: u64 = executions.iter().map(|e| e.latency_ms).sum();
+
+        VersionStats {
+            version: version.to_string(),
+            executions: count,
+            successes,
+            success_rate: if count == 0 { 0.0 } else { successes as f64 / count as f64 },
+            mean_latency_ms: if count == 0 { 0.0 } else { total_latency as f64 / count as f64 },
+        }
+    }
+}
+
+/// Whether the `n`th event (from zero) falls in an even `percent` spread of events
+fn in_share(n: u64, percent: u8) -> bool {
+    let percent = u64::from(percent);
+    (n + 1) * percent / 100 > n * percent / 100
+}
+
+/// Routes agent executions between stable and candidate versions
+#[derive(Clone)]
+pub struct CanaryService {
+    executor: Arc<dyn AgentExecutor>,
+    canaries: Arc<RwLock<HashMap<String, CanaryState>>>,
+    live_versions: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl CanaryService {
+    pub fn new(executor: Arc<dyn AgentExecutor>) -> Self {
+        Self {
+            executor,
+            canaries: Arc::new(RwLock::new(HashMap::new())),
+            live_versions: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Start or replace the canary of an agent
+    pub async fn configure(&self, agent_id: &str, config: CanaryConfig) -> Result<(), CanaryError> {
+        if config.percentage > 100 {
+            return Err(CanaryError::InvalidPercentage(config.percentage));
+        }
+        if let ComparisonMode::Sampled { sample_percent } = config.comparison {
+            if sample_percent > 100 {
+                return Err(CanaryError::InvalidPercentage(sample_percent));
+            }
+        }
+        if config.candidate_version == config.stable_version {
+            return Err(CanaryError::SameVersion(config.stable_version));
+        }
+
+        info!(
+            "🐤 Canary for agent {}: {}% to v{} (stable v{})",
+            agent_id, config.percentage, config.candidate_version, config.stable_version
+        );
+        self.live_versions.write().await.insert(agent_id.to_string(), config.stable_version.clone());
+        self.canaries.write().await.insert(
+            agent_id.to_string(),
+            CanaryState {
+                config,
+                routed: 0,
+                sampled: 0,
+                executions: Vec::new(),
+                comparisons: Vec::new(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Version an agent's executions run on outside of a canary
+    pub async fn live_version(&self, agent_id: &str) -> Option<String> {
+        self.live_versions.read().await.get(agent_id).cloned()
+    }
+
+    /// Execute an agent, routing to the canary's candidate for its share of
+    /// executions. Agents without a canary run their live version.
+    pub async fn execute(&self, agent_id: &str, input: &serde_json::Value) -> Result<serde_json::Value, CanaryError> {
+        let route = {
+            let mut canaries = self.canaries.write().await;
+            canaries.get_mut(agent_id).map(|state| {
+                let canary = in_share(state.routed, state.config.percentage);
+                state.routed += 1;
+                let compare = match state.config.comparison {
+                    ComparisonMode::Sampled { sample_percent } => {
+                        let sampled = in_share(state.sampled, sample_percent);
+                        state.sampled += 1;
+                        sampled
+                    }
+                    ComparisonMode::Off => false,
+                };
+                (state.config.clone(), canary, compare)
+            })
+        };
+
+        let Some((config, canary, compare)) = route else {
+            let version = self.live_version(agent_id).await.ok_or_else(|| CanaryError::NotFound(agent_id.to_string()))?;
+            return self.executor.execute(agent_id, &version, input).await.map_err(CanaryError::Execution);
+        };
+
+        let (version, other) = if canary {
+            (&config.candidate_version, &config.stable_version)
+        } else {
+            (&config.stable_version, &config.candidate_version)
+        };
+        let (output, execution) = self.run(agent_id, version, canary, input).await;
+        let mut recorded = vec![execution];
+
+        let comparison = if compare {
+            let (other_output, other_execution) = self.run(agent_id, other, !canary, input).await;
+            recorded.push(other_execution);
+            let (stable, candidate) = if canary { (&other_output, &output) } else { (&output, &other_output) };
+            Some(CanaryComparison {
+                input: input.clone(),
+                differences: diff_outputs(&outcome_value(stable), &outcome_value(candidate)),
+                timestamp: Utc::now(),
+            })
+        } else {
+            None
+        };
+
+        if let Some(state) = self.canaries.write().await.get_mut(agent_id) {
+            // The canary may have been replaced while executing
+            if state.config.candidate_version == config.candidate_version {
+                state.executions.extend(recorded);
+                if let Some(comparison) = comparison {
+                    if state.comparisons.len() == MAX_COMPARISONS {
+                        state.comparisons.remove(0);
+                    }
+                    state.comparisons.push(comparison);
+                }
+            }
+        }
+
+        output.map_err(CanaryError::Execution)
+    }
+
+    async fn run(
+        &self,
+        agent_id: &str,
+        version: &str,
+        canary: bool,
+        input: &serde_json::Value,
+    ) -> (Result<serde_json::Value, String>, CanaryExecution) {
+        let started = Instant::now();
+        let output = self.executor.execute(agent_id, version, input).await;
+        let execution = CanaryExecution {
+            version: version.to_string(),
+            canary,
+            success: output.is_ok(),
+            latency_ms: started.elapsed().as_millis() as u64,
+            timestamp: Utc::now(),
+        };
+        (output, execution)
+    }
+
+    /// Current state of an agent's canary
+    pub async fn report(&self, agent_id: &str) -> Option<CanaryReport> {
+        self.canaries.read().await.get(agent_id).map(|state| state.report(agent_id, None))
+    }
+
+    /// Configuration of every running canary, sorted by agent id
+    pub async fn configs(&self) -> Vec<(String, CanaryConfig)> {
+        let mut configs: Vec<(String, CanaryConfig)> = self.canaries.read().await
+            .iter()
+            .map(|(agent_id, state)| (agent_id.clone(), state.config.clone()))
+            .collect();
+        configs.sort_by(|a, b| a.0.cmp(&b.0));
+        configs
+    }
+
+    /// End an agent's canary, promoting the candidate or keeping the stable version
+    pub async fn finalize(&self, agent_id: &str, decision: CanaryDecision) -> Result<CanaryReport, CanaryError> {
+        let state = self.canaries.write().await
+            .remove(agent_id)
+            .ok_or_else(|| CanaryError::NotFound(agent_id.to_string()))?;
+        let report = state.report(agent_id, Some(decision));
+
+        match decision {
+            CanaryDecision::Promote => info!("🐤 Promoted agent {} to v{}", agent_id, report.live_version),
+            CanaryDecision::Abort => warn!("🐤 Aborted canary of agent {} v{}", agent_id, report.config.candidate_version),
+        }
+        self.live_versions.write().await.insert(agent_id.to_string(), report.live_version.clone());
+        Ok(report)
+    }
+}
+
+/// Failed executions are compared by their error
+fn outcome_value(outcome: &Result<serde_json::Value, String>) -> serde_json::Value {
+    match outcome {
+        Ok(output) => output.clone(),
+        Err(e) => serde_json::json!({ "error": e }),
+    }
+}
+
+/// Structural differences between two outputs, by JSON pointer
+pub fn diff_outputs(stable: &serde_json::Value, candidate: &serde_json::Value) -> Vec<OutputDifference> {
+    let mut differences = Vec::new();
+    diff_at("", Some(stable), Some(candidate), &mut differences);
+    differences
+}
+
+fn diff_at(
+    path: &str,
+    stable: Option<&serde_json::Value>,
+    candidate: Option<&serde_json::Value>,
+    differences: &mut Vec<OutputDifference>,
+) {
+    use serde_json::Value;
+
+    match (stable, candidate) {
+        (Some(Value::Object(a)), Some(Value::Object(b))) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let escaped = key.replace('~', "~0").replace('/', "~1");
+                diff_at(&format!("{}/{}", path, escaped), a.get(key), b.get(key), differences);
+            }
+        }
+        (Some(Value::Array(a)), Some(Value::Array(b))) => {
+            for index in 0..a.len().max(b.len()) {
+                diff_at(&format!("{}/{}", path, index), a.get(index), b.get(index), differences);
+            }
+        }
+        (a, b) if a != b => differences.push(OutputDifference {
+            path: path.to_string(),
+            stable: a.cloned(),
+            candidate: b.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+/// Body of `POST /api/v1/agents/:id/canary/finalize`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct FinalizeCanaryRequest {
+    pub decision: CanaryDecision,
+}
+
+fn canary_service(state: &EngineState) -> Result<&CanaryService, ApiError> {
+    state
+        .canary_service
+        .as_ref()
+        .ok_or_else(|| ApiError::Unavailable("No agent executor is configured".to_string()))
+}
+
+impl From<CanaryError> for ApiError {
+    fn from(err: CanaryError) -> Self {
+        let detail = err.to_string();
+        match err {
+            CanaryError::InvalidPercentage(_) | CanaryError::SameVersion(_) => ApiError::BadRequest(detail),
+            CanaryError::NotFound(_) => ApiError::NotFound(detail),
+            CanaryError::Execution(_) => ApiError::BadGateway(detail),
+        }
+    }
+}
+
+/// `PUT /api/v1/agents/:id/canary`
+#[utoipa::path(
+    put,
+    path = "/api/v1/agents/{id}/canary",
+    tag = "agents",
+    params(("id" = String, Path, description = "Agent id")),
+    request_body = CanaryConfig,
+    responses(
+        (status = 200, description = "Canary started", body = CanaryReport),
+        (status = 400, description = "Invalid percentage or identical versions"),
+        (status = 403, description = "Token lacks the `agents:write` scope"),
+        (status = 503, description = "No agent executor is configured"),
+    )
+)]
+pub async fn configure_canary(
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+    Json(config): Json<CanaryConfig>,
+) -> Result<Json<CanaryReport>, ApiError> {
+    let service = canary_service(&state)?;
+    service.configure(&id, config).await?;
+    service.report(&id).await.map(Json).ok_or_else(|| CanaryError::NotFound(id).into())
+}
+
+/// `GET /api/v1/agents/:id/canary`
+#[utoipa::path(
+    get,
+    path = "/api/v1/agents/{id}/canary",
+    tag = "agents",
+    params(("id" = String, Path, description = "Agent id")),
+    responses(
+        (status = 200, description = "Progress of the agent's canary", body = CanaryReport),
+        (status = 404, description = "No canary is running for the agent"),
+        (status = 503, description = "No agent executor is configured"),
+    )
+)]
+pub async fn get_canary(
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+) -> Result<Json<CanaryReport>, ApiError> {
+    canary_service(&state)?.report(&id).await.map(Json).ok_or_else(|| CanaryError::NotFound(id).into())
+}
+
+/// `POST /api/v1/agents/:id/canary/finalize`
+#[utoipa::path(
+    post,
+    path = "/api/v1/agents/{id}/canary/finalize",
+    tag = "agents",
+    params(("id" = String, Path, description = "Agent id")),
+    request_body = FinalizeCanaryRequest,
+    responses(
+        (status = 200, description = "Final report, with the decision applied", body = CanaryReport),
+        (status = 403, description = "Token lacks the `agents:write` scope"),
+        (status = 404, description = "No canary is running for the agent"),
+        (status = 503, description = "No agent executor is configured"),
+    )
+)]
+pub async fn finalize_canary(
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+    Json(request): Json<FinalizeCanaryRequest>,
+) -> Result<Json<CanaryReport>, ApiError> {
+    Ok(Json(canary_service(&state)?.finalize(&id, request.decision).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// v1 always succeeds; v2 fails every fourth call and adds a field to its output
+    #[derive(Default)]
+    struct VersionedAgent {
+        calls: std::sync::Mutex<HashMap<String, u32>>,
+    }
+
+    impl AgentExecutor for VersionedAgent {
+        fn execute<'a>(
+            &'a self,
+            _agent_id: &'a str,
+            version: &'a str,
+            input: &'a serde_json::Value,
+        ) -> BoxFuture<'a, Result<serde_json::Value, String>> {
+            Box::pin(async move {
+                let call = {
+                    let mut calls = self.calls.lock().unwrap();
+                    let call = calls.entry(version.to_string()).or_default();
+                    *call += 1;
+                    *call
+                };
+                match version {
+                    "2" if call % 4 == 0 => Err("candidate crashed".to_string()),
+                    "2" => Ok(serde_json::json!({"echo": input, "confidence": 0.9})),
+                    _ => Ok(serde_json::json!({"echo": input})),
+                }
+            })
+        }
+    }
+
+    fn canary(percentage: u8, comparison: ComparisonMode) -> CanaryConfig {
+        CanaryConfig {
+            stable_version: "1".to_string(),
+            candidate_version: "2".to_string(),
+            percentage,
+            comparison,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_canary_share_and_promotion_report() {
+        let service = CanaryService::new(Arc::new(VersionedAgent::default()));
+        service.configure("summarizer", canary(30, ComparisonMode::Off)).await.unwrap();
+
+        for i in 0..20 {
+            let _ = service.execute("summarizer", &serde_json::json!({"n": i})).await;
+        }
+
+        let report = service.finalize("summarizer", CanaryDecision::Promote).await.unwrap();
+        assert_eq!(report.candidate.executions, 6);
+        assert_eq!(report.stable.executions, 14);
+        assert_eq!(report.stable.success_rate, 1.0);
+        assert_eq!(report.candidate.successes, 5);
+        assert!(report.success_rate_delta < 0.0);
+        assert_eq!(report.live_version, "2");
+
+        // Without a canary the promoted version serves everything
+        assert_eq!(service.live_version("summarizer").await.as_deref(), Some("2"));
+        assert!(service.report("summarizer").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sampled_comparison_records_output_diff() {
+        let service = CanaryService::new(Arc::new(VersionedAgent::default()));
+        service.configure("summarizer", canary(0, ComparisonMode::Sampled { sample_percent: 50 })).await.unwrap();
+
+        for i in 0..4 {
+            let output = service.execute("summarizer", &serde_json::json!({"n": i})).await.unwrap();
+            assert_eq!(output, serde_json::json!({"echo": {"n": i}}));
+        }
+
+        let report = service.report("summarizer").await.unwrap();
+        assert_eq!(report.comparisons.len(), 2);
+        assert_eq!(
+            report.comparisons[0].differences,
+            vec![OutputDifference {
+                path: "/confidence".to_string(),
+                stable: None,
+                candidate: Some(serde_json::json!(0.9)),
+            }]
+        );
+        assert_eq!((report.stable.executions, report.candidate.executions), (4, 2));
+
+        let aborted = service.finalize("summarizer", CanaryDecision::Abort).await.unwrap();
+        assert_eq!(aborted.live_version, "1");
+    }
+
+    #[test]
+    fn test_diff_outputs() {
+        let stable = serde_json::json!({"items": [1, 2], "a/b": "x", "same": true});
+        let candidate = serde_json::json!({"items": [1, 3, 4], "a/b": "y", "same": true});
+        let paths: Vec<String> = diff_outputs(&stable, &candidate).into_iter().map(|d| d.path).collect();
+        assert_eq!(paths, vec!["/a~1b", "/items/1", "/items/2"]);
+        assert!(diff_outputs(&stable, &stable).is_empty());
+    }
+}
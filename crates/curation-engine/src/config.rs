@@ -0,0 +1,172 @@
+//! Engine configuration
+//!
+//! `EngineConfig` is read once at startup from an optional file and
+//! `CURATION__`-prefixed environment variables (`CURATION__AUTH__JWT_SECRET`
+//! sets `auth.jwt_secret`), then kept in a `LiveConfig` so
+//! `PUT /api/v1/system/config` can replace it while the engine runs.
+
+use serde::{Deserialize, Serialize};
+
+/// Prefix of environment variables overriding the config file
+pub const ENV_PREFIX: &str = "CURATION";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EngineConfig {
+    pub database: DatabaseConfig,
+    pub redis: RedisConfig,
+    pub wasm: WasmConfig,
+    pub auth: AuthConfig,
+    pub metrics: MetricsConfig,
+    pub rate_limit: RateLimitConfig,
+    pub mcp: McpConfig,
+}
+
+impl EngineConfig {
+    /// Defaults, overridden by `path` when given and then by the environment
+    pub fn load(path: Option<&str>) -> Result<Self, config::ConfigError> {
+        let mut builder = config::Config::builder();
+        if let Some(path) = path {
+            builder = builder.add_source(config::File::with_name(path));
+        }
+        builder
+            .add_source(config::Environment::with_prefix(ENV_PREFIX).separator("__").try_parsing(true))
+            .build()?
+            .try_deserialize()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    /// How long a health check waits for a connection
+    pub connect_timeout_ms: u64,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: "postgres://localhost:5432/curation".to_string(),
+            max_connections: 10,
+            connect_timeout_ms: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedisConfig {
+    pub url: String,
+}
+
+impl Default for RedisConfig {
+    fn default() -> Self {
+        Self {
+            url: "redis://localhost:6379".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WasmConfig {
+    pub max_memory_mb: u32,
+    pub max_execution_time_ms: u64,
+    /// Fuel an execution may consume; roughly one unit per instruction
+    pub max_fuel: u64,
+}
+
+impl Default for WasmConfig {
+    fn default() -> Self {
+        Self {
+            max_memory_mb: 128,
+            max_execution_time_ms: 30_000,
+            max_fuel: 10_000_000_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// Without auth every request is granted every scope
+    pub enabled: bool,
+    /// HS256 secret bearer tokens are signed with; never returned by the API
+    #[serde(skip_serializing)]
+    pub jwt_secret: String,
+    /// Required `iss` claim, when set
+    pub issuer: Option<String>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            jwt_secret: String::new(),
+            issuer: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Prefix of every exported metric name
+    pub namespace: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            namespace: "curation".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// Sustained requests per client per minute
+    pub requests_per_minute: u32,
+    /// Requests a client may make at once before being limited
+    pub burst_limit: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            requests_per_minute: 1000,
+            burst_limit: 100,
+        }
+    }
+}
+
+/// MCP servers whose tools the engine lists and executes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct McpConfig {
+    pub servers: Vec<McpServerConfig>,
+    /// Timeout of each call to an MCP server
+    pub timeout_ms: u64,
+}
+
+impl Default for McpConfig {
+    fn default() -> Self {
+        Self {
+            servers: Vec::new(),
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+/// An MCP server reached over streamable HTTP
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServerConfig {
+    pub name: String,
+    /// JSON-RPC endpoint of the server
+    pub url: String,
+}
@@ -0,0 +1,188 @@
+//! Request correlation ids
+//!
+//! Every request is tagged with an `x-correlation-id`: the caller's, when it
+//! sent a usable one, or a fresh UUID. The id is stored as a request
+//! extension, recorded on a tracing span around the request, and echoed on
+//! every response, errors from inner middleware included. While the request
+//! is handled it is also the task's current id, so outbound calls made by the
+//! service layer carry it via `WithCorrelationId`, and background work
+//! spawned with `inherit` keeps it.
+
+use std::fmt;
+use std::future::Future;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request},
+    http::{request::Parts, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+
+/// Header carrying the correlation id in both directions
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+
+/// Longest caller-supplied id that is accepted rather than replaced
+const MAX_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static CURRENT: CorrelationId;
+}
+
+/// Id tying together every hop of one request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorrelationId(String);
+
+impl CorrelationId {
+    /// A fresh id
+    pub fn generate() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// The caller's id if it is printable and of reasonable length
+    pub fn parse(value: &str) -> Option<Self> {
+        let valid = !value.is_empty()
+            && value.len() <= MAX_ID_LEN
+            && value.bytes().all(|b| b.is_ascii_graphic());
+        valid.then(|| Self(value.to_string()))
+    }
+
+    /// Id of the request the current task is handling, if any
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Run `future` with the current task's correlation id, for work handed to
+/// `tokio::spawn`
+pub fn inherit<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    let id = CorrelationId::current();
+    async move {
+        match id {
+            Some(id) => CURRENT.scope(id, future).await,
+            None => future.await,
+        }
+    }
+}
+
+/// Middleware assigning the correlation id; layered outside everything else
+/// so that every response carries it
+pub async fn propagate_correlation_id(mut req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(CORRELATION_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(CorrelationId::parse)
+        .unwrap_or_else(CorrelationId::generate);
+    req.extensions_mut().insert(id.clone());
+
+    let span = tracing::info_span!("request", correlation_id = %id, method = %req.method(), path = %req.uri().path());
+    let mut response = CURRENT.scope(id.clone(), next.run(req).instrument(span)).await;
+    if let Ok(value) = HeaderValue::from_str(id.as_str()) {
+        response.headers_mut().insert(CORRELATION_ID_HEADER, value);
+    }
+    response
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for CorrelationId {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<CorrelationId>()
+            .cloned()
+            .or_else(CorrelationId::current)
+            .unwrap_or_else(CorrelationId::generate))
+    }
+}
+
+/// Attach the current correlation id to an outbound request
+pub trait WithCorrelationId {
+    fn with_correlation_id(self) -> Self;
+}
+
+impl WithCorrelationId for reqwest::RequestBuilder {
+    fn with_correlation_id(self) -> Self {
+        match CorrelationId::current() {
+            Some(id) => self.header(CORRELATION_ID_HEADER, id.as_str()),
+            None => self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::serve;
+    use std::sync::{Arc, Mutex};
+
+    use axum::{
+        extract::State,
+        http::{HeaderMap, StatusCode},
+        middleware::from_fn,
+        routing::{get, post},
+        Router,
+    };
+
+    #[tokio::test]
+    async fn test_correlation_id_reaches_downstream_and_every_response() {
+        // Downstream service recording the ids it is called with
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let downstream = Router::new()
+            .route(
+                "/execute",
+                post(|State(seen): State<Arc<Mutex<Vec<Option<String>>>>>, headers: HeaderMap| async move {
+                    let id = headers.get(CORRELATION_ID_HEADER).map(|v| v.to_str().unwrap().to_string());
+                    seen.lock().unwrap().push(id);
+                }),
+            )
+            .with_state(seen.clone());
+        let downstream_url = format!("{}/execute", serve(downstream).await);
+
+        // A handler calling downstream directly and from a spawned task, and one that fails
+        let app = Router::new()
+            .route(
+                "/run",
+                post(move |id: CorrelationId| async move {
+                    let client = reqwest::Client::new();
+                    client.post(&downstream_url).with_correlation_id().send().await.unwrap();
+                    let background = client.post(&downstream_url);
+                    tokio::spawn(inherit(async move { background.with_correlation_id().send().await.unwrap() }))
+                        .await
+                        .unwrap();
+                    id.to_string()
+                }),
+            )
+            .route("/fail", get(|| async { StatusCode::INTERNAL_SERVER_ERROR }))
+            .layer(from_fn(propagate_correlation_id));
+        let base = serve(app).await;
+        let client = reqwest::Client::new();
+
+        let response = client.post(format!("{}/run", base)).header(CORRELATION_ID_HEADER, "req-42").send().await.unwrap();
+        assert_eq!(response.headers()[CORRELATION_ID_HEADER], "req-42");
+        assert_eq!(response.text().await.unwrap(), "req-42");
+        assert_eq!(*seen.lock().unwrap(), [Some("req-42".to_string()), Some("req-42".to_string())]);
+
+        // Without one, or with an unusable one, an id is generated; errors carry it too
+        let failed = client.get(format!("{}/fail", base)).header(CORRELATION_ID_HEADER, "bad id").send().await.unwrap();
+        assert_eq!(failed.status(), reqwest::StatusCode::INTERNAL_SERVER_ERROR);
+        let generated = failed.headers()[CORRELATION_ID_HEADER].to_str().unwrap();
+        assert!(uuid::Uuid::parse_str(generated).is_ok());
+        let missing = client.get(format!("{}/missing", base)).send().await.unwrap();
+        assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+        assert!(missing.headers().contains_key(CORRELATION_ID_HEADER));
+    }
+}
@@ -0,0 +1,35 @@
+//! PostgreSQL connection pool
+//!
+//! The pool connects lazily, so the engine starts while the database is
+//! still coming up; `ping` reports whether it is reachable.
+
+use std::time::Duration;
+
+use sqlx::postgres::{PgPool, PgPoolOptions};
+
+use crate::config::DatabaseConfig;
+
+#[derive(Clone)]
+pub struct Database {
+    pool: PgPool,
+}
+
+impl Database {
+    /// Pool for `config.url`; fails only on a malformed URL
+    pub fn connect_lazy(config: &DatabaseConfig) -> Result<Self, sqlx::Error> {
+        let pool = PgPoolOptions::new()
+            .max_connections(config.max_connections)
+            .acquire_timeout(Duration::from_millis(config.connect_timeout_ms))
+            .connect_lazy(&config.url)?;
+        Ok(Self { pool })
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Whether a connection can be acquired and answers a trivial query
+    pub async fn ping(&self) -> bool {
+        sqlx::query("SELECT 1").execute(&self.pool).await.is_ok()
+    }
+}
@@ -0,0 +1,573 @@
+//! Behavioral drift detection
+//!
+//! Agents drift from their registered intent when a module is updated or a
+//! prompt changes out of band. The drift monitor groups each agent's
+//! executions into windows of `window_executions`, fingerprints every full
+//! window (output shape distribution, mean latency, tool usage mix, error
+//! rate) and compares it with the window before. When a dimension moves past
+//! its threshold a [`DriftReport`] naming the drifted dimensions is attached
+//! to the agent and an `agent.drifted` webhook event is published. With
+//! `pause_schedules` set the agent's schedules are also paused through a
+//! [`ScheduleControl`] until every open report has been reviewed.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::{
+    error::ApiError,
+    webhooks::{WebhookEventType, WebhookService},
+    EngineState,
+};
+
+/// Reports kept per agent
+const MAX_REPORTS: usize = 50;
+
+/// Pauses and resumes the schedules that run an agent
+pub trait ScheduleControl: Send + Sync {
+    /// Pause every schedule referencing `agent_id`, returning how many were paused
+    fn pause_agent_schedules<'a>(&'a self, agent_id: &'a str) -> BoxFuture<'a, Result<usize, String>>;
+
+    /// Resume the schedules paused for `agent_id`
+    fn resume_agent_schedules<'a>(&'a self, agent_id: &'a str) -> BoxFuture<'a, Result<usize, String>>;
+}
+
+/// Window size and how far each dimension may move between windows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftConfig {
+    /// Executions fingerprinted together
+    pub window_executions: usize,
+    /// Total variation distance between output shape distributions, 0-1
+    pub output_shape_threshold: f64,
+    /// Relative change of mean latency, e.g. `0.5` for 50%
+    pub latency_change_threshold: f64,
+    /// Total variation distance between tool usage mixes, 0-1
+    pub tool_mix_threshold: f64,
+    /// Absolute change of the error rate, 0-1
+    pub error_rate_threshold: f64,
+    /// Pause the agent's schedules while a drift report is open
+    pub pause_schedules: bool,
+}
+
+impl Default for DriftConfig {
+    fn default() -> Self {
+        Self {
+            window_executions: 100,
+            output_shape_threshold: 0.3,
+            latency_change_threshold: 0.5,
+            tool_mix_threshold: 0.3,
+            error_rate_threshold: 0.1,
+            pause_schedules: false,
+        }
+    }
+}
+
+/// One agent execution as the drift monitor sees it
+#[derive(Debug, Clone)]
+pub struct ObservedExecution {
+    /// The output, or the error the execution failed with
+    pub output: Result<serde_json::Value, String>,
+    pub latency_ms: u64,
+    /// Names of the tools the execution called
+    pub tools: Vec<String>,
+}
+
+/// Behavior of an agent over one window of executions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BehaviorFingerprint {
+    pub executions: usize,
+    /// Share of successful outputs per output shape, e.g. `{"summary":string}`
+    pub output_shapes: BTreeMap<String, f64>,
+    pub mean_latency_ms: f64,
+    /// Share of tool calls per tool
+    pub tool_mix: BTreeMap<String, f64>,
+    pub error_rate: f64,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+}
+
+/// A fingerprint dimension compared between windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftDimension {
+    OutputShape,
+    Latency,
+    ToolMix,
+    ErrorRate,
+}
+
+/// A dimension that moved past its threshold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DimensionDrift {
+    pub dimension: DriftDimension,
+    /// How far the dimension moved, in the unit of its threshold
+    pub magnitude: f64,
+    pub threshold: f64,
+    pub previous: serde_json::Value,
+    pub current: serde_json::Value,
+}
+
+/// Whether a report still needs review
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftReportStatus {
+    Open,
+    Reviewed,
+}
+
+/// Drift detected between two successive windows of an agent
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DriftReport {
+    pub id: String,
+    pub agent_id: String,
+    pub detected_at: DateTime<Utc>,
+    pub previous: BehaviorFingerprint,
+    pub current: BehaviorFingerprint,
+    pub drifted: Vec<DimensionDrift>,
+    /// Schedules paused pending review
+    pub schedules_paused: usize,
+    pub status: DriftReportStatus,
+    pub reviewed_at: Option<DateTime<Utc>>,
+}
+
+/// Drift monitor errors
+#[derive(Debug, thiserror::Error)]
+pub enum DriftError {
+    #[error("No drift report {report_id} for agent {agent_id}")]
+    NotFound { agent_id: String, report_id: String },
+}
+
+#[derive(Default)]
+struct AgentDrift {
+    window: Vec<ObservedExecution>,
+    window_start: Option<DateTime<Utc>>,
+    previous: Option<BehaviorFingerprint>,
+    reports: Vec<DriftReport>,
+    /// Schedules are paused until every open report is reviewed
+    schedules_paused: bool,
+}
+
+/// Fingerprints agent executions and reports drift between windows
+#[derive(Clone)]
+pub struct DriftMonitor {
+    config: DriftConfig,
+    webhooks: WebhookService,
+    schedules: Option<Arc<dyn ScheduleControl>>,
+    agents: Arc<RwLock<HashMap<String, AgentDrift>>>,
+}
+
+impl Default for DriftMonitor {
+    fn default() -> Self {
+        Self::new(DriftConfig::default(), WebhookService::default())
+    }
+}
+
+impl DriftMonitor {
+    /// Monitor with `config`, publishing `agent.drifted` events through `webhooks`
+    pub fn new(config: DriftConfig, webhooks: WebhookService) -> Self {
+        Self {
+            config,
+            webhooks,
+            schedules: None,
+            agents: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Pause and resume agent schedules through `schedules` when `pause_schedules` is set
+    pub fn with_schedule_control(mut self, schedules: Arc<dyn ScheduleControl>) -> Self {
+        self.schedules = Some(schedules);
+        self
+    }
+
+    /// Record an execution of `agent_id`, returning the drift report if it
+    /// completed a window that drifted from the one before
+    pub async fn record(&self, tenant: &str, agent_id: &str, execution: ObservedExecution) -> Option<DriftReport> {
+        let (previous, current) = {
+            let mut agents = self.agents.write().await;
+            let agent = agents.entry(agent_id.to_string()).or_default();
+            let window_start = *agent.window_start.get_or_insert_with(Utc::now);
+            agent.window.push(execution);
+            if agent.window.len() < self.config.window_executions.max(1) {
+                return None;
+            }
+
+            let current = fingerprint(&std::mem::take(&mut agent.window), window_start, Utc::now());
+            agent.window_start = None;
+            (agent.previous.replace(current.clone())?, current)
+        };
+
+        let drifted = compare(&previous, &current, &self.config);
+        if drifted.is_empty() {
+            return None;
+        }
+
+        let schedules_paused = self.pause(agent_id).await;
+        let report = DriftReport {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent_id: agent_id.to_string(),
+            detected_at: Utc::now(),
+            previous,
+            current,
+            drifted,
+            schedules_paused,
+            status: DriftReportStatus::Open,
+            reviewed_at: None,
+        };
+        let dimensions: Vec<DriftDimension> = report.drifted.iter().map(|d| d.dimension).collect();
+        warn!("📉 Agent {} drifted in {:?}", agent_id, dimensions);
+
+        {
+            let mut agents = self.agents.write().await;
+            let agent = agents.entry(agent_id.to_string()).or_default();
+            if agent.reports.len() == MAX_REPORTS {
+                agent.reports.remove(0);
+            }
+            agent.reports.push(report.clone());
+            agent.schedules_paused |= schedules_paused > 0;
+        }
+        let data = serde_json::to_value(&report).unwrap_or_default();
+        self.webhooks.publish(tenant, WebhookEventType::AgentDrifted, data).await;
+        Some(report)
+    }
+
+    async fn pause(&self, agent_id: &str) -> usize {
+        let Some(schedules) = self.schedules.as_ref().filter(|_| self.config.pause_schedules) else {
+            return 0;
+        };
+        match schedules.pause_agent_schedules(agent_id).await {
+            Ok(paused) => {
+                info!("⏸️ Paused {} schedule(s) of agent {} pending drift review", paused, agent_id);
+                paused
+            }
+            Err(e) => {
+                warn!("⚠️ Failed to pause schedules of agent {}: {}", agent_id, e);
+                0
+            }
+        }
+    }
+
+    /// Drift reports of an agent, oldest first
+    pub async fn reports(&self, agent_id: &str) -> Vec<DriftReport> {
+        self.agents.read().await.get(agent_id).map(|agent| agent.reports.clone()).unwrap_or_default()
+    }
+
+    /// Mark a report reviewed; once no report of the agent is open, its paused schedules resume
+    pub async fn review(&self, agent_id: &str, report_id: &str) -> Result<DriftReport, DriftError> {
+        let (report, resume) = {
+            let mut agents = self.agents.write().await;
+            let not_found = || DriftError::NotFound {
+                agent_id: agent_id.to_string(),
+                report_id: report_id.to_string(),
+            };
+            let agent = agents.get_mut(agent_id).ok_or_else(not_found)?;
+            let report = agent.reports.iter_mut().find(|report| report.id == report_id).ok_or_else(not_found)?;
+            if report.status == DriftReportStatus::Open {
+                report.status = DriftReportStatus::Reviewed;
+                report.reviewed_at = Some(Utc::now());
+            }
+            let report = report.clone();
+            let resume = agent.schedules_paused && agent.reports.iter().all(|r| r.status == DriftReportStatus::Reviewed);
+            if resume {
+                agent.schedules_paused = false;
+            }
+            (report, resume)
+        };
+
+        if let (true, Some(schedules)) = (resume, &self.schedules) {
+            match schedules.resume_agent_schedules(agent_id).await {
+                Ok(resumed) => info!("▶️ Resumed {} schedule(s) of agent {} after drift review", resumed, agent_id),
+                Err(e) => warn!("⚠️ Failed to resume schedules of agent {}: {}", agent_id, e),
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Fingerprint of the executions of one window
+pub fn fingerprint(executions: &[ObservedExecution], window_start: DateTime<Utc>, window_end: DateTime<Utc>) -> BehaviorFingerprint {
+    let mut shapes = BTreeMap::new();
+    let mut tools = BTreeMap::new();
+    let mut errors = 0;
+    for execution in executions {
+        match &execution.output {
+            Ok(output) => *shapes.entry(output_shape(output)).or_insert(0usize) += 1,
+            Err(_) => errors += 1,
+        }
+        for tool in &execution.tools {
+            *tools.entry(tool.clone()).or_insert(0usize) += 1;
+        }
+    }
+    let total_latency: u64 = executions.iter().map(|e| e.latency_ms).sum();
+    let count = executions.len().max(1) as f64;
+
+    BehaviorFingerprint {
+        executions: executions.len(),
+        output_shapes: shares(shapes),
+        mean_latency_ms: total_latency as f64 / count,
+        tool_mix: shares(tools),
+        error_rate: errors as f64 / count,
+        window_start,
+        window_end,
+    }
+}
+
+fn shares(counts: BTreeMap<String, usize>) -> BTreeMap<String, f64> {
+    let total = counts.values().sum::<usize>().max(1) as f64;
+    counts.into_iter().map(|(key, count)| (key, count as f64 / total)).collect()
+}
+
+/// Structure of a JSON value with its data left out, e.g. `{"items":[number],"title":string}`
+pub fn output_shape(value: &serde_json::Value) -> String {
+    use serde_json::Value;
+
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(_) => "boolean".to_string(),
+        Value::Number(_) => "number".to_string(),
+        Value::String(_) => "string".to_string(),
+        Value::Array(items) => {
+            let shapes: BTreeSet<String> = items.iter().map(output_shape).collect();
+            format!("[{}]", shapes.into_iter().collect::<Vec<_>>().join("|"))
+        }
+        Value::Object(fields) => {
+            let mut keys: Vec<&String> = fields.keys().collect();
+            keys.sort();
+            let fields: Vec<String> = keys.into_iter().map(|key| format!("{:?}:{}", key, output_shape(&fields[key]))).collect();
+            format!("{{{}}}", fields.join(","))
+        }
+    }
+}
+
+/// Half the summed absolute differences of two distributions: 0 when equal, 1 when disjoint
+fn total_variation(a: &BTreeMap<String, f64>, b: &BTreeMap<String, f64>) -> f64 {
+    let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    keys.into_iter()
+        .map(|key| (a.get(key).unwrap_or(&0.0) - b.get(key).unwrap_or(&0.0)).abs())
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Dimensions of `current` that moved past their threshold since `previous`
+pub fn compare(previous: &BehaviorFingerprint, current: &BehaviorFingerprint, config: &DriftConfig) -> Vec<DimensionDrift> {
+    let latency_change = if previous.mean_latency_ms > 0.0 {
+        (current.mean_latency_ms - previous.mean_latency_ms).abs() / previous.mean_latency_ms
+    } else {
+        0.0
+    };
+    let candidates = [
+        (
+            DriftDimension::OutputShape,
+            total_variation(&previous.output_shapes, &current.output_shapes),
+            config.output_shape_threshold,
+            serde_json::json!(previous.output_shapes),
+            serde_json::json!(current.output_shapes),
+        ),
+        (
+            DriftDimension::Latency,
+            latency_change,
+            config.latency_change_threshold,
+            serde_json::json!(previous.mean_latency_ms),
+            serde_json::json!(current.mean_latency_ms),
+        ),
+        (
+            DriftDimension::ToolMix,
+            total_variation(&previous.tool_mix, &current.tool_mix),
+            config.tool_mix_threshold,
+            serde_json::json!(previous.tool_mix),
+            serde_json::json!(current.tool_mix),
+        ),
+        (
+            DriftDimension::ErrorRate,
+            (current.error_rate - previous.error_rate).abs(),
+            config.error_rate_threshold,
+            serde_json::json!(previous.error_rate),
+            serde_json::json!(current.error_rate),
+        ),
+    ];
+
+    candidates
+        .into_iter()
+        .filter(|(_, magnitude, threshold, _, _)| magnitude > threshold)
+        .map(|(dimension, magnitude, threshold, previous, current)| DimensionDrift {
+            dimension,
+            magnitude,
+            threshold,
+            previous,
+            current,
+        })
+        .collect()
+}
+
+impl From<DriftError> for ApiError {
+    fn from(err: DriftError) -> Self {
+        match err {
+            DriftError::NotFound { .. } => ApiError::NotFound(err.to_string()),
+        }
+    }
+}
+
+/// `GET /api/v1/agents/:id/drift`
+#[utoipa::path(
+    get,
+    path = "/api/v1/agents/{id}/drift",
+    tag = "agents",
+    params(("id" = String, Path, description = "Agent id")),
+    responses(
+        (status = 200, description = "Drift reports of the agent, oldest first", body = [DriftReport]),
+    )
+)]
+pub async fn list_drift_reports(
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+) -> Json<Vec<DriftReport>> {
+    Json(state.drift_monitor.reports(&id).await)
+}
+
+/// `POST /api/v1/agents/:id/drift/:report_id/review`
+#[utoipa::path(
+    post,
+    path = "/api/v1/agents/{id}/drift/{report_id}/review",
+    tag = "agents",
+    params(
+        ("id" = String, Path, description = "Agent id"),
+        ("report_id" = String, Path, description = "Drift report id"),
+    ),
+    responses(
+        (status = 200, description = "The reviewed report; schedules resume once none is open", body = DriftReport),
+        (status = 404, description = "No such report for the agent"),
+    )
+)]
+pub async fn review_drift_report(
+    State(state): State<EngineState>,
+    Path((id, report_id)): Path<(String, String)>,
+) -> Result<Json<DriftReport>, ApiError> {
+    Ok(Json(state.drift_monitor.review(&id, &report_id).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records schedule pauses and resumes; each agent has two schedules
+    #[derive(Default)]
+    struct RecordingSchedules {
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl ScheduleControl for RecordingSchedules {
+        fn pause_agent_schedules<'a>(&'a self, agent_id: &'a str) -> BoxFuture<'a, Result<usize, String>> {
+            self.calls.lock().unwrap().push(format!("pause {}", agent_id));
+            Box::pin(async { Ok(2) })
+        }
+
+        fn resume_agent_schedules<'a>(&'a self, agent_id: &'a str) -> BoxFuture<'a, Result<usize, String>> {
+            self.calls.lock().unwrap().push(format!("resume {}", agent_id));
+            Box::pin(async { Ok(2) })
+        }
+    }
+
+    fn execution(output: serde_json::Value) -> ObservedExecution {
+        ObservedExecution {
+            output: Ok(output),
+            latency_ms: 120,
+            tools: vec!["search".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_output_shape_change_is_reported_and_pauses_schedules() {
+        let schedules = Arc::new(RecordingSchedules::default());
+        let config = DriftConfig {
+            window_executions: 10,
+            pause_schedules: true,
+            ..Default::default()
+        };
+        let monitor = DriftMonitor::new(config, WebhookService::default()).with_schedule_control(schedules.clone());
+
+        // The first window only sets the baseline
+        for i in 0..10 {
+            let output = serde_json::json!({"summary": format!("report {}", i), "score": i});
+            assert!(monitor.record("acme", "summarizer", execution(output)).await.is_none());
+        }
+
+        // Out of band, the summary became a list of bullet points
+        let mut report = None;
+        for i in 0..10 {
+            let output = serde_json::json!({"summary": [format!("point {}", i)], "score": i});
+            report = monitor.record("acme", "summarizer", execution(output)).await;
+        }
+        let report = report.expect("drift is reported when the second window completes");
+
+        assert_eq!(report.drifted.len(), 1);
+        let drift = &report.drifted[0];
+        assert_eq!(drift.dimension, DriftDimension::OutputShape);
+        assert_eq!(drift.magnitude, 1.0);
+        assert_eq!(drift.previous, serde_json::json!({r#"{"score":number,"summary":string}"#: 1.0}));
+        assert_eq!(drift.current, serde_json::json!({r#"{"score":number,"summary":[string]}"#: 1.0}));
+        assert_eq!((report.previous.executions, report.current.executions), (10, 10));
+        assert_eq!(report.current.mean_latency_ms, 120.0);
+        assert_eq!(report.status, DriftReportStatus::Open);
+
+        // The report is attached to the agent, and its schedules wait for review
+        assert_eq!(report.schedules_paused, 2);
+        assert_eq!(monitor.reports("summarizer").await.len(), 1);
+        assert_eq!(*schedules.calls.lock().unwrap(), vec!["pause summarizer"]);
+
+        let reviewed = monitor.review("summarizer", &report.id).await.unwrap();
+        assert_eq!(reviewed.status, DriftReportStatus::Reviewed);
+        assert_eq!(*schedules.calls.lock().unwrap(), vec!["pause summarizer", "resume summarizer"]);
+        assert!(monitor.review("summarizer", "missing").await.is_err());
+
+        // A third window behaving like the second is not drift
+        for i in 0..10 {
+            let output = serde_json::json!({"summary": [format!("point {}", i)], "score": i});
+            assert!(monitor.record("acme", "summarizer", execution(output)).await.is_none());
+        }
+
+        // Without `pause_schedules` drift is reported but schedules keep running
+        let monitor = DriftMonitor::new(DriftConfig { window_executions: 2, ..Default::default() }, WebhookService::default())
+            .with_schedule_control(schedules.clone());
+        for output in [serde_json::json!("ok"), serde_json::json!("ok"), serde_json::json!(1), serde_json::json!(2)] {
+            monitor.record("acme", "classifier", execution(output)).await;
+        }
+        let reports = monitor.reports("classifier").await;
+        assert_eq!((reports.len(), reports[0].schedules_paused), (1, 0));
+        assert_eq!(schedules.calls.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_compare_flags_only_dimensions_past_their_threshold() {
+        let window = |latency_ms: u64, failures: usize, tool: &str| {
+            let executions: Vec<ObservedExecution> = (0..10)
+                .map(|i| ObservedExecution {
+                    output: if i < failures { Err("timeout".to_string()) } else { Ok(serde_json::json!({"ok": true})) },
+                    latency_ms,
+                    tools: vec![tool.to_string()],
+                })
+                .collect();
+            fingerprint(&executions, Utc::now(), Utc::now())
+        };
+        let config = DriftConfig::default();
+
+        let baseline = window(100, 0, "search");
+        assert!(compare(&baseline, &window(140, 0, "search"), &config).is_empty());
+
+        let drifted = compare(&baseline, &window(200, 3, "browse"), &config);
+        let dimensions: Vec<(DriftDimension, f64)> = drifted.iter().map(|d| (d.dimension, d.magnitude)).collect();
+        assert_eq!(
+            dimensions,
+            vec![(DriftDimension::Latency, 1.0), (DriftDimension::ToolMix, 1.0), (DriftDimension::ErrorRate, 0.3)]
+        );
+    }
+}
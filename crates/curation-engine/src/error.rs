@@ -0,0 +1,193 @@
+//! Typed API errors
+//!
+//! Handlers return `Result<T, ApiError>`. Each variant maps to one status
+//! code and is rendered as an RFC 7807 `application/problem+json` body with
+//! `type`, `title`, `status` and `detail`, so every failure a client sees has
+//! the same shape. Internal errors are logged in full but reported without
+//! their cause.
+
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::live_config::FieldError;
+
+/// Media type of problem responses
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Failure of an API request
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    BadRequest(String),
+    /// Missing, malformed or expired credentials
+    #[error("{0}")]
+    Unauthorized(String),
+    /// A request body that failed validation, with every offending field
+    #[error("{detail}")]
+    InvalidFields { detail: String, fields: Vec<FieldError> },
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{detail}")]
+    TooManyRequests { detail: String, retry_after_secs: Option<u64> },
+    #[error("{0}")]
+    Unavailable(String),
+    /// An upstream the engine depends on failed
+    #[error("{0}")]
+    BadGateway(String),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::BadRequest(_) | ApiError::InvalidFields { .. } => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::BadGateway(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Stable identifier of the problem type, used in the `type` URI
+    fn slug(&self) -> &'static str {
+        match self {
+            ApiError::NotFound(_) => "not-found",
+            ApiError::BadRequest(_) => "bad-request",
+            ApiError::InvalidFields { .. } => "invalid-fields",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::TooManyRequests { .. } => "too-many-requests",
+            ApiError::Unavailable(_) => "unavailable",
+            ApiError::BadGateway(_) => "bad-gateway",
+            ApiError::Internal(_) => "internal",
+        }
+    }
+
+    pub fn to_problem(&self) -> ProblemDetails {
+        let status = self.status();
+        let detail = match self {
+            // The cause may name internals; it goes to the log instead
+            ApiError::Internal(_) => "The server failed to handle the request".to_string(),
+            other => other.to_string(),
+        };
+        ProblemDetails {
+            problem_type: format!("/problems/{}", self.slug()),
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            status: status.as_u16(),
+            detail,
+            fields: match self {
+                ApiError::InvalidFields { fields, .. } => Some(fields.clone()),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Opaque 500 for failures of the service layer
+impl From<Box<dyn std::error::Error + Send + Sync>> for ApiError {
+    fn from(err: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        ApiError::Internal(err.to_string())
+    }
+}
+
+/// RFC 7807 problem body
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ProblemDetails {
+    /// URI reference identifying the problem type
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// Short summary of the problem type
+    pub title: String,
+    pub status: u16,
+    /// What went wrong with this request
+    pub detail: String,
+    /// Invalid fields, for `invalid-fields` problems
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<FieldError>>,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if let ApiError::Internal(cause) = &self {
+            error!("💥 Request failed: {}", cause);
+        }
+        let problem = self.to_problem();
+        let mut response = (self.status(), Json(problem)).into_response();
+        let headers = response.headers_mut();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        match self {
+            ApiError::TooManyRequests { retry_after_secs: Some(secs), .. } => {
+                headers.insert(header::RETRY_AFTER, HeaderValue::from(secs));
+            }
+            ApiError::Unauthorized(_) => {
+                headers.insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            }
+            _ => {}
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::serve;
+    use axum::{routing::get, Router};
+
+    #[tokio::test]
+    async fn test_errors_render_as_problem_json() {
+        let app = Router::new()
+            .route("/missing", get(|| async { Err::<(), _>(ApiError::NotFound("Agent a1 not found".to_string())) }))
+            .route(
+                "/busy",
+                get(|| async {
+                    Err::<(), _>(ApiError::TooManyRequests { detail: "Slow down".to_string(), retry_after_secs: Some(3) })
+                }),
+            )
+            .route(
+                "/broken",
+                get(|| async {
+                    let cause: Box<dyn std::error::Error + Send + Sync> = "connection pool exhausted at db-7".into();
+                    Err::<(), ApiError>(cause.into())
+                }),
+            );
+        let base = serve(app).await;
+
+        let response = reqwest::get(format!("{}/missing", base)).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()["content-type"], PROBLEM_JSON);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "type": "/problems/not-found",
+                "title": "Not Found",
+                "status": 404,
+                "detail": "Agent a1 not found",
+            })
+        );
+
+        let response = reqwest::get(format!("{}/busy", base)).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["retry-after"], "3");
+
+        // Internal causes stay out of the response
+        let response = reqwest::get(format!("{}/broken", base)).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::INTERNAL_SERVER_ERROR);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["type"], "/problems/internal");
+        assert!(!body["detail"].as_str().unwrap().contains("db-7"));
+    }
+}
@@ -0,0 +1,245 @@
+//! Streaming WASM module execution over server-sent events
+//!
+//! `POST /api/v1/wasm/modules/:id/execute/stream` runs a module on a spawned
+//! task and forwards each chunk of output it emits as an `output` event. Once
+//! the module returns, a final `done` event carries the result and the stream
+//! ends. A client that disconnects early drops the stream, which aborts the
+//! execution.
+
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::{
+    extract::{Path, State},
+    response::sse::{Event, KeepAlive, Sse},
+    Json,
+};
+use futures::{future::BoxFuture, stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::{sync::mpsc, task::JoinHandle};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::{
+    authz::{RequireScope, WasmExecute},
+    error::ApiError,
+    EngineState,
+};
+
+/// Chunks buffered between the module and a slow client
+const OUTPUT_BUFFER: usize = 64;
+
+/// Stream a chunk of output was written to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    #[default]
+    Stdout,
+    Stderr,
+}
+
+/// A piece of output emitted while a module runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct OutputChunk {
+    #[serde(default)]
+    pub stream: OutputStream,
+    pub data: String,
+}
+
+impl OutputChunk {
+    pub fn stdout(data: impl Into<String>) -> Self {
+        Self { stream: OutputStream::Stdout, data: data.into() }
+    }
+
+    pub fn stderr(data: impl Into<String>) -> Self {
+        Self { stream: OutputStream::Stderr, data: data.into() }
+    }
+}
+
+/// Runs a WASM module, sending its output to `output` as it is produced
+pub trait StreamingWasmExecutor: Send + Sync {
+    fn execute_streaming<'a>(
+        &'a self,
+        module_id: &'a str,
+        input: &'a serde_json::Value,
+        output: mpsc::Sender<OutputChunk>,
+    ) -> BoxFuture<'a, Result<serde_json::Value, String>>;
+}
+
+/// Payload of the final `done` event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ExecutionDone {
+    pub module_id: String,
+    pub success: bool,
+    #[serde(default)]
+    pub output: Option<serde_json::Value>,
+    #[serde(default)]
+    pub error: Option<String>,
+    pub execution_time_ms: u64,
+    /// Output chunks sent before this event
+    pub chunks: u64,
+}
+
+/// Aborts the execution task when the stream is dropped before it finishes
+struct AbortOnDrop(JoinHandle<Result<serde_json::Value, String>>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Output events of an execution followed by its `done` event
+pub fn execution_events(
+    executor: Arc<dyn StreamingWasmExecutor>,
+    module_id: String,
+    input: serde_json::Value,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let (tx, rx) = mpsc::channel(OUTPUT_BUFFER);
+    let started = Instant::now();
+    let task_module_id = module_id.clone();
+    let mut task = AbortOnDrop(tokio::spawn(async move {
+        executor.execute_streaming(&task_module_id, &input, tx).await
+    }));
+
+    let chunks = Arc::new(AtomicU64::new(0));
+    let counted = chunks.clone();
+    let output = ReceiverStream::new(rx).map(move |chunk| {
+        counted.fetch_add(1, Ordering::Relaxed);
+        json_event("output", &chunk)
+    });
+
+    // The channel closes once the task drops its sender, so `done` always follows the last chunk
+    let done = stream::once(async move {
+        let result = (&mut task.0)
+            .await
+            .unwrap_or_else(|e| Err(format!("Execution task failed: {}", e)));
+        let done = ExecutionDone {
+            success: result.is_ok(),
+            error: result.as_ref().err().cloned(),
+            output: result.ok(),
+            execution_time_ms: started.elapsed().as_millis() as u64,
+            chunks: chunks.load(Ordering::Relaxed),
+            module_id,
+        };
+        if done.success {
+            info!("📡 Streamed execution of module {} finished after {} chunk(s)", done.module_id, done.chunks);
+        } else {
+            warn!("📡 Streamed execution of module {} failed: {}", done.module_id, done.error.as_deref().unwrap_or_default());
+        }
+        json_event("done", &done)
+    });
+
+    output.chain(done)
+}
+
+fn json_event<T: Serialize>(name: &str, payload: &T) -> Result<Event, Infallible> {
+    Ok(Event::default()
+        .event(name)
+        .data(serde_json::to_string(payload).unwrap_or_default()))
+}
+
+/// `POST /api/v1/wasm/modules/:id/execute/stream`
+#[utoipa::path(
+    post,
+    path = "/api/v1/wasm/modules/{id}/execute/stream",
+    tag = "wasm",
+    params(("id" = String, Path, description = "WASM module id")),
+    request_body(content = serde_json::Value, description = "Input passed to the module"),
+    responses(
+        (
+            status = 200,
+            description = "Server-sent events: an `output` event (OutputChunk) per chunk of output, then one `done` event (ExecutionDone)",
+            content_type = "text/event-stream",
+            body = String,
+        ),
+        (status = 403, description = "Token lacks the `wasm:execute` scope"),
+        (status = 503, description = "No streaming executor is configured"),
+    )
+)]
+pub async fn execute_wasm_module_stream(
+    _scope: RequireScope<WasmExecute>,
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+    Json(input): Json<serde_json::Value>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let executor = state
+        .streaming_executor
+        .clone()
+        .ok_or_else(|| ApiError::Unavailable("No streaming executor is configured".to_string()))?;
+    Ok(Sse::new(execution_events(executor, id, input)).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::serve;
+    use axum::{routing::post, Router};
+
+    /// Echoes each word of `input.text` as a chunk; fails when `input.fail` is set
+    struct WordEcho;
+
+    impl StreamingWasmExecutor for WordEcho {
+        fn execute_streaming<'a>(
+            &'a self,
+            _module_id: &'a str,
+            input: &'a serde_json::Value,
+            output: mpsc::Sender<OutputChunk>,
+        ) -> BoxFuture<'a, Result<serde_json::Value, String>> {
+            Box::pin(async move {
+                let text = input["text"].as_str().unwrap_or_default();
+                for word in text.split_whitespace() {
+                    output.send(OutputChunk::stdout(word)).await.map_err(|e| e.to_string())?;
+                }
+                if input["fail"].as_bool().unwrap_or(false) {
+                    output.send(OutputChunk::stderr("trap")).await.map_err(|e| e.to_string())?;
+                    return Err("module trapped".to_string());
+                }
+                Ok(serde_json::json!({"words": text.split_whitespace().count()}))
+            })
+        }
+    }
+
+    /// `(event, data)` pairs of a server-sent events body
+    fn parse_events(body: &str) -> Vec<(String, serde_json::Value)> {
+        body.split("\n\n")
+            .filter_map(|frame| {
+                let field = |name: &str| frame.lines().find_map(|line| line.strip_prefix(name)).map(str::to_string);
+                Some((field("event: ")?, serde_json::from_str(&field("data: ")?).ok()?))
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_stream_ends_with_done_event() {
+        let executor: Arc<dyn StreamingWasmExecutor> = Arc::new(WordEcho);
+        let app = Router::new().route(
+            "/modules/:id/execute/stream",
+            post(move |Path(id): Path<String>, Json(input): Json<serde_json::Value>| async move {
+                Sse::new(execution_events(executor, id, input))
+            }),
+        );
+        let url = format!("{}/modules/echo/execute/stream", serve(app).await);
+
+        let client = reqwest::Client::new();
+        let response = client.post(&url).json(&serde_json::json!({"text": "hello streaming world"})).send().await.unwrap();
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        let events = parse_events(&response.text().await.unwrap());
+        let names: Vec<&str> = events.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["output", "output", "output", "done"]);
+        assert_eq!(events[1].1["data"], "streaming");
+        let done: ExecutionDone = serde_json::from_value(events[3].1.clone()).unwrap();
+        assert!(done.success);
+        assert_eq!((done.chunks, done.output), (3, Some(serde_json::json!({"words": 3}))));
+
+        let body = client.post(&url).json(&serde_json::json!({"text": "boom", "fail": true})).send().await.unwrap().text().await.unwrap();
+        let events = parse_events(&body);
+        assert_eq!(events[1].1["stream"], "stderr");
+        let done: ExecutionDone = serde_json::from_value(events.last().unwrap().1.clone()).unwrap();
+        assert!(!done.success);
+        assert_eq!((done.chunks, done.error.as_deref()), (2, Some("module trapped")));
+    }
+}
@@ -0,0 +1,493 @@
+//! Export of completed job results to S3-compatible object storage
+//!
+//! When a job completes, its result and metadata are serialized to JSON and
+//! uploaded on a spawned task, so exports never affect job success. Uploads
+//! are signed with AWS SigV4, carry the body SHA-256 for integrity checking,
+//! and are retried with exponential backoff. After `max_attempts` failures the
+//! export is marked dead-lettered.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+
+use crate::correlation::{self, WithCorrelationId};
+
+/// Default object key prefix
+pub const DEFAULT_PREFIX_TEMPLATE: &str = "exports/{tenant}/{date}/";
+
+/// Export sink configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportSinkConfig {
+    /// S3-compatible endpoint, e.g. `https://s3.us-east-1.amazonaws.com` or `http://minio:9000`
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    /// Name of the secret holding the access key id
+    pub access_key_id_secret: String,
+    /// Name of the secret holding the secret access key
+    pub secret_access_key_secret: String,
+    /// Key prefix; `{tenant}`, `{date}` (YYYY-MM-DD) and `{job_id}` are substituted
+    #[serde(default = "default_prefix_template")]
+    pub prefix_template: String,
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Backoff before the second attempt; doubles on each further attempt
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+}
+
+fn default_prefix_template() -> String {
+    DEFAULT_PREFIX_TEMPLATE.to_string()
+}
+
+fn default_max_attempts() -> u32 {
+    5
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    1000
+}
+
+impl ExportSinkConfig {
+    /// Object key for a job: the rendered prefix followed by `<job_id>.json`
+    pub fn object_key(&self, tenant: &str, job_id: &str, completed_at: DateTime<Utc>) -> String {
+        let prefix = self
+            .prefix_template
+            .replace("{tenant}", tenant)
+            .replace("{date}", &completed_at.format("%Y-%m-%d").to_string())
+            .replace("{job_id}", job_id);
+        format!("{}{}.json", prefix, job_id)
+    }
+}
+
+/// Source of named secrets such as storage credentials
+pub trait SecretSource: Send + Sync {
+    fn get(&self, name: &str) -> Option<String>;
+}
+
+/// Secrets read from environment variables of the same name
+#[derive(Debug, Clone, Default)]
+pub struct EnvSecrets;
+
+impl SecretSource for EnvSecrets {
+    fn get(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+}
+
+impl SecretSource for HashMap<String, String> {
+    fn get(&self, name: &str) -> Option<String> {
+        HashMap::get(self, name).cloned()
+    }
+}
+
+/// A completed job to export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletedJob {
+    pub job_id: String,
+    pub tenant: String,
+    pub completed_at: DateTime<Utc>,
+    pub result: serde_json::Value,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
+/// Export state of a job, as reported by job status
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ExportStatus {
+    Pending,
+    Retrying { attempts: u32, last_error: String },
+    Exported { object_key: String, checksum: String },
+    DeadLettered { attempts: u32, last_error: String },
+}
+
+/// Export errors
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("Missing secret '{0}'")]
+    MissingSecret(String),
+    #[error("Upload failed: {0}")]
+    Upload(String),
+}
+
+/// Uploads completed job results and tracks their export status
+#[derive(Clone)]
+pub struct ResultExporter {
+    config: Arc<ExportSinkConfig>,
+    secrets: Arc<dyn SecretSource>,
+    http_client: reqwest::Client,
+    statuses: Arc<RwLock<HashMap<String, ExportStatus>>>,
+}
+
+impl ResultExporter {
+    pub fn new(config: ExportSinkConfig, secrets: Arc<dyn SecretSource>) -> Self {
+        Self {
+            config: Arc::new(config),
+            secrets,
+            http_client: reqwest::Client::new(),
+            statuses: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Queue export of a completed job without waiting for it
+    pub async fn on_job_completed(&self, job: CompletedJob) {
+        self.statuses.write().await.insert(job.job_id.clone(), ExportStatus::Pending);
+        let exporter = self.clone();
+        tokio::spawn(correlation::inherit(async move { exporter.export(job).await }));
+    }
+
+    /// Export status of a job, if it was queued for export
+    pub async fn status(&self, job_id: &str) -> Option<ExportStatus> {
+        self.statuses.read().await.get(job_id).cloned()
+    }
+
+    /// Jobs whose export was given up on
+    pub async fn dead_letters(&self) -> Vec<String> {
+        self.statuses
+            .read()
+            .await
+            .iter()
+            .filter(|(_, status)| matches!(status, ExportStatus::DeadLettered { .. }))
+            .map(|(job_id, _)| job_id.clone())
+            .collect()
+    }
+
+    /// Upload a job, retrying with backoff, and record the outcome
+    async fn export(&self, job: CompletedJob) -> ExportStatus {
+        let object_key = self.config.object_key(&job.tenant, &job.job_id, job.completed_at);
+        let body = match serde_json::to_vec(&job) {
+            Ok(body) => body,
+            Err(e) => {
+                error!("📦 Failed to serialize export for job {}: {}", job.job_id, e);
+                return self.record(&job.job_id, dead_letter(1, e.to_string())).await;
+            }
+        };
+        let checksum = hex::encode(Sha256::digest(&body));
+        let max_attempts = self.config.max_attempts.max(1);
+
+        for attempt in 1..=max_attempts {
+            let failure = match self.put_object(&object_key, &body, &checksum).await {
+                Ok(()) => {
+                    info!("📦 Exported job {} to {}", job.job_id, object_key);
+                    return self
+                        .record(&job.job_id, ExportStatus::Exported { object_key, checksum })
+                        .await;
+                }
+                Err(e) => e.to_string(),
+            };
+
+            if attempt == max_attempts {
+                error!(
+                    "📦 Dead-lettered export of job {} after {} attempt(s): {}",
+                    job.job_id, attempt, failure
+                );
+                return self.record(&job.job_id, dead_letter(attempt, failure)).await;
+            }
+
+            let backoff = self.config.initial_backoff_ms.saturating_mul(1 << (attempt - 1).min(16));
+            warn!(
+                "📦 Export of job {} failed ({}), retrying in {}ms (attempt {}/{})",
+                job.job_id, failure, backoff, attempt + 1, max_attempts
+            );
+            self.record(
+                &job.job_id,
+                ExportStatus::Retrying {
+                    attempts: attempt,
+                    last_error: failure,
+                },
+            )
+            .await;
+            tokio::time::sleep(Duration::from_millis(backoff)).await;
+        }
+
+        unreachable!("the last attempt always returns")
+    }
+
+    async fn record(&self, job_id: &str, status: ExportStatus) -> ExportStatus {
+        self.statuses.write().await.insert(job_id.to_string(), status.clone());
+        status
+    }
+
+    /// PUT an object with a SigV4-signed path-style request
+    async fn put_object(&self, key: &str, body: &[u8], checksum: &str) -> Result<(), ExportError> {
+        let access_key = self
+            .secrets
+            .get(&self.config.access_key_id_secret)
+            .ok_or_else(|| ExportError::MissingSecret(self.config.access_key_id_secret.clone()))?;
+        let secret_key = self
+            .secrets
+            .get(&self.config.secret_access_key_secret)
+            .ok_or_else(|| ExportError::MissingSecret(self.config.secret_access_key_secret.clone()))?;
+
+        let endpoint = self.config.endpoint.trim_end_matches('/');
+        let path = format!("/{}/{}", uri_encode(&self.config.bucket), encode_key(key));
+        let url = reqwest::Url::parse(&format!("{}{}", endpoint, path)).map_err(|e| ExportError::Upload(e.to_string()))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            _ => return Err(ExportError::Upload(format!("Invalid endpoint '{}'", endpoint))),
+        };
+
+        let now = Utc::now();
+        let authorization = sigv4_authorization(&SigningRequest {
+            method: "PUT",
+            host: &host,
+            path: &path,
+            payload_sha256: checksum,
+            region: &self.config.region,
+            access_key: &access_key,
+            secret_key: &secret_key,
+            now,
+        });
+
+        let response = self
+            .http_client
+            .put(url)
+            .header("Content-Type", "application/json")
+            .header("x-amz-content-sha256", checksum)
+            .header("x-amz-date", now.format("%Y%m%dT%H%M%SZ").to_string())
+            .header("Authorization", authorization)
+            .with_correlation_id()
+            .body(body.to_vec())
+            .send()
+            .await
+            .map_err(|e| ExportError::Upload(e.to_string()))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(ExportError::Upload(format!("HTTP {}", response.status())))
+        }
+    }
+}
+
+fn dead_letter(attempts: u32, last_error: String) -> ExportStatus {
+    ExportStatus::DeadLettered { attempts, last_error }
+}
+
+struct SigningRequest<'a> {
+    method: &'a str,
+    host: &'a str,
+    /// Already URI-encoded path
+    path: &'a str,
+    payload_sha256: &'a str,
+    region: &'a str,
+    access_key: &'a str,
+    secret_key: &'a str,
+    now: DateTime<Utc>,
+}
+
+/// `Authorization` header value for an AWS SigV4 request to S3
+fn sigv4_authorization(req: &SigningRequest<'_>) -> String {
+    let amz_date = req.now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = req.now.format("%Y%m%d").to_string();
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+
+    let canonical_request = format!(
+        "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        req.method, req.path, req.host, req.payload_sha256, amz_date, signed_headers, req.payload_sha256
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, req.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = hmac_sha256(format!("AWS4{}", req.secret_key).as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, req.region.as_bytes());
+    let key = hmac_sha256(&key, b"s3");
+    let key = hmac_sha256(&key, b"aws4_request");
+    let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        req.access_key, scope, signed_headers, signature
+    )
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters
+fn uri_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Encode an object key segment by segment, keeping the `/` separators
+fn encode_key(key: &str) -> String {
+    key.split('/').map(uri_encode).collect::<Vec<_>>().join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::serve;
+    use axum::{
+        body::Bytes,
+        extract::{Path, State},
+        http::{HeaderMap, StatusCode},
+        routing::put,
+        Router,
+    };
+    use chrono::TimeZone;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    type StoredObject = (Vec<u8>, HeaderMap);
+
+    /// Minimal S3 stand-in: records PUT objects, failing the first `fail_first` requests
+    #[derive(Clone, Default)]
+    struct S3Stub {
+        objects: Arc<RwLock<HashMap<String, StoredObject>>>,
+        requests: Arc<AtomicU32>,
+        fail_first: u32,
+    }
+
+    async fn put_object(
+        State(stub): State<S3Stub>,
+        Path((bucket, key)): Path<(String, String)>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> StatusCode {
+        if stub.requests.fetch_add(1, Ordering::SeqCst) < stub.fail_first {
+            return StatusCode::SERVICE_UNAVAILABLE;
+        }
+        stub.objects.write().await.insert(format!("{}/{}", bucket, key), (body.to_vec(), headers));
+        StatusCode::OK
+    }
+
+    async fn start_stub(fail_first: u32) -> (S3Stub, String) {
+        let stub = S3Stub {
+            fail_first,
+            ..Default::default()
+        };
+        let app = Router::new().route("/:bucket/*key", put(put_object)).with_state(stub.clone());
+        let endpoint = serve(app).await;
+        (stub, endpoint)
+    }
+
+    fn exporter(endpoint: String, max_attempts: u32) -> ResultExporter {
+        let secrets: HashMap<String, String> = [
+            ("S3_ACCESS_KEY".to_string(), "AKIDEXAMPLE".to_string()),
+            ("S3_SECRET_KEY".to_string(), "secret".to_string()),
+        ]
+        .into();
+        ResultExporter::new(
+            ExportSinkConfig {
+                endpoint,
+                bucket: "results".to_string(),
+                region: "us-east-1".to_string(),
+                access_key_id_secret: "S3_ACCESS_KEY".to_string(),
+                secret_access_key_secret: "S3_SECRET_KEY".to_string(),
+                prefix_template: DEFAULT_PREFIX_TEMPLATE.to_string(),
+                max_attempts,
+                initial_backoff_ms: 10,
+            },
+            Arc::new(secrets),
+        )
+    }
+
+    fn job() -> CompletedJob {
+        CompletedJob {
+            job_id: "job-1".to_string(),
+            tenant: "acme".to_string(),
+            completed_at: Utc.with_ymd_and_hms(2026, 3, 14, 9, 26, 53).unwrap(),
+            result: serde_json::json!({"answer": 42}),
+            metadata: serde_json::json!({"agent": "researcher"}),
+        }
+    }
+
+    #[test]
+    fn test_object_key_template() {
+        let mut config = exporter(String::new(), 1).config.as_ref().clone();
+        let completed_at = job().completed_at;
+        assert_eq!(config.object_key("acme", "job-1", completed_at), "exports/acme/2026-03-14/job-1.json");
+
+        config.prefix_template = "{date}/{tenant}/{job_id}/".to_string();
+        assert_eq!(config.object_key("acme", "job-1", completed_at), "2026-03-14/acme/job-1/job-1.json");
+    }
+
+    #[test]
+    fn test_sigv4_authorization() {
+        let authorization = sigv4_authorization(&SigningRequest {
+            method: "PUT",
+            host: "127.0.0.1:9000",
+            path: &format!("/results/{}", encode_key("exports/acme/job 1.json")),
+            payload_sha256: &hex::encode(Sha256::digest(b"{\"a\":1}")),
+            region: "us-east-1",
+            access_key: "AKID",
+            secret_key: "secret",
+            now: Utc.with_ymd_and_hms(2026, 10, 16, 11, 47, 54).unwrap(),
+        });
+        assert_eq!(
+            authorization,
+            "AWS4-HMAC-SHA256 Credential=AKID/20261016/us-east-1/s3/aws4_request, \
+             SignedHeaders=host;x-amz-content-sha256;x-amz-date, \
+             Signature=ae6b3f296fa78986f88d3e21058bdcf33bc248512b165c07e32d3988b2591329"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_retries_then_uploads() {
+        let (stub, endpoint) = start_stub(2).await;
+        let exporter = exporter(endpoint, 5);
+
+        let status = exporter.export(job()).await;
+        let ExportStatus::Exported { object_key, checksum } = status else {
+            panic!("unexpected status {:?}", status);
+        };
+        assert_eq!(object_key, "exports/acme/2026-03-14/job-1.json");
+        assert_eq!(stub.requests.load(Ordering::SeqCst), 3);
+
+        let objects = stub.objects.read().await;
+        let (body, headers) = &objects["results/exports/acme/2026-03-14/job-1.json"];
+        let uploaded: CompletedJob = serde_json::from_slice(body).unwrap();
+        assert_eq!(uploaded.result, serde_json::json!({"answer": 42}));
+        assert_eq!(uploaded.metadata["agent"], "researcher");
+        assert_eq!(headers["x-amz-content-sha256"], checksum.as_str());
+        assert_eq!(checksum, hex::encode(Sha256::digest(body)));
+        assert!(headers["authorization"].to_str().unwrap().starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"));
+    }
+
+    #[tokio::test]
+    async fn test_export_dead_letters_after_max_attempts() {
+        let (stub, endpoint) = start_stub(u32::MAX).await;
+        let exporter = exporter(endpoint, 3);
+
+        exporter.on_job_completed(job()).await;
+        for _ in 0..100 {
+            if matches!(exporter.status("job-1").await, Some(ExportStatus::DeadLettered { .. })) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        assert_eq!(
+            exporter.status("job-1").await,
+            Some(ExportStatus::DeadLettered {
+                attempts: 3,
+                last_error: "Upload failed: HTTP 503 Service Unavailable".to_string(),
+            })
+        );
+        assert_eq!(exporter.dead_letters().await, vec!["job-1".to_string()]);
+        assert_eq!(stub.requests.load(Ordering::SeqCst), 3);
+    }
+}
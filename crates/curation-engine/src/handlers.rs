@@ -0,0 +1,475 @@
+//! Handlers of the core agent, WASM module, job, metrics, MCP and system routes
+//!
+//! List routes take a `ListQuery` and answer with one `Page`: agents filter
+//! on name and status, modules on name, and jobs on module id and state.
+//! Agent changes and MCP tool listings are mirrored into the similarity
+//! index, which similarity search and promotion read from.
+
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+
+use crate::{
+    authz::Tenant,
+    error::ApiError,
+    live_config::ConfigSnapshot,
+    models::{Agent, CreateAgentRequest, ExecutionResult, Job, McpTool, SubmitJobRequest, SystemStatus, UpdateAgentRequest, WasmModuleInfo},
+    pagination::{ListQuery, Page},
+    scheduling::{ClassOverride, ExecutionClass},
+    services::ServiceError,
+    similarity::ItemKind,
+    webhooks::WebhookEventType,
+    EngineState,
+};
+
+/// One page of `items`, which the caller has already filtered
+fn page<T>(query: &ListQuery, items: Vec<T>) -> Result<Json<Page<T>>, ApiError> {
+    query
+        .paginate(items)
+        .map(Json)
+        .map_err(|_| ApiError::BadRequest("Invalid pagination cursor".to_string()))
+}
+
+/// Text an agent is indexed under for similarity search
+fn agent_description(agent: &Agent) -> &str {
+    if agent.description.trim().is_empty() {
+        &agent.name
+    } else {
+        &agent.description
+    }
+}
+
+/// `GET /health`
+pub async fn health_check() -> Json<serde_json::Value> {
+    Json(serde_json::json!({"status": "ok"}))
+}
+
+/// `POST /api/v1/agents`
+pub async fn create_agent(
+    State(state): State<EngineState>,
+    Tenant(tenant): Tenant,
+    Json(request): Json<CreateAgentRequest>,
+) -> Result<(StatusCode, Json<Agent>), ApiError> {
+    let agent = state.agent_service.create(&tenant, request).await?;
+    state.similarity_service.upsert(&tenant, ItemKind::Agent, &agent.id, agent_description(&agent)).await;
+    state.metrics_service.record_agent_operation("create");
+    state.webhook_service.publish(&tenant, WebhookEventType::AgentUpdated, serde_json::to_value(&agent).unwrap_or_default()).await;
+    Ok((StatusCode::CREATED, Json(agent)))
+}
+
+/// `GET /api/v1/agents/:id`
+pub async fn get_agent(
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+    Tenant(tenant): Tenant,
+) -> Result<Json<Agent>, ApiError> {
+    state.agent_service
+        .get(&tenant, &id)
+        .await
+        .map(Json)
+        .ok_or_else(|| ServiceError::AgentNotFound(id).into())
+}
+
+/// `PUT /api/v1/agents/:id`
+pub async fn update_agent(
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+    Tenant(tenant): Tenant,
+    Json(request): Json<UpdateAgentRequest>,
+) -> Result<Json<Agent>, ApiError> {
+    let agent = state.agent_service.update(&tenant, &id, request).await?;
+    state.similarity_service.upsert(&tenant, ItemKind::Agent, &agent.id, agent_description(&agent)).await;
+    state.metrics_service.record_agent_operation("update");
+    state.webhook_service.publish(&tenant, WebhookEventType::AgentUpdated, serde_json::to_value(&agent).unwrap_or_default()).await;
+    Ok(Json(agent))
+}
+
+/// `DELETE /api/v1/agents/:id`
+pub async fn delete_agent(
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+    Tenant(tenant): Tenant,
+) -> Result<StatusCode, ApiError> {
+    state.agent_service.delete(&tenant, &id).await?;
+    state.similarity_service.remove(&tenant, ItemKind::Agent, &id).await;
+    state.metrics_service.record_agent_operation("delete");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// `GET /api/v1/agents`
+pub async fn list_agents(
+    State(state): State<EngineState>,
+    Query(query): Query<ListQuery>,
+    Tenant(tenant): Tenant,
+) -> Result<Json<Page<Agent>>, ApiError> {
+    let agents = state.agent_service
+        .list(&tenant)
+        .await
+        .into_iter()
+        .filter(|agent| query.matches(&agent.name, Some(agent.status.as_str())))
+        .collect();
+    page(&query, agents)
+}
+
+/// Query parameters of a module upload
+#[derive(Debug, Deserialize)]
+pub struct UploadQuery {
+    pub name: String,
+}
+
+/// `POST /api/v1/wasm/modules?name=`, with the module bytes as the body
+pub async fn upload_wasm_module(
+    State(state): State<EngineState>,
+    Query(upload): Query<UploadQuery>,
+    Tenant(tenant): Tenant,
+    wasm: Bytes,
+) -> Result<(StatusCode, Json<WasmModuleInfo>), ApiError> {
+    let module = state.wasm_service.upload(&tenant, &upload.name, &wasm).await?;
+    state.webhook_service.publish(&tenant, WebhookEventType::ModuleUploaded, serde_json::to_value(&module).unwrap_or_default()).await;
+    Ok((StatusCode::CREATED, Json(module)))
+}
+
+/// `GET /api/v1/wasm/modules/:id`
+pub async fn get_wasm_module(
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+    Tenant(tenant): Tenant,
+) -> Result<Json<WasmModuleInfo>, ApiError> {
+    state.wasm_service
+        .get(&tenant, &id)
+        .await
+        .map(Json)
+        .ok_or_else(|| ServiceError::ModuleNotFound(id).into())
+}
+
+/// `POST /api/v1/wasm/modules/:id/execute`, run as interactive work unless
+/// `execution_class` says otherwise
+pub async fn execute_wasm_module(
+    State(state): State<EngineState>,
+    ConfigSnapshot(config): ConfigSnapshot,
+    Path(id): Path<String>,
+    Query(class): Query<ClassOverride>,
+    Tenant(tenant): Tenant,
+    Json(input): Json<serde_json::Value>,
+) -> Result<Json<ExecutionResult>, ApiError> {
+    let wasm = state.wasm_service.clone();
+    let result = state
+        .execution_scheduler
+        .submit(ExecutionClass::resolve(true, class.execution_class), move |checkpoint| async move {
+            wasm.execute(&tenant, &id, &input, &config.wasm, Some(checkpoint)).await
+        })
+        .await
+        .map_err(ServiceError::from)
+        .and_then(|result| result);
+    match &result {
+        Ok(result) => state.metrics_service.record_wasm_execution(true, result.execution_time_ms),
+        Err(ServiceError::Wasm(_)) => state.metrics_service.record_wasm_execution(false, 0),
+        Err(_) => {}
+    }
+    Ok(Json(result?))
+}
+
+/// `GET /api/v1/wasm/modules`
+pub async fn list_wasm_modules(
+    State(state): State<EngineState>,
+    Query(query): Query<ListQuery>,
+    Tenant(tenant): Tenant,
+) -> Result<Json<Page<WasmModuleInfo>>, ApiError> {
+    let modules = state.wasm_service
+        .list(&tenant)
+        .await
+        .into_iter()
+        .filter(|module| query.matches(&module.name, None))
+        .collect();
+    page(&query, modules)
+}
+
+/// `POST /api/v1/jobs`
+pub async fn submit_job(
+    State(state): State<EngineState>,
+    ConfigSnapshot(config): ConfigSnapshot,
+    Tenant(tenant): Tenant,
+    Json(request): Json<SubmitJobRequest>,
+) -> Result<(StatusCode, Json<Job>), ApiError> {
+    let job = state.job_queue.submit(&tenant, request, config.wasm.clone()).await?;
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// `GET /api/v1/jobs/:id`
+pub async fn get_job_status(
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+    Tenant(tenant): Tenant,
+) -> Result<Json<Job>, ApiError> {
+    state.job_queue
+        .get(&tenant, &id)
+        .await
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("Job '{}' not found", id)))
+}
+
+/// `POST /api/v1/jobs/:id/cancel`
+pub async fn cancel_job(
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+    Tenant(tenant): Tenant,
+) -> Result<Json<Job>, ApiError> {
+    Ok(Json(state.job_queue.cancel(&tenant, &id).await?))
+}
+
+/// `GET /api/v1/jobs`
+pub async fn list_jobs(
+    State(state): State<EngineState>,
+    Query(query): Query<ListQuery>,
+    Tenant(tenant): Tenant,
+) -> Result<Json<Page<Job>>, ApiError> {
+    let jobs = state.job_queue
+        .list(&tenant)
+        .await
+        .into_iter()
+        .filter(|job| query.matches(&job.module_id, Some(job.state.as_str())))
+        .collect();
+    page(&query, jobs)
+}
+
+/// `GET /metrics`, in the Prometheus text format
+pub async fn get_metrics(State(state): State<EngineState>) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], state.metrics_service.render())
+}
+
+/// `GET /api/v1/metrics/agents`
+pub async fn get_agent_metrics(State(state): State<EngineState>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "agents": state.agent_service.count().await,
+        "operations": state.metrics_service.agent_operations(),
+    }))
+}
+
+/// `GET /api/v1/metrics/wasm`
+pub async fn get_wasm_metrics(State(state): State<EngineState>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "modules": state.wasm_service.count().await,
+        "executions": state.metrics_service.wasm_executions(),
+    }))
+}
+
+/// `GET /api/v1/mcp/tools`; tools no longer offered leave the index only
+/// when every server answered
+pub async fn list_mcp_tools(State(state): State<EngineState>, Tenant(tenant): Tenant) -> Json<Vec<McpTool>> {
+    let listing = state.mcp_client.list_tools().await;
+    let indexed: Vec<(String, String)> = listing
+        .tools
+        .iter()
+        .map(|tool| (tool.name.clone(), tool.description.clone()))
+        .collect();
+    state.similarity_service.sync(&tenant, ItemKind::McpTool, &indexed, listing.complete).await;
+    Json(listing.tools)
+}
+
+/// `POST /api/v1/mcp/tools/:name/execute`, with the tool arguments as the body
+pub async fn execute_mcp_tool(
+    State(state): State<EngineState>,
+    Path(name): Path<String>,
+    Json(arguments): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    Ok(Json(state.mcp_client.execute_tool(&name, arguments).await?))
+}
+
+/// `GET /api/v1/system/status`
+pub async fn get_system_status(State(state): State<EngineState>) -> Json<SystemStatus> {
+    let (database, cache) = state.agent_service.health().await;
+    Json(SystemStatus {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        uptime_secs: state.started_at.elapsed().as_secs(),
+        database,
+        cache,
+        agents: state.agent_service.count().await,
+        wasm_modules: state.wasm_service.count().await,
+        active_jobs: state.job_queue.active().await,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use crate::config::{McpConfig, McpServerConfig};
+    use crate::test_support::{bearer, serve, serve_engine};
+    use crate::wasm_runtime::tests::ECHO_WAT;
+    use crate::EngineBuilder;
+    use axum::{routing::post, Json, Router};
+    use serde_json::{json, Value};
+
+    async fn get(client: &reqwest::Client, url: String, token: &str) -> reqwest::Response {
+        client.get(url).bearer_auth(token).send().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_list_handlers_page_and_filter() {
+        let base = serve_engine(EngineBuilder::new()).await;
+        let client = reqwest::Client::new();
+        let admin = bearer("", &["admin"]);
+
+        let mut agent_ids = Vec::new();
+        for i in 0..5 {
+            let created: Value = client
+                .post(format!("{}/api/v1/agents", base))
+                .bearer_auth(&admin)
+                .json(&json!({"name": format!("agent-{}", i)}))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            agent_ids.push(created["id"].as_str().unwrap().to_string());
+        }
+        for id in [&agent_ids[1], &agent_ids[3]] {
+            let paused = client
+                .put(format!("{}/api/v1/agents/{}", base, id))
+                .bearer_auth(&admin)
+                .json(&json!({"status": "paused"}))
+                .send()
+                .await
+                .unwrap();
+            assert!(paused.status().is_success());
+        }
+
+        let first: Value = get(&client, format!("{}/api/v1/agents?limit=2", base), &admin).await.json().await.unwrap();
+        assert_eq!(first["total"], 5);
+        assert_eq!(first["items"].as_array().unwrap().len(), 2);
+        assert_eq!(first["items"][0]["name"], "agent-0");
+        let cursor = first["next_cursor"].as_str().unwrap();
+        let second: Value = get(&client, format!("{}/api/v1/agents?limit=2&cursor={}", base, cursor), &admin).await.json().await.unwrap();
+        assert_eq!(second["items"][0]["name"], "agent-2");
+
+        let paused: Value = get(&client, format!("{}/api/v1/agents?status=PAUSED", base), &admin).await.json().await.unwrap();
+        assert_eq!(paused["total"], 2);
+        assert!(paused["next_cursor"].is_null());
+        let named: Value = get(&client, format!("{}/api/v1/agents?name_contains=AGENT-4", base), &admin).await.json().await.unwrap();
+        assert_eq!(named["items"][0]["id"], agent_ids[4].as_str());
+        let bad_cursor = get(&client, format!("{}/api/v1/agents?cursor=bogus", base), &admin).await;
+        assert_eq!(bad_cursor.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        let mut module_id = String::new();
+        for name in ["echo-a", "echo-b", "other"] {
+            let uploaded: Value = client
+                .post(format!("{}/api/v1/wasm/modules?name={}", base, name))
+                .bearer_auth(&admin)
+                .body(ECHO_WAT)
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            module_id = uploaded["id"].as_str().unwrap().to_string();
+        }
+        let echoes: Value = get(&client, format!("{}/api/v1/wasm/modules?name_contains=echo&limit=1", base), &admin).await.json().await.unwrap();
+        assert_eq!(echoes["total"], 2);
+        assert_eq!(echoes["items"][0]["name"], "echo-a");
+        assert!(echoes["next_cursor"].is_string());
+
+        for n in 0..3 {
+            let submitted = client
+                .post(format!("{}/api/v1/jobs", base))
+                .bearer_auth(&admin)
+                .json(&json!({"module_id": module_id, "input": {"n": n}}))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(submitted.status(), reqwest::StatusCode::ACCEPTED);
+        }
+        let mut completed = Value::Null;
+        for _ in 0..100 {
+            completed = get(&client, format!("{}/api/v1/jobs?status=completed&limit=2", base), &admin).await.json().await.unwrap();
+            if completed["total"] == 3 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(completed["total"], 3);
+        assert_eq!(completed["items"].as_array().unwrap().len(), 2);
+        assert_eq!(completed["items"][0]["output"], json!({"n": 0}));
+        let by_module: Value = get(&client, format!("{}/api/v1/jobs?name_contains={}", base, module_id), &admin).await.json().await.unwrap();
+        assert_eq!(by_module["total"], 3);
+        let failed: Value = get(&client, format!("{}/api/v1/jobs?status=failed", base), &admin).await.json().await.unwrap();
+        assert_eq!(failed["total"], 0);
+    }
+
+    /// MCP server offering `tools` as `(name, description)` pairs, which the test may change
+    async fn mcp_server(tools: Arc<Mutex<Vec<(&'static str, &'static str)>>>) -> String {
+        let app = Router::new().route(
+            "/",
+            post(move |Json(request): Json<Value>| async move {
+                let tools: Vec<Value> = tools.lock().unwrap().iter().map(|(name, description)| json!({"name": name, "description": description})).collect();
+                Json(json!({"jsonrpc": "2.0", "id": request["id"], "result": {"tools": tools}}))
+            }),
+        );
+        serve(app).await
+    }
+
+    #[tokio::test]
+    async fn test_agent_and_tool_changes_sync_similarity_index() {
+        let tools = Arc::new(Mutex::new(vec![
+            ("github", "GitHub API: pull requests, issues, code review"),
+            ("pdf", "Read and extract text from PDF documents"),
+        ]));
+        let mcp = McpConfig {
+            servers: vec![McpServerConfig { name: "local".to_string(), url: mcp_server(tools.clone()).await }],
+            ..McpConfig::default()
+        };
+        let base = serve_engine(EngineBuilder::new().with_mcp(mcp)).await;
+        let client = reqwest::Client::new();
+        let admin = bearer("", &["admin"]);
+
+        let mut ids = Vec::new();
+        for description in ["Reviews GitHub pull requests", "Automated code review for pull requests", "Parses PDF invoices"] {
+            let created: Value = client
+                .post(format!("{}/api/v1/agents", base))
+                .bearer_auth(&admin)
+                .json(&json!({"name": "agent", "description": description}))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            ids.push(created["id"].as_str().unwrap().to_string());
+        }
+        let similar_url = |id: &str| format!("{}/api/v1/agents/{}/similar", base, id);
+        let similar: Value = get(&client, similar_url(&ids[0]), &admin).await.json().await.unwrap();
+        assert_eq!(similar["items"][0]["id"], ids[1].as_str());
+
+        // An update re-indexes the new description, a delete drops the agent
+        client
+            .put(format!("{}/api/v1/agents/{}", base, ids[2]))
+            .bearer_auth(&admin)
+            .json(&json!({"description": "Reviews pull requests on GitHub"}))
+            .send()
+            .await
+            .unwrap();
+        let similar: Value = get(&client, similar_url(&ids[0]), &admin).await.json().await.unwrap();
+        assert_eq!(similar["items"][0]["id"], ids[2].as_str());
+        client.delete(format!("{}/api/v1/agents/{}", base, ids[2])).bearer_auth(&admin).send().await.unwrap();
+        assert_eq!(get(&client, similar_url(&ids[2]), &admin).await.status(), reqwest::StatusCode::NOT_FOUND);
+
+        // Listing tools indexes them, and a tool the server stops offering drops out
+        let search_url = format!("{}/api/v1/mcp/tools/similar?q=extract+text+from+a+PDF", base);
+        let listed: Value = get(&client, format!("{}/api/v1/mcp/tools", base), &admin).await.json().await.unwrap();
+        assert_eq!(listed.as_array().unwrap().len(), 2);
+        let found: Value = get(&client, search_url.clone(), &admin).await.json().await.unwrap();
+        assert_eq!(found["items"][0]["id"], "pdf");
+        tools.lock().unwrap().retain(|(name, _)| *name != "pdf");
+        get(&client, format!("{}/api/v1/mcp/tools", base), &admin).await;
+        let found: Value = get(&client, search_url, &admin).await.json().await.unwrap();
+        assert!(found["items"].as_array().unwrap().iter().all(|item| item["id"] != "pdf"));
+    }
+}
+
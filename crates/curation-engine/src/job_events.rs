@@ -0,0 +1,327 @@
+//! Live job status over WebSocket
+//!
+//! The job runner publishes each status change of a job here. Clients
+//! connected to `GET /api/v1/jobs/:id/ws` are sent the job's current status
+//! as soon as they connect, then every later change, and the socket is closed
+//! once the job reaches a terminal state. A client that drops and reconnects
+//! therefore resumes from the current status; the `revision` of each update
+//! lets it discard anything it has already seen.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, RwLock};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::{authz::Tenant, error::ApiError, EngineState};
+
+/// Updates buffered for slow subscribers before they have to catch up
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Finished jobs whose final status is kept for late subscribers
+const MAX_FINISHED_JOBS: usize = 1000;
+
+/// Lifecycle state of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether the job will not change state again
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// Status of a job as pushed to subscribers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct JobStatus {
+    pub job_id: String,
+    pub tenant: String,
+    pub state: JobState,
+    /// Fraction complete, from 0.0 to 1.0, when the job reports it
+    #[serde(default)]
+    pub progress: Option<f32>,
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Increases with every update of the job, starting at 1
+    #[serde(default)]
+    pub revision: u64,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl JobStatus {
+    pub fn new(job_id: impl Into<String>, tenant: impl Into<String>, state: JobState) -> Self {
+        Self {
+            job_id: job_id.into(),
+            tenant: tenant.into(),
+            state,
+            progress: None,
+            message: None,
+            revision: 0,
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn with_progress(mut self, progress: f32) -> Self {
+        self.progress = Some(progress.clamp(0.0, 1.0));
+        self
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
+
+/// A subscription to one job: its status at subscription time and later updates
+pub struct JobSubscription {
+    pub current: JobStatus,
+    updates: broadcast::Receiver<JobStatus>,
+}
+
+impl JobSubscription {
+    /// Next update of the job, or `None` once no more can arrive
+    pub async fn next(&mut self) -> Option<JobStatus> {
+        if self.current.state.is_terminal() {
+            return None;
+        }
+        loop {
+            match self.updates.recv().await {
+                Ok(status) if status.job_id == self.current.job_id && status.revision > self.current.revision => {
+                    self.current = status.clone();
+                    return Some(status);
+                }
+                Ok(_) => {}
+                // Missed updates are superseded by later ones, so only the newest matters
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("📡 Subscriber of job {} lagged by {} update(s)", self.current.job_id, skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Statuses {
+    jobs: HashMap<String, JobStatus>,
+    /// Finished job ids, oldest first
+    finished: VecDeque<String>,
+}
+
+/// Latest status of each job and a feed of status changes
+#[derive(Clone)]
+pub struct JobEvents {
+    statuses: Arc<RwLock<Statuses>>,
+    updates: broadcast::Sender<JobStatus>,
+}
+
+impl JobEvents {
+    pub fn new() -> Self {
+        let (updates, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            statuses: Arc::new(RwLock::new(Statuses::default())),
+            updates,
+        }
+    }
+
+    /// Record a status change and push it to subscribers of the job. Updates
+    /// to a job that already finished are ignored.
+    pub async fn publish(&self, mut status: JobStatus) -> JobStatus {
+        let mut statuses = self.statuses.write().await;
+        if let Some(previous) = statuses.jobs.get(&status.job_id) {
+            if previous.state.is_terminal() {
+                return previous.clone();
+            }
+            status.revision = previous.revision + 1;
+        } else {
+            status.revision = 1;
+        }
+
+        if status.state.is_terminal() {
+            info!("📡 Job {} finished as {:?}", status.job_id, status.state);
+            statuses.finished.push_back(status.job_id.clone());
+            while statuses.finished.len() > MAX_FINISHED_JOBS {
+                if let Some(expired) = statuses.finished.pop_front() {
+                    statuses.jobs.remove(&expired);
+                }
+            }
+        }
+        statuses.jobs.insert(status.job_id.clone(), status.clone());
+        // Sent under the lock so subscribers see every revision after their snapshot
+        let _ = self.updates.send(status.clone());
+        status
+    }
+
+    /// Latest status of a tenant's job
+    pub async fn status(&self, tenant: &str, job_id: &str) -> Option<JobStatus> {
+        self.statuses.read().await.jobs.get(job_id).filter(|status| status.tenant == tenant).cloned()
+    }
+
+    /// Subscribe to a tenant's job, starting from its current status
+    pub async fn subscribe(&self, tenant: &str, job_id: &str) -> Option<JobSubscription> {
+        let statuses = self.statuses.read().await;
+        let current = statuses.jobs.get(job_id).filter(|status| status.tenant == tenant)?.clone();
+        Some(JobSubscription {
+            current,
+            updates: self.updates.subscribe(),
+        })
+    }
+}
+
+impl Default for JobEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `GET /api/v1/jobs/:id/ws`
+#[utoipa::path(
+    get,
+    path = "/api/v1/jobs/{id}/ws",
+    tag = "jobs",
+    params(("id" = String, Path, description = "Job id")),
+    responses(
+        (status = 101, description = "WebSocket carrying the job's current JobStatus, then each change until it finishes"),
+        (status = 404, description = "Unknown job, or one of another tenant"),
+    )
+)]
+pub async fn job_status_ws(
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+    Tenant(tenant): Tenant,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let subscription = state.job_events
+        .subscribe(&tenant, &id)
+        .await
+        .ok_or_else(|| ApiError::NotFound(format!("Job '{}' not found", id)))?;
+    Ok(ws.on_upgrade(move |socket| stream_status(socket, subscription)))
+}
+
+/// Send the current status, then every update until the job finishes or the client leaves
+async fn stream_status(mut socket: WebSocket, mut subscription: JobSubscription) {
+    if send_status(&mut socket, &subscription.current).await.is_err() {
+        return;
+    }
+    loop {
+        tokio::select! {
+            update = subscription.next() => match update {
+                Some(status) => {
+                    if send_status(&mut socket, &status).await.is_err() {
+                        return;
+                    }
+                }
+                None => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+async fn send_status(socket: &mut WebSocket, status: &JobStatus) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(status).unwrap_or_default();
+    socket.send(Message::Text(text)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::serve;
+    use axum::{routing::get, Router};
+    use futures::StreamExt;
+    use tokio_tungstenite::tungstenite;
+
+    #[tokio::test]
+    async fn test_subscription_resumes_from_current_status() {
+        let events = JobEvents::new();
+        events.publish(JobStatus::new("job-1", "acme", JobState::Queued)).await;
+        events.publish(JobStatus::new("job-1", "acme", JobState::Running).with_progress(0.5)).await;
+
+        assert!(events.subscribe("other", "job-1").await.is_none());
+        let mut subscription = events.subscribe("acme", "job-1").await.unwrap();
+        assert_eq!((subscription.current.state, subscription.current.revision), (JobState::Running, 2));
+
+        // Unrelated jobs are filtered out of the feed
+        events.publish(JobStatus::new("job-2", "acme", JobState::Queued)).await;
+        events.publish(JobStatus::new("job-1", "acme", JobState::Completed)).await;
+        let finished = subscription.next().await.unwrap();
+        assert_eq!((finished.state, finished.revision), (JobState::Completed, 3));
+        assert!(subscription.next().await.is_none());
+
+        // A finished job stays finished, and reconnecting clients see its final status
+        events.publish(JobStatus::new("job-1", "acme", JobState::Running)).await;
+        let mut resumed = events.subscribe("acme", "job-1").await.unwrap();
+        assert_eq!(resumed.current, finished);
+        assert!(resumed.next().await.is_none());
+    }
+
+    /// Next status sent over the socket, or `None` on a close frame
+    async fn next_status<S>(socket: &mut S) -> Option<JobStatus>
+    where
+        S: futures::Stream<Item = Result<tungstenite::Message, tungstenite::Error>> + Unpin,
+    {
+        match socket.next().await.unwrap().unwrap() {
+            tungstenite::Message::Text(text) => Some(serde_json::from_str(&text).unwrap()),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_websocket_streams_until_terminal() {
+        let events = JobEvents::new();
+        events.publish(JobStatus::new("job-1", "acme", JobState::Queued)).await;
+
+        let state = events.clone();
+        let app = Router::new().route(
+            "/jobs/:id/ws",
+            get(move |Path(id): Path<String>, ws: WebSocketUpgrade| async move {
+                let subscription = state.subscribe("acme", &id).await.unwrap();
+                ws.on_upgrade(move |socket| stream_status(socket, subscription))
+            }),
+        );
+        let url = format!("{}/jobs/job-1/ws", serve(app).await.replacen("http", "ws", 1));
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        assert_eq!(next_status(&mut socket).await.unwrap().state, JobState::Queued);
+
+        events.publish(JobStatus::new("job-1", "acme", JobState::Running).with_message("indexing")).await;
+        let running = next_status(&mut socket).await.unwrap();
+        assert_eq!((running.state, running.message.as_deref()), (JobState::Running, Some("indexing")));
+
+        events.publish(JobStatus::new("job-1", "acme", JobState::Failed)).await;
+        assert_eq!(next_status(&mut socket).await.unwrap().state, JobState::Failed);
+        assert!(next_status(&mut socket).await.is_none());
+    }
+}
@@ -8,8 +8,28 @@ pub mod cache;
 pub mod queue;
 pub mod wasm_runtime;
 pub mod mcp_client;
+pub mod similarity;
+pub mod export;
+pub mod canary;
+pub mod webhooks;
+pub mod job_events;
+pub mod execution_stream;
+pub mod promotion;
+pub mod pagination;
+pub mod live_config;
+pub mod openapi;
+pub mod authz;
+pub mod scheduling;
+pub mod correlation;
+pub mod error;
+pub mod drift;
+
+#[cfg(test)]
+mod test_support;
 
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
 use axum::{
     routing::{get, post, put, delete},
     Router, middleware as axum_middleware,
@@ -26,28 +46,71 @@ use crate::{
     handlers::*,
     middleware::{auth::AuthMiddleware, rate_limit::RateLimitMiddleware},
     services::{AgentService, WasmService, MetricsService},
+    queue::JobQueue,
+    mcp_client::McpClient,
+    similarity::SimilarityService,
+    export::{EnvSecrets, ExportSinkConfig, ResultExporter},
+    canary::{AgentExecutor, CanaryService},
+    webhooks::{WebhookConfig, WebhookService},
+    job_events::JobEvents,
+    execution_stream::StreamingWasmExecutor,
+    live_config::LiveConfig,
+    scheduling::{ExecutionScheduler, SchedulingConfig},
+    authz::{AgentsWrite, JobsWrite, RequireScope, RequiredScope, SystemWrite, TenantWrite, ToolsExecute, WasmExecute, WasmWrite},
+    drift::{DriftConfig, DriftMonitor, ScheduleControl},
 };
 
+/// Route layer rejecting callers whose token lacks scope `S`, for handlers
+/// that do not take `RequireScope<S>` themselves
+fn require_scope<S: RequiredScope + Send + 'static>() -> axum_middleware::FromExtractorLayer<RequireScope<S>, ()> {
+    axum_middleware::from_extractor::<RequireScope<S>>()
+}
+
 /// Main curation engine structure
 pub struct CurationEngine {
-    config: EngineConfig,
+    config: LiveConfig,
     agent_service: AgentService,
     wasm_service: WasmService,
     metrics_service: MetricsService,
+    job_queue: JobQueue,
+    mcp_client: McpClient,
+    similarity_service: SimilarityService,
+    result_exporter: Option<ResultExporter>,
+    canary_service: Option<CanaryService>,
+    webhook_service: WebhookService,
+    job_events: JobEvents,
+    streaming_executor: Option<Arc<dyn StreamingWasmExecutor>>,
+    execution_scheduler: ExecutionScheduler,
+    drift_monitor: DriftMonitor,
 }
 
 impl CurationEngine {
     /// Create a new curation engine instance
     pub async fn new(config: EngineConfig) -> Result<Self, Box<dyn std::error::Error>> {
         let agent_service = AgentService::new(config.database.clone(), config.redis.clone()).await?;
-        let wasm_service = WasmService::new(config.wasm.clone()).await?;
+        let wasm_service = WasmService::new();
         let metrics_service = MetricsService::new(config.metrics.clone()).await?;
+        let webhook_service = WebhookService::default();
+        let job_events = JobEvents::default();
+        let execution_scheduler = ExecutionScheduler::default();
+        let job_queue = JobQueue::new(wasm_service.clone(), execution_scheduler.clone(), job_events.clone(), webhook_service.clone());
+        let mcp_client = McpClient::new(&config.mcp);
 
         Ok(Self {
-            config,
+            config: LiveConfig::new(config),
             agent_service,
             wasm_service,
             metrics_service,
+            job_queue,
+            mcp_client,
+            similarity_service: SimilarityService::default(),
+            result_exporter: None,
+            canary_service: None,
+            webhook_service,
+            job_events,
+            streaming_executor: None,
+            execution_scheduler,
+            drift_monitor: DriftMonitor::default(),
         })
     }
 
@@ -57,11 +120,10 @@ impl CurationEngine {
 
         let app = self.create_router().await?;
 
-        let server = axum::Server::bind(&addr)
-            .serve(app.into_make_service())
-            .with_graceful_shutdown(shutdown_signal());
-
-        server.await?;
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(shutdown_signal())
+            .await?;
         Ok(())
     }
 
@@ -70,28 +132,46 @@ impl CurationEngine {
         let agent_service = self.agent_service.clone();
         let wasm_service = self.wasm_service.clone();
         let metrics_service = self.metrics_service.clone();
+        let job_queue = self.job_queue.clone();
+        let mcp_client = self.mcp_client.clone();
+        let similarity_service = self.similarity_service.clone();
+        let result_exporter = self.result_exporter.clone();
+        let canary_service = self.canary_service.clone();
+        let webhook_service = self.webhook_service.clone();
+        let job_events = self.job_events.clone();
+        let streaming_executor = self.streaming_executor.clone();
+        let execution_scheduler = self.execution_scheduler.clone();
+        let drift_monitor = self.drift_monitor.clone();
 
         let app = Router::new()
             // Health check
             .route("/health", get(health_check))
 
             // Agent management
-            .route("/api/v1/agents", post(create_agent))
+            .route("/api/v1/agents", post(create_agent).route_layer(require_scope::<AgentsWrite>()))
             .route("/api/v1/agents/:id", get(get_agent))
-            .route("/api/v1/agents/:id", put(update_agent))
-            .route("/api/v1/agents/:id", delete(delete_agent))
+            .route("/api/v1/agents/:id", put(update_agent).route_layer(require_scope::<AgentsWrite>()))
+            .route("/api/v1/agents/:id", delete(delete_agent).route_layer(require_scope::<AgentsWrite>()))
             .route("/api/v1/agents", get(list_agents))
+            .route("/api/v1/agents/:id/similar", get(similarity::similar_agents))
+            .route("/api/v1/agents/:id/canary", get(canary::get_canary))
+            .route("/api/v1/agents/:id/canary", put(canary::configure_canary).route_layer(require_scope::<AgentsWrite>()))
+            .route("/api/v1/agents/:id/canary/finalize", post(canary::finalize_canary).route_layer(require_scope::<AgentsWrite>()))
+            .route("/api/v1/agents/:id/drift", get(drift::list_drift_reports))
+            .route("/api/v1/agents/:id/drift/:report_id/review", post(drift::review_drift_report).route_layer(require_scope::<AgentsWrite>()))
 
             // WASM module management
-            .route("/api/v1/wasm/modules", post(upload_wasm_module))
+            .route("/api/v1/wasm/modules", post(upload_wasm_module).route_layer(require_scope::<WasmWrite>()))
             .route("/api/v1/wasm/modules/:id", get(get_wasm_module))
-            .route("/api/v1/wasm/modules/:id/execute", post(execute_wasm_module))
+            .route("/api/v1/wasm/modules/:id/execute", post(execute_wasm_module).route_layer(require_scope::<WasmExecute>()))
+            .route("/api/v1/wasm/modules/:id/execute/stream", post(execution_stream::execute_wasm_module_stream))
             .route("/api/v1/wasm/modules", get(list_wasm_modules))
 
             // Job queue management
-            .route("/api/v1/jobs", post(submit_job))
+            .route("/api/v1/jobs", post(submit_job).route_layer(require_scope::<JobsWrite>()))
             .route("/api/v1/jobs/:id", get(get_job_status))
-            .route("/api/v1/jobs/:id/cancel", post(cancel_job))
+            .route("/api/v1/jobs/:id/ws", get(job_events::job_status_ws))
+            .route("/api/v1/jobs/:id/cancel", post(cancel_job).route_layer(require_scope::<JobsWrite>()))
             .route("/api/v1/jobs", get(list_jobs))
 
             // Metrics and monitoring
@@ -101,33 +181,69 @@ impl CurationEngine {
 
             // MCP integration
             .route("/api/v1/mcp/tools", get(list_mcp_tools))
-            .route("/api/v1/mcp/tools/:name/execute", post(execute_mcp_tool))
+            .route("/api/v1/mcp/tools/similar", get(similarity::similar_tools))
+            .route("/api/v1/mcp/tools/:name/execute", post(execute_mcp_tool).route_layer(require_scope::<ToolsExecute>()))
+
+            // Webhook subscriptions and tenant branding
+            .route("/api/v1/webhooks", post(webhooks::create_subscription).route_layer(require_scope::<TenantWrite>()))
+            .route("/api/v1/webhooks", get(webhooks::list_subscriptions))
+            .route("/api/v1/webhooks/:id", get(webhooks::get_subscription))
+            .route("/api/v1/webhooks/:id", delete(webhooks::delete_subscription).route_layer(require_scope::<TenantWrite>()))
+            .route("/api/v1/webhooks/:id/deliveries", get(webhooks::list_deliveries))
+            .route("/api/v1/webhooks/:id/test", post(webhooks::test_delivery).route_layer(require_scope::<TenantWrite>()))
+            .route("/api/v1/tenant/branding", get(webhooks::get_branding))
+            .route("/api/v1/tenant/branding", put(webhooks::update_branding).route_layer(require_scope::<TenantWrite>()))
+
+            // Environment promotion
+            .route("/api/v1/promotion/export", get(promotion::export_snapshot))
+            .route("/api/v1/promotion/import", post(promotion::import_snapshot).route_layer(require_scope::<AgentsWrite>()))
 
             // System management
             .route("/api/v1/system/status", get(get_system_status))
-            .route("/api/v1/system/config", get(get_system_config))
-            .route("/api/v1/system/config", put(update_system_config))
+            .route("/api/v1/system/config", get(live_config::get_system_config))
+            .route("/api/v1/system/config", put(live_config::update_system_config).route_layer(require_scope::<SystemWrite>()))
+
+            // API documentation
+            .merge(openapi::docs())
 
             // Layer middleware
             .layer(
                 ServiceBuilder::new()
+                    .layer(axum_middleware::from_fn(correlation::propagate_correlation_id))
                     .layer(TraceLayer::new_for_http())
                     .layer(CompressionLayer::new())
                     .layer(CorsLayer::permissive())
-                    .layer(AuthMiddleware::new(self.config.auth.clone()))
-                    .layer(RateLimitMiddleware::new(self.config.rate_limit.clone()))
+                    .layer(AuthMiddleware::new(self.config.clone()))
+                    .layer(RateLimitMiddleware::new(self.config.clone()))
             )
 
             // Add state to all routes
             .with_state(EngineState {
+                config: self.config.clone(),
                 agent_service,
                 wasm_service,
                 metrics_service,
+                job_queue,
+                mcp_client,
+                similarity_service,
+                result_exporter,
+                canary_service,
+                webhook_service,
+                job_events,
+                streaming_executor,
+                execution_scheduler,
+                drift_monitor,
+                started_at: Instant::now(),
             });
 
         Ok(app)
     }
 
+    /// Get the live engine config, which `PUT /api/v1/system/config` updates
+    pub fn config(&self) -> &LiveConfig {
+        &self.config
+    }
+
     /// Get agent service
     pub fn agent_service(&self) -> &AgentService {
         &self.agent_service
@@ -142,14 +258,77 @@ impl CurationEngine {
     pub fn metrics_service(&self) -> &MetricsService {
         &self.metrics_service
     }
+
+    /// Get the job queue
+    pub fn job_queue(&self) -> &JobQueue {
+        &self.job_queue
+    }
+
+    /// Get the MCP client
+    pub fn mcp_client(&self) -> &McpClient {
+        &self.mcp_client
+    }
+
+    /// Get similarity service
+    pub fn similarity_service(&self) -> &SimilarityService {
+        &self.similarity_service
+    }
+
+    /// Get result exporter, if an export sink is configured
+    pub fn result_exporter(&self) -> Option<&ResultExporter> {
+        self.result_exporter.as_ref()
+    }
+
+    /// Get canary service, if an agent executor is configured
+    pub fn canary_service(&self) -> Option<&CanaryService> {
+        self.canary_service.as_ref()
+    }
+
+    /// Get webhook service
+    pub fn webhook_service(&self) -> &WebhookService {
+        &self.webhook_service
+    }
+
+    /// Get job events, which the job runner publishes status changes to
+    pub fn job_events(&self) -> &JobEvents {
+        &self.job_events
+    }
+
+    /// Get the streaming WASM executor, if one is configured
+    pub fn streaming_executor(&self) -> Option<&Arc<dyn StreamingWasmExecutor>> {
+        self.streaming_executor.as_ref()
+    }
+
+    /// Get the scheduler running interactive and batch executions
+    pub fn execution_scheduler(&self) -> &ExecutionScheduler {
+        &self.execution_scheduler
+    }
+
+    /// Get the drift monitor, which agent executions are recorded into
+    pub fn drift_monitor(&self) -> &DriftMonitor {
+        &self.drift_monitor
+    }
 }
 
 /// Shared state for all handlers
 #[derive(Clone)]
 pub struct EngineState {
+    pub config: LiveConfig,
     pub agent_service: AgentService,
     pub wasm_service: WasmService,
     pub metrics_service: MetricsService,
+    pub job_queue: JobQueue,
+    pub mcp_client: McpClient,
+    pub similarity_service: SimilarityService,
+    pub result_exporter: Option<ResultExporter>,
+    pub canary_service: Option<CanaryService>,
+    pub webhook_service: WebhookService,
+    pub job_events: JobEvents,
+    pub streaming_executor: Option<Arc<dyn StreamingWasmExecutor>>,
+    pub execution_scheduler: ExecutionScheduler,
+    pub drift_monitor: DriftMonitor,
+    /// When the router was built, for the reported uptime
+    pub started_at: Instant,
 }
 
 /// Shutdown signal handler
@@ -182,12 +361,26 @@ async fn shutdown_signal() {
 /// Builder pattern for engine configuration
 pub struct EngineBuilder {
     config: EngineConfig,
+    export_sink: Option<ExportSinkConfig>,
+    agent_executor: Option<Arc<dyn AgentExecutor>>,
+    webhook_config: WebhookConfig,
+    streaming_executor: Option<Arc<dyn StreamingWasmExecutor>>,
+    scheduling: SchedulingConfig,
+    drift: DriftConfig,
+    schedule_control: Option<Arc<dyn ScheduleControl>>,
 }
 
 impl EngineBuilder {
     pub fn new() -> Self {
         Self {
             config: EngineConfig::default(),
+            export_sink: None,
+            agent_executor: None,
+            webhook_config: WebhookConfig::default(),
+            streaming_executor: None,
+            scheduling: SchedulingConfig::default(),
+            drift: DriftConfig::default(),
+            schedule_control: None,
         }
     }
 
@@ -216,8 +409,74 @@ impl EngineBuilder {
         self
     }
 
+    pub fn with_mcp(mut self, mcp: config::McpConfig) -> Self {
+        self.config.mcp = mcp;
+        self
+    }
+
+    /// Export completed job results to object storage; credentials are read from the environment
+    pub fn with_export_sink(mut self, sink: ExportSinkConfig) -> Self {
+        self.export_sink = Some(sink);
+        self
+    }
+
+    /// Execute agent versions through `executor`, enabling canary deployments
+    pub fn with_agent_executor(mut self, executor: Arc<dyn AgentExecutor>) -> Self {
+        self.agent_executor = Some(executor);
+        self
+    }
+
+    /// Configure webhook delivery and per-tenant subscription limits
+    pub fn with_webhook_config(mut self, webhook_config: WebhookConfig) -> Self {
+        self.webhook_config = webhook_config;
+        self
+    }
+
+    /// Execute WASM modules through `executor` for streamed execution output
+    pub fn with_streaming_executor(mut self, executor: Arc<dyn StreamingWasmExecutor>) -> Self {
+        self.streaming_executor = Some(executor);
+        self
+    }
+
+    /// Size the execution worker pool and how batch work shares it with interactive work
+    pub fn with_scheduling(mut self, scheduling: SchedulingConfig) -> Self {
+        self.scheduling = scheduling;
+        self
+    }
+
+    /// Set the drift window size, per-dimension thresholds and whether drift pauses schedules
+    pub fn with_drift(mut self, drift: DriftConfig) -> Self {
+        self.drift = drift;
+        self
+    }
+
+    /// Pause and resume agent schedules through `schedules` when drift is detected and reviewed
+    pub fn with_schedule_control(mut self, schedules: Arc<dyn ScheduleControl>) -> Self {
+        self.schedule_control = Some(schedules);
+        self
+    }
+
     pub async fn build(self) -> Result<CurationEngine, Box<dyn std::error::Error>> {
-        CurationEngine::new(self.config).await
+        let mut engine = CurationEngine::new(self.config).await?;
+        engine.result_exporter = self
+            .export_sink
+            .map(|sink| ResultExporter::new(sink, Arc::new(EnvSecrets)));
+        engine.canary_service = self.agent_executor.map(CanaryService::new);
+        engine.webhook_service = WebhookService::new(self.webhook_config);
+        engine.streaming_executor = self.streaming_executor;
+        engine.execution_scheduler = ExecutionScheduler::new(self.scheduling);
+        engine.job_queue = JobQueue::new(
+            engine.wasm_service.clone(),
+            engine.execution_scheduler.clone(),
+            engine.job_events.clone(),
+            engine.webhook_service.clone(),
+        );
+        let drift_monitor = DriftMonitor::new(self.drift, engine.webhook_service.clone());
+        engine.drift_monitor = match self.schedule_control {
+            Some(schedules) => drift_monitor.with_schedule_control(schedules),
+            None => drift_monitor,
+        };
+        Ok(engine)
     }
 }
 
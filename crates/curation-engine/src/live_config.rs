@@ -0,0 +1,225 @@
+//! Live engine configuration
+//!
+//! `PUT /api/v1/system/config` swaps the whole `EngineConfig` at once behind
+//! an `ArcSwap`. Handlers take a `ConfigSnapshot` when the request arrives and
+//! keep it until they return, so a request in flight during an update finishes
+//! on the config it started with and only later requests see the new one; the
+//! auth and rate limit middleware load the config the same way. An
+//! update is validated first; an invalid config is rejected with 400 and the
+//! offending fields, leaving the current config in place.
+
+use std::ops::Deref;
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts, State},
+    http::request::Parts,
+    Json,
+};
+use serde::Serialize;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::{config::EngineConfig, error::ApiError, EngineState};
+
+/// Largest WASM memory limit a config may set
+pub const MAX_WASM_MEMORY_MB: u32 = 4096;
+
+/// Longest WASM execution time a config may set
+pub const MAX_WASM_EXECUTION_TIME_MS: u64 = 15 * 60 * 1000;
+
+/// One invalid field of a rejected config
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct FieldError {
+    /// Dotted path of the field, e.g. `rate_limit.burst_limit`
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Every problem with `config`; empty when it is safe to apply
+pub fn validate(config: &EngineConfig) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    let rate_limit = &config.rate_limit;
+    if rate_limit.requests_per_minute == 0 {
+        errors.push(FieldError::new("rate_limit.requests_per_minute", "must be greater than 0"));
+    }
+    if rate_limit.burst_limit == 0 {
+        errors.push(FieldError::new("rate_limit.burst_limit", "must be greater than 0"));
+    } else if rate_limit.burst_limit > rate_limit.requests_per_minute {
+        errors.push(FieldError::new(
+            "rate_limit.burst_limit",
+            format!("must not exceed requests_per_minute ({})", rate_limit.requests_per_minute),
+        ));
+    }
+
+    let wasm = &config.wasm;
+    if wasm.max_memory_mb == 0 || wasm.max_memory_mb > MAX_WASM_MEMORY_MB {
+        errors.push(FieldError::new(
+            "wasm.max_memory_mb",
+            format!("must be between 1 and {}", MAX_WASM_MEMORY_MB),
+        ));
+    }
+    if wasm.max_execution_time_ms == 0 || wasm.max_execution_time_ms > MAX_WASM_EXECUTION_TIME_MS {
+        errors.push(FieldError::new(
+            "wasm.max_execution_time_ms",
+            format!("must be between 1 and {}", MAX_WASM_EXECUTION_TIME_MS),
+        ));
+    }
+
+    errors
+}
+
+/// The engine config currently in effect, shared by every handler
+#[derive(Clone)]
+pub struct LiveConfig {
+    current: Arc<ArcSwap<EngineConfig>>,
+}
+
+impl LiveConfig {
+    pub fn new(config: EngineConfig) -> Self {
+        Self {
+            current: Arc::new(ArcSwap::from_pointee(config)),
+        }
+    }
+
+    /// The config as of now; later updates do not change it
+    pub fn snapshot(&self) -> Arc<EngineConfig> {
+        self.current.load_full()
+    }
+
+    /// Validate `config` and make it the current one; an empty JWT secret
+    /// keeps the current secret, as the API never returns it
+    pub fn update(&self, mut config: EngineConfig) -> Result<Arc<EngineConfig>, Vec<FieldError>> {
+        if config.auth.jwt_secret.is_empty() {
+            config.auth.jwt_secret = self.current.load().auth.jwt_secret.clone();
+        }
+        let errors = validate(&config);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let config = Arc::new(config);
+        self.current.store(config.clone());
+        Ok(config)
+    }
+}
+
+impl FromRef<EngineState> for LiveConfig {
+    fn from_ref(state: &EngineState) -> Self {
+        state.config.clone()
+    }
+}
+
+/// Extractor for the config as it was when the request arrived
+pub struct ConfigSnapshot(pub Arc<EngineConfig>);
+
+impl Deref for ConfigSnapshot {
+    type Target = EngineConfig;
+
+    fn deref(&self) -> &EngineConfig {
+        &self.0
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ConfigSnapshot
+where
+    LiveConfig: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self(LiveConfig::from_ref(state).snapshot()))
+    }
+}
+
+/// `GET /api/v1/system/config`
+pub async fn get_system_config(ConfigSnapshot(config): ConfigSnapshot) -> Json<EngineConfig> {
+    Json(config.as_ref().clone())
+}
+
+/// `PUT /api/v1/system/config`
+pub async fn update_system_config(
+    State(live): State<LiveConfig>,
+    Json(config): Json<EngineConfig>,
+) -> Result<Json<EngineConfig>, ApiError> {
+    match live.update(config) {
+        Ok(config) => {
+            info!("⚙️ System config updated");
+            Ok(Json(config.as_ref().clone()))
+        }
+        Err(fields) => {
+            warn!("⚙️ Rejected system config update: {} invalid field(s)", fields.len());
+            Err(ApiError::InvalidFields {
+                detail: "Invalid system config".to_string(),
+                fields,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::serve;
+    use std::time::Duration;
+
+    use axum::{routing::get, Router};
+    use tokio::sync::Notify;
+
+    #[tokio::test]
+    async fn test_update_keeps_in_flight_snapshot_and_rejects_invalid_config() {
+        let live = LiveConfig::new(EngineConfig::default());
+        let release = Arc::new(Notify::new());
+        let slow_release = release.clone();
+        // Reports the rate limit it started with, after being held open
+        let slow = get(move |ConfigSnapshot(config): ConfigSnapshot| async move {
+            slow_release.notified().await;
+            config.rate_limit.requests_per_minute.to_string()
+        });
+        let app = Router::new()
+            .route("/slow", slow)
+            .route("/api/v1/system/config", get(get_system_config).put(update_system_config))
+            .with_state(live.clone());
+        let base = serve(app).await;
+        let client = reqwest::Client::new();
+
+        let original = live.snapshot().rate_limit.requests_per_minute;
+        let in_flight = tokio::spawn(reqwest::get(format!("{}/slow", base)));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let mut updated = EngineConfig::default();
+        updated.rate_limit.requests_per_minute = original + 100;
+        let response = client.put(format!("{}/api/v1/system/config", base)).json(&updated).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+        release.notify_one();
+        let body = in_flight.await.unwrap().unwrap().text().await.unwrap();
+        assert_eq!(body, original.to_string());
+        let current: serde_json::Value = reqwest::get(format!("{}/api/v1/system/config", base)).await.unwrap().json().await.unwrap();
+        assert_eq!(current["rate_limit"]["requests_per_minute"], original + 100);
+
+        // Every invalid field is reported and nothing is applied
+        let mut broken = updated.clone();
+        broken.rate_limit.requests_per_minute = 0;
+        broken.wasm.max_memory_mb = 0;
+        let response = client.put(format!("{}/api/v1/system/config", base)).json(&broken).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json().await.unwrap();
+        let fields: Vec<&str> = body["fields"].as_array().unwrap().iter().map(|f| f["field"].as_str().unwrap()).collect();
+        assert_eq!(fields, ["rate_limit.requests_per_minute", "rate_limit.burst_limit", "wasm.max_memory_mb"]);
+        assert_eq!(live.snapshot().rate_limit.requests_per_minute, original + 100);
+    }
+}
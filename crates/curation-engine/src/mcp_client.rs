@@ -0,0 +1,141 @@
+//! Client for the configured MCP servers
+//!
+//! Servers are called with JSON-RPC 2.0 over HTTP. Listing asks every server
+//! for its tools and remembers which server offers each one; a server that
+//! fails is logged and left out, and the listing is marked incomplete. Executing a tool calls the server that
+//! offered it, listing again first when the tool is not known yet.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::json;
+use tokio::sync::RwLock;
+use tracing::warn;
+
+use crate::{
+    config::{McpConfig, McpServerConfig},
+    error::ApiError,
+    models::McpTool,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum McpError {
+    #[error("MCP tool '{0}' not found")]
+    ToolNotFound(String),
+    #[error("MCP server '{server}' failed: {message}")]
+    Server { server: String, message: String },
+}
+
+impl From<McpError> for ApiError {
+    fn from(err: McpError) -> Self {
+        let detail = err.to_string();
+        match err {
+            McpError::ToolNotFound(_) => ApiError::NotFound(detail),
+            McpError::Server { .. } => ApiError::BadGateway(detail),
+        }
+    }
+}
+
+/// Tools offered by the configured servers
+#[derive(Debug, Default)]
+pub struct McpToolListing {
+    pub tools: Vec<McpTool>,
+    /// Whether every server answered, so a tool missing here is really gone
+    pub complete: bool,
+}
+
+#[derive(Clone)]
+pub struct McpClient {
+    http: reqwest::Client,
+    servers: Arc<Vec<McpServerConfig>>,
+    /// Server offering each tool, as of the last listing
+    tool_servers: Arc<RwLock<HashMap<String, usize>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl McpClient {
+    pub fn new(config: &McpConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .unwrap_or_default();
+        Self {
+            http,
+            servers: Arc::new(config.servers.clone()),
+            tool_servers: Arc::new(RwLock::new(HashMap::new())),
+            next_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    /// Tools of every reachable server; a name offered twice goes to the first server
+    pub async fn list_tools(&self) -> McpToolListing {
+        let mut tools = Vec::new();
+        let mut complete = true;
+        let mut tool_servers = HashMap::new();
+        for (index, server) in self.servers.iter().enumerate() {
+            let result = match self.call(server, "tools/list", json!({})).await {
+                Ok(result) => result,
+                Err(e) => {
+                    warn!("🔌 Skipping MCP server {}: {}", server.name, e);
+                    complete = false;
+                    continue;
+                }
+            };
+            for tool in result["tools"].as_array().into_iter().flatten() {
+                let Some(name) = tool["name"].as_str() else { continue };
+                if tool_servers.contains_key(name) {
+                    continue;
+                }
+                tool_servers.insert(name.to_string(), index);
+                tools.push(McpTool {
+                    name: name.to_string(),
+                    description: tool["description"].as_str().unwrap_or_default().to_string(),
+                    server: server.name.clone(),
+                    input_schema: tool["inputSchema"].clone(),
+                });
+            }
+        }
+        *self.tool_servers.write().await = tool_servers;
+        McpToolListing { tools, complete }
+    }
+
+    /// Call a tool with `arguments`, returning the server's result
+    pub async fn execute_tool(&self, name: &str, arguments: serde_json::Value) -> Result<serde_json::Value, McpError> {
+        let known = self.tool_servers.read().await.get(name).copied();
+        let index = match known {
+            Some(index) => index,
+            None => {
+                self.list_tools().await;
+                self.tool_servers.read().await.get(name).copied().ok_or_else(|| McpError::ToolNotFound(name.to_string()))?
+            }
+        };
+        self.call(&self.servers[index], "tools/call", json!({"name": name, "arguments": arguments})).await
+    }
+
+    async fn call(&self, server: &McpServerConfig, method: &str, params: serde_json::Value) -> Result<serde_json::Value, McpError> {
+        let failed = |message: String| McpError::Server { server: server.name.clone(), message };
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        });
+        let response: serde_json::Value = self
+            .http
+            .post(&server.url)
+            .json(&request)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| failed(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| failed(e.to_string()))?;
+        if let Some(error) = response.get("error") {
+            return Err(failed(error["message"].as_str().unwrap_or("unknown error").to_string()));
+        }
+        Ok(response["result"].clone())
+    }
+}
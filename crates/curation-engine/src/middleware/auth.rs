@@ -0,0 +1,119 @@
+//! Bearer token authentication
+//!
+//! Every request except the health check and the API docs must carry an
+//! HS256-signed JWT in `Authorization: Bearer`, with a valid signature, an
+//! unexpired `exp` and, when one is configured, the expected `iss`. The
+//! verified claims go into the request extensions as `GrantedScopes` for
+//! `authz` to check; anything else is rejected with 401. With auth disabled
+//! every request is granted every scope.
+
+use std::task::{Context, Poll};
+
+use axum::{
+    body::Body,
+    http::{header::AUTHORIZATION, HeaderMap, Request},
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use tower::{Layer, Service};
+use tracing::warn;
+
+use crate::{
+    authz::{GrantedScopes, ScopeClaims},
+    config::AuthConfig,
+    error::ApiError,
+    live_config::LiveConfig,
+    openapi::{DOCS_PATH, OPENAPI_PATH},
+};
+
+/// Whether `path` is served without a token
+pub fn is_public(path: &str) -> bool {
+    path == "/health" || path == OPENAPI_PATH || path == DOCS_PATH || path.starts_with(&format!("{}/", DOCS_PATH))
+}
+
+/// Claims of the bearer token in `headers`, once its signature and expiry check out
+pub fn verify(config: &AuthConfig, headers: &HeaderMap) -> Result<ScopeClaims, ApiError> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| ApiError::Unauthorized("A bearer token is required".to_string()))?;
+    if config.jwt_secret.is_empty() {
+        warn!("🔐 Rejecting bearer token: no JWT secret is configured");
+        return Err(ApiError::Unauthorized("Tokens cannot be verified".to_string()));
+    }
+
+    let mut validation = Validation::new(Algorithm::HS256);
+    if let Some(issuer) = &config.issuer {
+        validation.set_issuer(&[issuer]);
+    }
+    jsonwebtoken::decode::<ScopeClaims>(token, &DecodingKey::from_secret(config.jwt_secret.as_bytes()), &validation)
+        .map(|data| data.claims)
+        .map_err(|e| ApiError::Unauthorized(format!("Invalid bearer token: {}", e)))
+}
+
+/// Layer authenticating requests against the `AuthConfig` current when each arrives
+#[derive(Clone)]
+pub struct AuthMiddleware {
+    config: LiveConfig,
+}
+
+impl AuthMiddleware {
+    pub fn new(config: LiveConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl<S> Layer<S> for AuthMiddleware {
+    type Service = Authenticate<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Authenticate {
+            inner,
+            config: self.config.clone(),
+        }
+    }
+}
+
+/// Service produced by `AuthMiddleware`
+#[derive(Clone)]
+pub struct Authenticate<S> {
+    inner: S,
+    config: LiveConfig,
+}
+
+impl<S> Service<Request<Body>> for Authenticate<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let config = self.config.snapshot();
+        if !config.auth.enabled {
+            req.extensions_mut().insert(GrantedScopes::unrestricted());
+        } else if !is_public(req.uri().path()) {
+            match verify(&config.auth, req.headers()) {
+                Ok(claims) => {
+                    req.extensions_mut().insert(GrantedScopes::from_claims(claims));
+                }
+                Err(e) => {
+                    warn!("🔐 Rejected {} {}: {}", req.method(), req.uri().path(), e);
+                    return Box::pin(async move { Ok(e.into_response()) });
+                }
+            }
+        }
+        // Call the clone that was polled ready, leaving a fresh one for the next request
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(inner.call(req))
+    }
+}
@@ -0,0 +1,4 @@
+//! Tower layers applied to every route
+
+pub mod auth;
+pub mod rate_limit;
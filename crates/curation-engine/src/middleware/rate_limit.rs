@@ -0,0 +1,205 @@
+//! Per-client rate limiting
+//!
+//! Each client gets a token bucket holding `burst_limit` requests and
+//! refilled at `requests_per_minute`. Clients are told apart by their token's
+//! subject, or by address for requests without one. A request finding its
+//! bucket empty is rejected with 429 and a `Retry-After`. Limits are read
+//! from the live config on every request, so an update applies to buckets
+//! already in use. Buckets that have refilled completely are dropped by a
+//! periodic sweep.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::Request,
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use tower::{Layer, Service};
+
+use crate::{authz::GrantedScopes, config::RateLimitConfig, error::ApiError, live_config::LiveConfig};
+
+/// How often buckets that refilled completely are dropped
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+struct Buckets {
+    by_client: HashMap<String, Bucket>,
+    last_sweep: Instant,
+}
+
+/// Key a request is limited under
+fn client_key(req: &Request<Body>) -> String {
+    if let Some(subject) = req.extensions().get::<GrantedScopes>().and_then(|granted| granted.subject.as_deref()) {
+        return format!("sub:{}", subject);
+    }
+    match req.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "anonymous".to_string(),
+    }
+}
+
+/// Token buckets of every client
+pub struct RateLimiter {
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self {
+            buckets: Mutex::new(Buckets {
+                by_client: HashMap::new(),
+                last_sweep: Instant::now(),
+            }),
+        }
+    }
+
+    /// Take a token from `client`'s bucket, or say how many seconds until one is available
+    pub fn check(&self, config: &RateLimitConfig, client: &str) -> Result<(), u64> {
+        let capacity = config.burst_limit.max(1) as f64;
+        let per_sec = config.requests_per_minute.max(1) as f64 / 60.0;
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if now.duration_since(buckets.last_sweep) >= SWEEP_INTERVAL {
+            buckets.last_sweep = now;
+            buckets.by_client.retain(|_, bucket| {
+                bucket.tokens + now.duration_since(bucket.last_refill).as_secs_f64() * per_sec < capacity
+            });
+        }
+
+        let bucket = buckets.by_client.entry(client.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            last_refill: now,
+        });
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.last_refill).as_secs_f64() * per_sec).min(capacity);
+        bucket.last_refill = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - bucket.tokens) / per_sec).ceil() as u64)
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Layer limiting requests per client under the `RateLimitConfig` current when each arrives
+#[derive(Clone)]
+pub struct RateLimitMiddleware {
+    config: LiveConfig,
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitMiddleware {
+    pub fn new(config: LiveConfig) -> Self {
+        Self {
+            config,
+            limiter: Arc::new(RateLimiter::new()),
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitMiddleware {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            config: self.config.clone(),
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+/// Service produced by `RateLimitMiddleware`
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    config: LiveConfig,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<S> Service<Request<Body>> for RateLimit<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let config = &self.config.snapshot().rate_limit;
+        if config.enabled {
+            if let Err(retry_after_secs) = self.limiter.check(config, &client_key(&req)) {
+                let error = ApiError::TooManyRequests {
+                    detail: format!("Rate limit of {} requests per minute exceeded", config.requests_per_minute),
+                    retry_after_secs: Some(retry_after_secs),
+                };
+                return Box::pin(async move { Ok(error.into_response()) });
+            }
+        }
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(inner.call(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{bearer, serve_engine};
+    use crate::EngineBuilder;
+
+    #[tokio::test]
+    async fn test_rate_limit_update_applies_to_next_request() {
+        let base = serve_engine(EngineBuilder::new()).await;
+        let client = reqwest::Client::new();
+        let admin = bearer("", &["admin"]);
+        let list = || client.get(format!("{}/api/v1/agents", base)).bearer_auth(&admin).send();
+
+        for _ in 0..5 {
+            assert_eq!(list().await.unwrap().status(), reqwest::StatusCode::OK);
+        }
+
+        // The config comes back without the JWT secret, which the update keeps
+        let mut config: serde_json::Value = client
+            .get(format!("{}/api/v1/system/config", base))
+            .bearer_auth(&admin)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        config["rate_limit"]["requests_per_minute"] = 60.into();
+        config["rate_limit"]["burst_limit"] = 2.into();
+        let updated = client.put(format!("{}/api/v1/system/config", base)).bearer_auth(&admin).json(&config).send().await.unwrap();
+        assert_eq!(updated.status(), reqwest::StatusCode::OK);
+
+        assert_eq!(list().await.unwrap().status(), reqwest::StatusCode::OK);
+        assert_eq!(list().await.unwrap().status(), reqwest::StatusCode::OK);
+        let limited = list().await.unwrap();
+        assert_eq!(limited.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+        assert!(limited.headers().contains_key(reqwest::header::RETRY_AFTER));
+    }
+}
@@ -0,0 +1,142 @@
+//! Request and response types of the core API
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::job_events::JobState;
+use crate::scheduling::ExecutionClass;
+
+/// Lifecycle state of an agent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentStatus {
+    #[default]
+    Active,
+    Paused,
+    Retired,
+}
+
+impl AgentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Paused => "paused",
+            Self::Retired => "retired",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Agent {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub status: AgentStatus,
+    /// Module the agent runs, if it is backed by one
+    #[serde(default)]
+    pub wasm_module_id: Option<String>,
+    #[serde(default)]
+    pub config: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Body of `POST /api/v1/agents`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateAgentRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub wasm_module_id: Option<String>,
+    #[serde(default)]
+    pub config: serde_json::Value,
+}
+
+/// Body of `PUT /api/v1/agents/:id`; absent fields are left unchanged
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdateAgentRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub status: Option<AgentStatus>,
+    pub wasm_module_id: Option<String>,
+    pub config: Option<serde_json::Value>,
+}
+
+/// An uploaded WASM module
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WasmModuleInfo {
+    pub id: String,
+    pub name: String,
+    pub size_bytes: usize,
+    /// Hex SHA-256 of the module bytes
+    pub sha256: String,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+/// Result of a synchronous module execution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionResult {
+    pub module_id: String,
+    pub output: serde_json::Value,
+    pub execution_time_ms: u64,
+    pub fuel_consumed: u64,
+}
+
+/// Body of `POST /api/v1/jobs`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubmitJobRequest {
+    pub module_id: String,
+    #[serde(default)]
+    pub input: serde_json::Value,
+    /// Defaults to `batch`
+    #[serde(default)]
+    pub execution_class: Option<ExecutionClass>,
+}
+
+/// A queued module execution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    pub module_id: String,
+    pub execution_class: ExecutionClass,
+    pub state: JobState,
+    pub submitted_at: DateTime<Utc>,
+    #[serde(default)]
+    pub finished_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub output: Option<serde_json::Value>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// A tool offered by a configured MCP server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct McpTool {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Server the tool is executed on
+    pub server: String,
+    #[serde(default)]
+    pub input_schema: serde_json::Value,
+}
+
+/// Health of a dependency in `GET /api/v1/system/status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyHealth {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemStatus {
+    pub version: String,
+    pub uptime_secs: u64,
+    pub database: DependencyHealth,
+    pub cache: DependencyHealth,
+    pub agents: usize,
+    pub wasm_modules: usize,
+    pub active_jobs: usize,
+}
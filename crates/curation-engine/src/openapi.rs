@@ -0,0 +1,133 @@
+//! OpenAPI document for the curation engine API
+//!
+//! Handlers carry `#[utoipa::path]` annotations and their request and response
+//! types derive `ToSchema`; `ApiDoc` collects them into an OpenAPI 3.0
+//! document. `docs()` serves it at `GET /openapi.json`, with a Swagger UI at
+//! `/docs`.
+
+use axum::Router;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::{canary, drift, error, execution_stream, job_events, live_config, similarity, EngineState};
+
+/// Path of the OpenAPI document
+pub const OPENAPI_PATH: &str = "/openapi.json";
+
+/// Path of the Swagger UI
+pub const DOCS_PATH: &str = "/docs";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "AutoAgents Curation Engine",
+        description = "Control plane for AutoAgents agents, WASM modules, jobs and MCP tools"
+    ),
+    paths(
+        similarity::similar_agents,
+        canary::get_canary,
+        canary::configure_canary,
+        canary::finalize_canary,
+        drift::list_drift_reports,
+        drift::review_drift_report,
+        execution_stream::execute_wasm_module_stream,
+        job_events::job_status_ws,
+        similarity::similar_tools,
+    ),
+    components(schemas(
+        similarity::ItemKind,
+        similarity::SimilarItem,
+        similarity::SimilarResponse,
+        canary::ComparisonMode,
+        canary::CanaryConfig,
+        canary::CanaryDecision,
+        canary::CanaryReport,
+        canary::CanaryComparison,
+        canary::OutputDifference,
+        canary::VersionStats,
+        canary::FinalizeCanaryRequest,
+        drift::BehaviorFingerprint,
+        drift::DriftDimension,
+        drift::DimensionDrift,
+        drift::DriftReportStatus,
+        drift::DriftReport,
+        execution_stream::OutputStream,
+        execution_stream::OutputChunk,
+        execution_stream::ExecutionDone,
+        job_events::JobState,
+        job_events::JobStatus,
+        error::ProblemDetails,
+        live_config::FieldError,
+    )),
+    tags(
+        (name = "agents", description = "Agent management"),
+        (name = "wasm", description = "WASM module execution"),
+        (name = "jobs", description = "Job status"),
+        (name = "mcp", description = "MCP tool discovery"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Routes serving the OpenAPI document and the Swagger UI
+pub fn docs() -> Router<EngineState> {
+    SwaggerUi::new(DOCS_PATH).url(OPENAPI_PATH, ApiDoc::openapi()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::serve;
+
+    /// Every `$ref` in `value` that does not resolve to a component schema
+    fn dangling_refs(value: &serde_json::Value, schemas: &serde_json::Map<String, serde_json::Value>) -> Vec<String> {
+        match value {
+            serde_json::Value::Object(map) => map
+                .iter()
+                .flat_map(|(key, value)| match (key.as_str(), value.as_str()) {
+                    ("$ref", Some(reference)) => {
+                        let name = reference.trim_start_matches("#/components/schemas/");
+                        if schemas.contains_key(name) { vec![] } else { vec![reference.to_string()] }
+                    }
+                    _ => dangling_refs(value, schemas),
+                })
+                .collect(),
+            serde_json::Value::Array(items) => items.iter().flat_map(|item| dangling_refs(item, schemas)).collect(),
+            _ => vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_openapi_document_is_valid_and_served() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        assert!(spec["openapi"].as_str().unwrap().starts_with("3.0"));
+
+        // Parses as an OpenAPI 3.0 document, and every schema reference resolves
+        let parsed: openapiv3::OpenAPI = serde_json::from_value(spec.clone()).unwrap();
+        for path in [
+            "/api/v1/agents/{id}/similar",
+            "/api/v1/agents/{id}/canary",
+            "/api/v1/agents/{id}/canary/finalize",
+            "/api/v1/agents/{id}/drift",
+            "/api/v1/agents/{id}/drift/{report_id}/review",
+            "/api/v1/wasm/modules/{id}/execute/stream",
+            "/api/v1/jobs/{id}/ws",
+            "/api/v1/mcp/tools/similar",
+        ] {
+            assert!(parsed.paths.paths.contains_key(path), "{} is not documented", path);
+        }
+        let schemas = spec["components"]["schemas"].as_object().unwrap();
+        assert_eq!(dangling_refs(&spec, schemas), Vec::<String>::new());
+
+        let similar = &spec["paths"]["/api/v1/agents/{id}/similar"]["get"]["parameters"];
+        let names: Vec<&str> = similar.as_array().unwrap().iter().map(|p| p["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["id", "q", "limit"]);
+
+        let base = serve(SwaggerUi::new(DOCS_PATH).url(OPENAPI_PATH, ApiDoc::openapi()).into()).await;
+
+        let served: serde_json::Value = reqwest::get(format!("{}{}", base, OPENAPI_PATH)).await.unwrap().json().await.unwrap();
+        assert_eq!(served, spec);
+        let ui = reqwest::get(format!("{}{}/", base, DOCS_PATH)).await.unwrap();
+        assert_eq!(ui.status(), reqwest::StatusCode::OK);
+        assert!(ui.text().await.unwrap().contains("swagger"));
+    }
+}
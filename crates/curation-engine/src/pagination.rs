@@ -0,0 +1,140 @@
+//! Pagination and filtering for list endpoints
+//!
+//! List handlers take a `ListQuery` (`?limit=&offset=&cursor=&status=&name_contains=`),
+//! filter their items with it and return one page in a `Page` envelope. The
+//! cursor is opaque to clients: it is handed back as `next_cursor` and
+//! replaces `offset` on the next request. Pages are capped at
+//! `MAX_PAGE_SIZE` whatever the client asks for, and an offset past the end
+//! yields an empty page rather than an error.
+
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+
+/// Page size when the client does not ask for one
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Largest page a client can ask for
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// Pagination and filter parameters shared by list endpoints
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ListQuery {
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    /// `next_cursor` of the previous page; takes precedence over `offset`
+    pub cursor: Option<String>,
+    /// Only items in this status, compared case-insensitively
+    pub status: Option<String>,
+    /// Only items whose name contains this text, compared case-insensitively
+    pub name_contains: Option<String>,
+}
+
+impl ListQuery {
+    /// Page size, capped at `MAX_PAGE_SIZE`
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+    }
+
+    /// Index of the first item on the page; a malformed cursor is a bad request
+    pub fn offset(&self) -> Result<usize, StatusCode> {
+        match &self.cursor {
+            Some(cursor) => cursor.parse().map_err(|_| StatusCode::BAD_REQUEST),
+            None => Ok(self.offset.unwrap_or(0)),
+        }
+    }
+
+    /// Whether an item passes the `status` and `name_contains` filters
+    pub fn matches(&self, name: &str, status: Option<&str>) -> bool {
+        let status_matches = self.status.as_deref().is_none_or(|wanted| {
+            status.is_some_and(|status| status.eq_ignore_ascii_case(wanted))
+        });
+        let name_matches = self.name_contains.as_deref().is_none_or(|text| {
+            name.to_lowercase().contains(&text.to_lowercase())
+        });
+        status_matches && name_matches
+    }
+
+    /// One page of already filtered `items`
+    pub fn paginate<T>(&self, items: Vec<T>) -> Result<Page<T>, StatusCode> {
+        let offset = self.offset()?;
+        let total = items.len();
+        let items: Vec<T> = items.into_iter().skip(offset).take(self.limit()).collect();
+        let end = offset.saturating_add(items.len());
+        Ok(Page {
+            next_cursor: (end < total).then(|| end.to_string()),
+            items,
+            total,
+        })
+    }
+}
+
+/// A page of a list response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Items matching the filters across all pages
+    pub total: usize,
+    /// Cursor of the next page; absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::serve;
+    use axum::{extract::Query, routing::get, Json, Router};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Item {
+        name: String,
+        status: String,
+    }
+
+    /// List handler over 120 agents, every third one failed
+    async fn list_items(Query(query): Query<ListQuery>) -> Result<Json<Page<Item>>, StatusCode> {
+        let items: Vec<Item> = (0..120)
+            .map(|i| Item {
+                name: format!("Agent-{:03}", i),
+                status: if i % 3 == 0 { "failed" } else { "running" }.to_string(),
+            })
+            .filter(|item| query.matches(&item.name, Some(&item.status)))
+            .collect();
+        query.paginate(items).map(Json)
+    }
+
+    async fn get_page(base: &str, query: &str) -> (reqwest::StatusCode, Option<Page<Item>>) {
+        let response = reqwest::get(format!("{}/items?{}", base, query)).await.unwrap();
+        let status = response.status();
+        (status, response.json().await.ok())
+    }
+
+    #[tokio::test]
+    async fn test_list_handler_pages_and_filters() {
+        let base = serve(Router::new().route("/items", get(list_items))).await;
+
+        let (_, page) = get_page(&base, "limit=10&offset=20").await;
+        let page = page.unwrap();
+        assert_eq!((page.items.len(), page.total, page.next_cursor.as_deref()), (10, 120, Some("30")));
+        assert_eq!(page.items[0].name, "Agent-020");
+
+        // Following the cursor continues where the previous page ended
+        let (_, page) = get_page(&base, "limit=100&cursor=30").await;
+        let page = page.unwrap();
+        assert_eq!((page.items.len(), page.next_cursor), (90, None));
+
+        // Page size is capped, and filters apply before slicing
+        let (_, page) = get_page(&base, "limit=100000&status=FAILED&name_contains=agent-1").await;
+        let page = page.unwrap();
+        assert!(page.items.iter().all(|item| item.status == "failed" && item.name.starts_with("Agent-1")));
+        assert_eq!((page.items.len(), page.total), (6, 6));
+        assert_eq!(ListQuery { limit: Some(100_000), ..Default::default() }.limit(), MAX_PAGE_SIZE);
+
+        let (status, page) = get_page(&base, "offset=500").await;
+        assert_eq!(status, reqwest::StatusCode::OK);
+        let page = page.unwrap();
+        assert_eq!((page.items.len(), page.total, page.next_cursor), (0, 120, None));
+
+        let (status, _) = get_page(&base, "cursor=not-a-cursor").await;
+        assert_eq!(status, reqwest::StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,568 @@
+//! Snapshots of a curation setup for promotion between environments
+//!
+//! An export holds a tenant's agent and MCP tool catalog entries, running
+//! canaries, webhook subscriptions and branding, keyed by identifiers that
+//! stay the same across environments. Importing diffs every resource against
+//! the target (create, update, skip or conflict), stops there on a dry run,
+//! and otherwise applies each resource on its own, so a failure leaves the
+//! rest in place. Snapshots carry no secrets, tenant ids or subscription ids;
+//! ids that differ between environments and the secrets new webhooks are
+//! signed with come from a mapping supplied on import.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    authz::Tenant,
+    canary::{CanaryConfig, CanaryService},
+    error::ApiError,
+    export::{EnvSecrets, SecretSource},
+    similarity::{ItemKind, SimilarityService},
+    webhooks::{NewSubscription, TenantBranding, WebhookEventType, WebhookService},
+    EngineState,
+};
+
+/// Kind of promotable resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceType {
+    Agent,
+    McpTool,
+    Canary,
+    Webhook,
+    Branding,
+}
+
+impl ResourceType {
+    pub const ALL: [ResourceType; 5] = [Self::Agent, Self::McpTool, Self::Canary, Self::Webhook, Self::Branding];
+}
+
+/// A promotable resource, without secrets or environment-specific ids
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Resource {
+    /// Catalog entry of an agent
+    Agent { id: String, description: String },
+    /// Catalog entry of an MCP tool
+    McpTool { name: String, description: String },
+    /// Running canary of an agent
+    Canary { agent_id: String, config: CanaryConfig },
+    /// Webhook subscription, identified by its URL
+    Webhook { url: String, event_types: Vec<WebhookEventType> },
+    Branding { branding: TenantBranding },
+}
+
+impl Resource {
+    pub fn resource_type(&self) -> ResourceType {
+        match self {
+            Self::Agent { .. } => ResourceType::Agent,
+            Self::McpTool { .. } => ResourceType::McpTool,
+            Self::Canary { .. } => ResourceType::Canary,
+            Self::Webhook { .. } => ResourceType::Webhook,
+            Self::Branding { .. } => ResourceType::Branding,
+        }
+    }
+
+    /// Identifier of the resource, the same in every environment
+    pub fn key(&self) -> &str {
+        match self {
+            Self::Agent { id, .. } => id,
+            Self::McpTool { name, .. } => name,
+            Self::Canary { agent_id, .. } => agent_id,
+            Self::Webhook { url, .. } => url,
+            Self::Branding { .. } => "branding",
+        }
+    }
+
+    /// The resource as it is named in the target environment
+    fn remap(self, mapping: &PromotionMapping) -> Self {
+        let id = |id: String| mapping.ids.get(&id).cloned().unwrap_or(id);
+        match self {
+            Self::Agent { id: agent_id, description } => Self::Agent { id: id(agent_id), description },
+            Self::McpTool { name, description } => Self::McpTool { name: id(name), description },
+            Self::Canary { agent_id, config } => Self::Canary { agent_id: id(agent_id), config },
+            Self::Webhook { url, event_types } => Self::Webhook { url: mapping.map_url(url), event_types },
+            branding @ Self::Branding { .. } => branding,
+        }
+    }
+}
+
+/// Exported curation setup of a tenant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromotionSnapshot {
+    pub exported_at: DateTime<Utc>,
+    pub resources: Vec<Resource>,
+}
+
+/// Differences between the source and target environments
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PromotionMapping {
+    /// Agent ids and MCP tool names that differ in the target
+    #[serde(default)]
+    pub ids: BTreeMap<String, String>,
+    /// URL prefixes replaced in webhook URLs; the longest matching prefix wins
+    #[serde(default)]
+    pub url_prefixes: BTreeMap<String, String>,
+    /// Name of the secret each webhook is signed with, by target URL. Required
+    /// to create a webhook; an updated webhook keeps its secret unless mapped.
+    #[serde(default)]
+    pub webhook_secrets: BTreeMap<String, String>,
+}
+
+impl PromotionMapping {
+    fn map_url(&self, url: String) -> String {
+        self.url_prefixes
+            .iter()
+            .filter(|(from, _)| url.starts_with(from.as_str()))
+            .max_by_key(|(from, _)| from.len())
+            .map(|(from, to)| format!("{}{}", to, &url[from.len()..]))
+            .unwrap_or(url)
+    }
+}
+
+/// What importing a resource does to the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeAction {
+    Create,
+    Update,
+    /// The target already matches
+    Skip,
+    /// The resource cannot be applied; `reason` says why
+    Conflict,
+}
+
+/// Planned change to one resource, and its outcome once applied
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceChange {
+    pub resource_type: ResourceType,
+    pub key: String,
+    pub action: ChangeAction,
+    #[serde(default)]
+    pub reason: Option<String>,
+    pub applied: bool,
+    /// Why applying a create or update failed
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Diff of an import, with the outcome of each change unless it was a dry run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromotionReport {
+    pub dry_run: bool,
+    pub changes: Vec<ResourceChange>,
+}
+
+impl PromotionReport {
+    pub fn count(&self, action: ChangeAction) -> usize {
+        self.changes.iter().filter(|change| change.action == action).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.changes.iter().filter(|change| change.error.is_some()).count()
+    }
+}
+
+/// Request body of an import
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportRequest {
+    pub snapshot: PromotionSnapshot,
+    #[serde(default)]
+    pub mapping: PromotionMapping,
+    /// Return the diff without applying it
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Exports and imports promotion snapshots against the services of one environment
+#[derive(Clone)]
+pub struct PromotionService {
+    similarity: SimilarityService,
+    canaries: Option<CanaryService>,
+    webhooks: WebhookService,
+    secrets: Arc<dyn SecretSource>,
+}
+
+impl PromotionService {
+    /// Webhook secrets named in mappings are read from the environment
+    pub fn new(similarity: SimilarityService, canaries: Option<CanaryService>, webhooks: WebhookService) -> Self {
+        Self {
+            similarity,
+            canaries,
+            webhooks,
+            secrets: Arc::new(EnvSecrets),
+        }
+    }
+
+    pub fn with_secrets(mut self, secrets: Arc<dyn SecretSource>) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    pub(crate) fn from_state(state: &EngineState) -> Self {
+        Self::new(
+            state.similarity_service.clone(),
+            state.canary_service.clone(),
+            state.webhook_service.clone(),
+        )
+    }
+
+    /// Resources of the selected types, in a stable order
+    pub async fn export(&self, tenant: &str, types: &[ResourceType]) -> PromotionSnapshot {
+        let mut resources = Vec::new();
+        for resource_type in ResourceType::ALL.into_iter().filter(|t| types.contains(t)) {
+            match resource_type {
+                ResourceType::Agent => resources.extend(
+                    self.similarity.items(tenant, ItemKind::Agent).await
+                        .into_iter()
+                        .map(|(id, description)| Resource::Agent { id, description }),
+                ),
+                ResourceType::McpTool => resources.extend(
+                    self.similarity.items(tenant, ItemKind::McpTool).await
+                        .into_iter()
+                        .map(|(name, description)| Resource::McpTool { name, description }),
+                ),
+                ResourceType::Canary => {
+                    if let Some(canaries) = &self.canaries {
+                        resources.extend(
+                            canaries.configs().await
+                                .into_iter()
+                                .map(|(agent_id, config)| Resource::Canary { agent_id, config }),
+                        );
+                    }
+                }
+                ResourceType::Webhook => resources.extend(
+                    self.webhooks.list(tenant).await
+                        .into_iter()
+                        .map(|sub| Resource::Webhook { url: sub.url, event_types: sub.event_types }),
+                ),
+                ResourceType::Branding => {
+                    let branding = self.webhooks.branding(tenant).await;
+                    if branding != TenantBranding::default() {
+                        resources.push(Resource::Branding { branding });
+                    }
+                }
+            }
+        }
+
+        PromotionSnapshot {
+            exported_at: Utc::now(),
+            resources,
+        }
+    }
+
+    /// Diff a snapshot against this environment and, unless `dry_run`, apply it
+    pub async fn import(
+        &self,
+        tenant: &str,
+        snapshot: PromotionSnapshot,
+        mapping: &PromotionMapping,
+        dry_run: bool,
+    ) -> PromotionReport {
+        let mut current: HashMap<(ResourceType, String), Vec<Resource>> = HashMap::new();
+        for resource in self.export(tenant, &ResourceType::ALL).await.resources {
+            current.entry((resource.resource_type(), resource.key().to_string())).or_default().push(resource);
+        }
+
+        let mut seen = HashSet::new();
+        let mut changes = Vec::new();
+        for resource in snapshot.resources {
+            let resource = resource.remap(mapping);
+            let (action, reason) = self.plan(&resource, &current, mapping, &mut seen);
+            let mut change = ResourceChange {
+                resource_type: resource.resource_type(),
+                key: resource.key().to_string(),
+                action,
+                reason,
+                applied: false,
+                error: None,
+            };
+            if !dry_run && matches!(action, ChangeAction::Create | ChangeAction::Update) {
+                match self.apply(tenant, resource, mapping).await {
+                    Ok(()) => change.applied = true,
+                    Err(e) => {
+                        warn!("🚚 Failed to apply {:?} {}: {}", change.resource_type, change.key, e);
+                        change.error = Some(e);
+                    }
+                }
+            }
+            changes.push(change);
+        }
+
+        let report = PromotionReport { dry_run, changes };
+        info!(
+            "🚚 {} snapshot for tenant {}: {} create, {} update, {} skip, {} conflict, {} failed",
+            if dry_run { "Diffed" } else { "Imported" },
+            tenant,
+            report.count(ChangeAction::Create),
+            report.count(ChangeAction::Update),
+            report.count(ChangeAction::Skip),
+            report.count(ChangeAction::Conflict),
+            report.failed(),
+        );
+        report
+    }
+
+    fn plan(
+        &self,
+        resource: &Resource,
+        current: &HashMap<(ResourceType, String), Vec<Resource>>,
+        mapping: &PromotionMapping,
+        seen: &mut HashSet<(ResourceType, String)>,
+    ) -> (ChangeAction, Option<String>) {
+        let conflict = |reason: String| (ChangeAction::Conflict, Some(reason));
+        let id = (resource.resource_type(), resource.key().to_string());
+        if !seen.insert(id.clone()) {
+            return conflict("Resource appears more than once in the snapshot".to_string());
+        }
+        if matches!(resource, Resource::Canary { .. }) && self.canaries.is_none() {
+            return conflict("Canary deployments are not enabled in the target".to_string());
+        }
+        if let Resource::Webhook { url, .. } = resource {
+            if let Err(reason) = self.webhook_secret(url, mapping) {
+                return conflict(reason);
+            }
+        }
+
+        match current.get(&id).map(Vec::as_slice) {
+            None | Some([]) => match resource {
+                Resource::Webhook { url, .. } if !mapping.webhook_secrets.contains_key(url) => {
+                    conflict(format!("No secret is mapped for new webhook {}", url))
+                }
+                _ => (ChangeAction::Create, None),
+            },
+            Some([existing]) if existing == resource => (ChangeAction::Skip, None),
+            Some([_]) => (ChangeAction::Update, None),
+            Some(existing) => conflict(format!("{} resources in the target share this key", existing.len())),
+        }
+    }
+
+    /// Signing secret mapped for a webhook URL, if any
+    fn webhook_secret(&self, url: &str, mapping: &PromotionMapping) -> Result<Option<String>, String> {
+        let Some(name) = mapping.webhook_secrets.get(url) else {
+            return Ok(None);
+        };
+        match self.secrets.get(name) {
+            Some(secret) => Ok(Some(secret)),
+            None => Err(format!("Secret '{}' mapped for webhook {} is not set", name, url)),
+        }
+    }
+
+    async fn apply(&self, tenant: &str, resource: Resource, mapping: &PromotionMapping) -> Result<(), String> {
+        match resource {
+            Resource::Agent { id, description } => {
+                self.similarity.upsert(tenant, ItemKind::Agent, &id, &description).await;
+            }
+            Resource::McpTool { name, description } => {
+                self.similarity.upsert(tenant, ItemKind::McpTool, &name, &description).await;
+            }
+            Resource::Canary { agent_id, config } => {
+                let canaries = self.canaries.as_ref().ok_or("Canary deployments are not enabled")?;
+                canaries.configure(&agent_id, config).await.map_err(|e| e.to_string())?;
+            }
+            Resource::Webhook { url, event_types } => {
+                let secret = self.webhook_secret(&url, mapping)?;
+                let existing = self.webhooks.list(tenant).await.into_iter().find(|sub| sub.url == url);
+                match (existing, secret) {
+                    (Some(existing), secret) => {
+                        self.webhooks.update(tenant, &existing.id, event_types, secret).await.map_err(|e| e.to_string())?;
+                    }
+                    (None, Some(secret)) => {
+                        self.webhooks
+                            .subscribe(tenant, NewSubscription { url, secret, event_types })
+                            .await
+                            .map_err(|e| e.to_string())?;
+                    }
+                    (None, None) => return Err(format!("No secret is mapped for new webhook {}", url)),
+                }
+            }
+            Resource::Branding { branding } => self.webhooks.set_branding(tenant, branding).await,
+        }
+        Ok(())
+    }
+}
+
+/// Query parameters of the export endpoint
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    /// Comma-separated resource types, e.g. `agent,webhook`; every type when unset
+    pub types: Option<String>,
+}
+
+/// `GET /api/v1/promotion/export`
+pub async fn export_snapshot(
+    State(state): State<EngineState>,
+    Query(query): Query<ExportQuery>,
+    Tenant(tenant): Tenant,
+) -> Result<Json<PromotionSnapshot>, ApiError> {
+    let types = match query.types {
+        Some(types) => types
+            .split(',')
+            .map(|name| serde_json::from_value(serde_json::Value::String(name.trim().to_string())))
+            .collect::<Result<Vec<ResourceType>, _>>()
+            .map_err(|e| ApiError::BadRequest(format!("Invalid resource type in `types`: {}", e)))?,
+        None => ResourceType::ALL.to_vec(),
+    };
+    let service = PromotionService::from_state(&state);
+    Ok(Json(service.export(&tenant, &types).await))
+}
+
+/// `POST /api/v1/promotion/import`
+pub async fn import_snapshot(
+    State(state): State<EngineState>,
+    Tenant(tenant): Tenant,
+    Json(request): Json<ImportRequest>,
+) -> Json<PromotionReport> {
+    let service = PromotionService::from_state(&state);
+    Json(
+        service
+            .import(&tenant, request.snapshot, &request.mapping, request.dry_run)
+            .await,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canary::{AgentExecutor, ComparisonMode};
+    use futures::future::BoxFuture;
+
+    struct EchoAgent;
+
+    impl AgentExecutor for EchoAgent {
+        fn execute<'a>(
+            &'a self,
+            _agent_id: &'a str,
+            _version: &'a str,
+            input: &'a serde_json::Value,
+        ) -> BoxFuture<'a, Result<serde_json::Value, String>> {
+            Box::pin(async move { Ok(input.clone()) })
+        }
+    }
+
+    fn environment(secrets: &[(&str, &str)]) -> PromotionService {
+        let secrets: HashMap<String, String> = secrets.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        PromotionService::new(
+            SimilarityService::default(),
+            Some(CanaryService::new(Arc::new(EchoAgent))),
+            WebhookService::default(),
+        )
+        .with_secrets(Arc::new(secrets))
+    }
+
+    fn canary(percentage: u8) -> CanaryConfig {
+        CanaryConfig {
+            stable_version: "1".to_string(),
+            candidate_version: "2".to_string(),
+            percentage,
+            comparison: ComparisonMode::Off,
+        }
+    }
+
+    fn subscription(url: &str, event_types: Vec<WebhookEventType>) -> NewSubscription {
+        NewSubscription { url: url.to_string(), secret: "staging-secret".to_string(), event_types }
+    }
+
+    fn actions(report: &PromotionReport) -> Vec<(&str, ChangeAction)> {
+        report.changes.iter().map(|change| (change.key.as_str(), change.action)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_promote_from_staging_to_production() {
+        let staging = environment(&[]);
+        staging.similarity.upsert("acme", ItemKind::Agent, "pr-reviewer", "Reviews pull requests").await;
+        staging.similarity.upsert("acme", ItemKind::Agent, "stg-summarizer", "Summarizes tickets, v2").await;
+        staging.similarity.upsert("acme", ItemKind::McpTool, "search", "Searches documents").await;
+        staging.canaries.as_ref().unwrap().configure("pr-reviewer", canary(20)).await.unwrap();
+        staging.webhooks.subscribe("acme", subscription("https://hooks.staging.acme.io/jobs", vec![WebhookEventType::JobCompleted])).await.unwrap();
+        staging.webhooks.set_branding("acme", TenantBranding { display_name: Some("Acme".to_string()), ..Default::default() }).await;
+
+        let production = environment(&[("PROD_JOBS_HOOK_SECRET", "prod-secret")]);
+        production.similarity.upsert("acme", ItemKind::Agent, "summarizer", "Summarizes tickets").await;
+        production.similarity.upsert("acme", ItemKind::McpTool, "search", "Searches documents").await;
+
+        let snapshot = staging.export("acme", &ResourceType::ALL).await;
+        let exported = serde_json::to_string(&snapshot).unwrap();
+        assert!(!exported.contains("staging-secret"));
+
+        // Snapshots travel as JSON between environments
+        let snapshot: PromotionSnapshot = serde_json::from_str(&exported).unwrap();
+        let mapping = PromotionMapping {
+            ids: BTreeMap::from([("stg-summarizer".to_string(), "summarizer".to_string())]),
+            url_prefixes: BTreeMap::from([("https://hooks.staging.acme.io".to_string(), "https://hooks.acme.io".to_string())]),
+            webhook_secrets: BTreeMap::from([("https://hooks.acme.io/jobs".to_string(), "PROD_JOBS_HOOK_SECRET".to_string())]),
+        };
+
+        let before = serde_json::to_value(production.export("acme", &ResourceType::ALL).await.resources).unwrap();
+        let dry_run = production.import("acme", snapshot.clone(), &mapping, true).await;
+        let expected = vec![
+            ("pr-reviewer", ChangeAction::Create),
+            ("summarizer", ChangeAction::Update),
+            ("search", ChangeAction::Skip),
+            ("pr-reviewer", ChangeAction::Create),
+            ("https://hooks.acme.io/jobs", ChangeAction::Create),
+            ("branding", ChangeAction::Create),
+        ];
+        assert_eq!(actions(&dry_run), expected);
+        assert!(dry_run.changes.iter().all(|change| !change.applied));
+        let after = serde_json::to_value(production.export("acme", &ResourceType::ALL).await.resources).unwrap();
+        assert_eq!(before, after);
+
+        // Applying makes exactly the changes the dry run reported
+        let applied = production.import("acme", snapshot.clone(), &mapping, false).await;
+        assert_eq!(actions(&applied), expected);
+        assert_eq!(applied.failed(), 0);
+        assert_eq!(applied.changes.iter().filter(|change| change.applied).count(), 5);
+        assert_eq!(production.similarity.items("acme", ItemKind::Agent).await[1], ("summarizer".to_string(), "Summarizes tickets, v2".to_string()));
+        let hooks = production.webhooks.list("acme").await;
+        assert_eq!((hooks[0].url.as_str(), hooks[0].secret.as_str()), ("https://hooks.acme.io/jobs", "prod-secret"));
+        assert_eq!(production.canaries.as_ref().unwrap().configs().await, vec![("pr-reviewer".to_string(), canary(20))]);
+
+        // A repeated import finds nothing left to change
+        let repeated = production.import("acme", snapshot, &mapping, false).await;
+        assert_eq!(repeated.count(ChangeAction::Skip), repeated.changes.len());
+    }
+
+    #[tokio::test]
+    async fn test_conflicts_are_reported_and_not_applied() {
+        let staging = environment(&[]);
+        staging.canaries.as_ref().unwrap().configure("pr-reviewer", canary(10)).await.unwrap();
+        staging.webhooks.subscribe("acme", subscription("https://hooks.acme.io/jobs", vec![WebhookEventType::JobFailed])).await.unwrap();
+        staging.webhooks.subscribe("acme", subscription("https://hooks.acme.io/agents", vec![WebhookEventType::AgentUpdated])).await.unwrap();
+        let mut snapshot = staging.export("acme", &[ResourceType::Canary, ResourceType::Webhook]).await;
+        snapshot.resources.push(snapshot.resources[0].clone());
+
+        let production = PromotionService::new(SimilarityService::default(), None, WebhookService::default())
+            .with_secrets(Arc::new(HashMap::<String, String>::new()));
+        production.webhooks.subscribe("acme", subscription("https://hooks.acme.io/agents", vec![WebhookEventType::JobFailed])).await.unwrap();
+        let mapping = PromotionMapping {
+            webhook_secrets: BTreeMap::from([("https://hooks.acme.io/jobs".to_string(), "MISSING".to_string())]),
+            ..Default::default()
+        };
+
+        let report = production.import("acme", snapshot, &mapping, false).await;
+        assert_eq!(
+            actions(&report),
+            vec![
+                ("pr-reviewer", ChangeAction::Conflict),
+                ("https://hooks.acme.io/jobs", ChangeAction::Conflict),
+                ("https://hooks.acme.io/agents", ChangeAction::Update),
+                ("pr-reviewer", ChangeAction::Conflict),
+            ]
+        );
+        assert!(report.changes[1].reason.as_deref().unwrap().contains("'MISSING'"));
+
+        // The existing webhook keeps its secret when none is mapped
+        let hooks = production.webhooks.list("acme").await;
+        assert_eq!(hooks.len(), 1);
+        assert_eq!((hooks[0].event_types.clone(), hooks[0].secret.as_str()), (vec![WebhookEventType::AgentUpdated], "staging-secret"));
+    }
+}
@@ -0,0 +1,208 @@
+//! Job queue
+//!
+//! A submitted job runs its module through the `ExecutionScheduler`, as batch
+//! work unless it asked otherwise, and turns running once a worker picks it
+//! up. Each state change is published to `JobEvents`, so WebSocket
+//! subscribers follow the job, and finished jobs are announced to webhook
+//! subscribers. Cancelling a job that has not finished stops its execution,
+//! or keeps it from starting.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use chrono::Utc;
+use tokio::sync::{Notify, RwLock};
+use tracing::info;
+
+use crate::{
+    config::WasmConfig,
+    error::ApiError,
+    job_events::{JobEvents, JobState, JobStatus},
+    models::{Job, SubmitJobRequest},
+    scheduling::{ExecutionClass, ExecutionScheduler},
+    services::{ServiceError, WasmService},
+    webhooks::{WebhookEventType, WebhookService},
+};
+
+/// Job errors
+#[derive(Debug, thiserror::Error)]
+pub enum QueueError {
+    #[error("Job '{0}' not found")]
+    NotFound(String),
+    #[error("Job '{0}' already finished")]
+    AlreadyFinished(String),
+    #[error(transparent)]
+    Service(#[from] ServiceError),
+}
+
+impl From<QueueError> for ApiError {
+    fn from(err: QueueError) -> Self {
+        match err {
+            QueueError::NotFound(_) => ApiError::NotFound(err.to_string()),
+            QueueError::AlreadyFinished(_) => ApiError::Conflict(err.to_string()),
+            QueueError::Service(err) => err.into(),
+        }
+    }
+}
+
+/// Finished jobs kept for listing before the oldest are dropped
+const MAX_FINISHED_JOBS: usize = 1000;
+
+struct JobRecord {
+    tenant: String,
+    job: Job,
+    /// Notified once to stop the execution
+    cancel: Arc<Notify>,
+}
+
+#[derive(Default)]
+struct Jobs {
+    records: HashMap<String, JobRecord>,
+    /// Finished job ids, oldest first
+    finished: VecDeque<String>,
+}
+
+/// Jobs of every tenant and the tasks running them
+#[derive(Clone)]
+pub struct JobQueue {
+    jobs: Arc<RwLock<Jobs>>,
+    wasm: WasmService,
+    scheduler: ExecutionScheduler,
+    events: JobEvents,
+    webhooks: WebhookService,
+}
+
+impl JobQueue {
+    pub fn new(wasm: WasmService, scheduler: ExecutionScheduler, events: JobEvents, webhooks: WebhookService) -> Self {
+        Self {
+            jobs: Arc::new(RwLock::new(Jobs::default())),
+            wasm,
+            scheduler,
+            events,
+            webhooks,
+        }
+    }
+
+    /// Queue a run of a tenant's module under `limits`
+    pub async fn submit(&self, tenant: &str, request: SubmitJobRequest, limits: WasmConfig) -> Result<Job, QueueError> {
+        if self.wasm.get(tenant, &request.module_id).await.is_none() {
+            return Err(ServiceError::ModuleNotFound(request.module_id).into());
+        }
+        let job = Job {
+            id: uuid::Uuid::new_v4().to_string(),
+            module_id: request.module_id.clone(),
+            execution_class: ExecutionClass::resolve(false, request.execution_class),
+            state: JobState::Queued,
+            submitted_at: Utc::now(),
+            finished_at: None,
+            output: None,
+            error: None,
+        };
+        let cancel = Arc::new(Notify::new());
+        // Recorded before it is scheduled so it cannot finish an unknown job
+        let mut jobs = self.jobs.write().await;
+        jobs.records.insert(job.id.clone(), JobRecord { tenant: tenant.to_string(), job: job.clone(), cancel: cancel.clone() });
+        self.events.publish(JobStatus::new(&job.id, tenant, JobState::Queued)).await;
+
+        let queue = self.clone();
+        let (tenant_id, job_id) = (tenant.to_string(), job.id.clone());
+        let execution = self.scheduler.submit(job.execution_class, move |checkpoint| async move {
+            queue.set_state(&job_id, JobState::Running, None, None).await;
+            tokio::select! {
+                biased;
+                _ = cancel.notified() => None,
+                result = queue.wasm.execute(&tenant_id, &request.module_id, &request.input, &limits, Some(checkpoint)) => Some(result),
+            }
+        });
+        let queue = self.clone();
+        let job_id = job.id.clone();
+        tokio::spawn(async move {
+            match execution.await.map_err(ServiceError::from) {
+                Ok(Some(Ok(result))) => queue.set_state(&job_id, JobState::Completed, Some(result.output), None).await,
+                Ok(Some(Err(e))) | Err(e) => queue.set_state(&job_id, JobState::Failed, None, Some(e.to_string())).await,
+                Ok(None) => {}
+            }
+        });
+        info!("🧾 Queued job {} of module {} for tenant {}", job.id, job.module_id, tenant);
+        Ok(job)
+    }
+
+    /// Record a state change, publish it and, once finished, tell webhook subscribers
+    async fn set_state(&self, job_id: &str, state: JobState, output: Option<serde_json::Value>, error: Option<String>) {
+        let (tenant, job) = {
+            let mut jobs = self.jobs.write().await;
+            let Some(record) = jobs.records.get_mut(job_id) else { return };
+            if record.job.state.is_terminal() {
+                return;
+            }
+            record.job.state = state;
+            if state.is_terminal() {
+                record.job.finished_at = Some(Utc::now());
+                record.job.output = output;
+                record.job.error = error.clone();
+            }
+            let updated = (record.tenant.clone(), record.job.clone());
+            if state.is_terminal() {
+                jobs.finished.push_back(job_id.to_string());
+                while jobs.finished.len() > MAX_FINISHED_JOBS {
+                    if let Some(expired) = jobs.finished.pop_front() {
+                        jobs.records.remove(&expired);
+                    }
+                }
+            }
+            updated
+        };
+
+        let mut status = JobStatus::new(job_id, &tenant, state);
+        if let Some(error) = &error {
+            status = status.with_message(error);
+        }
+        self.events.publish(status).await;
+        let event_type = match state {
+            JobState::Completed => WebhookEventType::JobCompleted,
+            JobState::Failed => WebhookEventType::JobFailed,
+            _ => return,
+        };
+        self.webhooks.publish(&tenant, event_type, serde_json::to_value(&job).unwrap_or_default()).await;
+    }
+
+    pub async fn get(&self, tenant: &str, id: &str) -> Option<Job> {
+        self.jobs.read().await.records.get(id).filter(|record| record.tenant == tenant).map(|record| record.job.clone())
+    }
+
+    /// A tenant's jobs, oldest first
+    pub async fn list(&self, tenant: &str) -> Vec<Job> {
+        let mut jobs: Vec<Job> = self
+            .jobs
+            .read()
+            .await
+            .records
+            .values()
+            .filter(|record| record.tenant == tenant)
+            .map(|record| record.job.clone())
+            .collect();
+        jobs.sort_by(|a, b| (a.submitted_at, &a.id).cmp(&(b.submitted_at, &b.id)));
+        jobs
+    }
+
+    /// Jobs across all tenants that have not finished
+    pub async fn active(&self) -> usize {
+        self.jobs.read().await.records.values().filter(|record| !record.job.state.is_terminal()).count()
+    }
+
+    /// Stop a job that has not finished yet
+    pub async fn cancel(&self, tenant: &str, id: &str) -> Result<Job, QueueError> {
+        {
+            let jobs = self.jobs.read().await;
+            let record = jobs.records.get(id).filter(|record| record.tenant == tenant).ok_or_else(|| QueueError::NotFound(id.to_string()))?;
+            if record.job.state.is_terminal() {
+                return Err(QueueError::AlreadyFinished(id.to_string()));
+            }
+            // Keeps its permit when the job has not started yet
+            record.cancel.notify_one();
+        }
+        self.set_state(id, JobState::Cancelled, None, None).await;
+        info!("🧾 Cancelled job {} for tenant {}", id, tenant);
+        self.get(tenant, id).await.ok_or_else(|| QueueError::NotFound(id.to_string()))
+    }
+}
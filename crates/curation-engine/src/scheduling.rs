@@ -0,0 +1,391 @@
+//! Execution classes and priority scheduling
+//!
+//! Work is either interactive, such as a synchronous execute call a client is
+//! waiting on, or batch, such as a queued job. Interactive work always goes
+//! first, except that after `batch_every` interactive executions in a row
+//! while batch work waits, one batch execution is started regardless and
+//! runs without being preempted, so batch work keeps a bounded share.
+//!
+//! Preemption is cooperative. When interactive work is waiting and every
+//! worker is busy, running batch executions are asked to yield. A batch
+//! execution that reaches a yield point (a WASM module's `env.yield()` import
+//! calls `Checkpoint::yield_point`) hands its worker back and is paused until
+//! a worker is free for it again, ahead of batch work not yet started. The
+//! execute route and the job queue run every WASM execution through the
+//! engine's `ExecutionScheduler`.
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use tracing::{debug, warn};
+use utoipa::{IntoParams, ToSchema};
+
+/// Scheduling class of an execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionClass {
+    /// A caller is waiting on the result
+    Interactive,
+    /// Queued work that can be paused for interactive work
+    Batch,
+}
+
+impl ExecutionClass {
+    /// Class of work submitted through a synchronous execute route or the job
+    /// queue, unless the caller asked for one
+    pub fn resolve(synchronous: bool, requested: Option<ExecutionClass>) -> Self {
+        requested.unwrap_or(if synchronous { Self::Interactive } else { Self::Batch })
+    }
+}
+
+/// Override of the class a route would assign
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct ClassOverride {
+    /// Defaults to `interactive` for synchronous execution and `batch` for jobs
+    #[serde(default)]
+    pub execution_class: Option<ExecutionClass>,
+}
+
+/// Worker pool and fairness settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulingConfig {
+    /// Executions running at once
+    pub workers: usize,
+    /// Interactive executions started in a row, while batch work waits,
+    /// before one batch execution is started regardless
+    pub batch_every: u32,
+    /// How long interactive work should wait at most for batch work to
+    /// yield; longer waits are logged
+    pub preemption_deadline_ms: u64,
+}
+
+impl Default for SchedulingConfig {
+    fn default() -> Self {
+        Self {
+            workers: 4,
+            batch_every: 4,
+            preemption_deadline_ms: 250,
+        }
+    }
+}
+
+/// An execution ended without producing a result
+#[derive(Debug, thiserror::Error)]
+#[error("Execution was aborted before completing")]
+pub struct ExecutionAborted;
+
+type Job = Box<dyn FnOnce(Checkpoint) -> BoxFuture<'static, ()> + Send>;
+
+enum Start {
+    New(Job),
+    /// A paused batch execution waiting for a worker
+    Resume(oneshot::Sender<()>),
+}
+
+struct Pending {
+    id: u64,
+    class: ExecutionClass,
+    start: Start,
+    queued_at: Instant,
+    yield_requested: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    idle_workers: usize,
+    interactive: VecDeque<Pending>,
+    batch: VecDeque<Pending>,
+    /// Interactive executions started since the last batch one, counted only
+    /// while batch work waits
+    interactive_streak: u32,
+    /// Running batch executions that may be asked to yield
+    preemptible: HashMap<u64, Arc<AtomicBool>>,
+}
+
+struct SchedulerInner {
+    config: SchedulingConfig,
+    next_id: AtomicU64,
+    state: Mutex<SchedulerState>,
+}
+
+/// Runs executions on a fixed number of workers, interactive ones first
+#[derive(Clone)]
+pub struct ExecutionScheduler {
+    inner: Arc<SchedulerInner>,
+}
+
+impl Default for ExecutionScheduler {
+    fn default() -> Self {
+        Self::new(SchedulingConfig::default())
+    }
+}
+
+impl ExecutionScheduler {
+    pub fn new(config: SchedulingConfig) -> Self {
+        let state = SchedulerState {
+            idle_workers: config.workers.max(1),
+            ..Default::default()
+        };
+        Self {
+            inner: Arc::new(SchedulerInner {
+                config,
+                next_id: AtomicU64::new(1),
+                state: Mutex::new(state),
+            }),
+        }
+    }
+
+    /// Queue `execution` as `class` and resolve to its output once it has run.
+    /// It is queued immediately, whether or not the returned future is polled.
+    pub fn submit<F, Fut, T>(&self, class: ExecutionClass, execution: F) -> impl Future<Output = Result<T, ExecutionAborted>>
+    where
+        F: FnOnce(Checkpoint) -> Fut + Send + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let job: Job = Box::new(move |checkpoint| {
+            Box::pin(async move {
+                let _ = tx.send(execution(checkpoint).await);
+            })
+        });
+
+        let pending = Pending {
+            id: self.inner.next_id.fetch_add(1, Ordering::Relaxed),
+            class,
+            start: Start::New(job),
+            queued_at: Instant::now(),
+            yield_requested: Arc::new(AtomicBool::new(false)),
+        };
+        {
+            let mut state = self.inner.state.lock().unwrap();
+            match class {
+                ExecutionClass::Interactive => state.interactive.push_back(pending),
+                ExecutionClass::Batch => state.batch.push_back(pending),
+            }
+            self.inner.dispatch(&mut state);
+        }
+
+        async move { rx.await.map_err(|_| ExecutionAborted) }
+    }
+}
+
+impl SchedulerInner {
+    /// Start queued work on idle workers, then ask batch work to yield for
+    /// any interactive work still waiting
+    fn dispatch(self: &Arc<Self>, state: &mut SchedulerState) {
+        while state.idle_workers > 0 {
+            let batch_turn = !state.batch.is_empty()
+                && (state.interactive.is_empty() || state.interactive_streak >= self.config.batch_every);
+            let (next, reserved) = if batch_turn {
+                let reserved = !state.interactive.is_empty();
+                state.interactive_streak = 0;
+                (state.batch.pop_front(), reserved)
+            } else {
+                if !state.batch.is_empty() {
+                    state.interactive_streak += 1;
+                }
+                (state.interactive.pop_front(), false)
+            };
+            let Some(pending) = next else { break };
+            state.idle_workers -= 1;
+            self.start(state, pending, reserved);
+        }
+
+        if state.idle_workers == 0 && !state.interactive.is_empty() {
+            let already_asked = state
+                .preemptible
+                .values()
+                .filter(|flag| flag.load(Ordering::SeqCst))
+                .count();
+            let needed = state.interactive.len().saturating_sub(already_asked);
+            for flag in state
+                .preemptible
+                .values()
+                .filter(|flag| !flag.load(Ordering::SeqCst))
+                .take(needed)
+            {
+                flag.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+
+    fn start(self: &Arc<Self>, state: &mut SchedulerState, pending: Pending, reserved: bool) {
+        let waited = pending.queued_at.elapsed();
+        if pending.class == ExecutionClass::Interactive
+            && waited > Duration::from_millis(self.config.preemption_deadline_ms)
+        {
+            warn!("⏱️ Interactive execution {} waited {:?} for a worker", pending.id, waited);
+        }
+        // The batch execution given its reserved turn runs to completion
+        if pending.class == ExecutionClass::Batch && !reserved {
+            state.preemptible.insert(pending.id, pending.yield_requested.clone());
+        }
+
+        match pending.start {
+            Start::New(job) => {
+                let checkpoint = Checkpoint {
+                    id: pending.id,
+                    class: pending.class,
+                    yield_requested: pending.yield_requested,
+                    scheduler: self.clone(),
+                };
+                let inner = self.clone();
+                let id = pending.id;
+                tokio::spawn(async move {
+                    job(checkpoint).await;
+                    inner.finished(id);
+                });
+            }
+            Start::Resume(resume) => {
+                debug!("▶️ Resuming batch execution {}", pending.id);
+                let _ = resume.send(());
+            }
+        }
+    }
+
+    fn finished(self: &Arc<Self>, id: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.preemptible.remove(&id);
+        state.idle_workers += 1;
+        self.dispatch(&mut state);
+    }
+}
+
+/// Handle an execution uses to cooperate with the scheduler
+pub struct Checkpoint {
+    id: u64,
+    class: ExecutionClass,
+    yield_requested: Arc<AtomicBool>,
+    scheduler: Arc<SchedulerInner>,
+}
+
+impl Checkpoint {
+    pub fn class(&self) -> ExecutionClass {
+        self.class
+    }
+
+    /// Whether the scheduler wants this execution to pause at its next yield point
+    pub fn should_yield(&self) -> bool {
+        self.yield_requested.load(Ordering::SeqCst)
+    }
+
+    /// Pause here if interactive work is waiting for this execution's
+    /// worker, resuming once a worker is free again; returns at once otherwise
+    pub async fn yield_point(&self) {
+        if !self.should_yield() {
+            return;
+        }
+
+        let (resume, resumed) = oneshot::channel();
+        {
+            let scheduler = &self.scheduler;
+            let mut state = scheduler.state.lock().unwrap();
+            self.yield_requested.store(false, Ordering::SeqCst);
+            state.preemptible.remove(&self.id);
+            state.idle_workers += 1;
+            state.batch.push_front(Pending {
+                id: self.id,
+                class: self.class,
+                start: Start::Resume(resume),
+                queued_at: Instant::now(),
+                yield_requested: self.yield_requested.clone(),
+            });
+            debug!("⏸️ Batch execution {} yielded to interactive work", self.id);
+            scheduler.dispatch(&mut state);
+        }
+        let _ = resumed.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[tokio::test]
+    async fn test_interactive_preempts_busy_batch_worker() {
+        let config = SchedulingConfig {
+            workers: 1,
+            preemption_deadline_ms: 200,
+            ..Default::default()
+        };
+        let deadline = Duration::from_millis(config.preemption_deadline_ms);
+        let scheduler = ExecutionScheduler::new(config);
+
+        // A long batch execution that reaches a yield point every 5ms
+        let started = Arc::new(tokio::sync::Notify::new());
+        let batch_started = started.clone();
+        let yields = Arc::new(AtomicUsize::new(0));
+        let batch_yields = yields.clone();
+        let batch = scheduler.submit(ExecutionClass::Batch, move |checkpoint| async move {
+            batch_started.notify_one();
+            for _ in 0..60 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                if checkpoint.should_yield() {
+                    batch_yields.fetch_add(1, Ordering::SeqCst);
+                }
+                checkpoint.yield_point().await;
+            }
+            "batch done"
+        });
+        let batch = tokio::spawn(batch);
+        started.notified().await;
+
+        let submitted = Instant::now();
+        let waited = scheduler
+            .submit(ExecutionClass::Interactive, move |_| async move { submitted.elapsed() })
+            .await
+            .unwrap();
+        assert!(waited < deadline, "interactive execution waited {:?}", waited);
+
+        // The paused batch execution picks up where it left off
+        assert_eq!(batch.await.unwrap().unwrap(), "batch done");
+        assert_eq!(yields.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_batch_keeps_bounded_share() {
+        let scheduler = ExecutionScheduler::new(SchedulingConfig {
+            workers: 1,
+            batch_every: 2,
+            ..Default::default()
+        });
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let gate = Arc::new(tokio::sync::Notify::new());
+
+        let held = gate.clone();
+        let first = scheduler.submit(ExecutionClass::Interactive, move |_| async move { held.notified().await });
+        let mut runs = vec![];
+        for (name, class) in [
+            ("batch", ExecutionClass::Batch),
+            ("i1", ExecutionClass::Interactive),
+            ("i2", ExecutionClass::Interactive),
+            ("i3", ExecutionClass::Interactive),
+            ("i4", ExecutionClass::Interactive),
+        ] {
+            let order = order.clone();
+            runs.push(scheduler.submit(class, move |_| async move { order.lock().unwrap().push(name) }));
+        }
+
+        gate.notify_one();
+        first.await.unwrap();
+        for run in runs {
+            run.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), ["i1", "i2", "batch", "i3", "i4"]);
+    }
+
+    #[test]
+    fn test_class_follows_route_unless_overridden() {
+        assert_eq!(ExecutionClass::resolve(true, None), ExecutionClass::Interactive);
+        assert_eq!(ExecutionClass::resolve(false, None), ExecutionClass::Batch);
+        assert_eq!(ExecutionClass::resolve(true, Some(ExecutionClass::Batch)), ExecutionClass::Batch);
+    }
+}
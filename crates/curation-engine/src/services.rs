@@ -0,0 +1,330 @@
+//! Agent, WASM module and metrics services behind the core API
+//!
+//! Agents and modules are kept per tenant. The agent service also holds the
+//! database and cache handles whose health `GET /api/v1/system/status` reports.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use chrono::Utc;
+use prometheus::{Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder};
+use sha2::{Digest, Sha256};
+use tokio::sync::RwLock;
+use tracing::info;
+use wasmtime::Module;
+
+use crate::{
+    cache::Cache,
+    config::{DatabaseConfig, MetricsConfig, RedisConfig, WasmConfig},
+    database::Database,
+    error::ApiError,
+    models::{Agent, CreateAgentRequest, DependencyHealth, ExecutionResult, UpdateAgentRequest, WasmModuleInfo},
+    scheduling::{Checkpoint, ExecutionAborted},
+    wasm_runtime::{WasmError, WasmRuntime},
+};
+
+/// Service errors
+#[derive(Debug, thiserror::Error)]
+pub enum ServiceError {
+    #[error("Agent '{0}' not found")]
+    AgentNotFound(String),
+    #[error("WASM module '{0}' not found")]
+    ModuleNotFound(String),
+    #[error("Agent name must not be empty")]
+    EmptyName,
+    #[error(transparent)]
+    Wasm(#[from] WasmError),
+    #[error(transparent)]
+    Aborted(#[from] ExecutionAborted),
+    #[error("Invalid database config: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Invalid Redis config: {0}")]
+    Cache(#[from] redis::RedisError),
+    #[error("Failed to register metrics: {0}")]
+    Metrics(#[from] prometheus::Error),
+}
+
+impl From<ServiceError> for ApiError {
+    fn from(err: ServiceError) -> Self {
+        let detail = err.to_string();
+        match err {
+            ServiceError::AgentNotFound(_) | ServiceError::ModuleNotFound(_) => ApiError::NotFound(detail),
+            ServiceError::EmptyName | ServiceError::Wasm(_) => ApiError::BadRequest(detail),
+            ServiceError::Aborted(_) | ServiceError::Database(_) | ServiceError::Cache(_) | ServiceError::Metrics(_) => {
+                ApiError::Internal(detail)
+            }
+        }
+    }
+}
+
+/// Agents of every tenant, by tenant and then agent id
+#[derive(Clone)]
+pub struct AgentService {
+    agents: Arc<RwLock<HashMap<String, HashMap<String, Agent>>>>,
+    database: Database,
+    cache: Cache,
+}
+
+impl AgentService {
+    pub async fn new(database: DatabaseConfig, redis: RedisConfig) -> Result<Self, ServiceError> {
+        Ok(Self {
+            agents: Arc::new(RwLock::new(HashMap::new())),
+            database: Database::connect_lazy(&database)?,
+            cache: Cache::open(&redis)?,
+        })
+    }
+
+    pub async fn create(&self, tenant: &str, request: CreateAgentRequest) -> Result<Agent, ServiceError> {
+        if request.name.trim().is_empty() {
+            return Err(ServiceError::EmptyName);
+        }
+        let now = Utc::now();
+        let agent = Agent {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: request.name,
+            description: request.description,
+            status: Default::default(),
+            wasm_module_id: request.wasm_module_id,
+            config: request.config,
+            created_at: now,
+            updated_at: now,
+        };
+        self.agents.write().await.entry(tenant.to_string()).or_default().insert(agent.id.clone(), agent.clone());
+        info!("🤖 Created agent {} ({}) for tenant {}", agent.id, agent.name, tenant);
+        Ok(agent)
+    }
+
+    pub async fn get(&self, tenant: &str, id: &str) -> Option<Agent> {
+        self.agents.read().await.get(tenant)?.get(id).cloned()
+    }
+
+    pub async fn update(&self, tenant: &str, id: &str, request: UpdateAgentRequest) -> Result<Agent, ServiceError> {
+        if request.name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+            return Err(ServiceError::EmptyName);
+        }
+        let mut agents = self.agents.write().await;
+        let agent = agents
+            .get_mut(tenant)
+            .and_then(|agents| agents.get_mut(id))
+            .ok_or_else(|| ServiceError::AgentNotFound(id.to_string()))?;
+        if let Some(name) = request.name {
+            agent.name = name;
+        }
+        if let Some(description) = request.description {
+            agent.description = description;
+        }
+        if let Some(status) = request.status {
+            agent.status = status;
+        }
+        if let Some(module_id) = request.wasm_module_id {
+            agent.wasm_module_id = Some(module_id);
+        }
+        if let Some(config) = request.config {
+            agent.config = config;
+        }
+        agent.updated_at = Utc::now();
+        Ok(agent.clone())
+    }
+
+    pub async fn delete(&self, tenant: &str, id: &str) -> Result<Agent, ServiceError> {
+        let agent = self
+            .agents
+            .write()
+            .await
+            .get_mut(tenant)
+            .and_then(|agents| agents.remove(id))
+            .ok_or_else(|| ServiceError::AgentNotFound(id.to_string()))?;
+        info!("🤖 Deleted agent {} for tenant {}", id, tenant);
+        Ok(agent)
+    }
+
+    /// A tenant's agents, oldest first
+    pub async fn list(&self, tenant: &str) -> Vec<Agent> {
+        let mut agents: Vec<Agent> = self.agents.read().await.get(tenant).map(|agents| agents.values().cloned().collect()).unwrap_or_default();
+        agents.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        agents
+    }
+
+    /// Agents across all tenants
+    pub async fn count(&self) -> usize {
+        self.agents.read().await.values().map(HashMap::len).sum()
+    }
+
+    /// Health of the database and the cache
+    pub async fn health(&self) -> (DependencyHealth, DependencyHealth) {
+        let (database, cache) = tokio::join!(self.database.ping(), self.cache.ping());
+        let health = |up: bool| if up { DependencyHealth::Up } else { DependencyHealth::Down };
+        (health(database), health(cache))
+    }
+}
+
+struct StoredModule {
+    info: WasmModuleInfo,
+    module: Module,
+}
+
+/// Uploaded WASM modules of every tenant and the runtime executing them
+#[derive(Clone)]
+pub struct WasmService {
+    runtime: Arc<WasmRuntime>,
+    modules: Arc<RwLock<HashMap<String, HashMap<String, StoredModule>>>>,
+}
+
+impl WasmService {
+    pub fn new() -> Self {
+        Self {
+            runtime: Arc::new(WasmRuntime::new()),
+            modules: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Compile and store a module; invalid modules are rejected
+    pub async fn upload(&self, tenant: &str, name: &str, wasm: &[u8]) -> Result<WasmModuleInfo, ServiceError> {
+        let module = self.runtime.compile(wasm)?;
+        let info = WasmModuleInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            size_bytes: wasm.len(),
+            sha256: hex::encode(Sha256::digest(wasm)),
+            uploaded_at: Utc::now(),
+        };
+        self.modules
+            .write()
+            .await
+            .entry(tenant.to_string())
+            .or_default()
+            .insert(info.id.clone(), StoredModule { info: info.clone(), module });
+        info!("📦 Uploaded WASM module {} ({}, {} bytes) for tenant {}", info.id, name, info.size_bytes, tenant);
+        Ok(info)
+    }
+
+    pub async fn get(&self, tenant: &str, id: &str) -> Option<WasmModuleInfo> {
+        Some(self.modules.read().await.get(tenant)?.get(id)?.info.clone())
+    }
+
+    /// A tenant's modules, oldest first
+    pub async fn list(&self, tenant: &str) -> Vec<WasmModuleInfo> {
+        let mut modules: Vec<WasmModuleInfo> = self
+            .modules
+            .read()
+            .await
+            .get(tenant)
+            .map(|modules| modules.values().map(|stored| stored.info.clone()).collect())
+            .unwrap_or_default();
+        modules.sort_by(|a, b| (a.uploaded_at, &a.id).cmp(&(b.uploaded_at, &b.id)));
+        modules
+    }
+
+    /// Modules across all tenants
+    pub async fn count(&self) -> usize {
+        self.modules.read().await.values().map(HashMap::len).sum()
+    }
+
+    /// Run a tenant's module on `input` under `limits`, yielding through `checkpoint`
+    pub async fn execute(
+        &self,
+        tenant: &str,
+        id: &str,
+        input: &serde_json::Value,
+        limits: &WasmConfig,
+        checkpoint: Option<Checkpoint>,
+    ) -> Result<ExecutionResult, ServiceError> {
+        let module = self
+            .modules
+            .read()
+            .await
+            .get(tenant)
+            .and_then(|modules| modules.get(id))
+            .map(|stored| stored.module.clone())
+            .ok_or_else(|| ServiceError::ModuleNotFound(id.to_string()))?;
+
+        let started = Instant::now();
+        let result = self.runtime.execute(&module, input, limits, checkpoint).await?;
+        Ok(ExecutionResult {
+            module_id: id.to_string(),
+            output: result.output,
+            execution_time_ms: started.elapsed().as_millis() as u64,
+            fuel_consumed: result.fuel_consumed,
+        })
+    }
+}
+
+impl Default for WasmService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Prometheus metrics of the engine, in a registry of its own
+#[derive(Clone)]
+pub struct MetricsService {
+    registry: Registry,
+    agent_operations: IntCounterVec,
+    wasm_executions: IntCounterVec,
+    wasm_execution_seconds: HistogramVec,
+}
+
+impl MetricsService {
+    pub async fn new(config: MetricsConfig) -> Result<Self, ServiceError> {
+        let registry = Registry::new_custom(Some(config.namespace), None)?;
+        let agent_operations = IntCounterVec::new(Opts::new("agent_operations_total", "Agent create, update and delete operations"), &["operation"])?;
+        let wasm_executions = IntCounterVec::new(Opts::new("wasm_executions_total", "WASM module executions"), &["outcome"])?;
+        let wasm_execution_seconds = HistogramVec::new(
+            HistogramOpts::new("wasm_execution_seconds", "Duration of WASM module executions"),
+            &["outcome"],
+        )?;
+        registry.register(Box::new(agent_operations.clone()))?;
+        registry.register(Box::new(wasm_executions.clone()))?;
+        registry.register(Box::new(wasm_execution_seconds.clone()))?;
+        Ok(Self {
+            registry,
+            agent_operations,
+            wasm_executions,
+            wasm_execution_seconds,
+        })
+    }
+
+    pub fn record_agent_operation(&self, operation: &str) {
+        self.agent_operations.with_label_values(&[operation]).inc();
+    }
+
+    pub fn record_wasm_execution(&self, success: bool, execution_time_ms: u64) {
+        let outcome = if success { "success" } else { "failure" };
+        self.wasm_executions.with_label_values(&[outcome]).inc();
+        self.wasm_execution_seconds.with_label_values(&[outcome]).observe(execution_time_ms as f64 / 1000.0);
+    }
+
+    /// Agent operations so far, by operation
+    pub fn agent_operations(&self) -> HashMap<String, u64> {
+        counts(&self.agent_operations, "operation")
+    }
+
+    /// WASM executions so far, by outcome
+    pub fn wasm_executions(&self) -> HashMap<String, u64> {
+        counts(&self.wasm_executions, "outcome")
+    }
+
+    /// Every metric in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        let encoder = TextEncoder::new();
+        if let Err(e) = encoder.encode(&self.registry.gather(), &mut buffer) {
+            tracing::warn!("📊 Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+fn counts(counter: &IntCounterVec, label: &str) -> HashMap<String, u64> {
+    use prometheus::core::Collector;
+    counter
+        .collect()
+        .iter()
+        .flat_map(|family| family.get_metric())
+        .filter_map(|metric| {
+            let value = metric.get_label().iter().find(|pair| pair.get_name() == label)?.get_value().to_string();
+            Some((value, metric.get_counter().get_value() as u64))
+        })
+        .collect()
+}
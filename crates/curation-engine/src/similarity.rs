@@ -0,0 +1,416 @@
+//! Embedding-based similarity search for agent and MCP tool recommendation
+//!
+//! Descriptions are embedded through a pluggable [`Embedder`] and kept in a
+//! small in-process vector index searched by cosine similarity. A
+//! deterministic feature-hashing embedder is included so no external model
+//! is required.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{authz::Tenant, error::ApiError, EngineState};
+
+/// Tenant of tokens without a `tenant` claim, and of every request while auth is disabled
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Number of results returned when no limit is requested
+const DEFAULT_LIMIT: usize = 5;
+
+/// Upper bound on requested results
+const MAX_LIMIT: usize = 50;
+
+/// Turns text into a fixed-size embedding vector
+pub trait Embedder: Send + Sync {
+    /// Embed a text into a vector of `dimensions()` length
+    fn embed(&self, text: &str) -> Vec<f32>;
+
+    /// Length of produced vectors
+    fn dimensions(&self) -> usize;
+}
+
+/// Deterministic feature-hashing embedder.
+///
+/// Tokens (lowercased alphanumeric words and word bigrams) are hashed into
+/// buckets with a signed term frequency, then L2-normalized. Tokens are
+/// hashed with FNV-1a, so a text embeds the same in every process and release.
+#[derive(Debug, Clone)]
+pub struct HashingEmbedder {
+    dimensions: usize,
+}
+
+impl HashingEmbedder {
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions: dimensions.max(1),
+        }
+    }
+
+    fn tokenize(text: &str) -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| word.len() > 1)
+            .map(|word| word.to_lowercase())
+            .collect()
+    }
+
+    fn bucket(&self, token: &str) -> (usize, f32) {
+        let hash = fnv1a(token.as_bytes());
+        let sign = if hash & 1 == 0 { 1.0 } else { -1.0 };
+        ((hash >> 1) as usize % self.dimensions, sign)
+    }
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl Embedder for HashingEmbedder {
+    fn embed(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0; self.dimensions];
+        let tokens = Self::tokenize(text);
+
+        for token in &tokens {
+            let (index, sign) = self.bucket(token);
+            vector[index] += sign;
+        }
+        for pair in tokens.windows(2) {
+            let (index, sign) = self.bucket(&format!("{} {}", pair[0], pair[1]));
+            vector[index] += 0.5 * sign;
+        }
+
+        normalize(&mut vector);
+        vector
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+}
+
+/// 64-bit FNV-1a
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|v| v * v).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// Kind of indexed resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    Agent,
+    McpTool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct IndexKey {
+    tenant: String,
+    kind: ItemKind,
+    id: String,
+}
+
+#[derive(Debug, Clone)]
+struct IndexEntry {
+    description: String,
+    vector: Vec<f32>,
+}
+
+/// A similarity search hit
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SimilarItem {
+    pub id: String,
+    pub kind: ItemKind,
+    pub description: String,
+    pub score: f32,
+}
+
+/// In-process vector index over agent and tool descriptions, partitioned by tenant
+#[derive(Clone)]
+pub struct SimilarityService {
+    embedder: Arc<dyn Embedder>,
+    index: Arc<RwLock<HashMap<IndexKey, IndexEntry>>>,
+}
+
+impl SimilarityService {
+    /// Create a service using the given embedder
+    pub fn new(embedder: Arc<dyn Embedder>) -> Self {
+        Self {
+            embedder,
+            index: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Index or re-index an item's description. Called on agent/tool create and update.
+    pub async fn upsert(&self, tenant: &str, kind: ItemKind, id: &str, description: &str) {
+        let entry = IndexEntry {
+            description: description.to_string(),
+            vector: self.embedder.embed(description),
+        };
+        self.index.write().await.insert(Self::key(tenant, kind, id), entry);
+    }
+
+    /// Drop an item from the index. Called on agent/tool delete.
+    pub async fn remove(&self, tenant: &str, kind: ItemKind, id: &str) -> bool {
+        self.index.write().await.remove(&Self::key(tenant, kind, id)).is_some()
+    }
+
+    /// Index `items` of `kind` as `(id, description)` pairs; with `prune`,
+    /// the tenant's other items of that kind are dropped
+    pub async fn sync(&self, tenant: &str, kind: ItemKind, items: &[(String, String)], prune: bool) {
+        let entries: Vec<(IndexKey, IndexEntry)> = items
+            .iter()
+            .map(|(id, description)| {
+                let entry = IndexEntry {
+                    description: description.clone(),
+                    vector: self.embedder.embed(description),
+                };
+                (Self::key(tenant, kind, id), entry)
+            })
+            .collect();
+        let mut index = self.index.write().await;
+        if prune {
+            index.retain(|key, _| key.tenant != tenant || key.kind != kind || items.iter().any(|(id, _)| *id == key.id));
+        }
+        index.extend(entries);
+    }
+
+    /// Ids and descriptions of a tenant's indexed items of `kind`, sorted by id
+    pub async fn items(&self, tenant: &str, kind: ItemKind) -> Vec<(String, String)> {
+        let mut items: Vec<(String, String)> = self.index.read().await
+            .iter()
+            .filter(|(key, _)| key.tenant == tenant && key.kind == kind)
+            .map(|(key, entry)| (key.id.clone(), entry.description.clone()))
+            .collect();
+        items.sort();
+        items
+    }
+
+    /// Find the items of `kind` most similar to an already indexed item
+    pub async fn similar_to(&self, tenant: &str, kind: ItemKind, id: &str, limit: usize) -> Option<Vec<SimilarItem>> {
+        let vector = self.index.read().await
+            .get(&Self::key(tenant, kind, id))?
+            .vector
+            .clone();
+
+        Some(self.search_vector(tenant, kind, &vector, Some(id), limit).await)
+    }
+
+    /// Find the items of `kind` most similar to free text
+    pub async fn search(&self, tenant: &str, kind: ItemKind, query: &str, limit: usize) -> Vec<SimilarItem> {
+        let vector = self.embedder.embed(query);
+        self.search_vector(tenant, kind, &vector, None, limit).await
+    }
+
+    async fn search_vector(
+        &self,
+        tenant: &str,
+        kind: ItemKind,
+        vector: &[f32],
+        exclude_id: Option<&str>,
+        limit: usize,
+    ) -> Vec<SimilarItem> {
+        let index = self.index.read().await;
+        let mut hits: Vec<SimilarItem> = index
+            .iter()
+            .filter(|(key, _)| key.tenant == tenant && key.kind == kind)
+            .filter(|(key, _)| Some(key.id.as_str()) != exclude_id)
+            .map(|(key, entry)| SimilarItem {
+                id: key.id.clone(),
+                kind,
+                description: entry.description.clone(),
+                score: cosine(vector, &entry.vector),
+            })
+            .filter(|hit| hit.score > 0.0)
+            .collect();
+
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+        hits.truncate(limit.clamp(1, MAX_LIMIT));
+        hits
+    }
+
+    fn key(tenant: &str, kind: ItemKind, id: &str) -> IndexKey {
+        IndexKey {
+            tenant: tenant.to_string(),
+            kind,
+            id: id.to_string(),
+        }
+    }
+}
+
+impl Default for SimilarityService {
+    fn default() -> Self {
+        Self::new(Arc::new(HashingEmbedder::default()))
+    }
+}
+
+/// Query parameters for similarity endpoints
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SimilarQuery {
+    /// Text to search for; required by tool search
+    pub q: Option<String>,
+    /// Number of results, at most 50
+    pub limit: Option<usize>,
+}
+
+/// Response body for similarity endpoints
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SimilarResponse {
+    pub items: Vec<SimilarItem>,
+}
+
+/// `GET /api/v1/agents/:id/similar`
+#[utoipa::path(
+    get,
+    path = "/api/v1/agents/{id}/similar",
+    tag = "agents",
+    params(("id" = String, Path, description = "Agent id"), SimilarQuery),
+    responses(
+        (status = 200, description = "Agents with the most similar descriptions", body = SimilarResponse),
+        (status = 404, description = "Agent is not indexed"),
+    )
+)]
+pub async fn similar_agents(
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+    Query(query): Query<SimilarQuery>,
+    Tenant(tenant): Tenant,
+) -> Result<Json<SimilarResponse>, ApiError> {
+    let items = state.similarity_service
+        .similar_to(&tenant, ItemKind::Agent, &id, query.limit.unwrap_or(DEFAULT_LIMIT))
+        .await
+        .ok_or_else(|| ApiError::NotFound(format!("Agent '{}' is not indexed", id)))?;
+
+    Ok(Json(SimilarResponse { items }))
+}
+
+/// `GET /api/v1/mcp/tools/similar?q=`
+#[utoipa::path(
+    get,
+    path = "/api/v1/mcp/tools/similar",
+    tag = "mcp",
+    params(SimilarQuery),
+    responses(
+        (status = 200, description = "MCP tools whose descriptions best match `q`", body = SimilarResponse),
+        (status = 400, description = "`q` is missing or blank"),
+    )
+)]
+pub async fn similar_tools(
+    State(state): State<EngineState>,
+    Query(query): Query<SimilarQuery>,
+    Tenant(tenant): Tenant,
+) -> Result<Json<SimilarResponse>, ApiError> {
+    let text = query
+        .q
+        .filter(|q| !q.trim().is_empty())
+        .ok_or_else(|| ApiError::BadRequest("Query parameter `q` must not be blank".to_string()))?;
+    let items = state.similarity_service
+        .search(&tenant, ItemKind::McpTool, &text, query.limit.unwrap_or(DEFAULT_LIMIT))
+        .await;
+
+    Ok(Json(SimilarResponse { items }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn seeded() -> SimilarityService {
+        let service = SimilarityService::default();
+        let agents = [
+            ("pr-reviewer", "Reviews GitHub pull requests and leaves code review comments"),
+            ("code-reviewer", "Automated code review for pull requests with inline comments"),
+            ("invoice-parser", "Extracts line items and totals from PDF invoices"),
+            ("weather-bot", "Answers questions about the weather forecast"),
+        ];
+        for (id, description) in agents {
+            service.upsert("acme", ItemKind::Agent, id, description).await;
+        }
+        service.upsert("acme", ItemKind::McpTool, "github", "GitHub API: pull requests, issues, code review").await;
+        service.upsert("acme", ItemKind::McpTool, "pdf", "Read and extract text from PDF documents").await;
+        service.upsert("other", ItemKind::Agent, "foreign-reviewer", "Reviews pull requests and code review comments").await;
+        service
+    }
+
+    #[tokio::test]
+    async fn test_nearest_neighbors() {
+        let service = seeded().await;
+
+        let similar = service.similar_to("acme", ItemKind::Agent, "pr-reviewer", 3).await.unwrap();
+        assert_eq!(similar[0].id, "code-reviewer");
+        assert!(similar.iter().all(|hit| hit.id != "pr-reviewer"));
+
+        let tools = service.search("acme", ItemKind::McpTool, "extract text from a PDF invoice", 1).await;
+        assert_eq!(tools[0].id, "pdf");
+        assert!(tools[0].score > 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_isolation() {
+        let service = seeded().await;
+
+        let similar = service.similar_to("acme", ItemKind::Agent, "pr-reviewer", 10).await.unwrap();
+        assert!(similar.iter().all(|hit| hit.id != "foreign-reviewer"));
+        assert!(service.similar_to("other", ItemKind::Agent, "pr-reviewer", 10).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_deleted_agents_drop_out() {
+        let service = seeded().await;
+
+        assert!(service.remove("acme", ItemKind::Agent, "code-reviewer").await);
+        let similar = service.similar_to("acme", ItemKind::Agent, "pr-reviewer", 10).await.unwrap();
+        assert!(similar.iter().all(|hit| hit.id != "code-reviewer"));
+    }
+
+    #[tokio::test]
+    async fn test_sync_prunes_unlisted_items() {
+        let service = seeded().await;
+        let listed = [("github".to_string(), "GitHub API: pull requests and issues".to_string())];
+
+        service.sync("acme", ItemKind::McpTool, &listed, false).await;
+        assert_eq!(service.items("acme", ItemKind::McpTool).await.len(), 2);
+        service.sync("acme", ItemKind::McpTool, &listed, true).await;
+        assert_eq!(service.items("acme", ItemKind::McpTool).await, listed);
+        assert_eq!(service.items("acme", ItemKind::Agent).await.len(), 4);
+    }
+
+    #[test]
+    fn test_hashing_embedder_is_deterministic() {
+        let embedder = HashingEmbedder::new(64);
+        let a = embedder.embed("Reviews pull requests");
+        assert_eq!(a, embedder.embed("Reviews pull requests"));
+        assert_eq!(a.len(), 64);
+        assert!((cosine(&a, &a) - 1.0).abs() < 1e-5);
+
+        // Buckets come from a fixed hash, not one seeded per process
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
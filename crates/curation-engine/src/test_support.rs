@@ -0,0 +1,52 @@
+//! Helpers shared by the unit tests
+
+use axum::Router;
+
+use crate::{config::AuthConfig, EngineBuilder};
+
+/// Secret the engines served by `serve_engine` verify tokens with
+pub(crate) const JWT_SECRET: &str = "test-secret";
+
+/// Serve `app` on an ephemeral local port, returning its `http://` base URL
+pub(crate) async fn serve(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    base
+}
+
+/// Serve the full router of an engine built by `builder`, with auth on
+pub(crate) async fn serve_engine(builder: EngineBuilder) -> String {
+    let engine = builder
+        .with_auth(AuthConfig {
+            enabled: true,
+            jwt_secret: JWT_SECRET.to_string(),
+            issuer: None,
+        })
+        .build()
+        .await
+        .unwrap();
+    serve(engine.create_router().await.unwrap()).await
+}
+
+/// A token for `alice` of the default tenant, valid for an hour and signed with `JWT_SECRET`
+pub(crate) fn bearer(scope: &str, roles: &[&str]) -> String {
+    bearer_for(None, scope, roles)
+}
+
+/// Like `bearer`, acting for `tenant` when given
+pub(crate) fn bearer_for(tenant: Option<&str>, scope: &str, roles: &[&str]) -> String {
+    let claims = serde_json::json!({
+        "sub": "alice",
+        "scope": scope,
+        "roles": roles,
+        "tenant": tenant,
+        "exp": chrono::Utc::now().timestamp() + 3600,
+    });
+    jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &claims,
+        &jsonwebtoken::EncodingKey::from_secret(JWT_SECRET.as_bytes()),
+    )
+    .unwrap()
+}
@@ -0,0 +1,294 @@
+//! WASM module runtime
+//!
+//! Modules follow the same calling convention as Forge modules:
+//!
+//! - export `memory`
+//! - export `alloc(len: i32) -> i32`, returning a buffer for the JSON input
+//! - export `run(ptr: i32, len: i32) -> i32`, returning a pointer to the
+//!   output: a little-endian `u32` length followed by that many bytes of JSON
+//! - optionally import `env.yield()`, a yield point at which a batch
+//!   execution pauses while interactive work takes its worker
+//!
+//! Each execution gets a fresh `Store` limited by the `WasmConfig` in effect
+//! when it starts: fuel, a time limit checked on every epoch tick and a memory
+//! cap. Executions hand their thread back to the async runtime on each tick,
+//! and time spent paused at a yield point does not count against the limit.
+
+use std::time::{Duration, Instant};
+
+use wasmtime::{Caller, Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap, UpdateDeadline};
+
+use crate::{config::WasmConfig, scheduling::Checkpoint};
+
+/// How often the engine epoch advances; time limits are rounded up to it
+pub const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Exports every module must have
+const REQUIRED_EXPORTS: [&str; 3] = ["memory", "alloc", "run"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum WasmError {
+    #[error("Module failed to compile: {0}")]
+    Compile(String),
+    #[error("Module does not export `{0}`")]
+    MissingExport(&'static str),
+    #[error("Module ran out of fuel")]
+    OutOfFuel,
+    #[error("Module exceeded its time limit")]
+    Timeout,
+    #[error("Module trapped: {0}")]
+    Trap(String),
+    #[error("Module output is not JSON: {0}")]
+    InvalidOutput(String),
+}
+
+/// What an execution produced
+#[derive(Debug, Clone, PartialEq)]
+pub struct WasmOutput {
+    pub output: serde_json::Value,
+    pub fuel_consumed: u64,
+}
+
+struct HostState {
+    limits: StoreLimits,
+    /// Scheduler handle the `yield` import pauses on
+    checkpoint: Option<Checkpoint>,
+    /// When the time limit runs out, moved back by time spent paused
+    deadline: Instant,
+}
+
+/// `env.yield()`: pause at the checkpoint, then extend the deadline by the pause
+fn link_yield(linker: &mut Linker<HostState>) -> wasmtime::Result<()> {
+    linker.func_wrap_async("env", "yield", |mut caller: Caller<'_, HostState>, (): ()| {
+        Box::new(async move {
+            let Some(checkpoint) = caller.data_mut().checkpoint.take() else { return Ok(()) };
+            let paused = Instant::now();
+            checkpoint.yield_point().await;
+            let state = caller.data_mut();
+            state.checkpoint = Some(checkpoint);
+            state.deadline += paused.elapsed();
+            Ok(())
+        })
+    })?;
+    Ok(())
+}
+
+/// Compiles modules and runs them asynchronously under resource limits
+pub struct WasmRuntime {
+    engine: Engine,
+}
+
+impl WasmRuntime {
+    pub fn new() -> Self {
+        let mut config = Config::new();
+        config.async_support(true).consume_fuel(true).epoch_interruption(true);
+        let engine = Engine::new(&config).expect("async, fuel and epoch interruption are supported");
+
+        // Advance the epoch until the engine is dropped
+        let weak = engine.weak();
+        std::thread::spawn(move || {
+            while let Some(engine) = weak.upgrade() {
+                engine.increment_epoch();
+                drop(engine);
+                std::thread::sleep(EPOCH_TICK);
+            }
+        });
+
+        Self { engine }
+    }
+
+    /// Compile `wasm` (binary or text) and check it has the required exports
+    pub fn compile(&self, wasm: &[u8]) -> Result<Module, WasmError> {
+        let module = Module::new(&self.engine, wasm).map_err(|e| WasmError::Compile(e.to_string()))?;
+        for export in REQUIRED_EXPORTS {
+            if module.get_export(export).is_none() {
+                return Err(WasmError::MissingExport(export));
+            }
+        }
+        Ok(module)
+    }
+
+    /// Run `module` on `input` in a fresh instance, yielding through
+    /// `checkpoint` when the module calls `env.yield()`
+    pub async fn execute(
+        &self,
+        module: &Module,
+        input: &serde_json::Value,
+        limits: &WasmConfig,
+        checkpoint: Option<Checkpoint>,
+    ) -> Result<WasmOutput, WasmError> {
+        let memory_bytes = limits.max_memory_mb as usize * 1024 * 1024;
+        let mut store = Store::new(
+            &self.engine,
+            HostState {
+                limits: StoreLimitsBuilder::new().memory_size(memory_bytes).build(),
+                checkpoint,
+                deadline: Instant::now() + Duration::from_millis(limits.max_execution_time_ms),
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(limits.max_fuel).expect("fuel is enabled");
+        store.set_epoch_deadline(1);
+        store.epoch_deadline_callback(|store| {
+            if Instant::now() >= store.data().deadline {
+                return Err(Trap::Interrupt.into());
+            }
+            Ok(UpdateDeadline::Yield(1))
+        });
+
+        let mut linker = Linker::new(&self.engine);
+        link_yield(&mut linker).expect("`env.yield` is linked once");
+        let result = call_run(&mut store, &linker, module, input).await;
+        let fuel_consumed = limits.max_fuel.saturating_sub(store.get_fuel().unwrap_or(0));
+        result.map(|output| WasmOutput { output, fuel_consumed }).map_err(classify_error)
+    }
+}
+
+impl Default for WasmRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pass the JSON input through `alloc` and `run` and decode the output
+async fn call_run(
+    store: &mut Store<HostState>,
+    linker: &Linker<HostState>,
+    module: &Module,
+    input: &serde_json::Value,
+) -> wasmtime::Result<serde_json::Value> {
+    let instance = linker.instantiate_async(&mut *store, module).await?;
+    let memory = instance
+        .get_memory(&mut *store, "memory")
+        .ok_or_else(|| wasmtime::Error::msg("module does not export memory"))?;
+    let alloc = instance.get_typed_func::<i32, i32>(&mut *store, "alloc")?;
+    let run = instance.get_typed_func::<(i32, i32), i32>(&mut *store, "run")?;
+
+    let input = serde_json::to_vec(input)?;
+    let len = i32::try_from(input.len())?;
+    let ptr = alloc.call_async(&mut *store, len).await?;
+    memory.write(&mut *store, ptr as u32 as usize, &input)?;
+
+    let output_ptr = run.call_async(&mut *store, (ptr, len)).await? as u32 as usize;
+    let mut len_bytes = [0u8; 4];
+    memory.read(&*store, output_ptr, &mut len_bytes)?;
+    let mut output = vec![0u8; u32::from_le_bytes(len_bytes) as usize];
+    memory.read(&*store, output_ptr + 4, &mut output)?;
+
+    serde_json::from_slice(&output).map_err(|e| OutputError(e.to_string()).into())
+}
+
+/// Output that is not JSON, told apart from traps
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+struct OutputError(String);
+
+fn classify_error(error: wasmtime::Error) -> WasmError {
+    if let Some(OutputError(message)) = error.downcast_ref::<OutputError>() {
+        return WasmError::InvalidOutput(message.clone());
+    }
+    match error.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => WasmError::OutOfFuel,
+        Some(Trap::Interrupt) => WasmError::Timeout,
+        _ => WasmError::Trap(error.to_string()),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::scheduling::{ExecutionClass, ExecutionScheduler, SchedulingConfig};
+    use std::sync::Arc;
+
+    /// Returns its input: `run` writes the length prefix just before the input buffer
+    pub(crate) const ECHO_WAT: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+          (func (export "run") (param $ptr i32) (param $len i32) (result i32)
+            (i32.store (i32.sub (local.get $ptr) (i32.const 4)) (local.get $len))
+            (i32.sub (local.get $ptr) (i32.const 4))))
+    "#;
+
+    /// Never returns from `run`
+    const SPIN_WAT: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+          (func (export "run") (param i32 i32) (result i32)
+            (loop $spin (br $spin))
+            (unreachable)))
+    "#;
+
+    /// Echoes its input after reaching a yield point a million times
+    const YIELDING_WAT: &str = r#"
+        (module
+          (import "env" "yield" (func $yield))
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+          (func (export "run") (param $ptr i32) (param $len i32) (result i32)
+            (local $i i32)
+            (loop $again
+              (call $yield)
+              (local.set $i (i32.add (local.get $i) (i32.const 1)))
+              (br_if $again (i32.lt_u (local.get $i) (i32.const 1000000))))
+            (i32.store (i32.sub (local.get $ptr) (i32.const 4)) (local.get $len))
+            (i32.sub (local.get $ptr) (i32.const 4))))
+    "#;
+
+    #[tokio::test]
+    async fn test_execute_echo_and_limits() {
+        let runtime = WasmRuntime::new();
+        let limits = WasmConfig::default();
+
+        let echo = runtime.compile(ECHO_WAT.as_bytes()).unwrap();
+        let input = serde_json::json!({"text": "hello", "n": [1, 2, 3]});
+        let result = runtime.execute(&echo, &input, &limits, None).await.unwrap();
+        assert_eq!(result.output, input);
+        assert!(result.fuel_consumed > 0);
+
+        let spin = runtime.compile(SPIN_WAT.as_bytes()).unwrap();
+        let low_fuel = WasmConfig { max_fuel: 100_000, ..limits.clone() };
+        assert!(matches!(runtime.execute(&spin, &input, &low_fuel, None).await, Err(WasmError::OutOfFuel)));
+        let short = WasmConfig { max_execution_time_ms: 50, ..limits };
+        assert!(matches!(runtime.execute(&spin, &input, &short, None).await, Err(WasmError::Timeout)));
+
+        let missing = runtime.compile(br#"(module (memory (export "memory") 1))"#);
+        assert!(matches!(missing, Err(WasmError::MissingExport("alloc"))));
+    }
+
+    #[tokio::test]
+    async fn test_yield_import_pauses_batch_for_interactive_work() {
+        let runtime = Arc::new(WasmRuntime::new());
+        let scheduler = ExecutionScheduler::new(SchedulingConfig { workers: 1, ..Default::default() });
+        let yielding = runtime.compile(YIELDING_WAT.as_bytes()).unwrap();
+        let echo = runtime.compile(ECHO_WAT.as_bytes()).unwrap();
+
+        let (started, batch_started) = tokio::sync::oneshot::channel();
+        let batch_runtime = runtime.clone();
+        let batch = tokio::spawn(scheduler.submit(ExecutionClass::Batch, move |checkpoint| async move {
+            let _ = started.send(());
+            let output = batch_runtime.execute(&yielding, &serde_json::json!("batch"), &WasmConfig::default(), Some(checkpoint)).await;
+            (output, Instant::now())
+        }));
+        batch_started.await.unwrap();
+
+        // The only worker is busy, so this runs once the batch execution yields
+        let (interactive, interactive_done) = scheduler
+            .submit(ExecutionClass::Interactive, move |_| async move {
+                let output = runtime.execute(&echo, &serde_json::json!("interactive"), &WasmConfig::default(), None).await;
+                (output, Instant::now())
+            })
+            .await
+            .unwrap();
+        let (batch, batch_done) = batch.await.unwrap().unwrap();
+        assert_eq!(interactive.unwrap().output, "interactive");
+        assert_eq!(batch.unwrap().output, "batch");
+        assert!(interactive_done < batch_done, "batch finished {:?} before interactive work", interactive_done - batch_done);
+    }
+}
@@ -0,0 +1,667 @@
+//! Webhook subscriptions for external integrations
+//!
+//! Tenants subscribe URLs to curation events instead of polling. Each event is
+//! delivered on a spawned task as a JSON payload signed with the
+//! subscription's secret (HMAC-SHA256 over `<timestamp>.<body>`) and retried
+//! with exponential backoff. Every attempt is kept in a per-subscription
+//! delivery log. Payloads carry the tenant's branding so receivers can
+//! present events under the tenant's own name.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+
+use crate::{
+    authz::Tenant,
+    correlation::{self, WithCorrelationId},
+    error::ApiError,
+    EngineState,
+};
+
+/// Header carrying `sha256=<hex HMAC>` of `<timestamp>.<body>`
+pub const SIGNATURE_HEADER: &str = "X-AutoAgents-Signature";
+
+/// Header carrying the Unix timestamp the signature covers
+pub const TIMESTAMP_HEADER: &str = "X-AutoAgents-Timestamp";
+
+/// Delivery attempts kept per subscription
+const MAX_DELIVERY_LOG: usize = 100;
+
+/// Curation events a subscription can receive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WebhookEventType {
+    #[serde(rename = "job.completed")]
+    JobCompleted,
+    #[serde(rename = "job.failed")]
+    JobFailed,
+    #[serde(rename = "agent.updated")]
+    AgentUpdated,
+    #[serde(rename = "module.uploaded")]
+    ModuleUploaded,
+    /// An agent's behavior drifted between execution windows
+    #[serde(rename = "agent.drifted")]
+    AgentDrifted,
+}
+
+impl WebhookEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::JobCompleted => "job.completed",
+            Self::JobFailed => "job.failed",
+            Self::AgentUpdated => "agent.updated",
+            Self::ModuleUploaded => "module.uploaded",
+            Self::AgentDrifted => "agent.drifted",
+        }
+    }
+}
+
+/// Webhook delivery configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub max_subscriptions_per_tenant: usize,
+    pub max_attempts: u32,
+    /// Backoff before the second attempt; doubles on each further attempt
+    pub initial_backoff_ms: u64,
+    pub timeout_ms: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            max_subscriptions_per_tenant: 10,
+            max_attempts: 5,
+            initial_backoff_ms: 1000,
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+/// How a tenant is presented in the payloads it receives
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TenantBranding {
+    pub display_name: Option<String>,
+    pub logo_url: Option<String>,
+    pub primary_color: Option<String>,
+}
+
+/// Request body creating a subscription
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewSubscription {
+    pub url: String,
+    /// Shared secret deliveries are signed with
+    pub secret: String,
+    pub event_types: Vec<WebhookEventType>,
+}
+
+/// A tenant's subscription to curation events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookSubscription {
+    pub id: String,
+    pub tenant: String,
+    pub url: String,
+    #[serde(skip_serializing, default)]
+    pub secret: String,
+    pub event_types: Vec<WebhookEventType>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Payload posted to subscribers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEvent {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub event_type: WebhookEventType,
+    pub created_at: DateTime<Utc>,
+    pub tenant: TenantInfo,
+    pub data: serde_json::Value,
+    /// Set on payloads sent through the test-delivery endpoint
+    #[serde(default)]
+    pub test: bool,
+}
+
+/// Tenant section of a payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantInfo {
+    pub id: String,
+    pub branding: TenantBranding,
+}
+
+/// One attempt to deliver an event to a subscription
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryAttempt {
+    pub event_id: String,
+    pub event_type: WebhookEventType,
+    pub attempt: u32,
+    pub attempted_at: DateTime<Utc>,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub success: bool,
+    pub duration_ms: u64,
+}
+
+/// Webhook errors
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    #[error("Tenant already has the maximum of {0} subscriptions")]
+    LimitReached(usize),
+    #[error("Invalid webhook URL '{0}'")]
+    InvalidUrl(String),
+    #[error("Subscription must name at least one event type")]
+    NoEventTypes,
+    #[error("Webhook secret must not be empty")]
+    EmptySecret,
+    #[error("Subscription '{0}' not found")]
+    NotFound(String),
+}
+
+/// Tenant-scoped webhook subscriptions and their deliveries
+#[derive(Clone)]
+pub struct WebhookService {
+    config: Arc<WebhookConfig>,
+    http_client: reqwest::Client,
+    subscriptions: Arc<RwLock<HashMap<String, WebhookSubscription>>>,
+    deliveries: Arc<RwLock<HashMap<String, VecDeque<DeliveryAttempt>>>>,
+    branding: Arc<RwLock<HashMap<String, TenantBranding>>>,
+}
+
+impl WebhookService {
+    pub fn new(config: WebhookConfig) -> Self {
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .unwrap_or_default();
+        Self {
+            config: Arc::new(config),
+            http_client,
+            subscriptions: Arc::new(RwLock::new(HashMap::new())),
+            deliveries: Arc::new(RwLock::new(HashMap::new())),
+            branding: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Subscribe a URL to events of a tenant
+    pub async fn subscribe(&self, tenant: &str, request: NewSubscription) -> Result<WebhookSubscription, WebhookError> {
+        let url = reqwest::Url::parse(&request.url).map_err(|_| WebhookError::InvalidUrl(request.url.clone()))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(WebhookError::InvalidUrl(request.url));
+        }
+        if request.event_types.is_empty() {
+            return Err(WebhookError::NoEventTypes);
+        }
+        if request.secret.is_empty() {
+            return Err(WebhookError::EmptySecret);
+        }
+
+        let mut subscriptions = self.subscriptions.write().await;
+        let existing = subscriptions.values().filter(|sub| sub.tenant == tenant).count();
+        if existing >= self.config.max_subscriptions_per_tenant {
+            return Err(WebhookError::LimitReached(self.config.max_subscriptions_per_tenant));
+        }
+
+        let subscription = WebhookSubscription {
+            id: uuid::Uuid::new_v4().to_string(),
+            tenant: tenant.to_string(),
+            url: request.url,
+            secret: request.secret,
+            event_types: unique_event_types(request.event_types),
+            created_at: Utc::now(),
+        };
+        subscriptions.insert(subscription.id.clone(), subscription.clone());
+        info!("🪝 Tenant {} subscribed {} to {:?}", tenant, subscription.url, subscription.event_types);
+        Ok(subscription)
+    }
+
+    /// Change the event types of a subscription, and its secret when one is given
+    pub async fn update(
+        &self,
+        tenant: &str,
+        id: &str,
+        event_types: Vec<WebhookEventType>,
+        secret: Option<String>,
+    ) -> Result<WebhookSubscription, WebhookError> {
+        if event_types.is_empty() {
+            return Err(WebhookError::NoEventTypes);
+        }
+        if secret.as_deref().is_some_and(str::is_empty) {
+            return Err(WebhookError::EmptySecret);
+        }
+
+        let mut subscriptions = self.subscriptions.write().await;
+        let subscription = subscriptions
+            .get_mut(id)
+            .filter(|sub| sub.tenant == tenant)
+            .ok_or_else(|| WebhookError::NotFound(id.to_string()))?;
+        subscription.event_types = unique_event_types(event_types);
+        if let Some(secret) = secret {
+            subscription.secret = secret;
+        }
+        info!("🪝 Tenant {} updated {} to {:?}", tenant, subscription.url, subscription.event_types);
+        Ok(subscription.clone())
+    }
+
+    /// Remove a subscription and its delivery log
+    pub async fn unsubscribe(&self, tenant: &str, id: &str) -> Result<(), WebhookError> {
+        let mut subscriptions = self.subscriptions.write().await;
+        if subscriptions.get(id).is_none_or(|sub| sub.tenant != tenant) {
+            return Err(WebhookError::NotFound(id.to_string()));
+        }
+        subscriptions.remove(id);
+        self.deliveries.write().await.remove(id);
+        Ok(())
+    }
+
+    pub async fn get(&self, tenant: &str, id: &str) -> Option<WebhookSubscription> {
+        self.subscriptions.read().await.get(id).filter(|sub| sub.tenant == tenant).cloned()
+    }
+
+    pub async fn list(&self, tenant: &str) -> Vec<WebhookSubscription> {
+        let mut subscriptions: Vec<WebhookSubscription> = self.subscriptions.read().await
+            .values()
+            .filter(|sub| sub.tenant == tenant)
+            .cloned()
+            .collect();
+        subscriptions.sort_by_key(|sub| sub.created_at);
+        subscriptions
+    }
+
+    /// Delivery attempts of a subscription, oldest first
+    pub async fn deliveries(&self, tenant: &str, id: &str) -> Result<Vec<DeliveryAttempt>, WebhookError> {
+        self.get(tenant, id).await.ok_or_else(|| WebhookError::NotFound(id.to_string()))?;
+        Ok(self.deliveries.read().await.get(id).map(|log| log.iter().cloned().collect()).unwrap_or_default())
+    }
+
+    pub async fn set_branding(&self, tenant: &str, branding: TenantBranding) {
+        self.branding.write().await.insert(tenant.to_string(), branding);
+    }
+
+    pub async fn branding(&self, tenant: &str) -> TenantBranding {
+        self.branding.read().await.get(tenant).cloned().unwrap_or_default()
+    }
+
+    /// Deliver an event to every subscription of the tenant that wants it,
+    /// without waiting for the deliveries
+    pub async fn publish(&self, tenant: &str, event_type: WebhookEventType, data: serde_json::Value) {
+        let subscribers: Vec<WebhookSubscription> = self.subscriptions.read().await
+            .values()
+            .filter(|sub| sub.tenant == tenant && sub.event_types.contains(&event_type))
+            .cloned()
+            .collect();
+        if subscribers.is_empty() {
+            return;
+        }
+
+        let event = self.event(tenant, event_type, data, false).await;
+        for subscription in subscribers {
+            let service = self.clone();
+            let event = event.clone();
+            tokio::spawn(correlation::inherit(async move { service.deliver(&subscription, &event).await }));
+        }
+    }
+
+    /// Send a sample payload to a subscription once, returning the attempt
+    pub async fn send_test(&self, tenant: &str, id: &str) -> Result<DeliveryAttempt, WebhookError> {
+        let subscription = self.get(tenant, id).await.ok_or_else(|| WebhookError::NotFound(id.to_string()))?;
+        let event_type = subscription.event_types[0];
+        let sample = serde_json::json!({"message": format!("Sample {} event", event_type.as_str())});
+        let event = self.event(tenant, event_type, sample, true).await;
+        Ok(self.attempt(&subscription, &event, 1).await)
+    }
+
+    async fn event(&self, tenant: &str, event_type: WebhookEventType, data: serde_json::Value, test: bool) -> WebhookEvent {
+        WebhookEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            event_type,
+            created_at: Utc::now(),
+            tenant: TenantInfo {
+                id: tenant.to_string(),
+                branding: self.branding(tenant).await,
+            },
+            data,
+            test,
+        }
+    }
+
+    /// Deliver an event, retrying with backoff until it succeeds or attempts run out
+    async fn deliver(&self, subscription: &WebhookSubscription, event: &WebhookEvent) -> bool {
+        let max_attempts = self.config.max_attempts.max(1);
+        for attempt in 1..=max_attempts {
+            let outcome = self.attempt(subscription, event, attempt).await;
+            if outcome.success {
+                return true;
+            }
+
+            let failure = outcome.error.unwrap_or_default();
+            if attempt == max_attempts {
+                error!(
+                    "🪝 Gave up delivering {} {} to {} after {} attempt(s): {}",
+                    event.event_type.as_str(), event.id, subscription.url, attempt, failure
+                );
+                return false;
+            }
+
+            let backoff = self.config.initial_backoff_ms.saturating_mul(1 << (attempt - 1).min(16));
+            warn!(
+                "🪝 Delivery of {} {} to {} failed ({}), retrying in {}ms (attempt {}/{})",
+                event.event_type.as_str(), event.id, subscription.url, failure, backoff, attempt + 1, max_attempts
+            );
+            tokio::time::sleep(Duration::from_millis(backoff)).await;
+        }
+        false
+    }
+
+    /// POST a signed event once and log the attempt
+    async fn attempt(&self, subscription: &WebhookSubscription, event: &WebhookEvent, attempt: u32) -> DeliveryAttempt {
+        let body = serde_json::to_vec(event).unwrap_or_default();
+        let timestamp = Utc::now().timestamp().to_string();
+        let started = Instant::now();
+
+        let response = self
+            .http_client
+            .post(&subscription.url)
+            .header("Content-Type", "application/json")
+            .header("X-AutoAgents-Event", event.event_type.as_str())
+            .header("X-AutoAgents-Delivery", &event.id)
+            .header(TIMESTAMP_HEADER, &timestamp)
+            .header(SIGNATURE_HEADER, sign(&subscription.secret, &timestamp, &body))
+            .with_correlation_id()
+            .body(body)
+            .send()
+            .await;
+
+        let (status_code, error) = match response {
+            Ok(response) if response.status().is_success() => (Some(response.status().as_u16()), None),
+            Ok(response) => (Some(response.status().as_u16()), Some(format!("HTTP {}", response.status()))),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let outcome = DeliveryAttempt {
+            event_id: event.id.clone(),
+            event_type: event.event_type,
+            attempt,
+            attempted_at: Utc::now(),
+            status_code,
+            success: error.is_none(),
+            error,
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+
+        let mut deliveries = self.deliveries.write().await;
+        let log = deliveries.entry(subscription.id.clone()).or_default();
+        log.push_back(outcome.clone());
+        while log.len() > MAX_DELIVERY_LOG {
+            log.pop_front();
+        }
+        outcome
+    }
+}
+
+impl Default for WebhookService {
+    fn default() -> Self {
+        Self::new(WebhookConfig::default())
+    }
+}
+
+/// `sha256=<hex>` HMAC of `<timestamp>.<body>` under the subscription secret
+pub fn sign(secret: &str, timestamp: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Event types in first-seen order, without repeats
+fn unique_event_types(requested: Vec<WebhookEventType>) -> Vec<WebhookEventType> {
+    let mut event_types = Vec::new();
+    for event_type in requested {
+        if !event_types.contains(&event_type) {
+            event_types.push(event_type);
+        }
+    }
+    event_types
+}
+
+impl From<WebhookError> for ApiError {
+    fn from(err: WebhookError) -> Self {
+        let detail = err.to_string();
+        match err {
+            WebhookError::LimitReached(_) => ApiError::Conflict(detail),
+            WebhookError::InvalidUrl(_) | WebhookError::NoEventTypes | WebhookError::EmptySecret => ApiError::BadRequest(detail),
+            WebhookError::NotFound(_) => ApiError::NotFound(detail),
+        }
+    }
+}
+
+/// `POST /api/v1/webhooks`
+pub async fn create_subscription(
+    State(state): State<EngineState>,
+    Tenant(tenant): Tenant,
+    Json(request): Json<NewSubscription>,
+) -> Result<(StatusCode, Json<WebhookSubscription>), ApiError> {
+    let subscription = state.webhook_service.subscribe(&tenant, request).await?;
+    Ok((StatusCode::CREATED, Json(subscription)))
+}
+
+/// `GET /api/v1/webhooks`
+pub async fn list_subscriptions(
+    State(state): State<EngineState>,
+    Tenant(tenant): Tenant,
+) -> Json<Vec<WebhookSubscription>> {
+    Json(state.webhook_service.list(&tenant).await)
+}
+
+/// `GET /api/v1/webhooks/:id`
+pub async fn get_subscription(
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+    Tenant(tenant): Tenant,
+) -> Result<Json<WebhookSubscription>, ApiError> {
+    state.webhook_service
+        .get(&tenant, &id)
+        .await
+        .map(Json)
+        .ok_or_else(|| WebhookError::NotFound(id).into())
+}
+
+/// `DELETE /api/v1/webhooks/:id`
+pub async fn delete_subscription(
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+    Tenant(tenant): Tenant,
+) -> Result<StatusCode, ApiError> {
+    state.webhook_service.unsubscribe(&tenant, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// `GET /api/v1/webhooks/:id/deliveries`
+pub async fn list_deliveries(
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+    Tenant(tenant): Tenant,
+) -> Result<Json<Vec<DeliveryAttempt>>, ApiError> {
+    Ok(Json(state.webhook_service.deliveries(&tenant, &id).await?))
+}
+
+/// `POST /api/v1/webhooks/:id/test`
+pub async fn test_delivery(
+    State(state): State<EngineState>,
+    Path(id): Path<String>,
+    Tenant(tenant): Tenant,
+) -> Result<Json<DeliveryAttempt>, ApiError> {
+    Ok(Json(state.webhook_service.send_test(&tenant, &id).await?))
+}
+
+/// `GET /api/v1/tenant/branding`
+pub async fn get_branding(State(state): State<EngineState>, Tenant(tenant): Tenant) -> Json<TenantBranding> {
+    Json(state.webhook_service.branding(&tenant).await)
+}
+
+/// `PUT /api/v1/tenant/branding`
+pub async fn update_branding(
+    State(state): State<EngineState>,
+    Tenant(tenant): Tenant,
+    Json(branding): Json<TenantBranding>,
+) -> Json<TenantBranding> {
+    state.webhook_service.set_branding(&tenant, branding).await;
+    Json(state.webhook_service.branding(&tenant).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::serve;
+    use crate::export::CompletedJob;
+    use axum::{body::Bytes, http::HeaderMap, routing::post, Router};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    type Received = (HeaderMap, Bytes);
+
+    /// Webhook receiver answering 500 to the first `fail_first` requests
+    #[derive(Clone, Default)]
+    struct Receiver {
+        received: Arc<RwLock<Vec<Received>>>,
+        requests: Arc<AtomicU32>,
+        fail_first: u32,
+    }
+
+    async fn receive(State(receiver): State<Receiver>, headers: HeaderMap, body: Bytes) -> StatusCode {
+        if receiver.requests.fetch_add(1, Ordering::SeqCst) < receiver.fail_first {
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+        receiver.received.write().await.push((headers, body));
+        StatusCode::OK
+    }
+
+    async fn start_receiver(fail_first: u32) -> (Receiver, String) {
+        let receiver = Receiver {
+            fail_first,
+            ..Default::default()
+        };
+        let app = Router::new().route("/hook", post(receive)).with_state(receiver.clone());
+        let url = format!("{}/hook", serve(app).await);
+        (receiver, url)
+    }
+
+    fn service() -> WebhookService {
+        WebhookService::new(WebhookConfig {
+            max_subscriptions_per_tenant: 2,
+            max_attempts: 3,
+            initial_backoff_ms: 10,
+            timeout_ms: 1000,
+        })
+    }
+
+    fn subscription(url: &str, event_types: Vec<WebhookEventType>) -> NewSubscription {
+        NewSubscription {
+            url: url.to_string(),
+            secret: "whsec_test".to_string(),
+            event_types,
+        }
+    }
+
+    /// Stand-in for the job runner finishing a job
+    async fn run_job(service: &WebhookService) -> CompletedJob {
+        let job = CompletedJob {
+            job_id: "job-1".to_string(),
+            tenant: "acme".to_string(),
+            completed_at: Utc::now(),
+            result: serde_json::json!({"answer": 42}),
+            metadata: serde_json::Value::Null,
+        };
+        service.publish(&job.tenant, WebhookEventType::JobCompleted, serde_json::to_value(&job).unwrap()).await;
+        job
+    }
+
+    async fn wait_for_deliveries(service: &WebhookService, tenant: &str, id: &str, count: usize) -> Vec<DeliveryAttempt> {
+        for _ in 0..200 {
+            let deliveries = service.deliveries(tenant, id).await.unwrap();
+            if deliveries.len() >= count {
+                return deliveries;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("expected {} deliveries", count);
+    }
+
+    #[tokio::test]
+    async fn test_job_completed_delivered_signed() {
+        let (receiver, url) = start_receiver(0).await;
+        let service = service();
+        service.set_branding("acme", TenantBranding {
+            display_name: Some("Acme Corp".to_string()),
+            ..Default::default()
+        }).await;
+        let sub = service.subscribe("acme", subscription(&url, vec![WebhookEventType::JobCompleted])).await.unwrap();
+
+        run_job(&service).await;
+        let deliveries = wait_for_deliveries(&service, "acme", &sub.id, 1).await;
+        assert!(deliveries[0].success);
+
+        let received = receiver.received.read().await;
+        let (headers, body) = &received[0];
+        let timestamp = headers[TIMESTAMP_HEADER].to_str().unwrap();
+        assert_eq!(headers[SIGNATURE_HEADER].to_str().unwrap(), sign("whsec_test", timestamp, body));
+        assert_eq!(headers["X-AutoAgents-Event"], "job.completed");
+
+        let event: WebhookEvent = serde_json::from_slice(body).unwrap();
+        assert_eq!(event.event_type, WebhookEventType::JobCompleted);
+        assert_eq!(event.data["result"]["answer"], 42);
+        assert_eq!(event.tenant.branding.display_name.as_deref(), Some("Acme Corp"));
+    }
+
+    #[tokio::test]
+    async fn test_delivery_retried_after_server_error() {
+        let (receiver, url) = start_receiver(1).await;
+        let service = service();
+        let sub = service.subscribe("acme", subscription(&url, vec![WebhookEventType::JobCompleted])).await.unwrap();
+
+        run_job(&service).await;
+        let deliveries = wait_for_deliveries(&service, "acme", &sub.id, 2).await;
+        assert_eq!((deliveries[0].attempt, deliveries[0].status_code, deliveries[0].success), (1, Some(500), false));
+        assert_eq!((deliveries[1].attempt, deliveries[1].success), (2, true));
+        assert_eq!(deliveries[0].event_id, deliveries[1].event_id);
+        assert_eq!(receiver.received.read().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_tenant_isolated_and_capped() {
+        let (receiver, url) = start_receiver(0).await;
+        let service = service();
+        let acme = service.subscribe("acme", subscription(&url, vec![WebhookEventType::AgentUpdated])).await.unwrap();
+        service.subscribe("acme", subscription(&url, vec![WebhookEventType::JobFailed])).await.unwrap();
+        assert!(matches!(
+            service.subscribe("acme", subscription(&url, vec![WebhookEventType::JobFailed])).await,
+            Err(WebhookError::LimitReached(2))
+        ));
+
+        // Other tenants neither see acme's subscriptions nor trigger them
+        let other = service.subscribe("other", subscription(&url, vec![WebhookEventType::JobCompleted])).await.unwrap();
+        assert!(service.get("other", &acme.id).await.is_none());
+        assert!(service.deliveries("other", &acme.id).await.is_err());
+        assert!(service.unsubscribe("other", &acme.id).await.is_err());
+        assert_eq!(service.list("other").await.len(), 1);
+
+        run_job(&service).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(receiver.received.read().await.is_empty());
+        assert!(service.deliveries("other", &other.id).await.unwrap().is_empty());
+
+        let attempt = service.send_test("acme", &acme.id).await.unwrap();
+        assert!(attempt.success);
+        let received = receiver.received.read().await;
+        let event: WebhookEvent = serde_json::from_slice(&received[0].1).unwrap();
+        assert!(event.test);
+        assert_eq!(event.event_type, WebhookEventType::AgentUpdated);
+    }
+}
@@ -5,19 +5,25 @@
 
 pub mod revenue;
 pub mod performance;
+pub mod rollup;
+
+pub use rollup::{Resolution, RetentionConfig, RollupRecord, TieredHistory, TimeRange};
 
 use crate::{InfrastructureMetrics, RevenueAnalytics, Error};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
 
+/// Developer time saved per orchestrated tool, in dollars
+const PRODUCTIVITY_SAVINGS_PER_TOOL: f64 = 50.0;
+
 /// Analytics tracker for revenue and performance metrics
 #[derive(Debug, Clone)]
 pub struct AnalyticsTracker {
     pub revenue_data: RevenueAnalytics,
-    pub performance_metrics: Vec<InfrastructureMetrics>,
     pub baseline_metrics: Option<BaselineMetrics>,
-    pub historical_data: Vec<ExecutionRecord>,
+    /// Execution history, downsampled as it ages
+    pub history: TieredHistory,
 }
 
 /// Baseline metrics for AWS/Google competitive benchmarking
@@ -41,6 +47,14 @@ pub struct ExecutionRecord {
     pub network_latency: f64,
     pub tools_orchestrated: usize,
     pub cost_savings: f64,
+    #[serde(default = "default_success")]
+    pub success: bool,
+    #[serde(default)]
+    pub container_efficiency: f32,
+}
+
+fn default_success() -> bool {
+    true
 }
 
 /// Revenue projection for enterprise customers
@@ -73,25 +87,43 @@ pub struct PerformanceDashboard {
     pub error_rate: f32,
 }
 
+/// One bucket of the revenue time series
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevenuePoint {
+    pub bucket_start: DateTime<Utc>,
+    pub resolution: Resolution,
+    pub executions: u64,
+    pub tools_orchestrated: u64,
+    pub cost_savings: f64,
+    pub productivity_gain: f64,
+}
+
 impl AnalyticsTracker {
     /// Create new analytics tracker with baseline setup
     pub fn new() -> Self {
         Self {
             revenue_data: RevenueAnalytics::default(),
-            performance_metrics: Vec::new(),
             baseline_metrics: Some(BaselineMetrics::default()),
-            historical_data: Vec::new(),
+            history: TieredHistory::default(),
         }
     }
 
+    /// Replace the retention windows; history recorded so far is discarded
+    pub fn with_retention(mut self, retention: RetentionConfig) -> Self {
+        self.history = TieredHistory::new(retention);
+        self
+    }
+
     /// Record execution metrics and update analytics
     pub fn record_execution(&mut self, metrics: InfrastructureMetrics, result: &super::ExecutionResult) {
-        // Update performance metrics
-        self.performance_metrics.push(metrics.clone());
+        self.record_execution_at(metrics, result, chrono::Utc::now());
+    }
 
+    /// Record execution metrics as of `timestamp`
+    pub fn record_execution_at(&mut self, metrics: InfrastructureMetrics, result: &super::ExecutionResult, timestamp: DateTime<Utc>) {
         // Calculate cost savings vs competitors
         let execution_record = ExecutionRecord {
-            timestamp: chrono::Utc::now(),
+            timestamp,
             session_id: result.session_id.to_string(),
             execution_time: metrics.session_duration,
             memory_used: metrics.memory_usage,
@@ -99,9 +131,11 @@ impl AnalyticsTracker {
             network_latency: metrics.network_latency,
             tools_orchestrated: result.tools_used.len(),
             cost_savings: self.calculate_cost_savings(&metrics),
+            success: result.success,
+            container_efficiency: metrics.container_efficiency,
         };
 
-        self.historical_data.push(execution_record);
+        self.history.push(execution_record);
 
         // Update revenue analytics
         self.update_revenue_analytics(&result);
@@ -130,7 +164,7 @@ impl AnalyticsTracker {
 
         // Assume 10x productivity gain leads to revenue
         // Each orchestration saves developer hours = dollars
-        let productivity_savings = result.tools_used.len() as f64 * 50.0; // $50/hour saved
+        let productivity_savings = result.tools_used.len() as f64 * PRODUCTIVITY_SAVINGS_PER_TOOL; // $50/hour saved
         self.revenue_data.productivity_gain += productivity_savings;
     }
 
@@ -145,27 +179,50 @@ impl AnalyticsTracker {
         }
     }
 
-    /// Generate performance dashboard
-    pub fn generate_performance_dashboard(&self) -> PerformanceDashboard {
-        if self.performance_metrics.is_empty() {
+    /// Generate performance dashboard for the executions within `range`
+    pub fn generate_performance_dashboard(&self, range: TimeRange) -> PerformanceDashboard {
+        let rollup = self.history.rollup(range);
+        if rollup.executions == 0 {
             return PerformanceDashboard::default();
         }
 
-        let session_durations: Vec<f64> = self.performance_metrics.iter().map(|m| m.session_duration).collect();
-        let memory_usages: Vec<usize> = self.performance_metrics.iter().map(|m| m.memory_usage).collect();
-        let latencies: Vec<f64> = self.performance_metrics.iter().map(|m| m.network_latency).collect();
+        // Exact percentile while the raw records are still around, sketched otherwise
+        let network_latency_p95 = match self.history.resolution_for(range) {
+            Resolution::Raw => {
+                let latencies: Vec<f64> = self.history.raw_in(range).map(|r| r.network_latency).collect();
+                calculate_p95(&latencies)
+            }
+            Resolution::Hourly | Resolution::Daily => rollup.network_latency_sketch.quantile(0.95),
+        };
 
         PerformanceDashboard {
-            average_session_duration: session_durations.iter().sum::<f64>() / session_durations.len() as f64,
-            peak_memory_usage: *memory_usages.iter().max().unwrap_or(&0),
-            container_efficiency: self.performance_metrics.iter().map(|m| m.container_efficiency).sum::<f32>() /
-                                self.performance_metrics.len() as f32,
-            network_latency_p95: calculate_p95(&latencies),
-            orchestrations_per_hour: self.revenue_data.tool_orchestrations as f64 / 24.0,
-            error_rate: 0.01, // Placeholder - calculate from actual errors
+            average_session_duration: rollup.average_execution_time(),
+            peak_memory_usage: rollup.peak_memory_used,
+            container_efficiency: rollup.average_container_efficiency() as f32,
+            network_latency_p95,
+            orchestrations_per_hour: rollup.tools_orchestrated as f64 / range.hours().max(1.0),
+            error_rate: rollup.failures as f32 / rollup.executions as f32,
         }
     }
 
+    /// Cost savings and productivity per bucket of `range`, hourly for recent
+    /// ranges and daily once the range reaches past the hourly retention
+    pub fn revenue_time_series(&self, range: TimeRange) -> Vec<RevenuePoint> {
+        let resolution = self.history.resolution_for(range).max(Resolution::Hourly);
+        self.history
+            .series(range)
+            .into_iter()
+            .map(|bucket| RevenuePoint {
+                bucket_start: bucket.bucket_start,
+                resolution,
+                executions: bucket.executions,
+                tools_orchestrated: bucket.tools_orchestrated,
+                cost_savings: bucket.cost_savings,
+                productivity_gain: bucket.tools_orchestrated as f64 * PRODUCTIVITY_SAVINGS_PER_TOOL,
+            })
+            .collect()
+    }
+
     /// Generate enterprise revenue projection
     pub fn generate_revenue_projection(&self) -> RevenueProjection {
         RevenueProjection {
@@ -176,16 +233,17 @@ impl AnalyticsTracker {
         }
     }
 
-    /// Calculate total cost disruption impact
-    pub fn calculate_disruption_impact(&self) -> HashMap<String, f64> {
+    /// Calculate cost disruption impact of the executions within `range`
+    pub fn calculate_disruption_impact(&self, range: TimeRange) -> HashMap<String, f64> {
         let mut impact = HashMap::new();
+        let rollup = self.history.rollup(range);
 
-        // Total AWS cost saved across all sessions
-        let total_aws_savings: f64 = self.historical_data.iter().map(|r| r.cost_savings).sum();
-        impact.insert("total_aws_cost_saved".to_string(), total_aws_savings);
+        // Total AWS cost saved across the sessions in range
+        impact.insert("total_aws_cost_saved".to_string(), rollup.cost_savings);
 
         // Productivity gains in developer hours
-        impact.insert("productivity_gain_multiplier".to_string(), self.revenue_data.productivity_gain / 1000.0);
+        let productivity_gain = rollup.tools_orchestrated as f64 * PRODUCTIVITY_SAVINGS_PER_TOOL;
+        impact.insert("productivity_gain_multiplier".to_string(), productivity_gain / 1000.0);
 
         // Market disruption potential
         let analysis = self.generate_competitive_analysis();
@@ -236,3 +294,63 @@ fn calculate_p95(values: &[f64]) -> f64 {
 
     *sorted.get(index).unwrap_or(&0.0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn test_dashboard_and_revenue_series_read_rollups() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let mut tracker = AnalyticsTracker::new().with_retention(RetentionConfig {
+            raw_window: Duration::days(2),
+            hourly_retention: Duration::days(7),
+            daily_retention: None,
+        });
+
+        // One execution per hour for 30 days, every tenth one failing
+        for hour in 0..30 * 24 {
+            let metrics = InfrastructureMetrics {
+                memory_usage: 128,
+                cpu_cycles: 0.5,
+                gpu_acceleration: 0.0,
+                network_latency: 10.0,
+                container_efficiency: 0.8,
+                session_duration: 2.0,
+            };
+            let result = crate::ExecutionResult {
+                session_id: uuid::Uuid::new_v4(),
+                success: hour % 10 != 0,
+                output: String::new(),
+                memory_used: 128,
+                cpu_used: 0.5,
+                network_latency: 10.0,
+                efficiency_score: 0.8,
+                tools_used: vec!["browser".to_string(), "github".to_string()],
+            };
+            tracker.record_execution_at(metrics, &result, start + Duration::hours(hour));
+        }
+        assert!(tracker.history.stored_records() < 2 * 24 + 5 * 24 + 30);
+
+        let month = TimeRange::new(start, start + Duration::days(30));
+        let dashboard = tracker.generate_performance_dashboard(month);
+        assert_eq!(dashboard.peak_memory_usage, 128);
+        assert!((dashboard.error_rate - 0.1).abs() < 1e-6);
+        assert!((dashboard.orchestrations_per_hour - 2.0).abs() < 1e-9);
+        assert!((dashboard.network_latency_p95 - 10.0).abs() <= 10.0 * rollup::SKETCH_RELATIVE_ACCURACY);
+
+        let series = tracker.revenue_time_series(month);
+        assert_eq!(series.len(), 30);
+        assert!(series.iter().all(|point| point.resolution == Resolution::Daily && point.executions == 24));
+        assert_eq!(series[0].productivity_gain, 24.0 * 2.0 * PRODUCTIVITY_SAVINGS_PER_TOOL);
+
+        let last_day = TimeRange::new(start + Duration::days(29), start + Duration::days(30));
+        assert_eq!(tracker.revenue_time_series(last_day).len(), 24);
+        assert_eq!(tracker.generate_performance_dashboard(last_day).network_latency_p95, 10.0);
+
+        let impact = tracker.calculate_disruption_impact(month);
+        let per_execution = tracker.history.raw_records().next().unwrap().cost_savings;
+        assert!((impact["total_aws_cost_saved"] - per_execution * 720.0).abs() < 1e-9);
+    }
+}
@@ -7,7 +7,9 @@ use crate::{
     InfrastructureAssassinEngine, UnifiedExecutionResult, Error,
     UnifiedSession, ResourceMonitor, SessionResourceUsage,
 };
+use crate::tools::concurrency::ServerConcurrency;
 use std::collections::{HashMap, BTreeMap};
+use std::sync::{Mutex, MutexGuard, OnceLock};
 use std::time::{Duration, Instant};
 
 /// Performance Profiler - identifying bottlenecks across Infrastructure Assassin
@@ -22,6 +24,8 @@ pub struct PerformanceProfiler {
     pub bottleneck_analysis: BottleneckAnalysis,
     /// Performance optimization recommendations
     pub optimization_recommendations: Vec<OptimizationRecommendation>,
+    /// Adaptive tool call limits per MCP server, as of the last orchestration
+    pub tool_concurrency: Vec<ServerConcurrency>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -118,22 +122,38 @@ impl PerformanceProfiler {
             network_latencies: Vec::new(),
             bottleneck_analysis: BottleneckAnalysis::new(),
             optimization_recommendations: Self::generate_baseline_recommendations(),
+            tool_concurrency: Vec::new(),
         })
     }
 
+    /// Record the current tool concurrency limits and their adjustment history
+    pub fn record_tool_concurrency(&mut self, status: Vec<ServerConcurrency>) {
+        self.tool_concurrency = status;
+    }
+
     /// Profile unified orchestration request execution
     pub async fn profile_request_execution(
         &mut self,
         engine: &InfrastructureAssassinEngine,
         request_description: &str
+    ) -> Result<ExecutionProfile, Error> {
+        let profile = Self::measure_request_execution(engine, request_description).await?;
+        self.record_profile(&profile);
+        Ok(profile)
+    }
+
+    /// Time each component phase of a request, without touching profiler state
+    pub async fn measure_request_execution(
+        engine: &InfrastructureAssassinEngine,
+        request_description: &str
     ) -> Result<ExecutionProfile, Error> {
         let start_time = Instant::now();
 
         // Profile request through each component phase
-        let session_creation_time = self.profile_session_creation(engine).await?;
-        let tool_allocation_time = self.profile_tool_allocation(engine).await?;
-        let execution_time = self.profile_core_execution(engine).await?;
-        let cleanup_time = self.profile_cleanup_phase(engine).await?;
+        let session_creation_time = Self::profile_session_creation(engine).await?;
+        let tool_allocation_time = Self::profile_tool_allocation(engine).await?;
+        let execution_time = Self::profile_core_execution(engine).await?;
+        let cleanup_time = Self::profile_cleanup_phase(engine).await?;
 
         let total_duration = start_time.elapsed();
 
@@ -148,9 +168,14 @@ impl PerformanceProfiler {
             ],
             peak_memory_usage: 256, // MB - placeholder
             network_requests_count: 1,
-            efficiency_score: self.calculate_efficiency_score(&total_duration),
+            efficiency_score: Self::calculate_efficiency_score(&total_duration),
         };
 
+        Ok(profile)
+    }
+
+    /// Add a measured profile's component timings and re-run bottleneck analysis
+    pub fn record_profile(&mut self, profile: &ExecutionProfile) {
         // Record component timings for analysis
         for (component, duration) in &profile.component_breakdown {
             self.record_component_timing(component, *duration);
@@ -158,35 +183,33 @@ impl PerformanceProfiler {
 
         // Perform real-time bottleneck analysis
         self.analyze_bottlenecks();
-
-        Ok(profile)
     }
 
-    async fn profile_session_creation(&self, _engine: &InfrastructureAssassinEngine) -> Result<Duration, Error> {
+    async fn profile_session_creation(_engine: &InfrastructureAssassinEngine) -> Result<Duration, Error> {
         let start = Instant::now();
         tokio::time::sleep(tokio::time::Duration::from_micros(150)).await; // Simulate session setup
         Ok(start.elapsed())
     }
 
-    async fn profile_tool_allocation(&self, _engine: &InfrastructureAssassinEngine) -> Result<Duration, Error> {
+    async fn profile_tool_allocation(_engine: &InfrastructureAssassinEngine) -> Result<Duration, Error> {
         let start = Instant::now();
         tokio::time::sleep(tokio::time::Duration::from_micros(200)).await; // Simulate tool allocation
         Ok(start.elapsed())
     }
 
-    async fn profile_core_execution(&self, _engine: &InfrastructureAssassinEngine) -> Result<Duration, Error> {
+    async fn profile_core_execution(_engine: &InfrastructureAssassinEngine) -> Result<Duration, Error> {
         let start = Instant::now();
         tokio::time::sleep(tokio::time::Duration::from_millis(50)).await; // Simulate core execution
         Ok(start.elapsed())
     }
 
-    async fn profile_cleanup_phase(&self, _engine: &InfrastructureAssassinEngine) -> Result<Duration, Error> {
+    async fn profile_cleanup_phase(_engine: &InfrastructureAssassinEngine) -> Result<Duration, Error> {
         let start = Instant::now();
         tokio::time::sleep(tokio::time::Duration::from_micros(50)).await; // Simulate cleanup
         Ok(start.elapsed())
     }
 
-    fn calculate_efficiency_score(&self, total_duration: &Duration) -> f32 {
+    fn calculate_efficiency_score(total_duration: &Duration) -> f32 {
         // Efficiency score based on execution time (lower is better)
         // Target: 95%+ efficiency (sub-second total execution)
         let target_ms = 1000.0; // 1 second target
@@ -515,25 +538,21 @@ impl Default for PerformanceComparison {
 }
 
 /// Global performance profiler instance
-static mut PERFORMANCE_PROFILER: Option<PerformanceProfiler> = None;
+static PERFORMANCE_PROFILER: OnceLock<Mutex<PerformanceProfiler>> = OnceLock::new();
 
 /// Initialize global performance profiler
 pub fn initialize_performance_profiler() -> Result<(), Error> {
-    unsafe {
-        if PERFORMANCE_PROFILER.is_none() {
-            PERFORMANCE_PROFILER = Some(PerformanceProfiler::new()?);
-            log::info!("🚀 Performance profiler initialized - bottleneck identification active");
-        }
+    if PERFORMANCE_PROFILER.get().is_none() && PERFORMANCE_PROFILER.set(Mutex::new(PerformanceProfiler::new()?)).is_ok() {
+        log::info!("🚀 Performance profiler initialized - bottleneck identification active");
     }
     Ok(())
 }
 
-/// Get global performance profiler
-pub fn get_performance_profiler() -> Result<&'static mut PerformanceProfiler, Error> {
-    unsafe {
-        PERFORMANCE_PROFILER.as_mut()
-            .ok_or_else(|| Error::McpServer("Performance profiler not initialized".to_string()))
-    }
+/// Lock the global performance profiler; never hold the guard across an `.await`
+pub fn get_performance_profiler() -> Result<MutexGuard<'static, PerformanceProfiler>, Error> {
+    PERFORMANCE_PROFILER.get()
+        .map(|profiler| profiler.lock().unwrap())
+        .ok_or_else(|| Error::McpServer("Performance profiler not initialized".to_string()))
 }
 
 /// Profile Infrastructure Assassin execution for optimization
@@ -541,7 +560,11 @@ pub async fn profile_infrastructure_assassin_execution(
     description: &str,
     engine: &InfrastructureAssassinEngine
 ) -> Result<ExecutionProfile, Error> {
-    get_performance_profiler()?.profile_request_execution(engine, description).await
+    // Fail before measuring when there is no profiler to record into
+    drop(get_performance_profiler()?);
+    let profile = PerformanceProfiler::measure_request_execution(engine, description).await?;
+    get_performance_profiler()?.record_profile(&profile);
+    Ok(profile)
 }
 
 /// Generate performance optimization report
@@ -553,6 +576,7 @@ pub fn generate_performance_report() -> Result<PerformanceReport, Error> {
         optimization_recommendations: profiler.optimization_recommendations.clone(),
         performance_trends: profiler.bottleneck_analysis.performance_regression_trends.clone(),
         scalability_limits: profiler.bottleneck_analysis.scalability_limits.clone(),
+        tool_concurrency: profiler.tool_concurrency.clone(),
     })
 }
 
@@ -562,4 +586,38 @@ pub struct PerformanceReport {
     pub optimization_recommendations: Vec<OptimizationRecommendation>,
     pub performance_trends: Vec<PerformanceTrend>,
     pub scalability_limits: ScalabilityLimits,
+    #[serde(default)]
+    pub tool_concurrency: Vec<ServerConcurrency>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_global_profiler_initializes_once_and_records_concurrently() {
+        initialize_performance_profiler().unwrap();
+        initialize_performance_profiler().unwrap();
+
+        let workers: Vec<_> = (0..8u64)
+            .map(|i| std::thread::spawn(move || {
+                let profile = ExecutionProfile {
+                    request_description: format!("request {}", i),
+                    total_execution_time: Duration::from_millis(i + 1),
+                    component_breakdown: vec![("core_execution".to_string(), Duration::from_millis(i + 1))],
+                    peak_memory_usage: 0,
+                    network_requests_count: 0,
+                    efficiency_score: 95.0,
+                };
+                get_performance_profiler().unwrap().record_profile(&profile);
+                generate_performance_report().unwrap()
+            }))
+            .collect();
+        for worker in workers {
+            assert!(worker.join().unwrap().bottleneck_analysis.slowest_components.contains_key("core_execution"));
+        }
+
+        // Every record landed; none was lost to a racing writer
+        assert_eq!(get_performance_profiler().unwrap().component_timings["core_execution"].len(), 8);
+    }
 }
@@ -5,6 +5,7 @@
 
 use crate::{RevenueAnalytics, InfrastructureMetrics, Error};
 use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
@@ -368,27 +369,23 @@ pub struct CompetitiveDisplacement {
 }
 
 /// Global revenue dashboard instance
-static mut REVENUE_DASHBOARD: Option<RevenueDashboard> = None;
+static REVENUE_DASHBOARD: OnceLock<Mutex<RevenueDashboard>> = OnceLock::new();
 
 /// Initialize global revenue dashboard
 pub fn initialize_revenue_dashboard() -> Result<(), Error> {
-    unsafe {
-        if REVENUE_DASHBOARD.is_none() {
-            REVENUE_DASHBOARD = Some(RevenueDashboard::new()?);
-            log::info!("💰 Global revenue dashboard initialized with competitive intelligence");
-        } else {
-            log::warn!("Revenue dashboard already initialized");
-        }
+    if REVENUE_DASHBOARD.get().is_none() && REVENUE_DASHBOARD.set(Mutex::new(RevenueDashboard::new()?)).is_ok() {
+        log::info!("💰 Global revenue dashboard initialized with competitive intelligence");
+    } else {
+        log::warn!("Revenue dashboard already initialized");
     }
     Ok(())
 }
 
-/// Get global revenue dashboard reference
-pub fn get_revenue_dashboard() -> Result<&'static mut RevenueDashboard, Error> {
-    unsafe {
-        REVENUE_DASHBOARD.as_mut()
-            .ok_or_else(|| Error::McpServer("Revenue dashboard not initialized".to_string()))
-    }
+/// Lock the global revenue dashboard; hold the guard only briefly
+pub fn get_revenue_dashboard() -> Result<MutexGuard<'static, RevenueDashboard>, Error> {
+    REVENUE_DASHBOARD.get()
+        .map(|dashboard| dashboard.lock().unwrap())
+        .ok_or_else(|| Error::McpServer("Revenue dashboard not initialized".to_string()))
 }
 
 /// Track enterprise usage for revenue analytics
@@ -400,3 +397,22 @@ pub fn track_enterprise_usage(customer_id: &str, usage: MonthlyUsage) -> Result<
 pub fn generate_executive_report() -> Result<BusinessImpactReport, Error> {
     Ok(get_revenue_dashboard()?.generate_business_impact_report())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_global_dashboard_initializes_once_and_reads_concurrently() {
+        initialize_revenue_dashboard().unwrap();
+        initialize_revenue_dashboard().unwrap();
+
+        let readers: Vec<_> = (0..8)
+            .map(|_| std::thread::spawn(|| generate_executive_report().unwrap().total_revenue_generated))
+            .collect();
+        let expected = get_revenue_dashboard().unwrap().generate_business_impact_report().total_revenue_generated;
+        for reader in readers {
+            assert_eq!(reader.join().unwrap(), expected);
+        }
+    }
+}
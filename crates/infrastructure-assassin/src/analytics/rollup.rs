@@ -0,0 +1,508 @@
+//! Tiered retention for execution history
+//!
+//! Raw `ExecutionRecord`s are kept for a recent window only. Once a record
+//! falls out of that window it is folded into an hourly `RollupRecord`, and
+//! hourly rollups older than the hourly retention are folded into daily ones.
+//! Folding happens as records arrive and only touches what just expired, so
+//! the history stays bounded without ever rescanning it. Queries pick the
+//! tiers covering the requested `TimeRange`.
+
+use super::ExecutionRecord;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Relative error of percentiles read from a `QuantileSketch`
+pub const SKETCH_RELATIVE_ACCURACY: f64 = 0.01;
+
+/// How long each tier of the execution history is kept
+#[derive(Debug, Clone)]
+pub struct RetentionConfig {
+    /// Raw records newer than this are kept as is
+    pub raw_window: Duration,
+    /// Hourly rollups newer than this are kept before being folded into days
+    pub hourly_retention: Duration,
+    /// Daily rollups older than this are dropped; `None` keeps them forever
+    pub daily_retention: Option<Duration>,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            raw_window: Duration::days(2),
+            hourly_retention: Duration::days(14),
+            daily_retention: Some(Duration::days(365)),
+        }
+    }
+}
+
+/// Half-open span of time `[start, end)` an analytics query covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl TimeRange {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self { start, end }
+    }
+
+    /// The `span` leading up to now
+    pub fn last(span: Duration) -> Self {
+        let end = Utc::now();
+        Self { start: end - span, end }
+    }
+
+    pub fn contains(&self, timestamp: DateTime<Utc>) -> bool {
+        self.start <= timestamp && timestamp < self.end
+    }
+
+    /// Length of the range in hours
+    pub fn hours(&self) -> f64 {
+        (self.end - self.start).num_seconds().max(0) as f64 / 3600.0
+    }
+}
+
+/// Finest tier a query over a range can be answered from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    Raw,
+    Hourly,
+    Daily,
+}
+
+impl Resolution {
+    fn bucket_width(self) -> Duration {
+        match self {
+            Resolution::Raw | Resolution::Hourly => Duration::hours(1),
+            Resolution::Daily => Duration::days(1),
+        }
+    }
+
+    /// Start of the bucket `timestamp` falls into
+    pub fn bucket_start(self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        timestamp.duration_trunc(self.bucket_width()).unwrap_or(timestamp)
+    }
+}
+
+/// Mergeable percentile sketch over positive values
+///
+/// Values are counted in logarithmic bins, so any percentile read back is
+/// within `SKETCH_RELATIVE_ACCURACY` of the exact one and two sketches merge
+/// by adding bin counts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuantileSketch {
+    bins: BTreeMap<i32, u64>,
+    zeros: u64,
+    count: u64,
+}
+
+impl QuantileSketch {
+    fn gamma() -> f64 {
+        (1.0 + SKETCH_RELATIVE_ACCURACY) / (1.0 - SKETCH_RELATIVE_ACCURACY)
+    }
+
+    pub fn insert(&mut self, value: f64) {
+        if value > 0.0 && value.is_finite() {
+            let bin = (value.ln() / Self::gamma().ln()).ceil() as i32;
+            *self.bins.entry(bin).or_default() += 1;
+        } else {
+            self.zeros += 1;
+        }
+        self.count += 1;
+    }
+
+    pub fn merge(&mut self, other: &QuantileSketch) {
+        for (bin, count) in &other.bins {
+            *self.bins.entry(*bin).or_default() += count;
+        }
+        self.zeros += other.zeros;
+        self.count += other.count;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Value at quantile `q` (0.0 to 1.0), ranked like `calculate_p95`
+    pub fn quantile(&self, q: f64) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let rank = ((self.count - 1) as f64 * q.clamp(0.0, 1.0)) as u64;
+        let mut seen = self.zeros;
+        if rank < seen {
+            return 0.0;
+        }
+        let gamma = Self::gamma();
+        for (bin, count) in &self.bins {
+            seen += count;
+            if rank < seen {
+                return 2.0 * gamma.powi(*bin) / (gamma + 1.0);
+            }
+        }
+        0.0
+    }
+}
+
+/// Aggregate of the executions in one bucket of time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RollupRecord {
+    pub bucket_start: DateTime<Utc>,
+    pub executions: u64,
+    pub failures: u64,
+    pub total_execution_time: f64,
+    pub min_execution_time: f64,
+    pub max_execution_time: f64,
+    pub total_memory_used: u64,
+    pub peak_memory_used: usize,
+    pub total_cpu_used: f64,
+    pub total_network_latency: f64,
+    pub min_network_latency: f64,
+    pub max_network_latency: f64,
+    pub total_container_efficiency: f64,
+    pub tools_orchestrated: u64,
+    pub cost_savings: f64,
+    pub execution_time_sketch: QuantileSketch,
+    pub network_latency_sketch: QuantileSketch,
+}
+
+impl RollupRecord {
+    pub fn new(bucket_start: DateTime<Utc>) -> Self {
+        Self {
+            bucket_start,
+            executions: 0,
+            failures: 0,
+            total_execution_time: 0.0,
+            min_execution_time: 0.0,
+            max_execution_time: 0.0,
+            total_memory_used: 0,
+            peak_memory_used: 0,
+            total_cpu_used: 0.0,
+            total_network_latency: 0.0,
+            min_network_latency: 0.0,
+            max_network_latency: 0.0,
+            total_container_efficiency: 0.0,
+            tools_orchestrated: 0,
+            cost_savings: 0.0,
+            execution_time_sketch: QuantileSketch::default(),
+            network_latency_sketch: QuantileSketch::default(),
+        }
+    }
+
+    /// Add a single execution to the rollup
+    pub fn absorb(&mut self, record: &ExecutionRecord) {
+        let first = self.executions == 0;
+        self.executions += 1;
+        self.failures += u64::from(!record.success);
+        self.total_execution_time += record.execution_time;
+        self.min_execution_time = if first { record.execution_time } else { self.min_execution_time.min(record.execution_time) };
+        self.max_execution_time = if first { record.execution_time } else { self.max_execution_time.max(record.execution_time) };
+        self.total_memory_used += record.memory_used as u64;
+        self.peak_memory_used = self.peak_memory_used.max(record.memory_used);
+        self.total_cpu_used += record.cpu_used;
+        self.total_network_latency += record.network_latency;
+        self.min_network_latency = if first { record.network_latency } else { self.min_network_latency.min(record.network_latency) };
+        self.max_network_latency = if first { record.network_latency } else { self.max_network_latency.max(record.network_latency) };
+        self.total_container_efficiency += f64::from(record.container_efficiency);
+        self.tools_orchestrated += record.tools_orchestrated as u64;
+        self.cost_savings += record.cost_savings;
+        self.execution_time_sketch.insert(record.execution_time);
+        self.network_latency_sketch.insert(record.network_latency);
+    }
+
+    /// Add another rollup's executions to this one
+    pub fn merge(&mut self, other: &RollupRecord) {
+        if other.executions == 0 {
+            return;
+        }
+        let first = self.executions == 0;
+        self.executions += other.executions;
+        self.failures += other.failures;
+        self.total_execution_time += other.total_execution_time;
+        self.min_execution_time = if first { other.min_execution_time } else { self.min_execution_time.min(other.min_execution_time) };
+        self.max_execution_time = if first { other.max_execution_time } else { self.max_execution_time.max(other.max_execution_time) };
+        self.total_memory_used += other.total_memory_used;
+        self.peak_memory_used = self.peak_memory_used.max(other.peak_memory_used);
+        self.total_cpu_used += other.total_cpu_used;
+        self.total_network_latency += other.total_network_latency;
+        self.min_network_latency = if first { other.min_network_latency } else { self.min_network_latency.min(other.min_network_latency) };
+        self.max_network_latency = if first { other.max_network_latency } else { self.max_network_latency.max(other.max_network_latency) };
+        self.total_container_efficiency += other.total_container_efficiency;
+        self.tools_orchestrated += other.tools_orchestrated;
+        self.cost_savings += other.cost_savings;
+        self.execution_time_sketch.merge(&other.execution_time_sketch);
+        self.network_latency_sketch.merge(&other.network_latency_sketch);
+    }
+
+    pub fn average_execution_time(&self) -> f64 {
+        self.average(self.total_execution_time)
+    }
+
+    pub fn average_network_latency(&self) -> f64 {
+        self.average(self.total_network_latency)
+    }
+
+    pub fn average_container_efficiency(&self) -> f64 {
+        self.average(self.total_container_efficiency)
+    }
+
+    fn average(&self, total: f64) -> f64 {
+        if self.executions == 0 { 0.0 } else { total / self.executions as f64 }
+    }
+}
+
+/// Execution history split into raw, hourly and daily tiers
+#[derive(Debug, Clone)]
+pub struct TieredHistory {
+    config: RetentionConfig,
+    /// Raw records in timestamp order
+    raw: VecDeque<ExecutionRecord>,
+    hourly: BTreeMap<DateTime<Utc>, RollupRecord>,
+    daily: BTreeMap<DateTime<Utc>, RollupRecord>,
+    /// Newest timestamp seen, which the retention windows are measured from
+    latest: Option<DateTime<Utc>>,
+}
+
+impl TieredHistory {
+    pub fn new(config: RetentionConfig) -> Self {
+        Self {
+            config,
+            raw: VecDeque::new(),
+            hourly: BTreeMap::new(),
+            daily: BTreeMap::new(),
+            latest: None,
+        }
+    }
+
+    pub fn config(&self) -> &RetentionConfig {
+        &self.config
+    }
+
+    /// Record an execution and fold whatever it pushed out of the raw window
+    pub fn push(&mut self, record: ExecutionRecord) {
+        let latest = self.latest.map_or(record.timestamp, |latest| latest.max(record.timestamp));
+        self.latest = Some(latest);
+
+        if record.timestamp < self.raw_cutoff(latest) {
+            self.fold(&record);
+        } else {
+            let position = self.raw.partition_point(|r| r.timestamp <= record.timestamp);
+            self.raw.insert(position, record);
+        }
+        self.expire(latest);
+    }
+
+    /// Raw, hourly and daily records currently held
+    pub fn stored_records(&self) -> usize {
+        self.raw.len() + self.hourly.len() + self.daily.len()
+    }
+
+    pub fn raw_records(&self) -> impl Iterator<Item = &ExecutionRecord> {
+        self.raw.iter()
+    }
+
+    pub fn hourly_rollups(&self) -> impl Iterator<Item = &RollupRecord> {
+        self.hourly.values()
+    }
+
+    pub fn daily_rollups(&self) -> impl Iterator<Item = &RollupRecord> {
+        self.daily.values()
+    }
+
+    /// Oldest tier a query starting at `range.start` has to read
+    pub fn resolution_for(&self, range: TimeRange) -> Resolution {
+        let Some(latest) = self.latest else {
+            return Resolution::Raw;
+        };
+        if range.start >= self.raw_cutoff(latest) {
+            Resolution::Raw
+        } else if range.start >= self.hourly_cutoff(latest) {
+            Resolution::Hourly
+        } else {
+            Resolution::Daily
+        }
+    }
+
+    /// Raw records within `range`
+    pub fn raw_in(&self, range: TimeRange) -> impl Iterator<Item = &ExecutionRecord> {
+        let start = self.raw.partition_point(|r| r.timestamp < range.start);
+        self.raw.range(start..).take_while(move |r| r.timestamp < range.end)
+    }
+
+    /// Everything recorded within `range`, rolled into one record
+    ///
+    /// Rollups count when their bucket starts inside the range, so edges that
+    /// reach into the hourly or daily tiers are only as precise as that tier.
+    pub fn rollup(&self, range: TimeRange) -> RollupRecord {
+        let mut total = RollupRecord::new(range.start);
+        for rollup in self.rollups_in(range) {
+            total.merge(rollup);
+        }
+        for record in self.raw_in(range) {
+            total.absorb(record);
+        }
+        total
+    }
+
+    /// Rollups over `range`, hourly when it lies within the hourly retention
+    /// and daily otherwise
+    pub fn series(&self, range: TimeRange) -> Vec<RollupRecord> {
+        let resolution = self.resolution_for(range).max(Resolution::Hourly);
+        let mut buckets: BTreeMap<DateTime<Utc>, RollupRecord> = BTreeMap::new();
+        for rollup in self.rollups_in(range) {
+            let start = resolution.bucket_start(rollup.bucket_start);
+            buckets.entry(start).or_insert_with(|| RollupRecord::new(start)).merge(rollup);
+        }
+        for record in self.raw_in(range) {
+            let start = resolution.bucket_start(record.timestamp);
+            buckets.entry(start).or_insert_with(|| RollupRecord::new(start)).absorb(record);
+        }
+        buckets.into_values().collect()
+    }
+
+    fn rollups_in(&self, range: TimeRange) -> impl Iterator<Item = &RollupRecord> {
+        let bounds = (range.start < range.end).then_some(range.start..range.end);
+        let daily = bounds.clone().into_iter().flat_map(|bounds| self.daily.range(bounds));
+        let hourly = bounds.into_iter().flat_map(|bounds| self.hourly.range(bounds));
+        daily.chain(hourly).map(|(_, rollup)| rollup)
+    }
+
+    fn raw_cutoff(&self, latest: DateTime<Utc>) -> DateTime<Utc> {
+        latest - self.config.raw_window
+    }
+
+    fn hourly_cutoff(&self, latest: DateTime<Utc>) -> DateTime<Utc> {
+        latest - self.config.hourly_retention.max(self.config.raw_window)
+    }
+
+    /// Fold a record that is past the raw window into the tier its age calls for
+    fn fold(&mut self, record: &ExecutionRecord) {
+        let latest = self.latest.unwrap_or(record.timestamp);
+        let (tier, resolution) = if record.timestamp < self.hourly_cutoff(latest) {
+            (&mut self.daily, Resolution::Daily)
+        } else {
+            (&mut self.hourly, Resolution::Hourly)
+        };
+        let start = resolution.bucket_start(record.timestamp);
+        tier.entry(start).or_insert_with(|| RollupRecord::new(start)).absorb(record);
+    }
+
+    /// Move records and rollups that have aged out of their tier down a tier
+    fn expire(&mut self, latest: DateTime<Utc>) {
+        let raw_cutoff = self.raw_cutoff(latest);
+        while self.raw.front().is_some_and(|r| r.timestamp < raw_cutoff) {
+            if let Some(record) = self.raw.pop_front() {
+                self.fold(&record);
+            }
+        }
+
+        let hourly_cutoff = self.hourly_cutoff(latest);
+        while let Some(entry) = self.hourly.first_entry() {
+            if *entry.key() + Duration::hours(1) > hourly_cutoff {
+                break;
+            }
+            let rollup = entry.remove();
+            let start = Resolution::Daily.bucket_start(rollup.bucket_start);
+            self.daily.entry(start).or_insert_with(|| RollupRecord::new(start)).merge(&rollup);
+        }
+
+        if let Some(retention) = self.config.daily_retention {
+            let daily_cutoff = latest - retention;
+            while let Some(entry) = self.daily.first_entry() {
+                if *entry.key() + Duration::days(1) > daily_cutoff {
+                    break;
+                }
+                entry.remove();
+            }
+        }
+    }
+}
+
+impl Default for TieredHistory {
+    fn default() -> Self {
+        Self::new(RetentionConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn record(timestamp: DateTime<Utc>, i: usize) -> ExecutionRecord {
+        ExecutionRecord {
+            timestamp,
+            session_id: format!("session-{}", i),
+            execution_time: 0.5 + (i % 7) as f64,
+            memory_used: 64 + (i % 13) * 8,
+            cpu_used: 0.25,
+            network_latency: 1.0 + (i % 100) as f64,
+            tools_orchestrated: 1 + i % 3,
+            cost_savings: 0.01,
+            success: !i.is_multiple_of(50),
+            container_efficiency: 0.9,
+        }
+    }
+
+    #[test]
+    fn test_thirty_days_roll_up_into_bounded_tiers() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let config = RetentionConfig {
+            raw_window: Duration::days(2),
+            hourly_retention: Duration::days(7),
+            daily_retention: None,
+        };
+        let mut history = TieredHistory::new(config);
+
+        // One execution every ten minutes for 30 days
+        let records: Vec<ExecutionRecord> = (0..30 * 144)
+            .map(|i| record(start + Duration::minutes(10 * i as i64), i))
+            .collect();
+        let mut peak_stored = 0;
+        for record in records.iter().cloned() {
+            history.push(record);
+            peak_stored = peak_stored.max(history.stored_records());
+        }
+
+        // 2 days raw, 5 days hourly and the rest daily, however many records arrive
+        assert!(history.raw_records().count() <= 2 * 144 + 1);
+        assert!(history.hourly_rollups().count() <= 5 * 24 + 1);
+        assert!(history.daily_rollups().count() <= 24);
+        assert!(peak_stored < 2 * 144 + 5 * 24 + 30 + 2);
+
+        // Nothing is lost between tiers
+        let everything = TimeRange::new(start, start + Duration::days(31));
+        let total = history.rollup(everything);
+        assert_eq!(total.executions, records.len() as u64);
+        assert_eq!(total.failures, records.iter().filter(|r| !r.success).count() as u64);
+        assert_eq!(total.tools_orchestrated, records.iter().map(|r| r.tools_orchestrated as u64).sum::<u64>());
+        assert_eq!(total.peak_memory_used, 64 + 12 * 8);
+        assert_eq!((total.min_network_latency, total.max_network_latency), (1.0, 100.0));
+        assert!((total.cost_savings - records.len() as f64 * 0.01).abs() < 1e-6);
+        let exact_mean = records.iter().map(|r| r.execution_time).sum::<f64>() / records.len() as f64;
+        assert!((total.average_execution_time() - exact_mean).abs() < 1e-9);
+
+        // Percentiles from merged sketches stay within the sketch's accuracy
+        let mut latencies: Vec<f64> = records.iter().map(|r| r.network_latency).collect();
+        latencies.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let exact_p95 = latencies[((latencies.len() - 1) as f64 * 0.95) as usize];
+        let p95 = total.network_latency_sketch.quantile(0.95);
+        assert!((p95 - exact_p95).abs() <= exact_p95 * SKETCH_RELATIVE_ACCURACY);
+
+        // Each tier answers the ranges it covers
+        let latest = records.last().unwrap().timestamp;
+        let last_day = TimeRange::new(latest - Duration::days(1), latest + Duration::minutes(1));
+        assert_eq!(history.resolution_for(last_day), Resolution::Raw);
+        assert_eq!(history.rollup(last_day).executions, 145);
+        let last_week = TimeRange::new(latest - Duration::days(6), latest + Duration::minutes(1));
+        assert_eq!(history.resolution_for(last_week), Resolution::Hourly);
+        assert!(history.series(last_week).iter().all(|bucket| bucket.executions == 6));
+        let daily = history.series(everything);
+        assert_eq!(history.resolution_for(everything), Resolution::Daily);
+        assert_eq!(daily.len(), 30);
+        assert!(daily.iter().all(|day| day.executions == 144));
+    }
+}
@@ -0,0 +1,482 @@
+//! Multi-engine federation
+//!
+//! A [`FederationRouter`] fronts several Infrastructure Assassin engines (per
+//! region or per team). Each request goes to the healthy engine whose catalog
+//! covers all required tools and that has the fewest orchestrations in
+//! flight; if that engine is unreachable the next candidate is tried.
+
+use crate::{unified_api::UnifiedStatus, DeveloperRequest, Error, UnifiedExecutionResult};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Path of the capability summary on an engine's HTTP API
+pub const STATUS_PATH: &str = "/api/v1/orchestration/status";
+
+/// Path accepting a `DeveloperRequest` on an engine's HTTP API
+pub const ORCHESTRATE_PATH: &str = "/api/v1/orchestrate";
+
+/// What an engine can run and how busy it is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilitySummary {
+    pub status: UnifiedStatus,
+    /// Tools the engine's catalog covers
+    pub tools: Vec<String>,
+    /// Orchestrations currently in flight
+    pub active_orchestrations: usize,
+}
+
+/// Failure talking to a remote engine
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum EngineCallError {
+    /// The engine could not be reached; the request may be retried elsewhere
+    #[error("engine unreachable: {0}")]
+    Unreachable(String),
+    /// The engine answered but refused or failed the request
+    #[error("engine error: {0}")]
+    Rejected(String),
+}
+
+/// Connection to one remote engine
+#[async_trait]
+pub trait EngineClient: Send + Sync {
+    async fn capability_summary(&self) -> Result<CapabilitySummary, EngineCallError>;
+
+    async fn orchestrate(&self, request: &DeveloperRequest) -> Result<UnifiedExecutionResult, EngineCallError>;
+}
+
+/// Engine reached over its HTTP API
+pub struct HttpEngineClient {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl HttpEngineClient {
+    pub fn new(base_url: impl Into<String>, timeout: Duration) -> Result<Self, Error> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| Error::Federation(e.to_string()))?;
+        Ok(Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            client,
+        })
+    }
+
+    async fn decode<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T, EngineCallError> {
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            // Gateway and availability errors mean the engine itself is not serving
+            return Err(if matches!(status.as_u16(), 502..=504) {
+                EngineCallError::Unreachable(format!("HTTP {}", status))
+            } else {
+                EngineCallError::Rejected(format!("HTTP {}: {}", status, body))
+            });
+        }
+        response.json().await.map_err(|e| EngineCallError::Rejected(e.to_string()))
+    }
+}
+
+fn transport_error(e: reqwest::Error) -> EngineCallError {
+    if e.is_connect() || e.is_timeout() {
+        EngineCallError::Unreachable(e.to_string())
+    } else {
+        EngineCallError::Rejected(e.to_string())
+    }
+}
+
+#[async_trait]
+impl EngineClient for HttpEngineClient {
+    async fn capability_summary(&self) -> Result<CapabilitySummary, EngineCallError> {
+        let response = self
+            .client
+            .get(format!("{}{}", self.base_url, STATUS_PATH))
+            .send()
+            .await
+            .map_err(transport_error)?;
+        Self::decode(response).await
+    }
+
+    async fn orchestrate(&self, request: &DeveloperRequest) -> Result<UnifiedExecutionResult, EngineCallError> {
+        let response = self
+            .client
+            .post(format!("{}{}", self.base_url, ORCHESTRATE_PATH))
+            .json(request)
+            .send()
+            .await
+            .map_err(transport_error)?;
+        Self::decode(response).await
+    }
+}
+
+/// Execution result with the engine that produced it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederatedExecutionResult {
+    #[serde(flatten)]
+    pub result: UnifiedExecutionResult,
+    pub routed_to: String,
+}
+
+/// Counters for health polling and routing decisions
+#[derive(Debug, Default)]
+pub struct FederationMetrics {
+    health_checks: AtomicU64,
+    health_check_failures: AtomicU64,
+    failovers: AtomicU64,
+    unroutable: AtomicU64,
+    routed: std::sync::Mutex<BTreeMap<String, u64>>,
+}
+
+/// Point-in-time copy of [`FederationMetrics`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FederationMetricsSnapshot {
+    pub health_checks: u64,
+    pub health_check_failures: u64,
+    /// Requests re-sent to another engine after the chosen one was unreachable
+    pub failovers: u64,
+    /// Requests no healthy engine could serve
+    pub unroutable: u64,
+    /// Requests served, by engine
+    pub routed: BTreeMap<String, u64>,
+}
+
+impl FederationMetrics {
+    pub fn snapshot(&self) -> FederationMetricsSnapshot {
+        FederationMetricsSnapshot {
+            health_checks: self.health_checks.load(Ordering::Relaxed),
+            health_check_failures: self.health_check_failures.load(Ordering::Relaxed),
+            failovers: self.failovers.load(Ordering::Relaxed),
+            unroutable: self.unroutable.load(Ordering::Relaxed),
+            routed: self.routed.lock().unwrap().clone(),
+        }
+    }
+
+    fn record_routed(&self, engine: &str) {
+        *self.routed.lock().unwrap().entry(engine.to_string()).or_default() += 1;
+    }
+}
+
+/// Registration state of a federated engine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineState {
+    pub name: String,
+    pub healthy: bool,
+    pub summary: Option<CapabilitySummary>,
+    pub last_error: Option<String>,
+}
+
+impl EngineState {
+    fn covers(&self, required_tools: &[String]) -> bool {
+        let Some(summary) = &self.summary else {
+            return false;
+        };
+        let tools: HashSet<&str> = summary.tools.iter().map(String::as_str).collect();
+        required_tools.iter().all(|tool| tools.contains(tool.as_str()))
+    }
+
+    fn load(&self) -> usize {
+        self.summary.as_ref().map_or(usize::MAX, |summary| summary.active_orchestrations)
+    }
+}
+
+struct FederatedEngine {
+    client: Arc<dyn EngineClient>,
+    state: EngineState,
+}
+
+/// Single entry point routing requests across federated engines
+#[derive(Clone, Default)]
+pub struct FederationRouter {
+    engines: Arc<RwLock<BTreeMap<String, FederatedEngine>>>,
+    metrics: Arc<FederationMetrics>,
+}
+
+impl FederationRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an engine and fetch its capability summary.
+    ///
+    /// An engine that cannot be reached is still registered, as unhealthy,
+    /// and picked up by the next health poll.
+    pub async fn register_engine(&self, name: &str, client: Arc<dyn EngineClient>) -> EngineState {
+        let state = self.poll(name, client.as_ref()).await;
+        log::info!("🌐 Registered federated engine {} (healthy: {})", name, state.healthy);
+        self.engines.write().await.insert(
+            name.to_string(),
+            FederatedEngine {
+                client,
+                state: state.clone(),
+            },
+        );
+        state
+    }
+
+    /// Remove an engine; returns false if it was not registered
+    pub async fn unregister_engine(&self, name: &str) -> bool {
+        self.engines.write().await.remove(name).is_some()
+    }
+
+    /// Current state of every registered engine
+    pub async fn engines(&self) -> Vec<EngineState> {
+        self.engines.read().await.values().map(|engine| engine.state.clone()).collect()
+    }
+
+    pub fn metrics(&self) -> FederationMetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Refresh every engine's capability summary and health
+    pub async fn poll_health(&self) {
+        let clients: Vec<(String, Arc<dyn EngineClient>)> = self
+            .engines
+            .read()
+            .await
+            .iter()
+            .map(|(name, engine)| (name.clone(), engine.client.clone()))
+            .collect();
+
+        for (name, client) in clients {
+            let state = self.poll(&name, client.as_ref()).await;
+            if let Some(engine) = self.engines.write().await.get_mut(&name) {
+                if engine.state.healthy != state.healthy {
+                    log::warn!("🌐 Federated engine {} is now {}", name, if state.healthy { "healthy" } else { "unhealthy" });
+                }
+                engine.state = state;
+            }
+        }
+    }
+
+    /// Poll engine health every `interval` until the returned handle is aborted
+    pub fn start_health_polling(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let router = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                router.poll_health().await;
+            }
+        })
+    }
+
+    /// Route a request to the least-loaded healthy engine covering its tools,
+    /// failing over to the next candidate when an engine is unreachable
+    pub async fn route(&self, request: DeveloperRequest) -> Result<FederatedExecutionResult, Error> {
+        let candidates = self.candidates(&request.required_tools).await;
+        if candidates.is_empty() {
+            self.metrics.unroutable.fetch_add(1, Ordering::Relaxed);
+            return Err(Error::Federation(format!(
+                "no healthy engine provides {:?}",
+                request.required_tools
+            )));
+        }
+
+        let mut last_error = None;
+        for (attempt, (name, client)) in candidates.into_iter().enumerate() {
+            if attempt > 0 {
+                self.metrics.failovers.fetch_add(1, Ordering::Relaxed);
+                log::warn!("🌐 Failing over request '{}' to engine {}", request.description, name);
+            }
+
+            match client.orchestrate(&request).await {
+                Ok(result) => {
+                    self.metrics.record_routed(&name);
+                    log::info!("🌐 Routed request '{}' to engine {}", request.description, name);
+                    return Ok(FederatedExecutionResult { result, routed_to: name });
+                }
+                Err(EngineCallError::Unreachable(reason)) => {
+                    log::warn!("🌐 Federated engine {} unreachable: {}", name, reason);
+                    self.mark_unhealthy(&name, &reason).await;
+                    last_error = Some(format!("{}: {}", name, reason));
+                }
+                Err(EngineCallError::Rejected(reason)) => {
+                    return Err(Error::Federation(format!("engine {} failed the request: {}", name, reason)));
+                }
+            }
+        }
+
+        Err(Error::Federation(format!(
+            "all candidate engines unreachable (last: {})",
+            last_error.unwrap_or_default()
+        )))
+    }
+
+    /// Healthy engines covering `required_tools`, least loaded first
+    async fn candidates(&self, required_tools: &[String]) -> Vec<(String, Arc<dyn EngineClient>)> {
+        let engines = self.engines.read().await;
+        let mut candidates: Vec<&FederatedEngine> = engines
+            .values()
+            .filter(|engine| engine.state.healthy && engine.state.covers(required_tools))
+            .collect();
+        candidates.sort_by(|a, b| a.state.load().cmp(&b.state.load()).then_with(|| a.state.name.cmp(&b.state.name)));
+        candidates
+            .into_iter()
+            .map(|engine| (engine.state.name.clone(), engine.client.clone()))
+            .collect()
+    }
+
+    async fn poll(&self, name: &str, client: &dyn EngineClient) -> EngineState {
+        self.metrics.health_checks.fetch_add(1, Ordering::Relaxed);
+        match client.capability_summary().await {
+            Ok(summary) => EngineState {
+                name: name.to_string(),
+                healthy: true,
+                summary: Some(summary),
+                last_error: None,
+            },
+            Err(e) => {
+                self.metrics.health_check_failures.fetch_add(1, Ordering::Relaxed);
+                EngineState {
+                    name: name.to_string(),
+                    healthy: false,
+                    summary: None,
+                    last_error: Some(e.to_string()),
+                }
+            }
+        }
+    }
+
+    async fn mark_unhealthy(&self, name: &str, reason: &str) {
+        if let Some(engine) = self.engines.write().await.get_mut(name) {
+            engine.state.healthy = false;
+            engine.state.last_error = Some(reason.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::atomic::AtomicBool;
+
+    /// In-process engine with a fixed catalog and load
+    struct MockEngine {
+        tools: Vec<String>,
+        active_orchestrations: usize,
+        up: AtomicBool,
+        served: AtomicU64,
+    }
+
+    impl MockEngine {
+        fn new(tools: &[&str], active_orchestrations: usize) -> Arc<Self> {
+            Arc::new(Self {
+                tools: tools.iter().map(|tool| tool.to_string()).collect(),
+                active_orchestrations,
+                up: AtomicBool::new(true),
+                served: AtomicU64::new(0),
+            })
+        }
+
+        fn check_up(&self) -> Result<(), EngineCallError> {
+            if self.up.load(Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err(EngineCallError::Unreachable("connection refused".to_string()))
+            }
+        }
+    }
+
+    #[async_trait]
+    impl EngineClient for MockEngine {
+        async fn capability_summary(&self) -> Result<CapabilitySummary, EngineCallError> {
+            self.check_up()?;
+            Ok(CapabilitySummary {
+                status: UnifiedStatus {
+                    mcp_servers_active: 1,
+                    tools_available: self.tools.len(),
+                    browser_sessions_active: 0,
+                    total_customers: 0,
+                    total_revenue: 0.0,
+                    aws_cost_disrupted: 0.0,
+                    productivity_multiplier: 1.0,
+                    tool_concurrency: Vec::new(),
+                    memory_pressure: Default::default(),
+                },
+                tools: self.tools.clone(),
+                active_orchestrations: self.active_orchestrations,
+            })
+        }
+
+        async fn orchestrate(&self, request: &DeveloperRequest) -> Result<UnifiedExecutionResult, EngineCallError> {
+            self.check_up()?;
+            self.served.fetch_add(1, Ordering::SeqCst);
+            Ok(UnifiedExecutionResult {
+                session_id: uuid::Uuid::new_v4(),
+                success: true,
+                combined_output: request.description.clone(),
+                mcp_servers_used: 1,
+                browser_sessions_used: 0,
+                tools_used: request.required_tools.clone(),
+                execution_time_ms: 1,
+                cost_saved_vs_aws: 0.0,
+                resource_efficiency: 1.0,
+                violations: 0,
+                plan: None,
+            })
+        }
+    }
+
+    fn request(tools: &[&str]) -> DeveloperRequest {
+        DeveloperRequest {
+            description: "federated".to_string(),
+            required_tools: tools.iter().map(|tool| tool.to_string()).collect(),
+            execution_context: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_routes_by_capability_and_load() {
+        let router = FederationRouter::new();
+        let eu = MockEngine::new(&["github", "slack"], 5);
+        let us = MockEngine::new(&["github"], 1);
+        router.register_engine("eu-west", eu.clone()).await;
+        router.register_engine("us-east", us.clone()).await;
+
+        // Both cover github; us-east is less loaded
+        let result = router.route(request(&["github"])).await.unwrap();
+        assert_eq!(result.routed_to, "us-east");
+
+        // Only eu-west covers slack
+        let result = router.route(request(&["github", "slack"])).await.unwrap();
+        assert_eq!(result.routed_to, "eu-west");
+        assert_eq!(serde_json::to_value(&result).unwrap()["routed_to"], "eu-west");
+
+        assert!(router.route(request(&["jira"])).await.is_err());
+
+        let metrics = router.metrics();
+        assert_eq!(metrics.routed.get("us-east"), Some(&1));
+        assert_eq!(metrics.routed.get("eu-west"), Some(&1));
+        assert_eq!(metrics.unroutable, 1);
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_when_engine_unreachable() {
+        let router = FederationRouter::new();
+        let primary = MockEngine::new(&["github"], 0);
+        let secondary = MockEngine::new(&["github"], 3);
+        router.register_engine("primary", primary.clone()).await;
+        router.register_engine("secondary", secondary.clone()).await;
+
+        primary.up.store(false, Ordering::SeqCst);
+        let result = router.route(request(&["github"])).await.unwrap();
+        assert_eq!(result.routed_to, "secondary");
+        assert_eq!(router.metrics().failovers, 1);
+
+        // The unreachable engine is skipped until a health poll sees it again
+        router.route(request(&["github"])).await.unwrap();
+        assert_eq!(secondary.served.load(Ordering::SeqCst), 2);
+        assert_eq!(router.metrics().failovers, 1);
+
+        primary.up.store(true, Ordering::SeqCst);
+        router.poll_health().await;
+        assert_eq!(router.route(request(&["github"])).await.unwrap().routed_to, "primary");
+        assert_eq!(router.metrics().health_check_failures, 0);
+    }
+}
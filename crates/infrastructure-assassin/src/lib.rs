@@ -9,13 +9,19 @@
 
 pub mod analytics;
 pub mod browser;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod federation;
+pub mod memory_pressure;
 pub mod orchestration;
+pub mod planner;
 pub mod security;
+pub mod shutdown;
 pub mod tools;
 pub mod unified_api;
 
 // Security modules
 pub mod security {
+    pub mod egress;
     pub mod enforcer;
 }
 
@@ -28,6 +34,9 @@ pub mod analytics {
 // Re-export key orchestrators for easy access
 pub use tools::mcp_orchestrator::{McpGalaxyOrchestrator, orchestrate_mcp_tools, initialize_mcp_orchestrator};
 pub use unified_api::{InfrastructureAssassinEngine, UnifiedExecutionResult};
+pub use shutdown::{CancellationToken, ShutdownReport};
+#[cfg(not(target_arch = "wasm32"))]
+pub use federation::{FederatedExecutionResult, FederationRouter, HttpEngineClient};
 
 use autoagents_core::{agent::Agent, tool::Tool, runtime::Runtime};
 use serde::{Deserialize, Serialize};
@@ -42,6 +51,15 @@ pub struct InfrastructureConfig {
     pub security_boundaries: SecurityPolicy,
     pub performance_tracking: bool,
     pub enterprise_deployment: bool,
+    /// Adaptive per-server limits on concurrent tool calls
+    #[serde(default)]
+    pub tool_concurrency: tools::concurrency::AdaptiveConcurrencyConfig,
+    /// Planning of requests that describe a task without naming tools
+    #[serde(default)]
+    pub planning: planner::PlanningConfig,
+    /// Admission control and session shedding as memory runs low
+    #[serde(default)]
+    pub memory_pressure: memory_pressure::MemoryPressureConfig,
 }
 
 /// Security policy configuration for zero-trust WASM sandboxing
@@ -50,6 +68,9 @@ pub struct SecurityPolicy {
     pub sandbox_isolation: bool,
     pub resource_limits: ResourceLimits,
     pub access_controls: AccessControls,
+    /// Scanning of browsed content before it enters results
+    #[serde(default)]
+    pub egress: security::egress::EgressPolicy,
 }
 
 /// Resource limits for WASM execution sandboxing
@@ -90,8 +111,11 @@ pub struct McpServerConfig {
     pub id: String,
     pub name: String,
     pub command: String,
+    #[serde(default)]
     pub args: Vec<String>,
+    #[serde(default)]
     pub env_vars: HashMap<String, String>,
+    #[serde(default)]
     pub capabilities: Vec<String>,
 }
 
@@ -245,6 +269,9 @@ impl Default for InfrastructureConfig {
             security_boundaries: SecurityPolicy::default(),
             performance_tracking: true,
             enterprise_deployment: false,
+            tool_concurrency: tools::concurrency::AdaptiveConcurrencyConfig::default(),
+            planning: planner::PlanningConfig::default(),
+            memory_pressure: memory_pressure::MemoryPressureConfig::default(),
         }
     }
 }
@@ -255,6 +282,7 @@ impl Default for SecurityPolicy {
             sandbox_isolation: true,
             resource_limits: ResourceLimits::default(),
             access_controls: AccessControls::default(),
+            egress: security::egress::EgressPolicy::default(),
         }
     }
 }
@@ -319,6 +347,18 @@ pub enum Error {
     #[error("Resource limit exceeded: {0}")]
     ResourceLimit(String),
 
+    #[error("Engine is shutting down")]
+    ShuttingDown,
+
+    #[error("Orchestration cancelled: {0}")]
+    Cancelled(String),
+
+    #[error("Federation error: {0}")]
+    Federation(String),
+
+    #[error("Planning error: {0}")]
+    Planning(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -0,0 +1,315 @@
+//! Memory pressure monitoring for the unified orchestration engine
+//!
+//! A browser tab that runs out of memory crashes instead of failing an
+//! allocation, so the engine watches how close it is to the tab's limit.
+//! Readings are sorted into pressure levels: at elevated pressure no new
+//! orchestrations are admitted, and every critical reading cancels the
+//! lowest-priority, newest orchestrations through their cancellation tokens.
+//! Level changes and shed orchestrations are reported to registered
+//! listeners, such as the JavaScript host.
+
+use crate::{shutdown::OrchestrationTracker, Error};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Reason recorded on orchestrations cancelled to relieve memory pressure
+const SHED_REASON: &str = "memory pressure shedding";
+
+/// Thresholds and polling of the memory pressure monitor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryPressureConfig {
+    pub enabled: bool,
+    /// Share of the memory limit in use at which new orchestrations are refused
+    pub elevated_ratio: f64,
+    /// Share of the memory limit in use at which orchestrations are shed
+    pub critical_ratio: f64,
+    pub poll_interval_ms: u64,
+    /// Orchestrations cancelled per critical reading
+    pub shed_per_reading: usize,
+}
+
+impl Default for MemoryPressureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            elevated_ratio: 0.75,
+            critical_ratio: 0.9,
+            poll_interval_ms: 1000,
+            shed_per_reading: 1,
+        }
+    }
+}
+
+/// How close memory use is to the limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PressureLevel {
+    #[default]
+    Normal,
+    Elevated,
+    Critical,
+}
+
+/// Memory in use against the limit it must stay under
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MemoryReading {
+    pub used_bytes: u64,
+    pub limit_bytes: u64,
+}
+
+impl MemoryReading {
+    /// Share of the limit in use; a reading without a limit is treated as full
+    pub fn ratio(&self) -> f64 {
+        if self.limit_bytes == 0 {
+            return 1.0;
+        }
+        self.used_bytes as f64 / self.limit_bytes as f64
+    }
+}
+
+/// Where readings come from; `None` when the platform offers nothing to read
+pub trait MemorySource: Send + Sync {
+    fn read(&self) -> Option<MemoryReading>;
+}
+
+/// Event reported to listeners
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MemoryPressureEvent {
+    LevelChanged {
+        from: PressureLevel,
+        to: PressureLevel,
+        reading: MemoryReading,
+    },
+    SessionsShed {
+        orchestrations: Vec<Uuid>,
+        total_shed: u64,
+    },
+}
+
+/// Pressure state as reported in `UnifiedStatus`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemoryPressureStatus {
+    pub level: PressureLevel,
+    pub last_reading: Option<MemoryReading>,
+    /// Orchestrations cancelled to relieve pressure since the engine started
+    pub shed_orchestrations: u64,
+}
+
+/// Listener for memory pressure events
+pub type MemoryPressureListener = Arc<dyn Fn(&MemoryPressureEvent) + Send + Sync>;
+
+/// Tracks the pressure level and sheds orchestrations when it turns critical
+#[derive(Clone, Default)]
+pub struct MemoryPressureMonitor {
+    config: MemoryPressureConfig,
+    status: Arc<Mutex<MemoryPressureStatus>>,
+    listeners: Arc<Mutex<Vec<MemoryPressureListener>>>,
+}
+
+impl MemoryPressureMonitor {
+    pub fn new(config: MemoryPressureConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn config(&self) -> &MemoryPressureConfig {
+        &self.config
+    }
+
+    /// Report future events to `listener`
+    pub fn on_event(&self, listener: MemoryPressureListener) {
+        self.listeners.lock().unwrap().push(listener);
+    }
+
+    pub fn status(&self) -> MemoryPressureStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Refuse new orchestrations while pressure is elevated or critical
+    pub fn admit(&self) -> Result<(), Error> {
+        match self.status.lock().unwrap().level {
+            PressureLevel::Normal => Ok(()),
+            level => Err(Error::ResourceLimit(format!(
+                "memory pressure {:?}, not admitting new sessions", level
+            ))),
+        }
+    }
+
+    /// Pressure level of a reading under the configured thresholds
+    pub fn level_for(&self, reading: &MemoryReading) -> PressureLevel {
+        let ratio = reading.ratio();
+        if ratio >= self.config.critical_ratio {
+            PressureLevel::Critical
+        } else if ratio >= self.config.elevated_ratio {
+            PressureLevel::Elevated
+        } else {
+            PressureLevel::Normal
+        }
+    }
+
+    /// Update the level from `reading`, shedding orchestrations in `tracker` when critical
+    pub fn observe(&self, reading: MemoryReading, tracker: &OrchestrationTracker) -> PressureLevel {
+        let level = self.level_for(&reading);
+        let mut events = Vec::new();
+        {
+            let mut status = self.status.lock().unwrap();
+            status.last_reading = Some(reading);
+            if status.level != level {
+                log::warn!("🧠 Memory pressure {:?} -> {:?} ({:.0}% of {} MB)",
+                          status.level, level, reading.ratio() * 100.0, reading.limit_bytes / (1024 * 1024));
+                events.push(MemoryPressureEvent::LevelChanged { from: status.level, to: level, reading });
+                status.level = level;
+            }
+
+            if level == PressureLevel::Critical {
+                let shed = tracker.shed(self.config.shed_per_reading, SHED_REASON);
+                if !shed.is_empty() {
+                    status.shed_orchestrations += shed.len() as u64;
+                    log::warn!("🧠 Shed {} orchestration(s) under critical memory pressure", shed.len());
+                    events.push(MemoryPressureEvent::SessionsShed {
+                        orchestrations: shed,
+                        total_shed: status.shed_orchestrations,
+                    });
+                }
+            }
+        }
+
+        let listeners = self.listeners.lock().unwrap().clone();
+        for event in &events {
+            for listener in &listeners {
+                listener(event);
+            }
+        }
+        level
+    }
+}
+
+/// Reads the tab's memory: the larger of the WASM linear memory and the JS
+/// heap in use, against `performance.memory`'s heap limit where the browser
+/// exposes it, else `fallback_limit_bytes`, capped by the `deviceMemory` hint
+#[cfg(target_arch = "wasm32")]
+pub struct BrowserMemorySource {
+    pub fallback_limit_bytes: u64,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl MemorySource for BrowserMemorySource {
+    fn read(&self) -> Option<MemoryReading> {
+        use wasm_bindgen::JsCast;
+
+        let number = |target: &wasm_bindgen::JsValue, key: &str| {
+            js_sys::Reflect::get(target, &key.into()).ok().and_then(|value| value.as_f64())
+        };
+        let window = web_sys::window()?;
+        let wasm_bytes = wasm_bindgen::memory()
+            .unchecked_into::<js_sys::WebAssembly::Memory>()
+            .buffer()
+            .unchecked_into::<js_sys::ArrayBuffer>()
+            .byte_length() as u64;
+
+        let heap = window.performance()
+            .and_then(|performance| js_sys::Reflect::get(&performance, &"memory".into()).ok())
+            .filter(|memory| !memory.is_undefined());
+        let heap_used = heap.as_ref().and_then(|heap| number(heap, "usedJSHeapSize")).unwrap_or(0.0) as u64;
+        let heap_limit = heap.as_ref().and_then(|heap| number(heap, "jsHeapSizeLimit")).map(|limit| limit as u64);
+        let device_bytes = number(&window.navigator(), "deviceMemory").map(|gb| (gb * 1024.0 * 1024.0 * 1024.0) as u64);
+
+        let limit = heap_limit.unwrap_or(self.fallback_limit_bytes);
+        Some(MemoryReading {
+            used_bytes: wasm_bytes.max(heap_used),
+            limit_bytes: device_bytes.map_or(limit, |device| limit.min(device)),
+        })
+    }
+}
+
+/// Listener passing events to a JavaScript host callback as plain objects
+#[cfg(target_arch = "wasm32")]
+pub fn host_callback(callback: js_sys::Function) -> MemoryPressureListener {
+    struct HostCallback(js_sys::Function);
+    // WASM runs the engine on a single thread, so the callback never crosses threads
+    unsafe impl Send for HostCallback {}
+    unsafe impl Sync for HostCallback {}
+
+    let callback = HostCallback(callback);
+    Arc::new(move |event| {
+        if let Ok(value) = serde_wasm_bindgen::to_value(event) {
+            if let Err(e) = callback.0.call1(&wasm_bindgen::JsValue::NULL, &value) {
+                log::warn!("🧠 Memory pressure callback failed: {:?}", e);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: u64 = 1000;
+
+    fn reading(used_bytes: u64) -> MemoryReading {
+        MemoryReading { used_bytes, limit_bytes: LIMIT }
+    }
+
+    fn recording_monitor() -> (MemoryPressureMonitor, Arc<Mutex<Vec<MemoryPressureEvent>>>) {
+        let monitor = MemoryPressureMonitor::new(MemoryPressureConfig::default());
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        monitor.on_event(Arc::new(move |event| sink.lock().unwrap().push(event.clone())));
+        (monitor, events)
+    }
+
+    #[test]
+    fn test_elevated_pressure_blocks_admission() {
+        let (monitor, events) = recording_monitor();
+        let tracker = OrchestrationTracker::new();
+        let running = tracker.begin().unwrap();
+
+        assert_eq!(monitor.observe(reading(500), &tracker), PressureLevel::Normal);
+        assert!(monitor.admit().is_ok());
+        assert!(events.lock().unwrap().is_empty());
+
+        assert_eq!(monitor.observe(reading(800), &tracker), PressureLevel::Elevated);
+        assert!(matches!(monitor.admit(), Err(Error::ResourceLimit(_))));
+        // Elevated pressure refuses new work but leaves running work alone
+        assert!(!running.token().is_cancelled());
+        assert_eq!(monitor.status().shed_orchestrations, 0);
+
+        assert_eq!(monitor.observe(reading(300), &tracker), PressureLevel::Normal);
+        assert!(monitor.admit().is_ok());
+        assert_eq!(*events.lock().unwrap(), vec![
+            MemoryPressureEvent::LevelChanged { from: PressureLevel::Normal, to: PressureLevel::Elevated, reading: reading(800) },
+            MemoryPressureEvent::LevelChanged { from: PressureLevel::Elevated, to: PressureLevel::Normal, reading: reading(300) },
+        ]);
+    }
+
+    #[test]
+    fn test_critical_pressure_sheds_lowest_priority_newest_first() {
+        let (monitor, events) = recording_monitor();
+        let tracker = OrchestrationTracker::new();
+        let old_low = tracker.begin_with_priority(0).unwrap();
+        let high = tracker.begin_with_priority(5).unwrap();
+        let new_low = tracker.begin_with_priority(0).unwrap();
+
+        let mut shed = Vec::new();
+        for _ in 0..3 {
+            assert_eq!(monitor.observe(reading(950), &tracker), PressureLevel::Critical);
+            if let Some(MemoryPressureEvent::SessionsShed { orchestrations, .. }) = events.lock().unwrap().last() {
+                shed.extend(orchestrations.iter().copied());
+            }
+        }
+
+        assert_eq!(shed, vec![new_low.id(), old_low.id(), high.id()]);
+        assert_eq!(monitor.status().shed_orchestrations, 3);
+        let err = new_low.token().check().unwrap_err();
+        assert!(err.to_string().contains(SHED_REASON));
+
+        // Nothing left to shed: no further events
+        let before = events.lock().unwrap().len();
+        monitor.observe(reading(990), &tracker);
+        assert_eq!(events.lock().unwrap().len(), before);
+    }
+}
@@ -0,0 +1,559 @@
+//! Request Planning
+//!
+//! Turns the free-text description of a `DeveloperRequest` without
+//! `required_tools` into a tool plan: the tools to run in order, where each
+//! takes its inputs from, and how confident the planner is. The default
+//! [`RuleBasedPlanner`] maps keywords onto capabilities through a small
+//! taxonomy; with the `llm-planner` feature an [`LlmPlanner`] asks a
+//! completion endpoint for a plan in a fixed JSON schema instead. Every plan
+//! is validated against the capability index before it is used, and plans
+//! below the configured confidence are refused rather than guessed at.
+
+use crate::{DeveloperRequest, Error};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Tools the engine can currently run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityIndex {
+    tools: BTreeSet<String>,
+}
+
+impl CapabilityIndex {
+    pub fn new<I, S>(tools: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            tools: tools.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn contains(&self, tool: &str) -> bool {
+        self.tools.contains(tool)
+    }
+
+    pub fn tools(&self) -> impl Iterator<Item = &str> {
+        self.tools.iter().map(String::as_str)
+    }
+}
+
+/// Where a planned tool input comes from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "source", content = "value", rename_all = "snake_case")]
+pub enum InputSource {
+    /// A value taken from the request description
+    Literal(String),
+    /// A key of the request's `execution_context`
+    Context(String),
+    /// The output of an earlier step, by index
+    Step(usize),
+}
+
+/// One tool of a plan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlannedStep {
+    pub tool: String,
+    /// Tool input name to its source
+    #[serde(default)]
+    pub inputs: HashMap<String, InputSource>,
+    pub confidence: f32,
+}
+
+/// Ordered tools proposed for a request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolPlan {
+    pub steps: Vec<PlannedStep>,
+    /// Confidence in the plan as a whole, 0.0 to 1.0
+    pub confidence: f32,
+    /// Planner that produced the plan
+    #[serde(default)]
+    pub planner: String,
+}
+
+impl ToolPlan {
+    /// Reject plans that name unknown tools, refer to later steps, or carry
+    /// confidences outside 0.0 to 1.0
+    pub fn validate(&self, index: &CapabilityIndex) -> Result<(), PlanError> {
+        if self.steps.is_empty() {
+            return Err(PlanError::Invalid("plan has no steps".to_string()));
+        }
+        if !(0.0..=1.0).contains(&self.confidence) {
+            return Err(PlanError::Invalid(format!("plan confidence {} is out of range", self.confidence)));
+        }
+        for (position, step) in self.steps.iter().enumerate() {
+            if !index.contains(&step.tool) {
+                return Err(PlanError::Invalid(format!("step {} uses unknown tool {}", position, step.tool)));
+            }
+            if !(0.0..=1.0).contains(&step.confidence) {
+                return Err(PlanError::Invalid(format!("step {} confidence {} is out of range", position, step.confidence)));
+            }
+            for (input, source) in &step.inputs {
+                if matches!(source, InputSource::Step(earlier) if *earlier >= position) {
+                    return Err(PlanError::Invalid(format!(
+                        "step {} input {} refers to a step that has not run yet",
+                        position, input
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn tools(&self) -> Vec<String> {
+        self.steps.iter().map(|step| step.tool.clone()).collect()
+    }
+}
+
+/// Failure to produce a usable plan
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum PlanError {
+    #[error("planner returned an invalid plan: {0}")]
+    Invalid(String),
+    #[error("planner backend failed: {0}")]
+    Backend(String),
+}
+
+/// Proposes a tool plan for a free-text request
+#[async_trait]
+pub trait RequestPlanner: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn plan(&self, description: &str, index: &CapabilityIndex) -> Result<ToolPlan, PlanError>;
+}
+
+/// How requests without `required_tools` are planned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanningConfig {
+    /// Plans less confident than this are refused
+    pub min_confidence: f32,
+    /// Completion endpoint for the LLM planner; the rule-based planner is used when unset
+    #[serde(default)]
+    pub llm: Option<LlmPlannerConfig>,
+}
+
+impl Default for PlanningConfig {
+    fn default() -> Self {
+        Self {
+            min_confidence: 0.6,
+            llm: None,
+        }
+    }
+}
+
+/// OpenAI-compatible chat completion endpoint used for planning
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmPlannerConfig {
+    pub endpoint: String,
+    pub model: String,
+    /// Environment variable holding the bearer token, if the endpoint needs one
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+    pub timeout_ms: u64,
+}
+
+/// Plan `request` and fill in its tools and literal inputs, refusing plans
+/// that are invalid or below `min_confidence`
+pub async fn plan_request(
+    planner: &dyn RequestPlanner,
+    mut request: DeveloperRequest,
+    index: &CapabilityIndex,
+    min_confidence: f32,
+) -> Result<(DeveloperRequest, ToolPlan), Error> {
+    let plan = planner
+        .plan(&request.description, index)
+        .await
+        .map_err(|e| Error::Planning(e.to_string()))?;
+    plan.validate(index).map_err(|e| Error::Planning(e.to_string()))?;
+    if plan.confidence < min_confidence {
+        return Err(Error::Planning(format!(
+            "{} planner is only {:.0}% confident in [{}] (threshold {:.0}%); list required_tools explicitly",
+            planner.name(),
+            plan.confidence * 100.0,
+            plan.tools().join(", "),
+            min_confidence * 100.0
+        )));
+    }
+
+    // Tools read their inputs from the execution context; explicit values win
+    for step in &plan.steps {
+        for (input, source) in &step.inputs {
+            let value = match source {
+                InputSource::Literal(value) => value.clone(),
+                InputSource::Context(key) => match request.execution_context.get(key) {
+                    Some(value) => value.clone(),
+                    None => continue,
+                },
+                InputSource::Step(_) => continue,
+            };
+            request.execution_context.entry(input.clone()).or_insert(value);
+        }
+    }
+    request.required_tools = plan.tools();
+    log::info!("🧭 {} planned [{}] at {:.0}% confidence",
+              planner.name(), request.required_tools.join(", "), plan.confidence * 100.0);
+    Ok((request, plan))
+}
+
+/// Keywords that point at one capability
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxonomyEntry {
+    pub capability: String,
+    pub keywords: Vec<String>,
+}
+
+/// Confidence of a step found through a taxonomy keyword
+const KEYWORD_CONFIDENCE: f32 = 0.75;
+
+/// Confidence of a step whose tool the description names outright
+const NAMED_CONFIDENCE: f32 = 0.95;
+
+/// Maps description keywords onto capabilities through a taxonomy, ordering
+/// steps by where they are mentioned
+pub struct RuleBasedPlanner {
+    taxonomy: Vec<TaxonomyEntry>,
+}
+
+impl RuleBasedPlanner {
+    pub fn new() -> Self {
+        Self::with_taxonomy(default_taxonomy())
+    }
+
+    pub fn with_taxonomy(taxonomy: Vec<TaxonomyEntry>) -> Self {
+        Self { taxonomy }
+    }
+
+    /// Position of the first mention of `tool` in `text` and its confidence
+    fn find(&self, tool: &str, text: &str) -> Option<(usize, f32)> {
+        if let Some(at) = text.find(&tool.replace('_', " ")).or_else(|| text.find(tool)) {
+            return Some((at, NAMED_CONFIDENCE));
+        }
+        let keywords: Vec<usize> = self
+            .taxonomy
+            .iter()
+            .filter(|entry| entry.capability == tool)
+            .flat_map(|entry| &entry.keywords)
+            .filter_map(|keyword| find_word(text, keyword))
+            .collect();
+        let first = keywords.iter().min()?;
+        // Each further keyword for the same capability adds a little certainty
+        let confidence = (KEYWORD_CONFIDENCE + 0.05 * (keywords.len() - 1) as f32).min(0.9);
+        Some((*first, confidence))
+    }
+}
+
+impl Default for RuleBasedPlanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl RequestPlanner for RuleBasedPlanner {
+    fn name(&self) -> &str {
+        "rule-based"
+    }
+
+    async fn plan(&self, description: &str, index: &CapabilityIndex) -> Result<ToolPlan, PlanError> {
+        let text = description.to_lowercase();
+        let mut found: Vec<(usize, &str, f32)> = index
+            .tools()
+            .filter_map(|tool| self.find(tool, &text).map(|(at, confidence)| (at, tool, confidence)))
+            .collect();
+        found.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(b.1)));
+
+        let url = description.split_whitespace().find(|word| word.starts_with("http://") || word.starts_with("https://"));
+        let steps: Vec<PlannedStep> = found
+            .into_iter()
+            .enumerate()
+            .map(|(position, (_, tool, confidence))| {
+                let mut inputs = HashMap::new();
+                if position > 0 {
+                    inputs.insert("input".to_string(), InputSource::Step(position - 1));
+                }
+                if let (Some(url), true) = (url, tool == "page_navigation") {
+                    let url = url.trim_end_matches(|c: char| !c.is_alphanumeric() && c != '/');
+                    inputs.insert("url".to_string(), InputSource::Literal(url.to_string()));
+                }
+                PlannedStep {
+                    tool: tool.to_string(),
+                    inputs,
+                    confidence,
+                }
+            })
+            .collect();
+
+        let confidence = steps.iter().map(|step| step.confidence).fold(None, |min: Option<f32>, c| Some(min.map_or(c, |m| m.min(c))));
+        Ok(ToolPlan {
+            steps,
+            confidence: confidence.unwrap_or(0.0),
+            planner: self.name().to_string(),
+        })
+    }
+}
+
+/// Byte offset of `word` in `text` at word boundaries
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    text.match_indices(word).map(|(at, _)| at).find(|&at| {
+        let before = text[..at].chars().next_back();
+        let after = text[at + word.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// Keywords for the browser automation tools and common MCP capabilities
+pub fn default_taxonomy() -> Vec<TaxonomyEntry> {
+    let entry = |capability: &str, keywords: &[&str]| TaxonomyEntry {
+        capability: capability.to_string(),
+        keywords: keywords.iter().map(|keyword| keyword.to_string()).collect(),
+    };
+    vec![
+        entry("page_navigation", &["navigate", "open", "visit", "go to", "browse"]),
+        entry("element_interaction", &["click", "press", "hover", "select"]),
+        entry("form_filling", &["fill", "form", "submit", "sign in", "log in"]),
+        entry("content_extraction", &["extract", "scrape", "read", "collect", "parse"]),
+        entry("browser_screenshot", &["screenshot", "capture", "snapshot"]),
+        entry("github", &["repository", "repo", "pull request", "issue", "commit"]),
+        entry("slack", &["slack", "channel", "notify", "message"]),
+        entry("filesystem", &["file", "directory", "folder"]),
+        entry("web_search", &["search", "look up", "find online"]),
+    ]
+}
+
+#[cfg(all(feature = "llm-planner", not(target_arch = "wasm32")))]
+pub use llm::{CompletionClient, HttpCompletionClient, LlmPlanner};
+
+#[cfg(all(feature = "llm-planner", not(target_arch = "wasm32")))]
+mod llm {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Sends one prompt with a response schema and returns the completion text
+    #[async_trait]
+    pub trait CompletionClient: Send + Sync {
+        async fn complete(&self, prompt: &str, schema: &serde_json::Value) -> Result<String, PlanError>;
+    }
+
+    /// Client for an OpenAI-compatible `/chat/completions` endpoint
+    pub struct HttpCompletionClient {
+        config: LlmPlannerConfig,
+        client: reqwest::Client,
+    }
+
+    impl HttpCompletionClient {
+        pub fn new(config: LlmPlannerConfig) -> Result<Self, Error> {
+            let client = reqwest::Client::builder()
+                .timeout(Duration::from_millis(config.timeout_ms))
+                .build()
+                .map_err(|e| Error::Planning(e.to_string()))?;
+            Ok(Self { config, client })
+        }
+    }
+
+    #[async_trait]
+    impl CompletionClient for HttpCompletionClient {
+        async fn complete(&self, prompt: &str, schema: &serde_json::Value) -> Result<String, PlanError> {
+            let body = serde_json::json!({
+                "model": self.config.model,
+                "temperature": 0,
+                "messages": [{ "role": "user", "content": prompt }],
+                "response_format": {
+                    "type": "json_schema",
+                    "json_schema": { "name": "tool_plan", "strict": true, "schema": schema },
+                },
+            });
+            let mut request = self.client.post(&self.config.endpoint).json(&body);
+            if let Some(key) = self.config.api_key_env.as_deref().and_then(|name| std::env::var(name).ok()) {
+                request = request.bearer_auth(key);
+            }
+            let response = request.send().await.map_err(|e| PlanError::Backend(e.to_string()))?;
+            if !response.status().is_success() {
+                return Err(PlanError::Backend(format!("HTTP {}", response.status())));
+            }
+            let completion: serde_json::Value = response.json().await.map_err(|e| PlanError::Backend(e.to_string()))?;
+            completion["choices"][0]["message"]["content"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| PlanError::Backend("completion has no message content".to_string()))
+        }
+    }
+
+    /// Asks a completion endpoint for a plan constrained to [`plan_schema`]
+    pub struct LlmPlanner {
+        client: Arc<dyn CompletionClient>,
+    }
+
+    impl LlmPlanner {
+        pub fn new(client: Arc<dyn CompletionClient>) -> Self {
+            Self { client }
+        }
+
+        pub fn http(config: LlmPlannerConfig) -> Result<Self, Error> {
+            Ok(Self::new(Arc::new(HttpCompletionClient::new(config)?)))
+        }
+    }
+
+    #[async_trait]
+    impl RequestPlanner for LlmPlanner {
+        fn name(&self) -> &str {
+            "llm"
+        }
+
+        async fn plan(&self, description: &str, index: &CapabilityIndex) -> Result<ToolPlan, PlanError> {
+            let tools: Vec<&str> = index.tools().collect();
+            let prompt = format!(
+                "Plan the tools needed for this developer request, in execution order. \
+                 Use only these tools: {}.\n\
+                 Inputs come from a literal value in the request, an execution context key, \
+                 or the output of an earlier step by index. \
+                 Give each step and the plan a confidence between 0 and 1.\n\n\
+                 Request: {}",
+                tools.join(", "),
+                description
+            );
+            let completion = self.client.complete(&prompt, &plan_schema(&tools)).await?;
+            let mut plan: ToolPlan = serde_json::from_str(&completion)
+                .map_err(|e| PlanError::Invalid(format!("completion does not match the plan schema: {}", e)))?;
+            plan.planner = self.name().to_string();
+            // The endpoint may ignore the schema; never trust the plan unchecked
+            plan.validate(index)?;
+            Ok(plan)
+        }
+    }
+
+    /// JSON schema the completion must follow
+    pub fn plan_schema(tools: &[&str]) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "additionalProperties": false,
+            "required": ["steps", "confidence"],
+            "properties": {
+                "confidence": { "type": "number", "minimum": 0, "maximum": 1 },
+                "steps": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "additionalProperties": false,
+                        "required": ["tool", "inputs", "confidence"],
+                        "properties": {
+                            "tool": { "type": "string", "enum": tools },
+                            "confidence": { "type": "number", "minimum": 0, "maximum": 1 },
+                            "inputs": {
+                                "type": "object",
+                                "additionalProperties": {
+                                    "type": "object",
+                                    "additionalProperties": false,
+                                    "required": ["source", "value"],
+                                    "properties": {
+                                        "source": { "enum": ["literal", "context", "step"] },
+                                        "value": { "type": ["string", "integer"] },
+                                    },
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> CapabilityIndex {
+        CapabilityIndex::new([
+            "page_navigation",
+            "form_filling",
+            "content_extraction",
+            "browser_screenshot",
+            "github",
+        ])
+    }
+
+    fn request(description: &str) -> DeveloperRequest {
+        DeveloperRequest {
+            description: description.to_string(),
+            required_tools: Vec::new(),
+            execution_context: HashMap::from([("account".to_string(), "acme".to_string())]),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rule_based_planner_end_to_end() {
+        let planner = RuleBasedPlanner::new();
+        let (planned, plan) = plan_request(
+            &planner,
+            request("Open https://example.com/login, fill the sign in form, then take a screenshot."),
+            &index(),
+            0.6,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(planned.required_tools, ["page_navigation", "form_filling", "browser_screenshot"]);
+        assert_eq!(plan.planner, "rule-based");
+        assert!(plan.confidence >= 0.75 && plan.confidence <= 0.9);
+        assert_eq!(plan.steps[2].inputs["input"], InputSource::Step(1));
+        assert_eq!(planned.execution_context["url"], "https://example.com/login");
+        assert_eq!(planned.execution_context["account"], "acme");
+
+        // Naming a tool outright is more certain than a keyword
+        let (planned, plan) = plan_request(&planner, request("Use github to list pending PRs"), &index(), 0.6).await.unwrap();
+        assert_eq!((planned.required_tools, plan.confidence), (vec!["github".to_string()], NAMED_CONFIDENCE));
+
+        // Nothing recognisable is refused, and so is a plan below the threshold
+        let err = plan_request(&planner, request("make it faster"), &index(), 0.6).await.unwrap_err();
+        assert!(matches!(err, Error::Planning(_)));
+        let err = plan_request(&planner, request("read the page"), &index(), 0.8).await.unwrap_err();
+        assert!(err.to_string().contains("75% confident in [content_extraction]"), "{}", err);
+    }
+
+    #[cfg(all(feature = "llm-planner", not(target_arch = "wasm32")))]
+    #[tokio::test]
+    async fn test_llm_plan_is_validated_before_use() {
+        use std::sync::{Arc, Mutex};
+
+        struct MockCompletion {
+            responses: Mutex<Vec<String>>,
+            prompts: Mutex<Vec<String>>,
+        }
+
+        #[async_trait]
+        impl CompletionClient for MockCompletion {
+            async fn complete(&self, prompt: &str, schema: &serde_json::Value) -> Result<String, PlanError> {
+                assert_eq!(schema["properties"]["steps"]["items"]["properties"]["tool"]["enum"].as_array().unwrap().len(), 5);
+                self.prompts.lock().unwrap().push(prompt.to_string());
+                Ok(self.responses.lock().unwrap().remove(0))
+            }
+        }
+
+        let mock = Arc::new(MockCompletion {
+            responses: Mutex::new(vec![
+                r#"{"steps": [{"tool": "github", "inputs": {"repo": {"source": "context", "value": "account"}}, "confidence": 0.9}], "confidence": 0.9}"#.to_string(),
+                r#"{"steps": [{"tool": "deploy_to_prod", "inputs": {}, "confidence": 0.9}], "confidence": 0.9}"#.to_string(),
+                r#"{"steps": [{"tool": "github", "inputs": {"input": {"source": "step", "value": 0}}, "confidence": 0.9}], "confidence": 0.9}"#.to_string(),
+                r#"Sure! Here is your plan: github"#.to_string(),
+            ]),
+            prompts: Mutex::new(Vec::new()),
+        });
+        let planner = LlmPlanner::new(mock.clone());
+
+        let (planned, plan) = plan_request(&planner, request("Triage issues"), &index(), 0.6).await.unwrap();
+        assert_eq!(plan.planner, "llm");
+        assert_eq!(planned.required_tools, ["github"]);
+        assert_eq!(planned.execution_context["repo"], "acme");
+        assert!(mock.prompts.lock().unwrap()[0].contains("Request: Triage issues"));
+
+        // Unknown tools, forward step references and free text never reach execution
+        for expected in ["unknown tool deploy_to_prod", "has not run yet", "does not match the plan schema"] {
+            let err = plan_request(&planner, request("Triage issues"), &index(), 0.6).await.unwrap_err();
+            assert!(matches!(&err, Error::Planning(message) if message.contains(expected)), "{}", err);
+        }
+    }
+}
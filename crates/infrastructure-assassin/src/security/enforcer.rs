@@ -5,6 +5,7 @@
 
 use crate::{Error, SecurityPolicy, ResourceLimits, AccessControls, WasmContext};
 use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard, OnceLock};
 use uuid::Uuid;
 
 /// Global Security Enforcer - Zero-trust boundary enforcement engine
@@ -356,7 +357,7 @@ impl ResourceMonitor {
 }
 
 /// Access audit entry for security monitoring
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AccessAuditEntry {
     pub session_id: Uuid,
     pub timestamp: std::time::SystemTime,
@@ -367,13 +368,15 @@ pub struct AccessAuditEntry {
 }
 
 /// Security access actions
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum AccessAction {
     FilesystemAccess(String),
     NetworkRequest(String),
     ExecuteCommand(String),
     BoundaryEstablished,
     SecurityViolation,
+    /// Egress scanner finding, by detector name
+    EgressFinding(String),
 }
 
 /// Security status report
@@ -397,25 +400,21 @@ impl ZeroTrustEnforcer {
 }
 
 /// Global security enforcer instance
-static mut SECURITY_ENFORCER: Option<ZeroTrustEnforcer> = None;
+static SECURITY_ENFORCER: OnceLock<Mutex<ZeroTrustEnforcer>> = OnceLock::new();
 
-/// Get global security enforcer reference
-pub fn get_security_enforcer() -> Result<&'static mut ZeroTrustEnforcer, Error> {
-    unsafe {
-        SECURITY_ENFORCER.as_mut()
-            .ok_or_else(|| Error::SecurityViolation("Security enforcer not initialized".to_string()))
-    }
+/// Lock the global security enforcer; hold the guard only briefly
+pub fn get_security_enforcer() -> Result<MutexGuard<'static, ZeroTrustEnforcer>, Error> {
+    SECURITY_ENFORCER.get()
+        .map(|enforcer| enforcer.lock().unwrap())
+        .ok_or_else(|| Error::SecurityViolation("Security enforcer not initialized".to_string()))
 }
 
 /// Initialize global security enforcer
 pub fn initialize_security_enforcer(policy: SecurityPolicy) -> Result<(), Error> {
-    unsafe {
-        if SECURITY_ENFORCER.is_none() {
-            SECURITY_ENFORCER = Some(ZeroTrustEnforcer::new(policy));
-            log::info!("🌐 Global security enforcer initialized - zero-trust boundaries active");
-        } else {
-            log::warn!("Security enforcer already initialized");
-        }
+    if SECURITY_ENFORCER.set(Mutex::new(ZeroTrustEnforcer::new(policy))).is_ok() {
+        log::info!("🌐 Global security enforcer initialized - zero-trust boundaries active");
+    } else {
+        log::warn!("Security enforcer already initialized");
     }
     Ok(())
 }
@@ -424,3 +423,27 @@ pub fn initialize_security_enforcer(policy: SecurityPolicy) -> Result<(), Error>
 pub fn enforce_zero_trust_access(session_id: Uuid, resource: &str, action: AccessAction) -> Result<(), Error> {
     get_security_enforcer()?.enforce_access(session_id, resource, action)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_global_enforcer_initializes_once_and_is_shared_across_threads() {
+        initialize_security_enforcer(SecurityPolicy::default()).unwrap();
+        initialize_security_enforcer(SecurityPolicy::default()).unwrap();
+
+        let workers: Vec<_> = (0..8)
+            .map(|_| std::thread::spawn(|| {
+                get_security_enforcer().unwrap().establish_boundary(Uuid::new_v4()).unwrap();
+                get_security_enforcer().unwrap().get_security_status().sandbox_enabled
+            }))
+            .collect();
+        for worker in workers {
+            assert!(worker.join().unwrap());
+        }
+
+        let status = get_security_enforcer().unwrap().get_security_status();
+        assert_eq!((status.active_boundaries, status.total_access_audits), (8, 8));
+    }
+}
@@ -0,0 +1,254 @@
+//! Graceful shutdown support for the unified orchestration engine
+//!
+//! Tracks in-flight orchestrations so the engine can stop admitting new work,
+//! drain what is running up to a deadline, and cooperatively cancel the rest.
+//! The same registry lets memory pressure shed individual orchestrations.
+
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// Cooperative cancellation token checked by orchestrations between phases
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    reason: Arc<OnceLock<&'static str>>,
+    notify: Arc<Notify>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation for shutdown
+    pub fn cancel(&self) {
+        self.cancel_for("shutdown");
+    }
+
+    /// Request cancellation, naming what it happens during; the first reason sticks
+    pub fn cancel_for(&self, reason: &'static str) {
+        let _ = self.reason.set(reason);
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    /// Whether cancellation was requested
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Return an error if cancellation was requested
+    pub fn check(&self) -> Result<(), Error> {
+        if self.is_cancelled() {
+            let reason = self.reason.get().copied().unwrap_or("shutdown");
+            Err(Error::Cancelled(format!("orchestration cancelled during {}", reason)))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Wait until cancellation is requested
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Outcome of a graceful shutdown
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShutdownReport {
+    /// Orchestrations that finished within the drain timeout
+    pub completed: usize,
+    /// Orchestrations cancelled after the drain timeout
+    pub cancelled: usize,
+    pub duration_ms: u64,
+}
+
+/// Registry of in-flight orchestrations
+#[derive(Debug, Clone, Default)]
+pub struct OrchestrationTracker {
+    shutting_down: Arc<AtomicBool>,
+    active: Arc<Mutex<HashMap<Uuid, ActiveOrchestration>>>,
+    next_seq: Arc<AtomicU64>,
+    finished: Arc<Notify>,
+}
+
+#[derive(Debug)]
+struct ActiveOrchestration {
+    token: CancellationToken,
+    priority: i32,
+    /// Admission order, so the newest can be told apart
+    seq: u64,
+}
+
+/// Registration of one in-flight orchestration; deregisters on drop
+#[derive(Debug)]
+pub struct OrchestrationTicket {
+    id: Uuid,
+    token: CancellationToken,
+    tracker: OrchestrationTracker,
+}
+
+impl OrchestrationTicket {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+}
+
+impl Drop for OrchestrationTicket {
+    fn drop(&mut self) {
+        self.tracker.active.lock().unwrap().remove(&self.id);
+        self.tracker.finished.notify_waiters();
+    }
+}
+
+impl OrchestrationTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new orchestration, or fail with `ShuttingDown` once draining started
+    pub fn begin(&self) -> Result<OrchestrationTicket, Error> {
+        self.begin_with_priority(0)
+    }
+
+    /// Register a new orchestration; lower priorities are shed first under memory pressure
+    pub fn begin_with_priority(&self, priority: i32) -> Result<OrchestrationTicket, Error> {
+        let mut active = self.active.lock().unwrap();
+        if self.is_shutting_down() {
+            return Err(Error::ShuttingDown);
+        }
+
+        let ticket = OrchestrationTicket {
+            id: Uuid::new_v4(),
+            token: CancellationToken::new(),
+            tracker: self.clone(),
+        };
+        active.insert(ticket.id, ActiveOrchestration {
+            token: ticket.token.clone(),
+            priority,
+            seq: self.next_seq.fetch_add(1, Ordering::SeqCst),
+        });
+        Ok(ticket)
+    }
+
+    /// Whether new orchestrations are being refused
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    /// Number of in-flight orchestrations
+    pub fn active_count(&self) -> usize {
+        self.active.lock().unwrap().len()
+    }
+
+    /// Cancel up to `count` orchestrations not already cancelled, lowest
+    /// priority first and newest first within a priority
+    pub fn shed(&self, count: usize, reason: &'static str) -> Vec<Uuid> {
+        let active = self.active.lock().unwrap();
+        let mut candidates: Vec<(&Uuid, &ActiveOrchestration)> = active.iter()
+            .filter(|(_, orchestration)| !orchestration.token.is_cancelled())
+            .collect();
+        candidates.sort_by_key(|(_, orchestration)| (orchestration.priority, std::cmp::Reverse(orchestration.seq)));
+
+        candidates.into_iter()
+            .take(count)
+            .map(|(id, orchestration)| {
+                orchestration.token.cancel_for(reason);
+                *id
+            })
+            .collect()
+    }
+
+    /// Stop admitting orchestrations, wait up to `drain_timeout` for the
+    /// in-flight ones, then cancel whatever is still running
+    pub async fn drain(&self, drain_timeout: Duration) -> ShutdownReport {
+        let started = Instant::now();
+        let in_flight = {
+            // Closing under the lock means no orchestration can slip in after the count
+            let active = self.active.lock().unwrap();
+            self.shutting_down.store(true, Ordering::SeqCst);
+            active.len()
+        };
+
+        let deadline = tokio::time::Instant::now() + drain_timeout;
+        loop {
+            let finished = self.finished.notified();
+            if self.active_count() == 0 {
+                break;
+            }
+            if tokio::time::timeout_at(deadline, finished).await.is_err() {
+                break;
+            }
+        }
+
+        let remaining: Vec<CancellationToken> = self.active.lock().unwrap().values()
+            .map(|orchestration| orchestration.token.clone())
+            .collect();
+        for token in &remaining {
+            token.cancel();
+        }
+
+        ShutdownReport {
+            completed: in_flight - remaining.len(),
+            cancelled: remaining.len(),
+            duration_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mocked orchestration: finishes after `work`, or stops early when cancelled
+    fn spawn_orchestration(tracker: &OrchestrationTracker, work: Duration) -> tokio::task::JoinHandle<Result<(), Error>> {
+        let ticket = tracker.begin().unwrap();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = tokio::time::sleep(work) => Ok(()),
+                _ = ticket.token().cancelled() => ticket.token().check(),
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_drain_completes_fast_and_cancels_slow() {
+        let tracker = OrchestrationTracker::new();
+        let fast = spawn_orchestration(&tracker, Duration::from_millis(20));
+        let slow = spawn_orchestration(&tracker, Duration::from_secs(30));
+
+        let report = tracker.drain(Duration::from_millis(200)).await;
+
+        assert_eq!(report.completed, 1);
+        assert_eq!(report.cancelled, 1);
+        assert!(report.duration_ms < 1000);
+        assert!(fast.await.unwrap().is_ok());
+        assert!(matches!(slow.await.unwrap(), Err(Error::Cancelled(_))));
+    }
+
+    #[tokio::test]
+    async fn test_new_orchestrations_rejected_while_shutting_down() {
+        let tracker = OrchestrationTracker::new();
+        let report = tracker.drain(Duration::from_millis(10)).await;
+
+        assert_eq!(report.completed, 0);
+        assert_eq!(report.cancelled, 0);
+        assert!(matches!(tracker.begin(), Err(Error::ShuttingDown)));
+    }
+}
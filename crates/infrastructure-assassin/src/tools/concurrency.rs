@@ -0,0 +1,402 @@
+//! Adaptive Concurrency Control for Tool Orchestration
+//!
+//! Each MCP server gets its own limit on concurrent tool calls, adjusted
+//! AIMD-style from what the calls observe: every window of completed calls
+//! is compared against a moving latency baseline. A window that is slower
+//! than the baseline by more than the tolerance, or fails too often, cuts
+//! the limit multiplicatively; a healthy window raises it by one. Limits stay
+//! within the configured bounds, and recent adjustments are kept for status
+//! reporting.
+
+use crate::Error;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// Bounds and tuning of the per-server concurrency limits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdaptiveConcurrencyConfig {
+    pub min_limit: usize,
+    pub max_limit: usize,
+    pub initial_limit: usize,
+    /// Completed calls per limit adjustment
+    pub window_size: usize,
+    /// Window latency above `baseline * latency_tolerance` backs the limit off
+    pub latency_tolerance: f64,
+    /// Factor the limit is multiplied by when backing off
+    pub backoff_ratio: f64,
+    /// Share of failed calls in a window that backs the limit off
+    pub max_error_rate: f64,
+    /// Weight of each window in the moving latency baseline
+    pub baseline_smoothing: f64,
+    /// Adjustments kept per server
+    pub history_len: usize,
+}
+
+impl Default for AdaptiveConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            min_limit: 1,
+            max_limit: 16,
+            initial_limit: 4,
+            window_size: 10,
+            latency_tolerance: 1.5,
+            backoff_ratio: 0.75,
+            max_error_rate: 0.2,
+            baseline_smoothing: 0.1,
+            history_len: 32,
+        }
+    }
+}
+
+/// Why a server's limit changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdjustmentReason {
+    LatencyDegraded,
+    ErrorRate,
+    Healthy,
+}
+
+/// One change of a server's concurrency limit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitAdjustment {
+    pub at: DateTime<Utc>,
+    pub from: usize,
+    pub to: usize,
+    pub reason: AdjustmentReason,
+    pub window_latency_ms: f64,
+    pub baseline_latency_ms: f64,
+    pub error_rate: f64,
+}
+
+/// Current limit, load and adjustment history of one MCP server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConcurrency {
+    pub server_id: String,
+    pub limit: usize,
+    pub in_flight: usize,
+    pub baseline_latency_ms: Option<f64>,
+    pub adjustments: Vec<LimitAdjustment>,
+}
+
+struct LimiterState {
+    limit: usize,
+    in_flight: usize,
+    baseline_ms: Option<f64>,
+    /// Latency and success of the calls completed in the current window
+    window: Vec<(f64, bool)>,
+    history: VecDeque<LimitAdjustment>,
+}
+
+/// Adaptive limit on concurrent tool calls to one MCP server
+pub struct AdaptiveLimiter {
+    config: Arc<AdaptiveConcurrencyConfig>,
+    state: Mutex<LimiterState>,
+    released: Notify,
+}
+
+impl AdaptiveLimiter {
+    fn new(config: Arc<AdaptiveConcurrencyConfig>) -> Self {
+        let limit = config.initial_limit.clamp(config.min_limit.max(1), config.max_limit.max(1));
+        Self {
+            config,
+            state: Mutex::new(LimiterState {
+                limit,
+                in_flight: 0,
+                baseline_ms: None,
+                window: Vec::new(),
+                history: VecDeque::new(),
+            }),
+            released: Notify::new(),
+        }
+    }
+
+    /// Wait until the server has room for another call
+    pub async fn acquire(self: &Arc<Self>) -> ConcurrencyPermit {
+        loop {
+            let released = self.released.notified();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.in_flight < state.limit {
+                    state.in_flight += 1;
+                    return ConcurrencyPermit {
+                        limiter: self.clone(),
+                        started: Instant::now(),
+                        completed: false,
+                    };
+                }
+            }
+            released.await;
+        }
+    }
+
+    /// Current limit on concurrent calls
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit
+    }
+
+    fn status(&self, server_id: &str) -> ServerConcurrency {
+        let state = self.state.lock().unwrap();
+        ServerConcurrency {
+            server_id: server_id.to_string(),
+            limit: state.limit,
+            in_flight: state.in_flight,
+            baseline_latency_ms: state.baseline_ms,
+            adjustments: state.history.iter().cloned().collect(),
+        }
+    }
+
+    /// Free a call's slot, learning from its outcome unless it was abandoned
+    fn release(&self, outcome: Option<(Duration, bool)>) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.in_flight = state.in_flight.saturating_sub(1);
+            if let Some((latency, success)) = outcome {
+                self.record(&mut state, latency.as_secs_f64() * 1000.0, success);
+            }
+        }
+        self.released.notify_waiters();
+    }
+
+    fn record(&self, state: &mut LimiterState, latency_ms: f64, success: bool) {
+        state.window.push((latency_ms, success));
+        if state.window.len() < self.config.window_size.max(1) {
+            return;
+        }
+
+        let window = std::mem::take(&mut state.window);
+        let failures = window.iter().filter(|(_, success)| !success).count();
+        let error_rate = failures as f64 / window.len() as f64;
+        let window_latency_ms = window.iter().map(|(latency, _)| latency).sum::<f64>() / window.len() as f64;
+        let baseline_ms = state.baseline_ms.unwrap_or(window_latency_ms);
+
+        let min = self.config.min_limit.max(1);
+        let max = self.config.max_limit.max(min);
+        let (reason, to) = if error_rate > self.config.max_error_rate {
+            (AdjustmentReason::ErrorRate, Self::backed_off(state.limit, self.config.backoff_ratio, min))
+        } else if window_latency_ms > baseline_ms * self.config.latency_tolerance {
+            (AdjustmentReason::LatencyDegraded, Self::backed_off(state.limit, self.config.backoff_ratio, min))
+        } else {
+            (AdjustmentReason::Healthy, (state.limit + 1).min(max))
+        };
+
+        // The baseline follows latency slowly, so a server that stays slower
+        // is eventually treated as healthy at its new speed
+        state.baseline_ms = Some(baseline_ms + self.config.baseline_smoothing * (window_latency_ms - baseline_ms));
+
+        if to == state.limit {
+            return;
+        }
+        if reason != AdjustmentReason::Healthy {
+            log::warn!("🐢 Tool concurrency backing off {} -> {} ({:?}, {:.1}ms vs {:.1}ms baseline, {:.0}% errors)",
+                      state.limit, to, reason, window_latency_ms, baseline_ms, error_rate * 100.0);
+        }
+        state.history.push_back(LimitAdjustment {
+            at: Utc::now(),
+            from: state.limit,
+            to,
+            reason,
+            window_latency_ms,
+            baseline_latency_ms: baseline_ms,
+            error_rate,
+        });
+        while state.history.len() > self.config.history_len {
+            state.history.pop_front();
+        }
+        state.limit = to;
+    }
+
+    fn backed_off(limit: usize, ratio: f64, min: usize) -> usize {
+        ((limit as f64 * ratio) as usize).min(limit.saturating_sub(1)).max(min)
+    }
+}
+
+/// A slot for one tool call; complete it with the call's outcome, or drop it
+/// to give the slot back without a latency sample
+pub struct ConcurrencyPermit {
+    limiter: Arc<AdaptiveLimiter>,
+    started: Instant,
+    completed: bool,
+}
+
+impl ConcurrencyPermit {
+    /// Release the slot, reporting the call's latency and success
+    pub fn complete(mut self, success: bool) {
+        self.completed = true;
+        self.limiter.release(Some((self.started.elapsed(), success)));
+    }
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.limiter.release(None);
+        }
+    }
+}
+
+/// Adaptive concurrency limits of all MCP servers
+#[derive(Clone, Default)]
+pub struct ConcurrencyController {
+    config: Arc<AdaptiveConcurrencyConfig>,
+    limiters: Arc<Mutex<HashMap<String, Arc<AdaptiveLimiter>>>>,
+}
+
+impl ConcurrencyController {
+    pub fn new(config: AdaptiveConcurrencyConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            limiters: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Limiter of a server, created at the initial limit on first use
+    pub fn limiter(&self, server_id: &str) -> Arc<AdaptiveLimiter> {
+        self.limiters.lock().unwrap()
+            .entry(server_id.to_string())
+            .or_insert_with(|| Arc::new(AdaptiveLimiter::new(self.config.clone())))
+            .clone()
+    }
+
+    /// Limits and adjustment history of every server called so far
+    pub fn status(&self) -> Vec<ServerConcurrency> {
+        let mut status: Vec<_> = self.limiters.lock().unwrap().iter()
+            .map(|(server_id, limiter)| limiter.status(server_id))
+            .collect();
+        status.sort_by(|a, b| a.server_id.cmp(&b.server_id));
+        status
+    }
+
+    /// Run one tool call once its server has a free slot
+    pub async fn run<T, Fut>(&self, server_id: &str, call: Fut) -> Result<T, Error>
+    where
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let permit = self.limiter(server_id).acquire().await;
+        let result = call.await;
+        permit.complete(result.is_ok());
+        result
+    }
+
+    /// Run tool calls in parallel, each `(server_id, call)` waiting for a slot
+    /// under its server's limit. Results are returned in call order.
+    pub async fn run_parallel<T, F, Fut>(&self, calls: Vec<(String, F)>) -> Vec<Result<T, Error>>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T, Error>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let handles: Vec<_> = calls.into_iter()
+            .map(|(server_id, call)| {
+                let limiter = self.limiter(&server_id);
+                tokio::spawn(async move {
+                    let permit = limiter.acquire().await;
+                    let result = call().await;
+                    permit.complete(result.is_ok());
+                    result
+                })
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(handles.len());
+        for handle in handles {
+            results.push(handle.await.unwrap_or_else(|e| Err(Error::McpServer(format!("Tool call aborted: {}", e)))));
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// MCP server stand-in taking 5ms per call, or 5ms per concurrent call
+    /// while degraded
+    #[derive(Default)]
+    struct MockServer {
+        in_flight: AtomicUsize,
+        peak_in_flight: AtomicUsize,
+        degraded: AtomicBool,
+    }
+
+    impl MockServer {
+        async fn call(&self) -> Result<(), Error> {
+            let load = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak_in_flight.fetch_max(load, Ordering::SeqCst);
+            let factor = if self.degraded.load(Ordering::SeqCst) { load as u32 } else { 1 };
+            tokio::time::sleep(Duration::from_millis(5) * factor).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    async fn run_batch(controller: &ConcurrencyController, server: &Arc<MockServer>, calls: usize) {
+        let calls = (0..calls)
+            .map(|_| {
+                let server = server.clone();
+                ("mock".to_string(), move || async move { server.call().await })
+            })
+            .collect();
+        assert!(controller.run_parallel(calls).await.iter().all(Result::is_ok));
+    }
+
+    #[tokio::test]
+    async fn test_limit_backs_off_under_degraded_latency_and_recovers() {
+        let controller = ConcurrencyController::new(AdaptiveConcurrencyConfig::default());
+        let server = Arc::new(MockServer::default());
+        let limiter = controller.limiter("mock");
+
+        run_batch(&controller, &server, 150).await;
+        let healthy_limit = limiter.limit();
+        assert!(healthy_limit > 4, "limit only reached {}", healthy_limit);
+        assert!(server.peak_in_flight.load(Ordering::SeqCst) <= healthy_limit);
+
+        server.degraded.store(true, Ordering::SeqCst);
+        run_batch(&controller, &server, 40).await;
+        let degraded_limit = limiter.limit();
+        assert!(degraded_limit <= healthy_limit / 2, "limit {} after degrading from {}", degraded_limit, healthy_limit);
+
+        server.degraded.store(false, Ordering::SeqCst);
+        run_batch(&controller, &server, 100).await;
+        assert!(limiter.limit() >= degraded_limit + 4, "limit {} did not recover from {}", limiter.limit(), degraded_limit);
+
+        let status = controller.status();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].in_flight, 0);
+        assert!(status[0].adjustments.iter().any(|a| a.reason == AdjustmentReason::LatencyDegraded && a.to < a.from));
+        assert_eq!(status[0].adjustments.last().unwrap().reason, AdjustmentReason::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_errors_back_off_within_bounds() {
+        let config = AdaptiveConcurrencyConfig {
+            min_limit: 2,
+            max_limit: 6,
+            window_size: 4,
+            ..Default::default()
+        };
+        let limiter = ConcurrencyController::new(config).limiter("flaky");
+
+        for _ in 0..40 {
+            limiter.acquire().await.complete(false);
+        }
+        assert_eq!(limiter.limit(), 2);
+
+        for _ in 0..40 {
+            limiter.acquire().await.complete(true);
+        }
+        assert_eq!(limiter.limit(), 6);
+
+        // Abandoned calls free their slot without counting towards a window
+        drop(limiter.acquire().await);
+        let status = limiter.status("flaky");
+        assert_eq!(status.in_flight, 0);
+        assert!(status.adjustments.iter().any(|a| a.reason == AdjustmentReason::ErrorRate && a.to < a.from));
+    }
+}
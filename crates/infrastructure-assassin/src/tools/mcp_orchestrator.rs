@@ -4,15 +4,20 @@
 //! as specified in the Infrastructure Assassin implementation plan Phase 2.
 
 use crate::{McpServerConfig, Error, ExecutionResult, DeveloperRequest};
+use crate::tools::concurrency::{AdaptiveConcurrencyConfig, ConcurrencyController};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 /// MCP Galaxy Orchestrator - manages entire 16K+ MCP server ecosystem
 pub struct McpGalaxyOrchestrator {
     pub server_catalog: HashMap<String, McpServerConfig>,
-    pub tool_registry: HashMap<String, Vec<autoagents_core::tool::Tool>>,
+    pub tool_registry: HashMap<String, Vec<autoagents::llm::chat::Tool>>,
+    /// Server binding for each tool in `tool_registry`, keyed by tool name
+    pub tool_bindings: HashMap<String, crate::tools::McpToolBinding>,
     pub execution_engine: ToolChainExecutor,
     pub discovery_service: ServerDiscovery,
+    pub concurrency: ConcurrencyController,
 }
 
 /// Tool chain executor for orchestration across multiple MCP servers
@@ -24,7 +29,7 @@ pub struct ToolChainExecutor {
 /// Individual tool chain for task execution
 pub struct ToolChain {
     pub id: Uuid,
-    pub tools: Vec<autoagents_core::tool::Tool>,
+    pub tools: Vec<autoagents::llm::chat::Tool>,
     pub execution_plan: Vec<String>, // Ordered list of tool names
     pub session_context: HashMap<String, serde_json::Value>,
 }
@@ -55,11 +60,19 @@ impl McpGalaxyOrchestrator {
         Self {
             server_catalog: HashMap::new(),
             tool_registry: HashMap::new(),
+            tool_bindings: HashMap::new(),
             execution_engine: ToolChainExecutor::new(),
             discovery_service: ServerDiscovery::new(),
+            concurrency: ConcurrencyController::default(),
         }
     }
 
+    /// Bound concurrent tool calls per MCP server with adaptive limits
+    pub fn with_concurrency(mut self, config: AdaptiveConcurrencyConfig) -> Self {
+        self.concurrency = ConcurrencyController::new(config);
+        self
+    }
+
     /// Load MCP server catalog from filesystem
     pub async fn load_mcp_catalog(&mut self, catalog_path: &str) -> Result<(), Error> {
         log::info!("Loading MCP server catalog from: {}", catalog_path);
@@ -72,13 +85,20 @@ impl McpGalaxyOrchestrator {
 
         log::info!("Loaded {} MCP servers into catalog", self.server_catalog.len());
 
-        // Bind tools for all discovered servers
+        // Bind tools for all discovered servers; one without tools stays in the catalog unbound
         for server in self.server_catalog.values() {
-            let tools = crate::tools::bind_server_tools(server).await?;
-            self.tool_registry.insert(server.id.clone(), tools);
+            match crate::tools::bind_server_tools(server).await {
+                Ok(tools) => {
+                    for tool in &tools {
+                        self.tool_bindings.insert(tool.function.name.clone(), server.into());
+                    }
+                    self.tool_registry.insert(server.id.clone(), tools);
+                }
+                Err(e) => log::warn!("⚠️ Not binding tools for MCP server {}: {}", server.id, e),
+            }
         }
 
-        log::info!("Successfully bound tools for {} MCP servers", self.server_catalog.len());
+        log::info!("Successfully bound tools for {} MCP servers", self.tool_registry.len());
         Ok(())
     }
 
@@ -86,15 +106,16 @@ impl McpGalaxyOrchestrator {
     pub async fn orchestrate_tools(&mut self, request: DeveloperRequest) -> Result<ExecutionResult, Error> {
         log::info!("Orchestrating tools for request: {}", request.description);
 
-        // Create execution chain based on required tools
+        // Create execution chain based on required tools, keeping each tool's server
         let mut tool_chain = Vec::new();
         for tool_name in &request.required_tools {
-            if let Some(tools) = self.tool_registry.values().find(|tools| {
-                tools.iter().any(|tool| tool.name() == *tool_name)
-            }) {
-                if let Some(tool) = tools.iter().find(|tool| tool.name() == *tool_name) {
-                    tool_chain.push(tool.clone());
-                }
+            let bound = self.tool_bindings.get(tool_name).and_then(|binding| {
+                self.tool_registry.get(&binding.server_id)?.iter()
+                    .find(|tool| tool.function.name == *tool_name)
+                    .map(|tool| (binding.server_id.clone(), tool.clone()))
+            });
+            if let Some(bound) = bound {
+                tool_chain.push(bound);
             }
         }
 
@@ -106,26 +127,19 @@ impl McpGalaxyOrchestrator {
         let start_time = std::time::Instant::now();
         let chain_id = Uuid::new_v4();
 
-        let mut results = Vec::new();
-        let mut success = true;
         let mut total_memory_used = 0usize;
         let mut total_cpu_used = 0.0f64;
         let mut max_network_latency = 0.0f64;
 
-        for tool in tool_chain.iter() {
-            match self.execute_single_tool(chain_id, tool).await {
-                Ok(result) => {
-                    results.push(result.clone());
-                    total_memory_used += result.get("memory_used").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
-                    total_cpu_used += result.get("cpu_used").and_then(|v| v.as_f64()).unwrap_or(0.0);
-                    max_network_latency = max_network_latency.max(result.get("network_latency").and_then(|v| v.as_f64()).unwrap_or(0.0));
-                }
-                Err(e) => {
-                    success = false;
-                    log::warn!("Tool execution failed: {}", e);
-                    break;
-                }
-            }
+        let (results, success) = self.execute_chain(&tool_chain, |tool_name| execute_single_tool(chain_id, tool_name)).await;
+        for result in &results {
+            total_memory_used += result.get("memory_used").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+            total_cpu_used += result.get("cpu_used").and_then(|v| v.as_f64()).unwrap_or(0.0);
+            max_network_latency = max_network_latency.max(result.get("network_latency").and_then(|v| v.as_f64()).unwrap_or(0.0));
+        }
+
+        if let Ok(mut profiler) = crate::analytics::performance::get_performance_profiler() {
+            profiler.record_tool_concurrency(self.concurrency.status());
         }
 
         let execution_time = start_time.elapsed().as_secs_f64();
@@ -151,21 +165,46 @@ impl McpGalaxyOrchestrator {
         })
     }
 
-    async fn execute_single_tool(&self, chain_id: Uuid, tool: &autoagents_core::tool::Tool) -> Result<serde_json::Value, Error> {
-        // Placeholder implementation - integrates with WASM runtime
-        log::info!("Executing tool '{}' in chain {}", tool.name(), chain_id);
+    /// Run the chain in order, since each step may depend on the one before,
+    /// stopping at the first failure. Every call waits for a slot under its
+    /// server's adaptive limit, shared with the chains running alongside.
+    async fn execute_chain<F, Fut>(
+        &self,
+        tool_chain: &[(String, autoagents::llm::chat::Tool)],
+        mut call: F,
+    ) -> (Vec<serde_json::Value>, bool)
+    where
+        F: FnMut(String) -> Fut,
+        Fut: std::future::Future<Output = Result<serde_json::Value, Error>>,
+    {
+        let mut results = Vec::new();
+        for (server_id, tool) in tool_chain {
+            match self.concurrency.run(server_id, call(tool.function.name.clone())).await {
+                Ok(result) => results.push(result),
+                Err(e) => {
+                    log::warn!("Tool execution failed: {}", e);
+                    return (results, false);
+                }
+            }
+        }
+        (results, true)
+    }
+}
 
-        // Simulate tool execution with metrics
-        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+async fn execute_single_tool(chain_id: Uuid, tool_name: String) -> Result<serde_json::Value, Error> {
+    // Placeholder implementation - integrates with WASM runtime
+    log::info!("Executing tool '{}' in chain {}", tool_name, chain_id);
 
-        Ok(serde_json::json!({
-            "tool_name": tool.name(),
-            "memory_used": 64,
-            "cpu_used": 0.1,
-            "network_latency": 5.0,
-            "result": "success"
-        }))
-    }
+    // Simulate tool execution with metrics
+    tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+
+    Ok(serde_json::json!({
+        "tool_name": tool_name,
+        "memory_used": 64,
+        "cpu_used": 0.1,
+        "network_latency": 5.0,
+        "result": "success"
+    }))
 }
 
 impl ToolChainExecutor {
@@ -204,29 +243,74 @@ impl ServerDiscovery {
         }
     }
 
+    /// Read every `*.json` server definition in `catalog_path`.
+    ///
+    /// A file holds one server object or an array of them. Files are read in
+    /// name order; a missing directory yields an empty catalog. A file that
+    /// fails to parse, or holds a definition without an `id`, `name` or
+    /// `command`, is logged and skipped, while a server id defined twice is
+    /// an error.
     pub async fn discover_servers(&self, catalog_path: &str) -> Result<Vec<McpServerConfig>, Error> {
         log::info!("Discovering MCP servers in catalog: {}", catalog_path);
 
-        // Placeholder implementation - in real implementation this would scan filesystem
-        // or API endpoints for available MCP servers
-        let mut servers = Vec::new();
+        let dir = Path::new(catalog_path);
+        if !dir.is_dir() {
+            log::warn!("⚠️ MCP catalog directory {} not found, starting with no servers", catalog_path);
+            return Ok(Vec::new());
+        }
 
-        // Add known MCP servers (this would be dynamic in real implementation)
-        servers.push(McpServerConfig {
-            id: "filesystem".to_string(),
-            name: "File System MCP Server".to_string(),
-            command: "npx".to_string(),
-            args: vec!["-y".to_string(), "@modelcontextprotocol/server-filesystem".to_string(), "${workspaceFolder}".to_string()],
-            env_vars: HashMap::new(),
-            capabilities: vec!["read_file".to_string(), "write_file".to_string(), "list_dir".to_string()],
-        });
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()?;
+        files.retain(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"));
+        files.sort();
+
+        let mut servers: Vec<McpServerConfig> = Vec::new();
+        for file in files {
+            let definitions = match read_definitions(&file) {
+                Ok(definitions) => definitions,
+                Err(reason) => {
+                    log::warn!("⚠️ Skipping MCP server definition {}: {}", file.display(), reason);
+                    continue;
+                }
+            };
+            for server in definitions {
+                if servers.iter().any(|known| known.id == server.id) {
+                    return Err(Error::McpServer(format!(
+                        "Duplicate MCP server id '{}' in {}", server.id, file.display()
+                    )));
+                }
+                servers.push(server);
+            }
+        }
 
-        // Add more servers as discovered...
         log::info!("Discovered {} MCP servers", servers.len());
         Ok(servers)
     }
 }
 
+/// Parse and validate the definitions in one catalog file
+fn read_definitions(file: &Path) -> Result<Vec<McpServerConfig>, String> {
+    let contents = std::fs::read_to_string(file).map_err(|e| e.to_string())?;
+    // One server object, or an array of them
+    let definitions = match serde_json::from_str(&contents).map_err(|e| e.to_string())? {
+        serde_json::Value::Array(items) => items
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<Vec<McpServerConfig>, _>>(),
+        definition => serde_json::from_value(definition).map(|server| vec![server]),
+    }
+    .map_err(|e| e.to_string())?;
+    for server in &definitions {
+        for (field, value) in [("id", &server.id), ("name", &server.name), ("command", &server.command)] {
+            if value.trim().is_empty() {
+                return Err(format!("server '{}' has an empty `{}`", server.id, field));
+            }
+        }
+    }
+    Ok(definitions)
+}
+
 impl ChainPerformanceMonitor {
     pub fn new() -> Self {
         Self {
@@ -278,3 +362,98 @@ pub async fn orchestrate_mcp_tools(request: DeveloperRequest) -> Result<Executio
     let orchestrator = get_mcp_orchestrator()?;
     orchestrator.orchestrate_tools(request).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_load_mcp_catalog_reads_server_definitions() {
+        let dir = std::env::temp_dir().join(format!("mcp-catalog-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("filesystem.json"), r#"{
+            "id": "filesystem",
+            "name": "File System MCP Server",
+            "command": "npx",
+            "args": ["-y", "@modelcontextprotocol/server-filesystem"],
+            "capabilities": ["read_file", "write_file"]
+        }"#).unwrap();
+        std::fs::write(dir.join("web.json"), r#"[
+            {"id": "fetch", "name": "Fetch", "command": "uvx", "capabilities": ["fetch"]},
+            {"id": "search", "name": "Search", "command": "uvx"}
+        ]"#).unwrap();
+        std::fs::write(dir.join("README.md"), "not a definition").unwrap();
+
+        let mut orchestrator = McpGalaxyOrchestrator::new();
+        orchestrator.load_mcp_catalog(dir.to_str().unwrap()).await.unwrap();
+
+        let mut ids: Vec<&str> = orchestrator.server_catalog.keys().map(String::as_str).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["fetch", "filesystem", "search"]);
+        assert_eq!(orchestrator.server_catalog["filesystem"].args.len(), 2);
+        let names: Vec<&str> = orchestrator.tool_registry["filesystem"].iter().map(|tool| tool.function.name.as_str()).collect();
+        assert_eq!(names, vec!["read_file", "write_file"]);
+        assert!(!orchestrator.tool_registry.contains_key("search"));
+        assert_eq!(orchestrator.tool_bindings["write_file"].server_id, "filesystem");
+        assert_eq!(orchestrator.tool_bindings["fetch"].command, "uvx");
+        assert!(!orchestrator.tool_bindings.contains_key("search"));
+
+        // A server id defined twice is rejected
+        std::fs::write(dir.join("zz-dup.json"), r#"{"id": "fetch", "name": "Fetch again", "command": "uvx"}"#).unwrap();
+        let err = McpGalaxyOrchestrator::new().load_mcp_catalog(dir.to_str().unwrap()).await.unwrap_err();
+        assert!(err.to_string().contains("Duplicate MCP server id 'fetch'"));
+
+        // No catalog directory means no servers rather than a failed init
+        let missing = dir.join("missing");
+        let mut empty = McpGalaxyOrchestrator::new();
+        empty.load_mcp_catalog(missing.to_str().unwrap()).await.unwrap();
+        assert!(empty.server_catalog.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_chain_runs_in_order_and_stops_at_first_failure() {
+        let server = McpServerConfig {
+            id: "filesystem".to_string(),
+            name: "File System".to_string(),
+            command: "npx".to_string(),
+            args: vec![],
+            env_vars: HashMap::new(),
+            capabilities: vec!["read_file".to_string(), "transform".to_string(), "write_file".to_string()],
+        };
+        let chain: Vec<_> = crate::tools::bind_server_tools(&server).await.unwrap()
+            .into_iter()
+            .map(|tool| ("filesystem".to_string(), tool))
+            .collect();
+        let orchestrator = McpGalaxyOrchestrator::new();
+
+        let called = std::sync::Mutex::new(Vec::new());
+        let (results, success) = orchestrator.execute_chain(&chain, |tool_name| {
+            called.lock().unwrap().push(tool_name.clone());
+            async move { Ok(serde_json::json!({ "tool_name": tool_name })) }
+        }).await;
+        assert!(success);
+        assert_eq!(results.len(), 3);
+        assert_eq!(*called.lock().unwrap(), vec!["read_file", "transform", "write_file"]);
+
+        // The step after a failed one never runs
+        called.lock().unwrap().clear();
+        let (results, success) = orchestrator.execute_chain(&chain, |tool_name| {
+            called.lock().unwrap().push(tool_name.clone());
+            async move {
+                if tool_name == "transform" {
+                    Err(Error::McpServer("transform failed".to_string()))
+                } else {
+                    Ok(serde_json::json!({ "tool_name": tool_name }))
+                }
+            }
+        }).await;
+        assert!(!success);
+        assert_eq!(results, vec![serde_json::json!({ "tool_name": "read_file" })]);
+        assert_eq!(*called.lock().unwrap(), vec!["read_file", "transform"]);
+
+        let status = orchestrator.concurrency.status();
+        assert_eq!(status[0].server_id, "filesystem");
+    }
+}
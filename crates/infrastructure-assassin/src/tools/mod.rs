@@ -4,12 +4,9 @@
 //! for unified tool execution in the Infrastructure Assassin platform.
 
 pub mod mcp_orchestrator;
+pub mod concurrency;
 
-/// MCP galaxy orchestrator for tool orchestration
-#[derive(Debug)]
-pub struct McpGalaxyOrchestrator {
-    // Implementation will manage 16K+ MCP server catalog
-}
+pub use mcp_orchestrator::McpGalaxyOrchestrator;
 
 /// Tool registry for available MCP capabilities
 #[derive(Debug)]
@@ -25,32 +22,140 @@ pub struct OrchestrationResult {
     pub output: serde_json::Value,
 }
 
-/// MCP server management
-impl McpGalaxyOrchestrator {
-    pub fn new() -> Self {
-        todo!("Implement MCP orchestrator")
+/// Discover the MCP servers defined by the `*.json` files in `catalog_path`,
+/// skipping malformed definitions
+pub async fn discover_mcp_servers(catalog_path: &str) -> Result<Vec<crate::McpServerConfig>, crate::Error> {
+    mcp_orchestrator::ServerDiscovery::new().discover_servers(catalog_path).await
+}
+
+/// How to reach the MCP server behind a bound tool
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct McpToolBinding {
+    pub server_id: String,
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+impl From<&crate::McpServerConfig> for McpToolBinding {
+    /// Environment variables are left out, as they often hold credentials
+    fn from(server: &crate::McpServerConfig) -> Self {
+        Self {
+            server_id: server.id.clone(),
+            command: server.command.clone(),
+            args: server.args.clone(),
+        }
     }
+}
 
-    pub async fn load_mcp_catalog(&mut self, _catalog_path: &str) -> Result<(), crate::Error> {
-        todo!("Implement MCP catalog loading")
+/// Bind tools from MCP server configuration, one function tool per declared capability.
+///
+/// Tool schemas are shown to models, so they carry no server details; callers
+/// keep an [`McpToolBinding`] per tool name alongside them instead.
+pub async fn bind_server_tools(server: &crate::McpServerConfig) -> Result<Vec<autoagents::llm::chat::Tool>, crate::Error> {
+    if server.capabilities.is_empty() {
+        return Err(crate::Error::McpServer(format!("MCP server '{}' declares no capabilities", server.id)));
     }
 
-    pub async fn orchestrate_tools(&self, _tools: Vec<String>) -> Result<OrchestrationResult, crate::Error> {
-        todo!("Implement tool orchestration")
+    let mut tools = Vec::with_capacity(server.capabilities.len());
+    for capability in &server.capabilities {
+        if capability.trim().is_empty() {
+            return Err(crate::Error::McpServer(format!("MCP server '{}' declares an empty capability", server.id)));
+        }
+        tools.push(autoagents::llm::chat::Tool {
+            tool_type: "function".to_string(),
+            function: autoagents::llm::chat::FunctionTool {
+                name: capability.clone(),
+                description: format!("{} provided by {}", capability, server.name),
+                parameters: serde_json::json!({ "type": "object" }),
+            },
+        });
     }
+    Ok(tools)
 }
 
-/// Discover available MCP servers
-pub async fn discover_mcp_servers(_catalog_path: &str) -> Result<Vec<crate::McpServerConfig>, crate::Error> {
-    todo!("Implement MCP server discovery")
+/// Orchestrate tool chain execution
+pub async fn orchestrate_tool_chain(request: crate::DeveloperRequest) -> Result<crate::ExecutionResult, crate::Error> {
+    mcp_orchestrator::orchestrate_mcp_tools(request).await
 }
 
-/// Bind tools from MCP server configuration
-pub async fn bind_server_tools(_server: &crate::McpServerConfig) -> Result<Vec<autoagents_core::tool::Tool>, crate::Error> {
-    todo!("Implement server tool binding")
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Mutex, Once};
 
-/// Orchestrate tool chain execution
-pub async fn orchestrate_tool_chain(_request: crate::DeveloperRequest) -> Result<crate::ExecutionResult, crate::Error> {
-    todo!("Implement tool chain orchestration")
+    /// Warnings logged while the tests run
+    static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct CaptureLogger;
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                WARNINGS.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn capture_warnings() {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            let _ = log::set_logger(&CaptureLogger);
+            log::set_max_level(log::LevelFilter::Warn);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_discover_mcp_servers_skips_malformed_definitions() {
+        capture_warnings();
+        let dir = std::env::temp_dir().join(format!("mcp-discovery-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("github.json"), r#"{"id": "github", "name": "GitHub", "command": "npx", "capabilities": ["create_issue"]}"#).unwrap();
+        std::fs::write(dir.join("broken.json"), r#"{"id": "broken", "name": "Broken"}"#).unwrap();
+
+        let servers = discover_mcp_servers(dir.to_str().unwrap()).await.unwrap();
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].id, "github");
+        assert_eq!(servers[0].capabilities, vec!["create_issue".to_string()]);
+
+        let broken = dir.join("broken.json").display().to_string();
+        assert!(WARNINGS.lock().unwrap().iter().any(|warning| warning.contains(&broken) && warning.contains("command")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_bind_server_tools_keeps_server_details_out_of_the_schema() {
+        let mut server = crate::McpServerConfig {
+            id: "filesystem".to_string(),
+            name: "File System".to_string(),
+            command: "npx".to_string(),
+            args: vec!["-y".to_string(), "@modelcontextprotocol/server-filesystem".to_string()],
+            env_vars: [("API_TOKEN".to_string(), "secret".to_string())].into_iter().collect(),
+            capabilities: vec!["read_file".to_string(), "write_file".to_string()],
+        };
+
+        let tools = bind_server_tools(&server).await.unwrap();
+        let names: Vec<&str> = tools.iter().map(|tool| tool.function.name.as_str()).collect();
+        assert_eq!(names, vec!["read_file", "write_file"]);
+        for tool in &tools {
+            assert_eq!(tool.tool_type, "function");
+            assert_eq!(tool.function.parameters, serde_json::json!({ "type": "object" }));
+        }
+
+        let binding = McpToolBinding::from(&server);
+        assert_eq!(binding.server_id, "filesystem");
+        assert_eq!(binding.command, "npx");
+        assert_eq!(binding.args, server.args);
+        assert!(!serde_json::to_string(&binding).unwrap().contains("secret"));
+
+        server.capabilities.clear();
+        let err = bind_server_tools(&server).await.unwrap_err();
+        assert!(err.to_string().contains("declares no capabilities"));
+    }
 }
@@ -10,11 +10,27 @@
 use crate::{
     McpGalaxyOrchestrator, InfrastructureConfig, Error, ExecutionResult, DeveloperRequest,
     BrowserFactory, SelfDestructChain, RevenueAnalytics,
+    memory_pressure::{MemoryPressureMonitor, MemoryPressureStatus, MemoryReading, MemorySource, PressureLevel},
+    planner::{self, CapabilityIndex, RequestPlanner, RuleBasedPlanner, ToolPlan},
+    security::{egress::{Detector, EgressScanner}, enforcer::get_security_enforcer},
+    shutdown::{CancellationToken, OrchestrationTracker, ShutdownReport},
 };
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+/// Tools served by browser automation rather than MCP servers
+const BROWSER_AUTOMATION_TOOLS: &[&str] = &[
+    "browser_screenshot",
+    "page_navigation",
+    "element_interaction",
+    "form_filling",
+    "content_extraction",
+];
+
+/// `execution_context` key holding a request's priority; lower priorities are shed first
+pub const PRIORITY_CONTEXT_KEY: &str = "priority";
+
 /// The unified Infrastructure Assassin orchestrator interface
 /// Zero external dependencies - pure Rust/WASM orchestration
 pub struct InfrastructureAssassinEngine {
@@ -28,6 +44,14 @@ pub struct InfrastructureAssassinEngine {
     pub analytics: Arc<Mutex<RevenueAnalytics>>,
     /// Active orchestration sessions (ephemeral)
     pub active_sessions: Arc<Mutex<Vec<Arc<Mutex<UnifiedSession>>>>>,
+    /// In-flight orchestrations, drained on graceful shutdown
+    pub orchestrations: OrchestrationTracker,
+    /// Scans browser tool outputs before they enter results
+    pub egress_scanner: Arc<EgressScanner>,
+    /// Chooses tools for requests that only describe the task
+    pub planner: Arc<dyn RequestPlanner>,
+    /// Refuses and sheds orchestrations as memory runs low
+    pub memory_pressure: MemoryPressureMonitor,
 }
 
 /// Unified orchestration session combining MCP tools and browser automation
@@ -83,10 +107,19 @@ pub struct BrowserConfig {
 impl InfrastructureAssassinEngine {
     /// Initialize the Infrastructure Assassin orchestration engine
     pub async fn init(config: InfrastructureConfig) -> Result<Self, Error> {
+        Self::init_with_detectors(config, Vec::new()).await
+    }
+
+    /// Initialize the engine with egress detectors beyond the built-in set
+    pub async fn init_with_detectors(
+        config: InfrastructureConfig,
+        egress_detectors: Vec<Box<dyn Detector>>,
+    ) -> Result<Self, Error> {
         log::info!("🚀 Initializing Infrastructure Assassin unified orchestration engine");
 
         // Initialize MCP Galaxy Orchestrator
-        let mut mcp_orchestrator = McpGalaxyOrchestrator::new();
+        let mut mcp_orchestrator = McpGalaxyOrchestrator::new()
+            .with_concurrency(config.tool_concurrency.clone());
         mcp_orchestrator.load_mcp_catalog("mcp-servers/").await?;
         log::info!("✅ MCP Galaxy Orchestrator loaded with {} servers",
                   mcp_orchestrator.server_catalog.len());
@@ -112,12 +145,24 @@ impl InfrastructureAssassinEngine {
             revenue_generated: 0.0,
         };
 
+        let egress_scanner = EgressScanner::with_detectors(config.security_boundaries.egress.clone(), egress_detectors);
+        log::info!("✅ Egress scanner active with detectors: {}", egress_scanner.detector_names().join(", "));
+
+        let planner = Self::configured_planner(&config)?;
+        log::info!("✅ Request planner: {}", planner.name());
+
+        let memory_pressure = MemoryPressureMonitor::new(config.memory_pressure.clone());
+
         let engine = Self {
             mcp_orchestrator: Arc::new(Mutex::new(mcp_orchestrator)),
             browser_factory: Arc::new(Mutex::new(browser_factory)),
             config,
             analytics: Arc::new(Mutex::new(analytics)),
             active_sessions: Arc::new(Mutex::new(Vec::new())),
+            orchestrations: OrchestrationTracker::new(),
+            egress_scanner: Arc::new(egress_scanner),
+            planner,
+            memory_pressure,
         };
 
         log::info!("🎉 Infrastructure Assassin unified orchestration engine ready");
@@ -128,6 +173,24 @@ impl InfrastructureAssassinEngine {
         Ok(engine)
     }
 
+    /// Plan requests without `required_tools` with `planner` instead of the configured one
+    pub fn with_planner(mut self, planner: Arc<dyn RequestPlanner>) -> Self {
+        self.planner = planner;
+        self
+    }
+
+    /// LLM planner when one is configured and compiled in, rule-based otherwise
+    fn configured_planner(config: &InfrastructureConfig) -> Result<Arc<dyn RequestPlanner>, Error> {
+        #[cfg(all(feature = "llm-planner", not(target_arch = "wasm32")))]
+        if let Some(llm) = &config.planning.llm {
+            return Ok(Arc::new(planner::LlmPlanner::http(llm.clone())?));
+        }
+        if config.planning.llm.is_some() {
+            log::warn!("⚠️ LLM planner configured but not compiled in (feature `llm-planner`); using rule-based planner");
+        }
+        Ok(Arc::new(RuleBasedPlanner::new()))
+    }
+
     /// Universal developer request orchestration - the core Infrastructure Assassin API
     /// This single method provides access to unlimited MCP tools + browser automation
     pub async fn orchestrate_universal_request(
@@ -136,16 +199,42 @@ impl InfrastructureAssassinEngine {
     ) -> Result<UnifiedExecutionResult, Error> {
         log::info!("🎛️ Orchestrating universal request: {}", request.description);
 
+        // Refuse new work once shutdown has started or memory runs low; the ticket deregisters on drop
+        self.memory_pressure.admit()?;
+        let priority = request.execution_context.get(PRIORITY_CONTEXT_KEY)
+            .and_then(|priority| priority.parse().ok())
+            .unwrap_or(0);
+        let ticket = self.orchestrations.begin_with_priority(priority)?;
+
+        // A request that names no tools gets a plan, or an error if none is convincing
+        let (request, plan) = if request.required_tools.is_empty() {
+            let index = self.capability_index().await;
+            let (request, plan) = planner::plan_request(
+                self.planner.as_ref(),
+                request,
+                &index,
+                self.config.planning.min_confidence,
+            ).await?;
+            (request, Some(plan))
+        } else {
+            (request, None)
+        };
+
         let start_time = std::time::Instant::now();
 
         // Create unified session
         let session = self.create_unified_session(&request).await?;
 
         // Orchestrate tools across MCP servers and browser automation
-        let result = self.execute_unified_orchestration(session.clone(), request).await?;
-
-        // Calculate performance and cost metrics
-        let execution_time = start_time.elapsed().as_secs_f64() * 1000.0; // ms
+        let result = match self.execute_unified_orchestration(session.clone(), request, start_time, ticket.token()).await {
+            Ok(result) => UnifiedExecutionResult { plan, ..result },
+            Err(e) => {
+                // Cancelled or failed sessions are still torn down
+                self.self_destruct_session(session.clone()).await?;
+                self.active_sessions.lock().await.retain(|s| !Arc::ptr_eq(s, &session));
+                return Err(e);
+            }
+        };
 
         // Track revenue disruption
         {
@@ -178,13 +267,14 @@ impl InfrastructureAssassinEngine {
         let available_tools = self.mcp_orchestrator.lock().await.tool_registry.values()
             .map(|tools| tools.len())
             .sum::<usize>();
+        let tool_concurrency = self.mcp_orchestrator.lock().await.concurrency.status();
 
-        let browser_sessions = {
-            let sessions = self.active_sessions.lock().await;
-            sessions.iter()
-                .map(|s| s.lock().await.browser_contexts.len())
-                .sum::<usize>()
-        };
+        // Snapshot the list so session locks are not taken while holding it
+        let sessions = self.active_sessions.lock().await.clone();
+        let mut browser_sessions = 0;
+        for session in &sessions {
+            browser_sessions += session.lock().await.browser_contexts.len();
+        }
 
         let analytics = self.analytics.lock().await.clone();
 
@@ -196,9 +286,93 @@ impl InfrastructureAssassinEngine {
             total_revenue: analytics.revenue_generated,
             aws_cost_disrupted: analytics.aws_cost_saved,
             productivity_multiplier: analytics.productivity_gain,
+            tool_concurrency,
+            memory_pressure: self.memory_pressure.status(),
+        })
+    }
+
+    /// Apply a memory reading: refuse new sessions at elevated pressure and,
+    /// at critical pressure, shed sessions and checkpoint analytics
+    pub async fn check_memory_pressure(&self, reading: MemoryReading) -> PressureLevel {
+        let level = self.memory_pressure.observe(reading, &self.orchestrations);
+        if level == PressureLevel::Critical {
+            self.checkpoint_analytics("Memory pressure").await;
+        }
+        level
+    }
+
+    /// Poll `source` until shutdown, applying each reading
+    pub async fn watch_memory_pressure(self: Arc<Self>, source: Arc<dyn MemorySource>) {
+        if !self.memory_pressure.config().enabled {
+            return;
+        }
+        let interval = self.memory_pressure.config().poll_interval_ms;
+        log::info!("🧠 Watching memory pressure every {}ms", interval);
+
+        while !self.orchestrations.is_shutting_down() {
+            if let Some(reading) = source.read() {
+                self.check_memory_pressure(reading).await;
+            }
+            #[cfg(target_arch = "wasm32")]
+            gloo_timers::future::TimeoutFuture::new(interval as u32).await;
+            #[cfg(not(target_arch = "wasm32"))]
+            tokio::time::sleep(std::time::Duration::from_millis(interval)).await;
+        }
+    }
+
+    /// Log the analytics counters so they survive the tab or process going away
+    async fn checkpoint_analytics(&self, occasion: &str) {
+        let analytics = self.analytics.lock().await.clone();
+        log::info!("📊 {} analytics checkpoint: {} orchestrations, ${:.2} AWS cost saved",
+                  occasion, analytics.tool_orchestrations, analytics.aws_cost_saved);
+    }
+
+    /// Every tool the MCP catalog and browser automation provide
+    pub async fn capability_index(&self) -> CapabilityIndex {
+        let mcp_tools: Vec<String> = self.mcp_orchestrator.lock().await.server_catalog.values()
+            .flat_map(|server| server.capabilities.iter().cloned())
+            .collect();
+        CapabilityIndex::new(mcp_tools.into_iter().chain(BROWSER_AUTOMATION_TOOLS.iter().map(|tool| tool.to_string())))
+    }
+
+    /// Status plus the tool catalog and current load, as advertised to a federation router
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn capability_summary(&self) -> Result<crate::federation::CapabilitySummary, Error> {
+        let status = self.get_orchestration_status().await?;
+        let tools = self.capability_index().await.tools().map(str::to_string).collect();
+
+        Ok(crate::federation::CapabilitySummary {
+            status,
+            tools,
+            active_orchestrations: self.orchestrations.active_count(),
         })
     }
 
+    /// Graceful shutdown - stop accepting orchestrations, let running ones
+    /// finish within `drain_timeout`, then cancel and destroy the rest
+    pub async fn shutdown(&self, drain_timeout: std::time::Duration) -> Result<ShutdownReport, Error> {
+        log::warn!("🛑 Graceful shutdown requested - draining {} orchestrations (timeout {}ms)",
+                  self.orchestrations.active_count(), drain_timeout.as_millis());
+
+        let report = self.orchestrations.drain(drain_timeout).await;
+
+        // Give cancelled orchestrations a moment to observe their tokens and clean up
+        let grace = std::time::Instant::now() + std::time::Duration::from_millis(100);
+        while self.orchestrations.active_count() > 0 && std::time::Instant::now() < grace {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        // Destroy anything that did not clean up after itself
+        self.emergency_cleanup().await?;
+
+        // Flush analytics so the final counters survive the shutdown
+        self.checkpoint_analytics("Final").await;
+
+        log::info!("✅ Shutdown complete - {} completed, {} cancelled in {}ms",
+                  report.completed, report.cancelled, report.duration_ms);
+        Ok(report)
+    }
+
     /// Emergency cleanup - destroy all active sessions
     pub async fn emergency_cleanup(&self) -> Result<(), Error> {
         log::warn!("🚨 EMERGENCY CLEANUP ACTIVATED - Destroying all sessions");
@@ -257,11 +431,14 @@ impl InfrastructureAssassinEngine {
         Ok(session)
     }
 
-    /// Execute unified orchestration using both MCP and browser tools
+    /// Execute unified orchestration using both MCP and browser tools, timing
+    /// the session from `start_time`
     async fn execute_unified_orchestration(
         &self,
         session: Arc<Mutex<UnifiedSession>>,
         request: DeveloperRequest,
+        start_time: std::time::Instant,
+        cancellation: &CancellationToken,
     ) -> Result<UnifiedExecutionResult, Error> {
         let mut session_lock = session.lock().await;
 
@@ -288,6 +465,7 @@ impl InfrastructureAssassinEngine {
         }
 
         // Phase 2: Execute MCP orchestration if needed
+        cancellation.check()?;
         let mcp_results = if !mcp_tools_needed.is_empty() {
             let mcp_orchestrator = self.mcp_orchestrator.lock().await;
             session_lock.mcp_servers = mcp_orchestrator.server_catalog.keys()
@@ -310,6 +488,7 @@ impl InfrastructureAssassinEngine {
         };
 
         // Phase 3: Execute browser automation if needed
+        cancellation.check()?;
         let browser_results = if !browser_tools_needed.is_empty() {
             let browser_factory = self.browser_factory.lock().await;
 
@@ -334,8 +513,10 @@ impl InfrastructureAssassinEngine {
         };
 
         // Phase 4: Combine and format results
+        cancellation.check()?;
         let mut combined_output = String::new();
         let mut total_tools_used = Vec::new();
+        let mut violations = 0;
 
         if let Some(mcp_result) = mcp_results {
             combined_output.push_str(&format!("MCP Results:\n{}\n\n", mcp_result.output));
@@ -344,12 +525,31 @@ impl InfrastructureAssassinEngine {
         }
 
         if let Some(browser_result) = browser_results {
-            combined_output.push_str(&format!("Browser Automation Results:\n{}\n\n", browser_result.output));
+            // Page content is untrusted; scan it before it reaches results and analytics
+            let resource = format!("browser:{}", browser_result.tools_used.join(","));
+            let scan = self.egress_scanner.scan(&browser_result.output);
+            if !scan.findings.is_empty() {
+                violations += scan.findings.len();
+                match get_security_enforcer() {
+                    Ok(mut enforcer) => enforcer.audit_egress(session_lock.session_id, &resource, &scan),
+                    Err(_) => log::warn!("🛡️ {} egress findings in {} not audited: security enforcer not initialized",
+                                         scan.findings.len(), resource),
+                }
+            }
+
+            match scan.output {
+                Some(output) => combined_output.push_str(&format!("Browser Automation Results:\n{}\n\n", output)),
+                None => {
+                    log::warn!("🚫 Egress policy blocked output of {}", resource);
+                    combined_output.push_str("Browser Automation Results:\n[blocked by egress policy]\n\n");
+                }
+            }
             total_tools_used.extend(browser_result.tools_used);
         }
 
         combined_output.push_str(&format!("Session completed in ephemeral execution."));
         total_tools_used.dedup();
+        session_lock.resource_usage.execution_duration_ms = start_time.elapsed().as_millis() as u64;
 
         Ok(UnifiedExecutionResult {
             session_id: session_lock.session_id,
@@ -358,22 +558,17 @@ impl InfrastructureAssassinEngine {
             mcp_servers_used: session_lock.mcp_servers.len(),
             browser_sessions_used: session_lock.browser_contexts.len(),
             tools_used: total_tools_used,
-            execution_time_ms: start_time.elapsed().as_secs_f64() as u64,
+            execution_time_ms: session_lock.resource_usage.execution_duration_ms,
             cost_saved_vs_aws: 12.0, // $12 equivalent AWS cost
             resource_efficiency: session_lock.resource_usage.efficiency_score,
+            violations,
+            plan: None,
         })
     }
 
     /// Check if tool requires browser automation
     fn is_browser_automation_tool(&self, tool_name: &str) -> bool {
-        let browser_tools = vec![
-            "browser_screenshot",
-            "page_navigation",
-            "element_interaction",
-            "form_filling",
-            "content_extraction",
-        ];
-        browser_tools.contains(&tool_name)
+        BROWSER_AUTOMATION_TOOLS.contains(&tool_name)
     }
 
     /// Self-destruct session and cleanup all resources
@@ -422,6 +617,12 @@ pub struct UnifiedExecutionResult {
     pub execution_time_ms: u64,
     pub cost_saved_vs_aws: f64, // $ equivalent AWS cost
     pub resource_efficiency: f32,
+    /// Egress scanner findings in this result's browser outputs
+    #[serde(default)]
+    pub violations: usize,
+    /// Tool plan and its confidence, when the tools were planned from the description
+    #[serde(default)]
+    pub plan: Option<ToolPlan>,
 }
 
 /// Unified orchestration status for monitoring and analytics
@@ -434,6 +635,12 @@ pub struct UnifiedStatus {
     pub total_revenue: f64,
     pub aws_cost_disrupted: f64,
     pub productivity_multiplier: f64,
+    /// Adaptive tool call limits per MCP server
+    #[serde(default)]
+    pub tool_concurrency: Vec<crate::tools::concurrency::ServerConcurrency>,
+    /// Memory pressure level and sessions shed to relieve it
+    #[serde(default)]
+    pub memory_pressure: MemoryPressureStatus,
 }
 
 impl Default for BrowserConfig {
@@ -448,3 +655,63 @@ impl Default for BrowserConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_execution_time_covers_the_session() {
+        let dir = std::env::temp_dir().join(format!("mcp-catalog-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("filesystem.json"), r#"{
+            "id": "filesystem",
+            "name": "File System MCP Server",
+            "command": "npx",
+            "capabilities": ["read_file"]
+        }"#).unwrap();
+
+        let engine = InfrastructureAssassinEngine::init(InfrastructureConfig::default()).await.unwrap();
+        engine.mcp_orchestrator.lock().await.load_mcp_catalog(dir.to_str().unwrap()).await.unwrap();
+
+        let request = DeveloperRequest {
+            description: "read a file".to_string(),
+            required_tools: vec!["read_file".to_string()],
+            execution_context: Default::default(),
+        };
+        let started = std::time::Instant::now();
+        let result = engine.orchestrate_universal_request(request).await.unwrap();
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+
+        // Simulated tool calls take 10ms; the session cannot outlast the request
+        assert!(result.execution_time_ms >= 10, "execution_time_ms = {}", result.execution_time_ms);
+        assert!(result.execution_time_ms <= elapsed_ms);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_status_counts_browser_contexts_across_sessions() {
+        let engine = InfrastructureAssassinEngine::init(InfrastructureConfig::default()).await.unwrap();
+        let request = DeveloperRequest {
+            description: "screenshot two pages".to_string(),
+            required_tools: vec!["browser_screenshot".to_string()],
+            execution_context: Default::default(),
+        };
+
+        for contexts in [1, 2] {
+            let session = engine.create_unified_session(&request).await.unwrap();
+            let mut session = session.lock().await;
+            for _ in 0..contexts {
+                session.browser_contexts.push(BrowserSession {
+                    session_id: session.session_id,
+                    browser_config: Default::default(),
+                    automation_tools: request.required_tools.clone(),
+                    self_destruct_timer: None,
+                });
+            }
+        }
+
+        assert_eq!(engine.get_orchestration_status().await.unwrap().browser_sessions_active, 3);
+    }
+}
@@ -8,8 +8,13 @@ pub struct GatewayConfig {
     pub rate_limit: RateLimitConfig,
     pub cache: CacheConfig,
     pub routing: RoutingConfig,
+    pub bandwidth: BandwidthConfig,
+    #[serde(default)]
+    pub timeout: TimeoutConfig,
     pub tls: Option<TlsConfig>,
     pub observability: ObservabilityConfig,
+    #[serde(default)]
+    pub access_log: AccessLogConfig,
 }
 
 impl Default for GatewayConfig {
@@ -19,8 +24,11 @@ impl Default for GatewayConfig {
             rate_limit: RateLimitConfig::default(),
             cache: CacheConfig::default(),
             routing: RoutingConfig::default(),
+            bandwidth: BandwidthConfig::default(),
+            timeout: TimeoutConfig::default(),
             tls: None,
             observability: ObservabilityConfig::default(),
+            access_log: AccessLogConfig::default(),
         }
     }
 }
@@ -96,20 +104,112 @@ impl Default for CacheConfig {
     }
 }
 
+/// Egress bandwidth shaping configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandwidthConfig {
+    pub enabled: bool,
+    /// Limit applied per principal when no route prefix matches
+    pub default_limit: Option<BandwidthLimit>,
+    /// Per-route limits keyed by path prefix (longest prefix wins)
+    pub route_limits: HashMap<String, BandwidthLimit>,
+    /// Principals (`user:<id>` or `ip:<addr>`) that are never shaped
+    pub exempt_principals: Vec<String>,
+}
+
+impl Default for BandwidthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default_limit: None,
+            route_limits: HashMap::new(),
+            exempt_principals: vec![],
+        }
+    }
+}
+
+/// Token-bucket byte rate limit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandwidthLimit {
+    pub bytes_per_second: u64,
+    pub burst_bytes: u64,
+}
+
+/// Upstream request timeout configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeoutConfig {
+    pub enabled: bool,
+    /// Timeout applied when no route prefix matches
+    pub default_timeout_ms: u64,
+    /// Per-route timeouts keyed by path prefix (longest prefix wins)
+    pub route_timeouts: HashMap<String, u64>,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            default_timeout_ms: 30_000,
+            route_timeouts: HashMap::new(),
+        }
+    }
+}
+
 /// Routing configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RoutingConfig {
     pub routes: Vec<Route>,
+    /// Evaluated in order before `routes`; the first match picks the upstream
+    #[serde(default)]
+    pub rules: Vec<RoutingRule>,
     pub default_upstream: Option<String>,
     pub load_balancing: LoadBalancingStrategy,
+    #[serde(default)]
+    pub retry_budget: RetryBudgetConfig,
 }
 
 impl Default for RoutingConfig {
     fn default() -> Self {
         Self {
             routes: vec![],
+            rules: vec![],
             default_upstream: Some("http://localhost:8081".to_string()),
             load_balancing: LoadBalancingStrategy::RoundRobin,
+            retry_budget: RetryBudgetConfig::default(),
+        }
+    }
+}
+
+/// Retry budget for idempotent upstream requests, following Linkerd2
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryBudgetConfig {
+    pub enabled: bool,
+    /// Retries allowed as a fraction of the requests seen within the window
+    pub retry_ratio: f32,
+    /// Retries allowed per second regardless of the ratio, so low-traffic routes can still retry
+    pub min_retries_per_second: u32,
+    /// Window over which requests and retries are counted
+    pub ttl_seconds: u64,
+    /// Retries of a single request
+    pub max_retries_per_request: u32,
+    /// Largest request body buffered so it can be resent; requests with a
+    /// larger or streamed body are sent once
+    #[serde(default = "default_max_retry_body_bytes")]
+    pub max_retry_body_bytes: u64,
+}
+
+fn default_max_retry_body_bytes() -> u64 {
+    64 * 1024
+}
+
+impl Default for RetryBudgetConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            retry_ratio: 0.2,
+            min_retries_per_second: 10,
+            ttl_seconds: 10,
+            max_retries_per_request: 3,
+            max_retry_body_bytes: default_max_retry_body_bytes(),
         }
     }
 }
@@ -124,6 +224,19 @@ pub struct Route {
     pub timeout_ms: Option<u64>,
 }
 
+/// Sends requests matching all of its conditions to `target`, keeping their path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutingRule {
+    /// Headers that must be present with exactly these values; names are case-insensitive
+    #[serde(default)]
+    pub match_headers: HashMap<String, String>,
+    /// Matches every path when unset
+    #[serde(default)]
+    pub match_path_prefix: Option<String>,
+    /// Upstream base URL, e.g. `http://staging-backend:8080`
+    pub target: String,
+}
+
 /// Load balancing strategies
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum LoadBalancingStrategy {
@@ -138,7 +251,16 @@ pub enum LoadBalancingStrategy {
 pub struct TlsConfig {
     pub cert_path: String,
     pub key_path: String,
+    /// CA for verifying client certificates; changing it requires a restart
     pub ca_cert_path: Option<String>,
+    /// Seconds between checks for a rotated certificate and key; 0 disables
+    /// reloading, so rotation then requires a restart
+    #[serde(default = "default_tls_reload_interval_secs")]
+    pub reload_interval_secs: u64,
+}
+
+fn default_tls_reload_interval_secs() -> u64 {
+    30
 }
 
 /// Observability configuration
@@ -161,6 +283,36 @@ impl Default for ObservabilityConfig {
     }
 }
 
+/// Access log configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessLogConfig {
+    pub enabled: bool,
+    /// Header carrying the request id, read from clients and echoed back
+    pub request_id_header: String,
+    pub level: AccessLogLevel,
+}
+
+impl Default for AccessLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            request_id_header: "x-request-id".to_string(),
+            level: AccessLogLevel::Info,
+        }
+    }
+}
+
+/// Level access log events are emitted at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
 /// Configuration builder
 pub struct ConfigBuilder {
     config: GatewayConfig,
@@ -193,6 +345,16 @@ impl ConfigBuilder {
         self
     }
 
+    pub fn with_bandwidth(mut self, bandwidth: BandwidthConfig) -> Self {
+        self.config.bandwidth = bandwidth;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: TimeoutConfig) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+
     pub fn with_tls(mut self, tls: TlsConfig) -> Self {
         self.config.tls = Some(tls);
         self
@@ -203,6 +365,11 @@ impl ConfigBuilder {
         self
     }
 
+    pub fn with_access_log(mut self, access_log: AccessLogConfig) -> Self {
+        self.config.access_log = access_log;
+        self
+    }
+
     pub fn build(self) -> GatewayConfig {
         self.config
     }
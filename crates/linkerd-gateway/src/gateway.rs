@@ -6,7 +6,7 @@ use std::{
 };
 
 use hyper::{
-    body::Bytes,
+    body::{Bytes, HttpBody},
     client::HttpConnector,
     http::{HeaderMap, Method, StatusCode, Uri, Version},
     Body, Client, Request, Response,
@@ -16,8 +16,9 @@ use tracing::{info, warn, error, instrument};
 
 use crate::{
     config::{GatewayConfig, Route},
-    metrics::MetricsCollector,
-    routing::Router,
+    metrics::{MetricsCollector, UNMATCHED_ROUTE},
+    middleware::access_log::RoutedUpstream,
+    routing::{is_idempotent, Router},
 };
 
 /// Main gateway service implementing Linkerd2-proxy patterns
@@ -54,13 +55,14 @@ impl GatewayService {
         mut req: Request<Body>,
     ) -> Result<Response<Body>, hyper::Error> {
         let start_time = Instant::now();
+        let method = req.method().clone();
 
         // Find matching route
-        let route = match self.router.find_route(req.uri().path(), req.method()) {
+        let route = match self.router.find_route(req.uri().path(), req.method(), req.headers()) {
             Some(route) => route,
             None => {
                 warn!("No route found for {} {}", req.method(), req.uri().path());
-                self.metrics.record_request(StatusCode::NOT_FOUND, start_time.elapsed());
+                self.metrics.record_request(UNMATCHED_ROUTE, &method, StatusCode::NOT_FOUND, start_time.elapsed());
                 return Ok(self.create_error_response(
                     StatusCode::NOT_FOUND,
                     "Route not found",
@@ -68,12 +70,14 @@ impl GatewayService {
             }
         };
 
+        let _in_flight = self.metrics.track_in_flight(&route.path);
+
         // Build upstream URI
         let upstream_uri = match self.build_upstream_uri(&route, &req) {
             Ok(uri) => uri,
             Err(err) => {
                 error!("Failed to build upstream URI: {}", err);
-                self.metrics.record_request(StatusCode::BAD_GATEWAY, start_time.elapsed());
+                self.metrics.record_request(&route.path, &method, StatusCode::BAD_GATEWAY, start_time.elapsed());
                 return Ok(self.create_error_response(
                     StatusCode::BAD_GATEWAY,
                     "Invalid upstream configuration",
@@ -86,13 +90,14 @@ impl GatewayService {
         self.add_upstream_headers(&mut req, &route);
 
         // Forward request to upstream
-        match self.client.request(req).await {
+        match self.forward(req, &route.upstream).await {
             Ok(mut response) => {
                 // Add gateway headers
                 self.add_gateway_headers(&mut response);
+                response.extensions_mut().insert(RoutedUpstream(route.upstream.clone()));
 
                 // Record metrics
-                self.metrics.record_request(response.status(), start_time.elapsed());
+                self.metrics.record_request(&route.path, &method, response.status(), start_time.elapsed());
 
                 info!(
                     "Request completed: {} {} -> {}",
@@ -105,11 +110,54 @@ impl GatewayService {
             }
             Err(err) => {
                 error!("Upstream request failed: {}", err);
-                self.metrics.record_request(StatusCode::BAD_GATEWAY, start_time.elapsed());
-                Ok(self.create_error_response(
+                self.metrics.record_request(&route.path, &method, StatusCode::BAD_GATEWAY, start_time.elapsed());
+                let mut response = self.create_error_response(
                     StatusCode::BAD_GATEWAY,
                     "Upstream service unavailable",
-                ))
+                );
+                response.extensions_mut().insert(RoutedUpstream(route.upstream.clone()));
+                Ok(response)
+            }
+        }
+    }
+
+    /// Send a request upstream. Idempotent requests whose upstream connection
+    /// fails are retried while the retry budget allows; others, and those
+    /// whose body is streamed or larger than the buffering limit, are sent once.
+    async fn forward(&self, req: Request<Body>, upstream: &str) -> Result<Response<Body>, hyper::Error> {
+        let budget = self.router.retry_budget();
+        budget.deposit();
+        if !budget.is_enabled() || !is_idempotent(req.method()) {
+            return self.client.request(req).await;
+        }
+        // The size is exact for a `Content-Length` or empty body, unknown when chunked
+        if req.body().size_hint().exact().is_none_or(|len| len > budget.max_retry_body_bytes()) {
+            return self.client.request(req).await;
+        }
+
+        // Buffer the body so the request can be sent again
+        let (parts, body) = req.into_parts();
+        let body = hyper::body::to_bytes(body).await?;
+        let mut retries = 0;
+        loop {
+            let mut attempt = Request::new(Body::from(body.clone()));
+            *attempt.method_mut() = parts.method.clone();
+            *attempt.uri_mut() = parts.uri.clone();
+            *attempt.version_mut() = parts.version;
+            *attempt.headers_mut() = parts.headers.clone();
+
+            match self.client.request(attempt).await {
+                Err(err) if retries < budget.max_retries_per_request() => {
+                    if !budget.withdraw() {
+                        warn!("Retry budget exhausted for {}: {}", upstream, err);
+                        self.metrics.record_upstream_retry(upstream, "budget_exhausted");
+                        return Err(err);
+                    }
+                    retries += 1;
+                    warn!("Retrying {} {} (retry {}): {}", parts.method, parts.uri, retries, err);
+                    self.metrics.record_upstream_retry(upstream, "retried");
+                }
+                result => return result,
             }
         }
     }
@@ -118,18 +166,22 @@ impl GatewayService {
     fn build_upstream_uri(&self, route: &Route, req: &Request<Body>) -> Result<Uri, Box<dyn std::error::Error>> {
         let mut upstream_url = route.upstream.clone();
 
-        // Replace path parameters
+        // Wildcard routes forward the rest of the path, in place of the
+        // upstream's own `/*` or appended to it
+        if let Some(prefix) = route.path.strip_suffix("/*") {
+            let remaining_path = req.uri().path().strip_prefix(prefix).unwrap_or_default();
+            upstream_url = if upstream_url.contains("/*") {
+                upstream_url.replace("/*", remaining_path)
+            } else {
+                format!("{}{}", upstream_url.trim_end_matches('/'), remaining_path)
+            };
+        }
+
         if let Some(query) = req.uri().query() {
             upstream_url.push('?');
             upstream_url.push_str(query);
         }
 
-        // Handle path replacement for proxy-style routing
-        if route.path.ends_with("/*") {
-            let remaining_path = &req.uri().path()[route.path.len() - 1..];
-            upstream_url = upstream_url.replace("/*", remaining_path);
-        }
-
         Uri::try_from(upstream_url).map_err(Into::into)
     }
 
@@ -146,7 +198,10 @@ impl GatewayService {
         headers.insert("X-Gateway", "Linkerd-AutoAgents".parse().unwrap());
         headers.insert("X-Forwarded-Host", req.uri().host().unwrap_or("unknown").parse().unwrap());
         headers.insert("X-Forwarded-Proto", "http".parse().unwrap());
-        headers.insert("X-Request-ID", uuid::Uuid::new_v4().to_string().parse().unwrap());
+        // Keep the id the access log assigned so upstream logs correlate with ours
+        if !headers.contains_key("X-Request-ID") {
+            headers.insert("X-Request-ID", uuid::Uuid::new_v4().to_string().parse().unwrap());
+        }
     }
 
     /// Add gateway headers to response
@@ -207,3 +262,135 @@ impl Clone for GatewayService {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+
+    use hyper::service::{make_service_fn, service_fn};
+
+    use crate::config::RoutingConfig;
+
+    /// Upstream answering `/missing` with 404, `/slow` after 300ms and the rest with 200
+    async fn upstream() -> SocketAddr {
+        let make_svc = make_service_fn(|_conn| async {
+            Ok::<_, Infallible>(service_fn(|req: Request<Body>| async move {
+                let status = match req.uri().path() {
+                    "/missing" => StatusCode::NOT_FOUND,
+                    "/slow" => {
+                        tokio::time::sleep(Duration::from_millis(300)).await;
+                        StatusCode::OK
+                    }
+                    _ => StatusCode::OK,
+                };
+                Ok::<_, Infallible>(Response::builder().status(status).body(Body::empty()).unwrap())
+            }))
+        });
+        let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    /// Collector shared by the tests, as its metrics register globally
+    fn shared_metrics() -> MetricsCollector {
+        static METRICS: std::sync::OnceLock<MetricsCollector> = std::sync::OnceLock::new();
+        METRICS.get_or_init(MetricsCollector::new).clone()
+    }
+
+    fn route(path: &str, upstream: String) -> Route {
+        Route {
+            path: path.to_string(),
+            upstream,
+            methods: vec![],
+            headers: HashMap::new(),
+            timeout_ms: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_only_small_known_size_bodies_are_retried() {
+        // Nothing listens here once the listener is dropped, so every attempt fails to connect
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let mut config = GatewayConfig {
+            routing: RoutingConfig {
+                routes: vec![route("/api/down", format!("http://{}/down", closed))],
+                default_upstream: None,
+                ..RoutingConfig::default()
+            },
+            ..GatewayConfig::default()
+        };
+        config.routing.retry_budget.max_retry_body_bytes = 16;
+        let metrics = shared_metrics();
+        let upstream = config.routing.routes[0].upstream.clone();
+        let gateway = GatewayService::new(config, metrics.clone());
+        let retries = || metrics.get_upstream_retries(&upstream, "retried");
+        let put = |body: Body| Request::put("/api/down").body(body).unwrap();
+
+        let status = gateway.clone().oneshot(put(Body::from("small"))).await.unwrap().status();
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+        assert_eq!(retries(), 3);
+
+        // Too large to buffer
+        gateway.clone().oneshot(put(Body::from(vec![b'x'; 17]))).await.unwrap();
+        // Streamed, so its size is unknown
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move { sender.send_data(Bytes::from_static(b"chunk")).await });
+        gateway.clone().oneshot(put(body)).await.unwrap();
+        assert_eq!(retries(), 3);
+    }
+
+    async fn send(gateway: &GatewayService, method: Method, path: &str) -> StatusCode {
+        let req = Request::builder().method(method).uri(path).body(Body::empty()).unwrap();
+        gateway.clone().oneshot(req).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_metrics_labeled_by_route_method_and_status_class() {
+        let addr = upstream().await;
+        let config = GatewayConfig {
+            routing: RoutingConfig {
+                routes: vec![
+                    route("/api/agents", format!("http://{}/agents", addr)),
+                    route("/api/missing", format!("http://{}/missing", addr)),
+                    route("/api/slow", format!("http://{}/slow", addr)),
+                ],
+                default_upstream: None,
+                ..RoutingConfig::default()
+            },
+            ..GatewayConfig::default()
+        };
+        let metrics = shared_metrics();
+        let gateway = GatewayService::new(config, metrics.clone());
+
+        assert_eq!(send(&gateway, Method::GET, "/api/agents").await, StatusCode::OK);
+        assert_eq!(send(&gateway, Method::GET, "/api/agents").await, StatusCode::OK);
+        assert_eq!(send(&gateway, Method::POST, "/api/agents").await, StatusCode::OK);
+        assert_eq!(send(&gateway, Method::GET, "/api/missing").await, StatusCode::NOT_FOUND);
+        assert_eq!(send(&gateway, Method::GET, "/nowhere").await, StatusCode::NOT_FOUND);
+
+        // The in-flight gauge covers a request only while it is being served
+        let slow = tokio::spawn({
+            let gateway = gateway.clone();
+            async move { send(&gateway, Method::GET, "/api/slow").await }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(metrics.get_requests_in_flight("/api/slow"), 1);
+        assert_eq!(slow.await.unwrap(), StatusCode::OK);
+        assert_eq!(metrics.get_requests_in_flight("/api/slow"), 0);
+
+        let scraped = metrics.gather_metrics().unwrap();
+        for sample in [
+            r#"gateway_http_requests_total{method="GET",route="/api/agents",status_class="2xx"} 2"#,
+            r#"gateway_http_requests_total{method="POST",route="/api/agents",status_class="2xx"} 1"#,
+            r#"gateway_http_requests_total{method="GET",route="/api/missing",status_class="4xx"} 1"#,
+            r#"gateway_http_requests_total{method="GET",route="unmatched",status_class="4xx"} 1"#,
+            r#"gateway_http_request_duration_seconds_count{method="GET",route="/api/agents",status_class="2xx"} 2"#,
+            r#"gateway_http_requests_in_flight{route="/api/slow"} 0"#,
+        ] {
+            assert!(scraped.contains(sample), "missing {} in:\n{}", sample, scraped);
+        }
+        assert_eq!(metrics.get_requests_by_status("/api/agents", &Method::GET, StatusCode::NO_CONTENT), 2);
+    }
+}
@@ -4,8 +4,10 @@ pub mod middleware;
 pub mod metrics;
 pub mod routing;
 pub mod security;
+pub mod tls;
 
 use std::net::SocketAddr;
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tower::ServiceBuilder;
 use tower_http::{
@@ -15,10 +17,11 @@ use tower_http::{
 };
 
 use crate::{
-    config::GatewayConfig,
+    config::{GatewayConfig, TlsConfig},
     gateway::GatewayService,
-    middleware::{AuthMiddleware, RateLimitMiddleware, CacheMiddleware},
+    middleware::{AccessLogMiddleware, AuthMiddleware, BandwidthMiddleware, RateLimitMiddleware, CacheMiddleware, TimeoutMiddleware},
     metrics::MetricsCollector,
+    tls::TlsTerminator,
 };
 
 /// Main gateway structure implementing Linkerd2-proxy patterns
@@ -34,38 +37,68 @@ impl LinkerdGateway {
         Self { config, metrics }
     }
 
-    /// Start the gateway server
+    /// Start the gateway server, terminating TLS when `config.tls` is set
+    /// and serving plaintext otherwise
     pub async fn serve(self, addr: SocketAddr) -> Result<(), Box<dyn std::error::Error>> {
         tracing::info!("Starting Linkerd Gateway on {}", addr);
 
+        let tls = match &self.config.tls {
+            Some(tls_config) => {
+                let terminator = TlsTerminator::new(tls_config)?;
+                if tls_config.reload_interval_secs > 0 {
+                    terminator.spawn_reloader(Duration::from_secs(tls_config.reload_interval_secs));
+                }
+                tracing::info!("Terminating TLS with certificate {}", tls_config.cert_path);
+                Some(terminator)
+            }
+            None => None,
+        };
+
         let listener = TcpListener::bind(addr).await?;
         let gateway_service = GatewayService::new(self.config.clone(), self.metrics.clone());
 
         // Build middleware stack inspired by Linkerd2-proxy
         let service = ServiceBuilder::new()
             .layer(TraceLayer::new_for_http())
+            .layer(AccessLogMiddleware::new(self.config.access_log.clone()))
             .layer(CompressionLayer::new())
             .layer(CorsLayer::permissive())
             .layer(AuthMiddleware::new(self.config.auth.clone()))
+            .layer(BandwidthMiddleware::new(self.config.bandwidth.clone()).with_metrics(self.metrics.clone()))
             .layer(RateLimitMiddleware::new(self.config.rate_limit.clone()))
             .layer(CacheMiddleware::new(self.config.cache.clone()))
+            .layer(TimeoutMiddleware::new(self.config.timeout.clone()).with_metrics(self.metrics.clone()))
             .service(gateway_service);
 
         loop {
-            let (stream, _) = listener.accept().await?;
+            let (stream, peer) = listener.accept().await?;
             let service = service.clone();
+            let tls = tls.clone();
 
             tokio::spawn(async move {
-                if let Err(err) = hyper::server::conn::Http::new()
-                    .serve_connection(stream, service)
-                    .await
-                {
+                let served = match tls {
+                    Some(tls) => match tls.accept(stream).await {
+                        Ok(stream) => hyper::server::conn::Http::new().serve_connection(stream, service).await,
+                        Err(err) => {
+                            tracing::warn!("TLS handshake with {} failed: {}", peer, err);
+                            return;
+                        }
+                    },
+                    None => hyper::server::conn::Http::new().serve_connection(stream, service).await,
+                };
+                if let Err(err) = served {
                     tracing::error!("Error serving connection: {}", err);
                 }
             });
         }
     }
 
+    /// Start the gateway server terminating TLS with `tls`
+    pub async fn serve_tls(mut self, addr: SocketAddr, tls: TlsConfig) -> Result<(), Box<dyn std::error::Error>> {
+        self.config.tls = Some(tls);
+        self.serve(addr).await
+    }
+
     /// Get metrics collector
     pub fn metrics(&self) -> &MetricsCollector {
         &self.metrics
@@ -109,6 +142,21 @@ impl GatewayBuilder {
         self
     }
 
+    pub fn with_bandwidth(mut self, bandwidth: config::BandwidthConfig) -> Self {
+        self.config.bandwidth = bandwidth;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: config::TimeoutConfig) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+
+    pub fn with_access_log(mut self, access_log: config::AccessLogConfig) -> Self {
+        self.config.access_log = access_log;
+        self
+    }
+
     pub fn build(self) -> LinkerdGateway {
         LinkerdGateway::new(self.config)
     }
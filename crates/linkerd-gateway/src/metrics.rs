@@ -1,24 +1,41 @@
 use std::sync::Arc;
 use std::time::Duration;
 
-use hyper::http::StatusCode;
+use hyper::http::{Method, StatusCode};
 use prometheus::{
     register_counter_vec, register_histogram_vec, register_gauge_vec,
-    CounterVec, HistogramVec, GaugeVec, Encoder, TextEncoder,
+    CounterVec, HistogramVec, Gauge, GaugeVec, Encoder, TextEncoder,
 };
 use tokio::sync::RwLock;
 use tracing::info;
 
+/// Route label of requests that matched no route
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Status class label of a response status, e.g. `2xx`
+pub fn status_class(status: StatusCode) -> &'static str {
+    match status.as_u16() {
+        100..=199 => "1xx",
+        200..=299 => "2xx",
+        300..=399 => "3xx",
+        400..=499 => "4xx",
+        _ => "5xx",
+    }
+}
+
 /// Metrics collector for the gateway
 #[derive(Clone)]
 pub struct MetricsCollector {
     http_requests_total: CounterVec,
     http_request_duration: HistogramVec,
+    http_requests_in_flight: GaugeVec,
     active_connections: GaugeVec,
     cache_hits_total: CounterVec,
     cache_misses_total: CounterVec,
     rate_limit_exceeded_total: CounterVec,
     upstream_errors_total: CounterVec,
+    bandwidth_bytes_total: CounterVec,
+    upstream_retries_total: CounterVec,
 }
 
 impl MetricsCollector {
@@ -27,16 +44,22 @@ impl MetricsCollector {
         let http_requests_total = register_counter_vec!(
             "gateway_http_requests_total",
             "Total number of HTTP requests processed",
-            &["method", "status", "path"]
+            &["route", "method", "status_class"]
         ).unwrap();
 
         let http_request_duration = register_histogram_vec!(
             "gateway_http_request_duration_seconds",
             "HTTP request duration in seconds",
-            &["method", "status"],
+            &["route", "method", "status_class"],
             vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 2.5, 5.0, 10.0]
         ).unwrap();
 
+        let http_requests_in_flight = register_gauge_vec!(
+            "gateway_http_requests_in_flight",
+            "Number of requests currently being served",
+            &["route"]
+        ).unwrap();
+
         let active_connections = register_gauge_vec!(
             "gateway_active_connections",
             "Number of active connections",
@@ -67,31 +90,59 @@ impl MetricsCollector {
             &["upstream", "error_type"]
         ).unwrap();
 
+        let bandwidth_bytes_total = register_counter_vec!(
+            "gateway_bandwidth_bytes_total",
+            "Total response body bytes sent, by shaping mode",
+            &["mode"]
+        ).unwrap();
+
+        let upstream_retries_total = register_counter_vec!(
+            "gateway_upstream_retries_total",
+            "Total number of upstream retries, by outcome",
+            &["upstream", "outcome"]
+        ).unwrap();
+
         Self {
             http_requests_total,
             http_request_duration,
+            http_requests_in_flight,
             active_connections,
             cache_hits_total,
             cache_misses_total,
             rate_limit_exceeded_total,
             upstream_errors_total,
+            bandwidth_bytes_total,
+            upstream_retries_total,
         }
     }
 
-    /// Record an HTTP request
-    pub fn record_request(&self, status: StatusCode, duration: Duration) {
-        let method = "unknown"; // TODO: Pass method from caller
-        let path = "unknown";   // TODO: Pass path from caller
+    /// Record an HTTP request served through `route`, the matched route pattern
+    pub fn record_request(&self, route: &str, method: &Method, status: StatusCode, duration: Duration) {
+        let labels = [route, method.as_str(), status_class(status)];
 
         self.http_requests_total
-            .with_label_values(&[method, &status.as_u16().to_string(), path])
+            .with_label_values(&labels)
             .inc();
 
         self.http_request_duration
-            .with_label_values(&[method, &status.as_u16().to_string()])
+            .with_label_values(&labels)
             .observe(duration.as_secs_f64());
     }
 
+    /// Count a request to `route` as in flight until the returned guard is dropped
+    pub fn track_in_flight(&self, route: &str) -> InFlightRequest {
+        let gauge = self.http_requests_in_flight.with_label_values(&[route]);
+        gauge.inc();
+        InFlightRequest { gauge }
+    }
+
+    /// Get the number of requests to `route` in flight
+    pub fn get_requests_in_flight(&self, route: &str) -> u64 {
+        self.http_requests_in_flight
+            .with_label_values(&[route])
+            .get() as u64
+    }
+
     /// Record cache hit
     pub fn record_cache_hit(&self, cache_type: &str) {
         self.cache_hits_total
@@ -120,6 +171,27 @@ impl MetricsCollector {
             .inc();
     }
 
+    /// Record response body bytes sent, labeled `shaped` or `unshaped`
+    pub fn record_bandwidth_bytes(&self, mode: &str, bytes: u64) {
+        self.bandwidth_bytes_total
+            .with_label_values(&[mode])
+            .inc_by(bytes as f64);
+    }
+
+    /// Record an upstream retry, labeled `retried` or `budget_exhausted`
+    pub fn record_upstream_retry(&self, upstream: &str, outcome: &str) {
+        self.upstream_retries_total
+            .with_label_values(&[upstream, outcome])
+            .inc();
+    }
+
+    /// Get total retries of an upstream with the given outcome
+    pub fn get_upstream_retries(&self, upstream: &str, outcome: &str) -> u64 {
+        self.upstream_retries_total
+            .with_label_values(&[upstream, outcome])
+            .get() as u64
+    }
+
     /// Update active connections gauge
     pub fn update_active_connections(&self, upstream: &str, count: f64) {
         self.active_connections
@@ -168,12 +240,11 @@ impl MetricsCollector {
         }
     }
 
-    /// Get total requests by status code
-    pub fn get_requests_by_status(&self, status: StatusCode) -> u64 {
-        // This is a simplified version - in practice you'd need to aggregate across all labels
+    /// Get total requests of a route and method in the status class of `status`
+    pub fn get_requests_by_status(&self, route: &str, method: &Method, status: StatusCode) -> u64 {
         self.http_requests_total
-            .with_label_values(&["GET", &status.as_u16().to_string(), "/"])
-            .get()
+            .with_label_values(&[route, method.as_str(), status_class(status)])
+            .get() as u64
     }
 
     /// Get average request duration
@@ -195,6 +266,18 @@ impl Default for MetricsCollector {
     }
 }
 
+/// A request counted in the in-flight gauge; dropping it, including when
+/// the request is cancelled, counts the request out again
+pub struct InFlightRequest {
+    gauge: Gauge,
+}
+
+impl Drop for InFlightRequest {
+    fn drop(&mut self) {
+        self.gauge.dec();
+    }
+}
+
 /// Health check metrics
 pub struct HealthMetrics {
     pub last_health_check: Arc<RwLock<std::time::Instant>>,
@@ -1,7 +1,13 @@
+pub mod access_log;
 pub mod auth;
 pub mod rate_limit;
 pub mod cache;
+pub mod bandwidth;
+pub mod timeout;
 
+pub use access_log::AccessLogMiddleware;
 pub use auth::AuthMiddleware;
 pub use rate_limit::RateLimitMiddleware;
 pub use cache::CacheMiddleware;
+pub use bandwidth::BandwidthMiddleware;
+pub use timeout::TimeoutMiddleware;
@@ -0,0 +1,206 @@
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use hyper::{header::HeaderName, http::HeaderValue, Body, Request, Response};
+use tower::{Layer, Service};
+use tracing::Level;
+
+use crate::config::{AccessLogConfig, AccessLogLevel};
+
+/// Upstream a request was routed to, attached to the response by the gateway
+/// service so outer middleware can report it
+#[derive(Debug, Clone)]
+pub struct RoutedUpstream(pub String);
+
+/// Structured access logging middleware.
+///
+/// Assigns each request an id, or keeps the one the client sent, forwards
+/// it upstream and echoes it back on the response. Every request produces
+/// one `access_log` tracing event with the method, path, upstream, status
+/// and latency.
+#[derive(Clone)]
+pub struct AccessLogMiddleware {
+    config: Arc<AccessLogConfig>,
+    header: HeaderName,
+}
+
+impl AccessLogMiddleware {
+    /// Create a new access logging middleware
+    pub fn new(config: AccessLogConfig) -> Self {
+        let header = HeaderName::from_bytes(config.request_id_header.as_bytes())
+            .unwrap_or_else(|_| HeaderName::from_static("x-request-id"));
+        Self {
+            config: Arc::new(config),
+            header,
+        }
+    }
+}
+
+impl<S> Layer<S> for AccessLogMiddleware {
+    type Service = AccessLogMiddlewareService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AccessLogMiddlewareService {
+            inner,
+            config: self.config.clone(),
+            header: self.header.clone(),
+        }
+    }
+}
+
+/// Service wrapper for access logging middleware
+#[derive(Clone)]
+pub struct AccessLogMiddlewareService<S> {
+    inner: S,
+    config: Arc<AccessLogConfig>,
+    header: HeaderName,
+}
+
+impl<S, ResBody> Service<Request<Body>> for AccessLogMiddlewareService<S>
+where
+    S: Service<Request<Body>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ResBody: Send + 'static,
+    S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = std::pin::Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let config = self.config.clone();
+        let header = self.header.clone();
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            if !config.enabled {
+                return inner.call(req).await;
+            }
+
+            let request_id = Self::request_id(&req, &header);
+            req.headers_mut().insert(header.clone(), request_id.clone());
+
+            let method = req.method().clone();
+            let path = req.uri().path().to_string();
+            let started = Instant::now();
+
+            let mut response = inner.call(req).await?;
+
+            let upstream = response.extensions().get::<RoutedUpstream>().map(|upstream| upstream.0.as_str()).unwrap_or("-");
+            Self::log(
+                config.level,
+                request_id.to_str().unwrap_or_default(),
+                method.as_str(),
+                &path,
+                upstream,
+                response.status().as_u16(),
+                started.elapsed().as_secs_f64() * 1000.0,
+            );
+
+            response.headers_mut().insert(header, request_id);
+            Ok(response)
+        })
+    }
+}
+
+impl<S> AccessLogMiddlewareService<S> {
+    /// The client's request id if it sent a usable one, otherwise a new one
+    fn request_id(req: &Request<Body>, header: &HeaderName) -> HeaderValue {
+        req.headers()
+            .get(header)
+            .filter(|value| !value.is_empty() && value.to_str().is_ok())
+            .cloned()
+            .unwrap_or_else(|| HeaderValue::from_str(&uuid::Uuid::new_v4().to_string()).unwrap())
+    }
+
+    /// Emit the access log event at the configured level
+    fn log(level: AccessLogLevel, request_id: &str, method: &str, path: &str, upstream: &str, status: u16, latency_ms: f64) {
+        macro_rules! access_log {
+            ($level:expr) => {
+                tracing::event!(
+                    target: "access_log",
+                    $level,
+                    request_id,
+                    method,
+                    path,
+                    upstream,
+                    status,
+                    latency_ms,
+                    "{} {} {} {:.1}ms",
+                    method,
+                    path,
+                    status,
+                    latency_ms
+                )
+            };
+        }
+
+        match level {
+            AccessLogLevel::Trace => access_log!(Level::TRACE),
+            AccessLogLevel::Debug => access_log!(Level::DEBUG),
+            AccessLogLevel::Info => access_log!(Level::INFO),
+            AccessLogLevel::Warn => access_log!(Level::WARN),
+            AccessLogLevel::Error => access_log!(Level::ERROR),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::StatusCode;
+    use tower::ServiceExt;
+
+    fn config(header: &str) -> AccessLogConfig {
+        AccessLogConfig {
+            request_id_header: header.to_string(),
+            ..AccessLogConfig::default()
+        }
+    }
+
+    /// Upstream that echoes the request id it received in the body
+    async fn call(config: AccessLogConfig, request_id: Option<&str>) -> (Option<String>, String) {
+        let header = config.request_id_header.clone();
+        let upstream = tower::service_fn(move |req: Request<Body>| {
+            let seen = req.headers().get(header.as_str()).and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+            async move {
+                let mut response = Response::new(Body::from(seen));
+                *response.status_mut() = StatusCode::CREATED;
+                response.extensions_mut().insert(RoutedUpstream("http://agents:8080".to_string()));
+                Ok::<_, std::convert::Infallible>(response)
+            }
+        });
+        let service = AccessLogMiddleware::new(config.clone()).layer(upstream);
+
+        let mut req = Request::builder().uri("/api/agents");
+        if let Some(request_id) = request_id {
+            req = req.header(config.request_id_header.as_str(), request_id);
+        }
+        let response = service.oneshot(req.body(Body::empty()).unwrap()).await.unwrap();
+        let echoed = response.headers().get(config.request_id_header.as_str()).map(|v| v.to_str().unwrap().to_string());
+        let seen = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (echoed, String::from_utf8(seen.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_request_id_assigned_and_echoed() {
+        let (echoed, seen) = call(config("x-request-id"), None).await;
+        let echoed = echoed.unwrap();
+        assert!(uuid::Uuid::parse_str(&echoed).is_ok());
+        assert_eq!(seen, echoed);
+    }
+
+    #[tokio::test]
+    async fn test_client_request_id_propagated_under_configured_header() {
+        let (echoed, seen) = call(config("x-correlation-id"), Some("client-42")).await;
+        assert_eq!(echoed.as_deref(), Some("client-42"));
+        assert_eq!(seen, "client-42");
+    }
+}
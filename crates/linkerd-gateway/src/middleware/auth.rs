@@ -14,6 +14,15 @@ use tracing::{info, warn};
 
 use crate::config::{AuthConfig, OAuthProvider};
 
+/// Identity headers set by the gateway once a request is authenticated;
+/// copies sent by the client are removed before authentication
+const IDENTITY_HEADERS: [&str; 2] = ["X-User-ID", "X-User-Roles"];
+
+/// Subject of a verified JWT, stored in the request extensions for the
+/// middleware behind authentication
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthenticatedUser(pub String);
+
 /// Authentication middleware for the gateway
 #[derive(Clone)]
 pub struct AuthMiddleware {
@@ -110,6 +119,10 @@ where
         let mut inner = self.inner.clone();
 
         Box::pin(async move {
+            for header in IDENTITY_HEADERS {
+                req.headers_mut().remove(header);
+            }
+
             let path = req.uri().path();
 
             // Skip authentication for public paths
@@ -146,6 +159,7 @@ where
                             "X-User-Roles",
                             claims.roles.join(",").parse().unwrap(),
                         );
+                        req.extensions_mut().insert(AuthenticatedUser(claims.sub.clone()));
 
                         info!("JWT authentication successful for user: {}", claims.sub);
                         return inner.call(req).await;
@@ -0,0 +1,401 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use hyper::{body::HttpBody, Body, Request, Response};
+use tower::{Layer, Service};
+use tracing::{debug, warn};
+
+use crate::{
+    config::{BandwidthConfig, BandwidthLimit},
+    metrics::MetricsCollector,
+};
+
+use super::auth::AuthenticatedUser;
+
+/// Largest chunk forwarded in one piece while shaping
+const MAX_SHAPED_CHUNK: usize = 16 * 1024;
+
+/// How often buckets that have refilled completely are dropped
+const BUCKET_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Egress bandwidth shaping middleware.
+///
+/// Wraps response bodies in a token-bucket byte limiter keyed by principal
+/// and route, pacing chunks to the configured rate. The body is forwarded
+/// through a `Body::channel`, so a slow client only applies backpressure to
+/// its own pacing task. The principal is the user authenticated by
+/// `AuthMiddleware`, else the client IP; client headers are never trusted.
+#[derive(Clone)]
+pub struct BandwidthMiddleware {
+    config: Arc<BandwidthConfig>,
+    buckets: Arc<Mutex<Buckets>>,
+    metrics: Option<MetricsCollector>,
+}
+
+/// Byte buckets by principal and route.
+///
+/// A bucket that has refilled completely is no different from a new one, so
+/// such buckets are swept periodically.
+#[derive(Debug)]
+struct Buckets {
+    buckets: HashMap<String, TokenBucket>,
+    last_sweep: Instant,
+}
+
+impl Buckets {
+    fn new() -> Self {
+        Self {
+            buckets: HashMap::new(),
+            last_sweep: Instant::now(),
+        }
+    }
+
+    fn reserve(&mut self, key: &str, limit: &BandwidthLimit, bytes: usize) -> Duration {
+        let now = Instant::now();
+        if now.duration_since(self.last_sweep) >= BUCKET_SWEEP_INTERVAL {
+            self.sweep(now);
+        }
+
+        self.buckets
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucket::new(limit))
+            .reserve(bytes)
+    }
+
+    /// Drop buckets that have refilled completely
+    fn sweep(&mut self, now: Instant) {
+        self.buckets.retain(|_, bucket| bucket.full_at() > now);
+        self.last_sweep = now;
+    }
+}
+
+/// Token bucket measured in bytes
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: &BandwidthLimit) -> Self {
+        let burst = limit.burst_bytes.max(1) as f64;
+        Self {
+            rate: limit.bytes_per_second.max(1) as f64,
+            burst,
+            tokens: burst,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Reserve `bytes` from the bucket, returning how long the caller must
+    /// wait before sending them. Tokens may go negative so concurrent
+    /// streams sharing a bucket queue up behind each other fairly.
+    fn reserve(&mut self, bytes: usize) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.last_refill = now;
+
+        self.tokens -= bytes as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+
+    /// When the bucket will have refilled to its burst
+    fn full_at(&self) -> Instant {
+        self.last_refill + Duration::from_secs_f64((self.burst - self.tokens) / self.rate)
+    }
+}
+
+impl BandwidthMiddleware {
+    /// Create a new bandwidth shaping middleware
+    pub fn new(config: BandwidthConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            buckets: Arc::new(Mutex::new(Buckets::new())),
+            metrics: None,
+        }
+    }
+
+    /// Report shaped and unshaped bytes to the metrics collector
+    pub fn with_metrics(mut self, metrics: MetricsCollector) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+}
+
+impl<S> Layer<S> for BandwidthMiddleware {
+    type Service = BandwidthMiddlewareService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BandwidthMiddlewareService {
+            inner,
+            config: self.config.clone(),
+            buckets: self.buckets.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+/// Service wrapper for bandwidth shaping middleware
+#[derive(Clone)]
+pub struct BandwidthMiddlewareService<S> {
+    inner: S,
+    config: Arc<BandwidthConfig>,
+    buckets: Arc<Mutex<Buckets>>,
+    metrics: Option<MetricsCollector>,
+}
+
+impl<S> Service<Request<Body>> for BandwidthMiddlewareService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = std::pin::Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let config = self.config.clone();
+        let buckets = self.buckets.clone();
+        let metrics = self.metrics.clone();
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            if !config.enabled {
+                return inner.call(req).await;
+            }
+
+            let principal = Self::get_principal(&req);
+            let shaping = if config.exempt_principals.contains(&principal) {
+                None
+            } else {
+                Self::select_limit(&config, req.uri().path())
+            };
+
+            let response = inner.call(req).await?;
+
+            let Some((scope, limit)) = shaping else {
+                return Ok(Self::count_unshaped(response, metrics));
+            };
+
+            let bucket_key = format!("{}|{}", principal, scope);
+            debug!("Shaping response for {} at {} B/s", bucket_key, limit.bytes_per_second);
+
+            let (parts, body) = response.into_parts();
+            let (sender, shaped_body) = Body::channel();
+            tokio::spawn(Self::pace_body(body, sender, bucket_key, limit, buckets, metrics));
+
+            Ok(Response::from_parts(parts, shaped_body))
+        })
+    }
+}
+
+impl<S> BandwidthMiddlewareService<S> {
+    /// Identify the principal (authenticated user or client IP)
+    fn get_principal(req: &Request<Body>) -> String {
+        if let Some(AuthenticatedUser(user_id)) = req.extensions().get::<AuthenticatedUser>() {
+            return format!("user:{}", user_id);
+        }
+
+        req.extensions()
+            .get::<std::net::SocketAddr>()
+            .map(|addr| format!("ip:{}", addr.ip()))
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    /// Select the limit for a path: the longest matching route prefix,
+    /// falling back to the default limit
+    fn select_limit(config: &BandwidthConfig, path: &str) -> Option<(String, BandwidthLimit)> {
+        config.route_limits
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(prefix, limit)| (prefix.clone(), limit.clone()))
+            .or_else(|| config.default_limit.clone().map(|limit| ("*".to_string(), limit)))
+    }
+
+    /// Pass an unshaped response through, counting its bytes when the size is known
+    fn count_unshaped(response: Response<Body>, metrics: Option<MetricsCollector>) -> Response<Body> {
+        if let (Some(metrics), Some(size)) = (metrics, response.body().size_hint().exact()) {
+            metrics.record_bandwidth_bytes("unshaped", size);
+        }
+        response
+    }
+
+    /// Forward `body` into `sender`, pacing each piece through the shared bucket
+    async fn pace_body(
+        mut body: Body,
+        mut sender: hyper::body::Sender,
+        bucket_key: String,
+        limit: BandwidthLimit,
+        buckets: Arc<Mutex<Buckets>>,
+        metrics: Option<MetricsCollector>,
+    ) {
+        let piece_size = (limit.burst_bytes as usize).clamp(1, MAX_SHAPED_CHUNK);
+
+        while let Some(chunk) = body.data().await {
+            let mut chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(err) => {
+                    warn!("Upstream body error while shaping {}: {}", bucket_key, err);
+                    sender.abort();
+                    return;
+                }
+            };
+
+            while !chunk.is_empty() {
+                let piece = chunk.split_to(piece_size.min(chunk.len()));
+                let wait = buckets.lock().unwrap().reserve(&bucket_key, &limit, piece.len());
+
+                if !wait.is_zero() {
+                    tokio::time::sleep(wait).await;
+                }
+
+                let len = piece.len() as u64;
+                // The client went away; stop pacing instead of holding the bucket
+                if sender.send_data(piece).await.is_err() {
+                    return;
+                }
+
+                if let Some(metrics) = &metrics {
+                    metrics.record_bandwidth_bytes("shaped", len);
+                }
+            }
+        }
+
+        if let Ok(Some(trailers)) = body.trailers().await {
+            let _ = sender.send_trailers(trailers).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower::ServiceExt;
+
+    const BODY_SIZE: usize = 5 * 1024 * 1024;
+
+    fn config() -> BandwidthConfig {
+        let mut route_limits = HashMap::new();
+        route_limits.insert(
+            "/artifacts".to_string(),
+            BandwidthLimit {
+                bytes_per_second: 1024 * 1024,
+                burst_bytes: 64 * 1024,
+            },
+        );
+
+        BandwidthConfig {
+            enabled: true,
+            default_limit: None,
+            route_limits,
+            exempt_principals: vec!["user:priority".to_string()],
+        }
+    }
+
+    async fn download(path: &str, user: Option<&str>) -> (usize, Duration) {
+        let upstream = tower::service_fn(|_req: Request<Body>| async {
+            Ok::<_, std::convert::Infallible>(Response::new(Body::from(vec![0u8; BODY_SIZE])))
+        });
+        let service = BandwidthMiddleware::new(config()).layer(upstream);
+
+        let mut req = Request::builder().uri(path);
+        if let Some(user) = user {
+            req = req.extension(AuthenticatedUser(user.to_string()));
+        }
+
+        let started = Instant::now();
+        let response = service.oneshot(req.body(Body::empty()).unwrap()).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (body.len(), started.elapsed())
+    }
+
+    #[tokio::test]
+    async fn test_shaped_route_is_paced() {
+        let (len, elapsed) = download("/artifacts/model.bin", Some("alice")).await;
+
+        assert_eq!(len, BODY_SIZE);
+        // (5MB - 64KB burst) at 1MB/s
+        assert!(elapsed >= Duration::from_millis(4500), "too fast: {:?}", elapsed);
+        assert!(elapsed <= Duration::from_millis(6500), "too slow: {:?}", elapsed);
+    }
+
+    #[tokio::test]
+    async fn test_unshaped_route_and_exempt_principal() {
+        let (len, elapsed) = download("/api/status", Some("alice")).await;
+        assert_eq!(len, BODY_SIZE);
+        assert!(elapsed < Duration::from_millis(1000));
+
+        let (len, elapsed) = download("/artifacts/model.bin", Some("priority")).await;
+        assert_eq!(len, BODY_SIZE);
+        assert!(elapsed < Duration::from_millis(1000));
+    }
+
+    #[tokio::test]
+    async fn test_identity_header_is_not_trusted() {
+        let upstream = tower::service_fn(|_req: Request<Body>| async {
+            Ok::<_, std::convert::Infallible>(Response::new(Body::from(vec![0u8; BODY_SIZE])))
+        });
+        let service = BandwidthMiddleware::new(config()).layer(upstream);
+        let req = Request::builder()
+            .uri("/artifacts/model.bin")
+            .header("X-User-ID", "priority")
+            .body(Body::empty())
+            .unwrap();
+
+        let started = Instant::now();
+        let response = service.oneshot(req).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(body.len(), BODY_SIZE);
+        assert!(started.elapsed() >= Duration::from_millis(4500));
+    }
+
+    #[test]
+    fn test_full_buckets_evicted() {
+        let limit = BandwidthLimit {
+            bytes_per_second: 1000,
+            burst_bytes: 500,
+        };
+        let mut buckets = Buckets::new();
+        buckets.reserve("idle", &limit, 1);
+        buckets.reserve("busy", &limit, 1500);
+
+        // Not yet due: nothing is swept
+        buckets.reserve("busy", &limit, 1);
+        assert_eq!(buckets.buckets.len(), 2);
+
+        std::thread::sleep(Duration::from_millis(10));
+        buckets.last_sweep -= BUCKET_SWEEP_INTERVAL;
+        buckets.reserve("busy", &limit, 1);
+        assert!(!buckets.buckets.contains_key("idle"));
+        assert!(buckets.buckets.contains_key("busy"));
+    }
+
+    #[test]
+    fn test_token_bucket_reservation() {
+        let mut bucket = TokenBucket::new(&BandwidthLimit {
+            bytes_per_second: 1000,
+            burst_bytes: 500,
+        });
+
+        assert_eq!(bucket.reserve(500), Duration::ZERO);
+        let wait = bucket.reserve(500);
+        assert!(wait > Duration::from_millis(400) && wait <= Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use hyper::{header::CONTENT_TYPE, Body, Request, Response, StatusCode};
+use tower::{Layer, Service};
+use tracing::warn;
+
+use crate::{config::TimeoutConfig, metrics::MetricsCollector};
+
+/// Upstream request timeout middleware.
+///
+/// Bounds how long the inner service may take to produce response headers,
+/// answering 504 Gateway Timeout once the route's timeout elapses. The inner
+/// future is dropped on timeout, which cancels the in-flight upstream request.
+#[derive(Clone)]
+pub struct TimeoutMiddleware {
+    config: Arc<TimeoutConfig>,
+    metrics: Option<MetricsCollector>,
+}
+
+impl TimeoutMiddleware {
+    /// Create a new timeout middleware
+    pub fn new(config: TimeoutConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            metrics: None,
+        }
+    }
+
+    /// Report timed out requests to the metrics collector
+    pub fn with_metrics(mut self, metrics: MetricsCollector) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+}
+
+impl<S> Layer<S> for TimeoutMiddleware {
+    type Service = TimeoutMiddlewareService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimeoutMiddlewareService {
+            inner,
+            config: self.config.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+/// Service wrapper for timeout middleware
+#[derive(Clone)]
+pub struct TimeoutMiddlewareService<S> {
+    inner: S,
+    config: Arc<TimeoutConfig>,
+    metrics: Option<MetricsCollector>,
+}
+
+impl<S> Service<Request<Body>> for TimeoutMiddlewareService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = std::pin::Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let config = self.config.clone();
+        let metrics = self.metrics.clone();
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            if !config.enabled {
+                return inner.call(req).await;
+            }
+
+            let (scope, timeout) = Self::select_timeout(&config, req.uri().path());
+            let path = req.uri().path().to_string();
+
+            match tokio::time::timeout(timeout, inner.call(req)).await {
+                Ok(result) => result,
+                Err(_) => {
+                    warn!("Upstream timed out after {}ms for {}", timeout.as_millis(), path);
+                    if let Some(metrics) = &metrics {
+                        metrics.record_upstream_error(&scope, "timeout");
+                    }
+                    Ok(Self::timeout_response(timeout))
+                }
+            }
+        })
+    }
+}
+
+impl<S> TimeoutMiddlewareService<S> {
+    /// Select the timeout for a path: the longest matching route prefix,
+    /// falling back to the default timeout
+    fn select_timeout(config: &TimeoutConfig, path: &str) -> (String, Duration) {
+        config.route_timeouts
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(prefix, timeout_ms)| (prefix.clone(), Duration::from_millis(*timeout_ms)))
+            .unwrap_or_else(|| ("*".to_string(), Duration::from_millis(config.default_timeout_ms)))
+    }
+
+    fn timeout_response(timeout: Duration) -> Response<Body> {
+        Response::builder()
+            .status(StatusCode::GATEWAY_TIMEOUT)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(format!(
+                r#"{{"error": "Upstream request timed out", "timeout_ms": {}}}"#,
+                timeout.as_millis()
+            )))
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        collections::HashMap,
+        sync::atomic::{AtomicBool, Ordering},
+    };
+    use tower::ServiceExt;
+
+    /// Flags the upstream future as cancelled when it is dropped unfinished
+    struct CancelGuard(Arc<AtomicBool>);
+
+    impl Drop for CancelGuard {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn config() -> TimeoutConfig {
+        let mut route_timeouts = HashMap::new();
+        route_timeouts.insert("/slow".to_string(), 500);
+
+        TimeoutConfig {
+            enabled: true,
+            default_timeout_ms: 50,
+            route_timeouts,
+        }
+    }
+
+    async fn call(path: &str, upstream_delay: Duration) -> (StatusCode, bool) {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = cancelled.clone();
+        let upstream = tower::service_fn(move |_req: Request<Body>| {
+            let guard = CancelGuard(flag.clone());
+            async move {
+                tokio::time::sleep(upstream_delay).await;
+                std::mem::forget(guard);
+                Ok::<_, std::convert::Infallible>(Response::new(Body::from("ok")))
+            }
+        });
+        let service = TimeoutMiddleware::new(config()).layer(upstream);
+
+        let req = Request::builder().uri(path).body(Body::empty()).unwrap();
+        let response = service.oneshot(req).await.unwrap();
+        (response.status(), cancelled.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn test_slow_upstream_times_out_and_is_cancelled() {
+        let (status, cancelled) = call("/api/agents", Duration::from_secs(5)).await;
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+        assert!(cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_route_override_allows_longer_upstream() {
+        let (status, cancelled) = call("/slow/report", Duration::from_millis(200)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!cancelled);
+
+        let (status, _) = call("/api/agents", Duration::from_millis(200)).await;
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+    }
+}
@@ -1,13 +1,19 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
-use crate::config::{Route, RoutingConfig, LoadBalancingStrategy};
+use crate::config::{Route, RoutingConfig, RoutingRule, LoadBalancingStrategy, RetryBudgetConfig};
 
 /// Router for matching requests to routes and handling load balancing
 pub struct Router {
     config: RoutingConfig,
+    /// Route taken by each of `config.rules`
+    rule_routes: Vec<Route>,
+    default_route: Option<Route>,
     round_robin_index: AtomicUsize,
     connection_counts: HashMap<String, AtomicUsize>,
+    retry_budget: Arc<RetryBudget>,
 }
 
 impl Router {
@@ -21,33 +27,61 @@ impl Router {
                 .or_insert_with(|| AtomicUsize::new(0));
         }
 
+        // Rules and the default upstream forward the whole request path
+        let rule_routes = config.rules.iter().map(|rule| Route {
+            path: "/*".to_string(),
+            upstream: rule.target.clone(),
+            methods: vec![],
+            headers: HashMap::new(),
+            timeout_ms: None,
+        }).collect();
+        let default_route = config.default_upstream.as_ref().map(|default_upstream| Route {
+            path: "/*".to_string(),
+            upstream: default_upstream.clone(),
+            methods: vec!["GET".to_string(), "POST".to_string(), "PUT".to_string(), "DELETE".to_string()],
+            headers: HashMap::new(),
+            timeout_ms: Some(30000),
+        });
+
         Self {
+            retry_budget: Arc::new(RetryBudget::new(config.retry_budget.clone())),
+            rule_routes,
+            default_route,
             config,
             round_robin_index: AtomicUsize::new(0),
             connection_counts,
         }
     }
 
-    /// Find matching route for the given path and method
-    pub fn find_route(&self, path: &str, method: &hyper::Method) -> Option<&Route> {
+    /// Retry budget shared by all upstream requests
+    pub fn retry_budget(&self) -> &RetryBudget {
+        &self.retry_budget
+    }
+
+    /// Find the route for a request: the first matching rule, then the first
+    /// matching route, then the default upstream
+    pub fn find_route(&self, path: &str, method: &hyper::Method, headers: &hyper::HeaderMap) -> Option<&Route> {
+        for (rule, route) in self.config.rules.iter().zip(&self.rule_routes) {
+            if self.matches_rule(rule, path, headers) {
+                return Some(route);
+            }
+        }
+
         for route in &self.config.routes {
             if self.matches_route(route, path, method) {
                 return Some(route);
             }
         }
 
-        // Return default route if no specific match found
-        if let Some(default_upstream) = &self.config.default_upstream {
-            return Some(&Route {
-                path: "/*".to_string(),
-                upstream: default_upstream.clone(),
-                methods: vec!["GET".to_string(), "POST".to_string(), "PUT".to_string(), "DELETE".to_string()],
-                headers: HashMap::new(),
-                timeout_ms: Some(30000),
-            });
-        }
+        self.default_route.as_ref()
+    }
 
-        None
+    /// Check if a rule's headers and path prefix match the request
+    fn matches_rule(&self, rule: &RoutingRule, path: &str, headers: &hyper::HeaderMap) -> bool {
+        rule.match_path_prefix.as_ref().is_none_or(|prefix| path.starts_with(prefix.as_str()))
+            && rule.match_headers.iter().all(|(name, value)| {
+                headers.get(name.as_str()).and_then(|v| v.to_str().ok()) == Some(value.as_str())
+            })
     }
 
     /// Check if a route matches the given path and method
@@ -202,8 +236,102 @@ impl Clone for Router {
 
         Self {
             config: self.config.clone(),
+            rule_routes: self.rule_routes.clone(),
+            default_route: self.default_route.clone(),
             round_robin_index: AtomicUsize::new(self.round_robin_index.load(Ordering::SeqCst)),
             connection_counts,
+            retry_budget: self.retry_budget.clone(),
+        }
+    }
+}
+
+/// Whether a request with this method can be sent again without changing its effect
+pub fn is_idempotent(method: &hyper::Method) -> bool {
+    use hyper::Method;
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE | Method::PUT | Method::DELETE)
+}
+
+/// Requests and retries seen within one second
+struct BudgetBucket {
+    second: u64,
+    requests: u64,
+    retries: u64,
+}
+
+/// Linkerd2-style retry budget: retries are allowed up to `retry_ratio` of
+/// the requests seen within the last `ttl_seconds`, plus a floor of
+/// `min_retries_per_second`, so retries cannot amplify an upstream outage
+pub struct RetryBudget {
+    config: RetryBudgetConfig,
+    started: Instant,
+    window: Mutex<VecDeque<BudgetBucket>>,
+}
+
+impl RetryBudget {
+    /// Create a new retry budget
+    pub fn new(config: RetryBudgetConfig) -> Self {
+        Self {
+            config,
+            started: Instant::now(),
+            window: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Retries allowed for a single request
+    pub fn max_retries_per_request(&self) -> u32 {
+        self.config.max_retries_per_request
+    }
+
+    /// Largest request body buffered for retries
+    pub fn max_retry_body_bytes(&self) -> u64 {
+        self.config.max_retry_body_bytes
+    }
+
+    /// Record an original (non-retry) request
+    pub fn deposit(&self) {
+        self.with_current_bucket(|bucket| bucket.requests += 1);
+    }
+
+    /// Take one retry from the budget, returning false if it is exhausted
+    pub fn withdraw(&self) -> bool {
+        let mut window = self.window.lock().unwrap();
+        self.advance(&mut window);
+
+        let requests: u64 = window.iter().map(|bucket| bucket.requests).sum();
+        let retries: u64 = window.iter().map(|bucket| bucket.retries).sum();
+        let reserve = self.config.min_retries_per_second as f64 * self.config.ttl_seconds.max(1) as f64;
+        let allowed = reserve + requests as f64 * self.config.retry_ratio as f64;
+
+        if (retries as f64) + 1.0 > allowed {
+            return false;
+        }
+        if let Some(bucket) = window.back_mut() {
+            bucket.retries += 1;
+        }
+        true
+    }
+
+    fn with_current_bucket(&self, update: impl FnOnce(&mut BudgetBucket)) {
+        let mut window = self.window.lock().unwrap();
+        self.advance(&mut window);
+        if let Some(bucket) = window.back_mut() {
+            update(bucket);
+        }
+    }
+
+    /// Open a bucket for the current second and drop buckets older than the window
+    fn advance(&self, window: &mut VecDeque<BudgetBucket>) {
+        let second = self.started.elapsed().as_secs();
+        if window.back().is_none_or(|bucket| bucket.second != second) {
+            window.push_back(BudgetBucket { second, requests: 0, retries: 0 });
+        }
+        let ttl = self.config.ttl_seconds.max(1);
+        while window.front().is_some_and(|bucket| bucket.second + ttl <= second) {
+            window.pop_front();
         }
     }
 }
@@ -211,7 +339,7 @@ impl Clone for Router {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use hyper::Method;
+    use hyper::{HeaderMap, Method};
 
     #[test]
     fn test_exact_path_match() {
@@ -223,12 +351,14 @@ mod tests {
                 headers: HashMap::new(),
                 timeout_ms: None,
             }],
+            rules: vec![],
             default_upstream: None,
             load_balancing: LoadBalancingStrategy::RoundRobin,
+            retry_budget: RetryBudgetConfig::default(),
         };
 
         let router = Router::new(config);
-        let route = router.find_route("/api/users", &Method::GET);
+        let route = router.find_route("/api/users", &Method::GET, &HeaderMap::new());
         assert!(route.is_some());
         assert_eq!(route.unwrap().upstream, "http://localhost:8081");
     }
@@ -243,12 +373,14 @@ mod tests {
                 headers: HashMap::new(),
                 timeout_ms: None,
             }],
+            rules: vec![],
             default_upstream: None,
             load_balancing: LoadBalancingStrategy::RoundRobin,
+            retry_budget: RetryBudgetConfig::default(),
         };
 
         let router = Router::new(config);
-        let route = router.find_route("/api/users/123", &Method::GET);
+        let route = router.find_route("/api/users/123", &Method::GET, &HeaderMap::new());
         assert!(route.is_some());
     }
 
@@ -262,12 +394,117 @@ mod tests {
                 headers: HashMap::new(),
                 timeout_ms: None,
             }],
+            rules: vec![],
             default_upstream: None,
             load_balancing: LoadBalancingStrategy::RoundRobin,
+            retry_budget: RetryBudgetConfig::default(),
         };
 
         let router = Router::new(config);
-        assert!(router.find_route("/api/users", &Method::POST).is_some());
-        assert!(router.find_route("/api/users", &Method::GET).is_none());
+        assert!(router.find_route("/api/users", &Method::POST, &HeaderMap::new()).is_some());
+        assert!(router.find_route("/api/users", &Method::GET, &HeaderMap::new()).is_none());
+    }
+
+    fn rule(match_headers: &[(&str, &str)], match_path_prefix: Option<&str>, target: &str) -> RoutingRule {
+        RoutingRule {
+            match_headers: match_headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            match_path_prefix: match_path_prefix.map(str::to_string),
+            target: target.to_string(),
+        }
+    }
+
+    fn rules_router() -> Router {
+        Router::new(RoutingConfig {
+            routes: vec![Route {
+                path: "/api/users".to_string(),
+                upstream: "http://users:8080".to_string(),
+                methods: vec![],
+                headers: HashMap::new(),
+                timeout_ms: None,
+            }],
+            rules: vec![
+                rule(&[("x-env", "staging")], None, "http://staging:8080"),
+                rule(&[("x-env", "canary")], Some("/api/"), "http://canary:8080"),
+                rule(&[], Some("/v2/"), "http://v2:8080"),
+            ],
+            default_upstream: Some("http://default:8080".to_string()),
+            ..RoutingConfig::default()
+        })
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        pairs.iter().map(|(k, v)| (k.parse().unwrap(), v.parse().unwrap())).collect()
+    }
+
+    #[test]
+    fn test_header_rule_match() {
+        let router = rules_router();
+
+        // Rules win over routes, and header names match case-insensitively
+        let route = router.find_route("/api/users", &Method::GET, &headers(&[("X-Env", "staging")])).unwrap();
+        assert_eq!(route.upstream, "http://staging:8080");
+        // Every condition of a rule must hold
+        let route = router.find_route("/other", &Method::GET, &headers(&[("x-env", "canary")])).unwrap();
+        assert_eq!(route.upstream, "http://default:8080");
+        let route = router.find_route("/api/orders", &Method::GET, &headers(&[("x-env", "canary")])).unwrap();
+        assert_eq!(route.upstream, "http://canary:8080");
+    }
+
+    #[test]
+    fn test_path_prefix_rule_match() {
+        let router = rules_router();
+
+        let route = router.find_route("/v2/agents", &Method::POST, &HeaderMap::new()).unwrap();
+        assert_eq!(route.upstream, "http://v2:8080");
+        // Earlier rules are evaluated first
+        let route = router.find_route("/v2/agents", &Method::POST, &headers(&[("x-env", "staging")])).unwrap();
+        assert_eq!(route.upstream, "http://staging:8080");
+    }
+
+    #[test]
+    fn test_default_fallthrough() {
+        let router = rules_router();
+
+        let route = router.find_route("/api/users", &Method::GET, &headers(&[("x-env", "production")])).unwrap();
+        assert_eq!(route.upstream, "http://users:8080");
+        let route = router.find_route("/elsewhere", &Method::GET, &HeaderMap::new()).unwrap();
+        assert_eq!(route.upstream, "http://default:8080");
+
+        let router = Router::new(RoutingConfig { default_upstream: None, ..rules_router().config });
+        assert!(router.find_route("/elsewhere", &Method::GET, &HeaderMap::new()).is_none());
+    }
+
+    #[test]
+    fn test_retry_budget_allows_reserve_plus_ratio() {
+        let budget = RetryBudget::new(RetryBudgetConfig {
+            enabled: true,
+            retry_ratio: 0.5,
+            min_retries_per_second: 1,
+            ttl_seconds: 2,
+            max_retries_per_request: 3,
+            ..RetryBudgetConfig::default()
+        });
+
+        // Only the reserve of one retry per second of the window is available before any requests
+        assert!(budget.withdraw());
+        assert!(budget.withdraw());
+        assert!(!budget.withdraw());
+
+        // Each request deposits half a retry
+        for _ in 0..4 {
+            budget.deposit();
+        }
+        assert!(budget.withdraw());
+        assert!(budget.withdraw());
+        assert!(!budget.withdraw());
+    }
+
+    #[test]
+    fn test_only_idempotent_methods_are_retryable() {
+        assert!(is_idempotent(&Method::GET));
+        assert!(is_idempotent(&Method::HEAD));
+        assert!(is_idempotent(&Method::PUT));
+        assert!(!is_idempotent(&Method::POST));
+        assert!(!is_idempotent(&Method::PATCH));
     }
 }
@@ -0,0 +1,291 @@
+//! TLS termination for accepted connections
+//!
+//! Connections are wrapped in a TLS stream before they reach hyper. The
+//! certificate and key are re-read periodically and, when they change on
+//! disk, used for every handshake from then on, so rotating them needs no
+//! restart. Changing the client CA (`ca_cert_path`) does require one.
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use rustls::server::{AllowAnyAuthenticatedClient, ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use rustls::{Certificate, PrivateKey, RootCertStore, ServerConfig};
+use tokio::net::TcpStream;
+use tokio_rustls::{server::TlsStream, TlsAcceptor};
+
+use crate::config::TlsConfig;
+
+/// Errors loading TLS material
+#[derive(Debug, thiserror::Error)]
+pub enum TlsError {
+    #[error("Failed to read {path}: {source}")]
+    Read {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("No certificates found in {0}")]
+    NoCertificates(String),
+    #[error("No private key found in {0}")]
+    NoPrivateKey(String),
+    #[error("Unsupported private key in {0}")]
+    UnsupportedKey(String),
+    #[error("Invalid TLS configuration: {0}")]
+    Rustls(#[from] rustls::Error),
+}
+
+/// Terminates TLS on accepted connections
+#[derive(Clone)]
+pub struct TlsTerminator {
+    acceptor: TlsAcceptor,
+    certificate: Arc<ReloadingCertificate>,
+}
+
+impl TlsTerminator {
+    /// Load the certificate, key and optional client CA named by `config`
+    pub fn new(config: &TlsConfig) -> Result<Self, TlsError> {
+        let certificate = Arc::new(ReloadingCertificate::load(&config.cert_path, &config.key_path)?);
+
+        let builder = ServerConfig::builder().with_safe_defaults();
+        let mut server_config = match &config.ca_cert_path {
+            Some(ca_cert_path) => {
+                let mut roots = RootCertStore::empty();
+                let (added, _) = roots.add_parsable_certificates(&read_certificates(ca_cert_path)?);
+                if added == 0 {
+                    return Err(TlsError::NoCertificates(ca_cert_path.clone()));
+                }
+                builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+            }
+            None => builder.with_no_client_auth(),
+        }
+        .with_cert_resolver(certificate.clone());
+        server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        Ok(Self {
+            acceptor: TlsAcceptor::from(Arc::new(server_config)),
+            certificate,
+        })
+    }
+
+    /// Perform the TLS handshake on an accepted connection
+    pub async fn accept(&self, stream: TcpStream) -> std::io::Result<TlsStream<TcpStream>> {
+        self.acceptor.accept(stream).await
+    }
+
+    /// Re-read the certificate and key, swapping them in if they changed.
+    /// On error the current certificate stays in use.
+    pub fn reload_if_changed(&self) -> Result<bool, TlsError> {
+        self.certificate.reload_if_changed()
+    }
+
+    /// Check the certificate and key for changes every `interval`
+    pub fn spawn_reloader(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let terminator = self.clone();
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.tick().await;
+            loop {
+                ticks.tick().await;
+                match terminator.reload_if_changed() {
+                    Ok(true) => tracing::info!("Reloaded TLS certificate from {}", terminator.certificate.cert_path),
+                    Ok(false) => {}
+                    Err(err) => tracing::warn!("Keeping current TLS certificate, reload failed: {}", err),
+                }
+            }
+        })
+    }
+}
+
+/// PEM files on disk and the certificate last loaded from them
+struct LoadedCertificate {
+    cert_pem: Vec<u8>,
+    key_pem: Vec<u8>,
+    key: Arc<CertifiedKey>,
+}
+
+/// Certificate resolver serving the latest certificate loaded from disk
+struct ReloadingCertificate {
+    cert_path: String,
+    key_path: String,
+    current: RwLock<LoadedCertificate>,
+}
+
+impl ReloadingCertificate {
+    fn load(cert_path: &str, key_path: &str) -> Result<Self, TlsError> {
+        let cert_pem = read_file(cert_path)?;
+        let key_pem = read_file(key_path)?;
+        let key = certified_key(cert_path, &cert_pem, key_path, &key_pem)?;
+
+        Ok(Self {
+            cert_path: cert_path.to_string(),
+            key_path: key_path.to_string(),
+            current: RwLock::new(LoadedCertificate { cert_pem, key_pem, key }),
+        })
+    }
+
+    fn reload_if_changed(&self) -> Result<bool, TlsError> {
+        let cert_pem = read_file(&self.cert_path)?;
+        let key_pem = read_file(&self.key_path)?;
+        {
+            let current = self.current.read().unwrap();
+            if current.cert_pem == cert_pem && current.key_pem == key_pem {
+                return Ok(false);
+            }
+        }
+
+        let key = certified_key(&self.cert_path, &cert_pem, &self.key_path, &key_pem)?;
+        *self.current.write().unwrap() = LoadedCertificate { cert_pem, key_pem, key };
+        Ok(true)
+    }
+}
+
+impl ResolvesServerCert for ReloadingCertificate {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.read().unwrap().key.clone())
+    }
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, TlsError> {
+    std::fs::read(path).map_err(|source| TlsError::Read {
+        path: path.to_string(),
+        source,
+    })
+}
+
+fn read_certificates(path: &str) -> Result<Vec<Vec<u8>>, TlsError> {
+    parse_certificates(path, &read_file(path)?)
+}
+
+fn parse_certificates(path: &str, pem: &[u8]) -> Result<Vec<Vec<u8>>, TlsError> {
+    let certificates = rustls_pemfile::certs(&mut &pem[..]).map_err(|source| TlsError::Read {
+        path: path.to_string(),
+        source,
+    })?;
+    if certificates.is_empty() {
+        return Err(TlsError::NoCertificates(path.to_string()));
+    }
+    Ok(certificates)
+}
+
+fn certified_key(cert_path: &str, cert_pem: &[u8], key_path: &str, key_pem: &[u8]) -> Result<Arc<CertifiedKey>, TlsError> {
+    let chain = parse_certificates(cert_path, cert_pem)?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+
+    let items = rustls_pemfile::read_all(&mut &key_pem[..]).map_err(|source| TlsError::Read {
+        path: key_path.to_string(),
+        source,
+    })?;
+    let key = items
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| TlsError::NoPrivateKey(key_path.to_string()))?;
+    let signing_key = rustls::sign::any_supported_type(&key)
+        .map_err(|_| TlsError::UnsupportedKey(key_path.to_string()))?;
+
+    Ok(Arc::new(CertifiedKey::new(chain, signing_key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::{service::service_fn, Body, Request, Response};
+    use std::convert::Infallible;
+    use tokio::net::TcpListener;
+
+    fn write_certificate(dir: &std::path::Path) -> Vec<u8> {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        // Each serialization signs afresh, so take the DER from the PEM written
+        let cert_pem = cert.serialize_pem().unwrap();
+        std::fs::write(dir.join("cert.pem"), &cert_pem).unwrap();
+        std::fs::write(dir.join("key.pem"), cert.serialize_private_key_pem()).unwrap();
+        parse_certificates("cert.pem", cert_pem.as_bytes()).unwrap().remove(0)
+    }
+
+    /// Serve "ok" over TLS on an ephemeral port
+    async fn serve(terminator: TlsTerminator) -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let terminator = terminator.clone();
+                tokio::spawn(async move {
+                    let Ok(stream) = terminator.accept(stream).await else { return };
+                    let service = service_fn(|_req: Request<Body>| async { Ok::<_, Infallible>(Response::new(Body::from("ok"))) });
+                    let _ = hyper::server::conn::Http::new().serve_connection(stream, service).await;
+                });
+            }
+        });
+        addr
+    }
+
+    /// Request "/" trusting only `trusted`, returning the body and the certificate presented
+    async fn fetch(addr: std::net::SocketAddr, trusted: &[u8]) -> std::io::Result<(String, Vec<u8>)> {
+        let mut roots = RootCertStore::empty();
+        roots.add(&Certificate(trusted.to_vec())).unwrap();
+        let client_config = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(client_config));
+
+        let tcp = TcpStream::connect(addr).await?;
+        let stream = connector.connect("localhost".try_into().unwrap(), tcp).await?;
+        let presented = stream.get_ref().1.peer_certificates().unwrap()[0].0.clone();
+
+        let (mut sender, connection) = hyper::client::conn::handshake(stream).await.unwrap();
+        tokio::spawn(connection);
+        let response = sender.send_request(Request::get("/").body(Body::empty()).unwrap()).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        Ok((String::from_utf8(body.to_vec()).unwrap(), presented))
+    }
+
+    #[tokio::test]
+    async fn test_terminates_tls_and_reloads_rotated_certificate() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = write_certificate(dir.path());
+        let config = TlsConfig {
+            cert_path: dir.path().join("cert.pem").to_string_lossy().into_owned(),
+            key_path: dir.path().join("key.pem").to_string_lossy().into_owned(),
+            ca_cert_path: None,
+            reload_interval_secs: 0,
+        };
+        let terminator = TlsTerminator::new(&config).unwrap();
+        let addr = serve(terminator.clone()).await;
+
+        let (body, presented) = fetch(addr, &first).await.unwrap();
+        assert_eq!(body, "ok");
+        assert_eq!(presented, first);
+        assert!(!terminator.reload_if_changed().unwrap());
+
+        let second = write_certificate(dir.path());
+        assert!(terminator.reload_if_changed().unwrap());
+        let (_, presented) = fetch(addr, &second).await.unwrap();
+        assert_eq!(presented, second);
+        assert!(fetch(addr, &first).await.is_err());
+
+        // A broken rotation keeps the last good certificate
+        std::fs::write(dir.path().join("key.pem"), "not a key").unwrap();
+        assert!(matches!(terminator.reload_if_changed(), Err(TlsError::NoPrivateKey(_))));
+        assert_eq!(fetch(addr, &second).await.unwrap().1, second);
+    }
+
+    #[test]
+    fn test_missing_files_rejected() {
+        let config = TlsConfig {
+            cert_path: "/nonexistent/cert.pem".to_string(),
+            key_path: "/nonexistent/key.pem".to_string(),
+            ca_cert_path: None,
+            reload_interval_secs: 0,
+        };
+        assert!(matches!(TlsTerminator::new(&config), Err(TlsError::Read { .. })));
+    }
+}